@@ -32,6 +32,7 @@ pub struct MemorySearchResult {
 }
 
 /// Store a new memory in the agent memory bank
+#[allow(clippy::too_many_arguments)]
 pub fn store_memory(
     conn: &Connection,
     agent_name: &str,
@@ -59,7 +59,7 @@ impl ApiKeyConfig {
             if !key.is_empty() {
                 config.enabled = true;
                 config.master_key = Some(key);
-                println!("🔐 API key authentication enabled via FTP_API_KEY");
+                tracing::info!("API key authentication enabled via FTP_API_KEY");
             }
         }
 
@@ -73,14 +73,14 @@ impl ApiKeyConfig {
                         if file_config.enabled {
                             config.enabled = true;
                         }
-                        println!("🔑 Loaded {} API keys from config", config.keys.len());
+                        tracing::info!("Loaded {} API keys from config", config.keys.len());
                     }
                 }
             }
         }
 
         if !config.enabled {
-            println!("⚠️  API authentication disabled (set FTP_API_KEY to enable)");
+            tracing::warn!("API authentication disabled (set FTP_API_KEY to enable)");
         }
 
         config
@@ -200,6 +200,14 @@ pub enum AgentCommand {
         report: ResearchReportInput,
     },
 
+    // Read-only report templates
+    ListReportTemplates,
+    RunReportTemplate {
+        name: String,
+        #[serde(default)]
+        params: std::collections::HashMap<String, serde_json::Value>,
+    },
+
     // Schema & Health
     GetSchema,
     HealthCheck,
@@ -268,6 +276,9 @@ pub enum StreamingEvent {
 
 // ===== SERVER STATE =====
 
+/// WebSocket bridge port; the HTTP API listens on the next port up
+pub const AGENT_SERVER_PORT: u16 = 9528;
+
 pub struct AgentServer {
     port: u16,
     db_path: std::path::PathBuf,
@@ -292,14 +303,16 @@ impl AgentServer {
         let db_path_http = self.db_path.clone();
         let _http_handle = tokio::spawn(async move {
             if let Err(e) = start_http_server(http_addr, db_path_http).await {
-                eprintln!("❌ HTTP server error: {}", e);
+                tracing::error!("HTTP server error: {}", e);
             }
         });
 
-        println!("🚀 Flight Tracker Agent Server listening on:");
-        println!("   WebSocket: ws://{}", ws_addr);
-        println!("   HTTP API:  http://{}", http_addr);
-        println!("📡 Ready to receive commands from quantum-local-bridge and mobile clients");
+        tracing::info!(
+            "Flight Tracker Agent Server listening on ws://{} (WebSocket) and http://{} (HTTP API)",
+            ws_addr,
+            http_addr
+        );
+        tracing::info!("Ready to receive commands from quantum-local-bridge and mobile clients");
 
         // Start WebSocket server (blocking)
         let listener = TcpListener::bind(&ws_addr)
@@ -309,17 +322,17 @@ impl AgentServer {
         loop {
             match listener.accept().await {
                 Ok((stream, peer_addr)) => {
-                    println!("✅ New WebSocket connection from {}", peer_addr);
+                    tracing::info!("New WebSocket connection from {}", peer_addr);
                     let db_path = self.db_path.clone();
 
                     tokio::spawn(async move {
                         if let Err(e) = handle_connection(stream, db_path).await {
-                            eprintln!("❌ Connection error from {}: {}", peer_addr, e);
+                            tracing::error!("Connection error from {}: {}", peer_addr, e);
                         }
                     });
                 }
                 Err(e) => {
-                    eprintln!("❌ Failed to accept connection: {}", e);
+                    tracing::error!("Failed to accept connection: {}", e);
                 }
             }
         }
@@ -333,7 +346,7 @@ async fn handle_connection(stream: TcpStream, db_path: std::path::PathBuf) -> Re
 
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-    println!("🔗 WebSocket connection established");
+    tracing::debug!("WebSocket connection established");
 
     while let Some(msg) = ws_receiver.next().await {
         let msg = msg.context("Failed to receive message")?;
@@ -355,7 +368,7 @@ async fn handle_connection(stream: TcpStream, db_path: std::path::PathBuf) -> Re
                 }
             };
 
-            println!("📨 Received command: {:?}", command);
+            tracing::debug!("Received command: {:?}", command);
 
             // Execute command with streaming support
             if let Err(e) = execute_command(command, &db_path, &mut ws_sender).await {
@@ -367,7 +380,7 @@ async fn handle_connection(stream: TcpStream, db_path: std::path::PathBuf) -> Re
                 ws_sender.send(Message::Text(error_json)).await?;
             }
         } else if msg.is_close() {
-            println!("👋 Client closed connection");
+            tracing::debug!("Client closed connection");
             break;
         }
     }
@@ -436,7 +449,7 @@ async fn http_execute_command(
     AxumState(state): AxumState<Arc<AppState>>,
     Json(command): Json<AgentCommand>,
 ) -> impl IntoResponse {
-    println!("📱 HTTP API: Received command: {:?}", command);
+    tracing::debug!("HTTP API: Received command: {:?}", command);
 
     // Execute command without streaming (HTTP doesn't support WebSocket streaming)
     match execute_command_sync(command, &state.db_path).await {
@@ -459,7 +472,7 @@ async fn http_execute_command(
 
 async fn execute_command_sync(
     command: AgentCommand,
-    db_path: &std::path::PathBuf,
+    db_path: &std::path::Path,
 ) -> Result<serde_json::Value> {
     match command {
         AgentCommand::Ping { message } => {
@@ -516,7 +529,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::SearchMemories { query, limit } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let conn = db.get_connection();
 
             match crate::agent_memory::search_memories(conn, &query, limit.unwrap_or(10)) {
@@ -532,7 +545,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetMemoryStats => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let conn = db.get_connection();
 
             match crate::agent_memory::get_memory_stats(conn) {
@@ -576,13 +589,14 @@ async fn execute_command_sync(
                     "GET_STATISTICS", "GET_AIRPORT_STATS",
                     "LIST_RESEARCH_REPORTS", "GET_RESEARCH_REPORT", "SAVE_RESEARCH_REPORT",
                     "SEARCH_MEMORIES", "GET_MEMORY_STATS",
+                    "LIST_REPORT_TEMPLATES", "RUN_REPORT_TEMPLATE",
                     "RESEARCH_FLIGHT", "CHAT_WITH_AGENT", "EXPORT_DATA"
                 ]
             }))
         }
 
         AgentCommand::ListFlights { user_id, limit, offset } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let flights = db.list_flights(&user_id, limit.unwrap_or(1000), offset.unwrap_or(0))
                 .map_err(|e| anyhow::anyhow!("Failed to list flights: {}", e))?;
 
@@ -594,7 +608,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetFlight { flight_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             match db.get_flight(&flight_id) {
                 Ok(Some(flight)) => Ok(serde_json::to_value(flight)?),
                 Ok(None) => Err(anyhow::anyhow!("Flight not found: {}", flight_id)),
@@ -603,7 +617,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::CreateFlight { user_id, flight } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let flight_id = db.create_flight(&user_id, &flight)
                 .map_err(|e| anyhow::anyhow!("Failed to create flight: {}", e))?;
 
@@ -615,7 +629,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::DeleteFlight { flight_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             db.delete_flight(&flight_id)
                 .map_err(|e| anyhow::anyhow!("Failed to delete flight: {}", e))?;
 
@@ -627,7 +641,7 @@ async fn execute_command_sync(
 
         AgentCommand::UpdateFlight { flight_id, flight } => {
             // For now, we implement update as a note that the full update would require more params
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             // Check flight exists
             match db.get_flight(&flight_id) {
                 Ok(Some(_)) => {
@@ -646,7 +660,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::ListAirports => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let airports = db.list_all_airports()
                 .map_err(|e| anyhow::anyhow!("Failed to list airports: {}", e))?;
 
@@ -657,7 +671,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetAirport { airport_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             match db.get_airport(&airport_id) {
                 Ok(Some(airport)) => Ok(serde_json::to_value(airport)?),
                 Ok(None) => Err(anyhow::anyhow!("Airport not found: {}", airport_id)),
@@ -666,7 +680,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::CreateAirport { airport } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let airport_id = db.create_airport(
                 airport.icao_code.as_deref(),
                 airport.iata_code.as_deref(),
@@ -686,7 +700,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::SearchAirports { query, limit } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let all_airports = db.list_all_airports()
                 .map_err(|e| anyhow::anyhow!("Failed to list airports: {}", e))?;
 
@@ -710,7 +724,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetUser { user_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             match db.get_user(&user_id) {
                 Ok(Some(user)) => Ok(serde_json::to_value(user)?),
                 Ok(None) => Err(anyhow::anyhow!("User not found: {}", user_id)),
@@ -719,7 +733,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetPrimaryUser => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             match db.get_primary_user() {
                 Ok(Some(user)) => Ok(serde_json::to_value(user)?),
                 Ok(None) => Err(anyhow::anyhow!("No primary user found")),
@@ -728,14 +742,14 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetStatistics { user_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let stats = db.get_statistics(&user_id)
                 .map_err(|e| anyhow::anyhow!("Failed to get statistics: {}", e))?;
             Ok(serde_json::to_value(stats)?)
         }
 
         AgentCommand::GetAirportStats { airport_code, user_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let flights = db.list_flights(&user_id, 10000, 0)
                 .map_err(|e| anyhow::anyhow!("Failed to list flights: {}", e))?;
 
@@ -755,7 +769,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::ListResearchReports { user_id, limit } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let reports = db.list_research_reports(&user_id, limit.unwrap_or(50), 0)
                 .map_err(|e| anyhow::anyhow!("Failed to list reports: {}", e))?;
 
@@ -766,7 +780,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::GetResearchReport { report_id } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             match db.get_research_report(&report_id) {
                 Ok(Some(report)) => Ok(serde_json::to_value(report)?),
                 Ok(None) => Err(anyhow::anyhow!("Report not found: {}", report_id)),
@@ -775,7 +789,7 @@ async fn execute_command_sync(
         }
 
         AgentCommand::SaveResearchReport { user_id, report } => {
-            let db = Database::new(db_path.clone())?;
+            let db = Database::new(db_path.to_path_buf())?;
             let report_id = db.save_research_report(&user_id, &report)
                 .map_err(|e| anyhow::anyhow!("Failed to save report: {}", e))?;
 
@@ -786,6 +800,16 @@ async fn execute_command_sync(
             }))
         }
 
+        AgentCommand::ListReportTemplates => {
+            Ok(serde_json::to_value(crate::report_templates::list_templates())?)
+        }
+
+        AgentCommand::RunReportTemplate { name, params } => {
+            let db = Database::new(db_path.to_path_buf())?;
+            let result = crate::report_templates::run_template(&db.conn, &name, &params)?;
+            Ok(serde_json::to_value(result)?)
+        }
+
         _ => Err(anyhow::anyhow!("Command not supported via HTTP API")),
     }
 }
@@ -805,7 +829,7 @@ async fn start_http_server(addr: SocketAddr, db_path: std::path::PathBuf) -> Res
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    println!("🌐 HTTP API server listening on http://{}", addr);
+    tracing::info!("HTTP API server listening on http://{}", addr);
 
     axum::serve(listener, app).await?;
 
@@ -818,7 +842,7 @@ async fn start_http_server(addr: SocketAddr, db_path: std::path::PathBuf) -> Res
 
 async fn execute_command(
     command: AgentCommand,
-    db_path: &std::path::PathBuf,
+    db_path: &std::path::Path,
     ws_sender: &mut futures::stream::SplitSink<tokio_tungstenite::WebSocketStream<TcpStream>, Message>,
 ) -> Result<()> {
     match command {
@@ -906,7 +930,7 @@ async fn execute_command(
             match result {
                 Ok(data) => {
                     // Store research results in agent memory for future recall
-                    if let Ok(db) = Database::new(db_path.clone()) {
+                    if let Ok(db) = Database::new(db_path.to_path_buf()) {
                         let conn = db.get_connection();
                         let content = serde_json::to_string_pretty(&data).unwrap_or_default();
                         let summary = format!(
@@ -931,7 +955,7 @@ async fn execute_command(
                             Some(24), // Cache for 24 hours
                         );
 
-                        println!("💾 Stored research results in agent memory");
+                        tracing::info!("Stored research results in agent memory");
                     }
 
                     // Send completion event
@@ -998,7 +1022,7 @@ async fn execute_command(
             });
 
             // Store chat interaction in agent memory
-            if let Ok(db) = Database::new(db_path.clone()) {
+            if let Ok(db) = Database::new(db_path.to_path_buf()) {
                 let conn = db.get_connection();
                 let content = format!("Query: {}\nResponse: Chat functionality coming soon", query);
 
@@ -1018,7 +1042,7 @@ async fn execute_command(
                     Some(24), // Cache for 24 hours
                 );
 
-                println!("💾 Stored chat interaction in agent memory");
+                tracing::info!("Stored chat interaction in agent memory");
             }
 
             send_response(
@@ -1047,7 +1071,7 @@ async fn execute_command(
 
         AgentCommand::SearchMemories { query, limit } => {
             // Use the memory search functions
-            let response = match Database::new(db_path.clone()) {
+            let response = match Database::new(db_path.to_path_buf()) {
                 Ok(db) => {
                     let conn = db.get_connection();
                     match crate::agent_memory::search_memories(conn, &query, limit.unwrap_or(20)) {
@@ -1073,7 +1097,7 @@ async fn execute_command(
         }
 
         AgentCommand::GetMemoryStats => {
-            let response = match Database::new(db_path.clone()) {
+            let response = match Database::new(db_path.to_path_buf()) {
                 Ok(db) => {
                     let conn = db.get_connection();
                     match crate::agent_memory::get_memory_stats(conn) {
@@ -1162,11 +1186,12 @@ async fn send_streaming_event(
 
 // ===== BACKGROUND SERVER TASK =====
 
+#[allow(dead_code)]
 pub fn spawn_agent_server(db_path: std::path::PathBuf) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let server = AgentServer::new(9528, db_path);
         if let Err(e) = server.start().await {
-            eprintln!("❌ Agent server failed: {}", e);
+            tracing::error!("Agent server failed: {}", e);
         }
     })
 }
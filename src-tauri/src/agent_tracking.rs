@@ -3,6 +3,7 @@ use tauri::Emitter;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
 pub enum AgentStatus {
     Idle,
     Thinking,
@@ -12,6 +13,7 @@ pub enum AgentStatus {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct AgentState {
     pub agent_name: String,
     pub model: String,
@@ -122,7 +124,7 @@ pub fn emit_agent_event<R: tauri::Runtime>(
     event: AgentEvent,
 ) {
     if let Err(e) = app_handle.emit("agent:status", event) {
-        eprintln!("Failed to emit agent event: {}", e);
+        tracing::warn!("Failed to emit agent event: {}", e);
     }
 }
 
@@ -166,6 +168,7 @@ pub fn emit_agent_thinking<R: tauri::Runtime>(
 }
 
 /// Helper to emit agent executing event
+#[allow(dead_code)]
 pub fn emit_agent_executing<R: tauri::Runtime>(
     app_handle: &tauri::AppHandle<R>,
     agent_name: &str,
@@ -186,6 +189,7 @@ pub fn emit_agent_executing<R: tauri::Runtime>(
 }
 
 /// Helper to emit token update event
+#[allow(dead_code)]
 pub fn emit_agent_token_update<R: tauri::Runtime>(
     app_handle: &tauri::AppHandle<R>,
     agent_name: &str,
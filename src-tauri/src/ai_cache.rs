@@ -0,0 +1,231 @@
+// AI response cache
+// Responses are keyed by an MD5 of the query text and expire after a TTL. Every lookup is
+// logged to ai_cache_lookups so hit rates can be reported for a period, and hit_count on
+// the cached row counts the times it was served instead of a provider call.
+//
+// Savings are estimates: tokens come from the text length (web_search::estimate_tokens)
+// and cost from the agent_tracking pricing table, looked up by the row's provider.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::agent_tracking::ModelPricing;
+use crate::web_search::estimate_tokens;
+
+/// TTL for cached responses when the caller does not give one
+pub const DEFAULT_TTL_HOURS: i64 = 24 * 7;
+
+/// Period for the hit rate when none is given
+pub const DEFAULT_STATS_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub provider: String,
+    pub response_text: String,
+    pub created_at: String,
+    /// Times served from the cache, including this one
+    pub hit_count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AiCacheStats {
+    pub total_entries: i64,
+    /// Entries past their TTL; clear_ai_cache without a cutoff removes them
+    pub expired_entries: i64,
+    /// Responses served from the cache since it was created
+    pub total_hits: i64,
+    pub estimated_tokens_saved: i64,
+    pub estimated_cost_saved_usd: f64,
+    pub period_days: i64,
+    pub period_lookups: i64,
+    pub period_hits: i64,
+    /// period_hits / period_lookups; None when nothing was looked up in the period
+    pub hit_rate: Option<f64>,
+}
+
+pub fn query_hash(query_text: &str) -> String {
+    format!("{:x}", md5::compute(query_text))
+}
+
+/// The unexpired response for a query, counting the hit; every lookup is logged
+pub fn lookup(conn: &Connection, query_text: &str) -> Result<Option<CachedResponse>> {
+    lookup_within(conn, query_text, None)
+}
+
+/// Like lookup, but an entry older than `max_age_hours` is a miss even before it expires,
+/// so a TTL lowered after the entry was stored applies to it too
+pub fn lookup_within(conn: &Connection, query_text: &str, max_age_hours: Option<i64>) -> Result<Option<CachedResponse>> {
+    let hash = query_hash(query_text);
+    let cached = conn
+        .query_row(
+            "SELECT provider, response_text, created_at, hit_count FROM ai_response_cache
+             WHERE query_hash = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))
+               AND (?2 IS NULL OR created_at > datetime('now', ?2))",
+            params![hash, max_age_hours.map(|hours| format!("-{} hours", hours.max(0)))],
+            |row| {
+                Ok(CachedResponse {
+                    provider: row.get(0)?,
+                    response_text: row.get(1)?,
+                    created_at: row.get(2)?,
+                    hit_count: row.get::<_, Option<i64>>(3)?.unwrap_or(0) + 1,
+                })
+            },
+        )
+        .optional()?;
+
+    if cached.is_some() {
+        conn.execute(
+            "UPDATE ai_response_cache SET hit_count = COALESCE(hit_count, 0) + 1 WHERE query_hash = ?1",
+            [&hash],
+        )?;
+    }
+    conn.execute(
+        "INSERT INTO ai_cache_lookups (query_hash, hit) VALUES (?1, ?2)",
+        params![hash, cached.is_some()],
+    )?;
+    Ok(cached)
+}
+
+/// Cache a response; an existing (usually expired) entry for the query is replaced
+pub fn store(
+    conn: &Connection,
+    query_text: &str,
+    provider: &str,
+    response_text: &str,
+    confidence: Option<f64>,
+    ttl_hours: Option<i64>,
+) -> Result<()> {
+    let ttl = format!("+{} hours", ttl_hours.unwrap_or(DEFAULT_TTL_HOURS).max(0));
+    conn.execute(
+        "INSERT INTO ai_response_cache
+             (id, query_hash, provider, query_text, response_text, confidence, hit_count, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, datetime('now', ?7))
+         ON CONFLICT(query_hash) DO UPDATE SET
+             provider = excluded.provider,
+             response_text = excluded.response_text,
+             confidence = excluded.confidence,
+             hit_count = 0,
+             created_at = datetime('now'),
+             expires_at = excluded.expires_at",
+        params![
+            Uuid::new_v4().to_string(),
+            query_hash(query_text),
+            provider,
+            query_text,
+            response_text,
+            confidence,
+            ttl
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn stats(conn: &Connection, period_days: Option<i64>) -> Result<AiCacheStats> {
+    let period_days = period_days.unwrap_or(DEFAULT_STATS_DAYS).max(1);
+    let mut stats = AiCacheStats { period_days, ..Default::default() };
+
+    let mut stmt =
+        conn.prepare("SELECT provider, query_text, response_text, COALESCE(hit_count, 0) FROM ai_response_cache")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)?))
+    })?;
+    for row in rows {
+        let (provider, query_text, response_text, hits) = row?;
+        stats.total_entries += 1;
+        stats.total_hits += hits;
+        if hits == 0 {
+            continue;
+        }
+        // Each hit saved the prompt and the response of one provider call
+        let input = estimate_tokens(&query_text) as i64 * hits;
+        let output = estimate_tokens(&response_text) as i64 * hits;
+        stats.estimated_tokens_saved += input + output;
+        stats.estimated_cost_saved_usd +=
+            ModelPricing::get_pricing(&provider).calculate_cost(input as u32, output as u32);
+    }
+
+    stats.expired_entries = conn.query_row(
+        "SELECT COUNT(*) FROM ai_response_cache WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')",
+        [],
+        |row| row.get(0),
+    )?;
+    (stats.period_lookups, stats.period_hits) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(hit), 0) FROM ai_cache_lookups WHERE created_at >= datetime('now', ?1)",
+        [format!("-{} days", period_days)],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    if stats.period_lookups > 0 {
+        stats.hit_rate = Some(stats.period_hits as f64 / stats.period_lookups as f64);
+    }
+    Ok(stats)
+}
+
+/// Remove entries created more than `older_than_days` ago, or only expired ones when None
+///
+/// Lookups logged before the same cutoff go too. Returns the number of entries removed.
+pub fn clear(conn: &Connection, older_than_days: Option<i64>) -> Result<usize> {
+    let removed = match older_than_days {
+        Some(days) => {
+            let cutoff = format!("-{} days", days.max(0));
+            conn.execute(
+                "DELETE FROM ai_cache_lookups WHERE created_at < datetime('now', ?1)",
+                [&cutoff],
+            )?;
+            conn.execute(
+                "DELETE FROM ai_response_cache WHERE created_at < datetime('now', ?1)",
+                [&cutoff],
+            )?
+        }
+        None => conn.execute(
+            "DELETE FROM ai_response_cache WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')",
+            [],
+        )?,
+    };
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_hits_stats_and_clear() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let conn = &db.conn;
+
+        assert!(lookup(conn, "weather at LHR").unwrap().is_none());
+        store(conn, "weather at LHR", "deepseek-chat", "Rain all day", None, None).unwrap();
+        let hit = lookup(conn, "weather at LHR").unwrap().unwrap();
+        assert_eq!((hit.response_text.as_str(), hit.hit_count), ("Rain all day", 1));
+        lookup(conn, "weather at LHR").unwrap();
+
+        store(conn, "old query", "grok-beta", "stale", None, Some(0)).unwrap();
+        conn.execute("UPDATE ai_response_cache SET expires_at = datetime('now', '-1 hour') WHERE query_text = 'old query'", [])
+            .unwrap();
+        assert!(lookup(conn, "old query").unwrap().is_none());
+
+        let summary = stats(conn, None).unwrap();
+        assert_eq!((summary.total_entries, summary.expired_entries, summary.total_hits), (2, 1, 2));
+        assert_eq!((summary.period_lookups, summary.period_hits), (4, 2));
+        assert_eq!(summary.hit_rate, Some(0.5));
+        // "weather at LHR" and "Rain all day" are 4 and 3 tokens, saved twice
+        assert_eq!(summary.estimated_tokens_saved, 14);
+        assert!(summary.estimated_cost_saved_usd > 0.0);
+
+        assert_eq!(clear(conn, None).unwrap(), 1);
+        assert_eq!(clear(conn, Some(1)).unwrap(), 0);
+        assert_eq!(clear(conn, Some(0)).unwrap(), 0); // created this second, not before the cutoff
+        assert_eq!(stats(conn, None).unwrap().total_entries, 1);
+
+        // A maximum age below the TTL turns an older entry into a miss
+        store(conn, "aged query", "grok-beta", "three hours old", None, None).unwrap();
+        conn.execute("UPDATE ai_response_cache SET created_at = datetime('now', '-3 hours') WHERE query_text = 'aged query'", [])
+            .unwrap();
+        assert!(lookup_within(conn, "aged query", Some(2)).unwrap().is_none());
+        assert!(lookup_within(conn, "aged query", Some(4)).unwrap().is_some());
+    }
+}
@@ -0,0 +1,321 @@
+// Aircraft performance profiles
+// Cruise speed, fuel burn, CO2 factor and seats per aircraft type or registration, kept in the
+// aircraft_performance table so flight time, fuel and CO2 estimates come from one place and
+// can be tuned. Defaults for common ICAO type designators ship with the migration; editing
+// one makes it a user profile.
+//
+// Keys are matched exactly after uppercasing and dropping spaces and hyphens, so "g-abcd"
+// finds a "G-ABCD" profile. A flight's aircraft is looked up by registration, then by the
+// type designator of its aircraft type; aircraft with no profile fall back to the
+// heuristics in calculations.rs. Every flight time, fuel and CO2 estimate goes through
+// `resolve`.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::calculations::{fuel_gallons_to_kg, AircraftPerformance, TYPICAL_LOAD_FACTOR};
+use crate::database::Database;
+use crate::geo::KM_PER_NM;
+use crate::models::Flight;
+
+pub const KIND_TYPE: &str = "type";
+pub const KIND_REGISTRATION: &str = "registration";
+
+/// Where an aircraft's performance came from
+pub const BASIS_PROFILE: &str = "profile";
+pub const BASIS_TYPE_FAMILY: &str = "type_family";
+pub const BASIS_GENERIC: &str = "generic";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AircraftProfile {
+    pub id: String,
+    /// Type designator (e.g. B738) or registration, normalized
+    pub aircraft_key: String,
+    /// "type" or "registration"
+    pub key_kind: String,
+    pub cruise_kts: f64,
+    /// US gallons per hour at cruise
+    pub burn_gph: f64,
+    /// kg CO2 per kg of fuel
+    pub co2_factor: f64,
+    pub seats: Option<u32>,
+    /// Shipped with the app and not edited since
+    pub is_default: bool,
+    pub notes: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AircraftProfileInput {
+    pub aircraft_key: String,
+    pub key_kind: String,
+    pub cruise_kts: f64,
+    pub burn_gph: f64,
+    #[serde(default = "default_co2_factor")]
+    pub co2_factor: f64,
+    #[serde(default)]
+    pub seats: Option<u32>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+fn default_co2_factor() -> f64 {
+    AircraftPerformance::default().co2_factor
+}
+
+impl AircraftProfile {
+    /// The profile in the units the estimates use
+    pub fn performance(&self) -> AircraftPerformance {
+        let cruise_speed_kmh = self.cruise_kts * KM_PER_NM;
+        AircraftPerformance {
+            fuel_burn_rate_kg_per_km: fuel_gallons_to_kg(self.burn_gph) / cruise_speed_kmh,
+            co2_factor: self.co2_factor,
+            cruise_speed_kmh,
+            typical_load: match self.seats {
+                Some(seats) => seats as f64 * TYPICAL_LOAD_FACTOR,
+                None => AircraftPerformance::from_aircraft_type(&self.aircraft_key).typical_load,
+            },
+        }
+    }
+}
+
+/// "g-abcd " -> "GABCD"
+pub fn normalize_key(key: &str) -> String {
+    key.chars().filter(|c| !c.is_whitespace() && *c != '-').collect::<String>().to_uppercase()
+}
+
+impl AircraftProfileInput {
+    /// Field errors as (field, message)
+    pub fn invalid_fields(&self) -> Vec<(&'static str, &'static str)> {
+        let mut errors = Vec::new();
+        if normalize_key(&self.aircraft_key).is_empty() {
+            errors.push(("aircraft_key", "is required"));
+        }
+        if self.key_kind != KIND_TYPE && self.key_kind != KIND_REGISTRATION {
+            errors.push(("key_kind", "must be \"type\" or \"registration\""));
+        }
+        for (field, value) in [("cruise_kts", self.cruise_kts), ("burn_gph", self.burn_gph), ("co2_factor", self.co2_factor)] {
+            if !(value.is_finite() && value > 0.0) {
+                errors.push((field, "must be a positive number"));
+            }
+        }
+        if self.seats == Some(0) {
+            errors.push(("seats", "must be at least 1"));
+        }
+        errors
+    }
+}
+
+const SELECT_PROFILE: &str = "SELECT id, aircraft_key, key_kind, cruise_kts, burn_gph, co2_factor, seats, is_default, notes, updated_at
+                              FROM aircraft_performance";
+
+fn map_profile(row: &rusqlite::Row) -> rusqlite::Result<AircraftProfile> {
+    Ok(AircraftProfile {
+        id: row.get(0)?,
+        aircraft_key: row.get(1)?,
+        key_kind: row.get(2)?,
+        cruise_kts: row.get(3)?,
+        burn_gph: row.get(4)?,
+        co2_factor: row.get(5)?,
+        seats: row.get(6)?,
+        is_default: row.get(7)?,
+        notes: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+/// Registrations first, then types, each alphabetically
+pub fn list(conn: &Connection) -> Result<Vec<AircraftProfile>> {
+    let mut stmt = conn.prepare(&format!("{} ORDER BY key_kind = 'type', aircraft_key", SELECT_PROFILE))?;
+    let profiles = stmt.query_map([], map_profile)?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(profiles)
+}
+
+/// The profile for a type designator or registration, if there is one
+pub fn find(conn: &Connection, aircraft: &str) -> Result<Option<AircraftProfile>> {
+    let key = normalize_key(aircraft);
+    if key.is_empty() {
+        return Ok(None);
+    }
+    Ok(conn
+        .query_row(&format!("{} WHERE aircraft_key = ?1", SELECT_PROFILE), [key], map_profile)
+        .optional()?)
+}
+
+/// What a flight records about its aircraft
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AircraftRef<'a> {
+    /// Registration, or whatever the field holds ("Boeing 737" in older records)
+    pub registration: Option<&'a str>,
+    pub aircraft_type_id: Option<&'a str>,
+}
+
+impl<'a> AircraftRef<'a> {
+    /// An aircraft known only by a type designator, name or registration
+    pub fn named(aircraft: Option<&'a str>) -> Self {
+        Self { registration: aircraft, aircraft_type_id: None }
+    }
+
+    pub fn of_flight(flight: &'a Flight) -> Self {
+        Self {
+            registration: flight.aircraft_registration.as_deref(),
+            aircraft_type_id: flight.aircraft_type_id.as_deref(),
+        }
+    }
+}
+
+/// The stored profile and aircraft type name behind an AircraftRef
+#[derive(Debug, Clone, Default)]
+pub struct AircraftLookup {
+    /// The registration's profile, else the type designator's
+    pub profile: Option<AircraftProfile>,
+    /// Manufacturer, model and designator of the aircraft type, for the name heuristics
+    pub type_name: Option<String>,
+}
+
+pub fn lookup(db: &Database, aircraft: AircraftRef) -> Result<AircraftLookup> {
+    let aircraft_type = match aircraft.aircraft_type_id {
+        Some(id) => db.get_aircraft_type(id)?,
+        None => None,
+    };
+    let designator = aircraft_type.as_ref().and_then(|t| t.type_designator.as_deref());
+    let mut profile = None;
+    for key in [aircraft.registration, designator].into_iter().flatten() {
+        profile = find(&db.conn, key)?;
+        if profile.is_some() {
+            break;
+        }
+    }
+    let type_name = aircraft_type
+        .as_ref()
+        .map(|t| format!("{} {} {}", t.manufacturer, t.model, designator.unwrap_or("")).trim().to_string());
+    Ok(AircraftLookup { profile, type_name })
+}
+
+/// Performance for an aircraft and its basis: the stored profile, else the heuristic for a
+/// recognised type family (from the aircraft type's name, then the registration text), else
+/// the generic narrow-body figures
+pub fn resolve(db: &Database, aircraft: AircraftRef) -> (AircraftPerformance, &'static str) {
+    let found = lookup(db, aircraft).unwrap_or_else(|e| {
+        tracing::warn!("[Performance] Profile lookup failed, using heuristics: {}", e);
+        AircraftLookup::default()
+    });
+    if let Some(profile) = found.profile {
+        return (profile.performance(), BASIS_PROFILE);
+    }
+    let family = [found.type_name.as_deref(), aircraft.registration]
+        .into_iter()
+        .flatten()
+        .find_map(AircraftPerformance::known_type);
+    match family {
+        Some(performance) => (performance, BASIS_TYPE_FAMILY),
+        None => (AircraftPerformance::default(), BASIS_GENERIC),
+    }
+}
+
+/// Performance for an aircraft, without its basis
+pub fn performance_for(db: &Database, aircraft: AircraftRef) -> AircraftPerformance {
+    resolve(db, aircraft).0
+}
+
+/// Create or replace the profile for a key; an edited default becomes a user profile
+pub fn save(conn: &Connection, input: &AircraftProfileInput) -> Result<AircraftProfile> {
+    let key = normalize_key(&input.aircraft_key);
+    conn.execute(
+        "INSERT INTO aircraft_performance (id, aircraft_key, key_kind, cruise_kts, burn_gph, co2_factor, seats, is_default, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8)
+         ON CONFLICT(aircraft_key) DO UPDATE SET
+             key_kind = ?3, cruise_kts = ?4, burn_gph = ?5, co2_factor = ?6, seats = ?7, is_default = 0, notes = ?8,
+             updated_at = datetime('now')",
+        params![
+            Uuid::new_v4().to_string(),
+            key,
+            input.key_kind,
+            input.cruise_kts,
+            input.burn_gph,
+            input.co2_factor,
+            input.seats,
+            input.notes
+        ],
+    )?;
+    find(conn, &key)?.ok_or_else(|| anyhow::anyhow!("Profile {} was not saved", key))
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM aircraft_performance WHERE id = ?1", [id])? > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_profiles_feed_estimates() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+
+        // Shipped default: a 737-800 at 453 kt is 839 km/h
+        let b738 = find(&db.conn, "b738").unwrap().unwrap();
+        assert!(b738.is_default);
+        let performance = performance_for(&db, AircraftRef::named(Some("B738")));
+        assert!((performance.cruise_speed_kmh - 838.956).abs() < 0.01);
+        assert_eq!(performance.flight_time(839.0), 95);
+
+        // A registration profile wins for that aircraft; others keep the heuristics
+        let input = AircraftProfileInput {
+            aircraft_key: "g-abcd".to_string(),
+            key_kind: KIND_REGISTRATION.to_string(),
+            cruise_kts: 100.0,
+            burn_gph: 10.0,
+            co2_factor: 3.1,
+            seats: Some(3),
+            notes: None,
+        };
+        assert!(input.invalid_fields().is_empty());
+        let saved = save(&db.conn, &input).unwrap();
+        assert_eq!((saved.aircraft_key.as_str(), saved.is_default), ("GABCD", false));
+
+        let tuned = performance_for(&db, AircraftRef::named(Some("G-ABCD")));
+        assert_eq!(tuned.flight_time(185.2), 45 + 60);
+        let fuel_kg = fuel_gallons_to_kg(10.0);
+        assert!((tuned.co2_emissions(185.2) - (fuel_kg * 1.1 + 20.0) * 3.1).abs() < 0.01);
+        // Shared across a typical load of the seats, not a full aircraft
+        let share = crate::calculations::per_passenger_co2(&tuned, 185.2, None);
+        assert!((share - tuned.co2_emissions(185.2) / 2.4).abs() < 1e-9);
+
+        let boeing = AircraftRef::named(Some("Boeing 777"));
+        assert_eq!(resolve(&db, boeing), (AircraftPerformance::from_aircraft_type("777"), BASIS_TYPE_FAMILY));
+
+        // Flights created for the aircraft use its profile
+        db.conn.execute("INSERT INTO users (id, name) VALUES ('u1', 'Pilot')", []).unwrap();
+        let flight: crate::models::FlightInput = serde_json::from_value(serde_json::json!({
+            "departure_airport": "EGKB",
+            "arrival_airport": "EGHI",
+            "departure_datetime": "2025-05-01T09:00:00",
+            "aircraft_registration": "G-ABCD",
+            "distance_km": 185.2,
+        }))
+        .unwrap();
+        let id = db.create_flight("u1", &flight).unwrap();
+        assert_eq!(db.get_flight(&id).unwrap().unwrap().flight_duration, Some(105));
+
+        // A flight's aircraft type finds the type's profile when the registration has none
+        db.conn
+            .execute(
+                "INSERT INTO aircraft_types (id, manufacturer, model, type_designator) VALUES ('t1', 'Piper', 'Archer', 'PA28')",
+                [],
+            )
+            .unwrap();
+        let archer = AircraftRef { registration: Some("N8123X"), aircraft_type_id: Some("t1") };
+        let (performance, basis) = resolve(&db, archer);
+        assert_eq!(basis, BASIS_PROFILE);
+        assert_eq!(performance, find(&db.conn, "PA28").unwrap().unwrap().performance());
+        assert_eq!(resolve(&db, AircraftRef::named(Some("N8123X"))).1, BASIS_GENERIC);
+
+        assert!(delete(&db.conn, &saved.id).unwrap());
+        let bad = AircraftProfileInput { cruise_kts: 0.0, key_kind: "tail".to_string(), ..input };
+        assert_eq!(bad.invalid_fields().iter().map(|(f, _)| *f).collect::<Vec<_>>(), ["key_kind", "cruise_kts"]);
+    }
+}
@@ -0,0 +1,141 @@
+// Airline lookup - infer the operating carrier from a flight number
+//
+// Flight numbers are a carrier designator followed by 1-4 digits and an optional
+// operational suffix letter: "BA286", "BA 286", "U2 1234", or the ICAO form "BAW286".
+// The designator is resolved against the bundled resources/airlines.csv table.
+// Numeric-only numbers, private callsigns/registrations and codeshare strings
+// listing several designators ("BA286/AA6135") resolve to nothing.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Airline {
+    pub iata: String,
+    pub icao: String,
+    pub name: String,
+    /// "Star Alliance", "oneworld" or "SkyTeam"
+    pub alliance: Option<String>,
+    /// Frequent flyer programme the carrier's flights usually credit to
+    pub ffp_program: Option<String>,
+}
+
+static AIRLINES: OnceLock<Vec<Airline>> = OnceLock::new();
+
+fn airlines() -> &'static [Airline] {
+    AIRLINES.get_or_init(|| {
+        let mut reader = csv::Reader::from_reader(include_str!("../resources/airlines.csv").as_bytes());
+        reader
+            .records()
+            .filter_map(|record| record.ok())
+            .filter_map(|record| {
+                let field = |i: usize| record.get(i).map(str::trim).filter(|v| !v.is_empty());
+                Some(Airline {
+                    iata: field(0)?.to_uppercase(),
+                    icao: field(1)?.to_uppercase(),
+                    name: field(2)?.to_string(),
+                    alliance: field(3).map(str::to_string),
+                    ffp_program: field(4).map(str::to_string),
+                })
+            })
+            .collect()
+    })
+}
+
+/// Look up a carrier by its 2-character IATA or 3-letter ICAO designator
+pub fn lookup_code(code: &str) -> Option<&'static Airline> {
+    let code = code.trim().to_uppercase();
+    airlines().iter().find(|a| match code.len() {
+        2 => a.iata == code,
+        3 => a.icao == code,
+        _ => false,
+    })
+}
+
+/// Look up a carrier by its full name ("British Airways")
+pub fn lookup_name(name: &str) -> Option<&'static Airline> {
+    let name = name.trim();
+    airlines().iter().find(|a| a.name.eq_ignore_ascii_case(name))
+}
+
+/// The carrier whose frequent flyer programme has this name ("Executive Club")
+/// Shared programmes such as Miles & More resolve to the first listed carrier.
+pub fn lookup_program(program_name: &str) -> Option<&'static Airline> {
+    let program_name = program_name.trim();
+    airlines()
+        .iter()
+        .find(|a| a.ffp_program.as_deref().is_some_and(|p| p.eq_ignore_ascii_case(program_name)))
+}
+
+/// Operating carrier for a flight number, or None when it cannot be determined
+pub fn infer_from_flight_number(flight_number: &str) -> Option<&'static Airline> {
+    let upper = flight_number.trim().to_uppercase();
+    // Codeshares list several designators; the operating carrier is ambiguous
+    if upper.contains(['/', ',', '&', '+']) || upper.contains("OPERATED BY") {
+        return None;
+    }
+
+    let compact: String = upper.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+    if !compact.is_ascii() {
+        return None;
+    }
+
+    // ICAO designators are three letters; try them first so "BAW286" is not read as "BA" + "W286"
+    if compact.len() > 3 && compact[..3].chars().all(|c| c.is_ascii_alphabetic()) && is_flight_digits(&compact[3..]) {
+        if let Some(airline) = lookup_code(&compact[..3]) {
+            return Some(airline);
+        }
+    }
+
+    // IATA designators are two characters, at least one of them a letter ("U2", "6E")
+    let prefix = compact.get(..2)?;
+    if prefix.chars().all(|c| c.is_ascii_alphanumeric())
+        && prefix.chars().any(|c| c.is_ascii_alphabetic())
+        && is_flight_digits(&compact[2..])
+    {
+        return lookup_code(prefix);
+    }
+
+    None
+}
+
+/// 1-4 digits, optionally followed by a single operational suffix letter
+fn is_flight_digits(value: &str) -> bool {
+    let digits = value.strip_suffix(|c: char| c.is_ascii_alphabetic()).unwrap_or(value);
+    (1..=4).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iata(flight_number: &str) -> Option<&'static str> {
+        infer_from_flight_number(flight_number).map(|a| a.iata.as_str())
+    }
+
+    #[test]
+    fn test_infer_from_flight_number() {
+        assert_eq!(iata("BA286"), Some("BA"));
+        assert_eq!(iata(" ba 286 "), Some("BA"));
+        assert_eq!(iata("BAW286"), Some("BA"));
+        assert_eq!(iata("U2-1234"), Some("U2"));
+        assert_eq!(iata("6E2041"), Some("6E"));
+        assert_eq!(iata("LH400A"), Some("LH"));
+
+        let ba = infer_from_flight_number("BA286").unwrap();
+        assert_eq!(ba.alliance.as_deref(), Some("oneworld"));
+        assert_eq!(ba.ffp_program.as_deref(), Some("Executive Club"));
+    }
+
+    #[test]
+    fn test_uninferable_flight_numbers() {
+        assert_eq!(iata("1234"), None);
+        assert_eq!(iata("N123AB"), None);
+        assert_eq!(iata("GABCD"), None);
+        assert_eq!(iata("ZZ123"), None);
+        assert_eq!(iata("BA286/AA6135"), None);
+        assert_eq!(iata("AA6135 operated by BA"), None);
+        assert_eq!(iata("BA12345"), None);
+        assert_eq!(iata(""), None);
+    }
+}
@@ -0,0 +1,355 @@
+// Airport import
+// Loads a user's own airport CSV (an OurAirports export, a national AIP extract...) into the
+// airports table. The column mapping names the CSV header for each field. Fields it leaves
+// out are found by their usual header names ("icao_code" or "ident", "latitude_deg" or
+// "latitude", ...).
+//
+// Each row is matched to an existing airport by ICAO code, then IATA code. A match is updated
+// in place, keeping the stored value for any field the CSV leaves blank; anything else is
+// inserted. Rows with no plausible code, no name or coordinates out of range are skipped, as
+// are later rows repeating a code already imported from the file. The file is parsed first,
+// then written in batches of IMPORT_BATCH_SIZE rows, one transaction each, releasing the
+// database lock between batches so other commands are not held up by a large file. Imported
+// coordinates get coordinate_source = "import".
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+use std::sync::Mutex;
+
+use crate::database::Database;
+use crate::error::CommandError;
+
+/// Rows written per transaction; the database lock is released between batches
+const IMPORT_BATCH_SIZE: usize = 500;
+
+/// Skipped rows listed in the report; the count covers them all
+const MAX_REPORTED_SKIPS: usize = 100;
+
+/// CSV header for each airport field; None looks for the usual names
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AirportColumnMapping {
+    pub icao: Option<String>,
+    pub iata: Option<String>,
+    pub name: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub latitude: Option<String>,
+    pub longitude: Option<String>,
+    pub timezone: Option<String>,
+    pub elevation_ft: Option<String>,
+    pub runway_length_ft: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedAirportRow {
+    /// 1-based line in the file, counting the header
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AirportImportReport {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    /// The first skipped rows and why
+    pub skipped_rows: Vec<SkippedAirportRow>,
+}
+
+/// Column index per field, resolved against the header
+struct Columns {
+    icao: Option<usize>,
+    iata: Option<usize>,
+    name: Option<usize>,
+    city: Option<usize>,
+    country: Option<usize>,
+    latitude: Option<usize>,
+    longitude: Option<usize>,
+    timezone: Option<usize>,
+    elevation_ft: Option<usize>,
+    runway_length_ft: Option<usize>,
+}
+
+impl Columns {
+    fn resolve(headers: &csv::StringRecord, mapping: &AirportColumnMapping) -> Result<Self, CommandError> {
+        let position = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name.trim()));
+        let mut missing = Vec::new();
+        let mut column = |mapped: &Option<String>, usual: &[&str]| match mapped {
+            Some(name) => {
+                let found = position(name);
+                if found.is_none() {
+                    missing.push(name.clone());
+                }
+                found
+            }
+            None => usual.iter().find_map(|name| position(name)),
+        };
+        let columns = Columns {
+            // OurAirports keeps ICAO in icao_code and a local identifier in ident; codes that
+            // are not plausible ICAO codes are dropped row by row
+            icao: column(&mapping.icao, &["icao_code", "icao", "ident"]),
+            iata: column(&mapping.iata, &["iata_code", "iata"]),
+            name: column(&mapping.name, &["name", "airport_name"]),
+            city: column(&mapping.city, &["municipality", "city"]),
+            country: column(&mapping.country, &["iso_country", "country"]),
+            latitude: column(&mapping.latitude, &["latitude_deg", "latitude", "lat"]),
+            longitude: column(&mapping.longitude, &["longitude_deg", "longitude", "lon", "lng"]),
+            timezone: column(&mapping.timezone, &["timezone", "tz", "tz_database_time_zone"]),
+            elevation_ft: column(&mapping.elevation_ft, &["elevation_ft", "elevation"]),
+            runway_length_ft: column(&mapping.runway_length_ft, &["longest_runway_ft", "runway_length_ft", "runway_length"]),
+        };
+        if !missing.is_empty() {
+            return Err(CommandError::Validation(format!("Mapped columns not in the CSV header: {}", missing.join(", "))));
+        }
+        if columns.name.is_none() {
+            return Err(CommandError::Validation("No name column; map one with mapping.name".to_string()));
+        }
+        if columns.icao.is_none() && columns.iata.is_none() {
+            return Err(CommandError::Validation("No ICAO or IATA code column; map one with mapping.icao or mapping.iata".to_string()));
+        }
+        if columns.latitude.is_some() != columns.longitude.is_some() {
+            return Err(CommandError::Validation("Latitude and longitude must both be mapped, or neither".to_string()));
+        }
+        Ok(columns)
+    }
+}
+
+/// A validated row ready to upsert
+#[derive(Debug, Clone, PartialEq)]
+struct AirportRow {
+    icao: Option<String>,
+    iata: Option<String>,
+    name: String,
+    city: Option<String>,
+    country: Option<String>,
+    coordinates: Option<(f64, f64)>,
+    timezone: Option<String>,
+    elevation_ft: Option<i64>,
+    runway_length_ft: Option<i64>,
+}
+
+fn is_plausible_icao(code: &str) -> bool {
+    code.len() == 4 && code.chars().all(|c| c.is_ascii_alphanumeric()) && code.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn is_plausible_iata(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn parse_row(record: &csv::StringRecord, columns: &Columns) -> Result<AirportRow, String> {
+    let field = |index: Option<usize>| {
+        index
+            .and_then(|i| record.get(i))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let number = |index: Option<usize>, what: &str| -> Result<Option<f64>, String> {
+        field(index)
+            .map(|v| v.parse::<f64>().ok().filter(|n| n.is_finite()).ok_or_else(|| format!("{} \"{}\" is not a number", what, v)))
+            .transpose()
+    };
+
+    let icao = field(columns.icao).map(|c| c.to_uppercase()).filter(|c| is_plausible_icao(c));
+    let iata = field(columns.iata).map(|c| c.to_uppercase()).filter(|c| is_plausible_iata(c));
+    if icao.is_none() && iata.is_none() {
+        return Err("no plausible ICAO (4 letters/digits) or IATA (3 letters) code".to_string());
+    }
+    let name = field(columns.name).ok_or("no name")?;
+
+    let coordinates = match (number(columns.latitude, "latitude")?, number(columns.longitude, "longitude")?) {
+        (Some(lat), Some(lon)) => {
+            if !(-90.0..=90.0).contains(&lat) {
+                return Err(format!("latitude {} is out of range", lat));
+            }
+            if !(-180.0..=180.0).contains(&lon) {
+                return Err(format!("longitude {} is out of range", lon));
+            }
+            Some((lat, lon))
+        }
+        (None, None) => None,
+        _ => return Err("only one of latitude and longitude".to_string()),
+    };
+    let runway_length_ft = number(columns.runway_length_ft, "runway length")?.map(|ft| ft.round() as i64);
+    if runway_length_ft.is_some_and(|ft| ft <= 0) {
+        return Err("runway length must be positive".to_string());
+    }
+
+    Ok(AirportRow {
+        icao,
+        iata,
+        name,
+        city: field(columns.city),
+        country: field(columns.country),
+        coordinates,
+        timezone: field(columns.timezone),
+        elevation_ft: number(columns.elevation_ft, "elevation")?.map(|ft| ft.round() as i64),
+        runway_length_ft,
+    })
+}
+
+/// Existing airport with either code, ICAO first. Codes are stored upper case, as parse_row
+/// leaves them, so the plain comparison can use the code indexes.
+fn find_airport(conn: &Connection, row: &AirportRow) -> rusqlite::Result<Option<String>> {
+    for (column, code) in [("icao_code", &row.icao), ("iata_code", &row.iata)] {
+        let Some(code) = code else { continue };
+        let id = conn
+            .query_row(&format!("SELECT id FROM airports WHERE {} = ?1", column), [code], |r| r.get(0))
+            .optional()?;
+        if id.is_some() {
+            return Ok(id);
+        }
+    }
+    Ok(None)
+}
+
+/// Insert or update one airport; true when inserted
+fn upsert(conn: &Connection, row: &AirportRow) -> rusqlite::Result<bool> {
+    let (latitude, longitude) = row.coordinates.unzip();
+    let source = row.coordinates.map(|_| "import");
+    match find_airport(conn, row)? {
+        Some(id) => {
+            conn.execute(
+                "UPDATE airports SET
+                     icao_code = COALESCE(?2, icao_code), iata_code = COALESCE(?3, iata_code), name = ?4,
+                     city = COALESCE(?5, city), country = COALESCE(?6, country),
+                     latitude = COALESCE(?7, latitude), longitude = COALESCE(?8, longitude),
+                     coordinate_source = COALESCE(?9, coordinate_source), timezone = COALESCE(?10, timezone),
+                     elevation_ft = COALESCE(?11, elevation_ft), longest_runway_ft = COALESCE(?12, longest_runway_ft),
+                     updated_at = datetime('now')
+                 WHERE id = ?1",
+                params![
+                    id, row.icao, row.iata, row.name, row.city, row.country, latitude, longitude, source,
+                    row.timezone, row.elevation_ft, row.runway_length_ft
+                ],
+            )?;
+            Ok(false)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO airports (id, icao_code, iata_code, name, city, country, latitude, longitude,
+                                       coordinate_source, timezone, elevation_ft, longest_runway_ft)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    uuid::Uuid::new_v4().to_string(), row.icao, row.iata, row.name, row.city, row.country,
+                    latitude, longitude, source, row.timezone, row.elevation_ft, row.runway_length_ft
+                ],
+            )?;
+            Ok(true)
+        }
+    }
+}
+
+/// Import every row of the CSV in `reader`. Batches written before a failing one stay imported.
+pub fn import_airports<R: Read>(db: &Mutex<Database>, reader: R, mapping: &AirportColumnMapping) -> Result<AirportImportReport> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(reader);
+    let headers = csv.headers().context("Failed to read the CSV header")?.clone();
+    let columns = Columns::resolve(&headers, mapping)?;
+
+    let mut rows = Vec::new();
+    let mut report = AirportImportReport::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let skip = |report: &mut AirportImportReport, line: usize, reason: String| {
+        report.skipped += 1;
+        if report.skipped_rows.len() < MAX_REPORTED_SKIPS {
+            report.skipped_rows.push(SkippedAirportRow { line, reason });
+        }
+    };
+
+    for (i, record) in csv.records().enumerate() {
+        let line = i + 2;
+        let row = match record.map_err(|e| e.to_string()).and_then(|r| parse_row(&r, &columns)) {
+            Ok(row) => row,
+            Err(reason) => {
+                skip(&mut report, line, reason);
+                continue;
+            }
+        };
+        let codes: Vec<&String> = row.icao.iter().chain(row.iata.iter()).collect();
+        if let Some(repeated) = codes.iter().find(|code| seen.contains(code.as_str())) {
+            skip(&mut report, line, format!("{} already imported from an earlier row", repeated));
+            continue;
+        }
+        seen.extend(codes.into_iter().cloned());
+        rows.push(row);
+    }
+
+    for batch in rows.chunks(IMPORT_BATCH_SIZE) {
+        let db = db.lock().map_err(|e| anyhow::anyhow!("Database lock poisoned: {}", e))?;
+        let tx = db.conn.unchecked_transaction()?;
+        let mut inserted = 0;
+        for row in batch {
+            if upsert(&tx, row)? {
+                inserted += 1;
+            }
+        }
+        tx.commit()?;
+        report.inserted += inserted;
+        report.updated += batch.len() - inserted;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_import_airports() {
+        let db = Mutex::new(Database::new(PathBuf::from(":memory:")).unwrap());
+        db.lock()
+            .unwrap()
+            .conn
+            .execute_batch(
+                "INSERT INTO airports (id, icao_code, iata_code, name, city, timezone)
+                 VALUES ('EGLL', 'EGLL', 'LHR', 'Heathrow', 'London', 'Europe/London');",
+            )
+            .unwrap();
+
+        let csv = "ICAO,IATA,Airport,Lat,Lon,Runway\n\
+                   EGLL,LHR,London Heathrow Airport,51.4706,-0.461941,12802\n\
+                   LEMD,MAD,Adolfo Suarez Madrid-Barajas,40.4719,-3.5626,14268\n\
+                   XX-1,,Farm Strip,10,10,\n\
+                   ZZZZ,,Nowhere,95,0,\n\
+                   EDDF,FRA,Frankfurt,50.03,8.57,-5\n\
+                   LEMD,,Madrid again,40.47,-3.56,\n\
+                   ,bcn,Barcelona,41.30,2.08,\n";
+        let mapping = AirportColumnMapping {
+            icao: Some("ICAO".to_string()),
+            name: Some("Airport".to_string()),
+            latitude: Some("Lat".to_string()),
+            longitude: Some("Lon".to_string()),
+            runway_length_ft: Some("Runway".to_string()),
+            ..Default::default()
+        };
+        let report = import_airports(&db, csv.as_bytes(), &mapping).unwrap();
+        assert_eq!((report.inserted, report.updated, report.skipped), (2, 1, 4));
+        let lines: Vec<_> = report.skipped_rows.iter().map(|s| s.line).collect();
+        assert_eq!(lines, [4, 5, 6, 7]);
+        assert!(report.skipped_rows[1].reason.contains("latitude 95"));
+
+        let unknown = AirportColumnMapping { name: Some("Title".to_string()), ..Default::default() };
+        assert!(import_airports(&db, csv.as_bytes(), &unknown).unwrap_err().to_string().contains("Title"));
+
+        // Updated in place, keeping the timezone the CSV does not have
+        let db = db.into_inner().unwrap();
+        let (name, timezone, runway, source): (String, Option<String>, Option<i64>, Option<String>) = db
+            .conn
+            .query_row(
+                "SELECT name, timezone, longest_runway_ft, coordinate_source FROM airports WHERE id = 'EGLL'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (name.as_str(), timezone.as_deref(), runway, source.as_deref()),
+            ("London Heathrow Airport", Some("Europe/London"), Some(12802), Some("import"))
+        );
+        let bcn: Option<String> = db.conn.query_row("SELECT icao_code FROM airports WHERE iata_code = 'BCN'", [], |r| r.get(0)).unwrap();
+        assert_eq!(bcn, None);
+    }
+}
@@ -0,0 +1,203 @@
+// Arrival before departure
+// Flight times are stored as naive local times, and a westbound flight can legitimately land
+// at an earlier clock time than it left, so comparing the raw strings cannot tell it from a
+// real error. Both ends are converted to UTC with their airports' timezones instead, and a
+// flight is flagged only when it lands before it takes off in UTC.
+//
+// Flights without a recorded arrival time, or without a usable timezone at either airport,
+// cannot be checked and are counted as skipped.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::flights::build_flight_times;
+use crate::database::Database;
+use crate::flight_sanity::{raise_anomaly, resolve_open_anomaly, SanityIssue};
+use crate::models::{Flight, FlightEndpointTimes};
+
+pub const ANOMALY_TYPE: &str = "arrival_before_departure";
+
+pub const SUGGESTED_FIX: &str = "Check the arrival date and time, and that the airports are the right way round";
+
+/// Why a flight could not be checked, or what the check found
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    InOrder,
+    ArrivesFirst(Box<ArrivalOrderIssue>),
+    NoArrivalTime,
+    NoTimezone,
+    /// The local times could not be read or do not exist in their timezone
+    Unreadable,
+}
+
+/// The UTC times behind a flagged flight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrivalOrderIssue {
+    pub flight_id: String,
+    /// Set once recorded in flight_anomalies
+    pub anomaly_id: Option<String>,
+    pub departure: FlightEndpointTimes,
+    pub arrival: FlightEndpointTimes,
+    /// How long before departure the flight lands
+    pub minutes_early: i64,
+}
+
+impl ArrivalOrderIssue {
+    pub fn describe(&self) -> String {
+        let end = |times: &FlightEndpointTimes| {
+            format!(
+                "{} ({} local at {}, {})",
+                times.utc_time.as_deref().unwrap_or("?"),
+                times.local_time.as_deref().unwrap_or("?"),
+                times.airport_code,
+                times.timezone.as_deref().unwrap_or("?")
+            )
+        };
+        format!(
+            "Arrives at {}, {} min before it departs at {}",
+            end(&self.arrival),
+            self.minutes_early,
+            end(&self.departure)
+        )
+    }
+
+    fn to_sanity_issue(&self) -> SanityIssue {
+        SanityIssue::new(ANOMALY_TYPE, "error", self.describe(), SUGGESTED_FIX.to_string())
+    }
+}
+
+/// Compare the UTC times of a flight with its airports' timezones
+pub fn check(flight: &Flight, departure_tz: Option<String>, arrival_tz: Option<String>) -> Outcome {
+    if flight.arrival_datetime.as_deref().is_none_or(|a| a.trim().is_empty()) {
+        return Outcome::NoArrivalTime;
+    }
+    let times = build_flight_times(flight.clone(), departure_tz, arrival_tz);
+    if times.departure.timezone_status != "resolved" || times.arrival.timezone_status != "resolved" {
+        return Outcome::NoTimezone;
+    }
+    match times.elapsed_minutes {
+        None => Outcome::Unreadable,
+        Some(elapsed) if elapsed < 0 => Outcome::ArrivesFirst(Box::new(ArrivalOrderIssue {
+            flight_id: flight.id.clone(),
+            anomaly_id: None,
+            departure: times.departure,
+            arrival: times.arrival,
+            minutes_early: -elapsed,
+        })),
+        Some(_) => Outcome::InOrder,
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArrivalOrderScan {
+    pub checked_count: usize,
+    pub flagged: Vec<ArrivalOrderIssue>,
+    pub skipped_no_arrival: usize,
+    pub skipped_no_timezone: usize,
+    pub skipped_unreadable: usize,
+}
+
+/// Check a flight and store the outcome: a flight landing first raises an anomaly (or updates
+/// the open one), one that checks out resolves it. Skipped flights are left alone.
+pub fn record(db: &Database, flight: &Flight, timezones: &mut HashMap<String, Option<String>>) -> Result<Outcome> {
+    let mut timezone = |code: &str| -> Result<Option<String>> {
+        if let Some(tz) = timezones.get(code) {
+            return Ok(tz.clone());
+        }
+        let tz = db.get_airport_by_code(code)?.and_then(|a| a.timezone);
+        timezones.insert(code.to_string(), tz.clone());
+        Ok(tz)
+    };
+    let departure_tz = timezone(&flight.departure_airport)?;
+    let arrival_tz = timezone(&flight.arrival_airport)?;
+
+    let mut outcome = check(flight, departure_tz, arrival_tz);
+    match &mut outcome {
+        Outcome::ArrivesFirst(issue) => issue.anomaly_id = Some(raise_anomaly(&db.conn, &flight.id, &issue.to_sanity_issue())?),
+        Outcome::InOrder => resolve_open_anomaly(&db.conn, &flight.id, ANOMALY_TYPE)?,
+        _ => {}
+    }
+    Ok(outcome)
+}
+
+/// Check every flight of a user
+pub fn scan(db: &Database, user_id: &str) -> Result<ArrivalOrderScan> {
+    let flights = db.list_flights(user_id, i32::MAX, 0)?;
+    let mut timezones = HashMap::new();
+    let mut scan = ArrivalOrderScan::default();
+    let tx = db.conn.unchecked_transaction()?;
+    for flight in &flights {
+        match record(db, flight, &mut timezones)? {
+            Outcome::InOrder => scan.checked_count += 1,
+            Outcome::ArrivesFirst(issue) => {
+                scan.checked_count += 1;
+                scan.flagged.push(*issue);
+            }
+            Outcome::NoArrivalTime => scan.skipped_no_arrival += 1,
+            Outcome::NoTimezone => scan.skipped_no_timezone += 1,
+            Outcome::Unreadable => scan.skipped_unreadable += 1,
+        }
+    }
+    tx.commit()?;
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_scan_arrival_order() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, iata_code, name, timezone) VALUES
+                     ('LHR', 'LHR', 'Heathrow', 'Europe/London'),
+                     ('JFK', 'JFK', 'John F Kennedy', 'America/New_York'),
+                     ('LAX', 'LAX', 'Los Angeles', 'America/Los_Angeles');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, arrival_datetime) VALUES
+                     -- 10:00 in London to 13:00 in New York: earlier by the clock, eight hours in UTC
+                     ('west', 'u1', 'LHR', 'JFK', '2025-03-01T10:00:00', '2025-03-01T13:00:00'),
+                     -- Red-eye landing the next morning
+                     ('redeye', 'u1', 'JFK', 'LHR', '2025-03-01T22:00:00', '2025-03-02T10:00:00'),
+                     -- Leaves New York at 09:00 and lands in Los Angeles at 05:30 local, half an hour earlier in UTC
+                     ('bad', 'u1', 'JFK', 'LAX', '2025-03-01T09:00:00', '2025-03-01T05:30:00'),
+                     ('no_arrival', 'u1', 'JFK', 'LAX', '2025-03-02T09:00:00', NULL),
+                     ('no_tz', 'u1', 'JFK', 'XXX', '2025-03-03T09:00:00', '2025-03-03T08:00:00');",
+            )
+            .unwrap();
+
+        let result = scan(&db, "u1").unwrap();
+        assert_eq!(result.checked_count, 3);
+        assert_eq!((result.skipped_no_arrival, result.skipped_no_timezone, result.skipped_unreadable), (1, 1, 0));
+        assert_eq!(result.flagged.len(), 1);
+        let issue = &result.flagged[0];
+        assert_eq!(issue.flight_id, "bad");
+        // 09:00 EST is 14:00 UTC; 05:30 PST is 13:30 UTC
+        assert_eq!(issue.minutes_early, 30);
+        assert_eq!(issue.departure.utc_time.as_deref(), Some("2025-03-01T14:00:00Z"));
+
+        let description: String = db
+            .conn
+            .query_row(
+                "SELECT description FROM flight_anomalies WHERE flight_id = 'bad' AND anomaly_type = ?1 AND is_resolved = 0",
+                [ANOMALY_TYPE],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(description.contains("2025-03-01T13:30:00Z"));
+
+        // Correcting the arrival resolves it on the next scan
+        db.conn.execute("UPDATE flights SET arrival_datetime = '2025-03-01T12:00:00' WHERE id = 'bad'", []).unwrap();
+        assert!(scan(&db, "u1").unwrap().flagged.is_empty());
+        let open: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM flight_anomalies WHERE is_resolved = 0", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(open, 0);
+    }
+}
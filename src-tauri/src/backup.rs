@@ -0,0 +1,241 @@
+// Automatic database backups
+// Rotating copies of the SQLite database in <app data>/backups, taken on startup (before
+// migrations run, so a bad upgrade can be rolled back) and/or on clean shutdown. Copies
+// are made with SQLite's online backup API, which is consistent even while other
+// connections are writing in WAL mode.
+//
+// Settings: backup_on_startup (default true), backup_on_shutdown (default false),
+// backup_keep (default 5).
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{Connection, DatabaseName, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const BACKUP_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "flight_tracker-";
+const DEFAULT_KEEP: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupPolicy {
+    pub on_startup: bool,
+    pub on_shutdown: bool,
+    /// Backups kept; older ones are deleted after each new backup
+    pub keep: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            on_startup: true,
+            on_shutdown: false,
+            keep: DEFAULT_KEEP,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// File name, passed to restore_backup
+    pub name: String,
+    pub size_bytes: u64,
+    /// UTC, "YYYY-MM-DD HH:MM:SS"
+    pub created_at: String,
+    /// "startup", "shutdown", "manual" or "pre-restore"
+    pub reason: String,
+}
+
+pub fn backup_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join(BACKUP_DIR)
+}
+
+impl BackupPolicy {
+    /// Read the policy from the settings table; defaults for anything missing
+    pub fn load(conn: &Connection) -> Self {
+        let setting = |key: &str| -> Option<String> {
+            conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+                .optional()
+                .ok()
+                .flatten()
+        };
+        let defaults = Self::default();
+        Self {
+            on_startup: setting("backup_on_startup").map_or(defaults.on_startup, |v| v == "true"),
+            on_shutdown: setting("backup_on_shutdown").map_or(defaults.on_shutdown, |v| v == "true"),
+            keep: setting("backup_keep").and_then(|v| v.parse().ok()).unwrap_or(defaults.keep),
+        }
+    }
+
+    /// Policy of a database file that has not been opened yet (startup, before migrations)
+    /// A missing file or settings table gives the defaults.
+    pub fn load_from_file(db_path: &Path) -> Self {
+        Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map(|conn| Self::load(&conn))
+            .unwrap_or_default()
+    }
+}
+
+/// Back up the database at `db_path` before it is opened by the app
+/// Does nothing for a new install (no database file yet) or when disabled.
+pub fn backup_on_startup(db_path: &Path, app_dir: &Path) -> Result<Option<BackupInfo>> {
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let policy = BackupPolicy::load_from_file(db_path);
+    if !policy.on_startup || policy.keep == 0 {
+        return Ok(None);
+    }
+    let conn = Connection::open(db_path).context("Failed to open database for backup")?;
+    let info = create_backup(&conn, &backup_dir(app_dir), "startup")?;
+    rotate(&backup_dir(app_dir), policy.keep)?;
+    Ok(Some(info))
+}
+
+/// Copy the live database to a new timestamped file in `dir`
+pub fn create_backup(conn: &Connection, dir: &Path, reason: &str) -> Result<BackupInfo> {
+    std::fs::create_dir_all(dir).context("Failed to create backup directory")?;
+    // Millisecond timestamps; wait out a clash so a backup never overwrites another
+    let (now, name, path) = loop {
+        let now = chrono::Utc::now();
+        let name = format!("{}{}-{}.db", BACKUP_PREFIX, now.format("%Y%m%d-%H%M%S%3f"), reason);
+        let path = dir.join(&name);
+        if !path.exists() {
+            break (now, name, path);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    conn.backup(DatabaseName::Main, &path, None)
+        .with_context(|| format!("Failed to back up database to {}", path.display()))?;
+
+    Ok(BackupInfo {
+        size_bytes: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        created_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        reason: reason.to_string(),
+        name,
+    })
+}
+
+/// Backups in `dir`, newest first
+pub fn list_backups(dir: &Path) -> Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups: Vec<BackupInfo> = std::fs::read_dir(dir)
+        .context("Failed to read backup directory")?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (created_at, reason) = parse_backup_name(&name)?;
+            Some(BackupInfo {
+                size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                created_at,
+                reason,
+                name,
+            })
+        })
+        .collect();
+    // Names embed the timestamp, so they sort chronologically
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Delete all but the newest `keep` backups; returns how many were deleted
+pub fn rotate(dir: &Path, keep: usize) -> Result<usize> {
+    let backups = list_backups(dir)?;
+    let mut deleted = 0;
+    for backup in backups.iter().skip(keep) {
+        std::fs::remove_file(dir.join(&backup.name))
+            .with_context(|| format!("Failed to delete old backup {}", backup.name))?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// Replace the live database contents with a backup
+/// The current data is backed up first ("pre-restore") so a restore can be undone.
+pub fn restore_backup(conn: &mut Connection, dir: &Path, name: &str, keep: usize) -> Result<BackupInfo> {
+    if parse_backup_name(name).is_none() || name.contains(['/', '\\']) {
+        bail!("Not a backup file: {}", name);
+    }
+    let path = dir.join(name);
+    if !path.is_file() {
+        bail!("Backup not found: {}", name);
+    }
+    // Refuse anything SQLite cannot open as a database before touching live data
+    Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|backup| backup.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0)))
+        .with_context(|| format!("Backup {} is not a readable database", name))?;
+
+    let safety = create_backup(conn, dir, "pre-restore")?;
+    conn.restore(DatabaseName::Main, &path, None::<fn(rusqlite::backup::Progress)>)
+        .with_context(|| format!("Failed to restore backup {}", name))?;
+    // keep + 1 so the pre-restore copy does not push out the backup just restored
+    rotate(dir, keep.max(1) + 1)?;
+    Ok(safety)
+}
+
+/// (created_at, reason) from "flight_tracker-YYYYMMDD-HHMMSSmmm-reason.db"
+fn parse_backup_name(name: &str) -> Option<(String, String)> {
+    let stem = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".db")?;
+    let mut parts = stem.splitn(3, '-');
+    let (date, time, reason) = (parts.next()?, parts.next()?, parts.next()?);
+    let timestamp = chrono::NaiveDateTime::parse_from_str(&format!("{}{}", date, &time.get(..6)?), "%Y%m%d%H%M%S").ok()?;
+    Some((timestamp.format("%Y-%m-%d %H:%M:%S").to_string(), reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("backup_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_backup_rotate_and_restore() {
+        let app_dir = temp_dir();
+        let db_path = app_dir.join("flight_tracker.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);
+             INSERT INTO settings VALUES ('backup_keep', '2');
+             CREATE TABLE notes (body TEXT);
+             INSERT INTO notes VALUES ('original');",
+        )
+        .unwrap();
+
+        let first = backup_on_startup(&db_path, &app_dir).unwrap().unwrap();
+        assert_eq!(first.reason, "startup");
+        let dir = backup_dir(&app_dir);
+        create_backup(&conn, &dir, "manual").unwrap();
+        create_backup(&conn, &dir, "manual").unwrap();
+        assert_eq!(rotate(&dir, BackupPolicy::load(&conn).keep).unwrap(), 1);
+        assert_eq!(list_backups(&dir).unwrap().len(), 2);
+
+        let kept = list_backups(&dir).unwrap().pop().unwrap();
+        conn.execute("UPDATE notes SET body = 'changed'", []).unwrap();
+        let safety = restore_backup(&mut conn, &dir, &kept.name, 2).unwrap();
+        assert_eq!(safety.reason, "pre-restore");
+        let body: String = conn.query_row("SELECT body FROM notes", [], |row| row.get(0)).unwrap();
+        assert_eq!(body, "original");
+
+        assert!(restore_backup(&mut conn, &dir, "../flight_tracker.db", 2).is_err());
+        assert!(restore_backup(&mut conn, &dir, "flight_tracker-20240101-000000000-manual.db", 2).is_err());
+
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&app_dir);
+    }
+
+    #[test]
+    fn test_parse_backup_name() {
+        assert_eq!(
+            parse_backup_name("flight_tracker-20240601-103000123-pre-restore.db"),
+            Some(("2024-06-01 10:30:00".to_string(), "pre-restore".to_string()))
+        );
+        assert_eq!(parse_backup_name("flight_tracker.db"), None);
+        assert_eq!(parse_backup_name("notes.txt"), None);
+    }
+}
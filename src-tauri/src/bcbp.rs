@@ -0,0 +1,298 @@
+// IATA Bar Coded Boarding Pass (BCBP, Resolution 792)
+// The PDF417 / Aztec / QR code on a boarding pass holds a fixed-width string. The first
+// leg starts with the format code "M", the number of legs, the passenger name (20,
+// "DOE/JOHN MR") and an "E" for e-tickets; every leg then has the PNR (7), from (3), to (3),
+// operating carrier (3), flight number (5), day of the year of the flight (3), compartment
+// (1), seat (4), check-in sequence (5), passenger status (1) and the size in hex (2) of a
+// variable field:
+//
+//   M1DOE/JOHN MR         EABC123 LHRJFKBA 0117 032Y012A0042 100
+//
+// The variable field holds optional conditional data (issue date, ticket number, frequent
+// flyer number) and airline data. The year is not encoded: it comes from the issue date
+// when present, otherwise from a reference date such as when the photo was taken.
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::airlines;
+use crate::ocr::{OcrFlightResult, OcrMethod};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BcbpLeg {
+    pub pnr: String,
+    pub from: String,
+    pub to: String,
+    pub carrier: String,
+    /// Without leading zeros, suffix kept: "117", "1234A"
+    pub flight_number: String,
+    /// Day of the year, 1-366
+    pub day_of_year: u32,
+    pub compartment: String,
+    /// Without leading zeros: "12A"
+    pub seat: Option<String>,
+    pub sequence: Option<String>,
+    /// 13-digit e-ticket number, from the conditional data
+    pub ticket_number: Option<String>,
+    pub frequent_flyer_number: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardingPass {
+    /// As encoded: "DOE/JOHN MR"
+    pub passenger_name: String,
+    pub electronic_ticket: bool,
+    /// Last digit of the year and day of the year the pass was issued, when encoded
+    pub issued: Option<(u32, u32)>,
+    pub legs: Vec<BcbpLeg>,
+}
+
+/// Length of the first leg's mandatory fields
+const FIRST_LEG_LENGTH: usize = 60;
+
+/// Cursor over fixed-width fields
+struct Fields<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, width: usize) -> Result<&'a str> {
+        let Some(field) = self.text.get(self.pos..self.pos + width) else {
+            bail!("Boarding pass barcode is truncated at position {}", self.pos);
+        };
+        self.pos += width;
+        Ok(field)
+    }
+
+    fn hex(&mut self) -> Result<usize> {
+        let field = self.take(2)?;
+        usize::from_str_radix(field, 16).map_err(|_| anyhow::anyhow!("Invalid field size '{}' in boarding pass barcode", field))
+    }
+}
+
+fn text(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Drop leading zeros from a numeric field with an optional suffix letter ("0117 " -> "117")
+fn strip_zeros(value: &str) -> String {
+    let value = value.trim();
+    let stripped = value.trim_start_matches('0');
+    if stripped.is_empty() || stripped.starts_with(|c: char| !c.is_ascii_digit()) {
+        // "0000" or an all-suffix value: keep one zero before the suffix
+        format!("0{}", stripped)
+    } else {
+        stripped.to_string()
+    }
+}
+
+pub fn parse(data: &str) -> Result<BoardingPass> {
+    let data = data.trim_end_matches(['\r', '\n']);
+    if !data.is_ascii() || data.len() < FIRST_LEG_LENGTH || !data.starts_with('M') {
+        bail!("Not an IATA boarding pass barcode");
+    }
+    let mut fields = Fields { text: data, pos: 1 };
+    let leg_count = fields.take(1)?.parse::<usize>().unwrap_or(0);
+    if !(1..=4).contains(&leg_count) {
+        bail!("Invalid number of legs in boarding pass barcode");
+    }
+    let passenger_name = fields.take(20)?.trim().to_string();
+    let electronic_ticket = fields.take(1)? == "E";
+
+    let mut issued = None;
+    let mut legs = Vec::with_capacity(leg_count);
+    for index in 0..leg_count {
+        let pnr = fields.take(7)?.trim().to_string();
+        let from = fields.take(3)?.trim().to_string();
+        let to = fields.take(3)?.trim().to_string();
+        let carrier = fields.take(3)?.trim().to_string();
+        let flight_number = strip_zeros(fields.take(5)?);
+        let day_of_year = fields
+            .take(3)?
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|day| (1..=366).contains(day))
+            .ok_or_else(|| anyhow::anyhow!("Invalid flight date in boarding pass barcode"))?;
+        let compartment = fields.take(1)?.trim().to_string();
+        let seat = text(fields.take(4)?).map(|seat| strip_zeros(&seat));
+        let sequence = text(fields.take(5)?).map(|sequence| strip_zeros(&sequence));
+        fields.take(1)?; // passenger status
+        let variable_size = fields.hex()?;
+        let variable = fields.take(variable_size)?;
+
+        if from.len() != 3 || to.len() != 3 || carrier.is_empty() {
+            bail!("Invalid route in boarding pass barcode");
+        }
+        // Conditional data is optional and varies by airline; unreadable parts are skipped
+        let conditional = conditional_data(variable, index == 0);
+        if index == 0 {
+            issued = conditional.issued;
+        }
+        legs.push(BcbpLeg {
+            pnr,
+            from,
+            to,
+            carrier,
+            flight_number,
+            day_of_year,
+            compartment,
+            seat,
+            sequence,
+            ticket_number: conditional.ticket_number,
+            frequent_flyer_number: conditional.frequent_flyer_number,
+        });
+    }
+
+    Ok(BoardingPass { passenger_name, electronic_ticket, issued, legs })
+}
+
+#[derive(Default)]
+struct Conditional {
+    issued: Option<(u32, u32)>,
+    ticket_number: Option<String>,
+    frequent_flyer_number: Option<String>,
+}
+
+/// Conditional items in a leg's variable field; the first leg's starts with a version
+/// marker and the items unique to the pass
+fn conditional_data(variable: &str, first_leg: bool) -> Conditional {
+    let mut conditional = Conditional::default();
+    let mut fields = Fields { text: variable, pos: 0 };
+
+    if first_leg {
+        if !variable.starts_with('>') {
+            return conditional;
+        }
+        fields.pos = 2; // '>' and the version number
+        let Ok(unique_size) = fields.hex() else { return conditional };
+        let Ok(unique) = fields.take(unique_size) else { return conditional };
+        // Passenger description, check-in source and issuance source come first
+        conditional.issued = unique.get(3..7).and_then(|issued| {
+            let year_digit = issued.get(..1)?.parse().ok()?;
+            let day = issued.get(1..)?.parse().ok().filter(|day| (1..=366).contains(day))?;
+            Some((year_digit, day))
+        });
+    }
+
+    let Ok(repeated_size) = fields.hex() else { return conditional };
+    let Ok(repeated) = fields.take(repeated_size) else { return conditional };
+    // Airline numeric code (3) and document serial number (10) make the ticket number
+    conditional.ticket_number = repeated
+        .get(..13)
+        .map(str::trim)
+        .filter(|number| number.len() == 13 && number.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string);
+    // Selectee, document verification, marketing carrier and frequent flyer airline precede it
+    conditional.frequent_flyer_number = repeated.get(21..37).and_then(text);
+    conditional
+}
+
+impl BoardingPass {
+    /// "JOHN DOE" from "DOE/JOHN MR"
+    pub fn display_name(&self) -> String {
+        const TITLES: [&str; 7] = ["MR", "MRS", "MS", "MISS", "MSTR", "DR", "CHD"];
+        let (surname, given) = self.passenger_name.split_once('/').unwrap_or((&self.passenger_name, ""));
+        let mut given: Vec<&str> = given.split_whitespace().collect();
+        if given.len() > 1 && given.last().is_some_and(|last| TITLES.contains(last)) {
+            given.pop();
+        }
+        given.into_iter().chain(std::iter::once(surname.trim())).filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Flight date of a leg; `reference` (e.g. when the photo was taken) picks the year
+    /// when the pass carries no issue date
+    pub fn flight_date(&self, leg: &BcbpLeg, reference: NaiveDate) -> Option<NaiveDate> {
+        let on = |year: i32| NaiveDate::from_yo_opt(year, leg.day_of_year);
+        if let Some((year_digit, issue_day)) = self.issued {
+            // The latest year ending in year_digit that is not after the reference year
+            let mut year = reference.year() - (reference.year() - year_digit as i32).rem_euclid(10);
+            if year > reference.year() {
+                year -= 10;
+            }
+            // Flown after it was issued, possibly into the next year
+            return on(if leg.day_of_year < issue_day { year + 1 } else { year });
+        }
+        [reference.year() - 1, reference.year(), reference.year() + 1]
+            .into_iter()
+            .filter_map(on)
+            .min_by_key(|date| (*date - reference).num_days().abs())
+    }
+
+    /// The first leg as an OCR result; every field read is exact
+    pub fn to_ocr_result(&self, reference: NaiveDate) -> OcrFlightResult {
+        let leg = &self.legs[0];
+        let airline = airlines::lookup_code(&leg.carrier).map(|a| a.name.clone()).unwrap_or_else(|| leg.carrier.clone());
+        let mut result = OcrFlightResult {
+            flight_number: Some(format!("{}{}", leg.carrier, leg.flight_number)),
+            departure_airport: Some(leg.from.clone()),
+            arrival_airport: Some(leg.to.clone()),
+            departure_datetime: self.flight_date(leg, reference).map(|date| date.format("%Y-%m-%d").to_string()),
+            passenger_name: text(&self.display_name()),
+            booking_reference: text(&leg.pnr),
+            ticket_number: leg.ticket_number.clone(),
+            seat_number: leg.seat.clone(),
+            fare_class: text(&leg.compartment),
+            airline: Some(airline),
+            frequent_flyer_number: leg.frequent_flyer_number.clone(),
+            method: OcrMethod::Barcode,
+            ..Default::default()
+        };
+        result.set_model("barcode");
+        result.field_confidence = result.field_models.keys().map(|field| (field.clone(), 1.0)).collect();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two legs, laid out like the example in the BCBP implementation guide
+    const SAMPLE: &str = "M2DESMARAIS/LUC       EABC123 YULFRAAC 0834 226F001A0025 14D>6181WW6225BAC 00141234560032A0141234567890 1AC AC 1234567890123    20KYLX58ZDEF456 FRAGVALH 3664 227C012C0002 12C2A0141234567891 2LH                     20KN";
+
+    #[test]
+    fn test_parse_bcbp() {
+        let pass = parse(SAMPLE).unwrap();
+        assert_eq!(pass.passenger_name, "DESMARAIS/LUC");
+        assert_eq!(pass.display_name(), "LUC DESMARAIS");
+        assert!(pass.electronic_ticket);
+        assert_eq!(pass.issued, Some((6, 225)));
+        assert_eq!(pass.legs.len(), 2);
+
+        let first = &pass.legs[0];
+        assert_eq!((first.from.as_str(), first.to.as_str(), first.carrier.as_str()), ("YUL", "FRA", "AC"));
+        assert_eq!(first.flight_number, "834");
+        assert_eq!(first.seat.as_deref(), Some("1A"));
+        assert_eq!(first.ticket_number.as_deref(), Some("0141234567890"));
+        assert_eq!(first.frequent_flyer_number.as_deref(), Some("1234567890123"));
+        assert_eq!(pass.legs[1].flight_number, "3664");
+        assert_eq!(pass.legs[1].seat.as_deref(), Some("12C"));
+
+        // Issued on day 225 of a year ending in 6, flown on day 226
+        let date = pass.flight_date(first, NaiveDate::from_ymd_opt(2026, 10, 1).unwrap()).unwrap();
+        assert_eq!(date, NaiveDate::from_ymd_opt(2026, 8, 14).unwrap());
+
+        let result = pass.to_ocr_result(NaiveDate::from_ymd_opt(2026, 10, 1).unwrap());
+        assert_eq!(result.flight_number.as_deref(), Some("AC834"));
+        assert_eq!(result.departure_datetime.as_deref(), Some("2026-08-14"));
+        assert_eq!(result.method, OcrMethod::Barcode);
+        assert_eq!(result.field_confidence.get("seat_number"), Some(&1.0));
+
+        assert!(parse("Not a boarding pass").is_err());
+        assert!(parse(&SAMPLE[..50]).is_err());
+    }
+
+    #[test]
+    fn test_year_without_issue_date() {
+        let pass = parse("M1DOE/JOHN MR         EABC123 LHRJFKBA 0117 003Y012A0042 100").unwrap();
+        assert_eq!(pass.issued, None);
+        assert_eq!(pass.legs[0].seat.as_deref(), Some("12A"));
+        // Day 3 seen at the end of December is early January of the next year
+        let date = pass.flight_date(&pass.legs[0], NaiveDate::from_ymd_opt(2025, 12, 28).unwrap()).unwrap();
+        assert_eq!(date, NaiveDate::from_ymd_opt(2026, 1, 3).unwrap());
+    }
+}
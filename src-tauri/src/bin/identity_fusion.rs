@@ -114,6 +114,7 @@ async fn main() -> Result<()> {
             let match_type = match candidate.match_type {
                 MatchType::ExactMatch => "exact",
                 MatchType::Abbreviation => "abbrev",
+                MatchType::Initials => "initials",
                 MatchType::Substring => "partial",
                 MatchType::FuzzyMatch => "fuzzy",
                 MatchType::AIInferred => "ai",
@@ -142,6 +143,7 @@ async fn main() -> Result<()> {
             let match_type = match candidate.match_type {
                 MatchType::ExactMatch => "exact",
                 MatchType::Abbreviation => "abbrev",
+                MatchType::Initials => "initials",
                 MatchType::Substring => "partial",
                 MatchType::FuzzyMatch => "fuzzy",
                 MatchType::AIInferred => "ai",
@@ -244,19 +246,17 @@ async fn extract_passenger_names(csv_path: &PathBuf) -> Result<HashMap<String, u
 
     let mut name_counts: HashMap<String, usize> = HashMap::new();
 
-    for result in reader.records() {
-        if let Ok(record) = result {
-            if let Some(passengers) = record.get(passengers_idx) {
-                // Split by common delimiters
-                for name in passengers.split(|c| c == ';' || c == ',' || c == '|') {
-                    let cleaned = name.trim().to_uppercase();
-
-                    // Skip empty or very short names
-                    if cleaned.len() >= 2 {
-                        // Skip common non-name entries
-                        if !should_skip_name(&cleaned) {
-                            *name_counts.entry(cleaned).or_insert(0) += 1;
-                        }
+    for record in reader.records().flatten() {
+        if let Some(passengers) = record.get(passengers_idx) {
+            // Split by common delimiters
+            for name in passengers.split([';', ',', '|']) {
+                let cleaned = name.trim().to_uppercase();
+
+                // Skip empty or very short names
+                if cleaned.len() >= 2 {
+                    // Skip common non-name entries
+                    if !should_skip_name(&cleaned) {
+                        *name_counts.entry(cleaned).or_insert(0) += 1;
                     }
                 }
             }
@@ -341,16 +341,16 @@ fn generate_report(
     report.push_str("## Resolved Entities\n\n");
     let entities_ref = fusion.get_entities();
     let mut entities: Vec<_> = entities_ref.iter().collect();
-    entities.sort_by(|a, b| b.flight_count.cmp(&a.flight_count));
+    entities.sort_by_key(|e| std::cmp::Reverse(e.flight_count));
 
     for entity in entities.iter().take(50) {
         report.push_str(&format!("### {} ({} flights)\n", entity.canonical_name, entity.flight_count));
         if entity.aliases.len() > 1 {
             report.push_str("**Aliases:** ");
             report.push_str(&entity.aliases.join(", "));
-            report.push_str("\n");
+            report.push('\n');
         }
-        report.push_str("\n");
+        report.push('\n');
     }
 
     // Merge candidates needing review
@@ -364,6 +364,7 @@ fn generate_report(
             let match_type = match candidate.match_type {
                 MatchType::ExactMatch => "Exact",
                 MatchType::Abbreviation => "Abbreviation",
+                MatchType::Initials => "Initials",
                 MatchType::Substring => "Substring",
                 MatchType::FuzzyMatch => "Fuzzy",
                 MatchType::AIInferred => "AI",
@@ -373,7 +374,7 @@ fn generate_report(
                 candidate.source_name, candidate.target_canonical_name, match_type, candidate.similarity_score
             ));
         }
-        report.push_str("\n");
+        report.push('\n');
     }
 
     // Unmapped names
@@ -383,7 +384,7 @@ fn generate_report(
         for (name, count) in unmapped.iter().take(100) {
             report.push_str(&format!("- {} ({} flights)\n", name, count));
         }
-        report.push_str("\n");
+        report.push('\n');
     }
 
     report
@@ -1,21 +1,18 @@
 // Flight Log Ingestion CLI Tool
 // Orchestrates the PDF split -> Vision extraction -> Aggregation pipeline
 //
-// Usage: ingest_logs --pdf <path> [--concurrency 50] [--output <dir>] [--api-key <key>]
+// Usage: ingest_logs --pdf <path> [--concurrency 50] [--max-retries 3] [--output <dir>] [--api-key <key>]
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tokio::sync::Semaphore;
 use tokio::fs;
 
 // Import from the library
 use flight_tracker_pro_lib::extract::{
-    splitter::{split_pdf, get_page_count, SplitConfig, ImageFormat},
-    vision_agent::{VisionAgent, VisionAgentConfig, PageExtractionResult},
-    aggregator::{aggregate_results, save_master_log, save_csv_export},
+    splitter::{split_pdf, get_page_count, page_number_from_path, validate_page_range, SplitConfig, ImageFormat},
+    vision_agent::{process_images_concurrent, VisionAgent, VisionAgentConfig},
+    aggregator::{aggregate_page_range, save_master_log, save_csv_export, save_csv_export_by_confidence, DedupConfig},
 };
 
 #[derive(Parser, Debug)]
@@ -31,6 +28,10 @@ struct Args {
     #[arg(short, long, default_value = "10")]
     concurrency: usize,
 
+    /// Retries per page after rate limiting or server errors (default: 3)
+    #[arg(long, default_value = "3")]
+    max_retries: u32,
+
     /// Output directory for results (default: ./output)
     #[arg(short, long, default_value = "./output")]
     output: PathBuf,
@@ -45,11 +46,11 @@ struct Args {
 
     /// Start page (1-indexed, default: 1)
     #[arg(long)]
-    start_page: Option<u32>,
+    start_page: Option<usize>,
 
     /// End page (1-indexed, default: last page)
     #[arg(long)]
-    end_page: Option<u32>,
+    end_page: Option<usize>,
 
     /// Keep temporary page images after processing
     #[arg(long)]
@@ -63,6 +64,18 @@ struct Args {
     #[arg(long)]
     skip_split: bool,
 
+    /// Keep rows repeated across page breaks instead of merging them
+    #[arg(long)]
+    no_dedup: bool,
+
+    /// Maximum page distance for two rows to count as the same flight (default: 1)
+    #[arg(long, default_value = "1")]
+    dedup_page_gap: u32,
+
+    /// Only export rows at or above this confidence (0.0-1.0); the rest go to flight_log_review.csv
+    #[arg(long)]
+    min_confidence: Option<f64>,
+
     /// Dry run - only split PDF, don't call API
     #[arg(long)]
     dry_run: bool,
@@ -99,8 +112,15 @@ async fn main() -> Result<()> {
 
     // Step 1: Get page count and determine range
     let total_pages = get_page_count(&args.pdf).await?;
-    let start_page = args.start_page.unwrap_or(1);
-    let end_page = args.end_page.unwrap_or(total_pages as u32);
+    let page_range = if args.start_page.is_some() || args.end_page.is_some() {
+        Some(validate_page_range(
+            (args.start_page.unwrap_or(1), args.end_page.unwrap_or(total_pages)),
+            total_pages,
+        )?)
+    } else {
+        None
+    };
+    let (start_page, end_page) = page_range.unwrap_or((1, total_pages));
 
     println!("📄 PDF has {} pages total", total_pages);
     println!("📑 Processing pages {} to {}", start_page, end_page);
@@ -109,13 +129,13 @@ async fn main() -> Result<()> {
     // Step 2: Split PDF into page images
     let page_paths = if args.skip_split {
         println!("⏭️  Skipping PDF split, using existing images...");
-        collect_existing_images(&pages_dir).await?
+        collect_existing_images(&pages_dir, (start_page, end_page)).await?
     } else {
         println!("✂️  Splitting PDF into page images...");
         let config = SplitConfig {
             dpi: args.dpi,
             format: ImageFormat::Png,
-            page_range: Some((start_page, end_page)),
+            page_range,
         };
 
         let split_result = split_pdf(&args.pdf, &pages_dir, &config).await?;
@@ -141,14 +161,30 @@ async fn main() -> Result<()> {
         model: "gemini-2.5-flash-lite".to_string(),
         max_tokens: 8192,
         temperature: 0.1,
+        max_concurrency: args.concurrency,
+        max_retries: args.max_retries,
+        ..Default::default()
     };
 
-    let results = process_pages_with_progress(
-        &agent_config,
-        page_paths,
-        start_page,
-        args.concurrency,
-    ).await;
+    let agent = VisionAgent::new(agent_config);
+    let pages = page_paths
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let page_num = page_number_from_path(&path).unwrap_or(start_page + i);
+            (path, page_num as u32)
+        })
+        .collect();
+
+    let results = process_images_concurrent(&agent, pages, |progress| {
+        // Progress output
+        print!("\r   Processing: {}/{} pages ({} entries from page {}{})",
+               progress.completed, progress.total, progress.entries, progress.page_number,
+               if progress.attempts > 1 { format!(", {} attempts", progress.attempts) } else { String::new() });
+        use std::io::Write;
+        std::io::stdout().flush().ok();
+    }).await;
+    println!(); // New line after progress
 
     // Save individual page results if requested
     if args.save_page_results {
@@ -165,9 +201,27 @@ async fn main() -> Result<()> {
     println!();
     println!("📊 Aggregating results...");
 
-    let master_log = aggregate_results(results);
+    let dedup = DedupConfig {
+        enabled: !args.no_dedup,
+        max_page_gap: args.dedup_page_gap,
+        ..Default::default()
+    };
+    let master_log = aggregate_page_range(results, page_range, &dedup);
 
     println!("   Total entries extracted: {}", master_log.total_entries);
+    println!("   Duplicates collapsed: {}", master_log.duplicates_collapsed);
+    for collapsed in &master_log.collapsed_entries {
+        let field = |value: &Option<String>| value.clone().unwrap_or_else(|| "?".to_string());
+        println!(
+            "     - page {}: {} {} -> {} {} (merged into page {})",
+            collapsed.entry.source_page.map_or("?".to_string(), |p| p.to_string()),
+            field(&collapsed.entry.date),
+            field(&collapsed.entry.from),
+            field(&collapsed.entry.to),
+            field(&collapsed.entry.aircraft_registration),
+            collapsed.merged_into_page.map_or("?".to_string(), |p| p.to_string()),
+        );
+    }
     println!("   Pages with errors: {}", master_log.pages_with_errors);
     println!("   Unique aircraft: {}", master_log.unique_aircraft.len());
     println!("   Unique airports: {}", master_log.unique_airports.len());
@@ -185,8 +239,18 @@ async fn main() -> Result<()> {
     println!("   Master JSON: {}", master_json_path.display());
 
     let csv_path = args.output.join("flight_log.csv");
-    save_csv_export(&master_log, &csv_path).await?;
-    println!("   CSV export: {}", csv_path.display());
+    match args.min_confidence {
+        Some(min_confidence) => {
+            let review_path = args.output.join("flight_log_review.csv");
+            save_csv_export_by_confidence(&master_log, &csv_path, &review_path, min_confidence).await?;
+            println!("   CSV export (confidence >= {:.2}): {}", min_confidence, csv_path.display());
+            println!("   Low-confidence rows for review: {}", review_path.display());
+        }
+        None => {
+            save_csv_export(&master_log, &csv_path).await?;
+            println!("   CSV export: {}", csv_path.display());
+        }
+    }
 
     // Cleanup temp files if not keeping
     if !args.keep_images {
@@ -217,14 +281,15 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Collect existing PNG images from a directory
-async fn collect_existing_images(dir: &PathBuf) -> Result<Vec<PathBuf>> {
+/// Collect existing PNG images within the page range from a directory
+async fn collect_existing_images(dir: &PathBuf, (start, end): (usize, usize)) -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
     let mut entries = fs::read_dir(dir).await?;
 
     while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
-        if path.extension().map(|e| e == "png").unwrap_or(false) {
+        let in_range = page_number_from_path(&path).is_none_or(|n| (start..=end).contains(&n));
+        if path.extension().map(|e| e == "png").unwrap_or(false) && in_range {
             paths.push(path);
         }
     }
@@ -232,64 +297,3 @@ async fn collect_existing_images(dir: &PathBuf) -> Result<Vec<PathBuf>> {
     paths.sort();
     Ok(paths)
 }
-
-/// Process pages with progress reporting
-async fn process_pages_with_progress(
-    config: &VisionAgentConfig,
-    page_paths: Vec<PathBuf>,
-    start_page: u32,
-    concurrency: usize,
-) -> Vec<PageExtractionResult> {
-    let total = page_paths.len();
-    let processed = Arc::new(AtomicUsize::new(0));
-    let semaphore = Arc::new(Semaphore::new(concurrency));
-
-    let mut handles = Vec::new();
-
-    for (i, path) in page_paths.into_iter().enumerate() {
-        let page_num = start_page + i as u32;
-        let config = config.clone();
-        let processed = processed.clone();
-        let permit = semaphore.clone().acquire_owned().await.unwrap();
-
-        let handle = tokio::spawn(async move {
-            let agent = VisionAgent::new(config);
-            let result = agent.extract_from_image(&path, page_num).await;
-            drop(permit);
-
-            let count = processed.fetch_add(1, Ordering::SeqCst) + 1;
-            let entries_count = result.as_ref().map(|r| r.entries.len()).unwrap_or(0);
-
-            // Progress output
-            print!("\r   Processing: {}/{} pages ({} entries from page {})",
-                   count, total, entries_count, page_num);
-            use std::io::Write;
-            std::io::stdout().flush().ok();
-
-            match result {
-                Ok(r) => r,
-                Err(e) => PageExtractionResult {
-                    page_number: page_num,
-                    image_path: path.to_string_lossy().to_string(),
-                    entries: vec![],
-                    raw_response: None,
-                    error: Some(e.to_string()),
-                },
-            }
-        });
-
-        handles.push(handle);
-    }
-
-    // Collect all results
-    let mut results = Vec::new();
-    for handle in handles {
-        if let Ok(result) = handle.await {
-            results.push(result);
-        }
-    }
-
-    println!(); // New line after progress
-    results.sort_by_key(|r| r.page_number);
-    results
-}
@@ -1,12 +1,23 @@
 // Aviation Calculations - Fuel consumption and CO2 emissions
 // Based on ICAO and aviation industry standards
 
+use serde::{Deserialize, Serialize};
+
+use crate::fare_classes::Cabin;
+
+/// Share of seats filled on an average flight (ICAO carbon calculator methodology)
+pub const TYPICAL_LOAD_FACTOR: f64 = 0.8;
+
 /// Aircraft performance characteristics
-/// Based on typical fuel consumption for common aircraft types
-#[derive(Debug, Clone)]
+/// Based on typical fuel consumption for common aircraft types. Stored profiles (see
+/// aircraft_performance.rs) take precedence; these are the fallback when none matches.
+/// Estimates for a flight's aircraft come from aircraft_performance::resolve.
+#[derive(Debug, Clone, PartialEq)]
 pub struct AircraftPerformance {
     pub fuel_burn_rate_kg_per_km: f64, // Average fuel burn in kg per km
     pub co2_factor: f64,               // CO2 produced per kg of fuel (typically 3.15)
+    pub cruise_speed_kmh: f64,         // Typical cruise speed
+    pub typical_load: f64,             // Passengers on an average flight, when the count is unknown
 }
 
 impl AircraftPerformance {
@@ -16,52 +27,53 @@ impl AircraftPerformance {
         Self {
             fuel_burn_rate_kg_per_km: 3.5, // ~3.5 kg/km for typical narrow-body
             co2_factor: 3.15,              // 1 kg Jet-A1 = 3.15 kg CO2
+            cruise_speed_kmh: 850.0,
+            typical_load: 100.0,
         }
     }
 
     /// Get performance characteristics by aircraft type
     pub fn from_aircraft_type(aircraft_type: &str) -> Self {
+        Self::known_type(aircraft_type).unwrap_or_else(Self::default)
+    }
+
+    /// Performance for a recognised type family; None when the string matches none
+    pub fn known_type(aircraft_type: &str) -> Option<Self> {
         let aircraft_upper = aircraft_type.to_uppercase();
 
         // Match common aircraft types with their fuel consumption profiles
         // Values are approximate averages based on cruise conditions
+        let profile = |fuel_burn_rate_kg_per_km, cruise_speed_kmh, typical_load| Self {
+            fuel_burn_rate_kg_per_km,
+            cruise_speed_kmh,
+            typical_load,
+            ..Self::default()
+        };
 
         if aircraft_upper.contains("747") || aircraft_upper.contains("A380") {
             // Large wide-body (4 engines)
-            Self {
-                fuel_burn_rate_kg_per_km: 12.0,
-                co2_factor: 3.15,
-            }
+            Some(profile(12.0, 910.0, 350.0))
         } else if aircraft_upper.contains("777")
             || aircraft_upper.contains("787")
             || aircraft_upper.contains("A350")
             || aircraft_upper.contains("330")
         {
             // Modern wide-body (2 engines)
-            Self {
-                fuel_burn_rate_kg_per_km: 8.0,
-                co2_factor: 3.15,
-            }
+            Some(profile(8.0, 905.0, 250.0))
         } else if aircraft_upper.contains("737")
             || aircraft_upper.contains("A320")
             || aircraft_upper.contains("A319")
             || aircraft_upper.contains("A321")
         {
             // Narrow-body jets
-            Self {
-                fuel_burn_rate_kg_per_km: 3.5,
-                co2_factor: 3.15,
-            }
+            Some(profile(3.5, 850.0, 150.0))
         } else if aircraft_upper.contains("ERJ")
             || aircraft_upper.contains("CRJ")
             || aircraft_upper.contains("E170")
             || aircraft_upper.contains("E190")
         {
             // Regional jets
-            Self {
-                fuel_burn_rate_kg_per_km: 2.5,
-                co2_factor: 3.15,
-            }
+            Some(profile(2.5, 820.0, 70.0))
         } else if aircraft_upper.contains("CESSNA")
             || aircraft_upper.contains("C172")
             || aircraft_upper.contains("C182")
@@ -69,10 +81,7 @@ impl AircraftPerformance {
             || aircraft_upper.contains("PIPER")
         {
             // General aviation piston
-            Self {
-                fuel_burn_rate_kg_per_km: 0.4,
-                co2_factor: 3.15,
-            }
+            Some(profile(0.4, 200.0, 2.0))
         } else if aircraft_upper.contains("CITATION")
             || aircraft_upper.contains("GULFSTREAM")
             || aircraft_upper.contains("LEARJET")
@@ -81,98 +90,242 @@ impl AircraftPerformance {
             || aircraft_upper.contains("HS125")
         {
             // Business jets
-            Self {
-                fuel_burn_rate_kg_per_km: 1.8,
-                co2_factor: 3.15,
-            }
+            Some(profile(1.8, 750.0, 6.0))
         } else {
-            // Default to narrow-body average
-            Self::default()
+            None
         }
     }
+
+    /// Fuel consumption in kilograms for a flight of this distance
+    pub fn fuel_consumption(&self, distance_km: f64) -> f64 {
+        // Base fuel calculation
+        let mut fuel_kg = distance_km * self.fuel_burn_rate_kg_per_km;
+
+        // Add takeoff and landing overhead (approximately 10% for jet aircraft)
+        fuel_kg *= 1.10;
+
+        // Add taxi fuel (approximately 100 kg for jets, 20 kg for GA)
+        let taxi_fuel = if self.fuel_burn_rate_kg_per_km > 2.0 {
+            100.0 // Jet aircraft
+        } else {
+            20.0 // General aviation
+        };
+
+        fuel_kg + taxi_fuel
+    }
+
+    /// CO2 emissions in kilograms for a flight of this distance
+    pub fn co2_emissions(&self, distance_km: f64) -> f64 {
+        self.fuel_consumption(distance_km) * self.co2_factor
+    }
+
+    /// Flight duration in minutes (includes taxi, climb, cruise, descent)
+    pub fn flight_time(&self, distance_km: f64) -> i32 {
+        // Calculate cruise time in hours
+        let cruise_time_hours = distance_km / self.cruise_speed_kmh;
+
+        // Add overhead for taxi, takeoff, climb, descent (varies by distance)
+        let overhead_minutes = if distance_km < 500.0 {
+            45.0 // Short flights: more overhead relative to cruise
+        } else if distance_km < 1500.0 {
+            35.0 // Medium flights
+        } else if distance_km < 5000.0 {
+            30.0 // Long flights
+        } else {
+            25.0 // Ultra-long flights: overhead is smaller percentage
+        };
+
+        // Total time in minutes
+        let total_minutes = (cruise_time_hours * 60.0) + overhead_minutes;
+
+        total_minutes.round() as i32
+    }
 }
 
-/// Calculate fuel consumption for a flight
-///
-/// # Arguments
-/// * `distance_km` - Flight distance in kilometers
-/// * `aircraft_type` - Optional aircraft type string (e.g., "Boeing 737", "A320")
-///
-/// # Returns
-/// Fuel consumption in kilograms
-pub fn calculate_fuel_consumption(distance_km: f64, aircraft_type: Option<&str>) -> f64 {
-    let performance = match aircraft_type {
-        Some(ac) => AircraftPerformance::from_aircraft_type(ac),
-        None => AircraftPerformance::default(),
-    };
+/// Per-passenger CO2 with given performance; without a passenger count the CO2 is shared
+/// across the aircraft's typical load
+pub fn per_passenger_co2(performance: &AircraftPerformance, distance_km: f64, passenger_count: Option<u32>) -> f64 {
+    let passengers = passenger_count.filter(|&n| n > 0).map_or(performance.typical_load, f64::from);
+    performance.co2_emissions(distance_km) / passengers.max(1.0)
+}
 
-    // Base fuel calculation
-    let mut fuel_kg = distance_km * performance.fuel_burn_rate_kg_per_km;
+/// Relative seat weights used to split a flight's CO2 by cabin class
+/// Premium cabins take more floor space per seat and so carry a larger share
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CabinClassWeights {
+    pub economy: f64,
+    pub premium_economy: f64,
+    pub business: f64,
+    pub first: f64,
+}
 
-    // Add takeoff and landing overhead (approximately 10% for jet aircraft)
-    fuel_kg *= 1.10;
+impl Default for CabinClassWeights {
+    fn default() -> Self {
+        Self {
+            economy: 1.0,
+            premium_economy: 1.5,
+            business: 2.0,
+            first: 3.0,
+        }
+    }
+}
 
-    // Add taxi fuel (approximately 100 kg for jets, 20 kg for GA)
-    let taxi_fuel = if performance.fuel_burn_rate_kg_per_km > 2.0 {
-        100.0 // Jet aircraft
-    } else {
-        20.0 // General aviation
-    };
+impl CabinClassWeights {
+    /// Weight for a fare class name ("Business") or booking class letter ("J")
+    /// Returns None when the class is not recognised
+    pub fn weight_for(&self, fare_class: &str) -> Option<f64> {
+        match crate::fare_classes::builtin_cabin(fare_class)? {
+            Cabin::First => Some(self.first),
+            Cabin::Business => Some(self.business),
+            Cabin::Premium => Some(self.premium_economy),
+            Cabin::Economy => Some(self.economy),
+            Cabin::Other => None,
+        }
+    }
 
-    fuel_kg + taxi_fuel
+    /// Names of weights that are not positive numbers
+    pub fn invalid_fields(&self) -> Vec<&'static str> {
+        [
+            ("economy", self.economy),
+            ("premium_economy", self.premium_economy),
+            ("business", self.business),
+            ("first", self.first),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_finite() || *value <= 0.0)
+        .map(|(name, _)| name)
+        .collect()
+    }
 }
 
-/// Calculate CO2 emissions for a flight
-///
-/// # Arguments
-/// * `distance_km` - Flight distance in kilometers
-/// * `aircraft_type` - Optional aircraft type string
-///
-/// # Returns
-/// CO2 emissions in kilograms
-pub fn calculate_co2_emissions(distance_km: f64, aircraft_type: Option<&str>) -> f64 {
-    let fuel_kg = calculate_fuel_consumption(distance_km, aircraft_type);
+/// Factors for expressing CO2 as everyday equivalents
+/// Stored as JSON in the "emission_factors" setting; fields left out keep their default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmissionFactors {
+    /// Average passenger car, kg CO2 per km driven (US EPA: ~400 g/mile)
+    pub car_kg_per_km: f64,
+    /// Rail travel, kg CO2 per passenger-km (UK DEFRA national rail)
+    pub train_kg_per_km: f64,
+    /// CO2 one mature tree absorbs in a year
+    pub tree_kg_per_year: f64,
+}
 
-    let performance = match aircraft_type {
-        Some(ac) => AircraftPerformance::from_aircraft_type(ac),
-        None => AircraftPerformance::default(),
-    };
+impl Default for EmissionFactors {
+    fn default() -> Self {
+        Self {
+            car_kg_per_km: 0.25,
+            train_kg_per_km: 0.035,
+            tree_kg_per_year: 21.0,
+        }
+    }
+}
+
+impl EmissionFactors {
+    /// Names of factors that are not positive numbers
+    pub fn invalid_fields(&self) -> Vec<&'static str> {
+        [
+            ("car_kg_per_km", self.car_kg_per_km),
+            ("train_kg_per_km", self.train_kg_per_km),
+            ("tree_kg_per_year", self.tree_kg_per_year),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_finite() || *value <= 0.0)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// An amount of CO2 as car distance, train distance and tree-years of absorption
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmissionEquivalents {
+    pub co2_kg: f64,
+    pub car_km: f64,
+    pub train_km: f64,
+    pub tree_years: f64,
+}
+
+pub fn emission_equivalents(co2_kg: f64, factors: &EmissionFactors) -> EmissionEquivalents {
+    EmissionEquivalents {
+        co2_kg,
+        car_km: co2_kg / factors.car_kg_per_km,
+        train_km: co2_kg / factors.train_kg_per_km,
+        tree_years: co2_kg / factors.tree_kg_per_year,
+    }
+}
 
-    fuel_kg * performance.co2_factor
+/// How a flight's CO2 was divided between passengers (stored with per_passenger_co2_kg)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Co2SplitMethod {
+    Equal,
+    ClassWeighted,
 }
 
-/// Calculate per-passenger CO2 emissions
+impl Co2SplitMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Co2SplitMethod::Equal => "equal",
+            Co2SplitMethod::ClassWeighted => "class_weighted",
+        }
+    }
+}
+
+/// Split a flight's total CO2 between passengers by cabin class
 ///
-/// # Arguments
-/// * `distance_km` - Flight distance in kilometers
-/// * `aircraft_type` - Optional aircraft type string
-/// * `passenger_count` - Number of passengers (for load factor calculation)
+/// Passengers whose class is unknown count as economy seats. Without weights, or
+/// when no passenger has a recognised class, every passenger gets an equal share.
 ///
 /// # Returns
-/// CO2 emissions per passenger in kilograms
-pub fn calculate_per_passenger_co2(
-    distance_km: f64,
-    aircraft_type: Option<&str>,
-    passenger_count: Option<u32>,
-) -> f64 {
-    let total_co2 = calculate_co2_emissions(distance_km, aircraft_type);
-
-    // Use typical load factors if passenger count not provided
-    let passengers = passenger_count.unwrap_or_else(|| {
-        let ac_type = aircraft_type.unwrap_or("");
-        if ac_type.to_uppercase().contains("747") || ac_type.to_uppercase().contains("A380") {
-            350 // Large wide-body
-        } else if ac_type.to_uppercase().contains("777") || ac_type.to_uppercase().contains("787") {
-            250 // Medium wide-body
-        } else if ac_type.to_uppercase().contains("737") || ac_type.to_uppercase().contains("A320")
-        {
-            150 // Narrow-body
-        } else {
-            100 // Default/regional
+/// CO2 share per passenger (same order as `cabin_classes`) and the method used
+pub fn split_co2_by_cabin_class(
+    total_co2: f64,
+    cabin_classes: &[Option<&str>],
+    weights: Option<&CabinClassWeights>,
+) -> (Vec<f64>, Co2SplitMethod) {
+    if cabin_classes.is_empty() {
+        return (Vec::new(), Co2SplitMethod::Equal);
+    }
+
+    let known: Vec<Option<f64>> = cabin_classes
+        .iter()
+        .map(|class| weights.zip(*class).and_then(|(w, c)| w.weight_for(c)))
+        .collect();
+
+    match weights {
+        Some(w) if known.iter().any(Option::is_some) => {
+            let seat_weights: Vec<f64> = known.iter().map(|k| k.unwrap_or(w.economy).max(0.0)).collect();
+            let total_weight: f64 = seat_weights.iter().sum();
+            if total_weight > 0.0 {
+                let shares = seat_weights.iter().map(|sw| total_co2 * sw / total_weight).collect();
+                return (shares, Co2SplitMethod::ClassWeighted);
+            }
         }
-    });
+        _ => {}
+    }
 
-    total_co2 / passengers as f64
+    let share = total_co2 / cabin_classes.len() as f64;
+    (vec![share; cabin_classes.len()], Co2SplitMethod::Equal)
+}
+
+/// Number of names in a passenger list separated by commas or semicolons
+pub fn count_passenger_names(names: &str) -> u32 {
+    names.split([',', ';']).filter(|name| !name.trim().is_empty()).count() as u32
+}
+
+/// CO2 share of the traveller on a flight record, given the record's fare class
+///
+/// The other `passenger_count - 1` passengers are counted as economy seats.
+pub fn passenger_co2_share(
+    total_co2: f64,
+    passenger_count: u32,
+    fare_class: Option<&str>,
+    weights: Option<&CabinClassWeights>,
+) -> (f64, Co2SplitMethod) {
+    let mut classes = vec![None; passenger_count.max(1) as usize];
+    classes[0] = fare_class;
+    let (shares, method) = split_co2_by_cabin_class(total_co2, &classes, weights);
+    (shares[0], method)
 }
 
 /// Convert fuel from kg to liters (Jet-A1 density ~0.8 kg/L)
@@ -185,75 +338,9 @@ pub fn fuel_kg_to_gallons(fuel_kg: f64) -> f64 {
     fuel_kg * 2.20462 / 6.7 // kg to lbs, then lbs to gallons
 }
 
-/// Calculate estimated flight time based on distance and aircraft type
-///
-/// # Arguments
-/// * `distance_km` - Flight distance in kilometers
-/// * `aircraft_type` - Optional aircraft type string
-///
-/// # Returns
-/// Flight duration in minutes (includes taxi, climb, cruise, descent)
-pub fn calculate_flight_time(distance_km: f64, aircraft_type: Option<&str>) -> i32 {
-    let aircraft_upper = aircraft_type.unwrap_or("").to_uppercase();
-
-    // Determine cruise speed based on aircraft type (km/h)
-    let cruise_speed_kmh = if aircraft_upper.contains("747") || aircraft_upper.contains("A380") {
-        910.0 // Large wide-body
-    } else if aircraft_upper.contains("777")
-        || aircraft_upper.contains("787")
-        || aircraft_upper.contains("A350")
-        || aircraft_upper.contains("330")
-    {
-        905.0 // Modern wide-body
-    } else if aircraft_upper.contains("737")
-        || aircraft_upper.contains("A320")
-        || aircraft_upper.contains("A319")
-        || aircraft_upper.contains("A321")
-    {
-        850.0 // Narrow-body jets
-    } else if aircraft_upper.contains("ERJ")
-        || aircraft_upper.contains("CRJ")
-        || aircraft_upper.contains("E170")
-        || aircraft_upper.contains("E190")
-    {
-        820.0 // Regional jets
-    } else if aircraft_upper.contains("CESSNA")
-        || aircraft_upper.contains("C172")
-        || aircraft_upper.contains("C182")
-        || aircraft_upper.contains("PA-")
-        || aircraft_upper.contains("PIPER")
-    {
-        200.0 // General aviation piston
-    } else if aircraft_upper.contains("CITATION")
-        || aircraft_upper.contains("GULFSTREAM")
-        || aircraft_upper.contains("LEARJET")
-        || aircraft_upper.contains("FALCON")
-        || aircraft_upper.contains("CHALLENGER")
-        || aircraft_upper.contains("HS125")
-    {
-        750.0 // Business jets
-    } else {
-        850.0 // Default to narrow-body average
-    };
-
-    // Calculate cruise time in hours
-    let cruise_time_hours = distance_km / cruise_speed_kmh;
-
-    // Add overhead for taxi, takeoff, climb, descent (varies by distance)
-    let overhead_minutes = if distance_km < 500.0 {
-        45.0 // Short flights: more overhead relative to cruise
-    } else if distance_km < 1500.0 {
-        35.0 // Medium flights
-    } else if distance_km < 5000.0 {
-        30.0 // Long flights
-    } else {
-        25.0 // Ultra-long flights: overhead is smaller percentage
-    };
-
-    // Total time in minutes
-    let total_minutes = (cruise_time_hours * 60.0) + overhead_minutes;
-
-    total_minutes.round() as i32
+/// Convert fuel from US gallons to kg (Jet-A1 ~6.7 lbs/gal)
+pub fn fuel_gallons_to_kg(gallons: f64) -> f64 {
+    gallons * 6.7 / 2.20462
 }
 
 /// Calculate block time (gate-to-gate) which includes ground operations
@@ -268,6 +355,58 @@ pub fn calculate_block_time(flight_duration: i32) -> i32 {
     flight_duration + 25
 }
 
+/// Field elevation above which density altitude noticeably reduces takeoff and climb performance
+pub const HIGH_ALTITUDE_THRESHOLD_FT: i32 = 5000;
+
+/// Runway length assumed for airports without runway data (airports.longest_runway_ft)
+pub const ASSUMED_RUNWAY_LENGTH_FT: i32 = 5000;
+
+/// Outside air temperature used for the "hot day" density altitude estimate
+const HOT_DAY_TEMPERATURE_C: f64 = 30.0;
+
+/// Density altitude at a field on a hot day, using the 120 ft per degree C rule of thumb
+/// against the ISA temperature at that elevation (15 C at sea level, -2 C per 1000 ft)
+pub fn hot_day_density_altitude_ft(elevation_ft: i32) -> i32 {
+    let isa_temperature_c = 15.0 - 2.0 * elevation_ft as f64 / 1000.0;
+    (elevation_ft as f64 + 120.0 * (HOT_DAY_TEMPERATURE_C - isa_temperature_c)).round() as i32
+}
+
+/// Runway length left after the density altitude penalty: takeoff distance grows roughly
+/// 10% per 1000 ft of density altitude, so the runway is worth that much less
+pub fn effective_runway_length_ft(runway_length_ft: i32, elevation_ft: Option<i32>) -> i32 {
+    let density_altitude = elevation_ft.map(hot_day_density_altitude_ft).unwrap_or(0).max(0);
+    (runway_length_ft as f64 / (1.0 + 0.1 * density_altitude as f64 / 1000.0)).round() as i32
+}
+
+/// safe | marginal | watch_out | nope, from the runway length after the altitude penalty
+pub fn runway_risk_level(effective_runway_ft: i32) -> &'static str {
+    match effective_runway_ft {
+        ft if ft >= 5000 => "safe",
+        ft if ft >= 3000 => "marginal",
+        ft if ft >= 2000 => "watch_out",
+        _ => "nope",
+    }
+}
+
+/// A flight arriving (or, without a scheduled arrival, departing) within this many
+/// minutes of the schedule counts as on time, as in airline punctuality reporting
+pub const ON_TIME_THRESHOLD_MINUTES: i64 = 15;
+
+/// Minutes between a scheduled and an actual time at the same airport; negative when early.
+/// None unless both are set and carry a time of day.
+pub fn delay_minutes(scheduled: Option<&str>, actual: Option<&str>) -> Option<i64> {
+    let parse = |value: &str| {
+        // A bare date parses as midnight, which would make up a delay
+        if value.trim().len() <= 10 {
+            return None;
+        }
+        crate::timezone::parse_local_datetime(value)
+    };
+    let scheduled = parse(scheduled?)?;
+    let actual = parse(actual?)?;
+    Some((actual - scheduled).num_minutes())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,7 +415,7 @@ mod tests {
     fn test_fuel_calculation_737() {
         // JFK to LAX is approximately 3983 km
         let distance_km = 3983.0;
-        let fuel = calculate_fuel_consumption(distance_km, Some("Boeing 737"));
+        let fuel = AircraftPerformance::from_aircraft_type("Boeing 737").fuel_consumption(distance_km);
 
         // Expected: ~3983 * 3.5 * 1.1 + 100 = ~15,400 kg
         assert!(
@@ -289,12 +428,21 @@ mod tests {
     #[test]
     fn test_co2_calculation() {
         let distance_km = 1000.0;
-        let co2 = calculate_co2_emissions(distance_km, Some("A320"));
+        let co2 = AircraftPerformance::from_aircraft_type("A320").co2_emissions(distance_km);
 
         // CO2 should be roughly fuel * 3.15
         assert!(co2 > 10000.0, "CO2 for 1000km should be significant");
     }
 
+    #[test]
+    fn test_per_passenger_co2() {
+        let a320 = AircraftPerformance::from_aircraft_type("A320");
+        assert_eq!(per_passenger_co2(&a320, 1000.0, Some(120)), a320.co2_emissions(1000.0) / 120.0);
+        // No count: a typical narrow-body load, not a full cabin
+        assert_eq!(per_passenger_co2(&a320, 1000.0, None), a320.co2_emissions(1000.0) / 150.0);
+        assert_eq!(per_passenger_co2(&a320, 1000.0, Some(0)), per_passenger_co2(&a320, 1000.0, None));
+    }
+
     #[test]
     fn test_fuel_conversions() {
         let fuel_kg = 1000.0;
@@ -309,36 +457,100 @@ mod tests {
     fn test_flight_time_calculation() {
         // JFK to LAX: ~3983 km
         // Expected: ~3983/850 * 60 + 30 = ~311 minutes (5h 11m)
-        let time = calculate_flight_time(3983.0, Some("Boeing 737"));
+        let time = AircraftPerformance::from_aircraft_type("Boeing 737").flight_time(3983.0);
         assert!(
-            time >= 300 && time <= 330,
+            (300..=330).contains(&time),
             "JFK-LAX should be ~5-5.5 hours, got {} minutes",
             time
         );
 
         // Short regional flight: 500 km
         // Expected: ~500/850 * 60 + 45 = ~80 minutes
-        let short_time = calculate_flight_time(500.0, Some("ERJ"));
+        let short_time = AircraftPerformance::from_aircraft_type("ERJ").flight_time(500.0);
         assert!(
-            short_time >= 70 && short_time <= 90,
+            (70..=90).contains(&short_time),
             "500km should be ~1-1.5 hours, got {} minutes",
             short_time
         );
 
         // Ultra-long: 12000 km
         // Expected: ~12000/905 * 60 + 25 = ~820 minutes (13h 40m)
-        let long_time = calculate_flight_time(12000.0, Some("777"));
+        let long_time = AircraftPerformance::from_aircraft_type("777").flight_time(12000.0);
         assert!(
-            long_time >= 800 && long_time <= 850,
+            (800..=850).contains(&long_time),
             "12000km should be ~13-14 hours, got {} minutes",
             long_time
         );
     }
 
+    #[test]
+    fn test_cabin_class_split() {
+        let weights = CabinClassWeights::default();
+
+        // One business and two economy seats: 2 / (2 + 1 + 1) of the total
+        let (shares, method) = split_co2_by_cabin_class(
+            1000.0,
+            &[Some("Business"), Some("Y"), None],
+            Some(&weights),
+        );
+        assert_eq!(method, Co2SplitMethod::ClassWeighted);
+        assert!((shares[0] - 500.0).abs() < 1e-9);
+        assert!((shares[1] - 250.0).abs() < 1e-9);
+        assert!((shares.iter().sum::<f64>() - 1000.0).abs() < 1e-9);
+
+        // No class data or no weights falls back to an equal split
+        let (shares, method) = split_co2_by_cabin_class(900.0, &[None, None, None], Some(&weights));
+        assert_eq!(method, Co2SplitMethod::Equal);
+        assert_eq!(shares, vec![300.0; 3]);
+        let (share, method) = passenger_co2_share(900.0, 3, Some("First"), None);
+        assert_eq!((share, method), (300.0, Co2SplitMethod::Equal));
+
+        // Traveller in first with two others: 3 / (3 + 1 + 1)
+        let (share, _) = passenger_co2_share(1000.0, 3, Some("F"), Some(&weights));
+        assert!((share - 600.0).abs() < 1e-9);
+
+        let bad = CabinClassWeights { business: -2.0, first: f64::INFINITY, ..Default::default() };
+        assert_eq!(bad.invalid_fields(), vec!["business", "first"]);
+        assert!(weights.invalid_fields().is_empty());
+    }
+
     #[test]
     fn test_block_time() {
         let flight_duration = 300; // 5 hours
         let block = calculate_block_time(flight_duration);
         assert_eq!(block, 325, "Block time should add 25 minutes for taxi");
     }
+
+    #[test]
+    fn test_delay_minutes() {
+        assert_eq!(delay_minutes(Some("2025-03-01T08:00"), Some("2025-03-01T08:42:00")), Some(42));
+        assert_eq!(delay_minutes(Some("2025-03-01T23:50"), Some("2025-03-02T00:05")), Some(15));
+        assert_eq!(delay_minutes(Some("2025-03-01 09:00"), Some("2025-03-01T08:55")), Some(-5));
+        assert_eq!(delay_minutes(None, Some("2025-03-01T08:42")), None);
+        assert_eq!(delay_minutes(Some("2025-03-01"), Some("2025-03-01T08:42")), None);
+    }
+
+    #[test]
+    fn test_density_altitude_runway_risk() {
+        // Denver, 5434 ft: ISA there is about 4 C, so a 30 C day adds ~3100 ft
+        assert_eq!(hot_day_density_altitude_ft(5434), 8538);
+        assert_eq!(effective_runway_length_ft(12000, None), 12000);
+        assert_eq!(effective_runway_length_ft(5000, Some(5434)), 2697);
+        assert_eq!(runway_risk_level(effective_runway_length_ft(5000, Some(5434))), "watch_out");
+        assert_eq!(runway_risk_level(effective_runway_length_ft(12000, Some(13))), "safe");
+    }
+
+    #[test]
+    fn test_emission_equivalents() {
+        let factors: EmissionFactors = serde_json::from_str(r#"{"car_kg_per_km": 0.2}"#).unwrap();
+        assert_eq!(factors.tree_kg_per_year, 21.0);
+
+        let equivalents = emission_equivalents(210.0, &factors);
+        assert!((equivalents.car_km - 1050.0).abs() < 1e-9);
+        assert!((equivalents.train_km - 6000.0).abs() < 1e-9);
+        assert!((equivalents.tree_years - 10.0).abs() < 1e-9);
+
+        let bad = EmissionFactors { train_kg_per_km: 0.0, tree_kg_per_year: f64::NAN, ..Default::default() };
+        assert_eq!(bad.invalid_fields(), vec!["train_kg_per_km", "tree_kg_per_year"]);
+    }
 }
@@ -30,7 +30,8 @@ pub struct CpuMetrics {
     pub load: String,
     pub frequency: String,
     pub governor: String,
-    pub turbo_enabled: bool,
+    /// None when neither the kernel nor the sentinel reports it
+    pub turbo_enabled: Option<bool>,
     pub alert_level: String,
 }
 
@@ -46,6 +47,8 @@ pub struct MemoryMetrics {
 pub struct ThermalMetrics {
     pub hottest: String,
     pub sensors: String,
+    /// CPU package power limit, when the kernel exposes it
+    pub power_limit_watts: Option<u32>,
     pub alert_level: String,
 }
 
@@ -150,6 +153,142 @@ fn extract_dbus_string_data(raw: &str) -> Result<String, String> {
     }
 }
 
+// ===== READ-BACK =====
+//
+// A write through a sentinel can report success without changing anything, typically when
+// the service runs without root. Controls with a readable state read it back afterwards,
+// from the kernel (sysfs, /proc) where it is exposed since that is the state that counts,
+// else from the sentinel. A mismatch fails the command.
+
+const PRIVILEGE_HINT: &str = "the change did not take effect, most likely because the sentinel service lacks root privileges";
+
+fn read_trimmed(path: &str) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn current_governor() -> Option<String> {
+    read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor").or_else(|| {
+        dbus_call_json(
+            "org.jesternet.cpu.Sentinel",
+            "/org/jesternet/cpu/Sentinel",
+            "org.jesternet.cpu.Sentinel",
+            "GetGovernor",
+            &[]
+        )
+        .ok()
+        .map(|raw| extract_governor(&raw))
+        .filter(|governor| !governor.is_empty())
+    })
+}
+
+fn current_turbo_enabled() -> Option<bool> {
+    // intel_pstate reports the inverse; acpi-cpufreq and amd-pstate use the generic boost flag
+    if let Some(no_turbo) = read_trimmed("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+        return Some(no_turbo == "0");
+    }
+    if let Some(boost) = read_trimmed("/sys/devices/system/cpu/cpufreq/boost") {
+        return Some(boost == "1");
+    }
+    dbus_call_json(
+        "org.jesternet.cpu.Sentinel",
+        "/org/jesternet/cpu/Sentinel",
+        "org.jesternet.cpu.Sentinel",
+        "GetTurboEnabled",
+        &[]
+    )
+    .ok()
+    .and_then(|raw| extract_turbo_enabled(&raw))
+}
+
+/// Long-term CPU package power limit (RAPL PL1), in whole watts
+fn current_cpu_power_limit_watts() -> Option<u32> {
+    let microwatts: u64 = read_trimmed("/sys/class/powercap/intel-rapl:0/constraint_0_power_limit_uw")?.parse().ok()?;
+    Some((microwatts as f64 / 1_000_000.0).round() as u32)
+}
+
+fn current_gpu_power_limit_watts(gpu_index: u32) -> Option<u32> {
+    let output = Command::new("nvidia-smi")
+        .args(["-i", &gpu_index.to_string(), "--query-gpu=power.limit", "--format=csv,noheader,nounits"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let watts: f64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(watts.round() as u32)
+}
+
+fn blocked_ips() -> Option<Vec<String>> {
+    let raw = dbus_call_json(
+        "org.jesternet.network.FlowAnalyzer",
+        "/org/jesternet/network/FlowAnalyzer",
+        "org.jesternet.network.FlowAnalyzer",
+        "ListBlocked",
+        &[]
+    )
+    .ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// The fields of /proc/<pid>/stat the process commands care about
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProcStat {
+    state: char,
+    ppid: u32,
+    nice: i32,
+}
+
+fn parse_proc_stat(stat: &str) -> Option<ProcStat> {
+    // The command name may contain spaces; the remaining fields start after its ')'
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    Some(ProcStat {
+        state: fields.first()?.chars().next()?,
+        ppid: fields.get(1)?.parse().ok()?,
+        nice: fields.get(16)?.parse().ok()?,
+    })
+}
+
+fn process_stat(pid: u32) -> Option<ProcStat> {
+    parse_proc_stat(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+fn blocked_state(ip: &str) -> Option<String> {
+    blocked_ips().map(|ips| if ips.iter().any(|b| b == ip) { "blocked" } else { "not blocked" }.to_string())
+}
+
+fn stopped_state(pid: u32) -> Option<String> {
+    process_stat(pid).map(|stat| if matches!(stat.state, 'T' | 't') { "stopped" } else { "not stopped" }.to_string())
+}
+
+fn turbo_state() -> Option<String> {
+    current_turbo_enabled().map(|on| if on { "turbo on" } else { "turbo off" }.to_string())
+}
+
+/// Judge a write by the state read back after it; data holds both as JSON
+fn read_back(applied: SentinelResult, expected: &str, actual: Option<String>) -> SentinelResult {
+    if !applied.success {
+        return applied;
+    }
+    let data = Some(serde_json::json!({ "expected": expected, "actual": actual }).to_string());
+    match actual {
+        Some(actual) if actual == expected => SentinelResult {
+            success: true,
+            message: format!("{} (confirmed)", applied.message),
+            data,
+        },
+        Some(actual) => SentinelResult {
+            success: false,
+            message: format!("Requested {} but the system reports {}: {}", expected, actual, PRIVILEGE_HINT),
+            data,
+        },
+        None => SentinelResult {
+            success: true,
+            message: format!("{} (could not be read back to confirm)", applied.message),
+            data,
+        },
+    }
+}
+
 // ===== SYSTEM METRICS =====
 
 #[tauri::command]
@@ -192,7 +331,7 @@ fn format_cpu_frequency(json_str: &str) -> String {
             let max = value.get("max_frequency_mhz").and_then(|v| v.as_f64());
             let pct = value.get("frequency_pct").and_then(|v| v.as_f64());
 
-            if let (Some(max_freq), Some(percent)) = (max, pct) {
+            if let (Some(_), Some(percent)) = (max, pct) {
                 return format!("{:.0} MHz ({:.0}%)", freq, percent);
             }
             return format!("{:.0} MHz", freq);
@@ -226,18 +365,10 @@ fn extract_governor(json_str: &str) -> String {
     json_str.trim_matches('"').to_string()
 }
 
-/// Extract turbo state from JSON response
-fn extract_turbo_enabled(json_str: &str) -> bool {
-    if let Ok(value) = serde_json::from_str::<serde_json::Value>(json_str) {
-        if let Some(turbo) = value.get("turbo_enabled").and_then(|v| v.as_bool()) {
-            return turbo;
-        }
-        if let Some(turbo) = value.as_bool() {
-            return turbo;
-        }
-    }
-    // Default to true if we can't parse
-    true
+/// Extract turbo state from JSON response; None if it can't be parsed
+fn extract_turbo_enabled(json_str: &str) -> Option<bool> {
+    let value = serde_json::from_str::<serde_json::Value>(json_str).ok()?;
+    value.get("turbo_enabled").and_then(|v| v.as_bool()).or_else(|| value.as_bool())
 }
 
 /// Clean up alert level response
@@ -272,22 +403,6 @@ pub async fn get_cpu_snapshot() -> Result<CpuMetrics, String> {
         &[]
     ).unwrap_or_default();
 
-    let governor_raw = dbus_call_json(
-        "org.jesternet.cpu.Sentinel",
-        "/org/jesternet/cpu/Sentinel",
-        "org.jesternet.cpu.Sentinel",
-        "GetGovernor",
-        &[]
-    ).unwrap_or_else(|_| "unknown".to_string());
-
-    let turbo_raw = dbus_call_json(
-        "org.jesternet.cpu.Sentinel",
-        "/org/jesternet/cpu/Sentinel",
-        "org.jesternet.cpu.Sentinel",
-        "GetTurboEnabled",
-        &[]
-    ).unwrap_or_else(|_| "true".to_string());
-
     let alert_raw = dbus_call_json(
         "org.jesternet.cpu.Sentinel",
         "/org/jesternet/cpu/Sentinel",
@@ -299,8 +414,8 @@ pub async fn get_cpu_snapshot() -> Result<CpuMetrics, String> {
     Ok(CpuMetrics {
         load: format_cpu_load(&load_raw),
         frequency: format_cpu_frequency(&frequency_raw),
-        governor: extract_governor(&governor_raw),
-        turbo_enabled: extract_turbo_enabled(&turbo_raw),
+        governor: current_governor().unwrap_or_else(|| "unknown".to_string()),
+        turbo_enabled: current_turbo_enabled(),
         alert_level: clean_alert_level(&alert_raw),
     })
 }
@@ -315,18 +430,19 @@ pub async fn cpu_set_governor(governor: String) -> Result<SentinelResult, String
         &["s", &governor]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("CPU governor set to {}", governor),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, &governor, current_governor()))
 }
 
 #[tauri::command]
@@ -339,18 +455,19 @@ pub async fn cpu_disable_turbo() -> Result<SentinelResult, String> {
         &[]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: "Turbo boost disabled".to_string(),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, "turbo off", turbo_state()))
 }
 
 #[tauri::command]
@@ -363,18 +480,19 @@ pub async fn cpu_enable_turbo() -> Result<SentinelResult, String> {
         &[]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: "Turbo boost enabled".to_string(),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, "turbo on", turbo_state()))
 }
 
 #[tauri::command]
@@ -435,7 +553,7 @@ pub async fn get_memory_snapshot() -> Result<MemoryMetrics, String> {
         "org.jesternet.memory.Monitor",
         "GetAvailableMb",
         &[]
-    ).ok().and_then(|s| s.trim().split_whitespace().last().and_then(|n| n.parse().ok())).unwrap_or(0);
+    ).ok().and_then(|s| s.split_whitespace().last().and_then(|n| n.parse().ok())).unwrap_or(0);
 
     let mem_percent: f64 = dbus_call(
         "org.jesternet.memory.Monitor",
@@ -443,7 +561,7 @@ pub async fn get_memory_snapshot() -> Result<MemoryMetrics, String> {
         "org.jesternet.memory.Monitor",
         "GetMemoryPercent",
         &[]
-    ).ok().and_then(|s| s.trim().split_whitespace().last().and_then(|n| n.parse().ok())).unwrap_or(0.0);
+    ).ok().and_then(|s| s.split_whitespace().last().and_then(|n| n.parse().ok())).unwrap_or(0.0);
 
     let swap_percent: f64 = dbus_call(
         "org.jesternet.memory.Monitor",
@@ -451,7 +569,7 @@ pub async fn get_memory_snapshot() -> Result<MemoryMetrics, String> {
         "org.jesternet.memory.Monitor",
         "GetSwapPercent",
         &[]
-    ).ok().and_then(|s| s.trim().split_whitespace().last().and_then(|n| n.parse().ok())).unwrap_or(0.0);
+    ).ok().and_then(|s| s.split_whitespace().last().and_then(|n| n.parse().ok())).unwrap_or(0.0);
 
     let alert = dbus_call_json(
         "org.jesternet.memory.Monitor",
@@ -685,6 +803,7 @@ pub async fn get_thermal_snapshot() -> Result<ThermalMetrics, String> {
     Ok(ThermalMetrics {
         hottest: format_hottest_sensor(&hottest_raw),
         sensors: format_sensors(&sensors_raw),
+        power_limit_watts: current_cpu_power_limit_watts(),
         alert_level: clean_alert_level(&alert_raw),
     })
 }
@@ -723,18 +842,19 @@ pub async fn thermal_set_power_limit(watts: u32) -> Result<SentinelResult, Strin
         &["u", &watts.to_string()]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("Power limit set to {} watts", watts),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, &format!("{} W", watts), current_cpu_power_limit_watts().map(|w| format!("{} W", w))))
 }
 
 #[tauri::command]
@@ -989,18 +1109,19 @@ pub async fn gpu_set_power_limit(gpu_index: u32, watts: u32) -> Result<SentinelR
         &["u", &gpu_index.to_string(), "u", &watts.to_string()]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("GPU {} power limit set to {} watts", gpu_index, watts),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, &format!("{} W", watts), current_gpu_power_limit_watts(gpu_index).map(|w| format!("{} W", w))))
 }
 
 #[tauri::command]
@@ -1028,7 +1149,8 @@ pub async fn gpu_reset(gpu_index: u32) -> Result<SentinelResult, String> {
 }
 
 #[tauri::command]
-pub async fn gpu_kill_process(pid: u32) -> Result<SentinelResult, String> {
+pub async fn gpu_kill_process(pid: u32, state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    refuse_if_protected("kill", pid, &ProtectedAdditions::configured(&state)?)?;
     let result = dbus_call(
         "org.jesternet.gpu.Sentinel",
         "/org/jesternet/gpu/Sentinel",
@@ -1099,18 +1221,19 @@ pub async fn network_block_ip(ip: String) -> Result<SentinelResult, String> {
         &["s", &ip]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("Blocked IP: {}", ip),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, "blocked", blocked_state(&ip)))
 }
 
 #[tauri::command]
@@ -1123,18 +1246,19 @@ pub async fn network_unblock_ip(ip: String) -> Result<SentinelResult, String> {
         &["s", &ip]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("Unblocked IP: {}", ip),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, "not blocked", blocked_state(&ip)))
 }
 
 #[tauri::command]
@@ -1233,10 +1357,191 @@ pub async fn network_clear_all_blocks() -> Result<SentinelResult, String> {
     }
 }
 
+// ===== PROCESS PROTECTION =====
+//
+// process_kill, process_freeze, gpu_kill_process and process_reap_zombies refuse to act on
+// processes the desktop cannot survive losing: init, kernel threads, this app and its
+// webview, the display server and session plumbing, plus anything listed in the
+// "protected_processes" setting (comma-separated process names or PIDs).
+
+const PROTECTED_SETTING: &str = "protected_processes";
+
+/// Process names (as in /proc/<pid>/comm) that are always protected, with what they are
+const PROTECTED_NAMES: [(&str, &str); 20] = [
+    ("systemd", "init system"),
+    ("init", "init system"),
+    ("systemd-logind", "login manager"),
+    ("dbus-daemon", "message bus"),
+    ("dbus-broker", "message bus"),
+    ("Xorg", "display server"),
+    ("X", "display server"),
+    ("Xwayland", "display server"),
+    ("gnome-shell", "compositor"),
+    ("mutter", "compositor"),
+    ("kwin_wayland", "compositor"),
+    ("kwin_x11", "compositor"),
+    ("plasmashell", "desktop shell"),
+    ("sway", "compositor"),
+    ("Hyprland", "compositor"),
+    ("weston", "compositor"),
+    ("gdm", "display manager"),
+    ("sddm", "display manager"),
+    ("lightdm", "display manager"),
+    ("pipewire", "audio/video server"),
+];
+
+/// Longest name /proc/<pid>/comm reports; longer names are truncated there
+const MAX_COMM_LEN: usize = 15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectedProcess {
+    /// Set for a specific process
+    pub pid: Option<u32>,
+    /// Set for a rule that matches by process name, or the name of a specific process
+    pub name: Option<String>,
+    pub reason: String,
+    /// "builtin" or "setting"
+    pub source: String,
+}
+
+/// The user's additions from the protected_processes setting
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ProtectedAdditions {
+    pids: Vec<u32>,
+    names: Vec<String>,
+}
+
+impl ProtectedAdditions {
+    fn parse(raw: &str) -> Self {
+        let mut additions = Self::default();
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.parse::<u32>() {
+                Ok(pid) => additions.pids.push(pid),
+                Err(_) => additions.names.push(entry.to_string()),
+            }
+        }
+        additions
+    }
+
+    fn configured(state: &State<'_, AppState>) -> Result<Self, String> {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let raw = db.get_setting(PROTECTED_SETTING).map_err(|e| e.to_string())?;
+        Ok(Self::parse(raw.as_deref().unwrap_or("")))
+    }
+}
+
+fn process_name(pid: u32) -> Option<String> {
+    read_trimmed(&format!("/proc/{}/comm", pid))
+}
+
+/// Numeric entries of /proc, i.e. every running process
+fn running_pids() -> Vec<u32> {
+    std::fs::read_dir("/proc")
+        .map(|entries| entries.flatten().filter_map(|e| e.file_name().to_str()?.parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Why the process must be left alone, if it must. `name` and `ppid` are as read from /proc.
+fn protection_reason(pid: u32, name: Option<&str>, ppid: Option<u32>, additions: &ProtectedAdditions) -> Option<String> {
+    let own_pid = std::process::id();
+    if pid <= 1 {
+        return Some("PID 1 is init; the whole system goes down with it".to_string());
+    }
+    if pid == own_pid {
+        return Some("it is this app".to_string());
+    }
+    if ppid == Some(own_pid) {
+        return Some("it belongs to this app".to_string());
+    }
+    if pid == 2 || ppid == Some(2) {
+        return Some("it is a kernel thread".to_string());
+    }
+    if additions.pids.contains(&pid) {
+        return Some(format!("PID {} is in the {} setting", pid, PROTECTED_SETTING));
+    }
+    let name = name?;
+    if let Some((_, what)) = PROTECTED_NAMES.iter().find(|(protected, _)| *protected == name) {
+        return Some(format!("{} is the {}", name, what));
+    }
+    if additions.names.iter().any(|protected| protected == name) {
+        return Some(format!("{} is in the {} setting", name, PROTECTED_SETTING));
+    }
+    None
+}
+
+/// Err naming the process and why it is protected, if it is
+fn refuse_if_protected(action: &str, pid: u32, additions: &ProtectedAdditions) -> Result<(), String> {
+    let name = process_name(pid);
+    let ppid = process_stat(pid).map(|stat| stat.ppid);
+    match protection_reason(pid, name.as_deref(), ppid, additions) {
+        Some(reason) => Err(format!(
+            "Refusing to {} process {}{}: {}",
+            action,
+            pid,
+            name.map(|n| format!(" ({})", n)).unwrap_or_default(),
+            reason
+        )),
+        None => Ok(()),
+    }
+}
+
+fn protected_processes(additions: &ProtectedAdditions) -> Vec<ProtectedProcess> {
+    let builtin = |pid: Option<u32>, name: Option<String>, reason: &str| ProtectedProcess {
+        pid,
+        name,
+        reason: reason.to_string(),
+        source: "builtin".to_string(),
+    };
+    let own_pid = std::process::id();
+    let mut protected = vec![
+        builtin(Some(1), process_name(1), "init"),
+        builtin(Some(2), process_name(2), "kernel threads (kthreadd and its children)"),
+        builtin(Some(own_pid), process_name(own_pid), "this app"),
+    ];
+    for pid in running_pids() {
+        if process_stat(pid).is_some_and(|stat| stat.ppid == own_pid) {
+            protected.push(builtin(Some(pid), process_name(pid), "belongs to this app"));
+        }
+    }
+    protected.extend(PROTECTED_NAMES.iter().map(|(name, what)| builtin(None, Some(name.to_string()), what)));
+
+    let setting = |pid, name| ProtectedProcess { pid, name, reason: "user addition".to_string(), source: "setting".to_string() };
+    protected.extend(additions.pids.iter().map(|&pid| setting(Some(pid), process_name(pid))));
+    protected.extend(additions.names.iter().map(|name| setting(None, Some(name.clone()))));
+    protected
+}
+
+/// Everything the process commands refuse to act on: fixed PIDs, this app's processes and
+/// name rules, then the user's additions
+#[tauri::command]
+pub fn get_protected_processes(state: State<'_, AppState>) -> Result<Vec<ProtectedProcess>, String> {
+    Ok(protected_processes(&ProtectedAdditions::configured(&state)?))
+}
+
+/// Replace the user's additions to the protected list with `entries` (process names or PIDs)
+#[tauri::command]
+pub fn set_protected_processes(entries: Vec<String>, state: State<'_, AppState>) -> Result<Vec<ProtectedProcess>, String> {
+    let entries: Vec<&str> = entries.iter().map(|e| e.trim()).filter(|e| !e.is_empty()).collect();
+    if let Some(bad) = entries.iter().find(|e| e.contains(',')) {
+        return Err(format!("Invalid protected process \"{}\": one name or PID per entry", bad));
+    }
+    if let Some(long) = entries.iter().find(|e| e.len() > MAX_COMM_LEN) {
+        return Err(format!(
+            "Invalid protected process \"{}\": process names are at most {} characters, as in /proc/<pid>/comm",
+            long, MAX_COMM_LEN
+        ));
+    }
+    let raw = entries.join(",");
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_setting(PROTECTED_SETTING, &raw).map_err(|e| e.to_string())?;
+    Ok(protected_processes(&ProtectedAdditions::parse(&raw)))
+}
+
 // ===== PROCESS DIAGNOSTICIAN =====
 
 #[tauri::command]
-pub async fn process_freeze(pid: u32) -> Result<SentinelResult, String> {
+pub async fn process_freeze(pid: u32, state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    refuse_if_protected("freeze", pid, &ProtectedAdditions::configured(&state)?)?;
     let result = dbus_call(
         "org.jesternet.process.Diagnostician",
         "/org/jesternet/process/Diagnostician",
@@ -1245,18 +1550,19 @@ pub async fn process_freeze(pid: u32) -> Result<SentinelResult, String> {
         &["u", &pid.to_string()]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("Frozen process {}", pid),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, "stopped", stopped_state(pid)))
 }
 
 #[tauri::command]
@@ -1269,22 +1575,24 @@ pub async fn process_thaw(pid: u32) -> Result<SentinelResult, String> {
         &["u", &pid.to_string()]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("Thawed process {}", pid),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, "not stopped", stopped_state(pid)))
 }
 
 #[tauri::command]
-pub async fn process_kill(pid: u32, signal: i32) -> Result<SentinelResult, String> {
+pub async fn process_kill(pid: u32, signal: i32, state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    refuse_if_protected("kill", pid, &ProtectedAdditions::configured(&state)?)?;
     let result = dbus_call(
         "org.jesternet.process.Diagnostician",
         "/org/jesternet/process/Diagnostician",
@@ -1317,22 +1625,38 @@ pub async fn process_set_nice(pid: u32, nice: i32) -> Result<SentinelResult, Str
         &["u", &pid.to_string(), "i", &nice.to_string()]
     );
 
-    match result {
-        Ok(_) => Ok(SentinelResult {
+    let applied = match result {
+        Ok(_) => SentinelResult {
             success: true,
             message: format!("Set nice {} for process {}", nice, pid),
             data: None,
-        }),
-        Err(e) => Ok(SentinelResult {
+        },
+        Err(e) => SentinelResult {
             success: false,
             message: e,
             data: None,
-        }),
-    }
+        },
+    };
+    Ok(read_back(applied, &format!("nice {}", nice), process_stat(pid).map(|stat| format!("nice {}", stat.nice))))
 }
 
+/// Reaping a zombie means signalling its parent, so this refuses while any zombie's parent is
+/// protected
 #[tauri::command]
-pub async fn process_reap_zombies() -> Result<SentinelResult, String> {
+pub async fn process_reap_zombies(state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    let additions = ProtectedAdditions::configured(&state)?;
+    let mut parents: Vec<u32> = running_pids()
+        .into_iter()
+        .filter_map(process_stat)
+        .filter(|stat| stat.state == 'Z')
+        .map(|stat| stat.ppid)
+        .collect();
+    parents.sort_unstable();
+    parents.dedup();
+    for parent in parents {
+        refuse_if_protected("reap zombies of", parent, &additions)?;
+    }
+
     let result = dbus_call(
         "org.jesternet.process.Diagnostician",
         "/org/jesternet/process/Diagnostician",
@@ -1359,17 +1683,17 @@ pub async fn process_reap_zombies() -> Result<SentinelResult, String> {
 fn parse_process_list(json_str: &str) -> Vec<ProcessInfo> {
     if let Ok(value) = serde_json::from_str::<serde_json::Value>(json_str) {
         if let Some(procs) = value.as_array() {
-            return procs.iter().filter_map(|p| {
+            return procs.iter().map(|p| {
                 let pid = p.get("pid").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
                 let name = p.get("name").or_else(|| p.get("command")).and_then(|v| v.as_str()).unwrap_or("unknown").to_string();
                 let cpu_percent = p.get("cpu_percent").or_else(|| p.get("cpu")).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 let memory_mb = p.get("memory_mb").or_else(|| p.get("mem_mb")).or_else(|| p.get("memory")).and_then(|v| v.as_f64()).unwrap_or(0.0);
-                Some(ProcessInfo {
+                ProcessInfo {
                     pid,
                     name,
                     cpu_percent,
                     memory_mb,
-                })
+                }
             }).collect();
         }
     }
@@ -1400,44 +1724,332 @@ pub async fn process_get_top_memory(count: u32) -> Result<Vec<ProcessInfo>, Stri
     Ok(parse_process_list(&raw))
 }
 
-// ===== PANIC BUTTONS =====
+// ===== ACTION PROFILES =====
+//
+// What emergency_all_systems, performance_mode and lockdown_network actually do comes from
+// the profile in the "active_defense_profile" setting, so a composite command never does
+// something inappropriate for the machine (e.g. enabling turbo on a laptop that runs hot).
+// Without a stored profile the conservative preset applies.
+
+const PROFILE_SETTING: &str = "active_defense_profile";
+
+/// Governors the CPU sentinel accepts
+const CPU_GOVERNORS: [&str; 6] = ["performance", "powersave", "schedutil", "ondemand", "conservative", "userspace"];
+
+/// One control a composite command applies, with its arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "control", rename_all = "snake_case")]
+pub enum DefenseAction {
+    CpuSetGovernor { governor: String },
+    CpuDisableTurbo,
+    CpuEnableTurbo,
+    CpuEmergencyPowerReduce,
+    ThermalEmergencyCool,
+    ThermalSetPowerLimit { watts: u32 },
+    MemoryEmergencyRelief,
+    MemoryDropCaches { level: u32 },
+    MemoryCompact,
+    GpuEmergencyThrottle { gpu_index: u32 },
+    GpuSetPowerLimit { gpu_index: u32, watts: u32 },
+    NetworkBlockIp { ip: String },
+    NetworkRateLimitIp { ip: String, kbytes_per_sec: u32 },
+}
+
+impl DefenseAction {
+    /// Why the arguments are unusable, if they are
+    fn invalid_reason(&self) -> Option<String> {
+        match self {
+            Self::CpuSetGovernor { governor } if !CPU_GOVERNORS.contains(&governor.as_str()) => {
+                Some(format!("unknown governor \"{}\" (expected one of {})", governor, CPU_GOVERNORS.join(", ")))
+            }
+            Self::ThermalSetPowerLimit { watts: 0 } | Self::GpuSetPowerLimit { watts: 0, .. } => {
+                Some("watts must be greater than 0".to_string())
+            }
+            Self::MemoryDropCaches { level } if !(1..=3).contains(level) => Some("level must be 1, 2 or 3".to_string()),
+            Self::NetworkBlockIp { ip } | Self::NetworkRateLimitIp { ip, .. } if ip.trim().parse::<std::net::IpAddr>().is_err() => {
+                Some(format!("\"{}\" is not an IP address", ip))
+            }
+            Self::NetworkRateLimitIp { kbytes_per_sec: 0, .. } => Some("kbytes_per_sec must be greater than 0".to_string()),
+            _ => None,
+        }
+    }
+
+    /// D-Bus name of the sentinel that provides the control
+    fn service(&self) -> &'static str {
+        match self {
+            Self::CpuSetGovernor { .. } | Self::CpuDisableTurbo | Self::CpuEnableTurbo | Self::CpuEmergencyPowerReduce => {
+                "org.jesternet.cpu.Sentinel"
+            }
+            Self::ThermalEmergencyCool | Self::ThermalSetPowerLimit { .. } => "org.jesternet.thermal.Sentinel",
+            Self::MemoryEmergencyRelief | Self::MemoryDropCaches { .. } | Self::MemoryCompact => "org.jesternet.memory.Monitor",
+            Self::GpuEmergencyThrottle { .. } | Self::GpuSetPowerLimit { .. } => "org.jesternet.gpu.Sentinel",
+            Self::NetworkBlockIp { .. } | Self::NetworkRateLimitIp { .. } => "org.jesternet.network.FlowAnalyzer",
+        }
+    }
+
+    /// Why the control cannot be applied on this machine, if it cannot
+    fn unavailable_reason(&self, available: &AvailableControls) -> Option<String> {
+        let service = self.service();
+        if !available.services.contains(service) {
+            return Some(format!("{} is not running", service));
+        }
+        match self {
+            Self::GpuEmergencyThrottle { gpu_index } | Self::GpuSetPowerLimit { gpu_index, .. } => match available.gpu_count {
+                None => Some("the GPU sentinel did not list any GPUs".to_string()),
+                Some(count) if *gpu_index as usize >= count => {
+                    Some(format!("GPU {} not found ({} GPU(s) present)", gpu_index, count))
+                }
+                Some(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    async fn apply(&self) -> SentinelResult {
+        let result = match self {
+            Self::CpuSetGovernor { governor } => cpu_set_governor(governor.clone()).await,
+            Self::CpuDisableTurbo => cpu_disable_turbo().await,
+            Self::CpuEnableTurbo => cpu_enable_turbo().await,
+            Self::CpuEmergencyPowerReduce => cpu_emergency_power_reduce().await,
+            Self::ThermalEmergencyCool => thermal_emergency_cool().await,
+            Self::ThermalSetPowerLimit { watts } => thermal_set_power_limit(*watts).await,
+            Self::MemoryEmergencyRelief => memory_emergency_relief().await,
+            Self::MemoryDropCaches { level } => memory_drop_caches(*level).await,
+            Self::MemoryCompact => memory_compact().await,
+            Self::GpuEmergencyThrottle { gpu_index } => gpu_emergency_throttle(*gpu_index).await,
+            Self::GpuSetPowerLimit { gpu_index, watts } => gpu_set_power_limit(*gpu_index, *watts).await,
+            Self::NetworkBlockIp { ip } => network_block_ip(ip.trim().to_string()).await,
+            Self::NetworkRateLimitIp { ip, kbytes_per_sec } => {
+                network_rate_limit_ip(ip.trim().to_string(), *kbytes_per_sec).await
+            }
+        };
+        result.unwrap_or_else(|e| SentinelResult { success: false, message: e, data: None })
+    }
+}
+
+/// The sentinels running on this machine and the GPUs they manage
+#[derive(Debug, Clone, Default)]
+struct AvailableControls {
+    services: std::collections::HashSet<&'static str>,
+    /// None when the GPU sentinel is not running or its GPU list did not parse
+    gpu_count: Option<usize>,
+}
+
+impl AvailableControls {
+    const SERVICES: [&'static str; 5] = [
+        "org.jesternet.cpu.Sentinel",
+        "org.jesternet.thermal.Sentinel",
+        "org.jesternet.memory.Monitor",
+        "org.jesternet.gpu.Sentinel",
+        "org.jesternet.network.FlowAnalyzer",
+    ];
+
+    fn detect() -> Self {
+        let services: std::collections::HashSet<&'static str> = Self::SERVICES
+            .into_iter()
+            .filter(|service| {
+                dbus_call(
+                    "org.freedesktop.DBus",
+                    "/org/freedesktop/DBus",
+                    "org.freedesktop.DBus",
+                    "NameHasOwner",
+                    &["s", service]
+                )
+                .is_ok_and(|reply| reply.trim() == "b true")
+            })
+            .collect();
+        let gpu_count = if services.contains("org.jesternet.gpu.Sentinel") {
+            dbus_call_json(
+                "org.jesternet.gpu.Sentinel",
+                "/org/jesternet/gpu/Sentinel",
+                "org.jesternet.gpu.Sentinel",
+                "GetGpus",
+                &[]
+            )
+            .ok()
+            .and_then(|raw| parse_gpu_count(&raw))
+        } else {
+            None
+        };
+        AvailableControls { services, gpu_count }
+    }
+}
+
+/// Number of GPUs in a GetGpus reply: an array of GPUs, a count, or a single GPU's name
+fn parse_gpu_count(json_str: &str) -> Option<usize> {
+    let trimmed = json_str.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    match serde_json::from_str::<serde_json::Value>(trimmed) {
+        Ok(serde_json::Value::Array(gpus)) => Some(gpus.len()),
+        Ok(serde_json::Value::Number(count)) => count.as_u64().map(|n| n as usize),
+        Ok(serde_json::Value::String(name)) => Some(usize::from(!name.trim().is_empty())),
+        Ok(_) => None,
+        // A plain GPU name, as format_gpus also accepts
+        Err(_) if !trimmed.starts_with('{') && !trimmed.starts_with('[') => Some(1),
+        Err(_) => None,
+    }
+}
+
+/// The controls each composite command applies, in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveDefenseProfile {
+    /// "conservative", "aggressive" or "custom"
+    pub name: String,
+    pub emergency: Vec<DefenseAction>,
+    pub performance: Vec<DefenseAction>,
+    pub lockdown: Vec<DefenseAction>,
+}
+
+impl ActiveDefenseProfile {
+    /// Reversible actions only: no memory relief, turbo left as it is, nothing blocked
+    pub fn conservative() -> Self {
+        ActiveDefenseProfile {
+            name: "conservative".to_string(),
+            emergency: vec![DefenseAction::ThermalEmergencyCool, DefenseAction::CpuEmergencyPowerReduce],
+            performance: vec![DefenseAction::CpuSetGovernor { governor: "performance".to_string() }],
+            lockdown: Vec::new(),
+        }
+    }
+
+    /// Everything the composite commands did before profiles, plus throttling the first GPU
+    pub fn aggressive() -> Self {
+        ActiveDefenseProfile {
+            name: "aggressive".to_string(),
+            emergency: vec![
+                DefenseAction::ThermalEmergencyCool,
+                DefenseAction::CpuEmergencyPowerReduce,
+                DefenseAction::MemoryEmergencyRelief,
+                DefenseAction::GpuEmergencyThrottle { gpu_index: 0 },
+            ],
+            performance: vec![
+                DefenseAction::CpuEnableTurbo,
+                DefenseAction::CpuSetGovernor { governor: "performance".to_string() },
+            ],
+            lockdown: Vec::new(),
+        }
+    }
+
+    /// Each action with its position, as "emergency[1]"
+    fn labelled_actions(&self) -> impl Iterator<Item = (String, &DefenseAction)> {
+        [("emergency", &self.emergency), ("performance", &self.performance), ("lockdown", &self.lockdown)]
+            .into_iter()
+            .flat_map(|(command, actions)| {
+                actions.iter().enumerate().map(move |(i, action)| (format!("{}[{}]", command, i), action))
+            })
+    }
+
+    /// Every problem with the profile's arguments, as "emergency[1]: ..." lines
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.name.trim().is_empty() {
+            problems.push("name is required".to_string());
+        }
+        problems.extend(
+            self.labelled_actions()
+                .filter_map(|(label, action)| action.invalid_reason().map(|reason| format!("{}: {}", label, reason))),
+        );
+        problems
+    }
+
+    /// Every action this machine cannot apply, in the same form as problems()
+    fn unavailable(&self, available: &AvailableControls) -> Vec<String> {
+        self.labelled_actions()
+            .filter_map(|(label, action)| action.unavailable_reason(available).map(|reason| format!("{}: {}", label, reason)))
+            .collect()
+    }
+
+    /// The stored profile, or the conservative preset when none is stored or it no longer parses
+    fn configured(db: &crate::database::Database) -> Self {
+        db.get_setting(PROFILE_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str::<ActiveDefenseProfile>(&json).ok())
+            .filter(|profile| profile.problems().is_empty())
+            .unwrap_or_else(Self::conservative)
+    }
+}
+
+fn configured_profile(state: &State<'_, AppState>) -> Result<ActiveDefenseProfile, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(ActiveDefenseProfile::configured(&db))
+}
+
+/// Apply the actions in order, carrying on past failures; data lists each action's result
+async fn apply_actions(label: &str, actions: &[DefenseAction]) -> SentinelResult {
+    if actions.is_empty() {
+        return SentinelResult {
+            success: true,
+            message: format!("{}: no actions in the active defense profile", label),
+            data: None,
+        };
+    }
+    let mut results = Vec::with_capacity(actions.len());
+    for action in actions {
+        results.push((action, action.apply().await));
+    }
+    let applied = results.iter().filter(|(_, result)| result.success).count();
+    let data = results
+        .iter()
+        .map(|(action, result)| serde_json::json!({ "action": action, "success": result.success, "message": result.message }))
+        .collect::<Vec<_>>();
+    SentinelResult {
+        success: applied == actions.len(),
+        message: format!("{}: {} of {} actions applied", label, applied, actions.len()),
+        data: Some(serde_json::Value::Array(data).to_string()),
+    }
+}
 
 #[tauri::command]
-pub async fn emergency_all_systems() -> Result<SentinelResult, String> {
-    // Execute all emergency actions in parallel
-    let _ = thermal_emergency_cool().await;
-    let _ = cpu_emergency_power_reduce().await;
-    let _ = memory_emergency_relief().await;
+pub fn get_active_defense_profile(state: State<'_, AppState>) -> Result<ActiveDefenseProfile, String> {
+    configured_profile(&state)
+}
 
-    Ok(SentinelResult {
-        success: true,
-        message: "All emergency protocols activated".to_string(),
-        data: None,
-    })
+/// The built-in profiles, to start a custom one from
+#[tauri::command]
+pub fn list_active_defense_presets() -> Vec<ActiveDefenseProfile> {
+    vec![ActiveDefenseProfile::conservative(), ActiveDefenseProfile::aggressive()]
 }
 
+/// Store the profile the composite commands apply; rejected whole if any action is invalid
+/// or uses a control this machine does not have
 #[tauri::command]
-pub async fn lockdown_network() -> Result<SentinelResult, String> {
-    // Block all non-essential network traffic
-    // This is a placeholder - real implementation would be more sophisticated
-    Ok(SentinelResult {
-        success: true,
-        message: "Network lockdown initiated".to_string(),
-        data: None,
-    })
+pub async fn set_active_defense_profile(
+    profile: ActiveDefenseProfile,
+    state: State<'_, AppState>,
+) -> Result<ActiveDefenseProfile, String> {
+    let mut problems = profile.problems();
+    if problems.is_empty() {
+        problems = profile.unavailable(&AvailableControls::detect());
+    }
+    if !problems.is_empty() {
+        return Err(format!("Invalid active defense profile: {}", problems.join("; ")));
+    }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(&profile).map_err(|e| e.to_string())?;
+    db.set_setting(PROFILE_SETTING, &json).map_err(|e| e.to_string())?;
+    Ok(profile)
+}
+
+// ===== PANIC BUTTONS =====
+
+#[tauri::command]
+pub async fn emergency_all_systems(state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    let profile = configured_profile(&state)?;
+    Ok(apply_actions("Emergency protocols", &profile.emergency).await)
 }
 
 #[tauri::command]
-pub async fn performance_mode() -> Result<SentinelResult, String> {
-    // Enable turbo and set performance governor
-    let _ = cpu_enable_turbo().await;
-    let _ = cpu_set_governor("performance".to_string()).await;
+pub async fn lockdown_network(state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    let profile = configured_profile(&state)?;
+    Ok(apply_actions("Network lockdown", &profile.lockdown).await)
+}
 
-    Ok(SentinelResult {
-        success: true,
-        message: "Performance mode activated".to_string(),
-        data: None,
-    })
+#[tauri::command]
+pub async fn performance_mode(state: State<'_, AppState>) -> Result<SentinelResult, String> {
+    let profile = configured_profile(&state)?;
+    Ok(apply_actions("Performance mode", &profile.performance).await)
 }
 
 #[tauri::command]
@@ -1452,3 +2064,91 @@ pub async fn reset_all_controls() -> Result<SentinelResult, String> {
         data: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_back() {
+        // comm with spaces and parentheses; nice is the 19th field
+        let stat = "4242 (Web (Content) x) T 1 4242 4242 0 -1 4194304 100 0 0 0 5 3 0 0 20 5 1 0 100 1000 50";
+        assert_eq!(parse_proc_stat(stat), Some(ProcStat { state: 'T', ppid: 1, nice: 5 }));
+        assert_eq!(parse_proc_stat("garbage"), None);
+
+        let applied = |message: &str| SentinelResult { success: true, message: message.to_string(), data: None };
+        let confirmed = read_back(applied("CPU governor set to powersave"), "powersave", Some("powersave".to_string()));
+        assert!(confirmed.success && confirmed.message.ends_with("(confirmed)"));
+
+        let mismatch = read_back(applied("CPU governor set to powersave"), "powersave", Some("performance".to_string()));
+        assert!(!mismatch.success);
+        assert!(mismatch.message.contains("reports performance") && mismatch.message.contains("root privileges"));
+        assert_eq!(mismatch.data.as_deref(), Some(r#"{"actual":"performance","expected":"powersave"}"#));
+
+        assert!(read_back(applied("Turbo boost disabled"), "turbo off", None).success);
+        let failed = SentinelResult { success: false, message: "Access denied".to_string(), data: None };
+        assert_eq!(read_back(failed, "turbo off", Some("turbo on".to_string())).message, "Access denied");
+    }
+
+    #[test]
+    fn test_process_protection() {
+        let additions = ProtectedAdditions::parse(" 4242, firefox ,,steam");
+        assert_eq!(additions, ProtectedAdditions { pids: vec![4242], names: vec!["firefox".to_string(), "steam".to_string()] });
+
+        let own_pid = std::process::id();
+        let reason = |pid, name, ppid| protection_reason(pid, name, ppid, &additions);
+        assert!(reason(1, Some("systemd"), Some(0)).unwrap().contains("PID 1"));
+        assert_eq!(reason(own_pid, None, None).as_deref(), Some("it is this app"));
+        assert_eq!(reason(90001, Some("WebKitWebProcess"), Some(own_pid)).as_deref(), Some("it belongs to this app"));
+        assert_eq!(reason(90002, Some("kworker/0:1"), Some(2)).as_deref(), Some("it is a kernel thread"));
+        assert_eq!(reason(90003, Some("Xwayland"), Some(1500)).as_deref(), Some("Xwayland is the display server"));
+        assert!(reason(4242, None, None).unwrap().contains("protected_processes"));
+        assert!(reason(90004, Some("steam"), Some(1500)).unwrap().contains("protected_processes"));
+        assert_eq!(reason(90005, Some("stress-ng"), Some(1500)), None);
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        assert!(ActiveDefenseProfile::conservative().problems().is_empty());
+        assert!(ActiveDefenseProfile::aggressive().problems().is_empty());
+        assert!(!ActiveDefenseProfile::conservative().performance.contains(&DefenseAction::CpuEnableTurbo));
+
+        let profile: ActiveDefenseProfile = serde_json::from_value(serde_json::json!({
+            "name": "laptop",
+            "emergency": [{ "control": "thermal_set_power_limit", "watts": 0 }],
+            "performance": [{ "control": "cpu_set_governor", "governor": "turbo" }],
+            "lockdown": [{ "control": "network_block_ip", "ip": "10.0.0.9" }, { "control": "network_block_ip", "ip": "nope" }],
+        }))
+        .unwrap();
+        assert_eq!(profile.problems().iter().map(|p| &p[..p.find(':').unwrap()]).collect::<Vec<_>>(), [
+            "emergency[0]",
+            "performance[0]",
+            "lockdown[1]"
+        ]);
+
+        // Controls whose sentinel or GPU is missing are rejected before the profile is stored
+        let available = AvailableControls {
+            services: ["org.jesternet.cpu.Sentinel", "org.jesternet.thermal.Sentinel", "org.jesternet.gpu.Sentinel"].into(),
+            gpu_count: Some(1),
+        };
+        assert!(ActiveDefenseProfile::conservative().unavailable(&available).is_empty());
+        let mut aggressive = ActiveDefenseProfile::aggressive();
+        aggressive.lockdown.push(DefenseAction::GpuSetPowerLimit { gpu_index: 1, watts: 150 });
+        assert_eq!(aggressive.unavailable(&available), [
+            "emergency[2]: org.jesternet.memory.Monitor is not running",
+            "lockdown[0]: GPU 1 not found (1 GPU(s) present)",
+        ]);
+        let no_gpu_list = AvailableControls { gpu_count: None, ..available };
+        assert_eq!(aggressive.unavailable(&no_gpu_list).len(), 3);
+
+        assert_eq!(parse_gpu_count(r#"[{"name":"RTX 4090"},{"name":"RTX 3060"}]"#), Some(2));
+        assert_eq!(parse_gpu_count("2"), Some(2));
+        assert_eq!(parse_gpu_count("NVIDIA GeForce RTX 4090"), Some(1));
+        assert_eq!(parse_gpu_count("\"\""), Some(0));
+        assert_eq!(parse_gpu_count(""), None);
+
+        // Controls that do not exist are rejected when the profile is read
+        let unknown = serde_json::json!({ "name": "x", "emergency": [{ "control": "overclock" }], "performance": [], "lockdown": [] });
+        assert!(serde_json::from_value::<ActiveDefenseProfile>(unknown).is_err());
+    }
+}
@@ -71,7 +71,7 @@ pub fn get_ai_models(app_handle: tauri::AppHandle) -> Result<Vec<ProviderModels>
     for model in models {
         provider_map
             .entry(model.provider.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(model);
     }
 
@@ -3,12 +3,16 @@
 
 use tauri::{State, Manager};
 use super::AppState;
+use crate::error::CommandError;
+use crate::airport_import::{AirportColumnMapping, AirportImportReport};
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 use rusqlite::OptionalExtension;
 use std::path::PathBuf;
-use std::sync::{OnceLock, Mutex};
-use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use tauri::Emitter;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AirportData {
@@ -19,6 +23,8 @@ pub struct AirportData {
     pub longitude_deg: Option<f64>,
     pub municipality: Option<String>,
     pub iso_country: Option<String>,
+    #[serde(default)]
+    pub elevation_ft: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,8 +33,38 @@ pub struct EnrichmentResult {
     pub enriched_count: usize,
     pub new_airports: usize,
     pub errors: Vec<String>,
+    /// Stopped early by cancel_enrichment; total_processed counts the committed airports
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Airports given coordinates by the online geocoding fallback
+    #[serde(default)]
+    pub geocoded_count: usize,
 }
 
+/// Progress event emitted on "airport-enrichment:progress"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrichmentProgress {
+    pub current: usize,
+    pub total: usize,
+    pub enriched: usize,
+    pub new_airports: usize,
+    pub failed: usize,
+    /// Airport code just processed
+    pub airport: Option<String>,
+    pub airports_per_second: f64,
+    pub eta_seconds: Option<u64>,
+    pub complete: bool,
+    pub cancelled: bool,
+}
+
+/// Airports written per transaction; cancellation is checked between chunks
+const ENRICHMENT_CHUNK_SIZE: usize = 200;
+/// Emit progress at least this often even when nothing changed
+const ENRICHMENT_PROGRESS_EVERY: usize = 100;
+
+static ENRICHMENT_RUNNING: AtomicBool = AtomicBool::new(false);
+static ENRICHMENT_CANCEL: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AirportSearchResult {
     pub airport: AirportData,
@@ -42,21 +78,49 @@ pub struct AirportLookupResponse {
     pub suggestions: Vec<AirportSearchResult>,
 }
 
-// Global cache for loaded airport data (lazy-loaded once)
-static AIRPORT_CACHE: OnceLock<Mutex<Option<AirportCache>>> = OnceLock::new();
+// Global cache for loaded airport data (loaded once; a failed load is remembered too, so a
+// missing or corrupt CSV is not re-read on every lookup)
+static AIRPORT_CACHE: OnceLock<Result<AirportCache, String>> = OnceLock::new();
+
+/// Fewer airports than this in the bundled CSV means a truncated or wrong file
+pub(crate) const MIN_AIRPORT_ROWS: usize = 10_000;
+
+/// OurAirports columns the loader reads, by position
+const REQUIRED_COLUMNS: &[(usize, &str)] = &[
+    (1, "ident"),
+    (2, "type"),
+    (3, "name"),
+    (4, "latitude_deg"),
+    (5, "longitude_deg"),
+    (6, "elevation_ft"),
+    (8, "iso_country"),
+    (10, "municipality"),
+    (13, "iata_code"),
+];
 
 struct AirportCache {
     airports: Vec<AirportData>,
     icao_index: HashMap<String, usize>,  // ICAO -> index
     iata_index: HashMap<String, usize>,  // IATA -> index
+    // Lowercase name/city word -> airport indices; a sorted map, so a prefix is a range scan
+    word_index: BTreeMap<String, Vec<usize>>,
 }
 
 impl AirportCache {
     fn new(airports: Vec<AirportData>) -> Self {
         let mut icao_index = HashMap::new();
         let mut iata_index = HashMap::new();
+        let mut word_index: BTreeMap<String, Vec<usize>> = BTreeMap::new();
 
         for (idx, airport) in airports.iter().enumerate() {
+            let city = airport.municipality.as_deref().unwrap_or("");
+            for word in Self::words(&airport.name).chain(Self::words(city)) {
+                let entry = word_index.entry(word).or_default();
+                if entry.last() != Some(&idx) {
+                    entry.push(idx);
+                }
+            }
+
             // Index by ICAO (ident)
             if !airport.ident.is_empty() {
                 icao_index.insert(airport.ident.to_uppercase(), idx);
@@ -69,7 +133,20 @@ impl AirportCache {
             }
         }
 
-        AirportCache { airports, icao_index, iata_index }
+        AirportCache { airports, icao_index, iata_index, word_index }
+    }
+
+    /// The closest airport with an IATA code (most with airline service have one) and its
+    /// distance in km
+    fn nearest(&self, lat: f64, lon: f64) -> Option<(&AirportData, f64)> {
+        self.airports
+            .iter()
+            .filter(|airport| airport.iata_code.is_some())
+            .filter_map(|airport| {
+                let (_, km) = crate::geo::calculate_distance(lat, lon, airport.latitude_deg?, airport.longitude_deg?);
+                Some((airport, km))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
     }
 
     fn lookup_exact(&self, code: &str) -> Option<&AirportData> {
@@ -88,12 +165,52 @@ impl AirportCache {
         None
     }
 
+    fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+    }
+
+    /// Airports with a name or city word starting with each word of the query
+    fn prefix_matches(&self, query: &str) -> BTreeSet<usize> {
+        let mut matches: Option<BTreeSet<usize>> = None;
+        for word in Self::words(query) {
+            let hits: BTreeSet<usize> = self
+                .word_index
+                .range(word.clone()..)
+                .take_while(|(key, _)| key.starts_with(&word))
+                .flat_map(|(_, indices)| indices.iter().copied())
+                .collect();
+            matches = Some(match matches {
+                Some(previous) => previous.intersection(&hits).copied().collect(),
+                None => hits,
+            });
+        }
+        matches.unwrap_or_default()
+    }
+
+    /// Airports worth scoring for a query: name/city word prefixes from the index, plus
+    /// codes within edit distance when the query is short enough to be a code. Avoids
+    /// running the scorer over every airport.
+    fn search_candidates(&self, query_upper: &str) -> BTreeSet<usize> {
+        let mut candidates = self.prefix_matches(query_upper);
+        let len = query_upper.chars().count();
+        if len <= 4 && query_upper.chars().all(|c| c.is_ascii_alphanumeric()) {
+            for (code, &idx) in self.icao_index.iter().chain(self.iata_index.iter()) {
+                if Self::levenshtein_similarity(code, query_upper) >= 0.4 {
+                    candidates.insert(idx);
+                }
+            }
+        }
+        candidates
+    }
+
     fn search_fuzzy(&self, query: &str, limit: usize) -> Vec<AirportSearchResult> {
         let query_upper = query.to_uppercase();
         let query_lower = query.to_lowercase();
         let mut results: Vec<AirportSearchResult> = Vec::new();
 
-        for airport in &self.airports {
+        for airport in self.search_candidates(&query_upper).into_iter().map(|idx| &self.airports[idx]) {
             let mut best_score: f32 = 0.0;
             let mut match_type = "fuzzy";
 
@@ -166,7 +283,7 @@ impl AirportCache {
 
         // Quick rejection for very different lengths
         let max_len = len_a.max(len_b);
-        let len_diff = (len_a as i32 - len_b as i32).abs() as usize;
+        let len_diff = len_a.abs_diff(len_b);
         if len_diff > max_len / 2 { return 0.0; }
 
         let mut prev_row: Vec<usize> = (0..=len_b).collect();
@@ -186,13 +303,217 @@ impl AirportCache {
         let distance = prev_row[len_b];
         1.0 - (distance as f32 / max_len as f32)
     }
+
+    /// Resolve an OCR location that isn't a plain airport code: coordinates, a code
+    /// embedded in text ("London (LHR)"), an airport name or a city. Returns None when
+    /// the input is already a known code. Cities with several airports are left
+    /// unresolved with candidates rather than picking one arbitrarily.
+    fn resolve_location(&self, input: &str) -> Option<crate::ocr::AirportResolution> {
+        let text = input.trim();
+        if text.is_empty() || self.lookup_exact(text).is_some() {
+            return None;
+        }
+
+        let resolution = |airport_code: Option<String>, confidence: f32, method: &str, candidates| {
+            crate::ocr::AirportResolution {
+                input: text.to_string(),
+                airport_code,
+                confidence,
+                method: method.to_string(),
+                candidates,
+            }
+        };
+
+        if let Some((lat, lon)) = parse_coordinates(text) {
+            let mut nearest: Vec<(f64, &AirportData)> = self
+                .airports
+                .iter()
+                .filter_map(|a| {
+                    let (alat, alon) = (a.latitude_deg?, a.longitude_deg?);
+                    Some((crate::geo::calculate_distance(lat, lon, alat, alon).1, a))
+                })
+                .collect();
+            nearest.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            nearest.truncate(3);
+
+            let candidates = nearest
+                .iter()
+                .map(|(km, a)| Self::candidate(a, (1.0 - km / 100.0).max(0.0) as f32))
+                .collect();
+            return Some(match nearest.first() {
+                Some((km, a)) if *km <= 5.0 => resolution(Some(Self::code_of(a)), 0.9, "coordinates", candidates),
+                Some((km, a)) if *km <= 25.0 => resolution(Some(Self::code_of(a)), 0.7, "coordinates", candidates),
+                _ => resolution(None, 0.0, "unresolved", candidates),
+            });
+        }
+
+        let normalized = normalize_place(text);
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+
+        // Code embedded in the text - trust it more when the rest of the text agrees
+        let mut embedded: Vec<(f32, &AirportData)> = text
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|t| (3..=4).contains(&t.len()) && t.chars().all(|c| c.is_ascii_uppercase()))
+            .filter_map(|t| self.lookup_exact(t).map(|a| (t, a)))
+            .map(|(token, a)| {
+                let place = normalize_place(&format!("{} {}", a.name, a.municipality.as_deref().unwrap_or("")));
+                let context_agrees = words
+                    .iter()
+                    .any(|w| *w != token.to_lowercase() && w.len() > 2 && place.split_whitespace().any(|p| p == *w));
+                (if context_agrees { 0.95 } else { 0.7 }, a)
+            })
+            .collect();
+        embedded.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        match embedded.as_slice() {
+            [(score, a)] => return Some(resolution(Some(Self::code_of(a)), *score, "embedded_code", vec![])),
+            [(best, a), (second, _), ..] if best > second => {
+                return Some(resolution(Some(Self::code_of(a)), *best, "embedded_code", vec![]));
+            }
+            _ => {}
+        }
+
+        if normalized.is_empty() {
+            return Some(resolution(None, 0.0, "unresolved", vec![]));
+        }
+
+        // Exact airport name ("Heathrow", "John F Kennedy International Airport")
+        let name_matches: Vec<&AirportData> = self
+            .airports
+            .iter()
+            .filter(|a| normalize_place(&a.name) == normalized)
+            .collect();
+        // Exact city; prefer airports with an IATA code (scheduled service)
+        let city_matches: Vec<&AirportData> = self
+            .airports
+            .iter()
+            .filter(|a| a.municipality.as_deref().map(normalize_place).as_deref() == Some(normalized.as_str()))
+            .collect();
+        let commercial: Vec<&AirportData> = city_matches.iter().copied().filter(|a| a.iata_code.is_some()).collect();
+
+        let ambiguous = |matches: &[&AirportData]| {
+            let candidates = matches.iter().take(10).map(|a| Self::candidate(a, 0.5)).collect();
+            resolution(None, 0.5, "ambiguous", candidates)
+        };
+
+        match (name_matches.as_slice(), commercial.as_slice(), city_matches.as_slice()) {
+            ([a], _, _) => return Some(resolution(Some(Self::code_of(a)), 0.95, "name", vec![])),
+            (m, _, _) if m.len() > 1 => return Some(ambiguous(m)),
+            (_, [a], _) => return Some(resolution(Some(Self::code_of(a)), 0.85, "city", vec![])),
+            (_, m, _) if m.len() > 1 => return Some(ambiguous(m)),
+            (_, _, [a]) => return Some(resolution(Some(Self::code_of(a)), 0.6, "city", vec![])),
+            (_, _, m) if m.len() > 1 => return Some(ambiguous(m)),
+            _ => {}
+        }
+
+        // Fuzzy fallback: only accept a clear winner
+        let fuzzy = self.search_fuzzy(text, 5);
+        let candidates: Vec<crate::ocr::AirportCandidate> = fuzzy
+            .iter()
+            .map(|r| Self::candidate(&r.airport, r.similarity_score))
+            .collect();
+        match fuzzy.as_slice() {
+            [best, rest @ ..]
+                if best.similarity_score >= 0.85
+                    && rest.first().is_none_or(|r| best.similarity_score - r.similarity_score >= 0.1) =>
+            {
+                Some(resolution(
+                    Some(Self::code_of(&best.airport)),
+                    best.similarity_score * 0.9,
+                    "name",
+                    candidates,
+                ))
+            }
+            [] => Some(resolution(None, 0.0, "unresolved", candidates)),
+            _ => Some(resolution(None, 0.4, "ambiguous", candidates)),
+        }
+    }
+
+    /// IATA code when the airport has one, otherwise its ICAO ident
+    fn code_of(airport: &AirportData) -> String {
+        airport.iata_code.clone().unwrap_or_else(|| airport.ident.clone())
+    }
+
+    fn candidate(airport: &AirportData, score: f32) -> crate::ocr::AirportCandidate {
+        crate::ocr::AirportCandidate {
+            code: Self::code_of(airport),
+            name: airport.name.clone(),
+            city: airport.municipality.clone(),
+            country: airport.iso_country.clone(),
+            score,
+        }
+    }
+}
+
+/// Lowercase, strip punctuation and generic words ("airport", "international") for name comparison
+fn normalize_place(value: &str) -> String {
+    const GENERIC: [&str; 5] = ["airport", "international", "intl", "apt", "aeropuerto"];
+    value
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty() && !GENERIC.contains(w))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse "lat, lon" or "lat lon" decimal coordinates
+fn parse_coordinates(value: &str) -> Option<(f64, f64)> {
+    let parts: Vec<&str> = value
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .collect();
+    let [lat, lon] = parts.as_slice() else {
+        return None;
+    };
+    let (lat, lon): (f64, f64) = (lat.parse().ok()?, lon.parse().ok()?);
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// OCR post-processing: when the extracted departure/arrival isn't an airport code,
+/// resolve it against the bundled CSV. Confident matches replace the field with the
+/// code; the resolution (with candidates when ambiguous) is attached either way.
+pub(crate) fn resolve_ocr_airports(
+    app_handle: &tauri::AppHandle,
+    result: &mut crate::ocr::OcrFlightResult,
+) -> Result<(), String> {
+    let cache = airport_cache(app_handle).map_err(|e| e.to_string())?;
+
+    let departure = result.departure_airport.as_deref().and_then(|v| cache.resolve_location(v));
+    let arrival = result.arrival_airport.as_deref().and_then(|v| cache.resolve_location(v));
+
+    if let Some(code) = departure.as_ref().and_then(|r| r.airport_code.clone()) {
+        result.departure_airport = Some(code);
+    }
+    if let Some(code) = arrival.as_ref().and_then(|r| r.airport_code.clone()) {
+        result.arrival_airport = Some(code);
+    }
+    result.departure_resolution = departure;
+    result.arrival_resolution = arrival;
+
+    Ok(())
 }
 
 /// Load airport data from local CSV file (OurAirports format)
-fn load_airports_from_csv(csv_path: &PathBuf) -> Result<Vec<AirportData>> {
-    let mut airports = Vec::new();
-    let mut reader = csv::Reader::from_path(csv_path)?;
+pub(crate) fn load_airports_from_csv(csv_path: &PathBuf) -> Result<Vec<AirportData>> {
+    parse_airports_csv(csv::Reader::from_path(csv_path)?)
+}
 
+/// Parse OurAirports CSV rows, keeping airports with runways, an identifier and coordinates
+/// Fails when the header does not have the OurAirports columns where the parser expects them.
+fn parse_airports_csv<R: std::io::Read>(mut reader: csv::Reader<R>) -> Result<Vec<AirportData>> {
+    let headers = reader.headers()?.clone();
+    for (index, expected) in REQUIRED_COLUMNS {
+        let found = headers.get(*index).map(str::trim).unwrap_or("");
+        if found != *expected {
+            anyhow::bail!(
+                "Not an OurAirports airports.csv: column {} is '{}', expected '{}'",
+                index + 1,
+                found,
+                expected
+            );
+        }
+    }
+
+    let mut airports = Vec::new();
     for result in reader.records() {
         let record = match result {
             Ok(r) => r,
@@ -241,6 +562,7 @@ fn load_airports_from_csv(csv_path: &PathBuf) -> Result<Vec<AirportData>> {
             longitude_deg,
             municipality: record.get(10).map(|s| s.to_string()),
             iso_country: record.get(8).map(|s| s.to_string()),
+            elevation_ft: record.get(6).and_then(|s| s.trim().parse::<f64>().ok()).map(|ft| ft.round() as i32),
         });
     }
 
@@ -259,50 +581,7 @@ async fn fetch_ourairports_data() -> Result<Vec<AirportData>> {
     let response = client.get(OURAIRPORTS_CSV_URL).send().await?;
     let text = response.text().await?;
 
-    let mut airports = Vec::new();
-    let mut reader = csv::Reader::from_reader(text.as_bytes());
-
-    for result in reader.records() {
-        let record = match result {
-            Ok(r) => r,
-            Err(_) => continue,
-        };
-
-        let airport_type = record.get(2).unwrap_or("");
-        let valid_types = ["large_airport", "medium_airport", "small_airport"];
-        if !valid_types.contains(&airport_type) {
-            continue;
-        }
-
-        let ident = record.get(1).unwrap_or("").trim().to_string();
-        let iata_code = record.get(13).and_then(|s| {
-            let trimmed = s.trim();
-            if trimmed.is_empty() { None } else { Some(trimmed.to_string()) }
-        });
-
-        if ident.is_empty() && iata_code.is_none() {
-            continue;
-        }
-
-        let latitude_deg = record.get(4).and_then(|s| s.trim().parse::<f64>().ok());
-        let longitude_deg = record.get(5).and_then(|s| s.trim().parse::<f64>().ok());
-
-        if latitude_deg.is_none() || longitude_deg.is_none() {
-            continue;
-        }
-
-        airports.push(AirportData {
-            ident,
-            iata_code,
-            name: record.get(3).unwrap_or("").to_string(),
-            latitude_deg,
-            longitude_deg,
-            municipality: record.get(10).map(|s| s.to_string()),
-            iso_country: record.get(8).map(|s| s.to_string()),
-        });
-    }
-
-    Ok(airports)
+    parse_airports_csv(csv::Reader::from_reader(text.as_bytes()))
 }
 
 #[tauri::command]
@@ -315,6 +594,8 @@ pub async fn enrich_airport_data(
         enriched_count: 0,
         new_airports: 0,
         errors: Vec::new(),
+        cancelled: false,
+        geocoded_count: 0,
     };
 
     // Try to load from local CSV file first using fallback paths
@@ -331,70 +612,365 @@ pub async fn enrich_airport_data(
         }
     };
 
-    result.total_processed = airports.len();
+    if ENRICHMENT_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Airport enrichment is already running".to_string());
+    }
+    ENRICHMENT_CANCEL.store(false, Ordering::SeqCst);
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut outcome = write_enrichment(&airports, &mut result, &app_handle, &state);
 
-    for airport in airports {
-        let icao_code = &airport.ident;
-        let iata_code = airport.iata_code.as_deref();
+    // Airports the CSV could not place go to the online geocoder when it is configured
+    if outcome.is_ok() && !result.cancelled {
+        let api_key = match state.db.lock() {
+            Ok(db) => geocoding_api_key(&db),
+            Err(e) => {
+                outcome = Err(e.to_string());
+                None
+            }
+        };
+        if let Some(api_key) = api_key {
+            outcome = geocode_missing_airports(&api_key, &mut result, &state).await;
+        }
+    }
 
-        // Check if airport already exists by IATA or ICAO code
-        let existing = db.conn
-            .query_row(
-                "SELECT id, latitude, longitude FROM airports WHERE iata_code = ?1 OR icao_code = ?2",
-                rusqlite::params![iata_code.unwrap_or(""), icao_code],
-                |row| {
-                    let id: String = row.get(0)?;
-                    let lat: Option<f64> = row.get(1)?;
-                    let lon: Option<f64> = row.get(2)?;
-                    Ok((id, lat, lon))
-                }
-            )
-            .optional()
-            .map_err(|e| format!("Database error: {}", e))?;
-
-        match existing {
-            Some((id, existing_lat, existing_lon)) => {
-                // Update if coordinates are missing
-                if existing_lat.is_none() || existing_lon.is_none() {
-                    if let (Some(lat), Some(lon)) = (airport.latitude_deg, airport.longitude_deg) {
-                        match db.conn.execute(
-                            "UPDATE airports SET latitude = ?1, longitude = ?2, name = ?3, city = ?4, country = ?5 WHERE id = ?6",
-                            rusqlite::params![lat, lon, airport.name, airport.municipality, airport.iso_country, id],
-                        ) {
-                            Ok(_) => result.enriched_count += 1,
-                            Err(e) => result.errors.push(format!("Failed to update {}: {}", icao_code, e)),
+    ENRICHMENT_RUNNING.store(false, Ordering::SeqCst);
+    outcome?;
+
+    Ok(result)
+}
+
+/// Request that a running enrichment stop after the current chunk
+/// Returns false when no enrichment is running
+#[tauri::command]
+pub fn cancel_enrichment() -> Result<bool, String> {
+    let running = ENRICHMENT_RUNNING.load(Ordering::SeqCst);
+    if running {
+        ENRICHMENT_CANCEL.store(true, Ordering::SeqCst);
+    }
+    Ok(running)
+}
+
+/// Write airports in chunked transactions, emitting progress and honouring cancellation
+/// between chunks so a cancelled run never leaves a transaction open
+fn write_enrichment(
+    airports: &[AirportData],
+    result: &mut EnrichmentResult,
+    app_handle: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+) -> Result<(), String> {
+    let total = airports.len();
+    let started = std::time::Instant::now();
+    let progress = |current: usize, result: &EnrichmentResult, airport: Option<&str>, complete: bool| {
+        let elapsed = started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 { current as f64 / elapsed } else { 0.0 };
+        let eta_seconds = (rate > 0.0 && !complete).then(|| ((total - current) as f64 / rate).ceil() as u64);
+        let _ = app_handle.emit("airport-enrichment:progress", EnrichmentProgress {
+            current,
+            total,
+            enriched: result.enriched_count,
+            new_airports: result.new_airports,
+            failed: result.errors.len(),
+            airport: airport.map(str::to_string),
+            airports_per_second: rate,
+            eta_seconds,
+            complete,
+            cancelled: result.cancelled,
+        });
+    };
+
+    let mut processed = 0;
+    for chunk in airports.chunks(ENRICHMENT_CHUNK_SIZE) {
+        if ENRICHMENT_CANCEL.load(Ordering::SeqCst) {
+            result.cancelled = true;
+            break;
+        }
+
+        // The lock is released between chunks so other commands can run
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        // Dropping the transaction without commit rolls the chunk back
+        let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+        let mut write_chunk = || -> Result<(), String> {
+            for airport in chunk {
+                let mut changed = false;
+                let icao_code = &airport.ident;
+                let iata_code = airport.iata_code.as_deref();
+
+                // Check if airport already exists by IATA or ICAO code
+                let existing = db.conn
+                    .query_row(
+                        "SELECT id, latitude, longitude FROM airports WHERE iata_code = ?1 OR icao_code = ?2",
+                        rusqlite::params![iata_code.unwrap_or(""), icao_code],
+                        |row| {
+                            let id: String = row.get(0)?;
+                            let lat: Option<f64> = row.get(1)?;
+                            let lon: Option<f64> = row.get(2)?;
+                            Ok((id, lat, lon))
+                        }
+                    )
+                    .optional()
+                    .map_err(|e| format!("Database error: {}", e))?;
+
+                match existing {
+                    Some((id, existing_lat, existing_lon)) => {
+                        // Update if coordinates are missing
+                        if existing_lat.is_none() || existing_lon.is_none() {
+                            if let (Some(lat), Some(lon)) = (airport.latitude_deg, airport.longitude_deg) {
+                                match db.conn.execute(
+                                    "UPDATE airports SET latitude = ?1, longitude = ?2, name = ?3, city = ?4, country = ?5, coordinate_source = 'csv'
+                                     WHERE id = ?6",
+                                    rusqlite::params![lat, lon, airport.name, airport.municipality, airport.iso_country, id],
+                                ) {
+                                    Ok(_) => {
+                                        result.enriched_count += 1;
+                                        changed = true;
+                                    }
+                                    Err(e) => result.errors.push(format!("Failed to update {}: {}", icao_code, e)),
+                                }
+                            }
+                        }
+                        // Elevation is filled in separately so airports added before it was tracked get it too
+                        if let Some(elevation) = airport.elevation_ft {
+                            match db.conn.execute(
+                                "UPDATE airports SET elevation_ft = ?1 WHERE id = ?2 AND elevation_ft IS NULL",
+                                rusqlite::params![elevation, id],
+                            ) {
+                                Ok(rows) if rows > 0 && !changed => {
+                                    result.enriched_count += 1;
+                                    changed = true;
+                                }
+                                Ok(_) => {}
+                                Err(e) => result.errors.push(format!("Failed to update {}: {}", icao_code, e)),
+                            }
+                        }
+                    }
+                    None => {
+                        // Insert new airport (needs valid coordinates)
+                        if let (Some(lat), Some(lon)) = (airport.latitude_deg, airport.longitude_deg) {
+                            match db.conn.execute(
+                                "INSERT INTO airports (id, icao_code, iata_code, name, city, country, latitude, longitude, elevation_ft, coordinate_source)
+                                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'csv')",
+                                rusqlite::params![
+                                    uuid::Uuid::new_v4().to_string(),
+                                    icao_code,
+                                    iata_code,
+                                    airport.name,
+                                    airport.municipality,
+                                    airport.iso_country,
+                                    lat,
+                                    lon,
+                                    airport.elevation_ft
+                                ],
+                            ) {
+                                Ok(_) => {
+                                    result.new_airports += 1;
+                                    changed = true;
+                                }
+                                Err(e) => result.errors.push(format!("Failed to insert {}: {}", icao_code, e)),
+                            }
                         }
                     }
                 }
+
+                processed += 1;
+                if changed || processed % ENRICHMENT_PROGRESS_EVERY == 0 {
+                    progress(processed, result, Some(icao_code), false);
+                }
             }
+            Ok(())
+        };
+
+        write_chunk()?;
+        tx.commit().map_err(|e| e.to_string())?;
+        // Only committed rows count, so a cancelled run reports what was actually written
+        result.total_processed += chunk.len();
+    }
+
+    progress(processed, result, None, true);
+    Ok(())
+}
+
+// ===== ONLINE GEOCODING FALLBACK =====
+
+const GEOCODE_PROVIDER: &str = "opencage";
+/// OpenCage's free tier allows one request per second
+const GEOCODE_MIN_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1100);
+/// Places the geocoder could not find are retried after this many days
+const GEOCODE_NEGATIVE_CACHE_DAYS: i64 = 90;
+
+/// API key for the geocoding fallback, or None when the fallback is off
+/// Settings: geocoding_fallback_enabled ("true") and geocoding_api_key (or OPENCAGE_API_KEY)
+fn geocoding_api_key(db: &crate::database::Database) -> Option<String> {
+    let enabled = db.get_setting("geocoding_fallback_enabled").ok().flatten();
+    if enabled.as_deref() != Some("true") {
+        return None;
+    }
+    db.get_setting("geocoding_api_key")
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var("OPENCAGE_API_KEY").ok())
+        .filter(|key| !key.trim().is_empty())
+}
+
+/// Free-text query for an airport, normalized so it doubles as the cache key
+fn geocode_query(name: &str, city: Option<&str>, country: Option<&str>) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for part in [Some(name), city, country].into_iter().flatten() {
+        let part = part.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if !part.is_empty() && !parts.contains(&part) {
+            parts.push(part);
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Cached answer for a query: Some(Some(coords)) when found, Some(None) for a recent miss,
+/// None when the geocoder should be asked
+fn cached_geocode(conn: &rusqlite::Connection, query: &str) -> rusqlite::Result<Option<Option<(f64, f64)>>> {
+    let cached = conn
+        .query_row(
+            "SELECT found, latitude, longitude, created_at >= datetime('now', ?2)
+             FROM geocode_cache WHERE query = ?1",
+            rusqlite::params![query, format!("-{} days", GEOCODE_NEGATIVE_CACHE_DAYS)],
+            |row| {
+                Ok((
+                    row.get::<_, bool>(0)?,
+                    row.get::<_, Option<f64>>(1)?,
+                    row.get::<_, Option<f64>>(2)?,
+                    row.get::<_, bool>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    Ok(match cached {
+        Some((true, Some(lat), Some(lon), _)) => Some(Some((lat, lon))),
+        Some((false, _, _, true)) => Some(None),
+        _ => None,
+    })
+}
+
+/// Ask the geocoder for a place; Ok(None) when it has no match
+async fn request_geocode(api_key: &str, query: &str) -> Result<Option<(f64, f64, String)>, String> {
+    let response = reqwest::Client::new()
+        .get("https://api.opencagedata.com/geocode/v1/json")
+        .query(&[("q", query), ("key", api_key), ("limit", "1"), ("no_annotations", "1")])
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("Geocoding request failed: {}", e))?;
+
+    match response.status().as_u16() {
+        200 => {}
+        401 | 403 => return Err("Geocoding API key was rejected".to_string()),
+        402 | 429 => return Err("Geocoding quota or rate limit reached".to_string()),
+        status => return Err(format!("Geocoding service returned {}", status)),
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid geocoding response: {}", e))?;
+    let Some(first) = body["results"].get(0) else {
+        return Ok(None);
+    };
+    match (first["geometry"]["lat"].as_f64(), first["geometry"]["lng"].as_f64()) {
+        (Some(lat), Some(lon)) => Ok(Some((lat, lon, first["formatted"].as_str().unwrap_or("").to_string()))),
+        _ => Ok(None),
+    }
+}
+
+/// Geocode airports still missing coordinates after CSV enrichment
+///
+/// Results (including misses) are cached in geocode_cache and requests are spaced to stay
+/// under the provider's rate limit. Coordinates written here are marked
+/// coordinate_source = 'geocoded'. A rejected key or exhausted quota stops the pass.
+async fn geocode_missing_airports(
+    api_key: &str,
+    result: &mut EnrichmentResult,
+    state: &State<'_, AppState>,
+) -> Result<(), String> {
+    let targets: Vec<(String, String, Option<String>)> = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let mut stmt = db
+            .conn
+            .prepare(
+                "SELECT id, COALESCE(icao_code, iata_code, id), name, city, country
+                 FROM airports WHERE latitude IS NULL OR longitude IS NULL",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                let name: String = row.get(2)?;
+                let city: Option<String> = row.get(3)?;
+                let country: Option<String> = row.get(4)?;
+                Ok((row.get(0)?, row.get(1)?, geocode_query(&name, city.as_deref(), country.as_deref())))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut last_request: Option<std::time::Instant> = None;
+    for (id, code, query) in targets {
+        if ENRICHMENT_CANCEL.load(Ordering::SeqCst) {
+            result.cancelled = true;
+            break;
+        }
+        let Some(query) = query else { continue };
+
+        let cached = {
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            cached_geocode(&db.conn, &query).map_err(|e| e.to_string())?
+        };
+        let coordinates = match cached {
+            Some(hit) => hit,
             None => {
-                // Insert new airport (needs valid coordinates)
-                if let (Some(lat), Some(lon)) = (airport.latitude_deg, airport.longitude_deg) {
-                    match db.conn.execute(
-                        "INSERT INTO airports (id, icao_code, iata_code, name, city, country, latitude, longitude)
-                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                if let Some(wait) = last_request.and_then(|t| GEOCODE_MIN_INTERVAL.checked_sub(t.elapsed())) {
+                    tokio::time::sleep(wait).await;
+                }
+                last_request = Some(std::time::Instant::now());
+
+                let answer = match request_geocode(api_key, &query).await {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        result.errors.push(format!("Geocoding stopped at {}: {}", code, e));
+                        break;
+                    }
+                };
+                let db = state.db.lock().map_err(|e| e.to_string())?;
+                db.conn
+                    .execute(
+                        "INSERT OR REPLACE INTO geocode_cache (query, found, latitude, longitude, display_name, provider, created_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
                         rusqlite::params![
-                            uuid::Uuid::new_v4().to_string(),
-                            icao_code,
-                            iata_code,
-                            airport.name,
-                            airport.municipality,
-                            airport.iso_country,
-                            lat,
-                            lon
+                            query,
+                            answer.is_some(),
+                            answer.as_ref().map(|a| a.0),
+                            answer.as_ref().map(|a| a.1),
+                            answer.as_ref().map(|a| a.2.clone()),
+                            GEOCODE_PROVIDER
                         ],
-                    ) {
-                        Ok(_) => result.new_airports += 1,
-                        Err(e) => result.errors.push(format!("Failed to insert {}: {}", icao_code, e)),
-                    }
-                }
+                    )
+                    .map_err(|e| e.to_string())?;
+                answer.map(|(lat, lon, _)| (lat, lon))
             }
+        };
+
+        if let Some((lat, lon)) = coordinates {
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            let updated = db
+                .conn
+                .execute(
+                    "UPDATE airports SET latitude = ?1, longitude = ?2, coordinate_source = 'geocoded'
+                     WHERE id = ?3 AND (latitude IS NULL OR longitude IS NULL)",
+                    rusqlite::params![lat, lon, id],
+                )
+                .map_err(|e| e.to_string())?;
+            result.geocoded_count += updated;
         }
     }
 
-    Ok(result)
+    Ok(())
 }
 
 #[tauri::command]
@@ -432,7 +1008,7 @@ pub fn get_total_airports_count(
 }
 
 /// Find the airports.csv file, trying multiple paths for dev and production
-fn find_airports_csv(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+pub(crate) fn find_airports_csv(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
     let possible_paths = vec![
         // Production: bundled resources via Tauri
         app_handle.path()
@@ -447,34 +1023,90 @@ fn find_airports_csv(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
         Some(std::path::PathBuf::from("src-tauri/resources/airports.csv")),
     ];
 
-    for path_opt in possible_paths {
-        if let Some(path) = path_opt {
-            if path.exists() {
-                eprintln!("[AirportCache] Found airports.csv at: {:?}", path);
-                return Ok(path);
-            }
+    for path in possible_paths.into_iter().flatten() {
+        if path.exists() {
+            tracing::info!("[AirportCache] Found airports.csv at: {:?}", path);
+            return Ok(path);
         }
     }
 
     Err("airports.csv not found in any known location".to_string())
 }
 
-// Helper to ensure the airport cache is initialized
-fn get_or_init_cache(app_handle: &tauri::AppHandle) -> Result<std::sync::MutexGuard<'static, Option<AirportCache>>, String> {
-    let mutex = AIRPORT_CACHE.get_or_init(|| Mutex::new(None));
-    let mut guard = mutex.lock().map_err(|e| format!("Cache lock poisoned: {}", e))?;
+/// Where the bundled CSV is, found once; lookups without an app handle load the cache from here
+static AIRPORT_CSV_PATH: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+
+/// Validate and index the bundled CSV
+fn load_airport_cache(resource_path: PathBuf) -> Result<AirportCache, String> {
+    let airports = load_airports_from_csv(&resource_path)
+        .map_err(|e| format!("Failed to load {}: {}", resource_path.display(), e))?;
+    if airports.len() < MIN_AIRPORT_ROWS {
+        return Err(format!(
+            "Only {} airports in {} (expected at least {}); the file looks truncated",
+            airports.len(),
+            resource_path.display(),
+            MIN_AIRPORT_ROWS
+        ));
+    }
+    Ok(AirportCache::new(airports))
+}
 
-    if guard.is_none() {
-        let resource_path = find_airports_csv(app_handle)?;
+/// The cache, loaded on first use from the CSV at `path`. Concurrent callers wait for the
+/// one load, so a lookup gives the same answer whether or not the startup preload has finished.
+fn init_airport_cache(path: impl FnOnce() -> Result<PathBuf, String>) -> &'static Result<AirportCache, String> {
+    AIRPORT_CACHE.get_or_init(|| {
+        let loaded = path().and_then(load_airport_cache);
+        match &loaded {
+            Ok(cache) => tracing::info!("[AirportCache] Loaded {} airports from CSV", cache.airports.len()),
+            Err(e) => tracing::error!("[AirportCache] Airport data unavailable: {}", e),
+        }
+        loaded
+    })
+}
 
-        let airports = load_airports_from_csv(&resource_path)
-            .map_err(|e| format!("Failed to load airports.csv: {}", e))?;
+/// The airport cache, loading it on first use
+fn airport_cache(app_handle: &tauri::AppHandle) -> Result<&'static AirportCache, CommandError> {
+    init_airport_cache(|| locate_airport_csv(app_handle))
+        .as_ref()
+        .map_err(|e| {
+            CommandError::Unavailable(format!(
+                "Bundled airport data is unavailable ({}). Reinstall the app to restore it.",
+                e
+            ))
+        })
+}
 
-        eprintln!("[AirportCache] Loaded {} airports from CSV", airports.len());
-        *guard = Some(AirportCache::new(airports));
-    }
+/// The cache for code lookups that have no app handle (distance calculations). Without a
+/// located CSV (tests, before setup) there is no cache at all, never a partly loaded one.
+fn shared_airport_cache() -> Option<&'static AirportCache> {
+    let path = AIRPORT_CSV_PATH.get()?.clone();
+    init_airport_cache(|| path).as_ref().ok()
+}
+
+/// Find the bundled CSV once; called during setup, before anything computes a distance
+pub(crate) fn locate_airport_csv(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    AIRPORT_CSV_PATH.get_or_init(|| find_airports_csv(app_handle)).clone()
+}
+
+/// Load and validate the airport CSV at startup so problems show up in the log immediately
+pub(crate) fn preload_airport_cache(app_handle: &tauri::AppHandle) {
+    let _ = airport_cache(app_handle);
+}
+
+/// Number of airports loaded, or why the bundled CSV could not be used
+pub(crate) fn airport_cache_status(app_handle: &tauri::AppHandle) -> Result<usize, CommandError> {
+    airport_cache(app_handle).map(|cache| cache.airports.len())
+}
+
+/// Coordinates of an airport by ICAO or IATA code from the bundled CSV
+pub(crate) fn cached_airport_coords(code: &str) -> Option<(f64, f64)> {
+    let airport = shared_airport_cache()?.lookup_exact(code.trim())?;
+    Some((airport.latitude_deg?, airport.longitude_deg?))
+}
 
-    Ok(guard)
+/// The closest airport with an IATA code to a point and its distance in km, from the bundled CSV
+pub(crate) fn nearest_cached_airport(lat: f64, lon: f64) -> Option<(AirportData, f64)> {
+    shared_airport_cache()?.nearest(lat, lon).map(|(airport, km)| (airport.clone(), km))
 }
 
 /// Lookup an airport by ICAO or IATA code (exact match with fuzzy suggestions)
@@ -482,9 +1114,8 @@ fn get_or_init_cache(app_handle: &tauri::AppHandle) -> Result<std::sync::MutexGu
 pub fn lookup_airport(
     app_handle: tauri::AppHandle,
     code: String,
-) -> Result<AirportLookupResponse, String> {
-    let guard = get_or_init_cache(&app_handle)?;
-    let cache = guard.as_ref().ok_or("Airport cache not initialized")?;
+) -> Result<AirportLookupResponse, CommandError> {
+    let cache = airport_cache(&app_handle)?;
 
     let code = code.trim();
     if code.is_empty() {
@@ -516,9 +1147,8 @@ pub fn search_airports_csv(
     app_handle: tauri::AppHandle,
     query: String,
     limit: Option<usize>,
-) -> Result<Vec<AirportSearchResult>, String> {
-    let guard = get_or_init_cache(&app_handle)?;
-    let cache = guard.as_ref().ok_or("Airport cache not initialized")?;
+) -> Result<Vec<AirportSearchResult>, CommandError> {
+    let cache = airport_cache(&app_handle)?;
 
     let query = query.trim();
     if query.is_empty() {
@@ -535,9 +1165,8 @@ pub fn search_airports_csv(
 #[tauri::command]
 pub fn get_csv_airport_count(
     app_handle: tauri::AppHandle,
-) -> Result<usize, String> {
-    let guard = get_or_init_cache(&app_handle)?;
-    let cache = guard.as_ref().ok_or("Airport cache not initialized")?;
+) -> Result<usize, CommandError> {
+    let cache = airport_cache(&app_handle)?;
     Ok(cache.airports.len())
 }
 
@@ -546,9 +1175,8 @@ pub fn get_csv_airport_count(
 #[tauri::command]
 pub fn get_all_csv_airports(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<AirportData>, String> {
-    let guard = get_or_init_cache(&app_handle)?;
-    let cache = guard.as_ref().ok_or("Airport cache not initialized")?;
+) -> Result<Vec<AirportData>, CommandError> {
+    let cache = airport_cache(&app_handle)?;
     Ok(cache.airports.clone())
 }
 
@@ -557,17 +1185,14 @@ pub fn get_all_csv_airports(
 pub fn get_csv_airports_by_codes(
     app_handle: tauri::AppHandle,
     codes: Vec<String>,
-) -> Result<Vec<AirportData>, String> {
-    let guard = get_or_init_cache(&app_handle)?;
-    let cache = guard.as_ref().ok_or("Airport cache not initialized")?;
-
-    let codes_upper: Vec<String> = codes.iter().map(|c| c.to_uppercase()).collect();
-
-    let results: Vec<AirportData> = cache.airports.iter()
-        .filter(|a| {
-            codes_upper.contains(&a.ident.to_uppercase()) ||
-            a.iata_code.as_ref().map(|c| codes_upper.contains(&c.to_uppercase())).unwrap_or(false)
-        })
+) -> Result<Vec<AirportData>, CommandError> {
+    let cache = airport_cache(&app_handle)?;
+
+    let mut seen = HashSet::new();
+    let results: Vec<AirportData> = codes
+        .iter()
+        .filter_map(|code| cache.lookup_exact(code.trim()))
+        .filter(|airport| seen.insert(airport.ident.clone()))
         .cloned()
         .collect();
 
@@ -585,7 +1210,7 @@ pub fn get_visited_airport_codes(
 
     // Get departure airports
     let mut stmt = db.conn
-        .prepare("SELECT DISTINCT departure_airport FROM flights WHERE departure_airport IS NOT NULL AND departure_airport != ''")
+        .prepare("SELECT DISTINCT departure_airport FROM flights WHERE deleted_at IS NULL AND departure_airport IS NOT NULL AND departure_airport != ''")
         .map_err(|e| e.to_string())?;
 
     let dep_codes: Vec<String> = stmt
@@ -600,7 +1225,7 @@ pub fn get_visited_airport_codes(
 
     // Get arrival airports
     let mut stmt = db.conn
-        .prepare("SELECT DISTINCT arrival_airport FROM flights WHERE arrival_airport IS NOT NULL AND arrival_airport != ''")
+        .prepare("SELECT DISTINCT arrival_airport FROM flights WHERE deleted_at IS NULL AND arrival_airport IS NOT NULL AND arrival_airport != ''")
         .map_err(|e| e.to_string())?;
 
     let arr_codes: Vec<String> = stmt
@@ -626,6 +1251,19 @@ pub struct SmartImportResult {
     pub not_found_in_csv: Vec<String>,
 }
 
+/// Upsert airports from the user's own CSV (e.g. an OurAirports export). `mapping` names the
+/// header for each field; fields it leaves out are found by their usual header names.
+#[tauri::command]
+pub fn import_airports_from_csv(
+    path: String,
+    mapping: Option<AirportColumnMapping>,
+    state: State<'_, AppState>,
+) -> Result<AirportImportReport, CommandError> {
+    let file = std::fs::File::open(&path)
+        .map_err(|e| CommandError::Validation(format!("Cannot open {}: {}", path, e)))?;
+    Ok(crate::airport_import::import_airports(&state.db, file, &mapping.unwrap_or_default())?)
+}
+
 /// Import only airports that appear in flight logs (smart import)
 #[tauri::command]
 pub fn import_visited_airports(
@@ -639,7 +1277,7 @@ pub fn import_visited_airports(
         let mut codes: Vec<String> = Vec::new();
 
         let mut stmt = db.conn
-            .prepare("SELECT DISTINCT departure_airport FROM flights WHERE departure_airport IS NOT NULL AND departure_airport != ''")
+            .prepare("SELECT DISTINCT departure_airport FROM flights WHERE deleted_at IS NULL AND departure_airport IS NOT NULL AND departure_airport != ''")
             .map_err(|e| e.to_string())?;
 
         codes.extend(
@@ -651,7 +1289,7 @@ pub fn import_visited_airports(
         drop(stmt);
 
         let mut stmt = db.conn
-            .prepare("SELECT DISTINCT arrival_airport FROM flights WHERE arrival_airport IS NOT NULL AND arrival_airport != ''")
+            .prepare("SELECT DISTINCT arrival_airport FROM flights WHERE deleted_at IS NULL AND arrival_airport IS NOT NULL AND arrival_airport != ''")
             .map_err(|e| e.to_string())?;
 
         codes.extend(
@@ -668,8 +1306,7 @@ pub fn import_visited_airports(
     let codes_found = visited_codes.len();
 
     // Get CSV cache
-    let guard = get_or_init_cache(&app_handle)?;
-    let cache = guard.as_ref().ok_or("Airport cache not initialized")?;
+    let cache = airport_cache(&app_handle).map_err(|e| e.to_string())?;
 
     let mut already_in_db = 0;
     let mut imported = 0;
@@ -701,8 +1338,8 @@ pub fn import_visited_airports(
             Some(a) => {
                 if let (Some(lat), Some(lon)) = (a.latitude_deg, a.longitude_deg) {
                     let result = db.conn.execute(
-                        "INSERT INTO airports (id, icao_code, iata_code, name, city, country, latitude, longitude)
-                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        "INSERT INTO airports (id, icao_code, iata_code, name, city, country, latitude, longitude, elevation_ft)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
                         rusqlite::params![
                             uuid::Uuid::new_v4().to_string(),
                             &a.ident,
@@ -711,7 +1348,8 @@ pub fn import_visited_airports(
                             &a.municipality,
                             &a.iso_country,
                             lat,
-                            lon
+                            lon,
+                            a.elevation_ft
                         ],
                     );
 
@@ -733,3 +1371,164 @@ pub fn import_visited_airports(
         not_found_in_csv,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn airport(ident: &str, iata: Option<&str>, name: &str, city: &str, lat: f64, lon: f64) -> AirportData {
+        AirportData {
+            ident: ident.to_string(),
+            iata_code: iata.map(|c| c.to_string()),
+            name: name.to_string(),
+            latitude_deg: Some(lat),
+            longitude_deg: Some(lon),
+            municipality: Some(city.to_string()),
+            iso_country: Some("GB".to_string()),
+            elevation_ft: None,
+        }
+    }
+
+    fn cache() -> AirportCache {
+        AirportCache::new(vec![
+            airport("EGLL", Some("LHR"), "London Heathrow Airport", "London", 51.4706, -0.461941),
+            airport("EGKK", Some("LGW"), "London Gatwick Airport", "London", 51.148102, -0.190278),
+            airport("EGCC", Some("MAN"), "Manchester Airport", "Manchester", 53.349375, -2.279521),
+        ])
+    }
+
+    #[test]
+    fn test_parse_airports_csv_validates_header() {
+        let header = "id,ident,type,name,latitude_deg,longitude_deg,elevation_ft,continent,iso_country,iso_region,municipality,scheduled_service,icao_code,iata_code\n";
+        let rows = "1,EGLL,large_airport,London Heathrow Airport,51.47,-0.46,83,EU,GB,GB-ENG,London,yes,EGLL,LHR\n\
+                    2,EG01,heliport,Some Heliport,51.0,-0.1,0,EU,GB,GB-ENG,London,no,,\n";
+        let airports = parse_airports_csv(csv::Reader::from_reader(format!("{}{}", header, rows).as_bytes())).unwrap();
+        assert_eq!(airports.len(), 1);
+        assert_eq!(airports[0].iata_code.as_deref(), Some("LHR"));
+        assert_eq!(airports[0].elevation_ft, Some(83));
+
+        let shifted = "id,type,ident,name,latitude_deg,longitude_deg\n1,large_airport,EGLL,Heathrow,51.47,-0.46\n";
+        let error = parse_airports_csv(csv::Reader::from_reader(shifted.as_bytes())).unwrap_err();
+        assert!(error.to_string().contains("expected 'ident'"));
+        assert!(parse_airports_csv(csv::Reader::from_reader("".as_bytes())).is_err());
+    }
+
+    #[test]
+    fn test_search_uses_word_prefixes_and_codes() {
+        let cache = cache();
+
+        let names: Vec<String> = cache.search_fuzzy("london heath", 10).into_iter().map(|r| r.airport.ident).collect();
+        assert_eq!(names, vec!["EGLL"]);
+
+        let city = cache.search_fuzzy("manch", 10);
+        assert_eq!(city[0].airport.ident, "EGCC");
+        assert_eq!(city[0].match_type, "name_match");
+
+        let code = cache.search_fuzzy("LGW", 10);
+        assert_eq!(code[0].match_type, "exact_iata");
+        assert_eq!(code[0].airport.ident, "EGKK");
+        assert!(cache.search_fuzzy("zzzz zzzz", 10).is_empty());
+    }
+
+    #[test]
+    fn test_nearest_airport() {
+        let cache = cache();
+        // Crawley is closest to Gatwick
+        let (nearest, km) = cache.nearest(51.1091, -0.1872).unwrap();
+        assert_eq!(nearest.ident, "EGKK");
+        assert!(km < 5.0);
+        assert!(AirportCache::new(vec![airport("EG01", None, "Strip", "Crawley", 51.1, -0.18)]).nearest(51.1, -0.18).is_none());
+    }
+
+    #[test]
+    fn test_resolve_location_by_name_city_and_code() {
+        let cache = cache();
+
+        assert!(cache.resolve_location("LHR").is_none());
+
+        let named = cache.resolve_location("London Heathrow").unwrap();
+        assert_eq!(named.airport_code.as_deref(), Some("LHR"));
+
+        let city = cache.resolve_location("MANCHESTER").unwrap();
+        assert_eq!(city.airport_code.as_deref(), Some("MAN"));
+
+        let embedded = cache.resolve_location("London (LGW)").unwrap();
+        assert_eq!(embedded.airport_code.as_deref(), Some("LGW"));
+        assert!(embedded.confidence > 0.9);
+    }
+
+    #[test]
+    fn test_resolve_location_ambiguous_city_returns_candidates() {
+        let london = cache().resolve_location("London").unwrap();
+        assert_eq!(london.airport_code, None);
+        assert_eq!(london.method, "ambiguous");
+        assert_eq!(london.candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_resolve_location_coordinates() {
+        let near = cache().resolve_location("51.47, -0.45").unwrap();
+        assert_eq!((near.airport_code.as_deref(), near.method.as_str()), (Some("LHR"), "coordinates"));
+
+        assert_eq!(parse_coordinates("91.0, 10.0"), None);
+        assert_eq!(parse_coordinates("Paris"), None);
+    }
+
+    #[test]
+    fn test_geocode_query_and_cache() {
+        assert_eq!(
+            geocode_query("Smith  Farm Strip", Some("Ely"), Some("GB")).as_deref(),
+            Some("smith farm strip, ely, gb")
+        );
+        assert_eq!(geocode_query(" ", None, None), None);
+
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO geocode_cache (query, found, latitude, longitude, provider)
+                 VALUES ('found', 1, 52.4, 0.26, 'opencage');
+                 INSERT INTO geocode_cache (query, found, provider) VALUES ('recent miss', 0, 'opencage');
+                 INSERT INTO geocode_cache (query, found, provider, created_at)
+                 VALUES ('old miss', 0, 'opencage', datetime('now', '-200 days'));",
+            )
+            .unwrap();
+
+        assert_eq!(cached_geocode(&db.conn, "found").unwrap(), Some(Some((52.4, 0.26))));
+        assert_eq!(cached_geocode(&db.conn, "recent miss").unwrap(), Some(None));
+        assert_eq!(cached_geocode(&db.conn, "old miss").unwrap(), None);
+        assert_eq!(cached_geocode(&db.conn, "never asked").unwrap(), None);
+    }
+
+    /// Timing for resolving a 5000-row import's airport codes against the bundled CSV: a scan
+    /// of every airport per code, as before the indexes, against the ICAO/IATA maps. Run with
+    /// `cargo test --release bench_import_code_lookups -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_import_code_lookups() {
+        const ROWS: usize = 5000;
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/airports.csv");
+        let cache = load_airport_cache(path).unwrap();
+        let codes: Vec<&str> = cache.airports.iter().filter_map(|airport| airport.iata_code.as_deref()).collect();
+        let rows: Vec<(&str, &str)> =
+            (0..ROWS).map(|i| (codes[i * 7 % codes.len()], codes[(i * 13 + 1) % codes.len()])).collect();
+
+        let scan = |code: &str| {
+            cache.airports.iter().find(|a| {
+                a.ident.eq_ignore_ascii_case(code) || a.iata_code.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(code))
+            })
+        };
+        let started = std::time::Instant::now();
+        let scanned = rows.iter().filter(|(from, to)| scan(from).is_some() && scan(to).is_some()).count();
+        let scan_time = started.elapsed();
+
+        let started = std::time::Instant::now();
+        let indexed = rows
+            .iter()
+            .filter(|(from, to)| cache.lookup_exact(from).is_some() && cache.lookup_exact(to).is_some())
+            .count();
+        let index_time = started.elapsed();
+
+        assert_eq!(scanned, indexed);
+        println!("{} rows, {} airports: scan {:.2?}, index {:.2?}", rows.len(), cache.airports.len(), scan_time, index_time);
+    }
+}
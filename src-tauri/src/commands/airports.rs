@@ -67,3 +67,85 @@ pub fn delete_airport(airport_id: String, state: State<'_, AppState>) -> Result<
     let db = state.db.lock().map_err(|e| e.to_string())?;
     db.delete_airport(&airport_id).map_err(|e| e.to_string())
 }
+
+/// Pairs of airport rows that look like the same airfield
+#[tauri::command]
+pub fn find_duplicate_airports(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::models::DuplicateAirportPair>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.find_duplicate_airports().map_err(|e| e.to_string())
+}
+
+/// Show which fields differ and which references would move, without changing anything
+#[tauri::command]
+pub fn preview_airport_merge(
+    keep_id: String,
+    remove_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::models::AirportMergePreview, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.preview_airport_merge(&keep_id, &remove_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn merge_airports(
+    keep_id: String,
+    remove_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::models::AirportMergePreview, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.merge_airports(&keep_id, &remove_id)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_find_duplicate_airports() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO airports (id, icao_code, iata_code, name, latitude, longitude) VALUES
+                    ('a1', 'KJFK', 'JFK', 'Kennedy', 40.6413, -73.7781),
+                    ('a2', ' kjfk ', NULL, 'JFK duplicate', 40.6413, -73.7781),
+                    ('b1', NULL, 'BOS', 'Logan', 42.3656, -71.0096),
+                    ('b2', 'KBOS', NULL, 'Logan ICAO', 42.3700, -71.0200),
+                    ('c1', 'KLGA', NULL, 'LaGuardia', 40.7769, -73.8740),
+                    ('c2', 'KXYZ', NULL, 'Next door', 40.7770, -73.8741),
+                    ('d1', NULL, 'SFO', 'San Francisco', 37.6213, -122.3790),
+                    ('d2', NULL, 'sfo', 'SFO again', NULL, NULL),
+                    ('e1', NULL, NULL, 'Far away', -33.9399, 151.1753);",
+            )
+            .unwrap();
+
+        let pairs = db.find_duplicate_airports().unwrap();
+        let mut found: Vec<(String, String, String)> = pairs
+            .iter()
+            .map(|p| {
+                let mut ids = [p.airport_a.id.clone(), p.airport_b.id.clone()];
+                ids.sort();
+                let [a, b] = ids;
+                (a, b, p.reason.clone())
+            })
+            .collect();
+        found.sort();
+
+        assert_eq!(
+            found,
+            [
+                ("a1".to_string(), "a2".to_string(), "Same ICAO code KJFK".to_string()),
+                ("b1".to_string(), "b2".to_string(), "Coordinates 1.0 km apart".to_string()),
+                ("d1".to_string(), "d2".to_string(), "Same IATA code SFO".to_string()),
+            ]
+        );
+        // Conflicting codes are never paired on distance alone, and code matches keep their distance
+        assert!(pairs.iter().all(|p| p.airport_a.id != "c1" && p.airport_b.id != "c1"));
+        let jfk = pairs.iter().find(|p| p.reason.contains("KJFK")).unwrap();
+        assert_eq!(jfk.distance_km, Some(0.0));
+    }
+}
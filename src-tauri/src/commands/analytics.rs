@@ -1,7 +1,10 @@
 // Analytics chart data aggregation commands
+use std::time::Instant;
 use tauri::State;
 
 use super::AppState;
+use crate::units::UnitSystem;
+use crate::validation::FieldErrors;
 
 // Helper function to sanitize floats (replace NaN/Infinity with 0.0)
 fn sanitize_f64(value: f64) -> f64 {
@@ -19,12 +22,13 @@ pub fn get_temporal_analysis(
     request: crate::models::TemporalAnalysisRequest,
     state: State<'_, AppState>,
 ) -> Result<Vec<crate::models::TemporalFlightData>, String> {
-    eprintln!("[DEBUG] get_temporal_analysis called");
+    tracing::debug!("get_temporal_analysis called");
     let db = state.db.lock().map_err(|e| {
-        eprintln!("[ERROR] get_temporal_analysis: DB lock failed: {}", e);
+        tracing::error!("get_temporal_analysis: DB lock failed: {}", e);
         e.to_string()
     })?;
 
+    let started = Instant::now();
     let mut result = db.get_temporal_flight_data(
         &request.user_id,
         &request.granularity,
@@ -32,22 +36,33 @@ pub fn get_temporal_analysis(
         request.end_date.as_deref(),
     )
     .map_err(|e| {
-        eprintln!("[ERROR] get_temporal_analysis: Query failed: {}", e);
+        tracing::error!("get_temporal_analysis: Query failed: {}", e);
         e.to_string()
     })?;
+    let _ = db.record_query_performance(
+        "temporal_analysis",
+        "flights",
+        &["user_id", "departure_datetime"],
+        started.elapsed(),
+        result.len(),
+    );
 
     // Sanitize float values
+    let units = UnitSystem::configured(&db);
     for item in &mut result {
         item.total_distance_km = sanitize_f64(item.total_distance_km);
         item.total_co2_kg = sanitize_f64(item.total_co2_kg);
+        item.measurements = Some(
+            units.measure().distance_km(Some(item.total_distance_km)).carbon_kg(Some(item.total_co2_kg)),
+        );
     }
 
-    eprintln!("[DEBUG] get_temporal_analysis returning {} items", result.len());
+    tracing::debug!("get_temporal_analysis returning {} items", result.len());
     // Try to serialize to catch serialization errors
     match serde_json::to_string(&result) {
-        Ok(_) => eprintln!("[DEBUG] get_temporal_analysis: Serialization test passed"),
+        Ok(_) => tracing::debug!("get_temporal_analysis: Serialization test passed"),
         Err(e) => {
-            eprintln!("[ERROR] get_temporal_analysis: Serialization failed: {}", e);
+            tracing::error!("get_temporal_analysis: Serialization failed: {}", e);
             return Err(format!("Serialization error: {}", e));
         }
     }
@@ -60,12 +75,13 @@ pub fn get_geospatial_analysis(
     request: crate::models::GeospatialAnalysisRequest,
     state: State<'_, AppState>,
 ) -> Result<Vec<crate::models::AirportVisitData>, String> {
-    eprintln!("[DEBUG] get_geospatial_analysis called");
+    tracing::debug!("get_geospatial_analysis called");
     let db = state.db.lock().map_err(|e| {
-        eprintln!("[ERROR] get_geospatial_analysis: DB lock failed: {}", e);
+        tracing::error!("get_geospatial_analysis: DB lock failed: {}", e);
         e.to_string()
     })?;
 
+    let started = Instant::now();
     let result = db.get_airport_visit_data(
         &request.user_id,
         request.limit,
@@ -73,17 +89,24 @@ pub fn get_geospatial_analysis(
         request.end_date.as_deref(),
     )
     .map_err(|e| {
-        eprintln!("[ERROR] get_geospatial_analysis: Query failed: {}", e);
+        tracing::error!("get_geospatial_analysis: Query failed: {}", e);
         e.to_string()
     })?;
+    let _ = db.record_query_performance(
+        "geospatial_analysis",
+        "flights",
+        &["user_id", "departure_datetime"],
+        started.elapsed(),
+        result.len(),
+    );
 
-    eprintln!("[DEBUG] get_geospatial_analysis returning {} items", result.len());
+    tracing::debug!("get_geospatial_analysis returning {} items", result.len());
 
     // Try to serialize to catch serialization errors
     match serde_json::to_string(&result) {
-        Ok(_) => eprintln!("[DEBUG] get_geospatial_analysis: Serialization test passed"),
+        Ok(_) => tracing::debug!("get_geospatial_analysis: Serialization test passed"),
         Err(e) => {
-            eprintln!("[ERROR] get_geospatial_analysis: Serialization failed: {}", e);
+            tracing::error!("get_geospatial_analysis: Serialization failed: {}", e);
             return Err(format!("Serialization error: {}", e));
         }
     }
@@ -91,18 +114,49 @@ pub fn get_geospatial_analysis(
     Ok(result)
 }
 
+/// Routes flown with their flight counts and great-circle arcs, and airport traffic, weighted
+/// for a heatmap overlay. `combine_directions` counts a route and its return together.
+#[tauri::command]
+pub fn get_route_heatmap(
+    user_id: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    combine_directions: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<crate::route_heatmap::RouteHeatmap, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let heatmap = crate::route_heatmap::route_heatmap(
+        &db,
+        &user_id,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        combine_directions.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = db.record_query_performance(
+        "route_heatmap",
+        "flights",
+        &["user_id", "departure_datetime"],
+        started.elapsed(),
+        heatmap.routes.len(),
+    );
+    Ok(heatmap)
+}
+
 #[tauri::command]
 pub fn get_passenger_network(
     request: crate::models::NetworkAnalysisRequest,
     state: State<'_, AppState>,
 ) -> Result<crate::models::PassengerNetworkData, String> {
-    eprintln!("[DEBUG] get_passenger_network called");
+    tracing::debug!("get_passenger_network called");
     let db = state.db.lock().map_err(|e| {
-        eprintln!("[ERROR] get_passenger_network: DB lock failed: {}", e);
+        tracing::error!("get_passenger_network: DB lock failed: {}", e);
         e.to_string()
     })?;
     let min_shared_flights = request.min_flights_together.unwrap_or(1);
 
+    let started = Instant::now();
     let mut result = db.get_passenger_network_data(
         &request.user_id,
         min_shared_flights,
@@ -110,20 +164,27 @@ pub fn get_passenger_network(
         request.end_date.as_deref(),
     )
     .map_err(|e| {
-        eprintln!("[ERROR] get_passenger_network: Query failed: {}", e);
+        tracing::error!("get_passenger_network: Query failed: {}", e);
         e.to_string()
     })?;
+    let _ = db.record_query_performance(
+        "passenger_network",
+        "flights",
+        &["user_id", "departure_datetime"],
+        started.elapsed(),
+        result.nodes.len(),
+    );
 
     // Sanitize float values in nodes
     for node in &mut result.nodes {
         node.total_distance_km = sanitize_f64(node.total_distance_km);
     }
 
-    eprintln!("[DEBUG] get_passenger_network returning {} nodes, {} edges", result.nodes.len(), result.edges.len());
+    tracing::debug!("get_passenger_network returning {} nodes, {} edges", result.nodes.len(), result.edges.len());
     match serde_json::to_string(&result) {
-        Ok(_) => eprintln!("[DEBUG] get_passenger_network: Serialization test passed"),
+        Ok(_) => tracing::debug!("get_passenger_network: Serialization test passed"),
         Err(e) => {
-            eprintln!("[ERROR] get_passenger_network: Serialization failed: {}", e);
+            tracing::error!("get_passenger_network: Serialization failed: {}", e);
             return Err(format!("Serialization error: {}", e));
         }
     }
@@ -131,14 +192,37 @@ pub fn get_passenger_network(
     Ok(result)
 }
 
+/// The passenger network as GraphML or DOT ("graphml" / "dot") for Gephi, Cytoscape or Graphviz
+/// Same data and min_flights_together filter as get_passenger_network.
+#[tauri::command]
+pub fn export_passenger_network(
+    request: crate::models::NetworkAnalysisRequest,
+    format: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let format = crate::network_export::GraphFormat::parse(&format)?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let network = db
+        .get_passenger_network_data(
+            &request.user_id,
+            request.min_flights_together.unwrap_or(1),
+            request.start_date.as_deref(),
+            request.end_date.as_deref(),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(crate::network_export::export(&network, format))
+}
+
 #[tauri::command]
 pub fn get_comparative_metrics(
     request: crate::models::ComparativeMetricsRequest,
     state: State<'_, AppState>,
 ) -> Result<Vec<crate::models::PassengerMetrics>, String> {
-    eprintln!("[DEBUG] get_comparative_metrics called");
+    tracing::debug!("get_comparative_metrics called");
     let db = state.db.lock().map_err(|e| e.to_string())?;
 
+    let started = Instant::now();
     let mut result = db.get_passenger_metrics(
         &request.user_id,
         &request.rank_by,
@@ -147,9 +231,16 @@ pub fn get_comparative_metrics(
         request.end_date.as_deref(),
     )
     .map_err(|e| {
-        eprintln!("[ERROR] get_comparative_metrics: Query failed: {}", e);
+        tracing::error!("get_comparative_metrics: Query failed: {}", e);
         e.to_string()
     })?;
+    let _ = db.record_query_performance(
+        "comparative_metrics",
+        "flights",
+        &["user_id", "departure_datetime"],
+        started.elapsed(),
+        result.len(),
+    );
 
     // Sanitize float values
     for item in &mut result {
@@ -159,11 +250,11 @@ pub fn get_comparative_metrics(
         item.avg_flight_distance_km = sanitize_f64(item.avg_flight_distance_km);
     }
 
-    eprintln!("[DEBUG] get_comparative_metrics returning {} items", result.len());
+    tracing::debug!("get_comparative_metrics returning {} items", result.len());
     match serde_json::to_string(&result) {
-        Ok(_) => eprintln!("[DEBUG] get_comparative_metrics: Serialization test passed"),
+        Ok(_) => tracing::debug!("get_comparative_metrics: Serialization test passed"),
         Err(e) => {
-            eprintln!("[ERROR] get_comparative_metrics: Serialization failed: {}", e);
+            tracing::error!("get_comparative_metrics: Serialization failed: {}", e);
             return Err(format!("Serialization error: {}", e));
         }
     }
@@ -173,14 +264,39 @@ pub fn get_comparative_metrics(
 
 // ===== ADDITIONAL ANALYTICS COMMANDS =====
 
+/// Default number of days without a flight before an aircraft is flagged idle
+const DEFAULT_IDLE_THRESHOLD_DAYS: i64 = 30;
+const DAYS_PER_MONTH: f64 = 30.0;
+
+/// Aircraft utilization per tail number
+///
+/// `idle_threshold_days` (default 30) flags aircraft that have not flown recently.
+/// With `fixed_cost_per_month` and/or `hourly_cost` the idle cost and effective
+/// cost per flight hour are estimated.
 #[tauri::command]
 pub fn get_aircraft_utilization(
     user_id: String,
+    idle_threshold_days: Option<i64>,
+    fixed_cost_per_month: Option<f64>,
+    hourly_cost: Option<f64>,
     state: State<'_, AppState>,
 ) -> Result<Vec<crate::models::AircraftUtilization>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
+    let started = Instant::now();
     let mut result = db.get_aircraft_utilization(&user_id)
         .map_err(|e| e.to_string())?;
+    let _ = db.record_query_performance(
+        "aircraft_utilization",
+        "flights",
+        &["user_id", "aircraft_registration"],
+        started.elapsed(),
+        result.len(),
+    );
+
+    let idle_threshold_days = idle_threshold_days.unwrap_or(DEFAULT_IDLE_THRESHOLD_DAYS);
+    for item in &mut result {
+        annotate_utilization(item, idle_threshold_days, fixed_cost_per_month, hourly_cost);
+    }
 
     // Sanitize float values to prevent JSON serialization errors
     for item in &mut result {
@@ -193,11 +309,44 @@ pub fn get_aircraft_utilization(
         if item.avg_flight_hours.is_nan() || item.avg_flight_hours.is_infinite() {
             item.avg_flight_hours = 0.0;
         }
+        item.hours_per_month = item.hours_per_month.map(sanitize_f64);
+        item.idle_cost = item.idle_cost.map(sanitize_f64);
+        item.cost_per_flight_hour = item.cost_per_flight_hour.map(sanitize_f64);
     }
 
     Ok(result)
 }
 
+/// Fill in utilization rate, idle flag and cost estimates for one aircraft
+/// Rate and costs stay None when the first flight date is unknown
+fn annotate_utilization(
+    item: &mut crate::models::AircraftUtilization,
+    idle_threshold_days: i64,
+    fixed_cost_per_month: Option<f64>,
+    hourly_cost: Option<f64>,
+) {
+    item.is_idle = item
+        .days_since_last_flight
+        .is_some_and(|days| days > idle_threshold_days);
+
+    let Some(days_in_service) = item.days_in_service.filter(|d| *d > 0) else {
+        return;
+    };
+    let months_in_service = days_in_service as f64 / DAYS_PER_MONTH;
+    item.hours_per_month = Some(item.total_hours / months_in_service);
+
+    if let Some(fixed) = fixed_cost_per_month {
+        let idle_days = item.days_since_last_flight.unwrap_or(0).max(0);
+        item.idle_cost = Some(fixed * idle_days as f64 / DAYS_PER_MONTH);
+    }
+
+    if fixed_cost_per_month.is_some() || hourly_cost.is_some() {
+        let total_cost = fixed_cost_per_month.unwrap_or(0.0) * months_in_service
+            + hourly_cost.unwrap_or(0.0) * item.total_hours;
+        item.cost_per_flight_hour = (item.total_hours > 0.0).then(|| total_cost / item.total_hours);
+    }
+}
+
 #[tauri::command]
 pub fn get_cost_breakdown(
     user_id: String,
@@ -215,6 +364,48 @@ pub fn get_cost_breakdown(
     Ok(result)
 }
 
+/// Spend per normalized cabin (economy/premium/business/first/other) and currency
+#[tauri::command]
+pub fn get_revenue_by_cabin(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::fare_classes::CabinRevenue>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut result = crate::fare_classes::revenue_by_cabin(&db, &user_id)
+        .map_err(|e| e.to_string())?;
+
+    // Sanitize float values
+    for item in &mut result {
+        item.total_cost = sanitize_f64(item.total_cost);
+        item.base_fare = sanitize_f64(item.base_fare);
+        item.taxes = sanitize_f64(item.taxes);
+        item.average_cost = item.average_cost.map(sanitize_f64);
+    }
+
+    Ok(result)
+}
+
+/// The user's own fare class to cabin mapping, checked before the built-in rules
+#[tauri::command]
+pub fn get_fare_class_mapping(
+    state: State<'_, AppState>,
+) -> Result<std::collections::BTreeMap<String, crate::fare_classes::Cabin>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(crate::fare_classes::FareClassMapping::from_settings(&db).entries())
+}
+
+/// Replace the fare class mapping; keys are matched case-insensitively
+#[tauri::command]
+pub fn save_fare_class_mapping(
+    mapping: std::collections::HashMap<String, crate::fare_classes::Cabin>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    crate::fare_classes::FareClassMapping::new(mapping)
+        .save(&db)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_day_night_stats(
     user_id: String,
@@ -292,3 +483,200 @@ pub fn get_runway_risk_data(
     db.get_runway_risk_data(&user_id)
         .map_err(|e| e.to_string())
 }
+
+/// Visited airports high enough for density altitude to matter (default threshold 5000 ft)
+#[tauri::command]
+pub fn get_high_altitude_airports(
+    user_id: String,
+    threshold_ft: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<crate::models::HighAltitudeReport, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_high_altitude_airports(
+        &user_id,
+        threshold_ft.unwrap_or(crate::calculations::HIGH_ALTITUDE_THRESHOLD_FT),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Flight metrics grouped by tag, fare class, airline, aircraft class/category or a custom field
+#[tauri::command]
+pub fn get_analytics_by_dimension(
+    user_id: String,
+    dimension: String,
+    field_name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::models::DimensionGroupMetrics>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let mut result = db.get_analytics_by_dimension(&user_id, &dimension, field_name.as_deref())
+        .map_err(|e| e.to_string())?;
+    let _ = db.record_query_performance(
+        &format!("analytics_by_{}", dimension),
+        "flights",
+        &["user_id"],
+        started.elapsed(),
+        result.len(),
+    );
+
+    // Sanitize float values
+    let units = UnitSystem::configured(&db);
+    for item in &mut result {
+        item.total_distance_km = sanitize_f64(item.total_distance_km);
+        item.total_duration_hours = sanitize_f64(item.total_duration_hours);
+        item.total_cost = sanitize_f64(item.total_cost);
+        item.total_co2_kg = sanitize_f64(item.total_co2_kg);
+        item.measurements = Some(
+            units.measure().distance_km(Some(item.total_distance_km)).carbon_kg(Some(item.total_co2_kg)),
+        );
+    }
+
+    Ok(result)
+}
+
+/// Average delay, on-time percentage and the worst routes/airports for flights with a schedule
+#[tauri::command]
+pub fn get_punctuality_stats(
+    user_id: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<crate::models::PunctualityStats, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_punctuality_stats(&user_id, limit.unwrap_or(5))
+        .map_err(|e| e.to_string())
+}
+
+/// Set (or clear with None) the user's home base airport used by commute analytics
+#[tauri::command]
+pub fn set_home_base(
+    user_id: String,
+    airport_code: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let code = airport_code
+        .map(|c| c.trim().to_uppercase())
+        .unwrap_or_default();
+    if !code.is_empty() {
+        let mut errors = FieldErrors::default();
+        errors.airport_code("airport_code", &code);
+        errors.into_result()?;
+    }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_setting(&format!("home_base:{}", user_id), &code)
+        .map_err(|e| e.to_string())
+}
+
+/// Flights to/from home base, time spent away and most common away destinations
+#[tauri::command]
+pub fn get_commute_analytics(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::models::CommuteAnalytics, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut result = db.get_commute_analytics(&user_id)
+        .map_err(|e| e.to_string())?;
+
+    // Sanitize float values
+    result.average_trip_days = sanitize_f64(result.average_trip_days);
+    for candidate in &mut result.candidates {
+        candidate.share = sanitize_f64(candidate.share);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AircraftUtilization;
+
+    fn aircraft(total_hours: f64, days_since_last: Option<i64>, days_in_service: Option<i64>) -> AircraftUtilization {
+        AircraftUtilization {
+            tail_number: "N123AB".to_string(),
+            aircraft_type: "Cessna Citation".to_string(),
+            total_flights: 1,
+            total_hours,
+            total_distance_km: 0.0,
+            avg_flight_hours: total_hours,
+            last_flown: None,
+            days_since_last_flight: days_since_last,
+            first_flown: None,
+            days_in_service,
+            hours_per_month: None,
+            is_idle: false,
+            idle_cost: None,
+            cost_per_flight_hour: None,
+        }
+    }
+
+    #[test]
+    fn test_idle_aircraft_costs() {
+        let mut item = aircraft(20.0, Some(45), Some(120));
+        annotate_utilization(&mut item, 30, Some(3000.0), Some(500.0));
+
+        assert!(item.is_idle);
+        assert_eq!(item.hours_per_month, Some(5.0));
+        assert_eq!(item.idle_cost, Some(4500.0));
+        // 4 months of fixed cost (12000) plus 20 hours at 500 (10000) over 20 hours
+        assert_eq!(item.cost_per_flight_hour, Some(1100.0));
+    }
+
+    #[test]
+    fn test_single_flight_and_unknown_first_flight() {
+        // Single flight today: one day in service, no divide by zero
+        let mut item = aircraft(0.0, Some(0), Some(1));
+        annotate_utilization(&mut item, 30, Some(3000.0), None);
+        assert!(!item.is_idle);
+        assert_eq!(item.hours_per_month, Some(0.0));
+        assert_eq!(item.cost_per_flight_hour, None);
+
+        let mut item = aircraft(2.0, None, None);
+        annotate_utilization(&mut item, 30, Some(3000.0), Some(500.0));
+        assert!(!item.is_idle);
+        assert_eq!(item.hours_per_month, None);
+        assert_eq!(item.idle_cost, None);
+    }
+
+    #[test]
+    fn test_runway_risk_uses_runway_data() {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, icao_code, name, elevation_ft, longest_runway_ft) VALUES
+                    ('KASE', 'KASE', 'Aspen', 7820, 8006),
+                    ('KTEB', 'KTEB', 'Teterboro', 9, NULL);
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'KTEB', 'KASE', '2025-01-05T09:00:00');",
+            )
+            .unwrap();
+
+        let risks = db.get_runway_risk_data("u1").unwrap();
+        let aspen = risks.iter().find(|r| r.airport_code == "KASE").unwrap();
+        assert_eq!((aspen.runway_length_ft, aspen.runway_length_assumed), (8006, false));
+        assert_eq!(aspen.effective_runway_ft, crate::calculations::effective_runway_length_ft(8006, Some(7820)));
+
+        let teterboro = risks.iter().find(|r| r.airport_code == "KTEB").unwrap();
+        assert_eq!(teterboro.runway_length_ft, crate::calculations::ASSUMED_RUNWAY_LENGTH_FT);
+        assert!(teterboro.runway_length_assumed);
+    }
+
+    #[test]
+    fn test_commute_home_base_matches_icao_and_iata() {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, icao_code, iata_code, name) VALUES ('EGLL', 'EGLL', 'LHR', 'Heathrow');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, arrival_datetime) VALUES
+                    ('f1', 'u1', 'LHR', 'JFK', '2025-01-05T09:00:00', '2025-01-05T12:00:00'),
+                    ('f2', 'u1', 'JFK', 'EGLL', '2025-01-08T19:00:00', '2025-01-09T07:00:00');",
+            )
+            .unwrap();
+        db.set_setting("home_base:u1", "EGLL").unwrap();
+
+        let commute = db.get_commute_analytics("u1").unwrap();
+        assert_eq!((commute.flights_from_home, commute.flights_to_home, commute.trips_completed), (1, 1, 1));
+        assert_eq!(commute.top_away_destinations.len(), 1);
+    }
+}
@@ -0,0 +1,53 @@
+// Database backup commands
+// Backups are taken automatically on startup/shutdown (see backup.rs); these list them,
+// take one on demand and roll the database back to one.
+
+use super::AppState;
+use crate::backup::{self, BackupInfo, BackupPolicy};
+use crate::error::CommandError;
+use std::path::PathBuf;
+use tauri::{Manager, State};
+
+fn backup_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, CommandError> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| CommandError::Internal(format!("Failed to get app data directory: {}", e)))?;
+    Ok(backup::backup_dir(&app_dir))
+}
+
+/// Backups on disk, newest first
+#[tauri::command]
+pub fn list_backups(app_handle: tauri::AppHandle) -> Result<Vec<BackupInfo>, CommandError> {
+    Ok(backup::list_backups(&backup_dir(&app_handle)?)?)
+}
+
+/// Take a backup now; old backups beyond the configured count are rotated out
+#[tauri::command]
+pub fn create_backup(app_handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<BackupInfo, CommandError> {
+    let dir = backup_dir(&app_handle)?;
+    let db = state.db.lock()?;
+    let info = backup::create_backup(&db.conn, &dir, "manual")?;
+    backup::rotate(&dir, BackupPolicy::load(&db.conn).keep.max(1))?;
+    Ok(info)
+}
+
+/// Replace the database with the named backup
+/// Returns the "pre-restore" backup of the data that was replaced.
+#[tauri::command]
+pub fn restore_backup(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<BackupInfo, CommandError> {
+    let dir = backup_dir(&app_handle)?;
+    let mut db = state.db.lock()?;
+    if !dir.join(&name).is_file() || name.contains(['/', '\\']) {
+        return Err(CommandError::not_found("Backup", &name));
+    }
+    let keep = BackupPolicy::load(&db.conn).keep;
+    let safety = backup::restore_backup(&mut db.conn, &dir, &name, keep)?;
+    // Backups from an older version need the migrations added since
+    crate::database::Database::init_schema(&db.conn)?;
+    Ok(safety)
+}
@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use super::AppState;
+use crate::aircraft_performance::{performance_for, AircraftRef};
+use crate::geo::KM_PER_NM;
+use crate::operations::{OperationHandle, OperationRegistry};
 
 // ===== RESULT TYPES =====
 
@@ -33,6 +36,9 @@ pub struct CO2CalculationResult {
     pub total_co2_kg: f64,
     pub passenger_count: u32,
     pub per_passenger_co2_kg: f64,
+    pub fare_class: Option<String>,
+    /// "equal" or "class_weighted"
+    pub split_method: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,7 +87,7 @@ pub fn batch_calculate_missing_distances(
 
         let (distance_nm, distance_km, source) = match (dep_coords, arr_coords) {
             (Some((dep_lat, dep_lon)), Some((arr_lat, arr_lon))) => {
-                let (nm, km) = crate::geo::calculate_distance(dep_lat, dep_lon, arr_lat, arr_lon);
+                let (nm, km) = crate::geo::calculate_geodesic_distance(dep_lat, dep_lon, arr_lat, arr_lon);
                 (nm, km, "database".to_string())
             }
             _ => {
@@ -133,6 +139,25 @@ pub fn batch_calculate_missing_distances(
     })
 }
 
+/// (nm, km) between two airports: database coordinates first, then geo.rs
+pub(crate) fn route_distance(
+    db: &crate::database::Database,
+    departure: &str,
+    arrival: &str,
+) -> Option<(f64, f64)> {
+    match (get_airport_coords_from_db(db, departure), get_airport_coords_from_db(db, arrival)) {
+        (Some((dep_lat, dep_lon)), Some((arr_lat, arr_lon))) => {
+            Some(crate::geo::calculate_geodesic_distance(dep_lat, dep_lon, arr_lat, arr_lon))
+        }
+        _ => crate::geo::calculate_airport_distance(departure, arrival),
+    }
+}
+
+/// (lat, lon) of an airport: database coordinates first, then geo.rs
+pub(crate) fn airport_coords(db: &crate::database::Database, airport_code: &str) -> Option<(f64, f64)> {
+    get_airport_coords_from_db(db, airport_code).or_else(|| crate::geo::airport_coords(airport_code))
+}
+
 /// Get airport coordinates from the database airports table
 fn get_airport_coords_from_db(
     db: &crate::database::Database,
@@ -164,6 +189,7 @@ fn get_airport_coords_from_db(
 /// Recalculate CO2 emissions and per-passenger CO2 for all flights
 /// Uses the "Shared Responsibility" model: crew excluded from passenger count
 /// Formula: per_passenger_co2 = total_co2 / (passenger_count - crew_count)
+/// When the flight has a fare class the share is weighted by cabin class instead
 #[tauri::command]
 pub fn batch_recalculate_co2(
     user_id: String,
@@ -173,12 +199,12 @@ pub fn batch_recalculate_co2(
 
     // Get all flights with distance_km (required for CO2 calculation)
     let mut stmt = db.conn.prepare(
-        "SELECT id, departure_airport, arrival_airport, distance_km, notes, aircraft_registration
+        "SELECT id, distance_km, notes, aircraft_registration, fare_class, passenger_count, aircraft_type_id
          FROM flights
          WHERE user_id = ?1 AND distance_km IS NOT NULL AND distance_km > 0"
     ).map_err(|e| e.to_string())?;
 
-    let flights: Vec<(String, String, String, f64, Option<String>, Option<String>)> = stmt
+    let flights: Vec<FlightCo2Row> = stmt
         .query_map(rusqlite::params![user_id], |row| {
             Ok((
                 row.get(0)?,
@@ -187,6 +213,7 @@ pub fn batch_recalculate_co2(
                 row.get(3)?,
                 row.get(4)?,
                 row.get(5)?,
+                row.get(6)?,
             ))
         })
         .map_err(|e| e.to_string())?
@@ -197,9 +224,11 @@ pub fn batch_recalculate_co2(
     let mut skipped_count = 0;
     let mut calculations: Vec<CO2CalculationResult> = vec![];
 
-    for (flight_id, _departure, _arrival, distance_km, notes, aircraft_reg) in flights {
-        // Count passengers from notes field (format: "Passengers: Name1, Name2, Name3")
-        let passenger_count = count_passengers_from_notes(notes.as_deref());
+    let weights = super::calculations::co2_class_weights(&db)?;
+
+    for (flight_id, distance_km, notes, aircraft_reg, fare_class, stored_count, aircraft_type_id) in flights {
+        let passenger_count = resolve_passenger_count(&db.conn, &flight_id, stored_count, notes.as_deref())
+            .map_err(|e| e.to_string())?;
 
         if passenger_count == 0 {
             // No passengers recorded - skip per-passenger calculation but still calculate total CO2
@@ -208,21 +237,26 @@ pub fn batch_recalculate_co2(
         }
 
         // Get aircraft type for more accurate fuel burn calculation
-        let aircraft_type = aircraft_reg.as_deref();
+        let aircraft = AircraftRef { registration: aircraft_reg.as_deref(), aircraft_type_id: aircraft_type_id.as_deref() };
 
         // Calculate total CO2 using the standard formula:
         // Distance × Fuel Burn Rate × CO2 Factor (3.16 for Jet A1)
-        let total_co2_kg = crate::calculations::calculate_co2_emissions(distance_km, aircraft_type);
+        let total_co2_kg = performance_for(&db, aircraft).co2_emissions(distance_km);
 
         // Calculate per-passenger CO2 (excluding crew)
         // Crew (pilot, copilot) are considered part of the aircraft, not passengers
         // They fly regardless of passenger count, so CO2 responsibility is on passengers only
-        let per_passenger_co2_kg = total_co2_kg / passenger_count as f64;
+        let (per_passenger_co2_kg, split_method) = crate::calculations::passenger_co2_share(
+            total_co2_kg,
+            passenger_count,
+            fare_class.as_deref(),
+            Some(&weights),
+        );
 
         // Update the flight with calculated CO2 values
         match db.conn.execute(
-            "UPDATE flights SET carbon_emissions_kg = ?1, per_passenger_co2_kg = ?2, updated_at = datetime('now') WHERE id = ?3",
-            rusqlite::params![total_co2_kg, per_passenger_co2_kg, flight_id],
+            "UPDATE flights SET carbon_emissions_kg = ?1, per_passenger_co2_kg = ?2, co2_split_method = ?3, updated_at = datetime('now') WHERE id = ?4",
+            rusqlite::params![total_co2_kg, per_passenger_co2_kg, split_method.as_str(), flight_id],
         ) {
             Ok(_) => {
                 updated_count += 1;
@@ -231,6 +265,8 @@ pub fn batch_recalculate_co2(
                     total_co2_kg,
                     passenger_count,
                     per_passenger_co2_kg,
+                    fare_class,
+                    split_method: split_method.as_str().to_string(),
                 });
             }
             Err(_) => {
@@ -246,21 +282,212 @@ pub fn batch_recalculate_co2(
     })
 }
 
+/// (id, distance_km, notes, aircraft_registration, fare_class, passenger_count)
+/// id, distance_km, notes, aircraft_registration, fare_class, passenger_count, aircraft_type_id
+type FlightCo2Row = (String, f64, Option<String>, Option<String>, Option<String>, Option<i32>, Option<String>);
+
 /// Count passengers from the notes field
-/// Format: "Passengers: Name1, Name2, Name3"
+/// Format: "Passengers: Name1, Name2, Name3", up to the next " | " section or line
 /// Returns 0 if no passengers found
 fn count_passengers_from_notes(notes: Option<&str>) -> u32 {
-    match notes {
-        Some(n) if n.starts_with("Passengers: ") => {
-            let passenger_part = n.strip_prefix("Passengers: ").unwrap_or("");
-            passenger_part
-                .split(',')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .count() as u32
+    notes
+        .and_then(|n| n.strip_prefix("Passengers: "))
+        .and_then(|list| list.split(" | ").next())
+        .and_then(|list| list.lines().next())
+        .map_or(0, crate::calculations::count_passenger_names)
+}
+
+/// Passengers on a flight: the stored passenger_count, else counted from the notes and
+/// stored so later reads skip the parsing. Returns 0 if neither has a count.
+pub(crate) fn resolve_passenger_count(
+    conn: &rusqlite::Connection,
+    flight_id: &str,
+    stored: Option<i32>,
+    notes: Option<&str>,
+) -> rusqlite::Result<u32> {
+    if let Some(count) = stored {
+        return Ok(count.max(0) as u32);
+    }
+    let count = count_passengers_from_notes(notes);
+    if count > 0 {
+        store_passenger_count(conn, flight_id, count)?;
+    }
+    Ok(count)
+}
+
+fn store_passenger_count(conn: &rusqlite::Connection, flight_id: &str, count: u32) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE flights SET passenger_count = ?1 WHERE id = ?2 AND passenger_count IS NULL",
+        rusqlite::params![count, flight_id],
+    )?;
+    Ok(())
+}
+
+// ===== PER-PASSENGER CO2 BACKFILL =====
+
+/// What to store for a flight with CO2 but no recorded passengers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZeroPassengerPolicy {
+    /// Leave per_passenger_co2_kg null
+    #[default]
+    LeaveNull,
+    /// The whole flight's CO2, as for a sole traveller
+    Total,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerPassengerBackfillResult {
+    pub policy: ZeroPassengerPolicy,
+    pub updated_count: usize,
+    /// Flights that already had a passenger_count
+    pub from_stored: usize,
+    /// Passengers counted from the flight_passengers junction table
+    pub from_junction: usize,
+    /// Passengers counted from a "Passengers: ..." note
+    pub from_notes: usize,
+    /// Flights without passengers; they were given the total under the Total policy
+    pub no_passengers: usize,
+    /// Stopped early through cancel_operation
+    pub cancelled: bool,
+}
+
+/// Fill per_passenger_co2_kg from each flight's stored CO2 and its passenger count
+///
+/// The flight's passenger_count is used when set. Otherwise passengers are counted from
+/// the flight_passengers junction table (crew roles excluded), falling back to a
+/// "Passengers: ..." note, and the count is stored on the flight. Shares are weighted by cabin class like
+/// batch_recalculate_co2, but the stored total CO2 is kept as is. Only flights without a
+/// value are filled unless `overwrite` is set. The zero-passenger policy defaults to the
+/// "co2_zero_passenger_policy" setting. Progress is reported as an operation.
+#[tauri::command]
+pub fn backfill_per_passenger_co2(
+    user_id: String,
+    zero_passenger_policy: Option<ZeroPassengerPolicy>,
+    overwrite: Option<bool>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<PerPassengerBackfillResult, String> {
+    use tauri::Manager;
+
+    let operation = app_handle
+        .state::<OperationRegistry>()
+        .start("per_passenger_co2_backfill", operation_id)
+        .map_err(|e| e.to_string())?
+        .with_events(&app_handle);
+    let result = state.db.lock().map_err(|e| e.to_string()).and_then(|db| {
+        let policy = zero_passenger_policy.unwrap_or_else(|| zero_passenger_policy_setting(&db));
+        let weights = super::calculations::co2_class_weights(&db)?;
+        backfill_per_passenger(&db, &user_id, policy, &weights, overwrite.unwrap_or(false), &operation)
+            .map_err(|e| e.to_string())
+    });
+    operation.finish(&result);
+    result
+}
+
+fn zero_passenger_policy_setting(db: &crate::database::Database) -> ZeroPassengerPolicy {
+    db.get_setting("co2_zero_passenger_policy")
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_value(serde_json::Value::String(value.trim().to_string())).ok())
+        .unwrap_or_default()
+}
+
+/// (id, carbon_emissions_kg, fare_class, notes, passenger_count, junction passenger count)
+type BackfillRow = (String, f64, Option<String>, Option<String>, Option<i32>, u32);
+
+fn backfill_per_passenger(
+    db: &crate::database::Database,
+    user_id: &str,
+    policy: ZeroPassengerPolicy,
+    weights: &crate::calculations::CabinClassWeights,
+    overwrite: bool,
+    operation: &OperationHandle,
+) -> rusqlite::Result<PerPassengerBackfillResult> {
+    const PROGRESS_EVERY: usize = 50;
+
+    let flights: Vec<BackfillRow> = db
+        .conn
+        .prepare(
+            "SELECT f.id, f.carbon_emissions_kg, f.fare_class, f.notes, f.passenger_count,
+                    (SELECT COUNT(*) FROM flight_passengers fp
+                     WHERE fp.flight_id = f.id
+                       AND COALESCE(fp.role, 'passenger') NOT IN ('crew', 'pilot', 'copilot'))
+             FROM flights f
+             WHERE f.user_id = ?1 AND f.deleted_at IS NULL
+               AND f.carbon_emissions_kg IS NOT NULL AND f.carbon_emissions_kg > 0
+               AND (?2 OR f.per_passenger_co2_kg IS NULL)",
+        )?
+        .query_map(rusqlite::params![user_id, overwrite], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let total = flights.len();
+    let mut result = PerPassengerBackfillResult {
+        policy,
+        updated_count: 0,
+        from_stored: 0,
+        from_junction: 0,
+        from_notes: 0,
+        no_passengers: 0,
+        cancelled: false,
+    };
+
+    let tx = db.conn.unchecked_transaction()?;
+    for (i, (flight_id, total_co2_kg, fare_class, notes, stored_count, junction_count)) in flights.into_iter().enumerate() {
+        if operation.is_cancelled() {
+            result.cancelled = true;
+            break;
+        }
+        if i % PROGRESS_EVERY == 0 {
+            operation.progress_with(i, total, &result);
+        }
+
+        let passenger_count = if let Some(count) = stored_count {
+            result.from_stored += 1;
+            count.max(0) as u32
+        } else if junction_count > 0 {
+            result.from_junction += 1;
+            store_passenger_count(&tx, &flight_id, junction_count)?;
+            junction_count
+        } else {
+            let count = resolve_passenger_count(&tx, &flight_id, None, notes.as_deref())?;
+            if count > 0 {
+                result.from_notes += 1;
+            }
+            count
+        };
+
+        let (per_passenger_co2_kg, split_method) = if passenger_count > 0 {
+            let (share, method) = crate::calculations::passenger_co2_share(
+                total_co2_kg,
+                passenger_count,
+                fare_class.as_deref(),
+                Some(weights),
+            );
+            (Some(share), Some(method.as_str()))
+        } else {
+            result.no_passengers += 1;
+            match policy {
+                ZeroPassengerPolicy::LeaveNull => (None, None),
+                ZeroPassengerPolicy::Total => (Some(total_co2_kg), Some(crate::calculations::Co2SplitMethod::Equal.as_str())),
+            }
+        };
+
+        tx.execute(
+            "UPDATE flights SET per_passenger_co2_kg = ?1, co2_split_method = ?2, updated_at = datetime('now') WHERE id = ?3",
+            rusqlite::params![per_passenger_co2_kg, split_method, flight_id],
+        )?;
+        if per_passenger_co2_kg.is_some() {
+            result.updated_count += 1;
         }
-        _ => 0,
     }
+    tx.commit()?;
+
+    operation.progress_with(total, total, &result);
+    Ok(result)
 }
 
 // ===== COMBINED BATCH OPERATION =====
@@ -307,19 +534,42 @@ pub struct BatchProgressUpdate {
 pub struct StreamingBatchResult {
     pub distance_result: BatchDistanceResult,
     pub co2_result: BatchCO2Result,
+    /// Stopped early by cancel_operation; flights updated before that keep their values
+    pub cancelled: bool,
 }
 
-/// Run batch calculations with progress events emitted to frontend
-/// This allows the UI to show a progress bar and remain responsive
+/// Run batch calculations as an operation, with BatchProgressUpdate as the progress detail
+/// This allows the UI to show a progress bar, remain responsive and cancel the run
 #[tauri::command]
 pub async fn batch_calculate_streaming(
     user_id: String,
     batch_size: usize,
+    operation_id: Option<String>,
     state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<StreamingBatchResult, String> {
-    use tauri::Emitter;
+    use tauri::Manager;
+
+    let operation = app_handle
+        .state::<OperationRegistry>()
+        .start("batch_calculations", operation_id)
+        .map_err(|e| e.to_string())?
+        .with_events(&app_handle);
+    let result = run_streaming_batch(&user_id, batch_size, &state, &operation);
+    operation.finish(&result);
+    result
+}
 
+fn report_progress(operation: &OperationHandle, update: BatchProgressUpdate) {
+    operation.progress_with(update.current, update.total, &update);
+}
+
+fn run_streaming_batch(
+    user_id: &str,
+    batch_size: usize,
+    state: &State<'_, AppState>,
+    operation: &OperationHandle,
+) -> Result<StreamingBatchResult, String> {
     let batch_size = if batch_size == 0 { 50 } else { batch_size };
 
     // ===== PHASE 1: DISTANCE CALCULATION =====
@@ -349,9 +599,13 @@ pub async fn batch_calculate_streaming(
         let mut errors: Vec<String> = vec![];
 
         for (i, (flight_id, departure, arrival)) in flights_missing_distance.into_iter().enumerate() {
+            if operation.is_cancelled() {
+                break;
+            }
+
             // Emit progress every batch_size items or on last item
             if i % batch_size == 0 || i == total - 1 {
-                let _ = app_handle.emit("batch-progress", BatchProgressUpdate {
+                report_progress(operation, BatchProgressUpdate {
                     phase: "distance".to_string(),
                     current: i + 1,
                     total,
@@ -368,7 +622,7 @@ pub async fn batch_calculate_streaming(
 
             let (distance_nm, distance_km, source) = match (dep_coords, arr_coords) {
                 (Some((dep_lat, dep_lon)), Some((arr_lat, arr_lon))) => {
-                    let (nm, km) = crate::geo::calculate_distance(dep_lat, dep_lon, arr_lat, arr_lon);
+                    let (nm, km) = crate::geo::calculate_geodesic_distance(dep_lat, dep_lon, arr_lat, arr_lon);
                     (nm, km, "database".to_string())
                 }
                 _ => {
@@ -411,7 +665,7 @@ pub async fn batch_calculate_streaming(
         }
 
         // Emit distance phase complete
-        let _ = app_handle.emit("batch-progress", BatchProgressUpdate {
+        report_progress(operation, BatchProgressUpdate {
             phase: "distance".to_string(),
             current: total,
             total,
@@ -431,17 +685,19 @@ pub async fn batch_calculate_streaming(
     };
 
     // ===== PHASE 2: CO2 CALCULATION =====
-    let co2_result = {
+    let co2_result = if operation.is_cancelled() {
+        BatchCO2Result { updated_count: 0, skipped_count: 0, calculations: vec![] }
+    } else {
         let db = state.db.lock().map_err(|e| e.to_string())?;
 
         // Get all flights with distance_km
         let mut stmt = db.conn.prepare(
-            "SELECT id, departure_airport, arrival_airport, distance_km, notes, aircraft_registration
+            "SELECT id, distance_km, notes, aircraft_registration, fare_class, passenger_count, aircraft_type_id
              FROM flights
              WHERE user_id = ?1 AND distance_km IS NOT NULL AND distance_km > 0"
         ).map_err(|e| e.to_string())?;
 
-        let flights: Vec<(String, String, String, f64, Option<String>, Option<String>)> = stmt
+        let flights: Vec<FlightCo2Row> = stmt
             .query_map(rusqlite::params![user_id], |row| {
                 Ok((
                     row.get(0)?,
@@ -450,6 +706,7 @@ pub async fn batch_calculate_streaming(
                     row.get(3)?,
                     row.get(4)?,
                     row.get(5)?,
+                    row.get(6)?,
                 ))
             })
             .map_err(|e| e.to_string())?
@@ -461,10 +718,18 @@ pub async fn batch_calculate_streaming(
         let mut skipped_count = 0;
         let mut calculations: Vec<CO2CalculationResult> = vec![];
 
-        for (i, (flight_id, _departure, _arrival, distance_km, notes, aircraft_reg)) in flights.into_iter().enumerate() {
+        let weights = super::calculations::co2_class_weights(&db)?;
+
+        for (i, (flight_id, distance_km, notes, aircraft_reg, fare_class, stored_count, aircraft_type_id)) in
+            flights.into_iter().enumerate()
+        {
+            if operation.is_cancelled() {
+                break;
+            }
+
             // Emit progress every batch_size items or on last item
             if i % batch_size == 0 || i == total - 1 {
-                let _ = app_handle.emit("batch-progress", BatchProgressUpdate {
+                report_progress(operation, BatchProgressUpdate {
                     phase: "co2".to_string(),
                     current: i + 1,
                     total,
@@ -475,21 +740,26 @@ pub async fn batch_calculate_streaming(
                 });
             }
 
-            // Count passengers from notes field
-            let passenger_count = count_passengers_from_notes(notes.as_deref());
+            let passenger_count = resolve_passenger_count(&db.conn, &flight_id, stored_count, notes.as_deref())
+                .map_err(|e| e.to_string())?;
 
             if passenger_count == 0 {
                 skipped_count += 1;
                 continue;
             }
 
-            let aircraft_type = aircraft_reg.as_deref();
-            let total_co2_kg = crate::calculations::calculate_co2_emissions(distance_km, aircraft_type);
-            let per_passenger_co2_kg = total_co2_kg / passenger_count as f64;
+            let aircraft = AircraftRef { registration: aircraft_reg.as_deref(), aircraft_type_id: aircraft_type_id.as_deref() };
+            let total_co2_kg = performance_for(&db, aircraft).co2_emissions(distance_km);
+            let (per_passenger_co2_kg, split_method) = crate::calculations::passenger_co2_share(
+                total_co2_kg,
+                passenger_count,
+                fare_class.as_deref(),
+                Some(&weights),
+            );
 
             match db.conn.execute(
-                "UPDATE flights SET carbon_emissions_kg = ?1, per_passenger_co2_kg = ?2, updated_at = datetime('now') WHERE id = ?3",
-                rusqlite::params![total_co2_kg, per_passenger_co2_kg, flight_id],
+                "UPDATE flights SET carbon_emissions_kg = ?1, per_passenger_co2_kg = ?2, co2_split_method = ?3, updated_at = datetime('now') WHERE id = ?4",
+                rusqlite::params![total_co2_kg, per_passenger_co2_kg, split_method.as_str(), flight_id],
             ) {
                 Ok(_) => {
                     updated_count += 1;
@@ -498,6 +768,8 @@ pub async fn batch_calculate_streaming(
                         total_co2_kg,
                         passenger_count,
                         per_passenger_co2_kg,
+                        fare_class,
+                        split_method: split_method.as_str().to_string(),
                     });
                 }
                 Err(_) => {
@@ -507,7 +779,7 @@ pub async fn batch_calculate_streaming(
         }
 
         // Emit CO2 phase complete
-        let _ = app_handle.emit("batch-progress", BatchProgressUpdate {
+        report_progress(operation, BatchProgressUpdate {
             phase: "co2".to_string(),
             current: total,
             total,
@@ -527,6 +799,153 @@ pub async fn batch_calculate_streaming(
     Ok(StreamingBatchResult {
         distance_result,
         co2_result,
+        cancelled: operation.is_cancelled(),
+    })
+}
+
+// ===== DISTANCE CONSISTENCY =====
+
+/// Default allowed difference between distance_km and distance_nm * 1.852
+const DEFAULT_DISTANCE_TOLERANCE_KM: f64 = 1.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistanceInconsistency {
+    pub flight_id: String,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    pub distance_nm: Option<f64>,
+    pub distance_km: Option<f64>,
+    /// distance_nm converted to km
+    pub expected_km: Option<f64>,
+    /// Absolute difference in km; None when one column is missing
+    pub difference_km: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistanceFixResult {
+    /// Column the other was recomputed from ("nm" or "km")
+    pub authoritative: String,
+    pub fixed_count: usize,
+    pub inconsistencies: Vec<DistanceInconsistency>,
+}
+
+/// Compare the two distance columns; a missing column only counts when the other is set
+fn check_distance_pair(
+    distance_nm: Option<f64>,
+    distance_km: Option<f64>,
+    tolerance_km: f64,
+) -> Option<(Option<f64>, Option<f64>)> {
+    let present = |v: Option<f64>| v.filter(|d| *d > 0.0);
+    match (present(distance_nm), present(distance_km)) {
+        (Some(nm), Some(km)) => {
+            let expected = nm * KM_PER_NM;
+            let difference = (km - expected).abs();
+            (difference > tolerance_km).then_some((Some(expected), Some(difference)))
+        }
+        (Some(nm), None) => Some((Some(nm * KM_PER_NM), None)),
+        (None, Some(_)) => Some((None, None)),
+        (None, None) => None,
+    }
+}
+
+fn collect_distance_inconsistencies(
+    db: &crate::database::Database,
+    user_id: &str,
+    tolerance_km: f64,
+) -> Result<Vec<DistanceInconsistency>, String> {
+    let mut stmt = db.conn.prepare(
+        "SELECT id, departure_airport, arrival_airport, distance_nm, distance_km
+         FROM flights
+         WHERE user_id = ?1 AND deleted_at IS NULL AND (distance_nm > 0 OR distance_km > 0)
+         ORDER BY departure_datetime"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(rusqlite::params![user_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<f64>>(3)?,
+            row.get::<_, Option<f64>>(4)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let mut inconsistencies = Vec::new();
+    for row in rows {
+        let (flight_id, departure_airport, arrival_airport, distance_nm, distance_km) =
+            row.map_err(|e| e.to_string())?;
+        if let Some((expected_km, difference_km)) = check_distance_pair(distance_nm, distance_km, tolerance_km) {
+            inconsistencies.push(DistanceInconsistency {
+                flight_id,
+                departure_airport,
+                arrival_airport,
+                distance_nm,
+                distance_km,
+                expected_km,
+                difference_km,
+            });
+        }
+    }
+
+    Ok(inconsistencies)
+}
+
+/// Find flights where distance_km and distance_nm * 1.852 disagree beyond a tolerance
+/// (default 1 km), or where only one of the two columns is set
+#[tauri::command]
+pub fn find_distance_inconsistencies(
+    user_id: String,
+    tolerance_km: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DistanceInconsistency>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    collect_distance_inconsistencies(&db, &user_id, tolerance_km.unwrap_or(DEFAULT_DISTANCE_TOLERANCE_KM))
+}
+
+/// Recompute one distance column from the other for every inconsistent flight
+/// `authoritative` is "nm" (default, the column create/import paths derive from) or "km".
+/// Flights missing the authoritative column are filled from the other one instead.
+#[tauri::command]
+pub fn fix_distance_inconsistencies(
+    user_id: String,
+    authoritative: Option<String>,
+    tolerance_km: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<DistanceFixResult, String> {
+    let authoritative = match authoritative.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("nm") => "nm",
+        Some("km") => "km",
+        Some(other) => return Err(format!("Unknown authoritative column '{}' (expected nm or km)", other)),
+    };
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let inconsistencies =
+        collect_distance_inconsistencies(&db, &user_id, tolerance_km.unwrap_or(DEFAULT_DISTANCE_TOLERANCE_KM))?;
+
+    // All fixes land together or not at all; dropping the transaction on error rolls back
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut fixed_count = 0;
+    for item in &inconsistencies {
+        let nm = item.distance_nm.filter(|d| *d > 0.0);
+        let km = item.distance_km.filter(|d| *d > 0.0);
+        let (distance_nm, distance_km) = match (authoritative, nm, km) {
+            ("nm", Some(nm), _) | ("km", Some(nm), None) => (nm, nm * KM_PER_NM),
+            (_, _, Some(km)) => (km / KM_PER_NM, km),
+            _ => continue,
+        };
+
+        let updated = tx.execute(
+            "UPDATE flights SET distance_nm = ?1, distance_km = ?2 WHERE id = ?3",
+            rusqlite::params![distance_nm, distance_km, item.flight_id],
+        ).map_err(|e| e.to_string())?;
+        fixed_count += updated;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(DistanceFixResult {
+        authoritative: authoritative.to_string(),
+        fixed_count,
+        inconsistencies,
     })
 }
 
@@ -551,37 +970,38 @@ pub fn get_calculation_stats(
     let db = state.db.lock().map_err(|e| e.to_string())?;
 
     let total_flights: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     let flights_with_distance: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND distance_km IS NOT NULL AND distance_km > 0",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND distance_km IS NOT NULL AND distance_km > 0",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     let flights_missing_distance: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND (distance_km IS NULL OR distance_km = 0)",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND (distance_km IS NULL OR distance_km = 0)",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     let flights_with_co2: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND carbon_emissions_kg IS NOT NULL AND carbon_emissions_kg > 0",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND carbon_emissions_kg IS NOT NULL AND carbon_emissions_kg > 0",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     let flights_with_per_passenger_co2: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND per_passenger_co2_kg IS NOT NULL AND per_passenger_co2_kg > 0",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND per_passenger_co2_kg IS NOT NULL AND per_passenger_co2_kg > 0",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     let flights_with_passengers: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND notes IS NOT NULL AND notes LIKE 'Passengers:%'",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
+         AND (passenger_count > 0 OR (passenger_count IS NULL AND notes LIKE 'Passengers:%'))",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
@@ -595,3 +1015,78 @@ pub fn get_calculation_stats(
         flights_with_passengers,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_distance_pair() {
+        assert!(check_distance_pair(Some(100.0), Some(185.3), 1.0).is_none());
+        assert!(check_distance_pair(None, None, 1.0).is_none());
+
+        let (expected, difference) = check_distance_pair(Some(100.0), Some(250.0), 1.0).unwrap();
+        assert!((expected.unwrap() - 185.2).abs() < 1e-9);
+        assert!((difference.unwrap() - 64.8).abs() < 1e-9);
+
+        // Stale or missing nm alongside a km value is still flagged
+        assert_eq!(check_distance_pair(Some(0.0), Some(185.2), 1.0), Some((None, None)));
+        assert!(check_distance_pair(Some(100.0), None, 1.0).unwrap().1.is_none());
+    }
+
+    #[test]
+    fn test_count_passengers_from_notes() {
+        assert_eq!(count_passengers_from_notes(Some("Passengers: A, B, C")), 3);
+        assert_eq!(count_passengers_from_notes(Some("Passengers: A; B\nSource page: 4")), 2);
+        assert_eq!(count_passengers_from_notes(Some("Passengers: A, B | Original date: 1/2/03, 4")), 2);
+        assert_eq!(count_passengers_from_notes(Some("Met A, B")), 0);
+        assert_eq!(count_passengers_from_notes(None), 0);
+    }
+
+    #[test]
+    fn test_backfill_per_passenger() {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, carbon_emissions_kg, notes)
+                 VALUES ('junction', 'u1', 'KPBI', 'KTEB', '2024-01-01T10:00:00', 900.0, 'Passengers: A, B'),
+                        ('notes', 'u1', 'KTEB', 'KPBI', '2024-01-02T10:00:00', 900.0, 'Passengers: A, B'),
+                        ('empty', 'u1', 'KTEB', 'KPBI', '2024-01-03T10:00:00', 900.0, NULL);
+                 INSERT INTO passengers (id, canonical_name) VALUES ('p1', 'A'), ('p2', 'B'), ('p3', 'C');
+                 INSERT INTO flight_passengers (flight_id, passenger_id) VALUES ('junction', 'p1'), ('junction', 'p2'), ('junction', 'p3');
+                 INSERT INTO flight_passengers (flight_id, passenger_id, role) VALUES ('notes', 'p1', 'crew');",
+            )
+            .unwrap();
+        let per_passenger = |id: &str| -> Option<f64> {
+            db.conn
+                .query_row("SELECT per_passenger_co2_kg FROM flights WHERE id = ?1", [id], |row| row.get(0))
+                .unwrap()
+        };
+        let operation = OperationRegistry::default().start("test", None).unwrap();
+        let weights = crate::calculations::CabinClassWeights::default();
+
+        let result = backfill_per_passenger(&db, "u1", ZeroPassengerPolicy::LeaveNull, &weights, false, &operation).unwrap();
+        assert_eq!((result.updated_count, result.from_junction, result.from_notes, result.no_passengers), (2, 1, 1, 1));
+        assert_eq!(per_passenger("junction"), Some(300.0));
+        assert_eq!(per_passenger("notes"), Some(450.0));
+        assert_eq!(per_passenger("empty"), None);
+
+        // Derived counts are stored and preferred from then on
+        let stored = |id: &str| -> Option<i32> {
+            db.conn
+                .query_row("SELECT passenger_count FROM flights WHERE id = ?1", [id], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!((stored("junction"), stored("notes"), stored("empty")), (Some(3), Some(2), None));
+        db.conn.execute("UPDATE flights SET passenger_count = 4 WHERE id = 'notes'", []).unwrap();
+        let result = backfill_per_passenger(&db, "u1", ZeroPassengerPolicy::LeaveNull, &weights, true, &operation).unwrap();
+        assert_eq!((result.from_stored, result.from_junction, result.from_notes), (2, 0, 0));
+        assert_eq!(per_passenger("notes"), Some(225.0));
+
+        // Filled flights are left alone; the empty one now gets the total
+        let result = backfill_per_passenger(&db, "u1", ZeroPassengerPolicy::Total, &weights, false, &operation).unwrap();
+        assert_eq!(result.updated_count, 1);
+        assert_eq!(per_passenger("empty"), Some(900.0));
+    }
+}
@@ -1,13 +1,31 @@
 // Calculation-related commands
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use super::AppState;
+use crate::aircraft_performance::{self, performance_for, AircraftProfile, AircraftProfileInput, AircraftRef};
+use crate::calculations::{CabinClassWeights, EmissionEquivalents, EmissionFactors};
+use crate::error::CommandError;
+use crate::ghg_report::{GhgReport, GhgSettings, Operation};
+use crate::validation::FieldErrors;
 
+const EMISSION_FACTORS_SETTING: &str = "emission_factors";
+const CLASS_WEIGHTS_SETTING: &str = "co2_class_weights";
+
+/// Distance between two coordinates; `method` is "geodesic" (WGS84, default) or "haversine"
 #[tauri::command]
 pub fn calculate_distance(
     lat1: f64,
     lon1: f64,
     lat2: f64,
     lon2: f64,
+    method: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    let (distance_nm, distance_km) = crate::geo::calculate_distance(lat1, lon1, lat2, lon2);
+    let method = match method.as_deref() {
+        Some(name) => crate::geo::DistanceMethod::from_name(name)
+            .ok_or_else(|| format!("Unknown distance method: {}", name))?,
+        None => crate::geo::DistanceMethod::default(),
+    };
+    let (distance_nm, distance_km) = crate::geo::calculate_distance_with(method, lat1, lon1, lat2, lon2);
 
     Ok(serde_json::json!({
         "distance_nm": distance_nm,
@@ -15,33 +33,70 @@ pub fn calculate_distance(
     }))
 }
 
+/// CO2 in kg, from the aircraft's performance profile when it has one
 #[tauri::command]
-pub fn calculate_co2_emissions(distance_km: f64, aircraft_type: Option<String>) -> f64 {
-    crate::calculations::calculate_co2_emissions(
-        distance_km,
-        aircraft_type.as_deref(),
-    )
+pub fn calculate_co2_emissions(
+    distance_km: f64,
+    aircraft_type: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<f64, CommandError> {
+    let db = state.db.lock()?;
+    Ok(performance_for(&db, AircraftRef::named(aircraft_type.as_deref())).co2_emissions(distance_km))
 }
 
+/// Per-passenger CO2; with a cabin class the share is weighted by seat class
+/// (default weights unless `class_weights` is given), otherwise split equally
 #[tauri::command]
 pub fn calculate_per_passenger_co2(
     distance_km: f64,
     aircraft_type: Option<String>,
     passenger_count: Option<u32>,
-) -> f64 {
-    crate::calculations::calculate_per_passenger_co2(
-        distance_km,
-        aircraft_type.as_deref(),
-        passenger_count,
-    )
+    cabin_class: Option<String>,
+    class_weights: Option<CabinClassWeights>,
+    state: State<'_, AppState>,
+) -> Result<f64, CommandError> {
+    let performance = performance_for(&*state.db.lock()?, AircraftRef::named(aircraft_type.as_deref()));
+    let equal_share = crate::calculations::per_passenger_co2(&performance, distance_km, passenger_count);
+    Ok(match (cabin_class.as_deref(), passenger_count) {
+        (Some(class), Some(count)) if count > 0 => {
+            let weights = class_weights.unwrap_or_default();
+            let total_co2 = equal_share * count as f64;
+            crate::calculations::passenger_co2_share(total_co2, count, Some(class), Some(&weights)).0
+        }
+        _ => equal_share,
+    })
+}
+
+/// Flight minutes, from the aircraft's performance profile when it has one
+#[tauri::command]
+pub fn calculate_flight_duration(
+    distance_km: f64,
+    aircraft_type: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<i32, CommandError> {
+    let db = state.db.lock()?;
+    Ok(performance_for(&db, AircraftRef::named(aircraft_type.as_deref())).flight_time(distance_km))
 }
 
+/// Estimate a planned flight (distance, time, fuel, fuel cost, CO2) without recording it
 #[tauri::command]
-pub fn calculate_flight_duration(distance_km: f64, aircraft_type: Option<String>) -> i32 {
-    crate::calculations::calculate_flight_time(
-        distance_km,
+pub fn simulate_flight(
+    user_id: String,
+    from: String,
+    to: String,
+    aircraft_type: Option<String>,
+    passenger_count: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<crate::flight_simulation::FlightSimulation, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::flight_simulation::simulate(
+        &db,
+        &user_id,
+        &from,
+        &to,
         aircraft_type.as_deref(),
-    )
+        passenger_count,
+    )?)
 }
 
 #[tauri::command]
@@ -53,3 +108,268 @@ pub fn fuel_kg_to_liters(fuel_kg: f64) -> f64 {
 pub fn fuel_kg_to_gallons(fuel_kg: f64) -> f64 {
     crate::calculations::fuel_kg_to_gallons(fuel_kg)
 }
+
+/// A flight's CO2 as everyday equivalents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmissionsContext {
+    pub flight_id: String,
+    /// "passenger_share" when the flight has a per-passenger CO2 figure, else "flight_total"
+    pub basis: String,
+    #[serde(flatten)]
+    pub equivalents: EmissionEquivalents,
+    pub factors: EmissionFactors,
+}
+
+/// Emission factors from the setting, defaults when unset or invalid
+fn emission_factors(db: &crate::database::Database) -> EmissionFactors {
+    db.get_setting(EMISSION_FACTORS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<EmissionFactors>(&json).ok())
+        .filter(|factors| factors.invalid_fields().is_empty())
+        .unwrap_or_default()
+}
+
+/// A flight's CO2 (the traveller's share when known) expressed as car km, train km and tree-years
+#[tauri::command]
+pub fn get_emissions_context(flight_id: String, state: State<'_, AppState>) -> Result<EmissionsContext, CommandError> {
+    let db = state.db.lock()?;
+    let flight = db
+        .get_flight(&flight_id)?
+        .ok_or_else(|| CommandError::not_found("Flight", &flight_id))?;
+
+    let (co2_kg, basis) = match (flight.per_passenger_co2_kg, flight.carbon_emissions_kg) {
+        (Some(share), _) => (share, "passenger_share"),
+        (None, Some(total)) => (total, "flight_total"),
+        (None, None) => {
+            return Err(CommandError::Validation(
+                "This flight has no CO2 estimate yet; recalculate CO2 first".to_string(),
+            ))
+        }
+    };
+
+    let factors = emission_factors(&db);
+    Ok(EmissionsContext {
+        flight_id,
+        basis: basis.to_string(),
+        equivalents: crate::calculations::emission_equivalents(co2_kg, &factors),
+        factors,
+    })
+}
+
+#[tauri::command]
+pub fn get_emission_factors(state: State<'_, AppState>) -> Result<EmissionFactors, CommandError> {
+    let db = state.db.lock()?;
+    Ok(emission_factors(&db))
+}
+
+/// Save the emission factors; None restores the defaults
+#[tauri::command]
+pub fn set_emission_factors(
+    factors: Option<EmissionFactors>,
+    state: State<'_, AppState>,
+) -> Result<EmissionFactors, CommandError> {
+    let db = state.db.lock()?;
+    match factors {
+        Some(factors) => {
+            let mut errors = FieldErrors::default();
+            for field in factors.invalid_fields() {
+                errors.add(field, "must be a positive number");
+            }
+            errors.into_result()?;
+            let json = serde_json::to_string(&factors).map_err(|e| CommandError::Internal(e.to_string()))?;
+            db.set_setting(EMISSION_FACTORS_SETTING, &json)?;
+            Ok(factors)
+        }
+        None => {
+            db.conn
+                .execute("DELETE FROM settings WHERE key = ?1", [EMISSION_FACTORS_SETTING])?;
+            Ok(EmissionFactors::default())
+        }
+    }
+}
+
+/// Cabin class weights for the per-passenger CO2 split, defaults when unset
+/// A stored value that no longer parses or validates is an error rather than a silent
+/// fallback, so a bad edit never quietly changes every recalculated flight.
+pub(crate) fn co2_class_weights(db: &crate::database::Database) -> Result<CabinClassWeights, CommandError> {
+    let Some(json) = db.get_setting(CLASS_WEIGHTS_SETTING)? else {
+        return Ok(CabinClassWeights::default());
+    };
+    let weights: CabinClassWeights = serde_json::from_str(&json).map_err(|e| {
+        CommandError::Validation(format!("The {} setting is not valid JSON weights: {}", CLASS_WEIGHTS_SETTING, e))
+    })?;
+    let invalid = weights.invalid_fields();
+    if !invalid.is_empty() {
+        return Err(CommandError::Validation(format!(
+            "The {} setting has weights that are not positive numbers: {}",
+            CLASS_WEIGHTS_SETTING,
+            invalid.join(", ")
+        )));
+    }
+    Ok(weights)
+}
+
+#[tauri::command]
+pub fn get_co2_class_weights(state: State<'_, AppState>) -> Result<CabinClassWeights, CommandError> {
+    let db = state.db.lock()?;
+    co2_class_weights(&db)
+}
+
+/// Save the cabin class weights; None restores the defaults
+#[tauri::command]
+pub fn set_co2_class_weights(
+    weights: Option<CabinClassWeights>,
+    state: State<'_, AppState>,
+) -> Result<CabinClassWeights, CommandError> {
+    let db = state.db.lock()?;
+    match weights {
+        Some(weights) => {
+            let mut errors = FieldErrors::default();
+            for field in weights.invalid_fields() {
+                errors.add(field, "must be a positive number");
+            }
+            errors.into_result()?;
+            let json = serde_json::to_string(&weights).map_err(|e| CommandError::Internal(e.to_string()))?;
+            db.set_setting(CLASS_WEIGHTS_SETTING, &json)?;
+            Ok(weights)
+        }
+        None => {
+            db.conn.execute("DELETE FROM settings WHERE key = ?1", [CLASS_WEIGHTS_SETTING])?;
+            Ok(CabinClassWeights::default())
+        }
+    }
+}
+
+// ===== GHG PROTOCOL REPORT =====
+
+/// Flight emissions by GHG Protocol scope between `start_date` and `end_date` (YYYY-MM-DD,
+/// inclusive), with the stored report settings
+#[tauri::command]
+pub fn get_ghg_report(
+    user_id: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<GhgReport, CommandError> {
+    let db = state.db.lock()?;
+    let settings = GhgSettings::configured(&db);
+    Ok(crate::ghg_report::ghg_report(&db, &user_id, start_date.as_deref(), end_date.as_deref(), &settings)?)
+}
+
+#[tauri::command]
+pub fn get_ghg_settings(state: State<'_, AppState>) -> Result<GhgSettings, CommandError> {
+    let db = state.db.lock()?;
+    Ok(GhgSettings::configured(&db))
+}
+
+/// Save the GHG report settings; None restores the defaults
+#[tauri::command]
+pub fn set_ghg_settings(settings: Option<GhgSettings>, state: State<'_, AppState>) -> Result<GhgSettings, CommandError> {
+    let db = state.db.lock()?;
+    match settings {
+        Some(settings) => {
+            let mut errors = FieldErrors::default();
+            for (field, message) in settings.invalid_fields() {
+                errors.add(field, message);
+            }
+            errors.into_result()?;
+            let settings = settings.normalized();
+            let json = serde_json::to_string(&settings).map_err(|e| CommandError::Internal(e.to_string()))?;
+            db.set_setting(crate::ghg_report::SETTINGS_KEY, &json)?;
+            Ok(settings)
+        }
+        None => {
+            db.conn
+                .execute("DELETE FROM settings WHERE key = ?1", [crate::ghg_report::SETTINGS_KEY])?;
+            Ok(GhgSettings::default())
+        }
+    }
+}
+
+/// The operation type tagged on a flight, if any
+#[tauri::command]
+pub fn get_flight_operation_type(flight_id: String, state: State<'_, AppState>) -> Result<Option<Operation>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::ghg_report::flight_operation(&db.conn, &flight_id)?)
+}
+
+/// Mark how a flight was operated ("own", "chartered" or "commercial"), which decides its
+/// GHG scope; None goes back to the aircraft's listing in the settings
+#[tauri::command]
+pub fn set_flight_operation_type(
+    flight_id: String,
+    operation_type: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<Operation>, CommandError> {
+    let operation = match operation_type.as_deref() {
+        Some(name) => Some(Operation::parse(name).ok_or_else(|| {
+            CommandError::Validation(format!("Unknown operation type \"{}\"; expected own, chartered or commercial", name))
+        })?),
+        None => None,
+    };
+    let db = state.db.lock()?;
+    if db.get_flight(&flight_id)?.is_none() {
+        return Err(CommandError::not_found("Flight", &flight_id));
+    }
+    crate::ghg_report::set_flight_operation(&db.conn, &flight_id, operation)?;
+    Ok(operation)
+}
+
+// ===== AIRCRAFT PERFORMANCE PROFILES =====
+
+/// Stored profiles, registrations first
+#[tauri::command]
+pub fn list_aircraft_performance(state: State<'_, AppState>) -> Result<Vec<AircraftProfile>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(aircraft_performance::list(&db.conn)?)
+}
+
+/// Create or replace the profile for a type designator or registration
+#[tauri::command]
+pub fn save_aircraft_performance(
+    profile: AircraftProfileInput,
+    state: State<'_, AppState>,
+) -> Result<AircraftProfile, CommandError> {
+    let mut errors = FieldErrors::default();
+    for (field, message) in profile.invalid_fields() {
+        errors.add(field, message);
+    }
+    errors.into_result()?;
+    let db = state.db.lock()?;
+    Ok(aircraft_performance::save(&db.conn, &profile)?)
+}
+
+/// Delete a profile; its aircraft go back to the type heuristics
+#[tauri::command]
+pub fn delete_aircraft_performance(profile_id: String, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let db = state.db.lock()?;
+    if !aircraft_performance::delete(&db.conn, &profile_id)? {
+        return Err(CommandError::not_found("Aircraft performance profile", &profile_id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_co2_class_weights_setting() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        assert_eq!(co2_class_weights(&db).unwrap().business, 2.0);
+
+        db.set_setting(CLASS_WEIGHTS_SETTING, r#"{"economy": 1, "premium_economy": 1.2, "business": 2.5, "first": 4}"#)
+            .unwrap();
+        assert_eq!(co2_class_weights(&db).unwrap().business, 2.5);
+
+        db.set_setting(CLASS_WEIGHTS_SETTING, r#"{"economy": 1"#).unwrap();
+        assert_eq!(co2_class_weights(&db).unwrap_err().code(), "validation");
+
+        db.set_setting(CLASS_WEIGHTS_SETTING, r#"{"economy": 0, "premium_economy": 1.2, "business": 2.5, "first": 4}"#)
+            .unwrap();
+        let err = co2_class_weights(&db).unwrap_err();
+        assert!(err.to_string().contains("economy"));
+    }
+}
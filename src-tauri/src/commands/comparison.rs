@@ -0,0 +1,270 @@
+// Flight and period comparison commands
+// Field-by-field diff of two flights and side-by-side aggregates for two date ranges
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::AppState;
+use crate::models::Flight;
+
+/// Bookkeeping fields left out of the flight diff
+const IGNORED_FIELDS: [&str; 4] = ["id", "user_id", "created_at", "updated_at"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub value_1: Option<String>,
+    pub value_2: Option<String>,
+}
+
+/// One numeric metric compared between A and B
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub value_a: Option<f64>,
+    pub value_b: Option<f64>,
+    /// value_b - value_a, None when either side is missing
+    pub delta: Option<f64>,
+    /// None when either side is missing or value_a is zero
+    pub percent_change: Option<f64>,
+    /// Formatted change such as "+12.5%", or "n/a"
+    pub percent_change_label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightComparison {
+    pub flight_1: Flight,
+    pub flight_2: Flight,
+    /// Only the fields whose values differ
+    pub differences: Vec<FieldDiff>,
+    pub metrics: Vec<MetricDelta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateRange {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeriodStats {
+    pub flight_count: i64,
+    pub distance_km: f64,
+    pub flight_hours: f64,
+    pub co2_kg: f64,
+    pub total_cost: f64,
+    pub airports_visited: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodComparison {
+    pub range_a: DateRange,
+    pub range_b: DateRange,
+    pub stats_a: PeriodStats,
+    pub stats_b: PeriodStats,
+    pub metrics: Vec<MetricDelta>,
+}
+
+/// Compare two flights: differing fields plus distance, duration, CO2 and cost deltas
+/// (flight 2 relative to flight 1)
+#[tauri::command]
+pub fn compare_flights(
+    flight_id_1: String,
+    flight_id_2: String,
+    state: State<'_, AppState>,
+) -> Result<FlightComparison, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let flight_1 = db
+        .get_flight(&flight_id_1)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Flight not found: {}", flight_id_1))?;
+    let flight_2 = db
+        .get_flight(&flight_id_2)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Flight not found: {}", flight_id_2))?;
+
+    let differences = diff_flights(&flight_1, &flight_2).map_err(|e| e.to_string())?;
+    let duration = |f: &Flight| f.flight_duration.or(f.total_duration).map(f64::from);
+    let metrics = vec![
+        metric_delta("distance_km", flight_1.distance_km, flight_2.distance_km),
+        metric_delta("duration_minutes", duration(&flight_1), duration(&flight_2)),
+        metric_delta("co2_kg", flight_1.carbon_emissions_kg, flight_2.carbon_emissions_kg),
+        metric_delta("total_cost", flight_1.total_cost, flight_2.total_cost),
+    ];
+
+    Ok(FlightComparison {
+        flight_1,
+        flight_2,
+        differences,
+        metrics,
+    })
+}
+
+/// Aggregate stats for two date ranges side by side, with B's change relative to A
+/// Dates are inclusive YYYY-MM-DD.
+#[tauri::command]
+pub fn compare_periods(
+    user_id: String,
+    range_a: DateRange,
+    range_b: DateRange,
+    state: State<'_, AppState>,
+) -> Result<PeriodComparison, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let stats_a = period_stats(&db.conn, &user_id, &range_a).map_err(|e| e.to_string())?;
+    let stats_b = period_stats(&db.conn, &user_id, &range_b).map_err(|e| e.to_string())?;
+
+    let metrics = vec![
+        metric_delta("flight_count", Some(stats_a.flight_count as f64), Some(stats_b.flight_count as f64)),
+        metric_delta("distance_km", Some(stats_a.distance_km), Some(stats_b.distance_km)),
+        metric_delta("flight_hours", Some(stats_a.flight_hours), Some(stats_b.flight_hours)),
+        metric_delta("co2_kg", Some(stats_a.co2_kg), Some(stats_b.co2_kg)),
+        metric_delta("total_cost", Some(stats_a.total_cost), Some(stats_b.total_cost)),
+        metric_delta(
+            "airports_visited",
+            Some(stats_a.airports_visited as f64),
+            Some(stats_b.airports_visited as f64),
+        ),
+    ];
+
+    Ok(PeriodComparison {
+        range_a,
+        range_b,
+        stats_a,
+        stats_b,
+        metrics,
+    })
+}
+
+fn period_stats(conn: &rusqlite::Connection, user_id: &str, range: &DateRange) -> rusqlite::Result<PeriodStats> {
+    conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM(distance_km), 0.0),
+                COALESCE(SUM(COALESCE(flight_duration, total_duration)), 0) / 60.0,
+                COALESCE(SUM(carbon_emissions_kg), 0.0),
+                COALESCE(SUM(total_cost), 0.0),
+                (SELECT COUNT(DISTINCT code) FROM (
+                    SELECT departure_airport AS code FROM flights
+                    WHERE user_id = ?1 AND deleted_at IS NULL
+                      AND departure_datetime >= ?2 AND substr(departure_datetime, 1, 10) <= ?3
+                    UNION
+                    SELECT arrival_airport FROM flights
+                    WHERE user_id = ?1 AND deleted_at IS NULL
+                      AND departure_datetime >= ?2 AND substr(departure_datetime, 1, 10) <= ?3
+                ))
+         FROM flights
+         WHERE user_id = ?1 AND deleted_at IS NULL
+           AND departure_datetime >= ?2 AND substr(departure_datetime, 1, 10) <= ?3",
+        params![user_id, range.start_date, range.end_date],
+        |row| {
+            Ok(PeriodStats {
+                flight_count: row.get(0)?,
+                distance_km: row.get(1)?,
+                flight_hours: row.get(2)?,
+                co2_kg: row.get(3)?,
+                total_cost: row.get(4)?,
+                airports_visited: row.get(5)?,
+            })
+        },
+    )
+}
+
+/// Fields whose serialized values differ between the two flights
+fn diff_flights(flight_1: &Flight, flight_2: &Flight) -> serde_json::Result<Vec<FieldDiff>> {
+    let (serde_json::Value::Object(a), serde_json::Value::Object(b)) =
+        (serde_json::to_value(flight_1)?, serde_json::to_value(flight_2)?)
+    else {
+        return Ok(Vec::new());
+    };
+
+    let as_text = |value: Option<&serde_json::Value>| match value {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(s)) => Some(s.clone()),
+        Some(other) => Some(other.to_string()),
+    };
+
+    Ok(a.keys()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let value_1 = as_text(a.get(field));
+            let value_2 = as_text(b.get(field));
+            (value_1 != value_2).then(|| FieldDiff {
+                field: field.clone(),
+                value_1,
+                value_2,
+            })
+        })
+        .collect())
+}
+
+fn metric_delta(metric: &str, value_a: Option<f64>, value_b: Option<f64>) -> MetricDelta {
+    let delta = value_a.zip(value_b).map(|(a, b)| b - a);
+    let percent_change = value_a
+        .zip(delta)
+        .filter(|(a, _)| *a != 0.0)
+        .map(|(a, delta)| delta / a.abs() * 100.0)
+        .filter(|pct| pct.is_finite());
+
+    MetricDelta {
+        metric: metric.to_string(),
+        value_a,
+        value_b,
+        delta,
+        percent_change,
+        percent_change_label: percent_change
+            .map(|pct| format!("{:+.1}%", pct))
+            .unwrap_or_else(|| "n/a".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_delta() {
+        let up = metric_delta("distance_km", Some(200.0), Some(250.0));
+        assert_eq!(up.delta, Some(50.0));
+        assert_eq!(up.percent_change_label, "+25.0%");
+
+        let from_zero = metric_delta("co2_kg", Some(0.0), Some(10.0));
+        assert_eq!(from_zero.delta, Some(10.0));
+        assert_eq!(from_zero.percent_change, None);
+        assert_eq!(from_zero.percent_change_label, "n/a");
+
+        let missing = metric_delta("total_cost", None, Some(99.0));
+        assert_eq!(missing.delta, None);
+        assert_eq!(missing.percent_change_label, "n/a");
+    }
+
+    #[test]
+    fn test_diff_flights_skips_bookkeeping_fields() {
+        let flight_1: Flight = serde_json::from_value(serde_json::json!({
+            "id": "a", "user_id": "u", "flight_number": null,
+            "departure_airport": "KJFK", "arrival_airport": "KBOS",
+            "departure_datetime": "2025-01-01T10:00:00", "arrival_datetime": null,
+            "aircraft_type_id": null, "aircraft_registration": "N123AB",
+            "total_duration": null, "flight_duration": 60, "block_duration": null,
+            "distance_nm": 160.0, "distance_km": 296.3,
+            "booking_reference": null, "ticket_number": null, "seat_number": null,
+            "fare_class": null, "base_fare": null, "taxes": null, "total_cost": null,
+            "currency": null, "carbon_emissions_kg": null, "per_passenger_co2_kg": null,
+            "carbon_offset_purchased": 0, "frequent_flyer_program": null, "miles_earned": null,
+            "notes": null, "attachment_path": null, "data_source": "manual", "verified": 0,
+            "created_at": "2025-01-01", "updated_at": "2025-01-01"
+        }))
+        .unwrap();
+        let mut flight_2 = flight_1.clone();
+        flight_2.id = "b".to_string();
+        flight_2.arrival_airport = "KPWM".to_string();
+        flight_2.flight_duration = None;
+
+        let diffs = diff_flights(&flight_1, &flight_2).unwrap();
+        let fields: Vec<&str> = diffs.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields.len(), 2);
+        assert!(fields.contains(&"arrival_airport"));
+        let duration = diffs.iter().find(|d| d.field == "flight_duration").unwrap();
+        assert_eq!(duration.value_1.as_deref(), Some("60"));
+        assert_eq!(duration.value_2, None);
+    }
+}
@@ -1,7 +1,12 @@
 // CSV import commands
 use tauri::{State, Manager};
 use super::AppState;
+use crate::locale::Locale;
+use crate::currency::CurrencySettings;
+use crate::error::CommandError;
 use crate::models::FlightInput;
+use crate::validation;
+use crate::write_queue::{WritePriority, BULK_CHUNK_SIZE};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
@@ -33,6 +38,60 @@ pub struct CsvColumnMapping {
     pub passengers_column: Option<usize>,
     pub flight_number_column: Option<usize>,
     pub aircraft_registration_column: Option<usize>,
+    /// Index of the header row; logbook exports may have preamble sections before it.
+    /// None lets a preset choose it and otherwise means row 0; Some(0) is an explicit choice.
+    #[serde(default)]
+    pub header_row: Option<usize>,
+    /// Field delimiter (defaults to ",")
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// Route column ("KSFO KLAX") used for from/to when those columns are empty or point at it
+    #[serde(default)]
+    pub route_column: Option<usize>,
+    /// Pilot logbook columns, imported into pilot_logbook alongside the flight row
+    #[serde(default)]
+    pub logbook: Option<LogbookColumnMapping>,
+}
+
+impl CsvColumnMapping {
+    /// The passed mapping wins (the user may have overridden preset columns, including
+    /// an explicit header row of 0); the preset only fills in what the mapping leaves unset
+    fn fill_from_preset(&mut self, preset: CsvColumnMapping) {
+        self.header_row = self.header_row.or(preset.header_row);
+        self.delimiter = self.delimiter.take().or(preset.delimiter);
+        self.route_column = self.route_column.or(preset.route_column);
+        self.logbook = self.logbook.take().or(preset.logbook);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogbookColumnMapping {
+    pub total_time_column: Option<usize>,
+    pub pic_column: Option<usize>,
+    pub sic_column: Option<usize>,
+    pub dual_received_column: Option<usize>,
+    pub night_column: Option<usize>,
+    pub cross_country_column: Option<usize>,
+    pub actual_instrument_column: Option<usize>,
+    pub simulated_instrument_column: Option<usize>,
+    pub day_takeoffs_column: Option<usize>,
+    pub day_landings_column: Option<usize>,
+    pub night_takeoffs_column: Option<usize>,
+    pub night_landings_column: Option<usize>,
+    #[serde(default)]
+    pub approach_columns: Vec<usize>,
+    pub instructor_name_column: Option<usize>,
+    pub remarks_column: Option<usize>,
+}
+
+/// Named column layout for a logbook app export, shipped as data in csv_import_presets.json.
+/// Each field lists the header names it may appear under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvImportPreset {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub columns: HashMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,12 +105,12 @@ pub struct CsvPreviewRow {
     pub aircraft_registration: Option<String>,
     pub parsed_date: Option<String>,
     pub distance_nm: Option<f64>,
-    /// Warnings that might need user attention (not errors - all rows import)
+    /// Why the import would reject this row; empty when it imports cleanly
     pub validation_warnings: Vec<String>,
     pub raw_values: Vec<String>,
     /// Source page number for manual verification (extracted from notes or inferred)
     pub source_page: Option<u32>,
-    /// Flag indicating this row needs user review (has warnings)
+    /// Flag indicating this row needs editing before it will import (has warnings)
     pub needs_review: bool,
 }
 
@@ -65,40 +124,79 @@ pub struct CsvPreviewResult {
     pub total_rows: usize,
     /// Rows that will import cleanly
     pub clean_rows: usize,
-    /// Rows that need user review (have warnings but will still import)
+    /// Rows that need editing before they will import
     pub review_rows: usize,
+    /// True when only the first rows were read; the counts are then extrapolated from them
+    #[serde(default)]
+    pub is_estimate: bool,
+    /// Data rows actually read (all of them unless sampling stopped early)
+    #[serde(default)]
+    pub sampled_rows: usize,
 }
 
+/// Preview a CSV file. With `sample_rows` only that many data rows are read and the counts
+/// are estimated from them, which keeps the preview instant for huge exports; without it
+/// every row is read and the counts are exact.
 #[tauri::command]
 pub fn preview_csv_import(
     csv_path: String,
     max_preview_rows: Option<usize>,
+    preset: Option<String>,
+    sample_rows: Option<usize>,
+    state: State<'_, AppState>,
 ) -> Result<CsvPreviewResult, String> {
-    let file = File::open(&csv_path).map_err(|e| format!("Failed to open CSV file: {}", e))?;
+    let locale = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        Locale::configured(&db)
+    };
+    build_csv_preview(&csv_path, max_preview_rows.unwrap_or(20), preset.as_deref(), sample_rows, locale)
+}
 
-    let mut reader = ::csv::ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_reader(BufReader::new(file));
+fn build_csv_preview(
+    csv_path: &str,
+    max_rows: usize,
+    preset: Option<&str>,
+    sample_rows: Option<usize>,
+    locale: Locale,
+) -> Result<CsvPreviewResult, String> {
+    if sample_rows == Some(0) {
+        return Err("sample_rows must be at least 1".to_string());
+    }
+
+    // A preset fixes the header row, delimiter and column layout; otherwise detect from headers
+    let preset_mapping = match preset {
+        Some(id) => Some(resolve_preset_mapping(csv_path, &find_preset(id)?)?),
+        None => None,
+    };
+    let (header_row, delimiter) = preset_mapping
+        .as_ref()
+        .map(|m| (m.header_row.unwrap_or(0), csv_delimiter(m)))
+        .unwrap_or((0, b','));
+
+    let mut reader = open_csv_reader(csv_path, delimiter)?;
+    let mut records = reader.records();
 
     // Get headers
-    let headers: Vec<String> = reader
-        .headers()
+    let headers: Vec<String> = records
+        .nth(header_row)
+        .ok_or_else(|| "Failed to read CSV headers: file is empty".to_string())?
         .map_err(|e| format!("Failed to read CSV headers: {}", e))?
         .iter()
         .map(|h| h.to_string())
         .collect();
 
-    // Detect column mapping based on headers
-    let detected_mapping = detect_column_mapping(&headers);
+    let detected_mapping = match preset_mapping {
+        Some(mapping) => mapping,
+        None => detect_column_mapping(&headers),
+    };
 
-    let max_rows = max_preview_rows.unwrap_or(20);
+    let data_start = records.reader().position().byte();
     let mut all_rows = Vec::new();
     let mut total_rows = 0;
     let mut clean_rows = 0;
     let mut review_rows = 0;
 
-    for (idx, result) in reader.records().enumerate() {
+    for (idx, result) in records.by_ref().enumerate() {
         total_rows += 1;
 
         match result {
@@ -106,10 +204,11 @@ pub fn preview_csv_import(
                 let raw_values: Vec<String> = record.iter().map(|s| s.to_string()).collect();
 
                 let preview_row = parse_csv_row_preview(
-                    idx + 2, // +2 for header and 0-indexing
+                    header_row + idx + 2, // +2 for header and 0-indexing
                     &record,
                     &detected_mapping,
                     &raw_values,
+                    locale,
                 );
 
                 if preview_row.needs_review {
@@ -122,9 +221,9 @@ pub fn preview_csv_import(
             }
             Err(e) => {
                 review_rows += 1;
-                // Add row with warning for tracking - still imports
+                // Add row with warning for tracking - the import reports it as a parse error
                 all_rows.push(CsvPreviewRow {
-                    row_number: idx + 2,
+                    row_number: header_row + idx + 2,
                     date: String::new(),
                     departure_airport: String::new(),
                     arrival_airport: String::new(),
@@ -140,6 +239,25 @@ pub fn preview_csv_import(
                 });
             }
         }
+
+        if sample_rows == Some(total_rows) {
+            break;
+        }
+    }
+
+    // Stopped early: scale the counts by the bytes left, assuming the rest of the file has
+    // rows of the same average size and validity as the sample
+    let sampled_rows = total_rows;
+    let sample_end = records.reader().position().byte();
+    let file_size = std::fs::metadata(csv_path).map(|m| m.len()).unwrap_or(sample_end);
+    let is_estimate = sample_rows == Some(sampled_rows) && sample_end < file_size && sample_end > data_start;
+    if is_estimate {
+        let bytes_per_row = (sample_end - data_start) as f64 / sampled_rows as f64;
+        let estimated = (((file_size - data_start) as f64 / bytes_per_row).round() as usize).max(sampled_rows);
+        let scale = estimated as f64 / sampled_rows as f64;
+        clean_rows = (clean_rows as f64 * scale).round() as usize;
+        review_rows = estimated - clean_rows;
+        total_rows = estimated;
     }
 
     // Take first N rows for preview display
@@ -153,6 +271,8 @@ pub fn preview_csv_import(
         total_rows,
         clean_rows,
         review_rows,
+        is_estimate,
+        sampled_rows,
     })
 }
 
@@ -194,6 +314,10 @@ fn detect_column_mapping(headers: &[String]) -> CsvColumnMapping {
         passengers_column,
         flight_number_column,
         aircraft_registration_column,
+        header_row: None,
+        delimiter: None,
+        route_column: None,
+        logbook: None,
     }
 }
 
@@ -202,89 +326,29 @@ fn parse_csv_row_preview(
     record: &csv::StringRecord,
     mapping: &CsvColumnMapping,
     raw_values: &[String],
+    locale: Locale,
 ) -> CsvPreviewRow {
-    let mut validation_warnings = Vec::new();
-    let mut needs_review = false;
-
-    // Extract values
-    let date = record
-        .get(mapping.date_column)
-        .unwrap_or("")
-        .trim()
-        .to_string();
-    let departure_airport = record
-        .get(mapping.from_column)
-        .unwrap_or("")
-        .trim()
-        .to_uppercase();
-    let arrival_airport = record
-        .get(mapping.to_column)
-        .unwrap_or("")
-        .trim()
-        .to_uppercase();
-
-    let passengers = mapping
-        .passengers_column
-        .and_then(|col| record.get(col))
-        .unwrap_or("")
-        .trim()
-        .to_string();
+    let row = csv_record_row(row_number, record, mapping);
 
-    let flight_number = mapping
-        .flight_number_column
-        .and_then(|col| record.get(col))
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty());
-
-    let aircraft_registration = mapping
-        .aircraft_registration_column
-        .and_then(|col| record.get(col))
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty());
-
-    // Parse date - warn but don't reject
-    let parsed_date = if date.is_empty() {
-        validation_warnings.push("Date is empty".to_string());
-        needs_review = true;
-        None
-    } else if !is_valid_date_field(&date) {
-        validation_warnings.push("Date format unusual".to_string());
-        needs_review = true;
-        None
-    } else {
-        match parse_date(&date) {
-            Some(d) => Some(d),
-            None => {
-                validation_warnings.push("Date could not be parsed".to_string());
-                needs_review = true;
-                None
-            }
+    // Run the import's own conversion, so a clean row here is a row the import accepts
+    let validation_warnings = match csv_row_flight(&row, locale) {
+        Ok(_) => Vec::new(),
+        Err(CommandError::InvalidFields(fields)) => {
+            fields.iter().map(|f| format!("{} {}", f.field, f.message)).collect()
         }
+        Err(e) => vec![e.to_string()],
     };
-
-    // Check airports - warn but accept anything
-    if departure_airport.is_empty() {
-        validation_warnings.push("From field is empty".to_string());
-        needs_review = true;
-    } else if departure_airport.len() > 8 {
-        // Unusually long - might be misplaced data
-        validation_warnings.push("From field looks like it may contain other data".to_string());
-        needs_review = true;
-    } else if !departure_airport.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
-        validation_warnings.push("From field contains unusual characters".to_string());
-        needs_review = true;
-    }
-
-    if arrival_airport.is_empty() {
-        validation_warnings.push("To field is empty".to_string());
-        needs_review = true;
-    } else if arrival_airport.len() > 8 {
-        validation_warnings.push("To field looks like it may contain other data".to_string());
-        needs_review = true;
-    } else if !arrival_airport.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
-        validation_warnings.push("To field contains unusual characters".to_string());
-        needs_review = true;
-    }
+    let needs_review = !validation_warnings.is_empty();
+    let parsed_date = parse_row_date(&row.date, locale);
+    let EditedCsvRow {
+        date,
+        departure_airport,
+        arrival_airport,
+        passengers,
+        flight_number,
+        aircraft_registration,
+        ..
+    } = row;
 
     // Calculate distance - will work for recognized airports
     let distance_nm = crate::geo::calculate_airport_distance(&departure_airport, &arrival_airport)
@@ -298,7 +362,7 @@ fn parse_csv_row_preview(
         date,
         departure_airport,
         arrival_airport,
-        passengers,
+        passengers: passengers.unwrap_or_default(),
         flight_number,
         aircraft_registration,
         parsed_date,
@@ -310,21 +374,110 @@ fn parse_csv_row_preview(
     }
 }
 
-fn validate_csv_row(record: &csv::StringRecord, mapping: &CsvColumnMapping) -> bool {
-    let date = record.get(mapping.date_column).unwrap_or("").trim();
-    let departure = record.get(mapping.from_column).unwrap_or("").trim();
-    let arrival = record.get(mapping.to_column).unwrap_or("").trim();
+/// The values of a data row under the column mapping
+fn csv_record_row(row_number: usize, record: &csv::StringRecord, mapping: &CsvColumnMapping) -> EditedCsvRow {
+    let (departure_airport, arrival_airport) = mapped_airports(record, mapping);
+    let column = |col: Option<usize>| {
+        col.and_then(|col| record.get(col))
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
 
-    // Has errors if any validation fails
-    date.is_empty()
-        || !is_valid_date_field(date)
-        || parse_date(date).is_none()
-        || departure.is_empty()
-        || arrival.is_empty()
-        || departure.len() < 2
-        || departure.len() > 4
-        || arrival.len() < 2
-        || arrival.len() > 4
+    EditedCsvRow {
+        row_number,
+        date: record.get(mapping.date_column).unwrap_or("").trim().to_string(),
+        departure_airport,
+        arrival_airport,
+        passengers: column(mapping.passengers_column),
+        flight_number: column(mapping.flight_number_column),
+        aircraft_registration: column(mapping.aircraft_registration_column),
+    }
+}
+
+fn parse_row_date(date: &str, locale: Locale) -> Option<String> {
+    if date.is_empty() || !is_valid_date_field(date, locale) {
+        return None;
+    }
+    parse_date(date, locale)
+}
+
+/// The flight a row imports as, or why the import rejects it
+fn csv_row_flight(row: &EditedCsvRow, locale: Locale) -> Result<FlightInput, CommandError> {
+    let departure_datetime = parse_row_date(row.date.trim(), locale)
+        .ok_or_else(|| CommandError::Validation(format!("date could not be parsed, got \"{}\"", row.date)))?;
+    let departure_airport = row.departure_airport.trim().to_uppercase();
+    let arrival_airport = row.arrival_airport.trim().to_uppercase();
+    let passengers = row.passengers.as_deref().unwrap_or("").trim();
+
+    // Calculate distance - will work for recognized airports
+    let distance_nm =
+        crate::geo::calculate_airport_distance(&departure_airport, &arrival_airport).map(|(nm, _)| nm);
+
+    let flight = FlightInput {
+        flight_number: row.flight_number.clone(),
+        departure_airport,
+        arrival_airport,
+        departure_datetime,
+        arrival_datetime: None,
+        aircraft_type_id: None,
+        aircraft_registration: row.aircraft_registration.clone(),
+        total_duration: None,
+        flight_duration: None,
+        distance_nm,
+        distance_km: None,
+        carbon_emissions_kg: None,
+        booking_reference: None,
+        ticket_number: None,
+        seat_number: None,
+        fare_class: None,
+        base_fare: None,
+        taxes: None,
+        total_cost: None,
+        currency: None,
+        notes: Some(passengers)
+            .filter(|p| !p.is_empty())
+            .map(|p| format!("Passengers: {}", p)),
+        attachment_path: None,
+        passenger_count: Some(crate::calculations::count_passenger_names(passengers) as i32)
+            .filter(|count| *count > 0),
+    };
+    validation::validate_flight_input(&flight)?;
+    Ok(flight)
+}
+
+/// Largest import batch; one batch holds the writer, so interactive writes wait for it
+pub const MAX_IMPORT_BATCH_SIZE: usize = 5_000;
+
+/// A parsed row waiting to be written: (row number, flight, logbook)
+type PendingRow = (usize, FlightInput, Option<ImportedLogbook>);
+
+/// (row number, Ok(logbook error when only the logbook entry failed) or the row's error)
+type RowOutcome = (usize, Result<Option<String>, String>);
+
+/// Write a batch of rows in one transaction, so the batch costs one commit instead of one
+/// per row. If any flight fails the batch is rolled back and written again row by row: the
+/// good rows still go in and each failing row reports its own error.
+fn write_import_batch(
+    db: &crate::database::Database,
+    user_id: &str,
+    currency: &CurrencySettings,
+    batch: Vec<PendingRow>,
+) -> anyhow::Result<Vec<RowOutcome>> {
+    let write_row = |(row_number, flight, logbook): &PendingRow| -> RowOutcome {
+        let created = db.create_flight_with_currency(user_id, flight, "manual", currency).map(|flight_id| {
+            logbook.clone().and_then(|logbook| logbook.save(db, &flight_id).err().map(|e| e.to_string()))
+        });
+        (*row_number, created.map_err(|e| e.to_string()))
+    };
+
+    let tx = db.conn.unchecked_transaction()?;
+    let outcomes: Vec<RowOutcome> = batch.iter().map(write_row).collect();
+    if outcomes.iter().all(|(_, outcome)| outcome.is_ok()) {
+        tx.commit()?;
+        return Ok(outcomes);
+    }
+    tx.rollback()?;
+    Ok(batch.iter().map(write_row).collect())
 }
 
 #[tauri::command]
@@ -333,8 +486,21 @@ pub fn import_flights_from_csv_with_mapping(
     csv_path: String,
     column_mapping: CsvColumnMapping,
     edited_rows: Option<Vec<EditedCsvRow>>,
+    preset: Option<String>,
+    batch_size: Option<usize>,
     state: State<'_, AppState>,
 ) -> Result<CsvImportResult, String> {
+    let batch_size = batch_size.unwrap_or(BULK_CHUNK_SIZE);
+    if !(1..=MAX_IMPORT_BATCH_SIZE).contains(&batch_size) {
+        return Err(format!("batch_size must be between 1 and {}", MAX_IMPORT_BATCH_SIZE));
+    }
+
+    let mut column_mapping = column_mapping;
+    if let Some(id) = preset.as_deref() {
+        column_mapping.fill_from_preset(resolve_preset_mapping(&csv_path, &find_preset(id)?)?);
+    }
+    let header_row = column_mapping.header_row.unwrap_or(0);
+
     // Build a map of edited rows for quick lookup
     let edited_map: HashMap<usize, EditedCsvRow> = edited_rows
         .unwrap_or_default()
@@ -342,152 +508,47 @@ pub fn import_flights_from_csv_with_mapping(
         .map(|row| (row.row_number, row))
         .collect();
 
-    let file = File::open(&csv_path).map_err(|e| format!("Failed to open CSV file: {}", e))?;
+    let mut reader = open_csv_reader(&csv_path, csv_delimiter(&column_mapping))?;
 
-    let mut reader = ::csv::ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_reader(BufReader::new(file));
-
-    let mut success_count = 0;
     let mut error_count = 0;
     let mut errors = Vec::new();
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let (locale, currency) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        (Locale::configured(&db), CurrencySettings::configured(&db))
+    };
 
-    for (idx, result) in reader.records().enumerate() {
-        let row_number = idx + 2; // +2 for header and 0-indexing
+    let mut pending: Vec<PendingRow> = Vec::new();
+
+    for (idx, result) in reader.records().enumerate().skip(header_row + 1) {
+        let row_number = idx + 1; // header sits at idx == header_row
 
         match result {
             Ok(record) => {
                 // Check if this row has been edited
-                let flight_data = if let Some(edited) = edited_map.get(&row_number) {
-                    // Use edited data
-                    (
-                        edited.date.clone(),
-                        edited.departure_airport.clone(),
-                        edited.arrival_airport.clone(),
-                        edited.passengers.clone().unwrap_or_default(),
-                        edited.flight_number.clone(),
-                        edited.aircraft_registration.clone(),
-                    )
-                } else {
-                    // Use data from CSV with column mapping
-                    let date = record
-                        .get(column_mapping.date_column)
-                        .unwrap_or("")
-                        .trim()
-                        .to_string();
-                    let departure = record
-                        .get(column_mapping.from_column)
-                        .unwrap_or("")
-                        .trim()
-                        .to_uppercase();
-                    let arrival = record
-                        .get(column_mapping.to_column)
-                        .unwrap_or("")
-                        .trim()
-                        .to_uppercase();
-
-                    let passengers = column_mapping
-                        .passengers_column
-                        .and_then(|col| record.get(col))
-                        .unwrap_or("")
-                        .trim()
-                        .to_string();
-
-                    let flight_number = column_mapping
-                        .flight_number_column
-                        .and_then(|col| record.get(col))
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty());
-
-                    let aircraft_reg = column_mapping
-                        .aircraft_registration_column
-                        .and_then(|col| record.get(col))
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty());
-
-                    (
-                        date,
-                        departure,
-                        arrival,
-                        passengers,
-                        flight_number,
-                        aircraft_reg,
-                    )
+                let row = match edited_map.get(&row_number) {
+                    Some(edited) => edited.clone(),
+                    None => csv_record_row(row_number, &record, &column_mapping),
                 };
-
-                let (date, departure, arrival, passengers, flight_number, aircraft_registration) =
-                    flight_data;
-
-                // Build notes with all raw data for user review
-                let mut notes_parts = Vec::new();
-                if !passengers.is_empty() {
-                    notes_parts.push(format!("Passengers: {}", passengers));
-                }
-
-                // Try to parse date - use placeholder if fails
-                let departure_datetime = if date.is_empty() || !is_valid_date_field(&date) {
-                    notes_parts.push(format!("Original date: {}", date));
-                    "1900-01-01T00:00:00".to_string() // Placeholder for unparseable dates
-                } else {
-                    match parse_date(&date) {
-                        Some(dt) => dt,
-                        None => {
-                            notes_parts.push(format!("Original date: {}", date));
-                            "1900-01-01T00:00:00".to_string()
-                        }
-                    }
-                };
-
-                // Use empty string fallback for missing airports - import anyway
-                let departure_airport = if departure.is_empty() { "???".to_string() } else { departure };
-                let arrival_airport = if arrival.is_empty() { "???".to_string() } else { arrival };
-
-                // Add warning if airport codes look unusual
-                if departure_airport.len() > 4 || arrival_airport.len() > 4 {
-                    notes_parts.push(format!("Review: From={}, To={}", departure_airport, arrival_airport));
-                }
-
-                // Calculate distance - will work for recognized airports
-                let distance_nm =
-                    crate::geo::calculate_airport_distance(&departure_airport, &arrival_airport).map(|(nm, _)| nm);
-
-                let notes = if notes_parts.is_empty() { None } else { Some(notes_parts.join(" | ")) };
-
-                let flight = FlightInput {
-                    flight_number,
-                    departure_airport,
-                    arrival_airport,
-                    departure_datetime,
-                    arrival_datetime: None,
-                    aircraft_type_id: None,
-                    aircraft_registration,
-                    total_duration: None,
-                    flight_duration: None,
-                    distance_nm,
-                    distance_km: None,
-                    carbon_emissions_kg: None,
-                    booking_reference: None,
-                    ticket_number: None,
-                    seat_number: None,
-                    fare_class: None,
-                    base_fare: None,
-                    taxes: None,
-                    total_cost: None,
-                    currency: Some("USD".to_string()),
-                    notes,
-                    attachment_path: None,
-                };
-
-                match db.create_flight(&user_id, &flight) {
-                    Ok(_) => success_count += 1,
+                let mut flight = match csv_row_flight(&row, locale) {
+                    Ok(flight) => flight,
                     Err(e) => {
                         errors.push(format!("Row {}: {}", row_number, e));
                         error_count += 1;
+                        continue;
                     }
-                }
+                };
+
+                let logbook = column_mapping
+                    .logbook
+                    .as_ref()
+                    .and_then(|lb| ImportedLogbook::from_record(&record, lb, column_mapping.route_column, locale));
+                flight.flight_duration = logbook
+                    .as_ref()
+                    .and_then(|lb| lb.total_hours)
+                    .map(|hours| (hours * 60.0).round() as i32);
+
+                pending.push((row_number, flight, logbook));
             }
             Err(e) => {
                 errors.push(format!("Row {}: CSV parse error: {}", row_number, e));
@@ -496,11 +557,53 @@ pub fn import_flights_from_csv_with_mapping(
         }
     }
 
-    Ok(CsvImportResult {
-        success_count,
+    let mut result = CsvImportResult {
+        success_count: 0,
         error_count,
         errors,
-    })
+    };
+    submit_import_batches(&state.writer, &user_id, &currency, pending, batch_size, "Row", &mut result)?;
+    Ok(result)
+}
+
+/// Write parsed rows in bulk-priority batches on the write queue, so settings and single
+/// flight edits can run in between and no lock is held while the import runs
+fn submit_import_batches(
+    writer: &crate::write_queue::WriteQueue,
+    user_id: &str,
+    currency: &CurrencySettings,
+    pending: Vec<PendingRow>,
+    batch_size: usize,
+    label: &str,
+    result: &mut CsvImportResult,
+) -> Result<(), String> {
+    let mut rows = pending.into_iter().peekable();
+    while rows.peek().is_some() {
+        let batch: Vec<_> = rows.by_ref().take(batch_size).collect();
+        let user_id = user_id.to_string();
+        let currency = currency.clone();
+        let outcomes = writer
+            .submit(WritePriority::Bulk, move |db| write_import_batch(db, &user_id, &currency, batch))
+            .map_err(|e| e.to_string())?;
+
+        for (row_number, outcome) in outcomes {
+            match outcome {
+                Ok(logbook_error) => {
+                    result.success_count += 1;
+                    if let Some(e) = logbook_error {
+                        result
+                            .errors
+                            .push(format!("{} {}: flight imported but logbook entry failed: {}", label, row_number, e));
+                    }
+                }
+                Err(e) => {
+                    result.errors.push(format!("{} {}: {}", label, row_number, e));
+                    result.error_count += 1;
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -516,11 +619,15 @@ pub fn import_flights_from_csv(
         .flexible(true)
         .from_reader(BufReader::new(file));
 
-    let mut success_count = 0;
     let mut error_count = 0;
     let mut errors = Vec::new();
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let (locale, currency) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        (Locale::configured(&db), CurrencySettings::configured(&db))
+    };
+
+    let mut pending: Vec<PendingRow> = Vec::new();
 
     for (idx, result) in reader.records().enumerate() {
         let line_num = idx + 2; // +2 because line 1 is header and we're 0-indexed
@@ -535,12 +642,12 @@ pub fn import_flights_from_csv(
                 let date = record.get(0).unwrap_or("").trim();
 
                 // Skip rows where date field is empty or doesn't look like a date
-                if date.is_empty() || !is_valid_date_field(date) {
+                if date.is_empty() || !is_valid_date_field(date, locale) {
                     continue;
                 }
 
                 // Try to parse the date first - if it fails, skip this row as it's likely a header fragment
-                let departure_datetime = match parse_date(date) {
+                let departure_datetime = match parse_date(date, locale) {
                     Some(dt) => dt,
                     None => {
                         continue; // Skip invalid date rows silently (likely multiline headers)
@@ -616,22 +723,24 @@ pub fn import_flights_from_csv(
                     base_fare: None,
                     taxes: None,
                     total_cost: None,
-                    currency: Some("USD".to_string()),
+                    currency: None,
                     notes: if !passengers.is_empty() {
                         Some(format!("Passengers: {}", passengers))
                     } else {
                         None
                     },
                     attachment_path: None,
+                    passenger_count: Some(crate::calculations::count_passenger_names(passengers) as i32)
+                        .filter(|count| *count > 0),
                 };
 
-                match db.create_flight(&user_id, &flight) {
-                    Ok(_) => success_count += 1,
-                    Err(e) => {
-                        errors.push(format!("Line {}: {}", line_num, e));
-                        error_count += 1;
-                    }
+                if let Err(e) = validation::validate_flight_input(&flight) {
+                    errors.push(format!("Line {}: {}", line_num, e));
+                    error_count += 1;
+                    continue;
                 }
+
+                pending.push((line_num, flight, None));
             }
             Err(e) => {
                 errors.push(format!("Line {}: CSV parse error: {}", line_num, e));
@@ -640,46 +749,361 @@ pub fn import_flights_from_csv(
         }
     }
 
-    Ok(CsvImportResult {
-        success_count,
+    let mut result = CsvImportResult {
+        success_count: 0,
         error_count,
         errors,
-    })
+    };
+    submit_import_batches(&state.writer, &user_id, &currency, pending, BULK_CHUNK_SIZE, "Line", &mut result)?;
+    Ok(result)
 }
 
-fn is_valid_date_field(s: &str) -> bool {
+fn is_valid_date_field(s: &str, locale: Locale) -> bool {
     // Check if string looks like a date (contains digits and common separators)
     let has_digits = s.chars().any(|c| c.is_ascii_digit());
-    let has_separators = s.contains('-') || s.contains('/');
+    let has_separators = s.contains('-') || s.contains('/') || s.contains('.');
+
+    // Must have digits and either be all digits, have separators or name a month ("5 janvier 2024")
+    has_digits
+        && (s.chars().all(|c| c.is_ascii_digit()) || has_separators || locale.parse_date(s).is_some())
+}
+
+/// Parse a date in the configured locale's conventions into an ISO datetime at midnight
+fn parse_date(date_str: &str, locale: Locale) -> Option<String> {
+    locale
+        .parse_date(date_str)
+        .map(|date| format!("{}T00:00:00", date.format("%Y-%m-%d")))
+}
+
+/// List the built-in logbook export presets (ForeFlight, LogTen Pro, MyFlightbook)
+#[tauri::command]
+pub fn list_csv_import_presets() -> Result<Vec<CsvImportPreset>, String> {
+    load_presets()
+}
+
+fn load_presets() -> Result<Vec<CsvImportPreset>, String> {
+    serde_json::from_str(include_str!("csv_import_presets.json"))
+        .map_err(|e| format!("Invalid CSV import presets: {}", e))
+}
+
+fn find_preset(id: &str) -> Result<CsvImportPreset, String> {
+    load_presets()?
+        .into_iter()
+        .find(|p| p.id.eq_ignore_ascii_case(id.trim()))
+        .ok_or_else(|| format!("Unknown CSV import preset: {}", id))
+}
+
+fn open_csv_reader(csv_path: &str, delimiter: u8) -> Result<csv::Reader<BufReader<File>>, String> {
+    let file = File::open(csv_path).map_err(|e| format!("Failed to open CSV file: {}", e))?;
+
+    Ok(::csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(BufReader::new(file)))
+}
 
-    // Must have digits and either be all digits or have separators
-    has_digits && (s.chars().all(|c| c.is_ascii_digit()) || has_separators)
+fn csv_delimiter(mapping: &CsvColumnMapping) -> u8 {
+    mapping
+        .delimiter
+        .as_deref()
+        .and_then(|d| d.bytes().next())
+        .unwrap_or(b',')
 }
 
-fn parse_date(date_str: &str) -> Option<String> {
-    use chrono::NaiveDate;
+/// Tab-delimited exports (LogTen Pro default) vs comma-separated
+fn sniff_delimiter(csv_path: &str) -> Result<u8, String> {
+    use std::io::BufRead;
 
-    // Try ISO format (YYYY-MM-DD)
-    if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-        return Some(format!("{}T00:00:00", date.format("%Y-%m-%d")));
+    let file = File::open(csv_path).map_err(|e| format!("Failed to open CSV file: {}", e))?;
+    let (mut tabs, mut commas) = (0, 0);
+    for line in BufReader::new(file).lines().take(20).map_while(|l| l.ok()) {
+        tabs += line.matches('\t').count();
+        commas += line.matches(',').count();
     }
 
-    // Try US format (MM/DD/YYYY)
-    if let Ok(date) = NaiveDate::parse_from_str(date_str, "%m/%d/%Y") {
-        return Some(format!("{}T00:00:00", date.format("%Y-%m-%d")));
+    Ok(if tabs > commas { b'\t' } else { b',' })
+}
+
+/// Resolve a preset against the file's actual headers. The header row is the first
+/// row containing one of the preset's date column names (ForeFlight puts an aircraft
+/// table before the flights table).
+fn resolve_preset_mapping(csv_path: &str, preset: &CsvImportPreset) -> Result<CsvColumnMapping, String> {
+    let delimiter = sniff_delimiter(csv_path)?;
+    let mut reader = open_csv_reader(csv_path, delimiter)?;
+
+    let names = |field: &str| -> Vec<String> {
+        preset
+            .columns
+            .get(field)
+            .map(|n| n.iter().map(|h| h.trim().to_lowercase()).collect())
+            .unwrap_or_default()
+    };
+    let date_names = names("date");
+
+    let (header_row, headers) = reader
+        .records()
+        .take(200)
+        .enumerate()
+        .filter_map(|(idx, r)| r.ok().map(|rec| (idx, rec)))
+        .map(|(idx, rec)| (idx, rec.iter().map(|h| h.trim().to_lowercase()).collect::<Vec<_>>()))
+        .find(|(_, headers)| headers.iter().any(|h| date_names.contains(h)))
+        .ok_or_else(|| format!("No {} header row found in this file", preset.name))?;
+
+    // First listed name present in the headers wins
+    let column = |field: &str| -> Option<usize> {
+        names(field)
+            .iter()
+            .find_map(|name| headers.iter().position(|h| h == name))
+    };
+
+    let route_column = column("route");
+    let from_column = column("from").or(route_column);
+    let to_column = column("to").or(route_column);
+    let (from_column, to_column) = match (from_column, to_column) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Err(format!("{} export is missing From/To or Route columns", preset.name)),
+    };
+
+    let logbook = LogbookColumnMapping {
+        total_time_column: column("total_time"),
+        pic_column: column("pic"),
+        sic_column: column("sic"),
+        dual_received_column: column("dual_received"),
+        night_column: column("night"),
+        cross_country_column: column("cross_country"),
+        actual_instrument_column: column("actual_instrument"),
+        simulated_instrument_column: column("simulated_instrument"),
+        day_takeoffs_column: column("day_takeoffs"),
+        day_landings_column: column("day_landings"),
+        night_takeoffs_column: column("night_takeoffs"),
+        night_landings_column: column("night_landings"),
+        approach_columns: names("approaches")
+            .iter()
+            .filter_map(|name| headers.iter().position(|h| h == name))
+            .collect(),
+        instructor_name_column: column("instructor_name"),
+        remarks_column: column("remarks"),
+    };
+
+    Ok(CsvColumnMapping {
+        date_column: column("date").unwrap_or(0),
+        from_column,
+        to_column,
+        passengers_column: column("passengers"),
+        flight_number_column: column("flight_number"),
+        aircraft_registration_column: column("aircraft_registration"),
+        header_row: Some(header_row),
+        delimiter: Some((delimiter as char).to_string()),
+        route_column,
+        logbook: Some(logbook),
+    })
+}
+
+/// From/To airports for a row, falling back to the first/last waypoint of the route
+fn mapped_airports(record: &csv::StringRecord, mapping: &CsvColumnMapping) -> (String, String) {
+    let waypoints: Vec<String> = mapping
+        .route_column
+        .and_then(|col| record.get(col))
+        .map(|route| {
+            route
+                .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+                .filter(|w| !w.is_empty())
+                .map(|w| w.to_uppercase())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let endpoint = |column: usize, waypoint: Option<&String>| -> String {
+        let value = if Some(column) == mapping.route_column {
+            ""
+        } else {
+            record.get(column).unwrap_or("").trim()
+        };
+        if value.is_empty() {
+            waypoint.cloned().unwrap_or_default()
+        } else {
+            value.to_uppercase()
+        }
+    };
+
+    (
+        endpoint(mapping.from_column, waypoints.first()),
+        endpoint(mapping.to_column, waypoints.last()),
+    )
+}
+
+/// Logbook times and counts read from one row of a logbook export
+#[derive(Clone)]
+struct ImportedLogbook {
+    total_hours: Option<f64>,
+    pic_time: f64,
+    sic_time: f64,
+    dual_time: f64,
+    night_time: f64,
+    cross_country_time: f64,
+    actual_instrument_time: f64,
+    simulated_instrument_time: f64,
+    day_takeoffs: i32,
+    day_landings: i32,
+    night_takeoffs: i32,
+    night_landings: i32,
+    /// ILS, VOR, NDB, GPS, visual
+    approaches: [i32; 5],
+    untyped_approaches: i32,
+    instructor_name: Option<String>,
+    route: Option<String>,
+    remarks: Option<String>,
+}
+
+impl ImportedLogbook {
+    /// None when the row carries no logbook data at all
+    fn from_record(
+        record: &csv::StringRecord,
+        mapping: &LogbookColumnMapping,
+        route_column: Option<usize>,
+        locale: Locale,
+    ) -> Option<Self> {
+        let text = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let hours = |col: Option<usize>| text(col).and_then(|v| parse_logbook_hours(&v, locale));
+        let count = |col: Option<usize>| {
+            text(col)
+                .and_then(|v| locale.parse_number(&v))
+                .map(|n| n.max(0.0) as i32)
+                .unwrap_or(0)
+        };
+
+        let mut approaches = [0; 5];
+        let mut untyped_approaches = 0;
+        for &col in &mapping.approach_columns {
+            if let Some(cell) = text(Some(col)) {
+                let (n, kind) = parse_approach(&cell);
+                match kind {
+                    Some(idx) => approaches[idx] += n,
+                    None => untyped_approaches += n,
+                }
+            }
+        }
+
+        let entry = ImportedLogbook {
+            total_hours: hours(mapping.total_time_column),
+            pic_time: hours(mapping.pic_column).unwrap_or(0.0),
+            sic_time: hours(mapping.sic_column).unwrap_or(0.0),
+            dual_time: hours(mapping.dual_received_column).unwrap_or(0.0),
+            night_time: hours(mapping.night_column).unwrap_or(0.0),
+            cross_country_time: hours(mapping.cross_country_column).unwrap_or(0.0),
+            actual_instrument_time: hours(mapping.actual_instrument_column).unwrap_or(0.0),
+            simulated_instrument_time: hours(mapping.simulated_instrument_column).unwrap_or(0.0),
+            day_takeoffs: count(mapping.day_takeoffs_column),
+            day_landings: count(mapping.day_landings_column),
+            night_takeoffs: count(mapping.night_takeoffs_column),
+            night_landings: count(mapping.night_landings_column),
+            approaches,
+            untyped_approaches,
+            instructor_name: text(mapping.instructor_name_column),
+            route: text(route_column),
+            remarks: text(mapping.remarks_column),
+        };
+
+        let has_data = entry.total_hours.is_some_and(|h| h > 0.0)
+            || entry.pic_time + entry.sic_time + entry.dual_time + entry.night_time > 0.0
+            || entry.cross_country_time + entry.actual_instrument_time + entry.simulated_instrument_time > 0.0
+            || entry.day_takeoffs + entry.day_landings + entry.night_takeoffs + entry.night_landings > 0
+            || entry.approaches.iter().sum::<i32>() + entry.untyped_approaches > 0;
+
+        has_data.then_some(entry)
     }
 
-    // Try European format (DD/MM/YYYY)
-    if let Ok(date) = NaiveDate::parse_from_str(date_str, "%d/%m/%Y") {
-        return Some(format!("{}T00:00:00", date.format("%Y-%m-%d")));
+    fn save(mut self, db: &crate::database::Database, flight_id: &str) -> anyhow::Result<String> {
+        // pilot_logbook has no column for approaches of unknown type - keep them in remarks
+        if self.untyped_approaches > 0 {
+            let note = format!("Approaches: {}", self.untyped_approaches);
+            self.remarks = Some(match self.remarks.take() {
+                Some(r) => format!("{} | {}", r, note),
+                None => note,
+            });
+        }
+        let day_time = self
+            .total_hours
+            .map(|total| (total - self.night_time).max(0.0))
+            .unwrap_or(0.0);
+        let [ils, vor, ndb, gps, visual] = self.approaches;
+
+        db.create_pilot_logbook_entry(
+            flight_id,
+            self.pic_time,
+            self.sic_time,
+            self.dual_time,
+            0.0,
+            0.0,
+            self.cross_country_time,
+            day_time,
+            self.night_time,
+            self.actual_instrument_time,
+            self.simulated_instrument_time,
+            0.0,
+            self.day_takeoffs,
+            self.day_landings,
+            self.night_takeoffs,
+            self.night_landings,
+            ils,
+            vor,
+            ndb,
+            gps,
+            visual,
+            0.0,
+            0.0,
+            None,
+            None,
+            self.instructor_name.as_deref(),
+            self.route.as_deref(),
+            self.remarks.as_deref(),
+            None,
+        )
     }
+}
 
-    // Try compact format (YYYYMMDD)
-    if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y%m%d") {
-        return Some(format!("{}T00:00:00", date.format("%Y-%m-%d")));
+/// Logbook time as decimal hours ("1.3", or "1,3" with a decimal comma) or hours:minutes ("1:18")
+fn parse_logbook_hours(value: &str, locale: Locale) -> Option<f64> {
+    match value.split_once(':') {
+        Some((h, m)) => {
+            let hours: f64 = h.trim().parse().ok()?;
+            let minutes: f64 = m.trim().parse().ok()?;
+            Some(hours + minutes / 60.0)
+        }
+        None => locale.parse_number(value),
     }
+}
+
+/// Parse an approach cell such as "2;ILS OR LOC RWY 28L;28L;KSFO" (ForeFlight) or a bare count.
+/// Returns the count and the approach kind index (ILS, VOR, NDB, GPS, visual) when recognisable.
+fn parse_approach(cell: &str) -> (i32, Option<usize>) {
+    let mut parts = cell.splitn(2, |c: char| c == ';' || c.is_whitespace());
+    let first = parts.next().unwrap_or("").trim();
+    let (count, description) = match first.parse::<i32>() {
+        Ok(n) => (n.max(0), parts.next().unwrap_or("").to_uppercase()),
+        Err(_) => (1, cell.to_uppercase()),
+    };
+
+    let kind = if description.contains("ILS") || description.contains("LOC") || description.contains("LDA") {
+        Some(0)
+    } else if description.contains("GPS") || description.contains("RNAV") || description.contains("RNP") {
+        Some(3)
+    } else if description.contains("VOR") {
+        Some(1)
+    } else if description.contains("NDB") {
+        Some(2)
+    } else if description.contains("VISUAL") || description.contains("CONTACT") {
+        Some(4)
+    } else {
+        None
+    };
 
-    None
+    (count, kind)
 }
 
 /// Result for batched preload operations
@@ -730,7 +1154,7 @@ pub fn preload_test_data_batch(
         count_reader.into_records().count()
     };
 
-    let total_batches = (total_rows + batch_size - 1) / batch_size;
+    let total_batches = total_rows.div_ceil(batch_size);
     let start_idx = batch_number * batch_size;
     let end_idx = std::cmp::min(start_idx + batch_size, total_rows);
 
@@ -738,6 +1162,9 @@ pub fn preload_test_data_batch(
     let mut errors = Vec::new();
 
     let db = state.db.lock().map_err(|e| e.to_string())?;
+    // The bundled sample log is written with US dates regardless of the user's locale
+    let locale = Locale::En;
+    let currency = CurrencySettings::configured(&db);
 
     // Detect column mapping from headers
     let headers: Vec<String> = reader
@@ -762,102 +1189,15 @@ pub fn preload_test_data_batch(
 
         match result {
             Ok(record) => {
-                let date = record
-                    .get(mapping.date_column)
-                    .unwrap_or("")
-                    .trim()
-                    .to_string();
-                let departure = record
-                    .get(mapping.from_column)
-                    .unwrap_or("")
-                    .trim()
-                    .to_uppercase();
-                let arrival = record
-                    .get(mapping.to_column)
-                    .unwrap_or("")
-                    .trim()
-                    .to_uppercase();
-
-                let passengers = mapping
-                    .passengers_column
-                    .and_then(|col| record.get(col))
-                    .unwrap_or("")
-                    .trim()
-                    .to_string();
-
-                let flight_number = mapping
-                    .flight_number_column
-                    .and_then(|col| record.get(col))
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty());
-
-                let aircraft_registration = mapping
-                    .aircraft_registration_column
-                    .and_then(|col| record.get(col))
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty());
-
-                // Build notes with all raw data for user review
-                let mut notes_parts = Vec::new();
-                if !passengers.is_empty() {
-                    notes_parts.push(format!("Passengers: {}", passengers));
-                }
-
-                // Try to parse date - use placeholder if fails
-                let departure_datetime = if date.is_empty() || !is_valid_date_field(&date) {
-                    notes_parts.push(format!("Original date: {}", date));
-                    "1900-01-01T00:00:00".to_string()
-                } else {
-                    match parse_date(&date) {
-                        Some(dt) => dt,
-                        None => {
-                            notes_parts.push(format!("Original date: {}", date));
-                            "1900-01-01T00:00:00".to_string()
-                        }
+                let flight = match csv_row_flight(&csv_record_row(row_number, &record, &mapping), locale) {
+                    Ok(flight) => flight,
+                    Err(e) => {
+                        errors.push(format!("Row {}: {}", row_number, e));
+                        continue;
                     }
                 };
 
-                // Use fallback for missing airports - import anyway
-                let departure_airport = if departure.is_empty() { "???".to_string() } else { departure };
-                let arrival_airport = if arrival.is_empty() { "???".to_string() } else { arrival };
-
-                // Add warning if airport codes look unusual
-                if departure_airport.len() > 4 || arrival_airport.len() > 4 {
-                    notes_parts.push(format!("Review: From={}, To={}", departure_airport, arrival_airport));
-                }
-
-                // Calculate distance - will work for recognized airports
-                let distance_nm =
-                    crate::geo::calculate_airport_distance(&departure_airport, &arrival_airport).map(|(nm, _)| nm);
-
-                let notes = if notes_parts.is_empty() { None } else { Some(notes_parts.join(" | ")) };
-
-                let flight = FlightInput {
-                    flight_number,
-                    departure_airport,
-                    arrival_airport,
-                    departure_datetime,
-                    arrival_datetime: None,
-                    aircraft_type_id: None,
-                    aircraft_registration,
-                    total_duration: None,
-                    flight_duration: None,
-                    distance_nm,
-                    distance_km: None,
-                    carbon_emissions_kg: None,
-                    booking_reference: None,
-                    ticket_number: None,
-                    seat_number: None,
-                    fare_class: None,
-                    base_fare: None,
-                    taxes: None,
-                    total_cost: None,
-                    currency: Some("USD".to_string()),
-                    notes,
-                    attachment_path: None,
-                };
-
-                match db.create_flight(&user_id, &flight) {
+                match db.create_flight_with_currency(&user_id, &flight, "manual", &currency) {
                     Ok(_) => imported_this_batch += 1,
                     Err(e) => {
                         errors.push(format!("Row {}: {}", row_number, e));
@@ -883,3 +1223,188 @@ pub fn preload_test_data_batch(
         errors,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_load() {
+        let presets = load_presets().unwrap();
+        let ids: Vec<&str> = presets.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["foreflight", "logten", "myflightbook"]);
+        assert!(presets.iter().all(|p| p.columns.contains_key("date")));
+    }
+
+    #[test]
+    fn test_parse_logbook_values() {
+        assert_eq!(parse_logbook_hours("1.5", Locale::En), Some(1.5));
+        assert_eq!(parse_logbook_hours("1:30", Locale::En), Some(1.5));
+        assert_eq!(parse_logbook_hours("1,5", Locale::De), Some(1.5));
+        assert_eq!(parse_logbook_hours("n/a", Locale::En), None);
+
+        assert_eq!(parse_approach("2;ILS OR LOC RWY 28L;28L;KSFO;"), (2, Some(0)));
+        assert_eq!(parse_approach("1;RNAV (GPS) RWY 12;12;KAUS;"), (1, Some(3)));
+        assert_eq!(parse_approach("3"), (3, None));
+    }
+
+    #[test]
+    fn test_resolve_foreflight_preset() {
+        let path = std::env::temp_dir().join(format!("foreflight-{}.csv", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "ForeFlight Logbook Import,,,,\n\
+             Aircraft Table,,,,\n\
+             AircraftID,TypeCode,Year,,\n\
+             N12345,C172,1998,,\n\
+             Flights Table,,,,\n\
+             Date,AircraftID,From,To,Route,TotalTime,PIC,Night,Approach1\n\
+             2024-05-01,N12345,KPAO,KSQL,,1.2,1.2,0,1;VOR RWY 30;30;KSQL;\n",
+        )
+        .unwrap();
+
+        let mapping = resolve_preset_mapping(path.to_str().unwrap(), &find_preset("ForeFlight").unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(mapping.header_row, Some(5));
+
+        // An explicit header row wins over the preset's, even when it is 0
+        let mut explicit = CsvColumnMapping { header_row: Some(0), ..mapping.clone() };
+        explicit.fill_from_preset(mapping.clone());
+        assert_eq!(explicit.header_row, Some(0));
+        let mut unset = CsvColumnMapping { header_row: None, delimiter: None, ..mapping.clone() };
+        unset.fill_from_preset(mapping.clone());
+        assert_eq!((unset.header_row, unset.delimiter.as_deref()), (Some(5), Some(",")));
+        assert_eq!((mapping.date_column, mapping.from_column, mapping.to_column), (0, 2, 3));
+        assert_eq!(mapping.aircraft_registration_column, Some(1));
+        let logbook = mapping.logbook.unwrap();
+        assert_eq!(logbook.total_time_column, Some(5));
+        assert_eq!(logbook.approach_columns, vec![8]);
+    }
+
+    #[test]
+    fn test_write_import_batch_retries_failed_batch() {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 CREATE TRIGGER reject_bad BEFORE INSERT ON flights WHEN NEW.departure_airport = 'BAD'
+                 BEGIN SELECT RAISE(ABORT, 'bad airport'); END;",
+            )
+            .unwrap();
+        let row = |row_number: usize, from: &str| -> PendingRow {
+            let flight: FlightInput = serde_json::from_value(serde_json::json!({
+                "departure_airport": from,
+                "arrival_airport": "KSQL",
+                "departure_datetime": "2024-05-01T00:00:00",
+            }))
+            .unwrap();
+            (row_number, flight, None)
+        };
+        let count = || -> i64 { db.conn.query_row("SELECT COUNT(*) FROM flights", [], |r| r.get(0)).unwrap() };
+
+        let currency = CurrencySettings::configured(&db);
+        let outcomes = write_import_batch(&db, "u1", &currency, vec![row(2, "KPAO"), row(3, "KSJC")]).unwrap();
+        assert!(outcomes.iter().all(|(_, outcome)| outcome.is_ok()));
+        assert_eq!(count(), 2);
+
+        // The failing batch is rolled back and replayed: the good rows still go in once
+        let outcomes = write_import_batch(&db, "u1", &currency, vec![row(4, "KPAO"), row(5, "BAD"), row(6, "KSJC")]).unwrap();
+        let failed: Vec<usize> = outcomes.iter().filter(|(_, o)| o.is_err()).map(|(n, _)| *n).collect();
+        assert_eq!(failed, vec![5]);
+        assert_eq!(count(), 4);
+    }
+
+    /// Timing for the batch sizes, not a correctness check. Run with
+    /// `cargo test --release bench_import_batch_sizes -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_import_batch_sizes() {
+        const ROWS: usize = 10_000;
+        for batch_size in [1, 100, 1000] {
+            let path = std::env::temp_dir().join(format!("import-bench-{}.db", uuid::Uuid::new_v4()));
+            let db = crate::database::Database::new(path.clone()).unwrap();
+            db.conn.execute("INSERT INTO users (id, name) VALUES ('u1', 'Pilot')", []).unwrap();
+            let currency = CurrencySettings::configured(&db);
+            let rows: Vec<PendingRow> = (0..ROWS)
+                .map(|i| {
+                    let flight: FlightInput = serde_json::from_value(serde_json::json!({
+                        "departure_airport": "KPAO",
+                        "arrival_airport": "KSQL",
+                        "departure_datetime": format!("2024-05-01T{:02}:{:02}:00", i / 60 % 24, i % 60),
+                    }))
+                    .unwrap();
+                    (i + 2, flight, None)
+                })
+                .collect();
+
+            let started = std::time::Instant::now();
+            let mut rows = rows.into_iter().peekable();
+            while rows.peek().is_some() {
+                let batch: Vec<_> = rows.by_ref().take(batch_size).collect();
+                write_import_batch(&db, "u1", &currency, batch).unwrap();
+            }
+            println!("{} rows, {} per batch: {:.2?}", ROWS, batch_size, started.elapsed());
+
+            drop(db);
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_csv_row_flight() {
+        let row = |date: &str, from: &str, to: &str| EditedCsvRow {
+            row_number: 2,
+            date: date.to_string(),
+            departure_airport: from.to_string(),
+            arrival_airport: to.to_string(),
+            passengers: Some("Pilot".to_string()),
+            flight_number: None,
+            aircraft_registration: None,
+        };
+
+        let flight = csv_row_flight(&row("2024-05-01", "kpao", "KSQL"), Locale::En).unwrap();
+        assert_eq!((flight.departure_airport.as_str(), flight.passenger_count), ("KPAO", Some(1)));
+
+        // No placeholder date or airport: the row is rejected
+        let undated = csv_row_flight(&row("n/a", "KPAO", "KSQL"), Locale::En).unwrap_err();
+        assert_eq!(undated.code(), "validation");
+        match csv_row_flight(&row("2024-05-01", "", "Palo Alto"), Locale::En) {
+            Err(CommandError::InvalidFields(fields)) => {
+                let names: Vec<&str> = fields.iter().map(|f| f.field.as_str()).collect();
+                assert_eq!(names, ["departure_airport", "arrival_airport"]);
+            }
+            other => panic!("expected field errors, got {:?}", other.map(|f| f.departure_airport)),
+        }
+    }
+
+    #[test]
+    fn test_sampled_preview_estimates_counts() {
+        let path = std::env::temp_dir().join(format!("sample-{}.csv", uuid::Uuid::new_v4()));
+        let mut csv = String::from("Date,From,To,Passengers\n");
+        for day in 1..=28 {
+            csv.push_str(&format!("2024-02-{:02},KPAO,KSQL,Pilot\n", day));
+            // Every other row is missing its destination and needs review
+            csv.push_str(&format!("2024-03-{:02},KPAO,,Pilot\n", day));
+        }
+        std::fs::write(&path, csv).unwrap();
+        let csv_path = path.to_str().unwrap();
+
+        let exact = build_csv_preview(csv_path, 5, None, None, Locale::En).unwrap();
+        assert!(!exact.is_estimate);
+        assert_eq!((exact.total_rows, exact.sampled_rows, exact.clean_rows, exact.review_rows), (56, 56, 28, 28));
+
+        let sampled = build_csv_preview(csv_path, 5, None, Some(10), Locale::En).unwrap();
+        assert!(sampled.is_estimate);
+        assert_eq!((sampled.sampled_rows, sampled.all_rows.len(), sampled.preview_rows.len()), (10, 10, 5));
+        assert!((54..=58).contains(&sampled.total_rows), "estimated {}", sampled.total_rows);
+        assert_eq!(sampled.clean_rows + sampled.review_rows, sampled.total_rows);
+        assert!(sampled.clean_rows.abs_diff(28) <= 2);
+
+        // A sample covering the whole file is exact
+        let whole = build_csv_preview(csv_path, 5, None, Some(100), Locale::En).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(!whole.is_estimate);
+        assert_eq!(whole.total_rows, 56);
+    }
+}
@@ -259,17 +259,51 @@ pub fn list_custom_records(
     schema_id: String,
 ) -> Result<Vec<CustomRecord>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
+    query_custom_records(&db.conn, &schema_id, -1, 0)
+}
 
-    let mut stmt = db
+/// One page of a schema's records with the total count and whether more pages follow
+#[tauri::command]
+pub fn list_custom_records_page(
+    state: State<'_, AppState>,
+    schema_id: String,
+    limit: i64,
+    offset: i64,
+) -> Result<crate::models::Page<CustomRecord>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let items = query_custom_records(&db.conn, &schema_id, limit, offset)?;
+    let total: i64 = db
         .conn
+        .query_row(
+            "SELECT COUNT(*) FROM custom_records WHERE schema_id = ?1",
+            [&schema_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(crate::models::Page::new(items, total, limit, offset))
+}
+
+/// Records of a schema, newest first (a negative limit means no limit)
+fn query_custom_records(
+    conn: &rusqlite::Connection,
+    schema_id: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<CustomRecord>, String> {
+    let mut stmt = conn
         .prepare(
             "SELECT id, schema_id, user_id, data, created_at, updated_at
-             FROM custom_records WHERE schema_id = ?1 ORDER BY created_at DESC",
+             FROM custom_records WHERE schema_id = ?1 ORDER BY created_at DESC
+             LIMIT ?2 OFFSET ?3",
         )
         .map_err(|e| e.to_string())?;
 
     let records = stmt
-        .query_map([&schema_id], |row: &rusqlite::Row| {
+        .query_map(rusqlite::params![schema_id, limit, offset], |row: &rusqlite::Row| {
             Ok(CustomRecord {
                 id: row.get(0)?,
                 schema_id: row.get(1)?,
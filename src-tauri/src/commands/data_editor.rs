@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use super::AppState;
+use crate::error::CommandError;
+use crate::validation::FieldErrors;
 
 // ===== DATA TYPES =====
 
@@ -34,6 +36,20 @@ pub struct FlightEditInput {
     pub distance_km: Option<f64>,
     pub flight_duration: Option<i32>,
     pub carbon_emissions_kg: Option<f64>,
+    pub scheduled_departure: Option<String>,
+    pub scheduled_arrival: Option<String>,
+    /// Absent leaves it unchanged; an explicit null clears it
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub passenger_count: Option<Option<i32>>,
+}
+
+/// Some(None) for an explicit null, so it can be told apart from an absent field (None)
+fn explicit_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +65,8 @@ pub struct DataEditorStats {
     pub potential_duplicates: i64,
     pub flights_without_notes: i64,
     pub flights_without_distance: i64,
+    /// Rows pointing at deleted flights or other missing parents (see orphans.rs)
+    pub orphaned_records: i64,
 }
 
 // ===== DUPLICATE DETECTION =====
@@ -81,7 +99,7 @@ pub fn find_duplicate_flights(
                 AND f1.user_id = f2.user_id
                 AND f1.departure_airport = f2.departure_airport
                 AND f1.arrival_airport = f2.arrival_airport
-            WHERE f1.user_id = ?1
+            WHERE f1.user_id = ?1 AND f1.deleted_at IS NULL AND f2.deleted_at IS NULL
             HAVING minute_diff <= ?2
         )
         SELECT
@@ -147,12 +165,12 @@ pub fn find_duplicate_flights(
     }
 
     let mut result: Vec<DuplicateGroup> = groups.into_values().collect();
-    result.sort_by(|a, b| b.flight_count.cmp(&a.flight_count));
+    result.sort_by_key(|x| std::cmp::Reverse(x.flight_count));
 
     Ok(result)
 }
 
-/// Merge duplicate flights - keeps the first one, deletes the rest
+/// Merge duplicate flights - keeps the first one, moves the rest to the recycle bin
 #[tauri::command]
 pub fn merge_duplicate_flights(
     keep_flight_id: String,
@@ -167,20 +185,9 @@ pub fn merge_duplicate_flights(
             continue; // Don't delete the one we're keeping
         }
 
-        // Delete related records first
-        db.conn.execute(
-            "DELETE FROM pilot_logbook WHERE flight_id = ?1",
-            rusqlite::params![flight_id],
-        ).map_err(|e| e.to_string())?;
-
-        db.conn.execute(
-            "DELETE FROM journey_flights WHERE flight_id = ?1",
-            rusqlite::params![flight_id],
-        ).map_err(|e| e.to_string())?;
-
-        // Delete the flight
+        // Soft-delete: merged-away flights go to the recycle bin with their related records
         let rows = db.conn.execute(
-            "DELETE FROM flights WHERE id = ?1",
+            "UPDATE flights SET deleted_at = datetime('now') WHERE id = ?1 AND deleted_at IS NULL",
             rusqlite::params![flight_id],
         ).map_err(|e| e.to_string())?;
 
@@ -193,21 +200,45 @@ pub fn merge_duplicate_flights(
 // ===== FLIGHT EDITING =====
 
 /// Update a flight's fields
+/// The fields given are validated like `create_flight`; an empty arrival_datetime clears it.
 #[tauri::command]
 pub fn update_flight(
     flight_id: String,
     updates: FlightEditInput,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if let Some(v) = &updates.departure_airport {
+        errors.airport_code("departure_airport", v);
+    }
+    if let Some(v) = &updates.arrival_airport {
+        errors.airport_code("arrival_airport", v);
+    }
+    if let Some(v) = &updates.departure_datetime {
+        errors.datetime("departure_datetime", v);
+    }
+    errors.optional_datetime("arrival_datetime", updates.arrival_datetime.as_deref());
+    errors.optional_datetime("scheduled_departure", updates.scheduled_departure.as_deref());
+    errors.optional_datetime("scheduled_arrival", updates.scheduled_arrival.as_deref());
+    errors.non_negative("total_cost", updates.total_cost);
+    errors.non_negative("distance_km", updates.distance_km);
+    errors.non_negative_int("flight_duration", updates.flight_duration);
+    errors.non_negative("carbon_emissions_kg", updates.carbon_emissions_kg);
+    errors.non_negative_int("passenger_count", updates.passenger_count.flatten());
+    errors.into_result()?;
+
+    let db = state.db.lock()?;
 
     // Build dynamic update query
     let mut set_clauses: Vec<String> = vec![];
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
 
     if let Some(v) = updates.flight_number {
+        let airline = crate::airlines::infer_from_flight_number(&v).map(|a| a.iata.clone());
         set_clauses.push("flight_number = ?".to_string());
         params.push(Box::new(v));
+        set_clauses.push("airline = ?".to_string());
+        params.push(Box::new(airline));
     }
     if let Some(v) = updates.departure_airport {
         set_clauses.push("departure_airport = ?".to_string());
@@ -243,6 +274,7 @@ pub fn update_flight(
     }
     if let Some(v) = updates.currency {
         set_clauses.push("currency = ?".to_string());
+        set_clauses.push(format!("currency_source = '{}'", crate::currency::SOURCE_EXPLICIT));
         params.push(Box::new(v));
     }
     if let Some(v) = updates.booking_reference {
@@ -261,10 +293,24 @@ pub fn update_flight(
         set_clauses.push("flight_duration = ?".to_string());
         params.push(Box::new(v));
     }
+    // An empty scheduled time clears it
+    if let Some(v) = updates.scheduled_departure {
+        set_clauses.push("scheduled_departure = ?".to_string());
+        params.push(Box::new(Some(v).filter(|v| !v.trim().is_empty())));
+    }
+    if let Some(v) = updates.scheduled_arrival {
+        set_clauses.push("scheduled_arrival = ?".to_string());
+        params.push(Box::new(Some(v).filter(|v| !v.trim().is_empty())));
+    }
     if let Some(v) = updates.carbon_emissions_kg {
         set_clauses.push("carbon_emissions_kg = ?".to_string());
         params.push(Box::new(v));
     }
+    // Some(None) clears the count
+    if let Some(v) = updates.passenger_count {
+        set_clauses.push("passenger_count = ?".to_string());
+        params.push(Box::new(v));
+    }
 
     if set_clauses.is_empty() {
         return Ok(()); // Nothing to update
@@ -278,20 +324,23 @@ pub fn update_flight(
     );
 
     // Add flight_id as last parameter
-    params.push(Box::new(flight_id));
+    params.push(Box::new(flight_id.clone()));
 
     // Convert params to references
     let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
-    db.conn.execute(&query, param_refs.as_slice())
-        .map_err(|e| e.to_string())?;
+    let updated = db.conn.execute(&query, param_refs.as_slice())?;
+    if updated == 0 {
+        return Err(CommandError::not_found("Flight", &flight_id));
+    }
 
     Ok(())
 }
 
 // ===== BULK OPERATIONS =====
 
-/// Delete multiple flights at once
+/// Move multiple flights to the recycle bin at once
+/// Related records stay in place and come back with `restore_flight`
 #[tauri::command]
 pub fn bulk_delete_flights(
     flight_ids: Vec<String>,
@@ -303,20 +352,8 @@ pub fn bulk_delete_flights(
     let mut failed_ids: Vec<String> = vec![];
 
     for flight_id in &flight_ids {
-        // Delete related records first
-        let _ = db.conn.execute(
-            "DELETE FROM pilot_logbook WHERE flight_id = ?1",
-            rusqlite::params![flight_id],
-        );
-
-        let _ = db.conn.execute(
-            "DELETE FROM journey_flights WHERE flight_id = ?1",
-            rusqlite::params![flight_id],
-        );
-
-        // Delete the flight
         match db.conn.execute(
-            "DELETE FROM flights WHERE id = ?1",
+            "UPDATE flights SET deleted_at = datetime('now') WHERE id = ?1 AND deleted_at IS NULL",
             rusqlite::params![flight_id],
         ) {
             Ok(rows) if rows > 0 => deleted_count += 1,
@@ -330,8 +367,49 @@ pub fn bulk_delete_flights(
     })
 }
 
+/// Build the WHERE clause shared by the editor list and count queries.
+/// Placeholders are numbered from `first_param`.
+fn editor_filter(
+    search: Option<String>,
+    tag: Option<String>,
+    first_param: usize,
+) -> Result<(String, Vec<Box<dyn rusqlite::ToSql>>), String> {
+    let mut clauses: Vec<String> = vec!["deleted_at IS NULL".to_string()];
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+
+    if let Some(s) = search {
+        let idx = first_param + params.len();
+        clauses.push(format!(
+            "(departure_airport LIKE ?{0} ESCAPE '\\' OR
+                arrival_airport LIKE ?{0} ESCAPE '\\' OR
+                flight_number LIKE ?{0} ESCAPE '\\' OR
+                notes LIKE ?{0} ESCAPE '\\' OR
+                booking_reference LIKE ?{0} ESCAPE '\\')",
+            idx
+        ));
+        params.push(Box::new(format!("%{}%", crate::database::escape_like(&s))));
+    }
+
+    if let Some(t) = tag {
+        let idx = first_param + params.len();
+        clauses.push(format!(
+            "id IN (SELECT flight_id FROM flight_tags WHERE tag = ?{})",
+            idx
+        ));
+        params.push(Box::new(super::flight_tags::normalize_tag(&t)?));
+    }
+
+    let sql = clauses
+        .iter()
+        .map(|c| format!(" AND {}", c))
+        .collect::<String>();
+
+    Ok((sql, params))
+}
+
 /// Get all flights for editing (with pagination and optional filters)
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn get_flights_for_editor(
     user_id: String,
     limit: i32,
@@ -339,6 +417,7 @@ pub fn get_flights_for_editor(
     search: Option<String>,
     sort_by: Option<String>,
     sort_dir: Option<String>,
+    tag: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<Vec<crate::models::Flight>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
@@ -357,61 +436,36 @@ pub fn get_flights_for_editor(
         _ => "DESC",
     };
 
-    let query = if let Some(ref s) = search {
-        format!(
-            "SELECT id, user_id, flight_number, departure_airport, arrival_airport,
-                    departure_datetime, arrival_datetime, aircraft_type_id, aircraft_registration,
-                    total_duration, flight_duration, block_duration, distance_nm, distance_km,
-                    booking_reference, ticket_number, seat_number, fare_class, base_fare, taxes,
-                    total_cost, currency, carbon_emissions_kg, per_passenger_co2_kg, carbon_offset_purchased,
-                    frequent_flyer_program, miles_earned, notes, attachment_path, data_source,
-                    verified, created_at, updated_at
-             FROM flights
-             WHERE user_id = ?1 AND (
-                departure_airport LIKE ?4 OR
-                arrival_airport LIKE ?4 OR
-                flight_number LIKE ?4 OR
-                notes LIKE ?4 OR
-                booking_reference LIKE ?4
-             )
-             ORDER BY {} {}
-             LIMIT ?2 OFFSET ?3",
-            sort_column, sort_direction
-        )
-    } else {
-        format!(
-            "SELECT id, user_id, flight_number, departure_airport, arrival_airport,
-                    departure_datetime, arrival_datetime, aircraft_type_id, aircraft_registration,
-                    total_duration, flight_duration, block_duration, distance_nm, distance_km,
-                    booking_reference, ticket_number, seat_number, fare_class, base_fare, taxes,
-                    total_cost, currency, carbon_emissions_kg, per_passenger_co2_kg, carbon_offset_purchased,
-                    frequent_flyer_program, miles_earned, notes, attachment_path, data_source,
-                    verified, created_at, updated_at
-             FROM flights
-             WHERE user_id = ?1
-             ORDER BY {} {}
-             LIMIT ?2 OFFSET ?3",
-            sort_column, sort_direction
-        )
-    };
+    let (filter_sql, filter_params) = editor_filter(search, tag, 4)?;
+
+    let query = format!(
+        "SELECT id, user_id, flight_number, departure_airport, arrival_airport,
+                departure_datetime, arrival_datetime, aircraft_type_id, aircraft_registration,
+                total_duration, flight_duration, block_duration, distance_nm, distance_km,
+                booking_reference, ticket_number, seat_number, fare_class, base_fare, taxes,
+                total_cost, currency, carbon_emissions_kg, per_passenger_co2_kg, carbon_offset_purchased,
+                frequent_flyer_program, miles_earned, notes, attachment_path, data_source,
+                verified, created_at, updated_at, airline, scheduled_departure, scheduled_arrival,
+                passenger_count, currency_source
+         FROM flights
+         WHERE user_id = ?1{}
+         ORDER BY {} {}
+         LIMIT ?2 OFFSET ?3",
+        filter_sql, sort_column, sort_direction
+    );
+
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> =
+        vec![Box::new(user_id), Box::new(limit), Box::new(offset)];
+    params.extend(filter_params);
+    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
     let mut stmt = db.conn.prepare(&query).map_err(|e| e.to_string())?;
 
-    let flights = if let Some(s) = search {
-        let search_pattern = format!("%{}%", s);
-        stmt.query_map(
-            rusqlite::params![user_id, limit, offset, search_pattern],
-            map_flight_row,
-        )
-    } else {
-        stmt.query_map(
-            rusqlite::params![user_id, limit, offset],
-            map_flight_row,
-        )
-    }
-    .map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
+    let flights = stmt
+        .query_map(param_refs.as_slice(), map_flight_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
 
     Ok(flights)
 }
@@ -421,31 +475,21 @@ pub fn get_flights_for_editor(
 pub fn get_flight_count(
     user_id: String,
     search: Option<String>,
+    tag: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<i64, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
 
-    let count: i64 = if let Some(s) = search {
-        let search_pattern = format!("%{}%", s);
-        db.conn.query_row(
-            "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND (
-                departure_airport LIKE ?2 OR
-                arrival_airport LIKE ?2 OR
-                flight_number LIKE ?2 OR
-                notes LIKE ?2 OR
-                booking_reference LIKE ?2
-            )",
-            rusqlite::params![user_id, search_pattern],
-            |row| row.get(0),
-        )
-    } else {
-        db.conn.query_row(
-            "SELECT COUNT(*) FROM flights WHERE user_id = ?1",
-            rusqlite::params![user_id],
-            |row| row.get(0),
-        )
-    }
-    .map_err(|e| e.to_string())?;
+    let (filter_sql, filter_params) = editor_filter(search, tag, 2)?;
+    let query = format!("SELECT COUNT(*) FROM flights WHERE user_id = ?1{}", filter_sql);
+
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
+    params.extend(filter_params);
+    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+    let count: i64 = db.conn
+        .query_row(&query, param_refs.as_slice(), |row| row.get(0))
+        .map_err(|e| e.to_string())?;
 
     Ok(count)
 }
@@ -461,14 +505,14 @@ pub fn get_data_editor_stats(
     let db = state.db.lock().map_err(|e| e.to_string())?;
 
     let total_flights: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     // Count unique passengers from notes
     let mut stmt = db.conn.prepare(
-        "SELECT notes FROM flights WHERE user_id = ?1 AND notes IS NOT NULL AND notes LIKE 'Passengers:%'"
+        "SELECT notes FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND notes IS NOT NULL AND notes LIKE 'Passengers:%'"
     ).map_err(|e| e.to_string())?;
 
     let notes_list: Vec<String> = stmt
@@ -498,7 +542,7 @@ pub fn get_data_editor_stats(
                 AND f1.user_id = f2.user_id
                 AND f1.departure_airport = f2.departure_airport
                 AND f1.arrival_airport = f2.arrival_airport
-            WHERE f1.user_id = ?1
+            WHERE f1.user_id = ?1 AND f1.deleted_at IS NULL AND f2.deleted_at IS NULL
             AND ABS((julianday(f1.departure_datetime) - julianday(f2.departure_datetime)) * 24 * 60) <= 60
         )",
         rusqlite::params![user_id],
@@ -506,23 +550,26 @@ pub fn get_data_editor_stats(
     ).unwrap_or(0);
 
     let flights_without_notes: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND (notes IS NULL OR notes = '')",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND (notes IS NULL OR notes = '')",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
     let flights_without_distance: i64 = db.conn.query_row(
-        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND distance_km IS NULL",
+        "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND distance_km IS NULL",
         rusqlite::params![user_id],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
+    let orphaned_records = crate::orphans::find_orphans(&db.conn).map_err(|e| e.to_string())?.total;
+
     Ok(DataEditorStats {
         total_flights,
         total_passengers: unique_passengers.len() as i64,
         potential_duplicates,
         flights_without_notes,
         flights_without_distance,
+        orphaned_records,
     })
 }
 
@@ -669,5 +716,23 @@ fn map_flight_row(row: &rusqlite::Row) -> Result<crate::models::Flight, rusqlite
         verified: row.get(30)?,
         created_at: row.get(31)?,
         updated_at: row.get(32)?,
+        airline: row.get(33)?,
+        scheduled_departure: row.get(34)?,
+        scheduled_arrival: row.get(35)?,
+        passenger_count: row.get(36)?,
+        currency_source: row.get(37)?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passenger_count_null_clears() {
+        let edit = |json: &str| serde_json::from_str::<FlightEditInput>(json).unwrap().passenger_count;
+        assert_eq!(edit("{}"), None);
+        assert_eq!(edit(r#"{"passenger_count": null}"#), Some(None));
+        assert_eq!(edit(r#"{"passenger_count": 3}"#), Some(Some(3)));
+    }
+}
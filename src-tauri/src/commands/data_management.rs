@@ -1,6 +1,7 @@
 // Data export and database management commands
 use tauri::State;
 use super::AppState;
+use crate::error::CommandError;
 use std::fs::File;
 
 #[tauri::command]
@@ -81,6 +82,300 @@ pub fn export_data_to_csv(
     Ok(flights.len())
 }
 
+/// Export flights as an iCalendar (.ics) file
+///
+/// `range` is "upcoming", "past" or "all" (default). Each flight becomes a VEVENT
+/// with a stable UID so re-importing updates existing calendar entries.
+#[tauri::command]
+pub fn export_flights_ics(
+    user_id: String,
+    range: Option<String>,
+    export_path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    use crate::timezone::AirportZone;
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let flights = db
+        .list_flights(&user_id, i32::MAX, 0)
+        .map_err(|e| e.to_string())?;
+
+    let range = range.unwrap_or_else(|| "all".to_string()).to_lowercase();
+    if !["upcoming", "past", "all"].contains(&range.as_str()) {
+        return Err(format!("Invalid range '{}': expected upcoming, past or all", range));
+    }
+
+    let now = chrono::Utc::now();
+    let dtstamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Quantum Encoding LTD//Flight Tracker Pro//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:Flights".to_string(),
+    ];
+    let mut exported = 0;
+
+    for mut flight in flights {
+        // Estimate duration when there is no arrival time and no recorded duration
+        if flight.arrival_datetime.is_none() && flight.flight_duration.is_none() && flight.total_duration.is_none() {
+            flight.flight_duration = flight.distance_km.map(|km| {
+                let aircraft = crate::aircraft_performance::AircraftRef::of_flight(&flight);
+                crate::aircraft_performance::performance_for(&db, aircraft).flight_time(km)
+            });
+        }
+
+        let departure_airport = db
+            .get_airport_by_code(&flight.departure_airport)
+            .map_err(|e| e.to_string())?;
+        let arrival_airport = db
+            .get_airport_by_code(&flight.arrival_airport)
+            .map_err(|e| e.to_string())?;
+        let aircraft = match flight.aircraft_type_id.as_deref() {
+            Some(id) => db.get_aircraft_type(id).map_err(|e| e.to_string())?,
+            None => None,
+        };
+
+        let times = super::flights::build_flight_times(
+            flight.clone(),
+            departure_airport.as_ref().and_then(|a| a.timezone.clone()),
+            arrival_airport.as_ref().and_then(|a| a.timezone.clone()),
+        );
+
+        let departure_local = match crate::timezone::parse_local_datetime(&flight.departure_datetime) {
+            Some(dt) => dt,
+            None => continue, // Unparseable departure - cannot place on a calendar
+        };
+
+        // Decide past/upcoming on the UTC instant when known, otherwise on wall-clock time
+        let departed = match times.departure.utc_time.as_deref() {
+            Some(utc) => chrono::DateTime::parse_from_rfc3339(utc)
+                .map(|dt| dt < now)
+                .unwrap_or(false),
+            None => departure_local < now.naive_utc(),
+        };
+        match range.as_str() {
+            "upcoming" if departed => continue,
+            "past" if !departed => continue,
+            _ => {}
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@flight-tracker-pro", flight.id));
+        lines.push(format!("DTSTAMP:{}", dtstamp));
+
+        let departure_zone = times.departure.timezone.as_deref().and_then(AirportZone::parse);
+        let start = ics_time(&times.departure.local_time, &times.departure.utc_time, departure_zone.as_ref())
+            .unwrap_or(IcsTime::Floating(departure_local));
+        lines.push(start.property("DTSTART"));
+
+        let arrival_zone = times.arrival.timezone.as_deref().and_then(AirportZone::parse);
+        let end = ics_time(&times.arrival.local_time, &times.arrival.utc_time, arrival_zone.as_ref());
+        let minutes = flight.flight_duration.or(flight.total_duration).unwrap_or(120);
+        lines.push(ics_end_property(start, end, minutes));
+
+        let route = format!("{} → {}", flight.departure_airport, flight.arrival_airport);
+        let summary = match flight.flight_number.as_deref().filter(|n| !n.trim().is_empty()) {
+            Some(number) => format!("{} {}", number.trim(), route),
+            None => format!("Flight {}", route),
+        };
+        lines.push(format!("SUMMARY:{}", ics_escape(&summary)));
+
+        let location = match &departure_airport {
+            Some(a) => format!("{} ({})", a.name, flight.departure_airport),
+            None => flight.departure_airport.clone(),
+        };
+        lines.push(format!("LOCATION:{}", ics_escape(&location)));
+
+        let mut description: Vec<String> = Vec::new();
+        if let Some(km) = flight.distance_km {
+            let nm = flight.distance_nm.unwrap_or(km / 1.852);
+            description.push(format!("Distance: {:.0} km ({:.0} nm)", km, nm));
+        }
+        let aircraft_label = match (&aircraft, flight.aircraft_registration.as_deref()) {
+            (Some(t), Some(reg)) => Some(format!("{} {} ({})", t.manufacturer, t.model, reg)),
+            (Some(t), None) => Some(format!("{} {}", t.manufacturer, t.model)),
+            (None, Some(reg)) => Some(reg.to_string()),
+            (None, None) => None,
+        };
+        if let Some(label) = aircraft_label {
+            description.push(format!("Aircraft: {}", label));
+        }
+        if times.arrival.is_estimated {
+            description.push("Arrival time estimated from flight duration".to_string());
+        }
+        if !description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", ics_escape(&description.join("\n"))));
+        }
+
+        lines.push("END:VEVENT".to_string());
+        exported += 1;
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let content: String = lines
+        .iter()
+        .map(|line| fold_ics_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n";
+
+    std::fs::write(&export_path, content)
+        .map_err(|e| format!("Failed to write calendar file: {}", e))?;
+
+    Ok(exported)
+}
+
+/// A DTSTART/DTEND value: UTC, or floating local time when the airport zone is unknown.
+/// UTC avoids TZID parameters, which RFC 5545 requires to be backed by a VTIMEZONE.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IcsTime {
+    Utc(chrono::DateTime<chrono::Utc>),
+    Floating(chrono::NaiveDateTime),
+}
+
+impl IcsTime {
+    fn property(&self, name: &str) -> String {
+        match self {
+            IcsTime::Utc(utc) => format!("{}:{}", name, utc.format("%Y%m%dT%H%M%SZ")),
+            IcsTime::Floating(local) => format!("{}:{}", name, local.format("%Y%m%dT%H%M%S")),
+        }
+    }
+}
+
+/// The stored UTC time, or the local time converted through the airport zone; floating
+/// local time only when the zone is unknown, None when neither time parses
+fn ics_time(
+    local_time: &Option<String>,
+    utc_time: &Option<String>,
+    zone: Option<&crate::timezone::AirportZone>,
+) -> Option<IcsTime> {
+    let local = local_time.as_deref().and_then(crate::timezone::parse_local_datetime);
+    let utc = utc_time
+        .as_deref()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .or_else(|| zone.zip(local).and_then(|(zone, local)| zone.local_to_utc(&local)));
+
+    utc.map(IcsTime::Utc).or(local.map(IcsTime::Floating))
+}
+
+/// DTEND when the arrival is the same kind of value as DTSTART and not before it; RFC 5545
+/// does not allow mixing UTC and floating times, so otherwise the event gets a DURATION
+fn ics_end_property(start: IcsTime, end: Option<IcsTime>, minutes: i32) -> String {
+    match (start, end) {
+        (IcsTime::Utc(s), Some(end @ IcsTime::Utc(e))) if e >= s => end.property("DTEND"),
+        (IcsTime::Floating(s), Some(end @ IcsTime::Floating(e))) if e >= s => end.property("DTEND"),
+        _ => format!("DURATION:PT{}M", minutes.max(1)),
+    }
+}
+
+/// Escape text values per RFC 5545
+fn ics_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold content lines longer than 75 octets (continuation lines start with a space)
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut octets = 0;
+
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if octets + len > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(ch);
+        octets += len;
+    }
+
+    folded
+}
+
+/// Applied and pending schema migrations (a dry run, nothing is applied)
+/// With `db_path` another database file (e.g. a backup) is inspected read-only instead.
+#[tauri::command]
+pub fn get_migration_status(
+    db_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::migrations::MigrationStatus, String> {
+    match db_path {
+        Some(path) => {
+            let conn = rusqlite::Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+            crate::migrations::migration_status(&conn).map_err(|e| e.to_string())
+        }
+        None => {
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            crate::migrations::migration_status(&db.conn).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Pending interactive and bulk writes in the write queue, for diagnostics
+#[tauri::command]
+pub fn get_write_queue_depth(state: State<'_, AppState>) -> Result<crate::write_queue::WriteQueueDepth, String> {
+    Ok(state.writer.depth())
+}
+
+/// Rows whose foreign key points at a deleted parent, counted per table and column
+#[tauri::command]
+pub fn find_orphaned_records(state: State<'_, AppState>) -> Result<crate::orphans::OrphanReport, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::orphans::find_orphans(&db.conn)?)
+}
+
+/// Delete orphaned link rows and clear dangling optional links, in one transaction
+#[tauri::command]
+pub fn repair_orphaned_records(state: State<'_, AppState>) -> Result<crate::orphans::OrphanReport, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::orphans::repair_orphans(&db.conn)?)
+}
+
+/// Retention limits for each log and cache table, with defaults filled in
+#[tauri::command]
+pub fn get_retention_policies(state: State<'_, AppState>) -> Result<Vec<crate::retention::RetentionPolicy>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::retention::policies(&db))
+}
+
+/// Delete log and cache rows past their retention limits, in one transaction
+#[tauri::command]
+pub fn run_retention_cleanup(state: State<'_, AppState>) -> Result<crate::retention::RetentionReport, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::retention::run(&db)?)
+}
+
+/// File size, free pages and per-table row counts and sizes
+#[tauri::command]
+pub fn get_database_stats(state: State<'_, AppState>) -> Result<crate::storage::DatabaseStats, CommandError> {
+    let db = state.db.lock()?;
+    Ok(crate::storage::database_stats(&db.conn)?)
+}
+
+/// Reclaim free pages with VACUUM (or an incremental vacuum); only while no writes are queued
+#[tauri::command]
+pub fn compact_database(state: State<'_, AppState>) -> Result<crate::storage::CompactResult, CommandError> {
+    let depth = state.writer.depth();
+    if depth.busy || depth.interactive + depth.bulk > 0 {
+        return Err(CommandError::Unavailable(
+            "The database is busy with queued writes; try again once they finish".to_string(),
+        ));
+    }
+    let db = state.db.lock()?;
+    Ok(crate::storage::compact(&db.conn)?)
+}
+
 #[tauri::command]
 pub fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
@@ -92,7 +387,7 @@ pub fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
 
     // Get all table names from the database
     let mut stmt = db.conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'")
+        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_migrations'")
         .map_err(|e| e.to_string())?;
 
     let table_names: Vec<String> = stmt
@@ -116,4 +411,52 @@ pub fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
         .map_err(|e| e.to_string())?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timezone::AirportZone;
+
+    #[test]
+    fn test_ics_time_uses_utc() {
+        let local = Some("2025-07-01T09:00:00".to_string());
+        let new_york = AirportZone::parse("America/New_York");
+        let property = |time: Option<IcsTime>, name: &str| time.map(|t| t.property(name));
+
+        // Stored UTC wins and never produces a TZID parameter
+        let utc = Some("2025-07-01T13:00:00+00:00".to_string());
+        let start = ics_time(&local, &utc, new_york.as_ref());
+        assert_eq!(property(start, "DTSTART").as_deref(), Some("DTSTART:20250701T130000Z"));
+
+        // Local time in a known zone is converted, respecting DST
+        assert_eq!(property(ics_time(&local, &None, new_york.as_ref()), "DTSTART").as_deref(), Some("DTSTART:20250701T130000Z"));
+        let winter = Some("2025-01-15T09:00:00".to_string());
+        assert_eq!(property(ics_time(&winter, &None, new_york.as_ref()), "DTEND").as_deref(), Some("DTEND:20250115T140000Z"));
+        let fixed = AirportZone::parse("+05:30");
+        assert_eq!(property(ics_time(&local, &None, fixed.as_ref()), "DTEND").as_deref(), Some("DTEND:20250701T033000Z"));
+
+        // Unknown zone: floating local time
+        let floating = ics_time(&local, &None, None);
+        assert_eq!(property(floating, "DTSTART").as_deref(), Some("DTSTART:20250701T090000"));
+        assert_eq!(ics_time(&None, &None, None), None);
+
+        // DTEND only when it matches DTSTART's kind and follows it; a DURATION otherwise
+        let start = start.unwrap();
+        let later = ics_time(&Some("2025-07-01T12:00:00".to_string()), &None, new_york.as_ref());
+        assert_eq!(ics_end_property(start, later, 90), "DTEND:20250701T160000Z");
+        let floating_end = ics_time(&Some("2025-07-01T12:00:00".to_string()), &None, None);
+        assert_eq!(ics_end_property(start, floating_end, 90), "DURATION:PT90M");
+        assert_eq!(ics_end_property(floating.unwrap(), floating_end, 90), "DTEND:20250701T120000");
+        assert_eq!(ics_end_property(start, None, 90), "DURATION:PT90M");
+        assert_eq!(ics_end_property(later.unwrap(), Some(start), 0), "DURATION:PT1M");
+    }
+
+    #[test]
+    fn test_fold_ics_line() {
+        let line = format!("SUMMARY:{}", "é".repeat(50));
+        let folded = fold_ics_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -0,0 +1,446 @@
+// Periodic digest generation
+// Summarises a week or month of activity as markdown under ~/flight-tracker-pro/digests
+
+use chrono::{Duration, Months, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::State;
+
+use super::AppState;
+use crate::maintenance::{self, MaintenanceDue, MaintenanceStatus};
+use crate::models::CurrencyItem;
+use crate::units::UnitSystem;
+
+/// Digests, and expiry alerts, are POSTed here as JSON when set
+const WEBHOOK_SETTING: &str = "digest_webhook_url";
+/// Every section, in the order it appears in the digest
+const ALL_SECTIONS: [&str; 7] = ["flights", "hours", "anomalies", "ai_spend", "currency", "maintenance", "security"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestResult {
+    pub period: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub sections: Vec<String>,
+    pub file_path: String,
+    pub markdown: String,
+    /// None when delivery was not requested
+    pub webhook_delivered: Option<bool>,
+    pub webhook_error: Option<String>,
+}
+
+/// Data gathered for one digest period
+#[derive(Debug, Default)]
+struct DigestData {
+    flights_added: i64,
+    recent_flights: Vec<(String, String, String)>, // (date, departure, arrival)
+    flights_flown: i64,
+    hours_flown: f64,
+    distance_km: f64,
+    units: UnitSystem,
+    anomalies: Vec<(String, String)>, // (severity, description)
+    ai_requests: i64,
+    ai_tokens: i64,
+    ai_cost_usd: f64,
+    currency: Vec<CurrencyItem>,
+    expiry_alerts: Vec<(String, String, String)>, // (label, expires_date, stage) alerted this period
+    maintenance: Vec<MaintenanceDue>, // overdue and due soon items only
+    security_alerts: Option<Vec<String>>, // None when no sentinel answered; the section is left out
+}
+
+/// Generate a weekly or monthly markdown digest and save it under ~/flight-tracker-pro/digests
+///
+/// Sections come from the comma separated "digest_sections" setting (all by default):
+/// flights, hours, anomalies, ai_spend, currency, maintenance, security. With `deliver` the digest is
+/// also POSTed as JSON to the URL in the "digest_webhook_url" setting.
+#[tauri::command]
+pub async fn generate_digest(
+    user_id: String,
+    period: String,
+    deliver: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<DigestResult, String> {
+    let period = match period.to_lowercase().as_str() {
+        "week" | "weekly" => "weekly",
+        "month" | "monthly" => "monthly",
+        other => return Err(format!("Unknown digest period '{}' (expected weekly or monthly)", other)),
+    };
+
+    let today = Utc::now().date_naive();
+    let start = if period == "weekly" {
+        today - Duration::days(7)
+    } else {
+        today.checked_sub_months(Months::new(1)).unwrap_or(today)
+    };
+    let start_date = start.format("%Y-%m-%d").to_string();
+    let end_date = today.format("%Y-%m-%d").to_string();
+
+    let (sections, webhook_url, mut data) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let sections = parse_sections(db.get_setting("digest_sections").ok().flatten().as_deref());
+        let webhook_url = webhook_url(&db);
+        let data = collect_digest_data(&db, &user_id, &start_date, &end_date, &sections).map_err(|e| e.to_string())?;
+        (sections, webhook_url, data)
+    };
+
+    if sections.iter().any(|s| s == "security") {
+        data.security_alerts = super::network_sentinel::sentinel_anomalies(10).await.map(|flows| {
+                flows
+                    .into_iter()
+                    .map(|flow| {
+                        format!(
+                            "{} ({}) -> {}:{}{}",
+                            flow.process_name,
+                            flow.pid,
+                            flow.remote_addr,
+                            flow.remote_port,
+                            flow.anomaly_reason.map(|r| format!(" - {}", r)).unwrap_or_default()
+                        )
+                    })
+                    .collect()
+            });
+    }
+
+    let markdown = render_digest(period, &start_date, &end_date, &sections, &data);
+
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let digest_dir = home_dir.join("flight-tracker-pro").join("digests");
+    fs::create_dir_all(&digest_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    let file_path = digest_dir.join(format!("digest-{}-{}.md", period, today.format("%Y%m%d")));
+    fs::write(&file_path, &markdown).map_err(|e| format!("Failed to write digest: {}", e))?;
+
+    let mut result = DigestResult {
+        period: period.to_string(),
+        start_date,
+        end_date,
+        sections,
+        file_path: file_path.to_string_lossy().to_string(),
+        markdown,
+        webhook_delivered: None,
+        webhook_error: None,
+    };
+
+    if deliver.unwrap_or(false) {
+        let delivery = match webhook_url {
+            Some(url) => post_webhook(
+                &url,
+                &serde_json::json!({
+                    "period": result.period,
+                    "start_date": result.start_date,
+                    "end_date": result.end_date,
+                    "markdown": result.markdown,
+                }),
+            )
+            .await,
+            None => Err("No webhook configured (digest_webhook_url)".to_string()),
+        };
+        result.webhook_delivered = Some(delivery.is_ok());
+        result.webhook_error = delivery.err();
+    }
+
+    Ok(result)
+}
+
+/// Sections named in the setting, in digest order; all sections when unset or empty
+fn parse_sections(setting: Option<&str>) -> Vec<String> {
+    let requested: Vec<String> = setting
+        .unwrap_or("")
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    ALL_SECTIONS
+        .iter()
+        .filter(|section| requested.is_empty() || requested.iter().any(|r| r == *section))
+        .map(|section| section.to_string())
+        .collect()
+}
+
+fn collect_digest_data(
+    db: &crate::database::Database,
+    user_id: &str,
+    start_date: &str,
+    end_date: &str,
+    sections: &[String],
+) -> anyhow::Result<DigestData> {
+    let wants = |name: &str| sections.iter().any(|s| s == name);
+    let mut data = DigestData {
+        units: UnitSystem::configured(db),
+        ..Default::default()
+    };
+
+    if wants("flights") {
+        data.flights_added = db.conn.query_row(
+            "SELECT COUNT(*) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND created_at >= ?2",
+            params![user_id, start_date],
+            |row| row.get(0),
+        )?;
+        let mut stmt = db.conn.prepare(
+            "SELECT substr(departure_datetime, 1, 10), departure_airport, arrival_airport
+             FROM flights
+             WHERE user_id = ?1 AND deleted_at IS NULL AND created_at >= ?2
+             ORDER BY departure_datetime DESC
+             LIMIT 10",
+        )?;
+        data.recent_flights = stmt
+            .query_map(params![user_id, start_date], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
+    if wants("hours") {
+        (data.flights_flown, data.hours_flown, data.distance_km) = db.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(total_duration), 0) / 60.0, COALESCE(SUM(distance_km), 0.0)
+             FROM flights
+             WHERE user_id = ?1 AND deleted_at IS NULL
+               AND departure_datetime >= ?2 AND substr(departure_datetime, 1, 10) <= ?3",
+            params![user_id, start_date, end_date],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+    }
+
+    if wants("anomalies") {
+        let mut stmt = db.conn.prepare(
+            "SELECT a.severity, a.description
+             FROM flight_anomalies a
+             JOIN flights f ON f.id = a.flight_id
+             WHERE f.user_id = ?1 AND f.deleted_at IS NULL AND a.created_at >= ?2
+             ORDER BY a.created_at DESC",
+        )?;
+        data.anomalies = stmt
+            .query_map(params![user_id, start_date], |row| {
+                Ok((row.get::<_, Option<String>>(0)?.unwrap_or_else(|| "warning".to_string()), row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
+    if wants("ai_spend") {
+        (data.ai_requests, data.ai_tokens, data.ai_cost_usd) = db.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(tokens_used), 0), COALESCE(SUM(cost_usd), 0.0)
+             FROM agent_memory
+             WHERE (user_id = ?1 OR user_id IS NULL) AND created_at >= ?2",
+            params![user_id, start_date],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+    }
+
+    if wants("currency") {
+        data.currency = db.get_pilot_currency(user_id)?;
+        data.expiry_alerts = crate::expiry_alerts::notified_since(&db.conn, user_id, start_date)?;
+    }
+
+    if wants("maintenance") {
+        data.maintenance = maintenance::maintenance_due(db, user_id, None, Utc::now().date_naive())?
+            .into_iter()
+            .filter(|item| matches!(item.status, MaintenanceStatus::Overdue | MaintenanceStatus::DueSoon))
+            .collect();
+    }
+
+    Ok(data)
+}
+
+fn render_digest(period: &str, start_date: &str, end_date: &str, sections: &[String], data: &DigestData) -> String {
+    let title = if period == "weekly" { "Weekly" } else { "Monthly" };
+    let mut md = format!("# {} Flight Digest\n\n**Period:** {} to {}\n\n", title, start_date, end_date);
+
+    for section in sections {
+        match section.as_str() {
+            "flights" => {
+                md.push_str("## Flights Added\n\n");
+                md.push_str(&format!("{} flight(s) added this period.\n\n", data.flights_added));
+                for (date, departure, arrival) in &data.recent_flights {
+                    md.push_str(&format!("- {} {} → {}\n", date, departure, arrival));
+                }
+                if !data.recent_flights.is_empty() {
+                    md.push('\n');
+                }
+            }
+            "hours" => {
+                md.push_str("## Hours Flown\n\n");
+                let distance = data.units.distance(data.distance_km);
+                md.push_str(&format!(
+                    "- Flights: {}\n- Hours: {:.1}\n- Distance: {:.0} {}\n\n",
+                    data.flights_flown, data.hours_flown, distance.value, distance.unit
+                ));
+            }
+            "anomalies" => {
+                md.push_str("## New Anomalies\n\n");
+                if data.anomalies.is_empty() {
+                    md.push_str("No new anomalies.\n\n");
+                } else {
+                    for (severity, description) in &data.anomalies {
+                        md.push_str(&format!("- **{}** {}\n", severity, description));
+                    }
+                    md.push('\n');
+                }
+            }
+            "ai_spend" => {
+                md.push_str("## AI Research Spend\n\n");
+                md.push_str(&format!(
+                    "- Requests: {}\n- Tokens: {}\n- Cost: ${:.2}\n\n",
+                    data.ai_requests, data.ai_tokens, data.ai_cost_usd
+                ));
+            }
+            "currency" => {
+                md.push_str("## Currency Status\n\n");
+                if data.currency.is_empty() {
+                    md.push_str("No currency data.\n\n");
+                } else {
+                    md.push_str("| Requirement | Current | Required | Status |\n|---|---|---|---|\n");
+                    for item in &data.currency {
+                        md.push_str(&format!(
+                            "| {} | {} | {} | {} |\n",
+                            item.requirement, item.current_count, item.required, item.status
+                        ));
+                    }
+                    md.push('\n');
+                }
+                if !data.expiry_alerts.is_empty() {
+                    md.push_str("Expiry alerts this period:\n\n");
+                    for (label, expires_date, stage) in &data.expiry_alerts {
+                        let verb = if stage == crate::expiry_alerts::STAGE_EXPIRED { "expired" } else { "expires" };
+                        md.push_str(&format!("- {} {} {}\n", label, verb, expires_date));
+                    }
+                    md.push('\n');
+                }
+            }
+            "maintenance" => {
+                md.push_str("## Maintenance Due\n\n");
+                if data.maintenance.is_empty() {
+                    md.push_str("Nothing overdue or due soon.\n\n");
+                } else {
+                    md.push_str("| Aircraft | Item | Hours left | Days left | Status |\n|---|---|---|---|---|\n");
+                    for item in &data.maintenance {
+                        let status = if item.status == MaintenanceStatus::Overdue { "**OVERDUE**" } else { "Due soon" };
+                        md.push_str(&format!(
+                            "| {} | {} | {} | {} | {} |\n",
+                            item.schedule.aircraft_registration,
+                            item.schedule.item,
+                            item.hours_remaining.map_or("-".to_string(), |h| format!("{:.1}", h)),
+                            item.days_remaining.map_or("-".to_string(), |d| d.to_string()),
+                            status
+                        ));
+                    }
+                    md.push('\n');
+                }
+            }
+            "security" => {
+                // Only a running sentinel is a source; never report placeholder alerts
+                if let Some(alerts) = &data.security_alerts {
+                    md.push_str("## Security Alerts\n\n");
+                    if alerts.is_empty() {
+                        md.push_str("No security alerts.\n\n");
+                    } else {
+                        for alert in alerts {
+                            md.push_str(&format!("- {}\n", alert));
+                        }
+                        md.push('\n');
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    md
+}
+
+/// The "digest_webhook_url" setting, when set. Expiry alerts are delivered there too.
+pub(crate) fn webhook_url(db: &crate::database::Database) -> Option<String> {
+    db.get_setting(WEBHOOK_SETTING).ok().flatten().filter(|url| !url.trim().is_empty())
+}
+
+/// POST a JSON payload to the digest webhook
+pub(crate) async fn post_webhook(url: &str, payload: &serde_json::Value) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(payload)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {}", e))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Webhook returned {}", response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sections() {
+        assert_eq!(parse_sections(None), ALL_SECTIONS.to_vec());
+        assert_eq!(parse_sections(Some(" currency, Flights,bogus")), vec!["flights", "currency"]);
+    }
+
+    #[test]
+    fn test_render_only_selected_sections() {
+        let data = DigestData {
+            flights_added: 2,
+            ai_cost_usd: 1.5,
+            ..Default::default()
+        };
+        let sections = parse_sections(Some("flights,ai_spend"));
+        let md = render_digest("weekly", "2025-01-01", "2025-01-08", &sections, &data);
+
+        assert!(md.starts_with("# Weekly Flight Digest"));
+        assert!(md.contains("2 flight(s) added"));
+        assert!(md.contains("Cost: $1.50"));
+        assert!(!md.contains("## Currency Status"));
+    }
+
+    #[test]
+    fn test_distance_in_configured_units() {
+        let data = DigestData {
+            distance_km: 1852.0,
+            units: UnitSystem::Aviation,
+            ..Default::default()
+        };
+        let md = render_digest("weekly", "2025-01-01", "2025-01-08", &parse_sections(Some("hours")), &data);
+        assert!(md.contains("- Distance: 1000 nm"));
+    }
+
+    #[test]
+    fn test_security_section_needs_a_sentinel() {
+        let sections = parse_sections(Some("security"));
+        let md = render_digest("weekly", "2025-01-01", "2025-01-08", &sections, &DigestData::default());
+        assert!(!md.contains("## Security Alerts"));
+
+        let data = DigestData {
+            security_alerts: Some(Vec::new()),
+            ..Default::default()
+        };
+        let md = render_digest("weekly", "2025-01-01", "2025-01-08", &sections, &data);
+        assert!(md.contains("No security alerts."));
+    }
+
+    #[test]
+    fn test_render_overdue_maintenance() {
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let schedule = maintenance::MaintenanceSchedule {
+            id: "m1".to_string(),
+            user_id: "u1".to_string(),
+            aircraft_registration: "N12345".to_string(),
+            item: "Oil change".to_string(),
+            interval_hours: Some(50.0),
+            interval_days: None,
+            last_done_hours: Some(100.0),
+            last_done_date: None,
+            notes: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        let data = DigestData {
+            maintenance: vec![maintenance::compute_due(schedule, 155.0, today)],
+            ..Default::default()
+        };
+        let md = render_digest("weekly", "2025-02-22", "2025-03-01", &parse_sections(Some("maintenance")), &data);
+
+        assert!(md.contains("| N12345 | Oil change | -5.0 | - | **OVERDUE** |"));
+    }
+}
@@ -126,7 +126,7 @@ pub async fn start_document_worker(
     // Spawn worker in background
     tauri::async_runtime::spawn(async move {
         if let Err(e) = worker_pool.run_worker().await {
-            eprintln!("❌ Document worker error: {}", e);
+            tracing::error!("Document worker error: {}", e);
         }
     });
 
@@ -228,7 +228,7 @@ pub fn build_flight_relationships(
     let mut stmt = conn.prepare(
         "SELECT f.id, f.flight_number, f.notes, f.departure_airport, f.arrival_airport, f.departure_datetime
          FROM flights f
-         WHERE f.notes IS NOT NULL AND f.notes LIKE 'Passengers:%'"
+         WHERE f.deleted_at IS NULL AND f.notes IS NOT NULL AND f.notes LIKE 'Passengers:%'"
     ).map_err(|e| {
         let _ = conn.execute("ROLLBACK", []);
         e.to_string()
@@ -1,4 +1,5 @@
 // Custom documents management commands
+use chrono::Local;
 use tauri::State;
 use std::fs;
 use std::path::Path;
@@ -97,6 +98,10 @@ pub fn delete_custom_document(
 
 /// Import a document from an image file using OCR (Gemini 2.5 Flash Lite)
 /// Extracts text from the image and creates a new document with the content
+///
+/// Flights found in the text (an itinerary or boarding pass with a flight number, known
+/// airports and a date) are created as OCR imports, with distance, duration and CO2
+/// calculated inline; a document naming a single flight is linked to it.
 #[tauri::command]
 pub async fn import_document_with_ocr(
     user_id: String,
@@ -140,16 +145,32 @@ pub async fn import_document_with_ocr(
         title
     };
 
-    // Create document with extracted text
+    // Create flights found in the text, then the document with extracted text
     let document_id = {
         let db = state.db.lock().map_err(|e| e.to_string())?;
+        let segments = crate::eml_import::extract_text_segments(&extracted_text, Local::now().date_naive(), |code| {
+            super::batch_calculations::airport_coords(&db, code).is_some()
+        });
+        let currency = crate::currency::CurrencySettings::configured(&db);
+        let mut flight_ids = Vec::new();
+        for segment in segments.iter().filter(|segment| segment.missing_required().is_empty()) {
+            let imported = super::ocr::import_ocr_flight(&db, &user_id, &currency, &segment.to_ocr_result()).map_err(|e| e.to_string())?;
+            flight_ids.extend(imported.flight_id);
+        }
+        if !flight_ids.is_empty() {
+            tracing::info!("[OCR] Created {} flight(s) from document {}", flight_ids.len(), filename);
+        }
+
         db.create_custom_document(
             &user_id,
             &document_title,
             &extracted_text,
             category.as_deref(),
             None, // tags
-            None, // flight_id
+            match flight_ids.as_slice() {
+                [only] => Some(only.as_str()),
+                _ => None,
+            },
             None, // journey_id
             None, // passenger_name
             None, // fuel_entry_id
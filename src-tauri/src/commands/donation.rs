@@ -41,6 +41,7 @@ pub fn generate_qr_code(data: String, size: Option<u32>) -> Result<String, Strin
     let pixel_size = size.unwrap_or(4);
 
     let svg_string = code.render()
+        .module_dimensions(pixel_size, pixel_size)
         .min_dimensions(200, 200)
         .dark_color(svg::Color("#ffffff"))
         .light_color(svg::Color("#00000000")) // Transparent background
@@ -83,7 +84,7 @@ pub fn get_donation_config() -> Result<DonationConfig, String> {
 #[tauri::command]
 pub fn record_donation_click(method: String) -> Result<(), String> {
     // Log the click for analytics (privacy-respecting, local only)
-    println!("Donation click recorded: {}", method);
+    tracing::info!("Donation click recorded: {}", method);
     Ok(())
 }
 
@@ -0,0 +1,121 @@
+// Email import commands - flights from saved airline confirmation emails (.eml)
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::AppState;
+use crate::aircraft_performance::{performance_for, AircraftRef};
+use crate::eml_import::{self, EmlSegment};
+use crate::error::CommandError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmlImportRow {
+    /// Position of the segment in the email; pass it back in `segments` to import it
+    pub index: usize,
+    pub segment: EmlSegment,
+    /// Fields that were found in the email
+    pub extracted: Vec<String>,
+    /// Required fields that were not found; the segment cannot be imported without them
+    pub missing: Vec<String>,
+    /// Set once the flight is written (never on a dry run)
+    pub flight_id: Option<String>,
+    pub error: Option<String>,
+    /// Not selected for import
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmlImportResult {
+    pub dry_run: bool,
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub segments: Vec<EmlImportRow>,
+    pub imported_count: usize,
+    pub error_count: usize,
+}
+
+/// Read the flights in a saved confirmation email and optionally import them
+///
+/// schema.org FlightReservation markup is used when the email has it; otherwise the
+/// itinerary text is parsed. Call with `dry_run` first to review what was extracted,
+/// then again with the chosen segment indices (all complete segments when omitted).
+/// Flights are stored with `data_source = 'email'`.
+#[tauri::command]
+pub fn import_flights_from_eml(
+    user_id: String,
+    file_path: String,
+    dry_run: Option<bool>,
+    segments: Option<Vec<usize>>,
+    state: State<'_, AppState>,
+) -> Result<EmlImportResult, CommandError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let raw = std::fs::read(&file_path)
+        .map_err(|e| CommandError::Validation(format!("Failed to read {}: {}", file_path, e)))?;
+    let message = eml_import::parse_eml(&raw).map_err(|e| CommandError::Validation(e.to_string()))?;
+
+    let db = state.db.lock()?;
+    let currency = crate::currency::CurrencySettings::configured(&db);
+    let found = eml_import::extract_segments(&message, |code| {
+        super::batch_calculations::airport_coords(&db, code).is_some()
+    });
+
+    let mut result = EmlImportResult {
+        dry_run,
+        subject: message.subject.clone(),
+        from: message.from.clone(),
+        segments: Vec::with_capacity(found.len()),
+        imported_count: 0,
+        error_count: 0,
+    };
+
+    // Dropped without commit on an early return, which rolls the import back
+    let tx = if dry_run { None } else { Some(db.conn.unchecked_transaction()?) };
+    for (index, segment) in found.into_iter().enumerate() {
+        let missing: Vec<String> = segment.missing_required().into_iter().map(String::from).collect();
+        let mut row = EmlImportRow {
+            index,
+            extracted: segment.extracted_fields().into_iter().map(String::from).collect(),
+            skipped: segments.as_ref().is_some_and(|selected| !selected.contains(&index)),
+            missing,
+            segment,
+            flight_id: None,
+            error: None,
+        };
+
+        if !row.missing.is_empty() {
+            row.error = Some(format!("Missing {}", row.missing.join(", ")));
+        } else if !row.skipped && !dry_run {
+            let ocr = row.segment.to_ocr_result();
+            let distance = ocr
+                .route_codes()
+                .and_then(|(departure, arrival)| super::batch_calculations::route_distance(&db, &departure, &arrival));
+            let performance = performance_for(&db, AircraftRef::named(ocr.aircraft_type.as_deref()));
+            let created = ocr.to_flight_input(distance, &performance).and_then(|mut flight| {
+                if let Some(subject) = &message.subject {
+                    let note = format!("Email: {}", subject);
+                    flight.notes = Some(match flight.notes {
+                        Some(notes) => format!("{} | {}", notes, note),
+                        None => note,
+                    });
+                }
+                crate::validation::validate_flight_input(&flight)?;
+                db.create_flight_with_currency(&user_id, &flight, "email", &currency)
+            });
+            match created {
+                Ok(flight_id) => {
+                    row.flight_id = Some(flight_id);
+                    result.imported_count += 1;
+                }
+                Err(e) => row.error = Some(e.to_string()),
+            }
+        }
+        if row.error.is_some() && !row.skipped {
+            result.error_count += 1;
+        }
+        result.segments.push(row);
+    }
+    if let Some(tx) = tx {
+        tx.commit()?;
+    }
+
+    Ok(result)
+}
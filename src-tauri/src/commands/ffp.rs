@@ -1,7 +1,12 @@
 // Frequent Flyer Program commands
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use super::AppState;
+use crate::airlines::{self, Airline};
+use crate::database::Database;
+use crate::fare_classes::{builtin_cabin, Cabin};
 
 // ===== FREQUENT FLYER PROGRAM COMMANDS =====
 
@@ -73,3 +78,303 @@ pub fn delete_ffp(ffp_id: String, state: State<'_, AppState>) -> Result<(), Stri
     let db = state.db.lock().map_err(|e| e.to_string())?;
     db.delete_ffp(&ffp_id).map_err(|e| e.to_string())
 }
+
+// ===== MILEAGE CREDITING =====
+
+const KM_PER_MILE: f64 = 1.609_344;
+
+/// A flight that could be credited to a program, with estimated earnings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfpCreditFlight {
+    pub flight_id: String,
+    pub flight_number: Option<String>,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    pub departure_datetime: String,
+    /// IATA code of the operating airline
+    pub airline: String,
+    /// "airline" when the program belongs to the operating carrier, "alliance" for partners
+    pub match_reason: String,
+    pub fare_class: Option<String>,
+    pub distance_miles: f64,
+    pub estimated_miles: f64,
+    pub estimated_tier_miles: f64,
+}
+
+/// Proposed balance changes for one program; nothing is applied until approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfpCreditSuggestion {
+    pub ffp_id: String,
+    pub program_name: String,
+    pub current_miles: f64,
+    pub tier_miles: f64,
+    pub proposed_current_miles: f64,
+    pub proposed_tier_miles: f64,
+    pub flights: Vec<FfpCreditFlight>,
+}
+
+/// Match flown, uncredited flights to the user's programs by operating airline or alliance
+/// and estimate the miles they would earn from distance and fare class
+///
+/// A flight is proposed for one program only, preferring the carrier's own program over an
+/// alliance partner. Flights already credited (or with miles_earned set) are skipped.
+#[tauri::command]
+pub fn suggest_ffp_credits(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<FfpCreditSuggestion>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    build_credit_suggestions(&db, &user_id).map_err(|e| e.to_string())
+}
+
+/// Apply an approved suggestion: credit the chosen flights to the program, add their miles
+/// to its balances and mark them credited. Returns the updated program.
+#[tauri::command]
+pub fn apply_ffp_credits(
+    ffp_id: String,
+    flight_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::models::FrequentFlyerProgram, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    apply_credits(&db, &ffp_id, &flight_ids).map_err(|e| e.to_string())
+}
+
+fn build_credit_suggestions(db: &Database, user_id: &str) -> anyhow::Result<Vec<FfpCreditSuggestion>> {
+    let programs = db.list_user_ffps(user_id)?;
+    let mut suggestions: Vec<FfpCreditSuggestion> = programs
+        .iter()
+        .map(|ffp| FfpCreditSuggestion {
+            ffp_id: ffp.id.clone(),
+            program_name: ffp.program_name.clone(),
+            current_miles: ffp.current_miles,
+            tier_miles: ffp.tier_miles,
+            proposed_current_miles: ffp.current_miles,
+            proposed_tier_miles: ffp.tier_miles,
+            flights: Vec::new(),
+        })
+        .collect();
+    if programs.is_empty() {
+        return Ok(suggestions);
+    }
+
+    let mut stmt = db.conn.prepare(
+        "SELECT id, flight_number, departure_airport, arrival_airport, departure_datetime,
+                airline, fare_class, distance_km
+         FROM flights
+         WHERE user_id = ?1 AND deleted_at IS NULL
+           AND distance_km IS NOT NULL AND miles_earned IS NULL
+           AND date(departure_datetime) <= date('now')
+           AND id NOT IN (SELECT flight_id FROM ffp_credits)
+         ORDER BY departure_datetime ASC",
+    )?;
+    let rows = stmt.query_map(params![user_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, f64>(7)?,
+        ))
+    })?;
+
+    for row in rows {
+        let (flight_id, flight_number, departure_airport, arrival_airport, departure_datetime, airline, fare_class, distance_km) = row?;
+        // Flights imported before airline inference existed may only have a flight number
+        let carrier = match airline.as_deref() {
+            Some(code) => airlines::lookup_code(code),
+            None => flight_number.as_deref().and_then(airlines::infer_from_flight_number),
+        };
+        let Some(carrier) = carrier else {
+            continue;
+        };
+
+        let matched = programs
+            .iter()
+            .position(|ffp| program_belongs_to(ffp, carrier))
+            .map(|i| (i, "airline"))
+            .or_else(|| {
+                let alliance = carrier.alliance.as_deref()?;
+                programs
+                    .iter()
+                    .position(|ffp| program_alliance(ffp).is_some_and(|a| a.eq_ignore_ascii_case(alliance)))
+                    .map(|i| (i, "alliance"))
+            });
+        let Some((index, match_reason)) = matched else {
+            continue;
+        };
+
+        let distance_miles = distance_km / KM_PER_MILE;
+        let (earn_rate, tier_rate) = fare_class_multipliers(fare_class.as_deref());
+        let suggestion = &mut suggestions[index];
+        let flight = FfpCreditFlight {
+            flight_id,
+            flight_number,
+            departure_airport,
+            arrival_airport,
+            departure_datetime,
+            airline: carrier.iata.clone(),
+            match_reason: match_reason.to_string(),
+            fare_class,
+            distance_miles: distance_miles.round(),
+            estimated_miles: (distance_miles * earn_rate).round(),
+            estimated_tier_miles: (distance_miles * tier_rate).round(),
+        };
+        suggestion.proposed_current_miles += flight.estimated_miles;
+        suggestion.proposed_tier_miles += flight.estimated_tier_miles;
+        suggestion.flights.push(flight);
+    }
+
+    Ok(suggestions)
+}
+
+fn apply_credits(
+    db: &Database,
+    ffp_id: &str,
+    flight_ids: &[String],
+) -> anyhow::Result<crate::models::FrequentFlyerProgram> {
+    let ffp = db
+        .get_ffp(ffp_id)?
+        .ok_or_else(|| anyhow::anyhow!("Frequent flyer program not found: {}", ffp_id))?;
+
+    // Re-derive the estimates so only flights still eligible for this program are credited
+    let credits: Vec<FfpCreditFlight> = build_credit_suggestions(db, &ffp.user_id)?
+        .into_iter()
+        .find(|s| s.ffp_id == ffp.id)
+        .map(|s| s.flights)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|f| flight_ids.contains(&f.flight_id))
+        .collect();
+
+    let miles: f64 = credits.iter().map(|f| f.estimated_miles).sum();
+    let tier_miles: f64 = credits.iter().map(|f| f.estimated_tier_miles).sum();
+
+    let tx = db.conn.unchecked_transaction()?;
+    for credit in &credits {
+        tx.execute(
+            "INSERT INTO ffp_credits (flight_id, ffp_id, miles, tier_miles) VALUES (?1, ?2, ?3, ?4)",
+            params![credit.flight_id, ffp.id, credit.estimated_miles, credit.estimated_tier_miles],
+        )?;
+        tx.execute(
+            "UPDATE flights SET frequent_flyer_program = ?1, miles_earned = ?2, updated_at = datetime('now')
+             WHERE id = ?3",
+            params![ffp.program_name, credit.estimated_miles, credit.flight_id],
+        )?;
+    }
+    db.update_ffp(
+        &ffp.id,
+        &ffp.program_name,
+        ffp.airline.as_deref(),
+        ffp.alliance.as_deref(),
+        ffp.member_number.as_deref(),
+        ffp.tier_status.as_deref(),
+        ffp.current_miles + miles,
+        ffp.lifetime_miles + miles,
+        ffp.tier_miles + tier_miles,
+        ffp.tier_expiry_date.as_deref(),
+        ffp.notes.as_deref(),
+    )?;
+    tx.commit()?;
+
+    db.get_ffp(&ffp.id)?
+        .ok_or_else(|| anyhow::anyhow!("Frequent flyer program not found: {}", ffp_id))
+}
+
+/// True when the program is the carrier's own (by program name or airline name/code)
+fn program_belongs_to(ffp: &crate::models::FrequentFlyerProgram, carrier: &Airline) -> bool {
+    let same = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+    carrier.ffp_program.as_deref().is_some_and(|p| same(p, &ffp.program_name))
+        || ffp.airline.as_deref().is_some_and(|a| {
+            same(a, &carrier.name) || same(a, &carrier.iata) || same(a, &carrier.icao)
+        })
+}
+
+/// The program's alliance, or the alliance of the airline it belongs to when not recorded
+fn program_alliance(ffp: &crate::models::FrequentFlyerProgram) -> Option<&str> {
+    if let Some(alliance) = ffp.alliance.as_deref().filter(|a| !a.trim().is_empty()) {
+        return Some(alliance.trim());
+    }
+    let owner = ffp
+        .airline
+        .as_deref()
+        .and_then(airlines::lookup_name)
+        .or_else(|| airlines::lookup_program(&ffp.program_name))?;
+    owner.alliance.as_deref()
+}
+
+/// (redeemable, tier) earning rate per flown mile for a cabin or booking class
+/// These are typical distance-based rates; actual earning depends on the program and fare.
+fn fare_class_multipliers(fare_class: Option<&str>) -> (f64, f64) {
+    let Some(fare_class) = fare_class.map(str::trim).filter(|f| !f.is_empty()) else {
+        return (1.0, 1.0);
+    };
+    // Deeply discounted economy booking classes earn at a reduced rate
+    let discounted = matches!(
+        fare_class.to_ascii_uppercase().as_str(),
+        "K" | "L" | "Q" | "T" | "V" | "S" | "N" | "O" | "G" | "X"
+    );
+
+    match builtin_cabin(fare_class) {
+        Some(Cabin::First) => (1.5, 1.5),
+        Some(Cabin::Business) => (1.25, 1.5),
+        Some(Cabin::Premium) => (1.1, 1.0),
+        Some(Cabin::Economy) if discounted => (0.5, 0.5),
+        _ => (1.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fare_class_multipliers() {
+        assert_eq!(fare_class_multipliers(Some("Business")), (1.25, 1.5));
+        assert_eq!(fare_class_multipliers(Some("j")), (1.25, 1.5));
+        assert_eq!(fare_class_multipliers(Some("Q")), (0.5, 0.5));
+        assert_eq!(fare_class_multipliers(Some("Upper Class")), (1.25, 1.5));
+        assert_eq!(fare_class_multipliers(Some("Prem Eco")), (1.1, 1.0));
+        assert_eq!(fare_class_multipliers(None), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_suggest_and_apply_credits() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Flyer');
+                 INSERT INTO frequent_flyer_programs (id, user_id, program_name, current_miles, lifetime_miles, tier_miles)
+                 VALUES ('p1', 'u1', 'AAdvantage', 1000, 5000, 200);
+                 INSERT INTO flights (id, user_id, flight_number, departure_airport, arrival_airport, departure_datetime, distance_km, fare_class, airline)
+                 VALUES ('own', 'u1', 'AA100', 'JFK', 'LHR', '2024-03-01T18:00:00', 1609.344, 'Business', 'AA'),
+                        ('partner', 'u1', 'BA117', 'LHR', 'JFK', '2024-03-08T11:00:00', 1609.344, 'Q', NULL),
+                        ('other', 'u1', 'LH400', 'FRA', 'JFK', '2024-03-09T10:00:00', 1609.344, NULL, 'LH'),
+                        ('future', 'u1', 'AA101', 'JFK', 'LHR', '2999-01-01T18:00:00', 1609.344, NULL, 'AA');",
+            )
+            .unwrap();
+
+        let suggestions = build_credit_suggestions(&db, "u1").unwrap();
+        let flights = &suggestions[0].flights;
+        assert_eq!(flights.len(), 2);
+        assert_eq!((flights[0].flight_id.as_str(), flights[0].match_reason.as_str()), ("own", "airline"));
+        assert_eq!((flights[1].flight_id.as_str(), flights[1].match_reason.as_str()), ("partner", "alliance"));
+        assert_eq!(flights[0].estimated_miles, 1250.0);
+        assert_eq!(flights[1].estimated_miles, 500.0);
+        assert_eq!(suggestions[0].proposed_current_miles, 2750.0);
+
+        let updated = apply_credits(&db, "p1", &["own".to_string()]).unwrap();
+        assert_eq!(updated.current_miles, 2250.0);
+        assert_eq!(updated.lifetime_miles, 6250.0);
+        assert_eq!(updated.tier_miles, 1700.0);
+
+        // Credited flights are never proposed or applied again
+        let remaining = build_credit_suggestions(&db, "u1").unwrap();
+        assert_eq!(remaining[0].flights.len(), 1);
+        let again = apply_credits(&db, "p1", &["own".to_string()]).unwrap();
+        assert_eq!(again.current_miles, 2250.0);
+    }
+}
@@ -0,0 +1,74 @@
+// Flight share card commands - a flight rendered as a PNG for sharing
+use base64::Engine;
+use tauri::{AppHandle, Manager, State};
+
+use super::AppState;
+use crate::error::CommandError;
+use crate::flight_card::{self, CardFonts, CardTheme, FlightCard};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FlightCardExport {
+    /// Where the PNG was saved, under the app data dir
+    pub path: String,
+    /// The same PNG, base64 encoded, for sharing without reading the file back
+    pub png_base64: String,
+}
+
+/// The card's file name; the flight ID becomes part of a path, so only UUID-style IDs are accepted
+fn card_file_name(flight_id: &str) -> Result<String, CommandError> {
+    if flight_id.is_empty() || !flight_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(CommandError::Validation(format!("Invalid flight ID '{}'", flight_id)));
+    }
+    Ok(format!("{}.png", flight_id))
+}
+
+/// Render a flight as a share card and save it as cards/<flight_id>.png
+///
+/// The theme takes the same hex colours as the themed donation QR codes; the default is
+/// white on dark. Flights without airport coordinates get the card without the map.
+#[tauri::command]
+pub fn export_flight_card(
+    flight_id: String,
+    theme: Option<CardTheme>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<FlightCardExport, CommandError> {
+    let file_name = card_file_name(&flight_id)?;
+    let theme = theme.unwrap_or_default();
+    theme.validate().map_err(|e| CommandError::Validation(e.to_string()))?;
+
+    let card = {
+        let db = state.db.lock()?;
+        FlightCard::load(&db, &flight_id)?.ok_or_else(|| CommandError::not_found("Flight", &flight_id))?
+    };
+    let fonts = CardFonts::bundled()?;
+    let png = flight_card::encode_png(&flight_card::render(&card, &theme, &fonts)?)?;
+
+    let cards_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| CommandError::Internal(e.to_string()))?
+        .join("cards");
+    std::fs::create_dir_all(&cards_dir)
+        .map_err(|e| CommandError::Internal(format!("Failed to create cards directory: {}", e)))?;
+    let path = cards_dir.join(file_name);
+    std::fs::write(&path, &png).map_err(|e| CommandError::Internal(format!("Failed to save card: {}", e)))?;
+
+    Ok(FlightCardExport {
+        path: path.to_string_lossy().to_string(),
+        png_base64: base64::engine::general_purpose::STANDARD.encode(&png),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_file_name() {
+        assert_eq!(card_file_name("6f1c2a4e-93b1-4d0e-8f7a-2b5c9d1e0a37").unwrap(), "6f1c2a4e-93b1-4d0e-8f7a-2b5c9d1e0a37.png");
+        for bad in ["", "../../.bashrc", "a/b", "C:\\x", "flight.png", "id with space"] {
+            assert_eq!(card_file_name(bad).unwrap_err().code(), "validation", "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// Flight tag commands - structured, user-scoped classification of flights
+use serde::{Deserialize, Serialize};
+use rusqlite::OptionalExtension;
+use tauri::State;
+
+use super::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub flight_count: i64,
+}
+
+/// Normalize a tag: trimmed, lowercase, internal whitespace collapsed
+pub(crate) fn normalize_tag(tag: &str) -> Result<String, String> {
+    let normalized = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    if normalized.is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+    if normalized.chars().count() > 64 {
+        return Err("Tag cannot be longer than 64 characters".to_string());
+    }
+
+    Ok(normalized)
+}
+
+/// Add a tag to a flight (no-op if already tagged)
+#[tauri::command]
+pub fn add_flight_tag(
+    flight_id: String,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let tag = normalize_tag(&tag)?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let exists = db.conn
+        .query_row(
+            "SELECT 1 FROM flights WHERE id = ?1",
+            rusqlite::params![flight_id],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .is_some();
+    if !exists {
+        return Err(format!("Flight not found: {}", flight_id));
+    }
+
+    db.conn
+        .execute(
+            "INSERT OR IGNORE INTO flight_tags (flight_id, tag) VALUES (?1, ?2)",
+            rusqlite::params![flight_id, tag],
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(tag)
+}
+
+/// Remove a tag from a flight
+#[tauri::command]
+pub fn remove_flight_tag(
+    flight_id: String,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let tag = normalize_tag(&tag)?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let rows = db.conn
+        .execute(
+            "DELETE FROM flight_tags WHERE flight_id = ?1 AND tag = ?2",
+            rusqlite::params![flight_id, tag],
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows > 0)
+}
+
+/// List tags on a single flight
+#[tauri::command]
+pub fn list_flight_tags(
+    flight_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = db.conn
+        .prepare("SELECT tag FROM flight_tags WHERE flight_id = ?1 ORDER BY tag")
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map(rusqlite::params![flight_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+/// List every tag used by a user's flights, most used first (for autocomplete)
+#[tauri::command]
+pub fn list_all_tags(
+    user_id: String,
+    prefix: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TagUsage>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    tag_usage(&db, &user_id, prefix.as_deref()).map_err(|e| e.to_string())
+}
+
+fn tag_usage(
+    db: &crate::database::Database,
+    user_id: &str,
+    prefix: Option<&str>,
+) -> rusqlite::Result<Vec<TagUsage>> {
+    let prefix_pattern = format!(
+        "{}%",
+        crate::database::escape_like(&prefix.map(|p| p.trim().to_lowercase()).unwrap_or_default())
+    );
+
+    let mut stmt = db.conn.prepare(
+        "SELECT t.tag, COUNT(*) as flight_count
+         FROM flight_tags t
+         JOIN flights f ON f.id = t.flight_id
+         WHERE f.user_id = ?1 AND f.deleted_at IS NULL AND t.tag LIKE ?2 ESCAPE '\\'
+         GROUP BY t.tag
+         ORDER BY flight_count DESC, t.tag ASC",
+    )?;
+
+    let tags = stmt
+        .query_map(rusqlite::params![user_id, prefix_pattern], |row| {
+            Ok(TagUsage {
+                tag: row.get(0)?,
+                flight_count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_tag_prefix_matches_literally() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'KJFK', 'KBOS', '2025-01-05T09:00:00');
+                 INSERT INTO flight_tags (flight_id, tag)
+                 VALUES ('f1', '100% business'), ('f1', '100 miles'), ('f1', 'a_b'), ('f1', 'axb'), ('f1', 'c\\d');",
+            )
+            .unwrap();
+        let tags = |prefix: &str| -> Vec<String> {
+            let mut tags: Vec<String> =
+                tag_usage(&db, "u1", Some(prefix)).unwrap().into_iter().map(|t| t.tag).collect();
+            tags.sort();
+            tags
+        };
+
+        assert_eq!(tags("100%"), ["100% business"]);
+        assert_eq!(tags("a_"), ["a_b"]);
+        assert_eq!(tags("c\\"), ["c\\d"]);
+        assert_eq!(tags("").len(), 5);
+    }
+}
@@ -0,0 +1,128 @@
+// Flight template commands - reusable field sets for repeatedly flown routes
+use chrono::NaiveDate;
+use tauri::State;
+
+use super::AppState;
+use crate::database::Database;
+use crate::error::CommandError;
+use crate::flight_templates::{self, FlightTemplate};
+use crate::models::FlightInput;
+use crate::write_queue::WritePriority;
+
+/// The user's template; another user's template is not found
+fn load_template(state: &State<'_, AppState>, user_id: &str, template_id: &str) -> Result<FlightTemplate, CommandError> {
+    let db = state.db.lock()?;
+    flight_templates::get_template(&db.conn, user_id, template_id)?
+        .ok_or_else(|| CommandError::not_found("Flight template", template_id))
+}
+
+fn parse_date(field: &str, value: &str) -> Result<NaiveDate, CommandError> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| CommandError::Validation(format!("{} must be a YYYY-MM-DD date, got \"{}\"", field, value)))
+}
+
+/// Create the template's flight on one date. The template's own distance wins; a template
+/// without one between two different airports gets the route distance.
+fn create_from_template(db: &Database, template: &FlightTemplate, date: NaiveDate) -> anyhow::Result<String> {
+    let mut flight = flight_templates::instantiate(&template.flight, date);
+    let has_distance = flight.distance_km.is_some() || flight.distance_nm.is_some();
+    if !has_distance && !flight.departure_airport.eq_ignore_ascii_case(&flight.arrival_airport) {
+        if let Some((nm, km)) = super::batch_calculations::route_distance(db, &flight.departure_airport, &flight.arrival_airport) {
+            flight.distance_nm = Some(nm);
+            flight.distance_km = Some(km);
+        }
+    }
+    db.create_flight_with_source(&template.user_id, &flight, "template")
+}
+
+/// Save a field set as a named template, either given directly or copied from a flight
+#[tauri::command]
+pub fn create_flight_template(
+    user_id: String,
+    name: String,
+    flight: Option<FlightInput>,
+    flight_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<FlightTemplate, CommandError> {
+    let db = state.db.lock()?;
+    let flight = match (flight, flight_id) {
+        (Some(flight), _) => flight,
+        (None, Some(flight_id)) => db
+            .get_flight(&flight_id)?
+            .map(|f| FlightInput::from(&f))
+            .ok_or_else(|| CommandError::not_found("Flight", &flight_id))?,
+        (None, None) => return Err(CommandError::Validation("Either flight or flight_id is required".to_string())),
+    };
+    Ok(flight_templates::create_template(&db.conn, &user_id, &name, &flight)?)
+}
+
+#[tauri::command]
+pub fn list_flight_templates(user_id: String, state: State<'_, AppState>) -> Result<Vec<FlightTemplate>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(flight_templates::list_templates(&db.conn, &user_id)?)
+}
+
+#[tauri::command]
+pub fn update_flight_template(
+    user_id: String,
+    template_id: String,
+    name: String,
+    flight: FlightInput,
+    state: State<'_, AppState>,
+) -> Result<FlightTemplate, CommandError> {
+    let db = state.db.lock()?;
+    Ok(flight_templates::update_template(&db.conn, &user_id, &template_id, &name, &flight)?)
+}
+
+#[tauri::command]
+pub fn delete_flight_template(user_id: String, template_id: String, state: State<'_, AppState>) -> Result<bool, CommandError> {
+    let db = state.db.lock()?;
+    Ok(flight_templates::delete_template(&db.conn, &user_id, &template_id)?)
+}
+
+/// New flight from a template on `date` (YYYY-MM-DD); returns its id
+#[tauri::command]
+pub fn create_flight_from_template(
+    user_id: String,
+    template_id: String,
+    date: String,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let template = load_template(&state, &user_id, &template_id)?;
+    let date = parse_date("date", &date)?;
+    state
+        .writer
+        .submit(WritePriority::Interactive, move |db| create_from_template(db, &template, date))
+        .map_err(CommandError::from)
+}
+
+/// One flight per date from `start_date` to `end_date` inclusive, every `every_days` days
+/// (default 1). All flights are created or none.
+#[tauri::command]
+pub fn create_flights_from_template_series(
+    user_id: String,
+    template_id: String,
+    start_date: String,
+    end_date: String,
+    every_days: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, CommandError> {
+    let template = load_template(&state, &user_id, &template_id)?;
+    let start = parse_date("start_date", &start_date)?;
+    let end = parse_date("end_date", &end_date)?;
+    let dates = flight_templates::series_dates(start, end, every_days.unwrap_or(1))
+        .map_err(|e| CommandError::Validation(e.to_string()))?;
+
+    state
+        .writer
+        .submit(WritePriority::Bulk, move |db| {
+            let tx = db.conn.unchecked_transaction()?;
+            let ids = dates
+                .into_iter()
+                .map(|date| create_from_template(db, &template, date))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            tx.commit()?;
+            Ok(ids)
+        })
+        .map_err(CommandError::from)
+}
@@ -1,39 +1,442 @@
 // Flight CRUD commands
 use tauri::State;
 use super::AppState;
-use crate::models::{Flight, FlightInput};
+use crate::airlines::{self, Airline};
+use crate::calculations;
+use crate::error::CommandError;
+use crate::models::{DeletedFlight, Flight, FlightEndpointTimes, FlightInput, FlightListFilter, FlightWithTimes, OnThisDayFlight, Page};
+use chrono::Datelike;
+use crate::timezone::{self, AirportZone};
+use crate::units::UnitSystem;
+use crate::validation;
+use crate::write_queue::WritePriority;
 
+/// Goes through the write queue so it is not held up by a running import
+/// Rejects bad airport codes, datetimes and negative numbers with a field-level error.
 #[tauri::command]
 pub fn create_flight(
     user_id: String,
     flight: FlightInput,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.create_flight(&user_id, &flight)
-        .map_err(|e| e.to_string())
+) -> Result<String, CommandError> {
+    validation::validate_flight_input(&flight)?;
+    state
+        .writer
+        .submit(WritePriority::Interactive, move |db| db.create_flight(&user_id, &flight))
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
-pub fn get_flight(flight_id: String, state: State<'_, AppState>) -> Result<Option<Flight>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.get_flight(&flight_id).map_err(|e| e.to_string())
+pub fn get_flight(flight_id: String, state: State<'_, AppState>) -> Result<Option<Flight>, CommandError> {
+    let db = state.db.lock()?;
+    db.get_flight(&flight_id).map_err(CommandError::from)
 }
 
+/// Get a flight with UTC times and offsets computed from the airports' timezones
+#[tauri::command]
+pub fn get_flight_with_times(
+    flight_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<FlightWithTimes>, CommandError> {
+    let db = state.db.lock()?;
+
+    let flight = match db.get_flight(&flight_id)? {
+        Some(f) => f,
+        None => return Ok(None),
+    };
+
+    let departure_tz = db.get_airport_by_code(&flight.departure_airport)?.and_then(|a| a.timezone);
+    let arrival_tz = db.get_airport_by_code(&flight.arrival_airport)?.and_then(|a| a.timezone);
+
+    let mut details = build_flight_times(flight, departure_tz, arrival_tz);
+    measure_flight(UnitSystem::configured(&db), &mut details);
+    Ok(Some(details))
+}
+
+/// Flights flown on today's month and day in earlier years, most recent year first,
+/// each with its computed times
+#[tauri::command]
+pub fn get_flights_on_this_day(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<OnThisDayFlight>, CommandError> {
+    let db = state.db.lock()?;
+    let today = chrono::Local::now().date_naive();
+
+    let units = UnitSystem::configured(&db);
+
+    let flights = db.list_flights_on_day(&user_id, &today.format("%m-%d").to_string(), today.year())?;
+    let mut results = Vec::with_capacity(flights.len());
+    for flight in flights {
+        let year: i32 = flight.departure_datetime.get(..4).and_then(|y| y.parse().ok()).unwrap_or(today.year());
+        let departure_tz = db.get_airport_by_code(&flight.departure_airport)?.and_then(|a| a.timezone);
+        let arrival_tz = db.get_airport_by_code(&flight.arrival_airport)?.and_then(|a| a.timezone);
+        let mut details = build_flight_times(flight, departure_tz, arrival_tz);
+        measure_flight(units, &mut details);
+        results.push(OnThisDayFlight { years_ago: today.year() - year, details });
+    }
+    Ok(results)
+}
+
+/// List flights, optionally filtered by date range, airport (departure or arrival) and
+/// aircraft, and sorted by `filter.sort_by`/`sort_dir`. Without a filter all flights are
+/// returned newest first.
 #[tauri::command]
 pub fn list_flights(
     user_id: String,
     limit: i32,
     offset: i32,
+    filter: Option<FlightListFilter>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Flight>, CommandError> {
+    let db = state.db.lock()?;
+    db.list_flights_filtered(&user_id, &filter.unwrap_or_default(), limit, offset)
+        .map_err(CommandError::from)
+}
+
+/// Like list_flights, plus the total matching count and whether more pages follow
+#[tauri::command]
+pub fn list_flights_page(
+    user_id: String,
+    limit: i32,
+    offset: i32,
+    filter: Option<FlightListFilter>,
     state: State<'_, AppState>,
-) -> Result<Vec<Flight>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.list_flights(&user_id, limit, offset)
-        .map_err(|e| e.to_string())
+) -> Result<Page<Flight>, CommandError> {
+    let db = state.db.lock()?;
+    let filter = filter.unwrap_or_default();
+    db.read_consistent(|db| {
+        let items = db.list_flights_filtered(&user_id, &filter, limit, offset)?;
+        let total = db.count_flights(&user_id, &filter)?;
+        Ok(Page::new(items, total, limit as i64, offset as i64))
+    })
+    .map_err(CommandError::from)
 }
 
+/// Operating carrier for a flight number, with its alliance and usual FFP
+/// None for numeric-only, private or codeshare flight numbers
 #[tauri::command]
-pub fn delete_flight(flight_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.delete_flight(&flight_id).map_err(|e| e.to_string())
-}
\ No newline at end of file
+pub fn lookup_airline(flight_number: String) -> Result<Option<Airline>, CommandError> {
+    Ok(airlines::infer_from_flight_number(&flight_number).cloned())
+}
+
+/// Backfill the airline of existing flights from their flight numbers
+#[tauri::command]
+pub fn infer_flight_airlines(
+    user_id: String,
+    overwrite: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<usize, CommandError> {
+    state
+        .writer
+        .submit(WritePriority::Bulk, move |db| {
+            db.infer_flight_airlines(&user_id, overwrite.unwrap_or(false))
+        })
+        .map_err(CommandError::from)
+}
+
+/// Move a flight to the recycle bin (see `restore_flight` / `purge_deleted`)
+#[tauri::command]
+pub fn delete_flight(flight_id: String, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let db = state.db.lock()?;
+    db.delete_flight(&flight_id).map_err(CommandError::from)
+}
+
+// ===== RECYCLE BIN =====
+
+#[tauri::command]
+pub fn list_deleted_flights(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<DeletedFlight>, CommandError> {
+    let db = state.db.lock()?;
+    db.list_deleted_flights(&user_id).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn restore_flight(flight_id: String, state: State<'_, AppState>) -> Result<bool, CommandError> {
+    let db = state.db.lock()?;
+    db.restore_flight(&flight_id).map_err(CommandError::from)
+}
+
+/// Permanently remove deleted flights; with `older_than_days` only those deleted before then
+#[tauri::command]
+pub fn purge_deleted(
+    user_id: String,
+    older_than_days: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<usize, CommandError> {
+    let db = state.db.lock()?;
+    db.purge_deleted_flights(&user_id, older_than_days)
+        .map_err(CommandError::from)
+}
+
+// ===== TIMEZONE HELPERS =====
+
+/// Resolve an airport timezone string, reporting why it could not be used
+fn resolve_zone(timezone: &Option<String>) -> (Option<AirportZone>, &'static str) {
+    match timezone.as_deref().map(str::trim) {
+        None | Some("") => (None, "missing"),
+        Some(tz) => match AirportZone::parse(tz) {
+            Some(zone) => (Some(zone), "resolved"),
+            None => (None, "unrecognized"),
+        },
+    }
+}
+
+fn format_local(dt: &chrono::NaiveDateTime) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn format_utc(dt: &chrono::DateTime<chrono::Utc>) -> String {
+    dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Compute local/UTC times for both ends of a flight.
+/// When the arrival time is missing it is estimated from departure + flight duration.
+pub(crate) fn build_flight_times(
+    flight: Flight,
+    departure_tz: Option<String>,
+    arrival_tz: Option<String>,
+) -> FlightWithTimes {
+    let (departure_zone, departure_status) = resolve_zone(&departure_tz);
+    let (arrival_zone, arrival_status) = resolve_zone(&arrival_tz);
+
+    // Departure end
+    let departure_local = timezone::parse_local_datetime(&flight.departure_datetime);
+    let departure_utc = match (&departure_zone, &departure_local) {
+        (Some(zone), Some(local)) => zone.local_to_utc(local),
+        _ => None,
+    };
+    let departure_offset = match (&departure_zone, &departure_local) {
+        (Some(zone), Some(local)) => zone.offset_at_local(local),
+        _ => None,
+    };
+
+    // Arrival end: prefer the recorded time, otherwise estimate from duration
+    let recorded_arrival = flight
+        .arrival_datetime
+        .as_deref()
+        .and_then(timezone::parse_local_datetime);
+    let duration_minutes = flight.flight_duration.or(flight.total_duration);
+
+    let (arrival_local, arrival_utc, arrival_estimated) = match recorded_arrival {
+        Some(local) => {
+            let utc = arrival_zone.as_ref().and_then(|zone| zone.local_to_utc(&local));
+            (Some(local), utc, false)
+        }
+        None => match (departure_utc, duration_minutes) {
+            (Some(dep_utc), Some(minutes)) => {
+                let utc = timezone::add_minutes(&dep_utc, minutes as i64);
+                let local = arrival_zone.as_ref().map(|zone| zone.utc_to_local(&utc));
+                (local, Some(utc), true)
+            }
+            _ => (None, None, false),
+        },
+    };
+    let arrival_offset = match (&arrival_zone, &arrival_utc) {
+        (Some(zone), Some(utc)) => Some(zone.offset_at_utc(utc)),
+        (Some(zone), None) => arrival_local.as_ref().and_then(|local| zone.offset_at_local(local)),
+        _ => None,
+    };
+
+    let elapsed_minutes = match (departure_utc, arrival_utc) {
+        (Some(dep), Some(arr)) => Some((arr - dep).num_minutes()),
+        _ => None,
+    };
+
+    let departure = FlightEndpointTimes {
+        airport_code: flight.departure_airport.clone(),
+        timezone: departure_tz,
+        timezone_status: departure_status.to_string(),
+        local_time: departure_local.as_ref().map(format_local),
+        utc_time: departure_utc.as_ref().map(format_utc),
+        utc_offset: departure_offset.as_ref().map(timezone::format_offset),
+        is_estimated: false,
+    };
+
+    let arrival = FlightEndpointTimes {
+        airport_code: flight.arrival_airport.clone(),
+        timezone: arrival_tz,
+        timezone_status: arrival_status.to_string(),
+        local_time: arrival_local.as_ref().map(format_local),
+        utc_time: arrival_utc.as_ref().map(format_utc),
+        utc_offset: arrival_offset.as_ref().map(timezone::format_offset),
+        is_estimated: arrival_estimated,
+    };
+
+    let departure_delay_minutes =
+        calculations::delay_minutes(flight.scheduled_departure.as_deref(), Some(&flight.departure_datetime));
+    let arrival_delay_minutes =
+        calculations::delay_minutes(flight.scheduled_arrival.as_deref(), flight.arrival_datetime.as_deref());
+
+    FlightWithTimes {
+        flight,
+        departure,
+        arrival,
+        elapsed_minutes,
+        departure_delay_minutes,
+        arrival_delay_minutes,
+        measurements: None,
+    }
+}
+
+fn measure_flight(units: UnitSystem, details: &mut FlightWithTimes) {
+    details.measurements = Some(
+        units
+            .measure()
+            .distance_km(details.flight.distance_km)
+            .carbon_kg(details.flight.carbon_emissions_kg),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::models::FlightListFilter;
+    use std::path::PathBuf;
+
+    fn seeded_db() -> Database {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, aircraft_registration, distance_km)
+                 VALUES ('f1', 'u1', 'KJFK', 'KBOS', '2025-01-05T09:00:00', 'N100AA', 300.0),
+                        ('f2', 'u1', 'KBOS', 'KPWM', '2025-01-31T18:30:00', 'N200BB', 150.0),
+                        ('f3', 'u1', 'KPWM', 'KJFK', '2025-02-10T07:00:00', 'N100AA', 450.0);",
+            )
+            .unwrap();
+        db
+    }
+
+    fn ids(db: &Database, filter: &FlightListFilter) -> Vec<String> {
+        db.list_flights_filtered("u1", filter, 100, 0)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_list_flights_filters() {
+        let db = seeded_db();
+
+        assert_eq!(ids(&db, &FlightListFilter::default()), vec!["f3", "f2", "f1"]);
+
+        let january = FlightListFilter {
+            start_date: Some("2025-01-01".to_string()),
+            end_date: Some("2025-01-31".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&db, &january), vec!["f2", "f1"]);
+        assert_eq!(db.count_flights("u1", &january).unwrap(), 2);
+
+        let jfk = FlightListFilter { airport: Some("kjfk".to_string()), ..Default::default() };
+        assert_eq!(ids(&db, &jfk), vec!["f3", "f1"]);
+
+        let by_distance = FlightListFilter {
+            aircraft: Some("n100aa".to_string()),
+            sort_by: Some("distance_km".to_string()),
+            sort_dir: Some("asc".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&db, &by_distance), vec!["f1", "f3"]);
+
+        let bad_sort = FlightListFilter { sort_by: Some("notes; DROP TABLE flights".to_string()), ..Default::default() };
+        assert!(db.list_flights_filtered("u1", &bad_sort, 100, 0).is_err());
+    }
+
+    #[test]
+    fn test_aircraft_filter_uses_index() {
+        let db = seeded_db();
+        let filter = FlightListFilter { aircraft: Some("n100aa".to_string()), ..Default::default() };
+        let (conditions, values) = crate::database::flight_filter_conditions("u1", &filter);
+        let plan: Vec<String> = db
+            .conn
+            .prepare(&format!("EXPLAIN QUERY PLAN SELECT id FROM flights WHERE {}", conditions))
+            .unwrap()
+            .query_map(rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())), |row| row.get(3))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(plan.iter().any(|step| step.contains("idx_flights_user_registration")), "{:?}", plan);
+    }
+
+    #[test]
+    fn test_list_flights_on_day() {
+        let db = seeded_db();
+        db.conn
+            .execute_batch(
+                "INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f4', 'u1', 'KJFK', 'KBOS', '2021-01-05T12:00:00'),
+                        ('f5', 'u1', 'KJFK', 'KBOS', '1900-01-05T00:00:00'),
+                        ('f6', 'u1', 'KJFK', 'KBOS', '2026-01-05T08:00:00');",
+            )
+            .unwrap();
+
+        let ids: Vec<String> = db.list_flights_on_day("u1", "01-05", 2026).unwrap().into_iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec!["f1", "f4"]);
+        assert!(db.list_flights_on_day("u1", "03-01", 2026).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_punctuality_stats() {
+        let db = seeded_db();
+        db.conn
+            .execute_batch(
+                "UPDATE flights SET arrival_datetime = '2025-01-05T10:40:00', scheduled_arrival = '2025-01-05T10:00:00' WHERE id = 'f1';
+                 UPDATE flights SET scheduled_departure = '2025-01-31T18:25' WHERE id = 'f2';",
+            )
+            .unwrap();
+
+        let stats = db.get_punctuality_stats("u1", 5).unwrap();
+        assert_eq!(stats.flights_with_schedule, 2);
+        assert_eq!(stats.average_delay_minutes, Some(22.5));
+        assert_eq!(stats.on_time_percentage, Some(50.0));
+        assert_eq!(stats.worst_routes[0].key, "KJFK-KBOS");
+        assert_eq!(stats.worst_airports[0].key, "KJFK");
+        assert_eq!(stats.worst_airports[1].key, "KBOS");
+        assert_eq!(stats.worst_airports[1].flights, 2);
+    }
+
+    #[test]
+    fn test_soft_delete_and_restore() {
+        let db = seeded_db();
+
+        db.delete_flight("f1").unwrap();
+        assert_eq!(ids(&db, &FlightListFilter::default()), vec!["f3", "f2"]);
+        assert_eq!(db.get_statistics("u1").unwrap().total_flights, 2);
+        let deleted: Vec<String> = db.list_deleted_flights("u1").unwrap().into_iter().map(|f| f.id).collect();
+        assert_eq!(deleted, vec!["f1"]);
+
+        assert!(db.restore_flight("f1").unwrap());
+        assert!(!db.restore_flight("f1").unwrap());
+        assert_eq!(ids(&db, &FlightListFilter::default()), vec!["f3", "f2", "f1"]);
+        assert!(db.list_deleted_flights("u1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_purge_deleted_flights() {
+        let db = seeded_db();
+        db.conn
+            .execute_batch(
+                "INSERT INTO pilot_logbook (id, flight_id) VALUES ('l1', 'f1'), ('l2', 'f2');
+                 INSERT INTO journeys (id, user_id, name, start_date) VALUES ('j1', 'u1', 'Trip', '2025-01-05');
+                 INSERT INTO journey_flights (journey_id, flight_id) VALUES ('j1', 'f1');",
+            )
+            .unwrap();
+        db.delete_flight("f1").unwrap();
+        let count = |sql: &str| -> i64 { db.conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+
+        // Deleted just now, so not yet older than a day
+        assert_eq!(db.purge_deleted_flights("u1", Some(1)).unwrap(), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM pilot_logbook"), 2);
+
+        assert_eq!(db.purge_deleted_flights("u1", None).unwrap(), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM flights"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM pilot_logbook"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM journey_flights"), 0);
+        assert!(db.list_deleted_flights("u1").unwrap().is_empty());
+    }
+}
@@ -520,6 +520,248 @@ pub fn delete_fuel_entry(
     Ok(())
 }
 
+// ===== FUEL EXPORT (expense reporting) =====
+
+/// Purchase dates to export, YYYY-MM-DD, both inclusive; either end may be open
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FuelExportRange {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuelCurrencyTotal {
+    pub currency: String,
+    pub entry_count: usize,
+    pub gallons: f64,
+    pub total_cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuelExportResult {
+    pub entries_exported: usize,
+    pub totals: Vec<FuelCurrencyTotal>,
+}
+
+/// A fuel entry with its linked flight and aircraft resolved for export
+#[derive(Debug, Clone)]
+struct FuelExportRow {
+    entry: FuelEntry,
+    /// "AA100 KPAO-KSQL" for the linked flight
+    flight: Option<String>,
+    /// The entry's aircraft, else the linked flight's registration
+    aircraft: Option<String>,
+}
+
+fn fuel_export_rows(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    range: &FuelExportRange,
+    aircraft: Option<&str>,
+) -> Result<Vec<FuelExportRow>, String> {
+    for date in [&range.start_date, &range.end_date].into_iter().flatten() {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD", date))?;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT e.id, e.user_id, e.flight_id, e.aircraft_id, e.airport_code, e.location_name,
+                    e.fuel_type, e.gallons, e.price_per_gallon, e.total_cost, COALESCE(e.currency, 'USD'),
+                    e.purchase_date, e.fbo_name, e.receipt_number, e.notes, e.created_at,
+                    f.flight_number, f.departure_airport, f.arrival_airport,
+                    COALESCE(e.aircraft_id, f.aircraft_registration)
+             FROM fuel_entries e
+             LEFT JOIN flights f ON f.id = e.flight_id
+             WHERE e.user_id = ?1
+               AND (?2 IS NULL OR substr(e.purchase_date, 1, 10) >= ?2)
+               AND (?3 IS NULL OR substr(e.purchase_date, 1, 10) <= ?3)
+               AND (?4 IS NULL OR UPPER(COALESCE(e.aircraft_id, f.aircraft_registration)) = UPPER(?4))
+             ORDER BY e.purchase_date ASC, e.created_at ASC",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![user_id, range.start_date, range.end_date, aircraft], |row| {
+            let flight_number: Option<String> = row.get(16)?;
+            let route: Option<(String, String)> = match (row.get(17)?, row.get(18)?) {
+                (Some(from), Some(to)) => Some((from, to)),
+                _ => None,
+            };
+            let flight = match (flight_number, route) {
+                (Some(number), Some((from, to))) => Some(format!("{} {}-{}", number, from, to)),
+                (None, Some((from, to))) => Some(format!("{}-{}", from, to)),
+                (Some(number), None) => Some(number),
+                (None, None) => None,
+            };
+            Ok(FuelExportRow {
+                entry: FuelEntry {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    flight_id: row.get(2)?,
+                    aircraft_id: row.get(3)?,
+                    airport_code: row.get(4)?,
+                    location_name: row.get(5)?,
+                    fuel_type: row.get(6)?,
+                    gallons: row.get(7)?,
+                    price_per_gallon: row.get(8)?,
+                    total_cost: row.get(9)?,
+                    currency: row.get(10)?,
+                    purchase_date: row.get(11)?,
+                    fbo_name: row.get(12)?,
+                    receipt_number: row.get(13)?,
+                    notes: row.get(14)?,
+                    created_at: row.get(15)?,
+                },
+                flight,
+                aircraft: row.get(19)?,
+            })
+        })
+        .map_err(|e| format!("Query failed: {}", e))?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("Query failed: {}", e))
+}
+
+/// Gallons and cost per currency, currencies in alphabetical order
+fn fuel_currency_totals(rows: &[FuelExportRow]) -> Vec<FuelCurrencyTotal> {
+    let mut totals: std::collections::BTreeMap<&str, FuelCurrencyTotal> = std::collections::BTreeMap::new();
+    for row in rows {
+        let total = totals.entry(&row.entry.currency).or_insert_with(|| FuelCurrencyTotal {
+            currency: row.entry.currency.clone(),
+            entry_count: 0,
+            gallons: 0.0,
+            total_cost: 0.0,
+        });
+        total.entry_count += 1;
+        total.gallons += row.entry.gallons;
+        total.total_cost += row.entry.total_cost;
+    }
+    totals.into_values().collect()
+}
+
+/// One row per purchase, then a blank line and a "Total (CUR)" row per currency
+fn write_fuel_csv<W: std::io::Write>(
+    writer: W,
+    rows: &[FuelExportRow],
+    totals: &[FuelCurrencyTotal],
+) -> Result<(), String> {
+    let mut csv = ::csv::WriterBuilder::new().flexible(true).from_writer(writer);
+    csv.write_record([
+        "Date", "Airport", "Location", "FBO", "Fuel Type", "Gallons", "Price per Gallon",
+        "Total", "Currency", "Receipt Number", "Flight", "Aircraft", "Notes",
+    ])
+    .map_err(|e| e.to_string())?;
+
+    for row in rows {
+        let e = &row.entry;
+        csv.write_record([
+            e.purchase_date.get(..10).unwrap_or(&e.purchase_date),
+            e.airport_code.as_deref().unwrap_or(""),
+            e.location_name.as_deref().unwrap_or(""),
+            e.fbo_name.as_deref().unwrap_or(""),
+            &e.fuel_type,
+            &format!("{:.2}", e.gallons),
+            &format!("{:.3}", e.price_per_gallon),
+            &format!("{:.2}", e.total_cost),
+            &e.currency,
+            e.receipt_number.as_deref().unwrap_or(""),
+            row.flight.as_deref().unwrap_or(""),
+            row.aircraft.as_deref().unwrap_or(""),
+            e.notes.as_deref().unwrap_or(""),
+        ])
+        .map_err(|e| e.to_string())?;
+    }
+
+    csv.write_record([""]).map_err(|e| e.to_string())?;
+    for total in totals {
+        csv.write_record([
+            &format!("Total ({})", total.currency),
+            "",
+            "",
+            "",
+            "",
+            &format!("{:.2}", total.gallons),
+            "",
+            &format!("{:.2}", total.total_cost),
+            &total.currency,
+        ])
+        .map_err(|e| e.to_string())?;
+    }
+    csv.flush().map_err(|e| e.to_string())
+}
+
+/// QIF bank transactions, one withdrawal per purchase under the Aircraft:Fuel category.
+/// QIF has no currency field, so the currency goes in the memo.
+fn fuel_qif(rows: &[FuelExportRow]) -> String {
+    let mut qif = String::from("!Type:Bank\n");
+    for row in rows {
+        let e = &row.entry;
+        let date = chrono::NaiveDate::parse_from_str(e.purchase_date.get(..10).unwrap_or(""), "%Y-%m-%d")
+            .map(|d| d.format("%m/%d/%Y").to_string())
+            .unwrap_or_else(|_| e.purchase_date.clone());
+        let payee = e
+            .fbo_name
+            .as_deref()
+            .or(e.location_name.as_deref())
+            .or(e.airport_code.as_deref())
+            .unwrap_or("Fuel");
+        let mut memo = format!(
+            "{:.2} gal {} @ {:.3} {}",
+            e.gallons, e.fuel_type, e.price_per_gallon, e.currency
+        );
+        for (label, value) in [
+            ("at", e.airport_code.as_deref()),
+            ("flight", row.flight.as_deref()),
+            ("aircraft", row.aircraft.as_deref()),
+        ] {
+            if let Some(value) = value {
+                memo.push_str(&format!(", {} {}", label, value));
+            }
+        }
+        qif.push_str(&format!("D{}\nT-{:.2}\nP{}\nM{}\nLAircraft:Fuel\n", date, e.total_cost, payee, memo));
+        if let Some(receipt) = &e.receipt_number {
+            qif.push_str(&format!("N{}\n", receipt));
+        }
+        qif.push_str("^\n");
+    }
+    qif
+}
+
+/// Export fuel purchases for expense reporting as "csv" (default) or "qif".
+/// `aircraft` matches the entry's aircraft or the linked flight's registration.
+#[tauri::command]
+pub fn export_fuel_entries(
+    user_id: String,
+    range: Option<FuelExportRange>,
+    aircraft: Option<String>,
+    format: Option<String>,
+    export_path: String,
+    state: State<'_, AppState>,
+) -> Result<FuelExportResult, String> {
+    let format = format.unwrap_or_else(|| "csv".to_string()).to_lowercase();
+    if !["csv", "qif"].contains(&format.as_str()) {
+        return Err(format!("Invalid format '{}': expected csv or qif", format));
+    }
+    let aircraft = aircraft.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+
+    let db = state.db.lock().map_err(|e| format!("DB lock error: {}", e))?;
+    let rows = fuel_export_rows(db.get_connection(), &user_id, &range.unwrap_or_default(), aircraft.as_deref())?;
+    let totals = fuel_currency_totals(&rows);
+
+    let file = std::fs::File::create(&export_path).map_err(|e| format!("Failed to create export file: {}", e))?;
+    if format == "qif" {
+        use std::io::Write;
+        let mut file = file;
+        file.write_all(fuel_qif(&rows).as_bytes()).map_err(|e| e.to_string())?;
+    } else {
+        write_fuel_csv(file, &rows, &totals)?;
+    }
+
+    Ok(FuelExportResult {
+        entries_exported: rows.len(),
+        totals,
+    })
+}
+
 // ===== CUSTOM FUEL TYPES =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -690,3 +932,53 @@ pub fn delete_fuel_type(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fuel_export_filters_and_totals() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, flight_number, departure_airport, arrival_airport, departure_datetime, aircraft_registration) VALUES
+                     ('f1', 'u1', 'N123', 'KPAO', 'KSQL', '2025-03-02T09:00:00', 'N123AB');
+                 INSERT INTO fuel_entries (id, user_id, flight_id, aircraft_id, airport_code, fuel_type, gallons, price_per_gallon, total_cost, currency, purchase_date, fbo_name, receipt_number) VALUES
+                     ('e1', 'u1', 'f1', NULL, 'KPAO', '100ll', 20.0, 6.50, 130.0, 'USD', '2025-03-02', 'Rabbit Aviation', 'R-1'),
+                     ('e2', 'u1', NULL, 'N123AB', 'EGLL', 'jet_a', 10.0, 2.00, 20.0, 'GBP', '2025-03-05', NULL, NULL),
+                     ('e3', 'u1', NULL, 'N999ZZ', 'KSQL', '100ll', 15.0, 7.00, 105.0, 'USD', '2025-03-06', NULL, NULL),
+                     ('e4', 'u1', NULL, NULL, 'KSFO', '100ll', 5.0, 8.00, 40.0, 'USD', '2025-04-01', NULL, NULL);",
+            )
+            .unwrap();
+
+        let range = FuelExportRange {
+            start_date: Some("2025-03-01".to_string()),
+            end_date: Some("2025-03-31".to_string()),
+        };
+        let rows = fuel_export_rows(&db.conn, "u1", &range, Some("n123ab")).unwrap();
+        let ids: Vec<&str> = rows.iter().map(|r| r.entry.id.as_str()).collect();
+        assert_eq!(ids, ["e1", "e2"]);
+        assert_eq!(rows[0].flight.as_deref(), Some("N123 KPAO-KSQL"));
+
+        let totals = fuel_currency_totals(&rows);
+        assert_eq!(totals.iter().map(|t| t.currency.as_str()).collect::<Vec<_>>(), ["GBP", "USD"]);
+        assert_eq!((totals[1].entry_count, totals[1].total_cost), (1, 130.0));
+
+        let mut csv = Vec::new();
+        write_fuel_csv(&mut csv, &rows, &totals).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.contains("2025-03-02,KPAO,,Rabbit Aviation,100ll,20.00,6.500,130.00,USD,R-1,N123 KPAO-KSQL,N123AB,"));
+        assert!(csv.ends_with("Total (USD),,,,,20.00,,130.00,USD\n"));
+
+        let qif = fuel_qif(&rows);
+        assert!(qif.starts_with("!Type:Bank\nD03/02/2025\nT-130.00\nPRabbit Aviation\n"));
+        assert_eq!(qif.matches("^\n").count(), 2);
+
+        let bad = FuelExportRange { start_date: Some("03/01/2025".to_string()), end_date: None };
+        assert!(fuel_export_rows(&db.conn, "u1", &bad, None).is_err());
+    }
+}
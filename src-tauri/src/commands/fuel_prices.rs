@@ -99,6 +99,33 @@ pub fn delete_fuel_price(
         .map_err(|e| e.to_string())
 }
 
+/// Liters of fuel and their cost at a price, for a fuel mass in kg
+pub(crate) fn fuel_cost(fuel_consumed_kg: f64, price: &crate::models::FuelPrice) -> (f64, f64) {
+    // Convert kg to liters based on fuel density
+    let density = if price.fuel_type.contains("Avgas") {
+        0.72 // Avgas density: ~0.72 kg/L
+    } else {
+        0.8 // Jet A density: ~0.8 kg/L
+    };
+    let estimated_fuel_liters = fuel_consumed_kg / density;
+
+    // Calculate total cost based on price unit
+    let total_cost = match price.unit.as_str() {
+        "liter" => estimated_fuel_liters * price.price_per_unit,
+        "gallon" => {
+            let gallons = estimated_fuel_liters / 3.78541; // Convert to US gallons
+            gallons * price.price_per_unit
+        }
+        "imperial_gallon" => {
+            let imp_gallons = estimated_fuel_liters / 4.54609; // Convert to imperial gallons
+            imp_gallons * price.price_per_unit
+        }
+        _ => estimated_fuel_liters * price.price_per_unit, // Default: treat as liters
+    };
+
+    (estimated_fuel_liters, total_cost)
+}
+
 #[tauri::command]
 pub fn calculate_fuel_cost(
     user_id: String,
@@ -122,27 +149,7 @@ pub fn calculate_fuel_cost(
         .map_err(|e| e.to_string())?;
 
     if let Some(price) = fuel_price {
-        // Convert kg to liters based on fuel density
-        let density = if price.fuel_type.contains("Avgas") {
-            0.72 // Avgas density: ~0.72 kg/L
-        } else {
-            0.8 // Jet A density: ~0.8 kg/L
-        };
-        let estimated_fuel_liters = fuel_consumed_kg / density;
-
-        // Calculate total cost based on price unit
-        let total_cost = match price.unit.as_str() {
-            "liter" => estimated_fuel_liters * price.price_per_unit,
-            "gallon" => {
-                let gallons = estimated_fuel_liters / 3.78541; // Convert to US gallons
-                gallons * price.price_per_unit
-            }
-            "imperial_gallon" => {
-                let imp_gallons = estimated_fuel_liters / 4.54609; // Convert to imperial gallons
-                imp_gallons * price.price_per_unit
-            }
-            _ => estimated_fuel_liters * price.price_per_unit, // Default: treat as liters
-        };
+        let (estimated_fuel_liters, total_cost) = fuel_cost(fuel_consumed_kg, &price);
 
         let calculation_method = format!(
             "Estimated {} kg fuel @ {} {}/{} = {:.2} {}",
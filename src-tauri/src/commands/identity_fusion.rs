@@ -1,13 +1,15 @@
 // Identity Fusion Commands
 // Manages canonical passenger identities and their aliases
 
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
 use uuid::Uuid;
 
 use super::AppState;
+use crate::extract::identity_fusion::{never_merge_key, FusionConfig, IdentityFusion, MatchType};
+use crate::identity_conflicts::{self, IdentityConflictReport};
 
 // ===== DATA TYPES =====
 
@@ -33,11 +35,83 @@ pub struct PassengerAlias {
     pub confidence: f64,
 }
 
+/// Auto-merge controls for identity bootstrap; omitted fields use the fusion defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BootstrapOptions {
+    /// Merge a name into an existing identity at or above this similarity (0.0-1.0)
+    pub auto_merge_threshold: Option<f64>,
+    /// Queue near matches at or above this similarity for review (at most auto_merge_threshold)
+    pub review_threshold: Option<f64>,
+    /// Auto-merge names that are only initials ("JE" into "JEFFREY EPSTEIN")
+    pub merge_initials: Option<bool>,
+    /// Skip never-merge pairs and passengers marked do-not-deduplicate (default true)
+    pub respect_never_merge: Option<bool>,
+}
+
+impl BootstrapOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        let defaults = FusionConfig::default();
+        let auto = self.auto_merge_threshold.unwrap_or(defaults.auto_merge_threshold);
+        let review = self.review_threshold.unwrap_or(defaults.fuzzy_threshold.min(auto));
+        for (name, value) in [("auto_merge_threshold", auto), ("review_threshold", review)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1", name));
+            }
+        }
+        if review > auto {
+            return Err("review_threshold cannot be above auto_merge_threshold".to_string());
+        }
+        Ok(())
+    }
+
+    /// A fusion engine seeded with the existing identities
+    fn fusion(&self, conn: &Connection) -> rusqlite::Result<IdentityFusion> {
+        let respect = self.respect_never_merge.unwrap_or(true);
+        let mut config = FusionConfig::default();
+        config.auto_merge_threshold = self.auto_merge_threshold.unwrap_or(config.auto_merge_threshold);
+        config.fuzzy_threshold = self.review_threshold.unwrap_or(config.fuzzy_threshold.min(config.auto_merge_threshold));
+        config.merge_initials = self.merge_initials.unwrap_or(config.merge_initials);
+        if respect {
+            let mut stmt = conn.prepare("SELECT name_a, name_b FROM passenger_never_merge")?;
+            config.never_merge = stmt
+                .query_map([], |row| Ok(never_merge_key(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?)))?
+                .collect::<Result<_, _>>()?;
+        }
+
+        let mut fusion = IdentityFusion::new(config);
+        let mut stmt = conn.prepare(
+            "SELECT id, canonical_name, COALESCE(total_flights, 0) FROM passengers
+             WHERE ?1 = 0 OR id NOT IN (SELECT passenger_id FROM passenger_no_dedup)",
+        )?;
+        let rows = stmt.query_map(params![respect], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+        })?;
+        for row in rows {
+            let (id, name, flights) = row?;
+            fusion.add_entity(&id, &name, flights.max(0) as usize);
+        }
+        Ok(fusion)
+    }
+}
+
+/// A name bootstrap added to an existing identity instead of creating a new one
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoMerge {
+    pub source_name: String,
+    pub target_passenger_id: String,
+    pub target_canonical_name: String,
+    pub confidence: f64,
+    pub match_type: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BootstrapResult {
     pub passengers_created: i32,
     pub aliases_created: i32,
     pub flight_links_created: i32,
+    pub merges: Vec<AutoMerge>,
+    /// Near matches added to the merge review queue
+    pub queued_for_review: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +121,8 @@ pub struct BootstrapBatchResult {
     pub passengers_created_this_batch: i32,
     pub aliases_created_this_batch: i32,
     pub flight_links_created_this_batch: i32,
+    pub merges_this_batch: Vec<AutoMerge>,
+    pub queued_this_batch: i32,
     pub total_passengers_created: i32,
     pub total_aliases_created: i32,
     pub total_flight_links_created: i32,
@@ -64,27 +140,63 @@ pub struct MergeResult {
 
 // ===== BOOTSTRAP COMMAND =====
 // Scans flight notes for passenger names and creates initial identity records
-// Optimized with batch processing and transactions for better performance
+// Runs in a single transaction for better performance
 
 #[tauri::command]
 pub fn bootstrap_identities(
     user_id: String,
+    options: Option<BootstrapOptions>,
     state: State<'_, AppState>,
 ) -> Result<BootstrapResult, String> {
+    let options = options.unwrap_or_default();
+    options.validate()?;
     let db = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let result = bootstrap_user_identities(&tx, &user_id, &options).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
 
-    // Step 1: Extract all unique passenger names from flights.notes
-    let mut stmt = db.conn.prepare(
-        "SELECT id, notes FROM flights WHERE user_id = ?1 AND notes IS NOT NULL"
-    ).map_err(|e| e.to_string())?;
+/// Create identities for the passenger names in a user's flight notes and link the
+/// flights to them; names already known as aliases reuse their identity, and names close
+/// enough to an identity are merged into it or queued for review as `options` allow.
+/// Leaves transaction control to the caller.
+pub(crate) fn bootstrap_user_identities(
+    conn: &Connection,
+    user_id: &str,
+    options: &BootstrapOptions,
+) -> rusqlite::Result<BootstrapResult> {
+    let (name_counts, flight_passengers) = passenger_names_in_notes(conn, user_id)?;
+    let mut name_to_passenger_id = alias_map(conn)?;
+    let new_names = new_names_in_order(&name_counts, &name_to_passenger_id);
+
+    let mut result = BootstrapResult::default();
+    let mut fusion = options.fusion(conn)?;
+    create_identities(conn, &mut fusion, &new_names, &mut name_to_passenger_id, &mut result)?;
+
+    for (flight_id, passenger_name) in &flight_passengers {
+        if let Some(pid) = name_to_passenger_id.get(passenger_name) {
+            result.flight_links_created += link_flight(conn, flight_id, pid)?;
+        }
+    }
+    refresh_merged_flight_counts(conn)?;
+    Ok(result)
+}
+
+/// (occurrences per name, (flight id, name) pairs)
+type NotesPassengers = (HashMap<String, i32>, Vec<(String, String)>);
+
+/// The passenger names in a user's flight notes
+fn passenger_names_in_notes(conn: &Connection, user_id: &str) -> rusqlite::Result<NotesPassengers> {
+    let mut stmt = conn.prepare(
+        "SELECT id, notes FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND notes IS NOT NULL"
+    )?;
 
     let flights: Vec<(String, String)> = stmt
         .query_map(params![user_id], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
 
     // Count occurrences of each name and collect flight-passenger pairs
     let mut name_counts: HashMap<String, i32> = HashMap::new();
@@ -99,7 +211,7 @@ pub fn bootstrap_identities(
         };
 
         // Split by common delimiters
-        for name in passenger_text.split(|c| c == ',' || c == ';' || c == '|') {
+        for name in passenger_text.split([',', ';', '|']) {
             let trimmed = name.trim().to_uppercase();
             if !trimmed.is_empty() && trimmed.len() > 1 {
                 *name_counts.entry(trimmed.clone()).or_insert(0) += 1;
@@ -107,264 +219,214 @@ pub fn bootstrap_identities(
             }
         }
     }
+    Ok((name_counts, flight_passengers))
+}
 
-    // Step 2: Get existing aliases in one batch query
-    let existing_aliases: std::collections::HashSet<String> = {
-        let mut stmt = db.conn.prepare(
-            "SELECT raw_name FROM passenger_aliases"
-        ).map_err(|e| e.to_string())?;
+/// Existing aliases and the passengers they belong to
+fn alias_map(conn: &Connection) -> rusqlite::Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare(
+        "SELECT raw_name, passenger_id FROM passenger_aliases"
+    )?;
 
-        let rows = stmt.query_map([], |row| row.get::<_, String>(0))
-            .map_err(|e| e.to_string())?;
-        rows.filter_map(|r| r.ok()).collect()
-    };
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
 
-    // Filter to only new names
-    let new_names: Vec<(&String, &i32)> = name_counts
+/// Names without an alias yet; full names first so initials and first names can match them
+fn new_names_in_order(name_counts: &HashMap<String, i32>, known: &HashMap<String, String>) -> Vec<(String, i32)> {
+    let mut names: Vec<(String, i32)> = name_counts
         .iter()
-        .filter(|(name, _)| !existing_aliases.contains(*name))
+        .filter(|(name, _)| !known.contains_key(*name))
+        .map(|(name, count)| (name.clone(), *count))
         .collect();
+    names.sort_by(|a, b| {
+        let words = |name: &str| name.split_whitespace().count();
+        words(&b.0).cmp(&words(&a.0)).then(b.1.cmp(&a.1)).then(a.0.cmp(&b.0))
+    });
+    names
+}
 
-    let mut passengers_created = 0;
-    let mut aliases_created = 0;
-    let mut flight_links_created = 0;
-
-    // Step 3: Batch insert passengers and aliases using a transaction
-    // Process in chunks of 500 for better memory management
-    const BATCH_SIZE: usize = 500;
-
-    // Create a mapping of name -> passenger_id for flight links
-    let mut name_to_passenger_id: HashMap<String, String> = HashMap::new();
-
-    // First, populate with existing aliases
-    {
-        let mut stmt = db.conn.prepare(
-            "SELECT raw_name, passenger_id FROM passenger_aliases"
-        ).map_err(|e| e.to_string())?;
-
-        for row in stmt.query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        }).map_err(|e| e.to_string())? {
-            if let Ok((name, pid)) = row {
-                name_to_passenger_id.insert(name, pid);
-            }
-        }
+fn match_type_label(match_type: &MatchType) -> &'static str {
+    match match_type {
+        MatchType::ExactMatch => "exact",
+        MatchType::Abbreviation => "abbreviation",
+        MatchType::Initials => "initials",
+        MatchType::Substring => "substring",
+        MatchType::FuzzyMatch => "fuzzy",
+        MatchType::AIInferred => "ai",
     }
+}
 
-    // Insert new passengers and aliases in batches
-    for chunk in new_names.chunks(BATCH_SIZE) {
-        db.conn.execute("BEGIN TRANSACTION", []).map_err(|e| e.to_string())?;
-
-        for (name, count) in chunk {
-            let passenger_id = Uuid::new_v4().to_string();
-            let alias_id = Uuid::new_v4().to_string();
-
-            db.conn.execute(
-                "INSERT INTO passengers (id, canonical_name, total_flights, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, datetime('now'), datetime('now'))",
-                params![passenger_id, name, count]
-            ).map_err(|e| e.to_string())?;
-            passengers_created += 1;
-
-            db.conn.execute(
+/// Give each name an identity: merged into the best match when it is confident enough,
+/// otherwise a new passenger, with near matches queued for review
+fn create_identities(
+    conn: &Connection,
+    fusion: &mut IdentityFusion,
+    names: &[(String, i32)],
+    name_to_passenger_id: &mut HashMap<String, String>,
+    result: &mut BootstrapResult,
+) -> rusqlite::Result<()> {
+    let review_threshold = fusion.config().fuzzy_threshold;
+    for (name, count) in names {
+        let candidate = fusion.best_match(name);
+        let alias_id = Uuid::new_v4().to_string();
+
+        if let Some(candidate) = candidate.as_ref().filter(|c| c.auto_merge) {
+            let match_type = match_type_label(&candidate.match_type);
+            conn.execute(
                 "INSERT INTO passenger_aliases (id, passenger_id, raw_name, usage_count, match_type, confidence, created_at)
-                 VALUES (?1, ?2, ?3, ?4, 'exact', 1.0, datetime('now'))",
-                params![alias_id, passenger_id, name, count]
-            ).map_err(|e| e.to_string())?;
-            aliases_created += 1;
-
-            name_to_passenger_id.insert((*name).clone(), passenger_id);
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
+                params![alias_id, candidate.target_entity_id, name, count, match_type, candidate.similarity_score]
+            )?;
+            result.aliases_created += 1;
+            result.merges.push(AutoMerge {
+                source_name: name.clone(),
+                target_passenger_id: candidate.target_entity_id.clone(),
+                target_canonical_name: candidate.target_canonical_name.clone(),
+                confidence: candidate.similarity_score,
+                match_type: match_type.to_string(),
+            });
+            name_to_passenger_id.insert(name.clone(), candidate.target_entity_id.clone());
+            continue;
         }
 
-        db.conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
-    }
-
-    // Step 4: Batch insert flight_passengers links
-    for chunk in flight_passengers.chunks(BATCH_SIZE) {
-        db.conn.execute("BEGIN TRANSACTION", []).map_err(|e| e.to_string())?;
-
-        for (flight_id, passenger_name) in chunk {
-            if let Some(pid) = name_to_passenger_id.get(passenger_name) {
-                let result = db.conn.execute(
-                    "INSERT OR IGNORE INTO flight_passengers (flight_id, passenger_id, created_at)
-                     VALUES (?1, ?2, datetime('now'))",
-                    params![flight_id, pid]
-                ).map_err(|e| e.to_string())?;
-
-                if result > 0 {
-                    flight_links_created += 1;
-                }
-            }
+        let passenger_id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO passengers (id, canonical_name, total_flights, created_at, updated_at)
+             VALUES (?1, ?2, ?3, datetime('now'), datetime('now'))",
+            params![passenger_id, name, count]
+        )?;
+        result.passengers_created += 1;
+
+        conn.execute(
+            "INSERT INTO passenger_aliases (id, passenger_id, raw_name, usage_count, match_type, confidence, created_at)
+             VALUES (?1, ?2, ?3, ?4, 'exact', 1.0, datetime('now'))",
+            params![alias_id, passenger_id, name, count]
+        )?;
+        result.aliases_created += 1;
+
+        if let Some(candidate) = candidate.filter(|c| c.similarity_score >= review_threshold) {
+            result.queued_for_review += conn.execute(
+                "INSERT OR IGNORE INTO passenger_merge_queue (id, source_name, target_passenger_id, similarity, match_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    name,
+                    candidate.target_entity_id,
+                    candidate.similarity_score,
+                    match_type_label(&candidate.match_type)
+                ],
+            )? as i32;
         }
 
-        db.conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
+        fusion.add_entity(&passenger_id, name, *count as usize);
+        name_to_passenger_id.insert(name.clone(), passenger_id);
     }
+    Ok(())
+}
 
-    Ok(BootstrapResult {
-        passengers_created,
-        aliases_created,
-        flight_links_created,
-    })
+/// 1 when the link is new
+fn link_flight(conn: &Connection, flight_id: &str, passenger_id: &str) -> rusqlite::Result<i32> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO flight_passengers (flight_id, passenger_id, created_at)
+         VALUES (?1, ?2, datetime('now'))",
+        params![flight_id, passenger_id]
+    )?;
+    Ok(inserted as i32)
+}
+
+/// Recount flights for identities that gained aliases by merging
+fn refresh_merged_flight_counts(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE passengers SET
+            total_flights = (SELECT COUNT(DISTINCT flight_id) FROM flight_passengers WHERE passenger_id = passengers.id),
+            updated_at = datetime('now')
+         WHERE id IN (SELECT passenger_id FROM passenger_aliases WHERE match_type NOT IN ('exact', 'manual'))",
+        [],
+    )?;
+    Ok(())
 }
 
 // ===== BATCHED BOOTSTRAP COMMAND =====
 // Processes identity bootstrap in batches to avoid UI freeze
-// Call with batch_number starting at 0, increment until is_complete is true
+// Call with batch_number starting at 0, increment until is_complete is true. Each batch
+// picks up where the database left off: first the names without an identity, then the
+// flights not yet linked to theirs.
 
 #[tauri::command]
 pub fn bootstrap_identities_batch(
     user_id: String,
     batch_number: usize,
     batch_size: usize,
+    options: Option<BootstrapOptions>,
     state: State<'_, AppState>,
 ) -> Result<BootstrapBatchResult, String> {
+    let options = options.unwrap_or_default();
+    options.validate()?;
     let db = state.db.lock().map_err(|e| e.to_string())?;
     let batch_size = if batch_size == 0 { 200 } else { batch_size };
 
-    // Phase 1: Extract all data on first batch (batch 0)
-    if batch_number == 0 {
-        // Clear any previous bootstrap state by starting fresh
-        // This is idempotent - we'll skip existing aliases
-    }
-
-    // Get all flights with notes
-    let mut stmt = db.conn.prepare(
-        "SELECT id, notes FROM flights WHERE user_id = ?1 AND notes IS NOT NULL"
-    ).map_err(|e| e.to_string())?;
-
-    let flights: Vec<(String, String)> = stmt
-        .query_map(params![user_id], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
-
-    // Parse all passenger names
-    let mut name_counts: HashMap<String, i32> = HashMap::new();
-    let mut flight_passengers: Vec<(String, String)> = Vec::new();
+    let (name_counts, flight_passengers) =
+        passenger_names_in_notes(&db.conn, &user_id).map_err(|e| e.to_string())?;
+    let mut name_to_passenger_id = alias_map(&db.conn).map_err(|e| e.to_string())?;
+    let new_names = new_names_in_order(&name_counts, &name_to_passenger_id);
 
-    for (flight_id, notes) in &flights {
-        let passenger_text = if let Some(part) = notes.strip_prefix("Passengers: ") {
-            part
-        } else {
-            notes.as_str()
-        };
-
-        for name in passenger_text.split(|c| c == ',' || c == ';' || c == '|') {
-            let trimmed = name.trim().to_uppercase();
-            if !trimmed.is_empty() && trimmed.len() > 1 {
-                *name_counts.entry(trimmed.clone()).or_insert(0) += 1;
-                flight_passengers.push((flight_id.clone(), trimmed));
-            }
-        }
-    }
-
-    // Get existing aliases
-    let existing_aliases: std::collections::HashSet<String> = {
-        let mut stmt = db.conn.prepare("SELECT raw_name FROM passenger_aliases")
+    let linked: std::collections::HashSet<(String, String)> = {
+        let mut stmt = db.conn.prepare("SELECT flight_id, passenger_id FROM flight_passengers")
             .map_err(|e| e.to_string())?;
-        let rows = stmt.query_map([], |row| row.get::<_, String>(0))
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
             .map_err(|e| e.to_string())?;
         rows.filter_map(|r| r.ok()).collect()
     };
-
-    // Filter to new names only
-    let new_names: Vec<(String, i32)> = name_counts
-        .iter()
-        .filter(|(name, _)| !existing_aliases.contains(*name))
-        .map(|(n, c)| (n.clone(), *c))
+    // Names without an identity yet count as unlinked
+    let mut seen = std::collections::HashSet::new();
+    let unlinked: Vec<(String, Option<String>)> = flight_passengers
+        .into_iter()
+        .map(|(flight_id, name)| (flight_id, name_to_passenger_id.get(&name).cloned().ok_or(name)))
+        .filter(|(flight_id, pid)| match pid {
+            Ok(pid) => !linked.contains(&(flight_id.clone(), pid.clone())) && seen.insert((flight_id.clone(), pid.clone())),
+            Err(_) => true,
+        })
+        .map(|(flight_id, pid)| (flight_id, pid.ok()))
         .collect();
 
-    let total_new_passengers = new_names.len();
-    let total_flight_links = flight_passengers.len();
-
-    // Calculate which phase we're in based on batch number
-    let passenger_batches = (total_new_passengers + batch_size - 1) / batch_size.max(1);
-    let link_batches = (total_flight_links + batch_size - 1) / batch_size.max(1);
-    let total_batches = passenger_batches + link_batches;
-
-    let mut passengers_created_this_batch = 0;
-    let mut aliases_created_this_batch = 0;
-    let mut flight_links_created_this_batch = 0;
-
-    // Get name -> passenger_id mapping
-    let mut name_to_passenger_id: HashMap<String, String> = {
-        let mut stmt = db.conn.prepare("SELECT raw_name, passenger_id FROM passenger_aliases")
-            .map_err(|e| e.to_string())?;
-        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
-            .map_err(|e| e.to_string())?;
-        rows.filter_map(|r| r.ok()).collect()
-    };
+    let passenger_batches = new_names.len().div_ceil(batch_size);
+    let link_batches = unlinked.len().div_ceil(batch_size);
+    let total_batches = batch_number + passenger_batches + link_batches.max(1);
 
-    let phase: String;
+    let mut batch = BootstrapResult::default();
+    let phase: &str;
     let is_complete: bool;
 
-    if batch_number < passenger_batches {
-        // Phase: Creating passengers
-        phase = "creating_passengers".to_string();
+    if !new_names.is_empty() {
+        phase = "creating_passengers";
         is_complete = false;
 
-        let start = batch_number * batch_size;
-        let end = (start + batch_size).min(total_new_passengers);
-
-        if start < total_new_passengers {
-            db.conn.execute("BEGIN TRANSACTION", []).map_err(|e| e.to_string())?;
-
-            for (name, count) in new_names.iter().skip(start).take(end - start) {
-                let passenger_id = Uuid::new_v4().to_string();
-                let alias_id = Uuid::new_v4().to_string();
-
-                db.conn.execute(
-                    "INSERT INTO passengers (id, canonical_name, total_flights, created_at, updated_at)
-                     VALUES (?1, ?2, ?3, datetime('now'), datetime('now'))",
-                    params![passenger_id, name, count]
-                ).map_err(|e| e.to_string())?;
-                passengers_created_this_batch += 1;
-
-                db.conn.execute(
-                    "INSERT INTO passenger_aliases (id, passenger_id, raw_name, usage_count, match_type, confidence, created_at)
-                     VALUES (?1, ?2, ?3, ?4, 'exact', 1.0, datetime('now'))",
-                    params![alias_id, passenger_id, name, count]
-                ).map_err(|e| e.to_string())?;
-                aliases_created_this_batch += 1;
-
-                name_to_passenger_id.insert(name.clone(), passenger_id);
+        let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let mut fusion = options.fusion(&tx).map_err(|e| e.to_string())?;
+        let names = &new_names[..batch_size.min(new_names.len())];
+        create_identities(&tx, &mut fusion, names, &mut name_to_passenger_id, &mut batch)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    } else if !unlinked.is_empty() {
+        phase = "linking_flights";
+        is_complete = unlinked.len() <= batch_size;
+
+        let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        for (flight_id, pid) in unlinked.iter().take(batch_size) {
+            if let Some(pid) = pid {
+                batch.flight_links_created += link_flight(&tx, flight_id, pid).map_err(|e| e.to_string())?;
             }
-
-            db.conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
         }
-    } else {
-        // Phase: Linking flights
-        let link_batch = batch_number - passenger_batches;
-        let start = link_batch * batch_size;
-        let end = (start + batch_size).min(total_flight_links);
-
-        if start < total_flight_links {
-            phase = "linking_flights".to_string();
-            is_complete = link_batch + 1 >= link_batches;
-
-            db.conn.execute("BEGIN TRANSACTION", []).map_err(|e| e.to_string())?;
-
-            for (flight_id, passenger_name) in flight_passengers.iter().skip(start).take(end - start) {
-                if let Some(pid) = name_to_passenger_id.get(passenger_name) {
-                    let result = db.conn.execute(
-                        "INSERT OR IGNORE INTO flight_passengers (flight_id, passenger_id, created_at)
-                         VALUES (?1, ?2, datetime('now'))",
-                        params![flight_id, pid]
-                    ).map_err(|e| e.to_string())?;
-
-                    if result > 0 {
-                        flight_links_created_this_batch += 1;
-                    }
-                }
-            }
-
-            db.conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
-        } else {
-            phase = "complete".to_string();
-            is_complete = true;
+        if is_complete {
+            refresh_merged_flight_counts(&tx).map_err(|e| e.to_string())?;
         }
+        tx.commit().map_err(|e| e.to_string())?;
+    } else {
+        phase = "complete";
+        is_complete = true;
     }
 
     // Get totals from database
@@ -383,14 +445,16 @@ pub fn bootstrap_identities_batch(
     Ok(BootstrapBatchResult {
         batch_number,
         total_batches,
-        passengers_created_this_batch,
-        aliases_created_this_batch,
-        flight_links_created_this_batch,
+        passengers_created_this_batch: batch.passengers_created,
+        aliases_created_this_batch: batch.aliases_created,
+        flight_links_created_this_batch: batch.flight_links_created,
+        merges_this_batch: batch.merges,
+        queued_this_batch: batch.queued_for_review,
         total_passengers_created,
         total_aliases_created,
         total_flight_links_created,
         is_complete,
-        phase,
+        phase: phase.to_string(),
     })
 }
 
@@ -404,16 +468,23 @@ pub fn merge_alias(
     state: State<'_, AppState>,
 ) -> Result<MergeResult, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
+    merge_alias_into(&db.conn, source_raw_name, target_passenger_id)
+}
 
+fn merge_alias_into(
+    conn: &Connection,
+    source_raw_name: String,
+    target_passenger_id: String,
+) -> Result<MergeResult, String> {
     // Get the source alias info
-    let (source_alias_id, source_passenger_id): (String, String) = db.conn.query_row(
+    let (source_alias_id, source_passenger_id): (String, String) = conn.query_row(
         "SELECT id, passenger_id FROM passenger_aliases WHERE raw_name = ?1",
         params![source_raw_name],
         |row| Ok((row.get(0)?, row.get(1)?))
     ).map_err(|e| format!("Source alias '{}' not found: {}", source_raw_name, e))?;
 
     // Get target canonical name
-    let target_canonical_name: String = db.conn.query_row(
+    let target_canonical_name: String = conn.query_row(
         "SELECT canonical_name FROM passengers WHERE id = ?1",
         params![target_passenger_id],
         |row| row.get(0)
@@ -425,19 +496,24 @@ pub fn merge_alias(
     }
 
     // Step 1: Update the alias to point to the new passenger
-    db.conn.execute(
+    conn.execute(
         "UPDATE passenger_aliases SET passenger_id = ?1, match_type = 'manual' WHERE id = ?2",
         params![target_passenger_id, source_alias_id]
     ).map_err(|e| e.to_string())?;
 
-    // Step 2: Update flight_passengers to point to the new passenger
-    let flights_updated = db.conn.execute(
-        "UPDATE flight_passengers SET passenger_id = ?1 WHERE passenger_id = ?2",
+    // Step 2: Update flight_passengers to point to the new passenger; flights both were
+    // already linked to keep the target's link
+    let flights_updated = conn.execute(
+        "UPDATE OR IGNORE flight_passengers SET passenger_id = ?1 WHERE passenger_id = ?2",
         params![target_passenger_id, source_passenger_id]
     ).map_err(|e| e.to_string())? as i32;
+    conn.execute(
+        "DELETE FROM flight_passengers WHERE passenger_id = ?1",
+        params![source_passenger_id]
+    ).map_err(|e| e.to_string())?;
 
     // Step 3: Check if the source passenger has any remaining aliases
-    let remaining_aliases: i32 = db.conn.query_row(
+    let remaining_aliases: i32 = conn.query_row(
         "SELECT COUNT(*) FROM passenger_aliases WHERE passenger_id = ?1",
         params![source_passenger_id],
         |row| row.get(0)
@@ -445,14 +521,14 @@ pub fn merge_alias(
 
     // Step 4: If no remaining aliases, delete the orphaned passenger record
     if remaining_aliases == 0 {
-        db.conn.execute(
+        conn.execute(
             "DELETE FROM passengers WHERE id = ?1",
             params![source_passenger_id]
         ).map_err(|e| e.to_string())?;
     }
 
     // Step 5: Update flight counts on target passenger
-    db.conn.execute(
+    conn.execute(
         "UPDATE passengers SET
             total_flights = (SELECT COUNT(DISTINCT flight_id) FROM flight_passengers WHERE passenger_id = ?1),
             updated_at = datetime('now')
@@ -468,6 +544,310 @@ pub fn merge_alias(
     })
 }
 
+// ===== MERGE REVIEW QUEUE =====
+// Near matches found by bootstrap, below the auto-merge threshold
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeReviewItem {
+    pub id: String,
+    pub source_name: String,
+    pub source_passenger_id: String,
+    pub target_passenger_id: String,
+    pub target_canonical_name: String,
+    pub similarity: f64,
+    pub match_type: String,
+    pub created_at: String,
+}
+
+/// Pending review items, most similar first; items already merged some other way are left out
+#[tauri::command]
+pub fn list_merge_review_queue(
+    state: State<'_, AppState>,
+) -> Result<Vec<MergeReviewItem>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = db.conn.prepare(
+        "SELECT q.id, q.source_name, a.passenger_id, q.target_passenger_id, p.canonical_name,
+                q.similarity, q.match_type, q.created_at
+         FROM passenger_merge_queue q
+         JOIN passenger_aliases a ON a.raw_name = q.source_name
+         JOIN passengers p ON p.id = q.target_passenger_id
+         WHERE q.status = 'pending' AND a.passenger_id != q.target_passenger_id
+         ORDER BY q.similarity DESC, q.source_name"
+    ).map_err(|e| e.to_string())?;
+
+    let items = stmt
+        .query_map([], |row| {
+            Ok(MergeReviewItem {
+                id: row.get(0)?,
+                source_name: row.get(1)?,
+                source_passenger_id: row.get(2)?,
+                target_passenger_id: row.get(3)?,
+                target_canonical_name: row.get(4)?,
+                similarity: row.get(5)?,
+                match_type: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(items)
+}
+
+/// Merge a queued pair, or keep the two apart; with `never_merge` the pair is also added to
+/// the never-merge list so bootstrap does not suggest it again
+#[tauri::command]
+pub fn resolve_merge_review(
+    item_id: String,
+    merge: bool,
+    never_merge: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Option<MergeResult>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let (source_name, target_passenger_id, target_canonical_name): (String, String, String) = db.conn
+        .query_row(
+            "SELECT q.source_name, q.target_passenger_id, p.canonical_name
+             FROM passenger_merge_queue q JOIN passengers p ON p.id = q.target_passenger_id
+             WHERE q.id = ?1",
+            params![item_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Review item not found: {}", item_id))?;
+
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let merged = if merge {
+        Some(merge_alias_into(&tx, source_name, target_passenger_id)?)
+    } else {
+        if never_merge.unwrap_or(false) {
+            insert_never_merge_pair(&tx, &source_name, &target_canonical_name)?;
+        }
+        None
+    };
+    tx.execute(
+        "UPDATE passenger_merge_queue SET status = ?2, resolved_at = datetime('now') WHERE id = ?1",
+        params![item_id, if merge { "merged" } else { "kept_separate" }],
+    ).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(merged)
+}
+
+// ===== NEVER-MERGE PAIRS =====
+// Name pairs bootstrap must not match, whatever their similarity
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeverMergePair {
+    pub name_a: String,
+    pub name_b: String,
+    pub created_at: String,
+}
+
+fn insert_never_merge_pair(conn: &Connection, name_a: &str, name_b: &str) -> Result<(), String> {
+    let (name_a, name_b) = never_merge_key(name_a, name_b);
+    if name_a.is_empty() || name_a == name_b {
+        return Err("A never-merge pair needs two different names".to_string());
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO passenger_never_merge (name_a, name_b) VALUES (?1, ?2)",
+        params![name_a, name_b],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn add_never_merge_pair(
+    name_a: String,
+    name_b: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    insert_never_merge_pair(&db.conn, &name_a, &name_b)
+}
+
+#[tauri::command]
+pub fn remove_never_merge_pair(
+    name_a: String,
+    name_b: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let (name_a, name_b) = never_merge_key(&name_a, &name_b);
+    let removed = db.conn.execute(
+        "DELETE FROM passenger_never_merge WHERE name_a = ?1 AND name_b = ?2",
+        params![name_a, name_b],
+    ).map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+#[tauri::command]
+pub fn list_never_merge_pairs(
+    state: State<'_, AppState>,
+) -> Result<Vec<NeverMergePair>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = db.conn.prepare(
+        "SELECT name_a, name_b, created_at FROM passenger_never_merge ORDER BY name_a, name_b"
+    ).map_err(|e| e.to_string())?;
+
+    let pairs = stmt
+        .query_map([], |row| {
+            Ok(NeverMergePair {
+                name_a: row.get(0)?,
+                name_b: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(pairs)
+}
+
+// ===== IDENTITY TIMELINE CONFLICTS =====
+// Flights one identity cannot all have taken, and the fixes: un-merge the alias that brought
+// a flight in, or mark the flight as erroneous
+
+#[tauri::command]
+pub fn detect_identity_conflicts(
+    passenger_id: String,
+    state: State<'_, AppState>,
+) -> Result<IdentityConflictReport, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    identity_conflicts::detect(&db, &passenger_id).map_err(|e| e.to_string())
+}
+
+/// Raise an identity_conflict anomaly on each flight; returns the anomaly ids
+#[tauri::command]
+pub fn flag_identity_conflict_flights(
+    passenger_id: String,
+    flight_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    identity_conflicts::flag_flights(&db, &passenger_id, &flight_ids).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnmergeResult {
+    pub raw_name: String,
+    pub original_passenger_id: String,
+    pub passenger_id: String,
+    /// Flights moved to the alias's own identity
+    pub flights_moved: i32,
+    /// Flights that also name another alias of the original identity, now linked to both
+    pub flights_shared: i32,
+}
+
+/// Undo a merge: the alias gets its own identity (or rejoins the existing one with its name)
+/// and takes the flights whose notes name it. The pair is recorded as never-merge so
+/// bootstrap leaves it apart.
+#[tauri::command]
+pub fn unmerge_alias(
+    raw_name: String,
+    state: State<'_, AppState>,
+) -> Result<UnmergeResult, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let result = unmerge_alias_from(&tx, &raw_name)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+fn unmerge_alias_from(conn: &Connection, raw_name: &str) -> Result<UnmergeResult, String> {
+    let (alias_id, original_id, canonical_name): (String, String, String) = conn.query_row(
+        "SELECT a.id, a.passenger_id, p.canonical_name
+         FROM passenger_aliases a JOIN passengers p ON p.id = a.passenger_id
+         WHERE a.raw_name = ?1",
+        params![raw_name],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|e| format!("Alias '{}' not found: {}", raw_name, e))?;
+    if raw_name == canonical_name {
+        return Err(format!("'{}' is the identity's canonical name; rename or split it instead", raw_name));
+    }
+
+    let other_aliases: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT raw_name FROM passenger_aliases WHERE passenger_id = ?1 AND id != ?2"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![original_id, alias_id], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    let existing: Option<String> = conn.query_row(
+        "SELECT id FROM passengers WHERE canonical_name = ?1",
+        params![raw_name],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    let passenger_id = match existing {
+        Some(id) => id,
+        None => {
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO passengers (id, canonical_name, total_flights, created_at, updated_at)
+                 VALUES (?1, ?2, 0, datetime('now'), datetime('now'))",
+                params![id, raw_name]
+            ).map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    conn.execute(
+        "UPDATE passenger_aliases SET passenger_id = ?1, match_type = 'manual', confidence = 1.0 WHERE id = ?2",
+        params![passenger_id, alias_id]
+    ).map_err(|e| e.to_string())?;
+
+    let flights: Vec<(String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT f.id, f.notes FROM flight_passengers fp JOIN flights f ON f.id = fp.flight_id
+             WHERE fp.passenger_id = ?1 AND f.notes IS NOT NULL"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![original_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    let mut flights_moved = 0;
+    let mut flights_shared = 0;
+    for (flight_id, notes) in &flights {
+        let names = names_in_notes(notes);
+        if !names.iter().any(|n| n == raw_name) {
+            continue;
+        }
+        link_flight(conn, flight_id, &passenger_id).map_err(|e| e.to_string())?;
+        if names.iter().any(|n| other_aliases.contains(n)) {
+            flights_shared += 1;
+        } else {
+            conn.execute(
+                "DELETE FROM flight_passengers WHERE flight_id = ?1 AND passenger_id = ?2",
+                params![flight_id, original_id]
+            ).map_err(|e| e.to_string())?;
+            flights_moved += 1;
+        }
+    }
+
+    for id in [&original_id, &passenger_id] {
+        conn.execute(
+            "UPDATE passengers SET
+                total_flights = (SELECT COUNT(DISTINCT flight_id) FROM flight_passengers WHERE passenger_id = ?1),
+                updated_at = datetime('now')
+             WHERE id = ?1",
+            params![id]
+        ).map_err(|e| e.to_string())?;
+    }
+    insert_never_merge_pair(conn, raw_name, &canonical_name)?;
+
+    Ok(UnmergeResult {
+        raw_name: raw_name.to_string(),
+        original_passenger_id: original_id,
+        passenger_id,
+        flights_moved,
+        flights_shared,
+    })
+}
+
 // ===== LIST COMMANDS =====
 
 #[tauri::command]
@@ -573,21 +953,223 @@ pub fn list_unmerged_passengers(
 }
 
 // ===== RENAME CANONICAL =====
+// The canonical name is also written out in flight notes ("Passengers: A, B") and in the
+// abbreviation mappings; a rename updates all of them together so no copy goes stale.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameResult {
+    pub passenger_id: String,
+    pub old_name: String,
+    pub new_name: String,
+    pub notes_updated: i32,
+    pub mappings_updated: i32,
+}
 
 #[tauri::command]
 pub fn rename_canonical_passenger(
     passenger_id: String,
     new_canonical_name: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<RenameResult, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
+    rename_passenger(&db.conn, &passenger_id, &new_canonical_name)
+}
 
-    db.conn.execute(
+/// Rename a canonical passenger, the notes of their linked flights and their mappings in one transaction
+fn rename_passenger(
+    conn: &rusqlite::Connection,
+    passenger_id: &str,
+    new_canonical_name: &str,
+) -> Result<RenameResult, String> {
+    let new_name = new_canonical_name.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+    if new_name.is_empty() {
+        return Err("Passenger name cannot be empty".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let old_name: String = tx.query_row(
+        "SELECT canonical_name FROM passengers WHERE id = ?1",
+        params![passenger_id],
+        |row| row.get(0)
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Passenger not found: {}", passenger_id))?;
+
+    let taken_by: Option<String> = tx.query_row(
+        "SELECT id FROM passengers WHERE canonical_name = ?1 AND id != ?2",
+        params![new_name, passenger_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    if taken_by.is_some() {
+        return Err(format!("Another passenger is already named {}; merge them instead", new_name));
+    }
+
+    tx.execute(
         "UPDATE passengers SET canonical_name = ?1, updated_at = datetime('now') WHERE id = ?2",
-        params![new_canonical_name.to_uppercase(), passenger_id]
+        params![new_name, passenger_id]
     ).map_err(|e| e.to_string())?;
 
-    Ok(())
+    // Notes will carry the new name, so it must resolve to this passenger on the next bootstrap.
+    // The old name stays an alias for documents that still use it.
+    tx.execute(
+        "INSERT OR IGNORE INTO passenger_aliases (id, passenger_id, raw_name, usage_count, match_type, confidence, created_at)
+         VALUES (?1, ?2, ?3, 0, 'manual', 1.0, datetime('now'))",
+        params![Uuid::new_v4().to_string(), passenger_id, new_name]
+    ).map_err(|e| e.to_string())?;
+
+    let flights: Vec<(String, String)> = {
+        let mut stmt = tx.prepare(
+            "SELECT f.id, f.notes FROM flights f
+             JOIN flight_passengers fp ON fp.flight_id = f.id
+             WHERE fp.passenger_id = ?1 AND f.notes IS NOT NULL"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![passenger_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    let mut notes_updated = 0;
+    for (flight_id, notes) in flights {
+        if let Some(new_notes) = rename_in_notes(&notes, &old_name, &new_name) {
+            tx.execute(
+                "UPDATE flights SET notes = ?1, updated_at = datetime('now') WHERE id = ?2",
+                params![new_notes, flight_id]
+            ).map_err(|e| e.to_string())?;
+            notes_updated += 1;
+        }
+    }
+
+    let mappings_updated = tx.execute(
+        "UPDATE passenger_mappings SET full_name = ?1, updated_at = datetime('now') WHERE UPPER(TRIM(full_name)) = ?2",
+        params![new_name, old_name]
+    ).map_err(|e| e.to_string())? as i32;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(RenameResult {
+        passenger_id: passenger_id.to_string(),
+        old_name,
+        new_name,
+        notes_updated,
+        mappings_updated,
+    })
+}
+
+/// Passenger names listed in flight notes, uppercased
+/// Reads the "Passengers: " line when there is one, otherwise the whole notes (as bootstrap does).
+pub(crate) fn names_in_notes(notes: &str) -> Vec<String> {
+    let list = match notes.strip_prefix("Passengers: ") {
+        Some(rest) => rest.lines().next().unwrap_or(""),
+        None => notes,
+    };
+    list.split([',', ';', '|'])
+        .map(|name| name.trim().to_uppercase())
+        .filter(|name| name.len() > 1)
+        .collect()
+}
+
+/// Notes with `old_name` replaced in the "Passengers: " line; None when it does not appear
+/// The rest of the notes is left as it is.
+fn rename_in_notes(notes: &str, old_name: &str, new_name: &str) -> Option<String> {
+    let rest = notes.strip_prefix("Passengers: ")?;
+    let (list, remainder) = match rest.find('\n') {
+        Some(end) => rest.split_at(end),
+        None => (rest, ""),
+    };
+
+    let mut changed = false;
+    let names: Vec<String> = list
+        .split(',')
+        .map(|name| {
+            let trimmed = name.trim();
+            if trimmed.eq_ignore_ascii_case(old_name) {
+                changed = true;
+                new_name.to_string()
+            } else {
+                trimmed.to_string()
+            }
+        })
+        .collect();
+
+    changed.then(|| format!("Passengers: {}{}", names.join(", "), remainder))
+}
+
+// ===== NAME CONSISTENCY CHECK =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassengerNameInconsistency {
+    pub passenger_id: String,
+    pub canonical_name: String,
+    /// Linked flights whose notes name neither the passenger nor any of their aliases
+    pub flight_ids: Vec<String>,
+    /// Names those notes list instead
+    pub names_in_notes: Vec<String>,
+}
+
+/// Find passengers whose canonical name disagrees with the notes of their linked flights
+#[tauri::command]
+pub fn find_passenger_name_inconsistencies(
+    state: State<'_, AppState>,
+) -> Result<Vec<PassengerNameInconsistency>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    find_name_inconsistencies(&db.conn).map_err(|e| e.to_string())
+}
+
+fn find_name_inconsistencies(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<PassengerNameInconsistency>> {
+    // Names each passenger is known by: canonical name plus aliases
+    let mut known: HashMap<String, std::collections::HashSet<String>> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT id, canonical_name FROM passengers
+         UNION ALL
+         SELECT passenger_id, raw_name FROM passenger_aliases"
+    )?;
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))? {
+        let (passenger_id, name) = row?;
+        known.entry(passenger_id).or_default().insert(name.trim().to_uppercase());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT p.id, p.canonical_name, f.id, f.notes
+         FROM flight_passengers fp
+         JOIN passengers p ON p.id = fp.passenger_id
+         JOIN flights f ON f.id = fp.flight_id
+         WHERE f.deleted_at IS NULL AND f.notes IS NOT NULL
+         ORDER BY p.canonical_name, f.departure_datetime"
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+    })?;
+
+    let mut inconsistencies: Vec<PassengerNameInconsistency> = Vec::new();
+    for row in rows {
+        let (passenger_id, canonical_name, flight_id, notes) = row?;
+        let names = names_in_notes(&notes);
+        let names_known = known.get(&passenger_id);
+        if names.iter().any(|name| names_known.is_some_and(|known| known.contains(name))) {
+            continue;
+        }
+
+        if inconsistencies.last().is_none_or(|last| last.passenger_id != passenger_id) {
+            inconsistencies.push(PassengerNameInconsistency {
+                passenger_id,
+                canonical_name,
+                flight_ids: Vec::new(),
+                names_in_notes: Vec::new(),
+            });
+        }
+        let entry = inconsistencies.last_mut().expect("pushed above");
+        entry.flight_ids.push(flight_id);
+        for name in names {
+            if !entry.names_in_notes.contains(&name) {
+                entry.names_in_notes.push(name);
+            }
+        }
+    }
+
+    Ok(inconsistencies)
 }
 
 // ===== STATS =====
@@ -1135,6 +1717,7 @@ pub fn search_passengers(
              LIMIT 500"
         ).map_err(|e| e.to_string())?;
 
+        #[allow(clippy::type_complexity)]
         let results: Vec<(String, String, Option<String>, i32, Option<String>, Option<String>, i32)> = stmt
             .query_map([], |row| {
                 Ok((
@@ -1200,6 +1783,7 @@ pub fn search_passengers(
 
     let like_pattern = format!("%{}%", query_upper);
 
+    #[allow(clippy::type_complexity)]
     let results: Vec<(String, String, Option<String>, i32, Option<String>, Option<String>, i32, Option<String>)> = stmt
         .query_map(params![like_pattern, query_upper], |row| {
             Ok((
@@ -1336,7 +1920,7 @@ pub fn get_passenger_details_aggregated(
         let mut stmt = db.conn.prepare(
             "SELECT id, notes, departure_airport, arrival_airport, departure_datetime, distance_km, carbon_emissions_kg, carbon_offset_purchased
              FROM flights
-             WHERE user_id = ?1 AND notes LIKE ?2"
+             WHERE user_id = ?1 AND deleted_at IS NULL AND notes LIKE ?2"
         ).map_err(|e| e.to_string())?;
 
         let flights: Vec<FlightData> = stmt
@@ -1383,7 +1967,7 @@ pub fn get_passenger_details_aggregated(
     // Create a set of all aliases for companion filtering
     let alias_set: std::collections::HashSet<&str> = aliases.iter().map(|s| s.as_str()).collect();
 
-    for (_id, (_, notes, dep, arr, datetime, dist, co2, offset)) in &flight_data_map {
+    for (_, notes, dep, arr, datetime, dist, co2, offset) in flight_data_map.values() {
         // Distance
         if let Some(d) = dist {
             total_distance_km += d;
@@ -1442,7 +2026,7 @@ pub fn get_passenger_details_aggregated(
             }
         })
         .collect();
-    top_routes.sort_by(|a, b| b.flight_count.cmp(&a.flight_count));
+    top_routes.sort_by_key(|x| std::cmp::Reverse(x.flight_count));
     top_routes.truncate(5);
 
     // Build travel companions
@@ -1453,7 +2037,7 @@ pub fn get_passenger_details_aggregated(
             flight_count: count,
         })
         .collect();
-    travel_companions.sort_by(|a, b| b.flight_count.cmp(&a.flight_count));
+    travel_companions.sort_by_key(|x| std::cmp::Reverse(x.flight_count));
     travel_companions.truncate(10);
 
     Ok(PassengerDetailsAggregated {
@@ -1631,3 +2215,142 @@ pub fn batch_split_passengers(
         errors,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> crate::database::Database {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn.execute_batch(
+            "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+             INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, notes) VALUES
+                ('f1', 'u1', 'PBI', 'TEB', '2024-01-01T10:00:00', 'Passengers: John Smith, JE\n\nFuel stop'),
+                ('f2', 'u1', 'TEB', 'PBI', '2024-01-02T10:00:00', 'Passengers: J SMITH'),
+                ('f3', 'u1', 'TEB', 'PBI', '2024-01-03T10:00:00', 'Passengers: SOMEONE ELSE');
+             INSERT INTO passengers (id, canonical_name) VALUES ('p1', 'JOHN SMITH'), ('p2', 'JE');
+             INSERT INTO passenger_aliases (id, passenger_id, raw_name) VALUES
+                ('a1', 'p1', 'JOHN SMITH'), ('a2', 'p1', 'J SMITH'), ('a3', 'p2', 'JE');
+             INSERT INTO flight_passengers (flight_id, passenger_id) VALUES
+                ('f1', 'p1'), ('f2', 'p1'), ('f3', 'p1'), ('f1', 'p2');
+             INSERT INTO passenger_mappings (abbreviation, full_name) VALUES ('JS', 'John Smith');",
+        ).unwrap();
+        db
+    }
+
+    fn bootstrap_db() -> crate::database::Database {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn.execute_batch(
+            "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+             INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, notes) VALUES
+                ('f1', 'u1', 'PBI', 'TEB', '2024-01-01T10:00:00', 'Passengers: Jeffrey Epstein, JE'),
+                ('f2', 'u1', 'TEB', 'PBI', '2024-01-02T10:00:00', 'Passengers: JEFFREY EPSTIEN'),
+                ('f3', 'u1', 'TEB', 'PBI', '2024-01-03T10:00:00', 'Passengers: JOHN SMITH'),
+                ('f4', 'u1', 'PBI', 'TEB', '2024-01-04T10:00:00', 'Passengers: JOHN DOE, JOHN');",
+        ).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_bootstrap_auto_merge_options() {
+        let db = bootstrap_db();
+        let result = bootstrap_user_identities(&db.conn, "u1", &BootstrapOptions::default()).unwrap();
+        let mut merged: Vec<_> = result.merges.iter().map(|m| (m.source_name.as_str(), m.match_type.as_str())).collect();
+        merged.sort();
+        assert_eq!(merged, [("JE", "initials"), ("JEFFREY EPSTIEN", "fuzzy")]);
+        // JOHN matches two people equally well, so it is only queued; JE shares f1 with its identity
+        assert_eq!((result.passengers_created, result.queued_for_review, result.flight_links_created), (4, 1, 5));
+        let flights: i32 = db.conn
+            .query_row("SELECT total_flights FROM passengers WHERE canonical_name = 'JEFFREY EPSTEIN'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(flights, 2);
+
+        let db = bootstrap_db();
+        insert_never_merge_pair(&db.conn, "jeffrey epstien", "Jeffrey Epstein").unwrap();
+        let options = BootstrapOptions { merge_initials: Some(false), ..Default::default() };
+        let result = bootstrap_user_identities(&db.conn, "u1", &options).unwrap();
+        assert!(result.merges.is_empty());
+        assert_eq!((result.passengers_created, result.queued_for_review), (6, 2));
+
+        // Accepting the queued initials match folds JE into whichever identity it was matched to
+        let (source, target): (String, String) = db.conn
+            .query_row(
+                "SELECT source_name, target_passenger_id FROM passenger_merge_queue WHERE match_type = 'initials'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        merge_alias_into(&db.conn, source, target.clone()).unwrap();
+        let je_owner: String = db.conn
+            .query_row("SELECT passenger_id FROM passenger_aliases WHERE raw_name = 'JE'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(je_owner, target);
+
+        assert!(BootstrapOptions { review_threshold: Some(0.99), ..Default::default() }.validate().is_err());
+        assert!(BootstrapOptions { auto_merge_threshold: Some(1.5), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_unmerge_alias() {
+        let db = bootstrap_db();
+        bootstrap_user_identities(&db.conn, "u1", &BootstrapOptions::default()).unwrap();
+        let original: String = db.conn
+            .query_row("SELECT id FROM passengers WHERE canonical_name = 'JEFFREY EPSTEIN'", [], |row| row.get(0))
+            .unwrap();
+
+        let result = unmerge_alias_from(&db.conn, "JEFFREY EPSTIEN").unwrap();
+        assert_eq!(result.original_passenger_id, original);
+        assert_ne!(result.passenger_id, original);
+        assert_eq!((result.flights_moved, result.flights_shared), (1, 0));
+        // f1 also names JEFFREY EPSTEIN, so JE takes it without the identity losing it
+        let result = unmerge_alias_from(&db.conn, "JE").unwrap();
+        assert_eq!((result.flights_moved, result.flights_shared), (0, 1));
+        let flights: i32 = db.conn
+            .query_row("SELECT total_flights FROM passengers WHERE id = ?1", params![original], |row| row.get(0))
+            .unwrap();
+        assert_eq!(flights, 1);
+
+        let pairs: i32 = db.conn
+            .query_row("SELECT COUNT(*) FROM passenger_never_merge WHERE 'JEFFREY EPSTEIN' IN (name_a, name_b)", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(pairs, 2);
+        assert!(unmerge_alias_from(&db.conn, "JEFFREY EPSTEIN").is_err());
+    }
+
+    #[test]
+    fn test_rename_in_notes() {
+        assert_eq!(
+            rename_in_notes("Passengers: john smith, JE\n\nFuel stop", "JOHN SMITH", "JOHN A SMITH").as_deref(),
+            Some("Passengers: JOHN A SMITH, JE\n\nFuel stop")
+        );
+        assert_eq!(rename_in_notes("Passengers: JE", "JOHN SMITH", "X"), None);
+        assert_eq!(rename_in_notes("John Smith flew", "JOHN SMITH", "X"), None);
+    }
+
+    #[test]
+    fn test_rename_updates_every_copy() {
+        let db = setup();
+        assert!(rename_passenger(&db.conn, "p1", "JE").is_err());
+
+        let result = rename_passenger(&db.conn, "p1", "  john  a smith ").unwrap();
+        assert_eq!(result.new_name, "JOHN A SMITH");
+        assert_eq!((result.notes_updated, result.mappings_updated), (1, 1));
+
+        let notes: String = db.conn.query_row("SELECT notes FROM flights WHERE id = 'f1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(notes, "Passengers: JOHN A SMITH, JE\n\nFuel stop");
+        let alias_owner: String = db.conn
+            .query_row("SELECT passenger_id FROM passenger_aliases WHERE raw_name = 'JOHN A SMITH'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(alias_owner, "p1");
+    }
+
+    #[test]
+    fn test_find_name_inconsistencies() {
+        let db = setup();
+        let found = find_name_inconsistencies(&db.conn).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].passenger_id, "p1");
+        assert_eq!(found[0].flight_ids, vec!["f3"]);
+        assert_eq!(found[0].names_in_notes, vec!["SOMEONE ELSE"]);
+    }
+}
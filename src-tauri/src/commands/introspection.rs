@@ -0,0 +1,372 @@
+// Command introspection
+// Machine-readable contract for every registered Tauri command, read from the command
+// sources compiled into the binary so it cannot drift from the actual signatures
+
+use serde::{Deserialize, Serialize};
+
+/// Source of every command module, embedded at compile time
+macro_rules! command_sources {
+    ($($module:literal),* $(,)?) => {
+        &[$(($module, include_str!(concat!($module, ".rs")))),*]
+    };
+}
+
+const COMMAND_SOURCES: &[(&str, &str)] = command_sources![
+    "mod",
+    "active_defense",
+    "agent_memory_commands",
+    "ai_models",
+    "aircraft",
+    "airport_enrichment",
+    "airports",
+    "analytics",
+    "backups",
+    "batch_calculations",
+    "calculations",
+    "comparison",
+    "csv_import",
+    "custom_schema_commands",
+    "data_editor",
+    "data_management",
+    "digest",
+    "doc_ingestion_commands",
+    "documents",
+    "donation",
+    "eml_import",
+    "ffp",
+    "flight_card",
+    "flight_tags",
+    "flight_templates",
+    "flights",
+    "fuel_commands",
+    "fuel_prices",
+    "identity_fusion",
+    "introspection",
+    "investigations",
+    "journeys",
+    "logs",
+    "maintenance",
+    "master_log_import",
+    "media_gallery",
+    "network_scanner",
+    "network_sentinel",
+    "network_tools",
+    "ocr",
+    "operations",
+    "passengers",
+    "pilot_credentials",
+    "pilot_logbook",
+    "query_advisor",
+    "reports",
+    "research",
+    "self_improvement",
+    "self_test",
+    "settings",
+    "statistics",
+    "trip_purpose",
+    "users",
+    "workflow",
+];
+
+/// lib.rs holds the generate_handler! list, the source of truth for what is registered
+const LIB_SOURCE: &str = include_str!("../lib.rs");
+
+/// Parameters Tauri injects itself; the frontend never passes them
+const INJECTED_TYPES: [&str; 4] = ["State<", "AppHandle", "Window", "WebviewWindow"];
+
+/// Commands whose names start with these delete or irreversibly rewrite data
+const DESTRUCTIVE_PREFIXES: [&str; 10] = [
+    "delete_",
+    "purge_",
+    "reset_",
+    "remove_",
+    "merge_",
+    "split_",
+    "batch_split_",
+    "bulk_delete_",
+    "fix_",
+    "batch_recalculate_",
+];
+
+/// Destructive commands the prefixes do not catch (data loss or system-level actions)
+const DESTRUCTIVE_COMMANDS: [&str; 20] = [
+    "clear_ai_cache",
+    "run_retention_cleanup",
+    "export_archived_reports",
+    "repair_orphaned_records",
+    "unlock_logbook_entry",
+    "restore_backup",
+    "cpu_emergency_power_reduce",
+    "memory_drop_caches",
+    "memory_emergency_relief",
+    "memory_trigger_oom_kill",
+    "thermal_emergency_cool",
+    "gpu_emergency_throttle",
+    "gpu_kill_process",
+    "network_block_ip",
+    "network_kill_connections_ip",
+    "network_block_process",
+    "network_clear_all_blocks",
+    "process_kill",
+    "emergency_all_systems",
+    "lockdown_network",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandParam {
+    /// Rust parameter name
+    pub name: String,
+    /// Key the frontend passes to invoke()
+    pub js_name: String,
+    pub rust_type: String,
+    /// Option<T> parameters may be omitted or null
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandInfo {
+    pub name: String,
+    pub module: String,
+    pub is_async: bool,
+    pub params: Vec<CommandParam>,
+    /// Rust return type, "()" when none
+    pub return_type: String,
+    /// Deletes or irreversibly changes data; callers should confirm first
+    pub destructive: bool,
+}
+
+/// Every registered command with its parameters, return type and destructive flag
+#[tauri::command]
+pub fn introspect_commands() -> Result<Vec<CommandInfo>, String> {
+    let mut defined = defined_commands();
+    let mut commands = Vec::new();
+    for name in registered_commands() {
+        if let Some(index) = defined.iter().position(|c| c.name == name) {
+            commands.push(defined.swap_remove(index));
+        }
+    }
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(commands)
+}
+
+/// Registered commands with no parseable definition in the embedded sources, and
+/// defined commands that were never registered; both empty when nothing has drifted
+pub(crate) fn registry_drift() -> (Vec<String>, Vec<String>) {
+    let registered = registered_commands();
+    let defined = defined_commands();
+
+    let missing = registered
+        .iter()
+        .filter(|name| !defined.iter().any(|c| &c.name == *name))
+        .cloned()
+        .collect();
+    let unregistered = defined
+        .into_iter()
+        .map(|c| c.name)
+        .filter(|name| !registered.contains(name))
+        .collect();
+    (missing, unregistered)
+}
+
+/// Command names listed in generate_handler! in lib.rs
+fn registered_commands() -> Vec<String> {
+    let Some(start) = LIB_SOURCE.find("generate_handler![") else {
+        return Vec::new();
+    };
+    let body = &LIB_SOURCE[start + "generate_handler![".len()..];
+    let body = &body[..body.find(']').unwrap_or(body.len())];
+
+    body.lines()
+        .map(|line| line.split("//").next().unwrap_or("").trim())
+        .flat_map(|line| line.split(','))
+        .map(|path| path.trim().rsplit("::").next().unwrap_or("").to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn defined_commands() -> Vec<CommandInfo> {
+    COMMAND_SOURCES
+        .iter()
+        .flat_map(|(module, source)| {
+            // Test modules may contain signatures in fixtures; only real code counts
+            let code = source.find("\n#[cfg(test)]\nmod tests").map_or(*source, |end| &source[..end]);
+            parse_commands(module, code)
+        })
+        .collect()
+}
+
+/// Parse every #[tauri::command] function signature in a source file
+fn parse_commands(module: &str, source: &str) -> Vec<CommandInfo> {
+    let mut commands = Vec::new();
+    let mut rest = source;
+
+    while let Some(attr) = rest.find("#[tauri::command") {
+        let after_attr = &rest[attr..];
+        let attr_end = after_attr.find(']').unwrap_or(0);
+        let snake_case = after_attr[..attr_end].contains("snake_case");
+        rest = &after_attr[attr_end..];
+
+        // The attribute must sit on its own line, not inside a string or comment
+        let line_start = source[..source.len() - after_attr.len()].rfind('\n').map_or(0, |i| i + 1);
+        if !source[line_start..source.len() - after_attr.len()].trim().is_empty() {
+            continue;
+        }
+
+        let Some(info) = parse_signature(module, rest, snake_case) else {
+            continue;
+        };
+        commands.push(info);
+    }
+
+    commands
+}
+
+fn parse_signature(module: &str, text: &str, snake_case: bool) -> Option<CommandInfo> {
+    let fn_pos = text.find("fn ")?;
+    let is_async = text[..fn_pos].contains("async");
+    let after_fn = &text[fn_pos + 3..];
+    let open = after_fn.find('(')?;
+    let name = after_fn[..open].split('<').next()?.trim().to_string();
+
+    // Matching close paren for the parameter list
+    let mut depth = 0;
+    let mut close = None;
+    for (i, c) in after_fn[open..].char_indices() {
+        match c {
+            '(' | '<' | '[' => depth += 1,
+            ')' | '>' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+
+    let params = split_top_level(&after_fn[open + 1..close])
+        .into_iter()
+        .filter_map(|param| {
+            let (name, rust_type) = param.split_once(':')?;
+            let name = name.trim().trim_start_matches("mut ").trim().to_string();
+            let rust_type = normalize_whitespace(rust_type);
+            if INJECTED_TYPES.iter().any(|t| rust_type.contains(t)) {
+                return None;
+            }
+            Some(CommandParam {
+                js_name: if snake_case { name.clone() } else { to_camel_case(&name) },
+                optional: rust_type.starts_with("Option<"),
+                name,
+                rust_type,
+            })
+        })
+        .collect();
+
+    let tail = &after_fn[close + 1..];
+    let body = tail.find('{').unwrap_or(tail.len());
+    let return_type = tail[..body]
+        .split_once("->")
+        .map(|(_, ret)| normalize_whitespace(ret.split(" where ").next().unwrap_or(ret)))
+        .unwrap_or_else(|| "()".to_string());
+
+    Some(CommandInfo {
+        destructive: is_destructive(&name),
+        name,
+        module: module.to_string(),
+        is_async,
+        params,
+        return_type,
+    })
+}
+
+/// Split on commas that are not nested inside <>, () or []
+fn split_top_level(params: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&params[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&params[start..]);
+    parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = !out.is_empty();
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn is_destructive(name: &str) -> bool {
+    DESTRUCTIVE_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) || DESTRUCTIVE_COMMANDS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signature() {
+        let source = r#"
+/// Docs
+#[tauri::command]
+pub async fn purge_deleted(
+    user_id: String,
+    older_than_days: Option<u32>,
+    filters: HashMap<String, Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    Ok(0)
+}
+
+#[tauri::command]
+pub fn ping() {}
+"#;
+        let commands = parse_commands("flights", source);
+        assert_eq!(commands.len(), 2);
+
+        let purge = &commands[0];
+        assert_eq!(purge.name, "purge_deleted");
+        assert!(purge.is_async && purge.destructive);
+        assert_eq!(purge.return_type, "Result<usize, String>");
+        let names: Vec<&str> = purge.params.iter().map(|p| p.js_name.as_str()).collect();
+        assert_eq!(names, vec!["userId", "olderThanDays", "filters"]);
+        assert!(purge.params[1].optional);
+        assert_eq!(purge.params[2].rust_type, "HashMap<String, Vec<String>>");
+
+        assert_eq!(commands[1].return_type, "()");
+        assert!(!commands[1].destructive);
+    }
+
+    #[test]
+    fn test_registry_has_no_drift() {
+        let (missing, unregistered) = registry_drift();
+        assert!(missing.is_empty(), "registered but not found: {:?}", missing);
+        assert!(unregistered.is_empty(), "defined but not registered: {:?}", unregistered);
+        assert!(introspect_commands().unwrap().len() > 200);
+    }
+}
@@ -57,6 +57,8 @@ pub async fn investigate_flight(
         .unwrap_or(&flight.departure_datetime)
         .to_string();
 
+    let names_text = passenger_names.join(", ");
+
     // Select investigation provider based on model parameter
     let selected_model = model.as_deref().unwrap_or("gemini");
 
@@ -71,8 +73,8 @@ pub async fn investigate_flight(
 
             crate::deepseek::run_deepseek_investigation(
                 passenger_names,
-                location,
-                date,
+                location.clone(),
+                date.clone(),
                 api_key,
             )
             .await
@@ -88,8 +90,8 @@ pub async fn investigate_flight(
 
             crate::grok::run_grok_investigation(
                 passenger_names,
-                location,
-                date,
+                location.clone(),
+                date.clone(),
                 api_key,
             )
             .await
@@ -103,14 +105,46 @@ pub async fn investigate_flight(
                 &state,
             )?;
 
-            investigation::run_investigation(passenger_names, location, date, api_key)
+            investigation::run_investigation(passenger_names, location.clone(), date.clone(), api_key)
                 .await
                 .map_err(|e| format!("Gemini investigation failed: {}", e))?
         }
     };
 
-    // Store in database cache (optional - for now just return)
-    // TODO: Save to investigations table
+    // Store the result with its per-claim evidence
+    let sources_json = serde_json::to_string(&result.sources).map_err(|e| e.to_string())?;
+    let queries_json = serde_json::to_string(&result.generated_queries).map_err(|e| e.to_string())?;
+    let evidence_json = serde_json::to_string(&serde_json::json!({
+        "claims": result.claims,
+        "score_explanation": result.score_explanation,
+    }))
+    .map_err(|e| e.to_string())?;
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.conn
+        .execute(
+            "INSERT INTO investigations (
+                id, flight_id, user_id, passenger_names, location, investigation_date,
+                generated_queries, status, ai_summary, sources_json, corroboration_score,
+                processing_time_ms, evidence_json, completed_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, datetime('now'))",
+            rusqlite::params![
+                result.investigation_id,
+                flight_id,
+                flight.user_id,
+                names_text,
+                location,
+                date,
+                queries_json,
+                result.status,
+                result.ai_summary,
+                sources_json,
+                result.corroboration_score,
+                result.processing_time_ms,
+                evidence_json,
+            ],
+        )
+        .map_err(|e| format!("Failed to save investigation: {}", e))?;
 
     Ok(result)
 }
@@ -127,7 +161,7 @@ pub fn get_flight_investigation(
         .query_row(
             "SELECT id, flight_id, user_id, passenger_names, location, investigation_date,
                     generated_queries, status, ai_summary, sources_json, corroboration_score,
-                    error_message, processing_time_ms, created_at, completed_at
+                    error_message, processing_time_ms, evidence_json, created_at, completed_at
              FROM investigations
              WHERE flight_id = ?1
              ORDER BY created_at DESC
@@ -148,8 +182,9 @@ pub fn get_flight_investigation(
                     corroboration_score: row.get(10)?,
                     error_message: row.get(11)?,
                     processing_time_ms: row.get(12)?,
-                    created_at: row.get(13)?,
-                    completed_at: row.get(14)?,
+                    evidence_json: row.get(13)?,
+                    created_at: row.get(14)?,
+                    completed_at: row.get(15)?,
                 })
             },
         )
@@ -181,9 +216,9 @@ pub fn get_airport_list(
             SUM(CASE WHEN type = 'arrival' THEN 1 ELSE 0 END) as arrival_count,
             COUNT(*) as total_visits
          FROM (
-            SELECT departure_airport as airport_code, 'departure' as type FROM flights WHERE user_id = ?1
+            SELECT departure_airport as airport_code, 'departure' as type FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
             UNION ALL
-            SELECT arrival_airport as airport_code, 'arrival' as type FROM flights WHERE user_id = ?1
+            SELECT arrival_airport as airport_code, 'arrival' as type FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
          )
          GROUP BY airport_code
          ORDER BY total_visits DESC, airport_code ASC"
@@ -237,7 +272,7 @@ pub fn list_all_investigations(
             i.created_at
          FROM flight_investigations i
          JOIN flights f ON i.flight_id = f.id
-         WHERE f.user_id = ?1
+         WHERE f.user_id = ?1 AND f.deleted_at IS NULL
          ORDER BY i.created_at DESC",
         )
         .map_err(|e| e.to_string())?;
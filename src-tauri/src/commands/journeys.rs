@@ -2,6 +2,9 @@
 use tauri::State;
 
 use super::AppState;
+use crate::error::CommandError;
+use crate::journey_detection::{self, JourneySuggestion};
+use crate::journey_itinerary::{self, JourneyItinerary};
 
 // ===== JOURNEY COMMANDS =====
 
@@ -97,3 +100,54 @@ pub fn get_journey_flights(
     db.get_journey_flights(&journey_id)
         .map_err(|e| e.to_string())
 }
+
+/// Each leg with local and UTC times and its timezone shift, and the connections between
+/// legs measured in UTC
+#[tauri::command]
+pub fn get_journey_itinerary(
+    journey_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<JourneyItinerary>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(journey_itinerary::build(&db, &journey_id)?)
+}
+
+/// Candidate journeys from chains of connecting flights that are in no journey yet.
+/// A connection departs from the previous leg's arrival airport within `max_layover_hours`
+/// (default 24) of its arrival.
+#[tauri::command]
+pub fn suggest_journeys(
+    user_id: String,
+    max_layover_hours: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<JourneySuggestion>, CommandError> {
+    let db = state.db.lock()?;
+    let max_layover_hours = max_layover_hours.unwrap_or(journey_detection::DEFAULT_MAX_LAYOVER_HOURS);
+    Ok(journey_detection::suggest_journeys(&db.conn, &user_id, max_layover_hours)?)
+}
+
+/// Create a journey from a suggestion (its name may have been edited) with its legs in
+/// sequence order; returns the new journey id
+#[tauri::command]
+pub fn accept_journey_suggestion(
+    user_id: String,
+    suggestion: JourneySuggestion,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    if suggestion.legs.is_empty() {
+        return Err(CommandError::Validation("A journey suggestion needs at least one flight".to_string()));
+    }
+    let name = suggestion.name.trim();
+    if name.is_empty() {
+        return Err(CommandError::Validation("Journey name is required".to_string()));
+    }
+
+    let db = state.db.lock()?;
+    let tx = db.conn.unchecked_transaction()?;
+    let journey_id = db.create_journey(&user_id, name, None, &suggestion.start_date, Some(&suggestion.end_date))?;
+    for leg in &suggestion.legs {
+        db.add_flight_to_journey(&journey_id, &leg.flight_id, leg.sequence_order)?;
+    }
+    tx.commit()?;
+    Ok(journey_id)
+}
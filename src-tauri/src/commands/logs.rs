@@ -0,0 +1,49 @@
+// Application log commands
+// Logs are written by logging.rs; these let users read the tail to attach to bug reports
+// and change the level without restarting.
+
+use super::AppState;
+use crate::error::CommandError;
+use crate::logging;
+use tauri::{Manager, State};
+
+/// Lines returned when the caller does not ask for a count
+const DEFAULT_LINES: usize = 200;
+const MAX_LINES: usize = 5000;
+
+/// The last `lines` log lines (API keys already redacted), oldest first
+#[tauri::command]
+pub fn get_recent_logs(app_handle: tauri::AppHandle, lines: Option<usize>) -> Result<String, CommandError> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| CommandError::Internal(format!("Failed to get app data directory: {}", e)))?;
+    let count = lines.unwrap_or(DEFAULT_LINES).clamp(1, MAX_LINES);
+    Ok(logging::recent_lines(&logging::log_dir(&app_dir), count)?.join("\n"))
+}
+
+#[tauri::command]
+pub fn get_log_level(state: State<'_, AppState>) -> Result<String, CommandError> {
+    let db = state.db.lock()?;
+    Ok(db
+        .get_setting(logging::LOG_LEVEL_SETTING)?
+        .filter(|level| logging::parse_level(level).is_ok())
+        .unwrap_or_else(|| logging::DEFAULT_LEVEL.to_string()))
+}
+
+/// Save the level and apply it to the running logger
+#[tauri::command]
+pub fn set_log_level(state: State<'_, AppState>, level: String) -> Result<(), CommandError> {
+    let level = level.trim().to_lowercase();
+    if logging::parse_level(&level).is_err() {
+        return Err(CommandError::Validation(format!(
+            "Unknown log level '{}': use one of {}",
+            level,
+            logging::LEVELS.join(", ")
+        )));
+    }
+    state.db.lock()?.set_setting(logging::LOG_LEVEL_SETTING, &level)?;
+    logging::set_level(&level)?;
+    tracing::info!("Log level set to {}", level);
+    Ok(())
+}
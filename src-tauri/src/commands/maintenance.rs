@@ -0,0 +1,98 @@
+// Aircraft maintenance commands - recurring inspection and service items per tail number
+use chrono::{NaiveDate, Utc};
+use tauri::State;
+
+use super::AppState;
+use crate::error::CommandError;
+use crate::maintenance::{self, MaintenanceDue, MaintenanceSchedule, MaintenanceScheduleInput, MaintenanceStatus};
+
+#[tauri::command]
+pub fn list_maintenance_schedules(
+    user_id: String,
+    aircraft_registration: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<MaintenanceSchedule>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(maintenance::list_schedules(&db.conn, &user_id, aircraft_registration.as_deref())?)
+}
+
+#[tauri::command]
+pub fn create_maintenance_schedule(
+    user_id: String,
+    schedule: MaintenanceScheduleInput,
+    state: State<'_, AppState>,
+) -> Result<MaintenanceSchedule, CommandError> {
+    let db = state.db.lock()?;
+    Ok(maintenance::create_schedule(&db.conn, &user_id, &schedule)?)
+}
+
+#[tauri::command]
+pub fn update_maintenance_schedule(
+    user_id: String,
+    schedule_id: String,
+    schedule: MaintenanceScheduleInput,
+    state: State<'_, AppState>,
+) -> Result<MaintenanceSchedule, CommandError> {
+    let db = state.db.lock()?;
+    Ok(maintenance::update_schedule(&db.conn, &user_id, &schedule_id, &schedule)?)
+}
+
+#[tauri::command]
+pub fn delete_maintenance_schedule(
+    user_id: String,
+    schedule_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, CommandError> {
+    let db = state.db.lock()?;
+    Ok(maintenance::delete_schedule(&db.conn, &user_id, &schedule_id)?)
+}
+
+/// Mark an item as done; hours default to the aircraft's current hours, the date to today
+#[tauri::command]
+pub fn record_maintenance_done(
+    user_id: String,
+    schedule_id: String,
+    done_hours: Option<f64>,
+    done_date: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<MaintenanceSchedule, CommandError> {
+    let db = state.db.lock()?;
+    let schedule = maintenance::get_schedule(&db.conn, &user_id, &schedule_id)?
+        .ok_or_else(|| CommandError::not_found("Maintenance schedule", &schedule_id))?;
+    let hours = match done_hours {
+        Some(hours) => hours,
+        None => maintenance::aircraft_hours(&db, &user_id)?
+            .get(&schedule.aircraft_registration)
+            .copied()
+            .unwrap_or(0.0),
+    };
+    let date = match done_date {
+        Some(date) => NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| CommandError::Validation(format!("done_date must be a YYYY-MM-DD date, got \"{}\"", date)))?,
+        None => Utc::now().date_naive(),
+    };
+    Ok(maintenance::record_done(&db.conn, &user_id, &schedule_id, hours, date)?)
+}
+
+/// Hours and days left until each item is next due, most urgent first
+///
+/// Without `aircraft_registration` every aircraft with a schedule is included.
+#[tauri::command]
+pub fn get_maintenance_due(
+    user_id: String,
+    aircraft_registration: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<MaintenanceDue>, CommandError> {
+    let db = state.db.lock()?;
+    let due = maintenance::maintenance_due(&db, &user_id, aircraft_registration.as_deref(), Utc::now().date_naive())?;
+    for item in due.iter().filter(|item| item.status == MaintenanceStatus::Overdue) {
+        tracing::warn!(
+            "[Maintenance] {} {} is overdue (hours remaining: {:?}, days remaining: {:?})",
+            item.schedule.aircraft_registration,
+            item.schedule.item,
+            item.hours_remaining,
+            item.days_remaining
+        );
+    }
+    Ok(due)
+}
@@ -0,0 +1,391 @@
+// Master flight log import
+// Loads an aggregated MasterFlightLog (extract pipeline output) straight into the flights table
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+use uuid::Uuid;
+
+use super::AppState;
+use crate::aircraft_performance::AircraftRef;
+use crate::extract::{IdentityFusion, MasterFlightLog};
+use crate::models::FlightInput;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MasterLogImportRow {
+    /// Position of the entry in the master log
+    pub index: usize,
+    pub source_page: Option<u32>,
+    pub date: Option<String>,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    /// Canonical passenger names after identity fusion
+    pub passengers: Vec<String>,
+    pub distance_nm: Option<f64>,
+    pub carbon_emissions_kg: Option<f64>,
+    /// Set once the flight is written (never on a dry run)
+    pub flight_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MasterLogImportResult {
+    pub dry_run: bool,
+    pub total_entries: usize,
+    pub imported_count: usize,
+    pub error_count: usize,
+    pub passengers_created: usize,
+    pub passenger_links: usize,
+    /// Raw names folded into another spelling by identity fusion
+    pub fused_names: HashMap<String, String>,
+    pub rows: Vec<MasterLogImportRow>,
+}
+
+/// Import an aggregated master log JSON file into the flights table
+///
+/// Passenger names go through identity fusion and are linked to existing canonical
+/// passengers where an alias already exists. Flights are stored with
+/// `data_source = 'extracted'`. A dry run goes through exactly the same writes inside a
+/// transaction that is rolled back, so its counts and errors match a real import.
+#[tauri::command]
+pub fn import_master_log(
+    user_id: String,
+    master_log_path: String,
+    dry_run: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<MasterLogImportResult, String> {
+    let dry_run = dry_run.unwrap_or(false);
+
+    let content = std::fs::read_to_string(&master_log_path)
+        .map_err(|e| format!("Failed to read master log: {}", e))?;
+    let log: MasterFlightLog = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse master log: {}", e))?;
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    import_log(&db, &user_id, &log, &master_log_path, dry_run)
+}
+
+fn import_log(
+    db: &crate::database::Database,
+    user_id: &str,
+    log: &MasterFlightLog,
+    master_log_path: &str,
+    dry_run: bool,
+) -> Result<MasterLogImportResult, String> {
+
+    // Identity fusion over every passenger name in the log
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    for entry in &log.entries {
+        for name in split_passengers(entry.passengers.as_deref()) {
+            *name_counts.entry(name).or_insert(0) += 1;
+        }
+    }
+    let mut fusion = IdentityFusion::default();
+    let counts: Vec<(String, usize)> = name_counts.into_iter().collect();
+    for candidate in fusion.analyze_names(&counts) {
+        if candidate.auto_merge {
+            fusion.apply_merge(&candidate);
+        }
+    }
+    let fused_names: HashMap<String, String> = fusion
+        .export_aliases()
+        .into_iter()
+        .filter(|(raw, canonical)| raw != canonical)
+        .collect();
+
+    let currency = crate::currency::CurrencySettings::configured(db);
+
+    // Existing aliases and canonical names resolve to their passenger record
+    let mut passenger_ids: HashMap<String, String> = HashMap::new();
+    {
+        let mut stmt = db
+            .conn
+            .prepare(
+                "SELECT UPPER(raw_name), passenger_id FROM passenger_aliases
+                 UNION ALL
+                 SELECT UPPER(canonical_name), id FROM passengers",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+        for (name, id) in rows.flatten() {
+            passenger_ids.entry(name).or_insert(id);
+        }
+    }
+
+    let mut result = MasterLogImportResult {
+        dry_run,
+        total_entries: log.entries.len(),
+        imported_count: 0,
+        error_count: 0,
+        passengers_created: 0,
+        passenger_links: 0,
+        fused_names: fused_names.clone(),
+        rows: Vec::new(),
+    };
+
+    // Dropped without commit on an error or a dry run, which rolls everything back
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let mut import_entries = || -> Result<(), String> {
+        for (index, entry) in log.entries.iter().enumerate() {
+            let departure = entry.from.as_deref().unwrap_or("").trim().to_uppercase();
+            let arrival = entry.to.as_deref().unwrap_or("").trim().to_uppercase();
+            // (raw name, canonical name) pairs
+            let names: Vec<(String, String)> = split_passengers(entry.passengers.as_deref())
+                .into_iter()
+                .map(|raw| {
+                    let canonical = fused_names.get(&raw).cloned().unwrap_or_else(|| raw.clone());
+                    (raw, canonical)
+                })
+                .collect();
+            let mut canonical_names: Vec<String> = Vec::new();
+            for (_, canonical) in &names {
+                if !canonical_names.contains(canonical) {
+                    canonical_names.push(canonical.clone());
+                }
+            }
+
+            let mut row = MasterLogImportRow {
+                index,
+                source_page: entry.source_page,
+                date: entry.date.clone(),
+                departure_airport: departure.clone(),
+                arrival_airport: arrival.clone(),
+                passengers: canonical_names,
+                distance_nm: None,
+                carbon_emissions_kg: None,
+                flight_id: None,
+                error: None,
+            };
+
+            let date = entry
+                .date
+                .as_deref()
+                .and_then(|d| chrono::NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok());
+            let problem = if date.is_none() {
+                Some(format!("Invalid or missing date: {}", entry.date.as_deref().unwrap_or("")))
+            } else if departure.is_empty() || arrival.is_empty() {
+                Some("Missing departure or arrival airport".to_string())
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                row.error = Some(problem);
+                result.error_count += 1;
+                result.rows.push(row);
+                continue;
+            }
+
+            let registration = entry
+                .aircraft_registration
+                .as_deref()
+                .map(str::trim)
+                .filter(|r| !r.is_empty());
+            let performance = crate::aircraft_performance::performance_for(db, AircraftRef::named(registration));
+            let distance_km = crate::geo::calculate_airport_distance(&departure, &arrival).map(|(nm, km)| {
+                row.distance_nm = Some(nm);
+                row.carbon_emissions_kg = Some(performance.co2_emissions(km));
+                km
+            });
+
+            let mut notes_parts = Vec::new();
+            if !row.passengers.is_empty() {
+                notes_parts.push(format!("Passengers: {}", row.passengers.join("; ")));
+            }
+            if let Some(page) = entry.source_page {
+                notes_parts.push(format!("Source page: {}", page));
+            }
+
+            let flight = FlightInput {
+                flight_number: entry.flight_number.clone().filter(|n| !n.trim().is_empty()),
+                departure_airport: departure,
+                arrival_airport: arrival,
+                departure_datetime: format!("{}T00:00:00", date.unwrap_or_default().format("%Y-%m-%d")),
+                arrival_datetime: None,
+                aircraft_type_id: None,
+                aircraft_registration: registration.map(str::to_string),
+                total_duration: None,
+                flight_duration: None,
+                distance_nm: row.distance_nm,
+                distance_km: None, // Will be calculated from distance_nm in database layer
+                carbon_emissions_kg: row.carbon_emissions_kg,
+                booking_reference: None,
+                ticket_number: None,
+                seat_number: None,
+                fare_class: None,
+                base_fare: None,
+                taxes: None,
+                total_cost: None,
+                currency: None,
+                notes: if notes_parts.is_empty() { None } else { Some(notes_parts.join("\n")) },
+                attachment_path: None,
+                passenger_count: (!row.passengers.is_empty()).then_some(row.passengers.len() as i32),
+            };
+
+            let flight_id = match db.create_flight_with_currency(user_id, &flight, "extracted", &currency) {
+                Ok(id) => id,
+                Err(e) => {
+                    row.error = Some(e.to_string());
+                    result.error_count += 1;
+                    result.rows.push(row);
+                    continue;
+                }
+            };
+
+            if let (Some(km), false) = (distance_km, row.passengers.is_empty()) {
+                // Same performance as the flight's total, so the shares add up to it
+                let per_passenger =
+                    crate::calculations::per_passenger_co2(&performance, km, Some(row.passengers.len() as u32));
+                db.conn
+                    .execute(
+                        "UPDATE flights SET per_passenger_co2_kg = ?1, co2_split_method = ?2 WHERE id = ?3",
+                        params![per_passenger, crate::calculations::Co2SplitMethod::Equal.as_str(), flight_id],
+                    )
+                    .map_err(|e| format!("Failed to store per-passenger CO2 for {}: {}", flight_id, e))?;
+            }
+
+            // Link passengers, creating canonical records and aliases as needed
+            for (raw, canonical) in &names {
+                let passenger_id = match passenger_ids
+                    .get(raw)
+                    .or_else(|| passenger_ids.get(canonical))
+                    .cloned()
+                {
+                    Some(id) => id,
+                    None => {
+                        let id = create_passenger(&db.conn, canonical)?;
+                        passenger_ids.insert(canonical.clone(), id.clone());
+                        result.passengers_created += 1;
+                        id
+                    }
+                };
+
+                if !passenger_ids.contains_key(raw) {
+                    db.conn
+                        .execute(
+                            "INSERT OR IGNORE INTO passenger_aliases (id, passenger_id, raw_name, usage_count, source_document, match_type, confidence, created_at)
+                             VALUES (?1, ?2, ?3, 1, ?4, 'fuzzy', 0.95, datetime('now'))",
+                            params![Uuid::new_v4().to_string(), passenger_id, raw, master_log_path],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    passenger_ids.insert(raw.clone(), passenger_id.clone());
+                }
+
+                let linked = db
+                    .conn
+                    .execute(
+                        "INSERT OR IGNORE INTO flight_passengers (flight_id, passenger_id, created_at)
+                         VALUES (?1, ?2, datetime('now'))",
+                        params![flight_id, passenger_id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                if linked > 0 {
+                    result.passenger_links += 1;
+                    db.conn
+                        .execute(
+                            "UPDATE passengers SET total_flights = COALESCE(total_flights, 0) + 1, updated_at = datetime('now')
+                             WHERE id = ?1",
+                            params![passenger_id],
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+
+            row.flight_id = (!dry_run).then_some(flight_id);
+            result.imported_count += 1;
+            result.rows.push(row);
+        }
+        Ok(())
+    };
+
+    import_entries()?;
+    if !dry_run {
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    Ok(result)
+}
+
+/// Split a semicolon/comma separated passenger list into normalized names
+fn split_passengers(passengers: Option<&str>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in passengers.unwrap_or("").split([';', ',', '|']) {
+        let trimmed = name.trim().to_uppercase();
+        if trimmed.len() > 1 && !names.contains(&trimmed) {
+            names.push(trimmed);
+        }
+    }
+    names
+}
+
+/// Find a passenger by canonical name or create it with a matching exact alias
+fn create_passenger(conn: &rusqlite::Connection, canonical_name: &str) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM passengers WHERE canonical_name = ?1",
+            params![canonical_name],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO passengers (id, canonical_name, total_flights, created_at, updated_at)
+         VALUES (?1, ?2, 0, datetime('now'), datetime('now'))",
+        params![id, canonical_name],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR IGNORE INTO passenger_aliases (id, passenger_id, raw_name, usage_count, match_type, confidence, created_at)
+         VALUES (?1, ?2, ?3, 0, 'exact', 1.0, datetime('now'))",
+        params![Uuid::new_v4().to_string(), id, canonical_name],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_dry_run_matches_import() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn.execute("INSERT INTO users (id, name) VALUES ('u1', 'Pilot')", []).unwrap();
+        let log: MasterFlightLog = serde_json::from_value(serde_json::json!({
+            "total_entries": 3,
+            "pages_processed": 1,
+            "pages_with_errors": 0,
+            "unique_aircraft": [],
+            "unique_airports": [],
+            "date_range": null,
+            "entries": [
+                {"date": "1995-07-03", "from": "TEB", "to": "PBI", "passengers": "J. Smith; A. Jones", "source_page": 1},
+                {"date": "1995-07-05", "from": "PBI", "to": "TEB", "passengers": "J. Smith", "source_page": 1},
+                {"date": "not a date", "from": "PBI", "to": "TEB", "source_page": 1}
+            ],
+            "processing_errors": []
+        }))
+        .unwrap();
+        let flight_count = || -> i64 { db.conn.query_row("SELECT COUNT(*) FROM flights", [], |row| row.get(0)).unwrap() };
+
+        let dry = import_log(&db, "u1", &log, "log.json", true).unwrap();
+        assert_eq!(flight_count(), 0);
+        assert!(dry.rows.iter().all(|row| row.flight_id.is_none()));
+
+        let real = import_log(&db, "u1", &log, "log.json", false).unwrap();
+        assert_eq!(flight_count(), 2);
+        let counts = |r: &MasterLogImportResult| (r.imported_count, r.error_count, r.passengers_created, r.passenger_links);
+        assert_eq!(counts(&dry), counts(&real));
+        assert_eq!(counts(&real), (2, 1, 2, 3));
+    }
+}
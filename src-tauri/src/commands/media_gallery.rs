@@ -78,6 +78,7 @@ fn get_media_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(media_dir)
 }
 
+#[allow(dead_code)]
 fn get_thumbnails_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     let app_dir = app_handle
         .path()
@@ -284,7 +285,45 @@ pub fn list_media_files(
     // Ensure table exists
     let _ = init_media_table(&db.conn);
 
-    let filter = filter.unwrap_or(MediaFilter {
+    query_media_files(&db.conn, user_id, &filter.unwrap_or_else(default_media_filter))
+}
+
+/// Like list_media_files, plus the total matching count and whether more pages follow
+#[tauri::command]
+pub fn list_media_files_page(
+    user_id: String,
+    filter: Option<MediaFilter>,
+    state: State<'_, AppState>,
+) -> Result<crate::models::Page<MediaFile>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let _ = init_media_table(&db.conn);
+
+    let filter = filter.unwrap_or_else(default_media_filter);
+    let tx = db.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let items = query_media_files(&db.conn, user_id.clone(), &filter)?;
+    let (where_clause, params) = media_conditions(user_id, &filter);
+    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    let total: i64 = db
+        .conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM media_files WHERE {}", where_clause),
+            param_refs.as_slice(),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(crate::models::Page::new(
+        items,
+        total,
+        filter.limit.unwrap_or(100) as i64,
+        filter.offset.unwrap_or(0) as i64,
+    ))
+}
+
+fn default_media_filter() -> MediaFilter {
+    MediaFilter {
         file_type: None,
         flight_id: None,
         journey_id: None,
@@ -292,34 +331,43 @@ pub fn list_media_files(
         search: None,
         limit: Some(100),
         offset: Some(0),
-    });
+    }
+}
 
+/// WHERE clause and its parameters for a media filter (limit/offset not included)
+fn media_conditions(user_id: String, filter: &MediaFilter) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
     let mut conditions = vec!["user_id = ?1".to_string()];
-    let mut param_idx = 2;
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
 
-    if filter.file_type.is_some() {
-        conditions.push(format!("file_type = ?{}", param_idx));
-        param_idx += 1;
+    if let Some(ft) = &filter.file_type {
+        params.push(Box::new(ft.clone()));
+        conditions.push(format!("file_type = ?{}", params.len()));
     }
-    if filter.flight_id.is_some() {
-        conditions.push(format!("flight_id = ?{}", param_idx));
-        param_idx += 1;
+    if let Some(fid) = &filter.flight_id {
+        params.push(Box::new(fid.clone()));
+        conditions.push(format!("flight_id = ?{}", params.len()));
     }
-    if filter.journey_id.is_some() {
-        conditions.push(format!("journey_id = ?{}", param_idx));
-        param_idx += 1;
+    if let Some(jid) = &filter.journey_id {
+        params.push(Box::new(jid.clone()));
+        conditions.push(format!("journey_id = ?{}", params.len()));
     }
     if filter.favorites_only == Some(true) {
         conditions.push("is_favorite = 1".to_string());
     }
-    if filter.search.is_some() {
+    if let Some(s) = &filter.search {
+        params.push(Box::new(format!("%{}%", s)));
+        let idx = params.len();
         conditions.push(format!(
             "(title LIKE ?{} OR description LIKE ?{} OR original_filename LIKE ?{})",
-            param_idx, param_idx, param_idx
+            idx, idx, idx
         ));
     }
 
-    let where_clause = conditions.join(" AND ");
+    (conditions.join(" AND "), params)
+}
+
+fn query_media_files(conn: &rusqlite::Connection, user_id: String, filter: &MediaFilter) -> Result<Vec<MediaFile>, String> {
+    let (where_clause, params) = media_conditions(user_id, filter);
     let limit = filter.limit.unwrap_or(100);
     let offset = filter.offset.unwrap_or(0);
 
@@ -334,25 +382,7 @@ pub fn list_media_files(
         where_clause, limit, offset
     );
 
-    let mut stmt = db.conn.prepare(&query).map_err(|e| e.to_string())?;
-
-    // Build params dynamically
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
-
-    if let Some(ft) = &filter.file_type {
-        params.push(Box::new(ft.clone()));
-    }
-    if let Some(fid) = &filter.flight_id {
-        params.push(Box::new(fid.clone()));
-    }
-    if let Some(jid) = &filter.journey_id {
-        params.push(Box::new(jid.clone()));
-    }
-    if let Some(s) = &filter.search {
-        let pattern = format!("%{}%", s);
-        params.push(Box::new(pattern));
-    }
-
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
     let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
     let files = stmt
@@ -445,6 +475,7 @@ pub fn get_media_file_path(
 
 /// Update media file metadata
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn update_media_file(
     file_id: String,
     title: Option<String>,
@@ -2,12 +2,15 @@
 // This module structure improves maintainability by grouping related commands
 
 use crate::database::Database;
+use crate::write_queue::WriteQueue;
 use std::sync::Mutex;
 use tauri::State;
 
 // Shared application state
 pub struct AppState {
     pub db: Mutex<Database>,
+    /// Prioritised writer for writes that must not wait behind long imports
+    pub writer: WriteQueue,
 }
 
 // Module declarations
@@ -16,6 +19,8 @@ pub mod settings;
 pub mod users;
 pub mod flights;
 pub mod csv_import;
+pub mod master_log_import;
+pub mod eml_import;
 pub mod statistics;
 pub mod ocr;
 pub mod data_management;
@@ -24,6 +29,7 @@ pub mod passengers;
 pub mod research;
 pub mod journeys;
 pub mod pilot_logbook;
+pub mod pilot_credentials;
 pub mod ffp;
 pub mod airports;
 pub mod airport_enrichment;
@@ -32,6 +38,12 @@ pub mod documents;
 pub mod fuel_prices;
 pub mod fuel_commands;
 pub mod analytics;
+pub mod query_advisor;
+pub mod comparison;
+pub mod digest;
+pub mod reports;
+pub mod self_test;
+pub mod introspection;
 pub mod network_scanner;
 pub mod network_sentinel;
 pub mod agent_memory_commands;
@@ -41,12 +53,20 @@ pub mod self_improvement;
 pub mod workflow;
 pub mod identity_fusion;
 pub mod data_editor;
+pub mod flight_tags;
+pub mod trip_purpose;
+pub mod flight_templates;
+pub mod flight_card;
 pub mod media_gallery;
 pub mod batch_calculations;
 pub mod active_defense;
 pub mod network_tools;
 pub mod donation;
 pub mod ai_models;
+pub mod backups;
+pub mod logs;
+pub mod maintenance;
+pub mod operations;
 
 // Re-export all commands for easy registration
 pub use calculations::*;
@@ -54,6 +74,8 @@ pub use settings::*;
 pub use users::*;
 pub use flights::*;
 pub use csv_import::*;
+pub use master_log_import::*;
+pub use eml_import::*;
 pub use statistics::*;
 pub use ocr::*;
 pub use data_management::*;
@@ -62,6 +84,7 @@ pub use passengers::*;
 pub use research::*;
 pub use journeys::*;
 pub use pilot_logbook::*;
+pub use pilot_credentials::*;
 pub use ffp::*;
 pub use airports::*;
 pub use airport_enrichment::*;
@@ -70,6 +93,12 @@ pub use documents::*;
 pub use fuel_prices::*;
 pub use fuel_commands::*;
 pub use analytics::*;
+pub use query_advisor::*;
+pub use comparison::*;
+pub use digest::*;
+pub use reports::*;
+pub use self_test::*;
+pub use introspection::*;
 pub use network_scanner::*;
 pub use network_sentinel::*;
 pub use agent_memory_commands::*;
@@ -79,12 +108,20 @@ pub use self_improvement::*;
 pub use workflow::*;
 pub use identity_fusion::*;
 pub use data_editor::*;
+pub use flight_tags::*;
+pub use trip_purpose::*;
+pub use flight_templates::*;
+pub use flight_card::*;
 pub use media_gallery::*;
 pub use batch_calculations::*;
 pub use active_defense::*;
 pub use network_tools::*;
 pub use donation::*;
 pub use ai_models::*;
+pub use backups::*;
+pub use logs::*;
+pub use maintenance::*;
+pub use operations::*;
 
 // ===== INITIALIZATION COMMAND =====
 
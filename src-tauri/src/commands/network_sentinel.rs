@@ -1,9 +1,25 @@
 // Network Sentinel Commands
 // Provides Tauri commands to interface with the network-sentinel daemon via D-Bus
 // Cross-platform support: Linux (D-Bus), macOS (lsof/netstat), Windows (netstat)
+// Flow history is sampled into the database on Linux (see network_history.rs)
 
 use serde::{Deserialize, Serialize};
 use std::process::Command;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::airport_enrichment::nearest_cached_airport;
+use super::AppState;
+use crate::location_change::{self, KnownLocation, LocationChange, LocationChangeSettings, LocationState, NearbyAirport};
+use crate::network_history::{self, FlowFilter, NetworkHistorySettings, NetworkHistorySummary};
+
+/// Most connections a history query returns
+const HISTORY_QUERY_LIMIT: u32 = 1000;
+/// Entries in each history summary ranking
+const HISTORY_SUMMARY_LIMIT: u32 = 10;
+/// How often the sampler applies the network_flows retention policy
+const HISTORY_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+/// Gives the window time to load and listen before the first background location check
+const LOCATION_CHECK_STARTUP_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
 
 /// Network flow data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -552,67 +568,246 @@ pub async fn get_network_anomalies(limit: u32) -> Result<Vec<NetworkFlow>, Strin
     }
 }
 
-/// Time-travel query: get flows for a specific time period
-/// Note: Historical queries require the network-sentinel daemon which stores flow history.
-/// On non-Linux platforms, returns mock data.
+/// Anomalies reported by a running network-sentinel daemon
+///
+/// Unlike `get_network_anomalies` this never falls back to mock data: None when the daemon is
+/// not running (or not on Linux) or its reply cannot be decoded as a list of flows.
+pub(crate) async fn sentinel_anomalies(limit: u32) -> Option<Vec<NetworkFlow>> {
+    #[cfg(target_os = "linux")]
+    {
+        let output = Command::new("busctl")
+            .args([
+                "call",
+                "--json=short",
+                "org.jesternet.network.Sentinel",
+                "/org/jesternet/network/Sentinel",
+                "org.jesternet.network.Sentinel",
+                "GetAnomalies",
+                "u",
+                &limit.to_string(),
+            ])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        decode_sentinel_flows(&String::from_utf8_lossy(&output.stdout))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = limit;
+        None
+    }
+}
+
+/// Flows from a `busctl --json=short` reply: {"type":"s","data":["[...flows as JSON...]"]}
+fn decode_sentinel_flows(reply: &str) -> Option<Vec<NetworkFlow>> {
+    let value: serde_json::Value = serde_json::from_str(reply.trim()).ok()?;
+    let payload = value.get("data")?.as_array()?.first()?;
+    match payload.as_str() {
+        Some(json) => serde_json::from_str(json).ok(),
+        None => serde_json::from_value(payload.clone()).ok(),
+    }
+}
+
+/// Local `date` (YYYY-MM-DD) and `time` (HH:MM) in the UTC form network_flows stores
+fn history_timestamp(date: &str, time: &str, seconds: u32) -> Result<String, String> {
+    use chrono::TimeZone;
+    let naive = chrono::NaiveDateTime::parse_from_str(&format!("{} {}:{:02}", date, time, seconds), "%Y-%m-%d %H:%M:%S")
+        .map_err(|_| format!("Invalid date and time: {} {}", date, time))?;
+    let local = chrono::Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("{} {} does not exist in the local time zone", date, time))?;
+    Ok(local.with_timezone(&chrono::Utc).format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn history_filter(
+    date: &str,
+    start_time: &str,
+    end_time: &str,
+    process_filter: Option<String>,
+    ip_filter: Option<String>,
+    port_filter: Option<u16>,
+) -> Result<FlowFilter, String> {
+    let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    Ok(FlowFilter {
+        start: Some(history_timestamp(date, start_time, 0)?),
+        end: Some(history_timestamp(date, end_time, 59)?),
+        ip: non_empty(ip_filter),
+        port: port_filter,
+        process: non_empty(process_filter),
+    })
+}
+
+/// Time-travel query: connections recorded on `date` between `start_time` and `end_time`
+/// (local HH:MM), optionally only those of one process or with an IP or port at either end.
+/// Empty until the history sampler has recorded something.
 #[tauri::command]
 pub async fn query_network_history(
     date: String,
     start_time: String,
     end_time: String,
     process_filter: Option<String>,
+    ip_filter: Option<String>,
+    port_filter: Option<u16>,
+    state: State<'_, AppState>,
 ) -> Result<Vec<NetworkFlow>, String> {
-    #[cfg(target_os = "linux")]
-    {
-        let start_datetime = format!("{}T{}:00", date, start_time);
-        let end_datetime = format!("{}T{}:00", date, end_time);
-
-        let args = vec![
-            "call".to_string(),
-            "org.jesternet.network.Sentinel".to_string(),
-            "/org/jesternet/network/Sentinel".to_string(),
-            "org.jesternet.network.Sentinel".to_string(),
-            "QueryHistory".to_string(),
-            "sss".to_string(),
-            start_datetime,
-            end_datetime,
-            process_filter.unwrap_or_default(),
-        ];
+    let filter = history_filter(&date, &start_time, &end_time, process_filter, ip_filter, port_filter)?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    network_history::query_flows(&db.conn, &filter, HISTORY_QUERY_LIMIT).map_err(|e| e.to_string())
+}
 
-        let output = Command::new("busctl")
-            .args(&args)
-            .output();
+/// Top talkers and bytes per process over the same period and filters as query_network_history
+#[tauri::command]
+pub async fn get_network_history_summary(
+    date: String,
+    start_time: String,
+    end_time: String,
+    process_filter: Option<String>,
+    ip_filter: Option<String>,
+    port_filter: Option<u16>,
+    state: State<'_, AppState>,
+) -> Result<NetworkHistorySummary, String> {
+    let filter = history_filter(&date, &start_time, &end_time, process_filter, ip_filter, port_filter)?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    network_history::summarize(&db.conn, &filter, HISTORY_SUMMARY_LIMIT).map_err(|e| e.to_string())
+}
 
-        match output {
-            Ok(result) => {
-                if result.status.success() {
-                    let stdout = String::from_utf8_lossy(&result.stdout);
-                    parse_flows_response(&stdout)
-                } else {
-                    Ok(mock_flows(25))
+/// Every current connection with its process and byte counters, for the history sampler.
+/// Unlike get_network_flows this never falls back to demo data.
+#[cfg(target_os = "linux")]
+fn sample_flows() -> Result<Vec<NetworkFlow>, String> {
+    let output = Command::new("ss")
+        .args(["-tunapi"])
+        .output()
+        .map_err(|e| format!("Failed to run ss: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ss failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_ss_sample(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sample_flows() -> Result<Vec<NetworkFlow>, String> {
+    Err("Network history sampling uses ss and is only available on Linux".to_string())
+}
+
+/// Connected sockets from `ss -tunapi`; -i puts each TCP socket's byte counters on an
+/// indented line after it
+#[cfg(target_os = "linux")]
+fn parse_ss_sample(output: &str) -> Vec<NetworkFlow> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut flows: Vec<NetworkFlow> = Vec::new();
+    // Whether the last socket line was kept, so its info line is not applied to another
+    let mut kept = false;
+
+    for line in output.lines().skip(1) {
+        if line.starts_with(char::is_whitespace) {
+            if let (true, Some(flow)) = (kept, flows.last_mut()) {
+                for token in line.split_whitespace() {
+                    if let Some(bytes) = token.strip_prefix("bytes_sent:") {
+                        flow.bytes_sent = bytes.parse().unwrap_or(0);
+                    } else if let Some(bytes) = token.strip_prefix("bytes_received:") {
+                        flow.bytes_recv = bytes.parse().unwrap_or(0);
+                    }
                 }
             }
-            Err(_) => Ok(mock_flows(25)),
+            continue;
         }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // Sockets without a peer (unconnected UDP) are not flows
+        kept = parts.len() >= 6 && !parts[5].ends_with('*');
+        if !kept {
+            continue;
+        }
+        let (local_addr, local_port) = parse_addr_port(parts[4]);
+        let (remote_addr, remote_port) = parse_addr_port(parts[5]);
+        // users:(("firefox",pid=1234,fd=88))
+        let users = line.find("users:((\"").map(|i| &line[i + 9..]);
+        let process_name = users
+            .and_then(|u| u.find('"').map(|end| u[..end].to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let pid = users
+            .and_then(|u| u.find("pid=").map(|i| &u[i + 4..]))
+            .and_then(|p| p.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok())
+            .unwrap_or(0);
+
+        flows.push(NetworkFlow {
+            id: flows.len() as i64,
+            process_name,
+            pid,
+            local_addr,
+            local_port,
+            remote_addr,
+            remote_port,
+            protocol: parts[0].to_uppercase(),
+            direction: "outbound".to_string(),
+            bytes_sent: 0,
+            bytes_recv: 0,
+            timestamp: now.clone(),
+            geo_country: None,
+            geo_city: None,
+            geo_asn: None,
+            is_anomaly: false,
+            anomaly_reason: None,
+        });
     }
-    #[cfg(not(target_os = "linux"))]
-    {
-        // Historical queries require the network-sentinel daemon (Linux-only)
-        let _ = (date, start_time, end_time, process_filter); // Suppress unused warnings
-        Ok(mock_flows(25))
+    flows
+}
+
+/// Record one sample if history is on, and apply the network_flows retention policy when
+/// `prune` is set (whether or not recording is on)
+fn sample_network_history(app_handle: &AppHandle, prune: bool) -> anyhow::Result<NetworkHistorySettings> {
+    let state = app_handle.state::<AppState>();
+    let lock = || state.db.lock().map_err(|e| anyhow::anyhow!("Database lock poisoned: {}", e));
+    let settings = {
+        let db = lock()?;
+        if prune {
+            crate::retention::run_table(&db, "network_flows")?;
+        }
+        NetworkHistorySettings::configured(&db)
+    };
+    if settings.enabled {
+        // ss runs without the database locked
+        let flows = sample_flows().map_err(anyhow::Error::msg)?;
+        let sampled_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        network_history::record_sample(&lock()?.conn, &sampled_at, &flows)?;
+    }
+    Ok(settings)
+}
+
+/// Background flow sampler: records every "network_history_interval_secs" while
+/// "network_history_enabled" is on, and keeps network_flows within its retention policy
+pub(crate) async fn run_network_history_sampler(app_handle: AppHandle) {
+    let mut last_pruned: Option<std::time::Instant> = None;
+    loop {
+        let prune = last_pruned.is_none_or(|at| at.elapsed() >= HISTORY_PRUNE_INTERVAL);
+        let interval_secs = match sample_network_history(&app_handle, prune) {
+            Ok(settings) => {
+                if prune {
+                    last_pruned = Some(std::time::Instant::now());
+                }
+                settings.interval_secs
+            }
+            Err(e) => {
+                tracing::warn!("Network history sample failed: {:#}", e);
+                network_history::DEFAULT_INTERVAL_SECS
+            }
+        };
+        tokio::time::sleep(std::time::Duration::from_secs(interval_secs)).await;
     }
 }
 
 // Helper functions
 
-fn parse_stats_response(response: &str) -> Result<NetworkStats, String> {
+fn parse_stats_response(_response: &str) -> Result<NetworkStats, String> {
     // Parse D-Bus response format
     // For now, return mock data as parsing D-Bus output is complex
     // In production, we'd use a proper D-Bus library like zbus
     Ok(mock_stats())
 }
 
-fn parse_flows_response(response: &str) -> Result<Vec<NetworkFlow>, String> {
+fn parse_flows_response(_response: &str) -> Result<Vec<NetworkFlow>, String> {
     // Parse D-Bus response format
     // For now, return mock data
     Ok(mock_flows(50))
@@ -699,9 +894,10 @@ pub struct GeoLocation {
     pub ip: Option<String>,
 }
 
-/// Detect user's location from their public IP using free geolocation API
+/// Detect user's location from their public IP using free geolocation API; None when the
+/// response has no coordinates
 #[tauri::command]
-pub async fn detect_location_from_ip() -> Result<GeoLocation, String> {
+pub async fn detect_location_from_ip() -> Result<Option<GeoLocation>, String> {
     // Use ip-api.com (free, no API key required, 45 requests/minute limit)
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(10))
@@ -729,14 +925,128 @@ pub async fn detect_location_from_ip() -> Result<GeoLocation, String> {
         return Err(format!("Geolocation failed: {}", message));
     }
 
-    Ok(GeoLocation {
-        lat: json.get("lat").and_then(|v| v.as_f64()).unwrap_or(0.0),
-        lng: json.get("lon").and_then(|v| v.as_f64()).unwrap_or(0.0),
+    // A response without coordinates is no location, not 0,0 in the Gulf of Guinea
+    let (Some(lat), Some(lng)) = (json.get("lat").and_then(|v| v.as_f64()), json.get("lon").and_then(|v| v.as_f64())) else {
+        return Ok(None);
+    };
+    Ok(Some(GeoLocation {
+        lat,
+        lng,
         city: json.get("city").and_then(|v| v.as_str()).map(String::from),
         country: json.get("country").and_then(|v| v.as_str()).map(String::from),
         country_code: json.get("countryCode").and_then(|v| v.as_str()).map(String::from),
         region: json.get("regionName").and_then(|v| v.as_str()).map(String::from),
         timezone: json.get("timezone").and_then(|v| v.as_str()).map(String::from),
         ip: json.get("query").and_then(|v| v.as_str()).map(String::from),
+    }))
+}
+
+fn nearby_airport(location: &KnownLocation) -> Option<NearbyAirport> {
+    let (airport, distance_km) = nearest_cached_airport(location.lat, location.lng)?;
+    Some(NearbyAirport {
+        code: airport.iata_code.unwrap_or(airport.ident),
+        name: airport.name,
+        distance_km,
     })
 }
+
+/// Fold the public IP's current location into the location change state. A change it
+/// confirms gets the nearest airports at both ends and is emitted as "location:changed".
+async fn sample_location(app_handle: &AppHandle) -> Result<Option<LocationChange>, String> {
+    let Some(location) = detect_location_from_ip().await? else {
+        return Ok(None);
+    };
+    let sample = KnownLocation {
+        lat: location.lat,
+        lng: location.lng,
+        city: location.city,
+        country: location.country,
+        seen_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let change = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let settings = LocationChangeSettings::configured(&db);
+        location_change::record_sample(&db, sample, &settings).map_err(|e| e.to_string())?
+    };
+    let Some(mut change) = change else {
+        return Ok(None);
+    };
+    change.from_airport = nearby_airport(&change.from);
+    change.to_airport = nearby_airport(&change.to);
+    if let Err(e) = app_handle.emit(location_change::CHANGE_EVENT, &change) {
+        tracing::warn!("Failed to emit location change: {}", e);
+    }
+    Ok(Some(change))
+}
+
+/// Sample the location now, whether or not background checks are on; returns the change it
+/// confirms, if any
+#[tauri::command]
+pub async fn check_location_change(app_handle: AppHandle) -> Result<Option<LocationChange>, String> {
+    sample_location(&app_handle).await
+}
+
+/// The last known location and any move still waiting for confirmation
+#[tauri::command]
+pub fn get_location_change_state(state: State<'_, AppState>) -> Result<LocationState, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(location_change::load_state(&db))
+}
+
+/// Background location check, shortly after startup and then every
+/// "location_change_interval_minutes" while "location_change_enabled" is on
+pub(crate) async fn run_location_change_checks(app_handle: AppHandle) {
+    tokio::time::sleep(LOCATION_CHECK_STARTUP_DELAY).await;
+    loop {
+        let settings = {
+            let state = app_handle.state::<AppState>();
+            let Ok(db) = state.db.lock() else {
+                tracing::warn!("Location change checks stopped: database lock poisoned");
+                return;
+            };
+            LocationChangeSettings::configured(&db)
+        };
+        if settings.enabled {
+            if let Err(e) = sample_location(&app_handle).await {
+                tracing::warn!("Location change check failed: {}", e);
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(settings.interval_minutes * 60)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_ss_sample() {
+        let output = "Netid State  Recv-Q Send-Q  Local Address:Port   Peer Address:Port Process
+udp   UNCONN 0      0       0.0.0.0:5353         0.0.0.0:*     users:((\"avahi-daemon\",pid=700,fd=12))
+tcp   ESTAB  0      0       192.168.1.5:50000    1.1.1.1:443   users:((\"firefox\",pid=4242,fd=88),(\"firefox\",pid=4242,fd=90))
+\t cubic wscale:7,7 rto:228 bytes_sent:1200 bytes_acked:1201 bytes_received:98000 segs_out:40
+tcp   ESTAB  0      0       [::1]:41000          [::1]:8080
+\t cubic bytes_sent:10 bytes_received:20
+";
+        let flows = parse_ss_sample(output);
+        let parsed: Vec<_> = flows
+            .iter()
+            .map(|f| (f.process_name.as_str(), f.pid, f.remote_addr.as_str(), f.remote_port, f.bytes_sent, f.bytes_recv))
+            .collect();
+        assert_eq!(parsed, [("firefox", 4242, "1.1.1.1", 443, 1200, 98000), ("unknown", 0, "::1", 8080, 10, 20)]);
+    }
+
+    #[test]
+    fn test_decode_sentinel_flows() {
+        let flow = serde_json::to_string(&mock_anomalies(1)).unwrap();
+        let reply = serde_json::json!({ "type": "s", "data": [flow] }).to_string();
+        let flows = decode_sentinel_flows(&reply).unwrap();
+        assert_eq!(flows.len(), 1);
+        assert!(flows[0].is_anomaly);
+
+        assert!(decode_sentinel_flows("s \"not json\"").is_none());
+        assert!(decode_sentinel_flows(r#"{"type":"s","data":["garbage"]}"#).is_none());
+    }
+}
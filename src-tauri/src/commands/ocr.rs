@@ -1,8 +1,14 @@
 // OCR and boarding pass analysis commands
 use tauri::{State, AppHandle, Manager};
 use super::AppState;
+use crate::aircraft_performance::{performance_for, AircraftRef};
+use crate::currency::CurrencySettings;
+use crate::error::CommandError;
+use crate::locale::Locale;
 use crate::ocr;
 use crate::ocr_learning;
+use crate::ocr_preprocess::{self, PreprocessOptions};
+use crate::operations::{OperationHandle, OperationRegistry};
 
 fn get_api_key(
     env_vars: &[&str],
@@ -30,78 +36,390 @@ fn get_api_key(
         })
 }
 
+/// `min_confidence` if given, else the "ocr_min_confidence" setting; None disables review flagging
+fn resolve_min_confidence(min_confidence: Option<f32>, state: &State<'_, AppState>) -> Result<Option<f32>, String> {
+    let min_confidence = match min_confidence {
+        Some(value) => Some(value),
+        None => {
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            db.get_setting("ocr_min_confidence")
+                .map_err(|e| e.to_string())?
+                .and_then(|v| v.trim().parse::<f32>().ok())
+        }
+    };
+    match min_confidence {
+        Some(value) if !(0.0..=1.0).contains(&value) => {
+            Err(format!("Minimum confidence must be between 0 and 1, got {}", value))
+        }
+        other => Ok(other),
+    }
+}
+
+/// Gemini API key, model, document locale and image pre-processing for an OCR run
+struct OcrSettings {
+    api_key: String,
+    model: &'static str,
+    locale: Locale,
+    preprocess: PreprocessOptions,
+}
+
+/// OCR settings with the preferred model (lite vs standard)
+fn ocr_settings(state: &State<'_, AppState>) -> Result<OcrSettings, String> {
+    // Get Gemini API key from environment or settings (try GENAI_API_KEY, GOOGLE_GENAI_API_KEY, or GEMINI_API_KEY)
+    let api_key = get_api_key(
+        &["GENAI_API_KEY", "GOOGLE_GENAI_API_KEY", "GEMINI_API_KEY"],
+        "gemini_api_key",
+        state,
+    )?;
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let use_lite_model = db
+        .get_setting("use_gemini_lite")
+        .map_err(|e| e.to_string())?
+        .unwrap_or_else(|| "true".to_string())
+        == "true";
+    Ok(OcrSettings {
+        api_key,
+        model: ocr::model_for(use_lite_model),
+        locale: Locale::configured(&db),
+        preprocess: PreprocessOptions::from_settings(&db),
+    })
+}
+
 #[tauri::command]
 pub async fn analyze_boarding_pass(
     file_path: String,
+    user_id: Option<String>,
+    min_confidence: Option<f32>,
     state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ocr::OcrFlightResult, String> {
+    let settings = ocr_settings(&state)?;
+    analyze_single(file_path, None, user_id, min_confidence, settings, &state, &app_handle).await
+}
+
+#[tauri::command]
+pub async fn batch_analyze_boarding_passes(
+    file_paths: Vec<String>,
+    user_id: Option<String>,
+    min_confidence: Option<f32>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ocr::BatchOcrResults, String> {
+    let settings = ocr_settings(&state)?;
+    let min_confidence = resolve_min_confidence(min_confidence, &state)?;
+    let items: Vec<ReanalyzeItem> = file_paths
+        .into_iter()
+        .map(|file_path| ReanalyzeItem { file_path, previous: None })
+        .collect();
+    let operation = start_batch(&items, operation_id, &app_handle)?;
+    analyze_batch(items, user_id, min_confidence, settings, &state, operation, &app_handle).await
+}
+
+/// A file to re-run, with the result it produced before so its confident fields are kept
+/// Batch items from the needs_review bucket deserialize into this directly.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ReanalyzeItem {
+    pub file_path: String,
+    #[serde(default, alias = "result")]
+    pub previous: Option<ocr::OcrFlightResult>,
+}
+
+/// Re-run OCR on a file with a specific model, by default the full (non-lite) one
+///
+/// With `previous`, each field keeps whichever reading is more confident, and
+/// `field_models` shows which model each field came from.
+#[tauri::command]
+pub async fn reanalyze_boarding_pass(
+    file_path: String,
+    model: Option<String>,
+    previous: Option<ocr::OcrFlightResult>,
+    user_id: Option<String>,
+    min_confidence: Option<f32>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ocr::OcrFlightResult, String> {
+    let model = escalation_model(model.as_deref())?;
+    let settings = OcrSettings { model, ..ocr_settings(&state)? };
+    analyze_single(file_path, previous, user_id, min_confidence, settings, &state, &app_handle).await
+}
+
+/// Batch variant of reanalyze_boarding_pass, for the files a lite pass left for review
+#[tauri::command]
+pub async fn batch_reanalyze_boarding_passes(
+    items: Vec<ReanalyzeItem>,
+    model: Option<String>,
+    user_id: Option<String>,
+    min_confidence: Option<f32>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ocr::BatchOcrResults, String> {
+    let model = escalation_model(model.as_deref())?;
+    let settings = OcrSettings { model, ..ocr_settings(&state)? };
+    let min_confidence = resolve_min_confidence(min_confidence, &state)?;
+    let operation = start_batch(&items, operation_id, &app_handle)?;
+    analyze_batch(items, user_id, min_confidence, settings, &state, operation, &app_handle).await
+}
+
+fn escalation_model(model: Option<&str>) -> Result<&'static str, String> {
+    model.map_or(Ok(ocr::PRO_MODEL), |m| ocr::parse_model(m).map_err(|e| e.to_string()))
+}
+
+async fn analyze_single(
+    file_path: String,
+    previous: Option<ocr::OcrFlightResult>,
+    user_id: Option<String>,
+    min_confidence: Option<f32>,
+    settings: OcrSettings,
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
 ) -> Result<ocr::OcrFlightResult, String> {
     // Validate file path
     if file_path.is_empty() {
         return Err("No file path provided".to_string());
     }
+    let min_confidence = resolve_min_confidence(min_confidence, state)?;
 
-    // Read the image file
-    let image_bytes =
-        std::fs::read(&file_path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    // Barcode first, then Gemini; the original file stays on disk for re-runs
+    let result = ocr::analyze_file(&file_path, &settings.api_key, settings.model, settings.locale, settings.preprocess)
+        .await
+        .map_err(|e| format!("OCR analysis failed: {}", e))?;
 
-    // Get Gemini API key from environment or settings (try GENAI_API_KEY, GOOGLE_GENAI_API_KEY, or GEMINI_API_KEY)
-    let api_key = get_api_key(
-        &["GENAI_API_KEY", "GOOGLE_GENAI_API_KEY", "GEMINI_API_KEY"],
-        "gemini_api_key",
-        &state,
-    )?;
+    finish_result(result, previous.as_ref(), user_id.as_deref(), min_confidence, state, app_handle)
+}
 
-    // Get model preference (lite vs standard)
-    let use_lite_model = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.get_setting("use_gemini_lite")
-            .map_err(|e| e.to_string())?
-            .unwrap_or_else(|| "true".to_string())
-            == "true"
+/// Register a batch OCR run as an operation so the UI can follow and cancel it
+fn start_batch(
+    items: &[ReanalyzeItem],
+    operation_id: Option<String>,
+    app_handle: &AppHandle,
+) -> Result<OperationHandle, String> {
+    if items.is_empty() {
+        return Err("No file paths provided".to_string());
+    }
+    Ok(app_handle
+        .state::<OperationRegistry>()
+        .start("batch_ocr", operation_id)
+        .map_err(|e| e.to_string())?
+        .with_events(app_handle))
+}
+
+/// `min_confidence` is already resolved; every outcome past this point is per file
+async fn analyze_batch(
+    items: Vec<ReanalyzeItem>,
+    user_id: Option<String>,
+    min_confidence: Option<f32>,
+    settings: OcrSettings,
+    state: &State<'_, AppState>,
+    operation: OperationHandle,
+    app_handle: &AppHandle,
+) -> Result<ocr::BatchOcrResults, String> {
+    // Process batch with parallel execution, progress tracking and cancellation
+    let file_paths = items.iter().map(|item| item.file_path.clone()).collect();
+    let results = ocr::batch_analyze_with_progress(file_paths, &settings.api_key, settings.model, settings.locale, settings.preprocess, &operation).await;
+
+    // Results come back in input order; below-threshold extractions go to needs_review
+    let mut buckets = ocr::BatchOcrResults::default();
+    for (index, (item, result)) in items.into_iter().zip(results).enumerate() {
+        let processed = result.map_err(|e| e.to_string()).and_then(|result| {
+            finish_result(result, item.previous.as_ref(), user_id.as_deref(), min_confidence, state, app_handle)
+        });
+        let file_path = item.file_path;
+        match processed {
+            Ok(result) if result.needs_review => {
+                buckets.needs_review.push(ocr::BatchOcrItem { index, file_path, result })
+            }
+            Ok(result) => buckets.accepted.push(ocr::BatchOcrItem { index, file_path, result }),
+            Err(error) => buckets.failed.push(ocr::BatchOcrFailure { index, file_path, error }),
+        }
+    }
+    buckets.cancelled = operation.is_cancelled();
+
+    let result = Ok(buckets);
+    operation.finish(&result);
+    result
+}
+
+/// Steps for a comparison: the explicit options, else the configured steps, else every step
+///
+/// Explicit options with every step off would compare the raw image with itself, so they
+/// are rejected rather than replaced.
+fn comparison_options(explicit: Option<PreprocessOptions>, configured: PreprocessOptions) -> Result<PreprocessOptions, String> {
+    match explicit {
+        Some(options) if !options.is_enabled() => {
+            Err("Select at least one pre-processing step to compare".to_string())
+        }
+        Some(options) => Ok(options),
+        None if configured.is_enabled() => Ok(configured),
+        None => Ok(PreprocessOptions::all()),
+    }
+}
+
+/// Run OCR on the raw image and on the pre-processed one side by side
+///
+/// `options` defaults to the configured steps, or every step when none is configured.
+#[tauri::command]
+pub async fn compare_ocr_preprocessing(
+    file_path: String,
+    options: Option<PreprocessOptions>,
+    user_id: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ocr::PreprocessComparison, String> {
+    let settings = ocr_settings(&state)?;
+    let options = comparison_options(options, settings.preprocess)?;
+    let min_confidence = resolve_min_confidence(None, &state)?;
+
+    let raw_bytes = std::fs::read(&file_path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    let processed = {
+        let raw_bytes = raw_bytes.clone();
+        tokio::task::spawn_blocking(move || ocr_preprocess::preprocess(&raw_bytes, &options))
+            .await
+            .map_err(|e| format!("Image pre-processing failed: {}", e))?
+            .map_err(|e| format!("Image pre-processing failed: {}", e))?
     };
 
-    // Call the Gemini OCR function
-    let result = ocr::analyze_with_gemini(image_bytes, &api_key, use_lite_model)
-        .await
-        .map_err(|e| format!("OCR analysis failed: {}", e))?;
+    let (raw, preprocessed) = futures::future::join(
+        ocr::analyze_with_model(raw_bytes, &settings.api_key, settings.model, settings.locale),
+        ocr::analyze_with_model(processed.bytes, &settings.api_key, settings.model, settings.locale),
+    )
+    .await;
+    let raw = raw.map_err(|e| format!("OCR analysis of the raw image failed: {}", e))?;
+    let preprocessed = preprocessed.map_err(|e| format!("OCR analysis of the pre-processed image failed: {}", e))?;
+
+    let raw = finish_result(raw, None, user_id.as_deref(), min_confidence, &state, &app_handle)?;
+    let preprocessed = finish_result(preprocessed, None, user_id.as_deref(), min_confidence, &state, &app_handle)?;
+    Ok(ocr::PreprocessComparison::new(raw, preprocessed, processed.report))
+}
 
+/// Post-process a fresh result, merge it with the one it re-runs, then score it
+fn finish_result(
+    mut result: ocr::OcrFlightResult,
+    previous: Option<&ocr::OcrFlightResult>,
+    user_id: Option<&str>,
+    min_confidence: Option<f32>,
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+) -> Result<ocr::OcrFlightResult, String> {
+    post_process_ocr_result(&mut result, user_id, state, app_handle)?;
+    if let Some(previous) = previous {
+        result = ocr::OcrFlightResult::escalate(previous, result);
+    }
+    result.apply_confidence(min_confidence);
     Ok(result)
 }
 
+/// Apply the user's learned airport misreads, then resolve place names to codes
+fn post_process_ocr_result(
+    result: &mut ocr::OcrFlightResult,
+    user_id: Option<&str>,
+    state: &State<'_, AppState>,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    if let Some(user_id) = user_id {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        ocr_learning::auto_apply_airport_patterns(db.get_connection(), user_id, result)
+            .map_err(|e| format!("Failed to apply learned corrections: {}", e))?;
+    }
+
+    // Best effort - OCR output is still useful without it
+    if let Err(e) = super::airport_enrichment::resolve_ocr_airports(app_handle, result) {
+        tracing::warn!("[OCR] Airport resolution skipped: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Create flights from OCR results with distance, duration and CO2 calculated inline
+///
+/// Airports are resolved again first, so corrections made after analysis count. A flight
+/// whose route cannot be resolved or measured is still created, with a warning anomaly,
+/// and left for the distance recalculation batch. Results missing an airport or the
+/// departure time are not imported and come back with an error.
 #[tauri::command]
-pub async fn batch_analyze_boarding_passes(
-    file_paths: Vec<String>,
+pub fn create_flights_from_ocr(
+    user_id: String,
+    results: Vec<ocr::OcrFlightResult>,
     state: State<'_, AppState>,
     app_handle: AppHandle,
-) -> Result<Vec<Result<ocr::OcrFlightResult, String>>, String> {
-    if file_paths.is_empty() {
-        return Err("No file paths provided".to_string());
+) -> Result<Vec<ocr::OcrFlightImport>, CommandError> {
+    let mut results = results;
+    for result in &mut results {
+        if let Err(e) = super::airport_enrichment::resolve_ocr_airports(&app_handle, result) {
+            tracing::warn!("[OCR] Airport resolution skipped: {}", e);
+        }
     }
 
-    // Get Gemini API key from environment or settings (try GENAI_API_KEY, GOOGLE_GENAI_API_KEY, or GEMINI_API_KEY)
-    let api_key = get_api_key(
-        &["GENAI_API_KEY", "GOOGLE_GENAI_API_KEY", "GEMINI_API_KEY"],
-        "gemini_api_key",
-        &state,
-    )?;
+    let db = state.db.lock()?;
+    let currency = CurrencySettings::configured(&db);
+    results.iter().map(|result| import_ocr_flight(&db, &user_id, &currency, result)).collect()
+}
 
-    // Get model preference
-    let use_lite_model = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.get_setting("use_gemini_lite")
-            .map_err(|e| e.to_string())?
-            .unwrap_or_else(|| "true".to_string())
-            == "true"
+/// Create one flight from an OCR result whose airports are already resolved; the flight
+/// and its recalculation flag are written in one transaction
+pub(crate) fn import_ocr_flight(
+    db: &crate::database::Database,
+    user_id: &str,
+    currency: &CurrencySettings,
+    result: &ocr::OcrFlightResult,
+) -> Result<ocr::OcrFlightImport, CommandError> {
+    let route = result.route_codes();
+    let distance = route
+        .as_ref()
+        .and_then(|(departure, arrival)| super::batch_calculations::route_distance(db, departure, arrival));
+    let performance = performance_for(db, AircraftRef::named(result.aircraft_type.as_deref()));
+    // A misread airport or date is reported for this result instead of failing the batch
+    let flight = match result.to_flight_input(distance, &performance).and_then(|flight| {
+        crate::validation::validate_flight_input(&flight)?;
+        Ok(flight)
+    }) {
+        Ok(flight) => flight,
+        Err(e) => {
+            return Ok(ocr::OcrFlightImport { flight_id: None, error: Some(e.to_string()), distance_km: None, flagged: false })
+        }
     };
 
-    // Process batch with parallel execution and progress tracking
-    let results = ocr::batch_analyze_with_progress(file_paths, &api_key, use_lite_model, &app_handle)
-        .await
-        .into_iter()
-        .map(|r| r.map_err(|e| e.to_string()))
-        .collect();
+    let tx = db.conn.unchecked_transaction()?;
+    let flight_id = db.create_flight_with_currency(user_id, &flight, "ocr", currency)?;
+    let flagged = distance.is_none();
+    if flagged {
+        flag_for_recalculation(db, &flight_id, result, route.is_some())?;
+    }
+    tx.commit()?;
+    Ok(ocr::OcrFlightImport {
+        flight_id: Some(flight_id),
+        error: None,
+        distance_km: distance.map(|(_, km)| km),
+        flagged,
+    })
+}
 
-    Ok(results)
+fn flag_for_recalculation(
+    db: &crate::database::Database,
+    flight_id: &str,
+    result: &ocr::OcrFlightResult,
+    route_resolved: bool,
+) -> rusqlite::Result<()> {
+    let (anomaly_type, description) = if route_resolved {
+        ("missing_distance", "No coordinates for this route; distance, duration and CO2 were not calculated".to_string())
+    } else {
+        (
+            "unresolved_airport",
+            format!(
+                "OCR airports '{}' → '{}' did not resolve to codes; distance, duration and CO2 were not calculated",
+                result.departure_airport.as_deref().unwrap_or(""),
+                result.arrival_airport.as_deref().unwrap_or("")
+            ),
+        )
+    };
+    db.conn.execute(
+        "INSERT INTO flight_anomalies (id, flight_id, anomaly_type, severity, description, suggested_fix)
+         VALUES (?1, ?2, ?3, 'warning', ?4, 'Correct the airports, then recalculate missing distances')",
+        rusqlite::params![uuid::Uuid::new_v4().to_string(), flight_id, anomaly_type, description],
+    )?;
+    Ok(())
 }
 
 // ===== OCR LEARNING COMMANDS =====
@@ -186,11 +504,76 @@ pub fn get_ocr_correction_history(
 #[tauri::command]
 pub fn get_active_learning_patterns(
     user_id: String,
+    include_inactive: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<Vec<ocr_learning::OcrLearningPattern>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
     let conn = db.get_connection();
 
-    ocr_learning::get_active_patterns(conn, &user_id)
+    ocr_learning::list_patterns(conn, &user_id, include_inactive.unwrap_or(false))
         .map_err(|e| format!("Failed to get learning patterns: {}", e))
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub fn set_learning_pattern_active(
+    user_id: String,
+    pattern_id: String,
+    active: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let conn = db.get_connection();
+
+    ocr_learning::set_pattern_active(conn, &user_id, &pattern_id, active)
+        .map_err(|e| format!("Failed to update learning pattern: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_comparison_options() {
+        let deskew = PreprocessOptions { deskew: true, ..Default::default() };
+        let crop = PreprocessOptions { crop: true, ..Default::default() };
+        assert_eq!(comparison_options(Some(deskew), crop), Ok(deskew));
+        assert_eq!(comparison_options(None, crop), Ok(crop));
+        assert_eq!(comparison_options(None, PreprocessOptions::default()), Ok(PreprocessOptions::all()));
+        assert!(comparison_options(Some(PreprocessOptions::default()), crop).is_err());
+    }
+
+    #[test]
+    fn test_import_ocr_flight() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn.execute("INSERT INTO users (id, name) VALUES ('u1', 'Pilot')", []).unwrap();
+        let result = |json: &str| -> ocr::OcrFlightResult { serde_json::from_str(json).unwrap() };
+        let currency = CurrencySettings::configured(&db);
+
+        // No coordinates for the route: created, and flagged along with it
+        let unmeasured = result(r#"{"departure_airport": "QQA", "arrival_airport": "QQB", "departure_datetime": "2025-06-01T10:00:00"}"#);
+        let imported = import_ocr_flight(&db, "u1", &currency, &unmeasured).unwrap();
+        assert!(imported.flagged);
+        let flight_id = imported.flight_id.unwrap();
+        let anomalies: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM flight_anomalies WHERE flight_id = ?1", [&flight_id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(anomalies, 1);
+
+        // No date: nothing is created
+        let undated = result(r#"{"departure_airport": "QQA", "arrival_airport": "QQB"}"#);
+        let imported = import_ocr_flight(&db, "u1", &currency, &undated).unwrap();
+        assert!(imported.flight_id.is_none());
+        assert_eq!(imported.error.as_deref(), Some("Missing departure_datetime"));
+
+        // A misread airport is rejected by validation, also without creating anything
+        let misread = result(r#"{"departure_airport": "Q?A", "arrival_airport": "QQB", "departure_datetime": "2025-06-01T10:00:00"}"#);
+        let imported = import_ocr_flight(&db, "u1", &currency, &misread).unwrap();
+        assert!(imported.flight_id.is_none());
+        assert!(imported.error.unwrap().contains("departure_airport"));
+        let flights: i64 = db.conn.query_row("SELECT COUNT(*) FROM flights", [], |row| row.get(0)).unwrap();
+        assert_eq!(flights, 1);
+    }
+}
@@ -0,0 +1,42 @@
+// Long running operation commands
+// Status and cancellation for work registered in operations.rs; progress also arrives as
+// `operation:progress` events while the operation runs.
+
+use crate::error::CommandError;
+use crate::operations::{OperationRegistry, OperationStatus};
+use tauri::State;
+
+#[tauri::command]
+pub fn get_operation_status(
+    operation_id: String,
+    operations: State<'_, OperationRegistry>,
+) -> Result<OperationStatus, CommandError> {
+    operations
+        .status(&operation_id)
+        .ok_or_else(|| CommandError::not_found("Operation", &operation_id))
+}
+
+/// Running and recently finished operations, newest first
+#[tauri::command]
+pub fn list_operations(operations: State<'_, OperationRegistry>) -> Result<Vec<OperationStatus>, CommandError> {
+    Ok(operations.list())
+}
+
+/// Ask an operation to stop after the item it is working on
+///
+/// Returns false when the operation already finished. Work completed before the
+/// cancellation is kept, and the operation's command returns it as usual.
+#[tauri::command]
+pub fn cancel_operation(
+    operation_id: String,
+    operations: State<'_, OperationRegistry>,
+) -> Result<bool, CommandError> {
+    if operations.status(&operation_id).is_none() {
+        return Err(CommandError::not_found("Operation", &operation_id));
+    }
+    let cancelled = operations.cancel(&operation_id);
+    if cancelled {
+        tracing::info!("Cancellation requested for operation {}", operation_id);
+    }
+    Ok(cancelled)
+}
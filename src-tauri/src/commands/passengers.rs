@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use super::AppState;
+use crate::passenger_filter::{self, PassengerFilter};
 
 // ===== PASSENGER TYPES =====
 
@@ -100,7 +101,7 @@ pub fn get_analytics(
             COALESCE(SUM(distance_km), 0) as total_distance,
             COALESCE(AVG(flight_duration), 0) as avg_duration
          FROM flights
-         WHERE user_id = ?1
+         WHERE user_id = ?1 AND deleted_at IS NULL
          GROUP BY departure_airport, arrival_airport
          ORDER BY flight_count DESC
          LIMIT ?2",
@@ -133,9 +134,9 @@ pub fn get_analytics(
             SUM(CASE WHEN type = 'arrival' THEN 1 ELSE 0 END) as arrival_count,
             COUNT(*) as total_visits
          FROM (
-            SELECT departure_airport as airport_code, 'departure' as type FROM flights WHERE user_id = ?1
+            SELECT departure_airport as airport_code, 'departure' as type FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
             UNION ALL
-            SELECT arrival_airport as airport_code, 'arrival' as type FROM flights WHERE user_id = ?1
+            SELECT arrival_airport as airport_code, 'arrival' as type FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
          )
          GROUP BY airport_code
          ORDER BY total_visits DESC
@@ -160,7 +161,7 @@ pub fn get_analytics(
         .conn
         .query_row(
             "SELECT COUNT(DISTINCT departure_airport || '-' || arrival_airport)
-         FROM flights WHERE user_id = ?1",
+         FROM flights WHERE user_id = ?1 AND deleted_at IS NULL",
             rusqlite::params![user_id],
             |row| row.get(0),
         )
@@ -235,7 +236,7 @@ pub fn get_all_passenger_names(
     // Extract all passenger names from notes field
     let mut stmt = db
         .conn
-        .prepare("SELECT notes FROM flights WHERE user_id = ?1 AND notes IS NOT NULL")
+        .prepare("SELECT notes FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND notes IS NOT NULL")
         .map_err(|e| e.to_string())?;
 
     let notes_list: Vec<String> = stmt
@@ -244,7 +245,8 @@ pub fn get_all_passenger_names(
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
 
-    // Parse passenger names from notes
+    // Parse passenger names from notes, skipping parsing artifacts
+    let filter = PassengerFilter::load(&db.conn);
     let mut name_counts: std::collections::HashMap<String, i32> = std::collections::HashMap::new();
 
     for notes in notes_list {
@@ -253,7 +255,7 @@ pub fn get_all_passenger_names(
             // Split by comma and trim
             for name in passenger_part.split(',') {
                 let trimmed = name.trim();
-                if !trimmed.is_empty() {
+                if !trimmed.is_empty() && !filter.is_artifact(trimmed) {
                     *name_counts.entry(trimmed.to_string()).or_insert(0) += 1;
                 }
             }
@@ -285,11 +287,198 @@ pub fn get_all_passenger_names(
         .collect();
 
     // Sort by usage count descending
-    result.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
+    result.sort_by_key(|x| std::cmp::Reverse(x.usage_count));
 
     Ok(result)
 }
 
+// ===== PARSING ARTIFACTS =====
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuspiciousPassenger {
+    pub name: String,
+    /// "stop-list", "digits only", "too short", "passenger count" or "header fragment"
+    pub reason: String,
+    /// Flights whose passenger line lists the name
+    pub flight_count: i32,
+    /// Canonical identity record with this name, if one was created
+    pub passenger_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactCleanupResult {
+    pub notes_updated: usize,
+    pub passengers_deleted: usize,
+}
+
+/// Passenger names in flight notes and identity records that look like parsing artifacts
+#[tauri::command]
+pub fn find_suspicious_passengers(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SuspiciousPassenger>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let filter = PassengerFilter::load(&db.conn);
+
+    let mut found: std::collections::BTreeMap<String, SuspiciousPassenger> = std::collections::BTreeMap::new();
+
+    let mut stmt = db
+        .conn
+        .prepare("SELECT notes FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND notes LIKE 'Passengers: %'")
+        .map_err(|e| e.to_string())?;
+    let notes_list: Vec<String> = stmt
+        .query_map(rusqlite::params![user_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for notes in &notes_list {
+        let line = notes.trim_start_matches("Passengers: ").lines().next().unwrap_or("");
+        for name in line.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if let Some(reason) = filter.artifact_reason(name) {
+                found
+                    .entry(passenger_filter::normalize(name))
+                    .or_insert_with(|| SuspiciousPassenger {
+                        name: passenger_filter::normalize(name),
+                        reason: reason.to_string(),
+                        flight_count: 0,
+                        passenger_id: None,
+                    })
+                    .flight_count += 1;
+            }
+        }
+    }
+
+    let mut stmt = db
+        .conn
+        .prepare(&format!("SELECT id, canonical_name, total_flights FROM passengers p WHERE {}", OWNED_PASSENGER))
+        .map_err(|e| e.to_string())?;
+    let identities: Vec<(String, String, Option<i32>)> = stmt
+        .query_map(rusqlite::params![user_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for (passenger_id, canonical_name, total_flights) in identities {
+        if let Some(reason) = filter.artifact_reason(&canonical_name) {
+            let name = passenger_filter::normalize(&canonical_name);
+            let entry = found.entry(name.clone()).or_insert_with(|| SuspiciousPassenger {
+                name,
+                reason: reason.to_string(),
+                flight_count: total_flights.unwrap_or(0),
+                passenger_id: None,
+            });
+            entry.passenger_id = Some(passenger_id);
+        }
+    }
+
+    let mut result: Vec<SuspiciousPassenger> = found.into_values().collect();
+    result.sort_by(|a, b| b.flight_count.cmp(&a.flight_count).then_with(|| a.name.cmp(&b.name)));
+    Ok(result)
+}
+
+/// Identity records (aliased `p`) flown only on ?1's flights; passengers are shared across users
+const OWNED_PASSENGER: &str = "EXISTS (SELECT 1 FROM flight_passengers fp JOIN flights f ON f.id = fp.flight_id
+                WHERE fp.passenger_id = p.id AND f.user_id = ?1)
+          AND NOT EXISTS (SELECT 1 FROM flight_passengers fp JOIN flights f ON f.id = fp.flight_id
+                WHERE fp.passenger_id = p.id AND f.user_id != ?1)";
+
+/// Remove artifact names from flight passenger lists and delete their identity records
+///
+/// Only names that still look like artifacts are touched, and only identities among
+/// `passenger_ids` that belong to the user's flights alone are deleted.
+#[tauri::command]
+pub fn delete_suspicious_passengers(
+    user_id: String,
+    names: Vec<String>,
+    passenger_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<ArtifactCleanupResult, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    remove_artifacts(&db.conn, &user_id, &names, &passenger_ids).map_err(|e| e.to_string())
+}
+
+fn remove_artifacts(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    names: &[String],
+    passenger_ids: &[String],
+) -> rusqlite::Result<ArtifactCleanupResult> {
+    let filter = PassengerFilter::load(conn);
+    let names: std::collections::HashSet<String> = names
+        .iter()
+        .filter(|n| filter.is_artifact(n))
+        .map(|n| passenger_filter::normalize(n))
+        .collect();
+
+    let tx = conn.unchecked_transaction()?;
+
+    let flights: Vec<(String, String)> = {
+        let mut stmt = tx.prepare("SELECT id, notes FROM flights WHERE user_id = ?1 AND notes LIKE 'Passengers: %'")?;
+        let rows = stmt
+            .query_map(rusqlite::params![user_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    let mut notes_updated = 0;
+    for (flight_id, notes) in flights {
+        if let Some(new_notes) = passenger_filter::remove_from_notes(&notes, &names) {
+            tx.execute(
+                "UPDATE flights SET notes = ?1, updated_at = datetime('now') WHERE id = ?2",
+                rusqlite::params![new_notes, flight_id],
+            )?;
+            notes_updated += 1;
+        }
+    }
+
+    // Aliases and flight links go with the passenger (ON DELETE CASCADE)
+    let mut passengers_deleted = 0;
+    for passenger_id in passenger_ids {
+        let canonical_name: Option<String> = tx
+            .query_row(
+                &format!("SELECT canonical_name FROM passengers p WHERE p.id = ?2 AND {}", OWNED_PASSENGER),
+                rusqlite::params![user_id, passenger_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if canonical_name.is_some_and(|name| filter.is_artifact(&name)) {
+            passengers_deleted += tx.execute("DELETE FROM passengers WHERE id = ?1", rusqlite::params![passenger_id])?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(ArtifactCleanupResult {
+        notes_updated,
+        passengers_deleted,
+    })
+}
+
+/// Effective stop-list: the user's, or the defaults
+#[tauri::command]
+pub fn get_passenger_stop_list(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(passenger_filter::load_stop_list(&db.conn)
+        .unwrap_or_else(|| passenger_filter::DEFAULT_STOP_LIST.iter().map(|s| s.to_string()).collect()))
+}
+
+/// Save the stop-list; None restores the defaults
+#[tauri::command]
+pub fn set_passenger_stop_list(words: Option<Vec<String>>, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    match words {
+        Some(words) => db
+            .set_setting(passenger_filter::STOP_LIST_SETTING, &passenger_filter::serialize_stop_list(&words))
+            .map_err(|e| e.to_string()),
+        None => db
+            .conn
+            .execute("DELETE FROM settings WHERE key = ?1", [passenger_filter::STOP_LIST_SETTING])
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    }
+}
+
 #[tauri::command]
 pub fn save_passenger_mapping(
     abbreviation: String,
@@ -383,7 +572,7 @@ pub fn get_passenger_details(
         .prepare(
             "SELECT id, notes, departure_airport, arrival_airport, departure_datetime, distance_km, carbon_emissions_kg, carbon_offset_purchased
          FROM flights
-         WHERE user_id = ?1 AND notes LIKE ?2
+         WHERE user_id = ?1 AND deleted_at IS NULL AND notes LIKE ?2
          ORDER BY departure_datetime DESC",
         )
         .map_err(|e| e.to_string())?;
@@ -469,7 +658,7 @@ pub fn get_passenger_details(
             }
         })
         .collect();
-    top_routes.sort_by(|a, b| b.flight_count.cmp(&a.flight_count));
+    top_routes.sort_by_key(|x| std::cmp::Reverse(x.flight_count));
     top_routes.truncate(5); // Top 5 routes
 
     // Calculate travel companions
@@ -495,7 +684,7 @@ pub fn get_passenger_details(
             flight_count: count,
         })
         .collect();
-    travel_companions.sort_by(|a, b| b.flight_count.cmp(&a.flight_count));
+    travel_companions.sort_by_key(|x| std::cmp::Reverse(x.flight_count));
     travel_companions.truncate(10); // Top 10 companions
 
     Ok(PassengerDetails {
@@ -600,3 +789,48 @@ pub fn export_passenger_dossier(
 
     Ok(format!("Dossier exported successfully to {}", output_path))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_remove_artifacts_is_scoped_to_user() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'One'), ('u2', 'Two');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, notes)
+                 VALUES ('f1', 'u1', 'KJFK', 'KBOS', '2025-01-05T09:00:00', 'Passengers: TBD, JOHN SMITH'),
+                        ('f2', 'u2', 'KJFK', 'KBOS', '2025-01-05T09:00:00', 'Passengers: TBA');
+                 INSERT INTO passengers (id, canonical_name) VALUES ('p1', 'TBD'), ('p2', 'JOHN SMITH'), ('p3', 'TBA');
+                 INSERT INTO flight_passengers (flight_id, passenger_id) VALUES ('f1', 'p1'), ('f1', 'p2'), ('f2', 'p3');",
+            )
+            .unwrap();
+
+        let names = ["TBD", "JOHN SMITH", "TBA"].map(String::from);
+        let ids = ["p1", "p2", "p3"].map(String::from);
+        let result = remove_artifacts(&db.conn, "u1", &names, &ids).unwrap();
+
+        // Only u1's artifact goes; the real name and u2's identity stay
+        assert_eq!(result.passengers_deleted, 1);
+        let remaining: Vec<String> = db
+            .conn
+            .prepare("SELECT id FROM passengers ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining, vec!["p2", "p3"]);
+
+        assert_eq!(result.notes_updated, 1);
+        let notes = |id: &str| -> String {
+            db.conn.query_row("SELECT notes FROM flights WHERE id = ?1", [id], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(notes("f1"), "Passengers: JOHN SMITH");
+        assert_eq!(notes("f2"), "Passengers: TBA");
+    }
+}
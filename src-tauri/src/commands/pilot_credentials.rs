@@ -0,0 +1,134 @@
+// Pilot credential commands - medicals, flight reviews, IPCs and type ratings with expiry,
+// and the alerts raised as they (or landing currency) come up for expiry
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::AppState;
+use crate::error::CommandError;
+use crate::expiry_alerts::{self, ExpiryAlert, ExpiryAlertSettings};
+use crate::pilot_credentials::{self, ExpiringCredential, PilotCredential, PilotCredentialInput};
+
+/// Warning window when the caller does not give one
+const DEFAULT_WITHIN_DAYS: u32 = 60;
+
+/// Gives the window time to load and listen before the first background check
+const EXPIRY_CHECK_STARTUP_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[tauri::command]
+pub fn create_pilot_credential(
+    user_id: String,
+    credential: PilotCredentialInput,
+    state: State<'_, AppState>,
+) -> Result<PilotCredential, CommandError> {
+    let db = state.db.lock()?;
+    Ok(pilot_credentials::create_credential(&db.conn, &user_id, &credential)?)
+}
+
+#[tauri::command]
+pub fn list_pilot_credentials(user_id: String, state: State<'_, AppState>) -> Result<Vec<PilotCredential>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(pilot_credentials::list_credentials(&db.conn, &user_id)?)
+}
+
+#[tauri::command]
+pub fn update_pilot_credential(
+    credential_id: String,
+    credential: PilotCredentialInput,
+    state: State<'_, AppState>,
+) -> Result<PilotCredential, CommandError> {
+    let db = state.db.lock()?;
+    Ok(pilot_credentials::update_credential(&db.conn, &credential_id, &credential)?)
+}
+
+#[tauri::command]
+pub fn delete_pilot_credential(credential_id: String, state: State<'_, AppState>) -> Result<bool, CommandError> {
+    let db = state.db.lock()?;
+    Ok(pilot_credentials::delete_credential(&db.conn, &credential_id)?)
+}
+
+/// Credentials expiring within `within_days` (default 60) or already expired, soonest
+/// first, including a flight review derived from the logbook
+#[tauri::command]
+pub fn get_expiring_credentials(
+    user_id: String,
+    within_days: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ExpiringCredential>, CommandError> {
+    let db = state.db.lock()?;
+    let today = chrono::Local::now().date_naive();
+    let within_days = within_days.unwrap_or(DEFAULT_WITHIN_DAYS);
+    Ok(pilot_credentials::expiring_credentials(&db.conn, &user_id, today, within_days.into())?)
+}
+
+/// Check now for landing currency and credentials coming up for expiry. Returns, and
+/// announces like the background check, only the ones not alerted before.
+#[tauri::command]
+pub async fn check_expiry_alerts(
+    user_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<ExpiryAlert>, CommandError> {
+    let (alerts, webhook_url) = {
+        let db = state.db.lock()?;
+        let settings = ExpiryAlertSettings::configured(&db);
+        let today = chrono::Local::now().date_naive();
+        (expiry_alerts::take_due_alerts(&db, &user_id, today, settings.window_days)?, super::digest::webhook_url(&db))
+    };
+    deliver_expiry_alerts(&app_handle, &user_id, &alerts, webhook_url.as_deref()).await;
+    Ok(alerts)
+}
+
+/// Emit the alerts to the window and POST them to the digest webhook, if one is configured
+async fn deliver_expiry_alerts(app_handle: &AppHandle, user_id: &str, alerts: &[ExpiryAlert], webhook_url: Option<&str>) {
+    if alerts.is_empty() {
+        return;
+    }
+    let payload = serde_json::json!({ "user_id": user_id, "alerts": alerts });
+    if let Err(e) = app_handle.emit(expiry_alerts::ALERT_EVENT, &payload) {
+        tracing::warn!("Failed to emit expiry alerts: {}", e);
+    }
+    let Some(url) = webhook_url else {
+        return;
+    };
+    if let Err(e) = super::digest::post_webhook(url, &payload).await {
+        tracing::warn!("Expiry alert delivery failed: {}", e);
+    }
+}
+
+/// A user id, the new alerts for that user and the digest webhook to deliver them to
+type UserAlerts = (String, Vec<ExpiryAlert>, Option<String>);
+
+/// New alerts for the primary user, with the settings in effect; no alerts while turned off
+fn primary_user_expiry_alerts(app_handle: &AppHandle) -> anyhow::Result<(ExpiryAlertSettings, Option<UserAlerts>)> {
+    let state = app_handle.state::<AppState>();
+    let db = state.db.lock().map_err(|e| anyhow::anyhow!("Database lock poisoned: {}", e))?;
+    let settings = ExpiryAlertSettings::configured(&db);
+    if !settings.enabled {
+        return Ok((settings, None));
+    }
+    let Some(user) = db.get_primary_user()? else {
+        return Ok((settings, None));
+    };
+    let today = chrono::Local::now().date_naive();
+    let alerts = expiry_alerts::take_due_alerts(&db, &user.id, today, settings.window_days)?;
+    Ok((settings, Some((user.id, alerts, super::digest::webhook_url(&db)))))
+}
+
+/// Background expiry check, shortly after startup and then every "expiry_alert_interval_hours"
+pub(crate) async fn run_expiry_alert_checks(app_handle: AppHandle) {
+    tokio::time::sleep(EXPIRY_CHECK_STARTUP_DELAY).await;
+    loop {
+        let interval_hours = match primary_user_expiry_alerts(&app_handle) {
+            Ok((settings, found)) => {
+                if let Some((user_id, alerts, webhook_url)) = found {
+                    deliver_expiry_alerts(&app_handle, &user_id, &alerts, webhook_url.as_deref()).await;
+                }
+                settings.interval_hours
+            }
+            Err(e) => {
+                tracing::warn!("Expiry alert check failed: {:#}", e);
+                expiry_alerts::DEFAULT_INTERVAL_HOURS
+            }
+        };
+        tokio::time::sleep(std::time::Duration::from_secs(interval_hours * 3600)).await;
+    }
+}
@@ -1,7 +1,12 @@
 // Pilot logbook commands
+// Signed (locked) entries are read-only: update and delete fail with a "locked" error
+// until they are unlocked, and every lock/unlock is written to pilot_logbook_audit.
 use tauri::State;
 
 use super::AppState;
+use crate::database::Database;
+use crate::error::CommandError;
+use crate::models::{LogbookAuditEntry, PilotLogbook};
 
 // ===== PILOT LOGBOOK COMMANDS =====
 
@@ -79,8 +84,8 @@ pub fn update_pilot_logbook_entry(
     entry_id: String,
     entry: crate::models::PilotLogbookInput,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let db = state.db.lock()?;
     db.update_pilot_logbook_entry(
         &entry_id,
         entry.pic_time,
@@ -111,18 +116,71 @@ pub fn update_pilot_logbook_entry(
         entry.route.as_deref(),
         entry.remarks.as_deref(),
         entry.endorsements.as_deref(),
-    )
-    .map_err(|e| e.to_string())
+    )?;
+    Ok(())
 }
 
 #[tauri::command]
 pub fn delete_pilot_logbook_entry(
     entry_id: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.delete_pilot_logbook_entry(&entry_id)
-        .map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let db = state.db.lock()?;
+    db.delete_pilot_logbook_entry(&entry_id)?;
+    Ok(())
+}
+
+/// Sign an entry, making it read-only. The signer defaults to the entry's pilot name.
+#[tauri::command]
+pub fn lock_logbook_entry(
+    entry_id: String,
+    signer: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<PilotLogbook, CommandError> {
+    let db = state.db.lock()?;
+    let entry = find_entry(&db, &entry_id)?;
+    if entry.locked {
+        return Err(CommandError::Validation(format!("Logbook entry {} is already signed", entry_id)));
+    }
+    let signer = signer
+        .or(entry.pilot_name)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| CommandError::Validation("A signer name is required to sign a logbook entry".to_string()))?;
+    db.lock_pilot_logbook_entry(&entry_id, &signer)?;
+    find_entry(&db, &entry_id)
+}
+
+/// Remove the signature so the entry can be corrected. Destructive: the frontend
+/// confirms first, and the reason is kept in the audit log.
+#[tauri::command]
+pub fn unlock_logbook_entry(
+    entry_id: String,
+    actor: Option<String>,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<PilotLogbook, CommandError> {
+    let db = state.db.lock()?;
+    if !find_entry(&db, &entry_id)?.locked {
+        return Err(CommandError::Validation(format!("Logbook entry {} is not signed", entry_id)));
+    }
+    db.unlock_pilot_logbook_entry(&entry_id, actor.as_deref(), reason.as_deref())?;
+    find_entry(&db, &entry_id)
+}
+
+/// Lock/unlock history for one entry, or for the whole logbook
+#[tauri::command]
+pub fn get_logbook_audit(
+    entry_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<LogbookAuditEntry>, CommandError> {
+    let db = state.db.lock()?;
+    Ok(db.list_logbook_audit(entry_id.as_deref())?)
+}
+
+fn find_entry(db: &Database, entry_id: &str) -> Result<PilotLogbook, CommandError> {
+    db.get_pilot_logbook_entry(entry_id)?
+        .ok_or_else(|| CommandError::not_found("Logbook entry", entry_id))
 }
 
 #[tauri::command]
@@ -132,3 +190,47 @@ pub fn get_pilot_logbook_totals(
     let db = state.db.lock().map_err(|e| e.to_string())?;
     db.get_pilot_logbook_totals().map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::error::CommandError;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_locked_entries_are_read_only() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'KJFK', 'KBOS', '2025-01-05T09:00:00');
+                 INSERT INTO pilot_logbook (id, flight_id, pic_time, remarks) VALUES ('e1', 'f1', 1.5, 'original');",
+            )
+            .unwrap();
+
+        assert!(db.lock_pilot_logbook_entry("e1", "A. Pilot").unwrap());
+        assert!(!db.lock_pilot_logbook_entry("e1", "A. Pilot").unwrap());
+        let entry = db.get_pilot_logbook_entry("e1").unwrap().unwrap();
+        assert!(entry.locked);
+        assert_eq!(entry.signed_by.as_deref(), Some("A. Pilot"));
+        assert!(entry.signed_at.is_some());
+
+        // Update and delete refuse a signed entry with an explicit "locked" error
+        let err = CommandError::from(db.delete_pilot_logbook_entry("e1").unwrap_err());
+        assert_eq!(err.code(), "locked");
+        assert!(err.to_string().contains("signed by A. Pilot"));
+        assert!(db.get_pilot_logbook_entry("e1").unwrap().is_some());
+        let err = CommandError::from(db.delete_pilot_logbook_entry("missing").unwrap_err());
+        assert_eq!(err.code(), "not_found");
+
+        assert!(db.unlock_pilot_logbook_entry("e1", Some("A. Pilot"), Some("wrong tail number")).unwrap());
+        let entry = db.get_pilot_logbook_entry("e1").unwrap().unwrap();
+        assert!(!entry.locked && entry.signed_by.is_none());
+
+        let audit = db.list_logbook_audit(Some("e1")).unwrap();
+        let actions: Vec<_> = audit.iter().map(|a| a.action.as_str()).collect();
+        assert_eq!(actions, ["unlock", "lock"]);
+        assert_eq!(audit[0].reason.as_deref(), Some("wrong tail number"));
+    }
+}
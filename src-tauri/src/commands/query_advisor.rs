@@ -0,0 +1,259 @@
+// Query performance advisor
+// Summarises the query_performance table and recommends indexes for slow filters
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::AppState;
+
+/// Average execution time (ms) above which a query counts as slow
+const DEFAULT_SLOW_QUERY_MS: i64 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowQuerySummary {
+    pub query_type: String,
+    pub table_name: Option<String>,
+    /// Comma separated columns the query filters on
+    pub filters: Option<String>,
+    pub executions: i64,
+    pub avg_ms: f64,
+    pub max_ms: i64,
+    pub total_ms: i64,
+    pub avg_result_count: f64,
+    pub last_seen: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexSuggestion {
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub index_name: String,
+    pub sql: String,
+    /// Recorded queries this index would serve
+    pub executions: i64,
+    pub avg_ms: f64,
+    /// True once the index has been created by this call
+    pub created: bool,
+}
+
+/// Slowest recorded query types, grouped by type, table and filters
+///
+/// Only groups whose average time is at least `threshold_ms` (default 100) are returned,
+/// slowest first.
+#[tauri::command]
+pub fn get_slow_queries(
+    threshold_ms: Option<i64>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SlowQuerySummary>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stmt = db
+        .conn
+        .prepare(
+            "SELECT query_type, table_name, filters, COUNT(*), AVG(execution_time_ms),
+                    MAX(execution_time_ms), SUM(execution_time_ms), AVG(COALESCE(result_count, 0)),
+                    MAX(created_at)
+             FROM query_performance
+             GROUP BY query_type, table_name, filters
+             HAVING AVG(execution_time_ms) >= ?1
+             ORDER BY AVG(execution_time_ms) DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let summaries = stmt
+        .query_map(
+            params![threshold_ms.unwrap_or(DEFAULT_SLOW_QUERY_MS), limit.unwrap_or(20)],
+            |row| {
+                Ok(SlowQuerySummary {
+                    query_type: row.get(0)?,
+                    table_name: row.get(1)?,
+                    filters: row.get(2)?,
+                    executions: row.get(3)?,
+                    avg_ms: row.get(4)?,
+                    max_ms: row.get(5)?,
+                    total_ms: row.get(6)?,
+                    avg_result_count: row.get(7)?,
+                    last_seen: row.get(8)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(summaries)
+}
+
+/// Recommend indexes for slow filter combinations that no existing index covers
+///
+/// With `apply` the suggested indexes are created straight away.
+#[tauri::command]
+pub fn suggest_indexes(
+    threshold_ms: Option<i64>,
+    apply: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<IndexSuggestion>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    build_suggestions(
+        &db.conn,
+        threshold_ms.unwrap_or(DEFAULT_SLOW_QUERY_MS),
+        apply.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn build_suggestions(conn: &Connection, threshold_ms: i64, apply: bool) -> rusqlite::Result<Vec<IndexSuggestion>> {
+    let groups: Vec<(String, String, i64, f64)> = {
+        let mut stmt = conn.prepare(
+            "SELECT table_name, filters, COUNT(*), AVG(execution_time_ms)
+             FROM query_performance
+             WHERE table_name IS NOT NULL AND filters IS NOT NULL AND filters != ''
+             GROUP BY table_name, filters
+             HAVING AVG(execution_time_ms) >= ?1
+             ORDER BY AVG(execution_time_ms) DESC",
+        )?;
+        let rows = stmt.query_map(params![threshold_ms], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut suggestions: Vec<IndexSuggestion> = Vec::new();
+    for (table, filters, executions, avg_ms) in groups {
+        if !is_identifier(&table) {
+            continue;
+        }
+        let existing_columns = table_columns(conn, &table)?;
+        let columns: Vec<String> = filters
+            .split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| existing_columns.contains(c))
+            .collect();
+        if columns.is_empty() || is_covered(&columns, &index_columns(conn, &table)?) {
+            continue;
+        }
+
+        if let Some(existing) = suggestions
+            .iter_mut()
+            .find(|s| s.table_name == table && s.columns == columns)
+        {
+            existing.executions += executions;
+            continue;
+        }
+
+        let index_name = format!("idx_auto_{}_{}", table, columns.join("_"));
+        let sql = format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {}({})",
+            index_name,
+            table,
+            columns.join(", ")
+        );
+        let created = if apply {
+            conn.execute(&sql, [])?;
+            conn.execute(&format!("ANALYZE {}", table), [])?;
+            true
+        } else {
+            false
+        };
+
+        suggestions.push(IndexSuggestion {
+            table_name: table,
+            columns,
+            index_name,
+            sql,
+            executions,
+            avg_ms,
+            created,
+        });
+    }
+
+    Ok(suggestions)
+}
+
+/// True when some index leads with exactly the filter columns (in any order)
+fn is_covered(filters: &[String], indexes: &[Vec<String>]) -> bool {
+    indexes.iter().any(|index| {
+        index.len() >= filters.len()
+            && index[..filters.len()].iter().all(|column| filters.contains(column))
+    })
+}
+
+/// Table and column names are interpolated into PRAGMA and DDL, so only plain identifiers pass
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn table_columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    columns.collect()
+}
+
+/// Column lists of every index on the table, in index order
+fn index_columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<Vec<String>>> {
+    let names: Vec<String> = {
+        let mut stmt = conn.prepare(&format!("PRAGMA index_list({})", table))?;
+        let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+        names.collect::<Result<_, _>>()?
+    };
+
+    let mut indexes = Vec::with_capacity(names.len());
+    for name in names {
+        let mut stmt = conn.prepare(&format!("PRAGMA index_info(\"{}\")", name))?;
+        let columns = stmt.query_map([], |row| row.get::<_, Option<String>>(2))?;
+        indexes.push(columns.collect::<Result<Vec<_>, _>>()?.into_iter().flatten().collect());
+    }
+    Ok(indexes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_is_covered() {
+        let filters = vec!["user_id".to_string(), "departure_datetime".to_string()];
+        let single = vec![vec!["user_id".to_string()]];
+        let composite = vec![vec![
+            "departure_datetime".to_string(),
+            "user_id".to_string(),
+            "deleted_at".to_string(),
+        ]];
+
+        assert!(!is_covered(&filters, &single));
+        assert!(is_covered(&filters, &composite));
+        assert!(is_covered(&filters[..1], &single));
+    }
+
+    #[test]
+    fn test_suggest_and_apply_index() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        for _ in 0..3 {
+            db.record_query_performance(
+                "temporal_analysis",
+                "flights",
+                &["user_id", "departure_datetime", "no_such_column"],
+                Duration::from_millis(250),
+                12,
+            )
+            .unwrap();
+        }
+        db.record_query_performance("fast_query", "flights", &["arrival_airport"], Duration::from_millis(1), 1)
+            .unwrap();
+
+        let suggestions = build_suggestions(&db.conn, 100, false).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].columns, vec!["user_id", "departure_datetime"]);
+        assert_eq!(suggestions[0].executions, 3);
+        assert!(!suggestions[0].created);
+
+        let applied = build_suggestions(&db.conn, 100, true).unwrap();
+        assert!(applied[0].created);
+        assert!(build_suggestions(&db.conn, 100, false).unwrap().is_empty());
+    }
+}
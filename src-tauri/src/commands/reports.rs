@@ -0,0 +1,25 @@
+// Report template commands
+// Named, parameterized read-only queries (see report_templates.rs for the catalogue)
+
+use std::collections::HashMap;
+use tauri::State;
+
+use super::AppState;
+use crate::report_templates::{self, ReportResult, ReportTemplate};
+
+/// List the available report templates with their parameters and output columns
+#[tauri::command]
+pub fn list_report_templates() -> Vec<ReportTemplate> {
+    report_templates::list_templates().to_vec()
+}
+
+/// Run a report template by name; `params` maps parameter names to values
+#[tauri::command]
+pub fn run_report_template(
+    name: String,
+    params: Option<HashMap<String, serde_json::Value>>,
+    state: State<'_, AppState>,
+) -> Result<ReportResult, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    report_templates::run_template(&db.conn, &name, &params.unwrap_or_default()).map_err(|e| e.to_string())
+}
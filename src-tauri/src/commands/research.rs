@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use super::AppState;
+use crate::error::CommandError;
+use crate::prompt_templates::{self, PromptTemplate, PromptTemplateInput, PromptTemplateValidation};
+use crate::web_search;
 
 // ===== RESEARCH TYPES =====
 
@@ -15,6 +18,9 @@ pub struct ResearchRequest {
     pub research_events: bool,
     pub research_weather: bool,
     pub research_passengers: bool,
+    /// Prompt template to use; None uses the built-in DeepSeek default
+    #[serde(default)]
+    pub prompt_template_id: Option<String>,
 }
 
 // ===== HELPER FUNCTIONS =====
@@ -24,7 +30,7 @@ fn get_api_key(
     env_vars: &[&str],
     db_key: &str,
     state: &State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // First try environment variables (in order of preference)
     for env_var in env_vars {
         if let Ok(key) = std::env::var(env_var) {
@@ -35,80 +41,19 @@ fn get_api_key(
     }
 
     // Fall back to database setting
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.get_setting(db_key)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| {
-            format!(
-                "{} not configured. Set {} environment variable or add it in Settings.",
-                db_key, env_vars[0]
-            )
-        })
+    let db = state.db.lock()?;
+    db.get_setting(db_key)?.ok_or_else(|| {
+        CommandError::Unauthorized(format!(
+            "{} not configured. Set {} environment variable or add it in Settings.",
+            db_key, env_vars[0]
+        ))
+    })
 }
 
-/// Perform web search using DuckDuckGo
-async fn perform_web_search(query: &str, max_results: usize) -> Result<Vec<String>, String> {
-    let client = reqwest::Client::new();
-    let encoded_query = urlencoding::encode(query);
-    let url = format!("https://html.duckduckgo.com/html/?q={}", encoded_query);
-
-    let response = client
-        .get(&url)
-        .header(
-            "User-Agent",
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
-        )
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let html = response.text().await.map_err(|e| e.to_string())?;
-
-    // Parse search results (basic extraction)
-    let mut results = Vec::new();
-
-    // Compile regex for HTML tag removal once (used in both loops)
-    let html_tag_regex = regex::Regex::new(r"<[^>]+>").unwrap();
-
-    // Simple regex-based extraction of result snippets
-    let re = regex::Regex::new(r#"<a class="result__snippet"[^>]*>(.*?)</a>"#)
-        .map_err(|e| e.to_string())?;
-
-    for cap in re.captures_iter(&html).take(max_results) {
-        if let Some(snippet) = cap.get(1) {
-            let text = snippet.as_str();
-            // Unescape HTML entities
-            let unescaped = html_escape::decode_html_entities(text).to_string();
-            // Remove HTML tags
-            let clean = html_tag_regex
-                .replace_all(&unescaped, "")
-                .to_string();
-
-            if !clean.trim().is_empty() {
-                results.push(clean.trim().to_string());
-            }
-        }
-    }
-
-    // Also try to extract titles
-    let title_re =
-        regex::Regex::new(r#"<a class="result__a"[^>]*>(.*?)</a>"#).map_err(|e| e.to_string())?;
-
-    for cap in title_re.captures_iter(&html).take(max_results) {
-        if let Some(title) = cap.get(1) {
-            let text = title.as_str();
-            let unescaped = html_escape::decode_html_entities(text).to_string();
-            let clean = html_tag_regex
-                .replace_all(&unescaped, "")
-                .to_string();
-
-            if !clean.trim().is_empty() {
-                results.push(format!("Title: {}", clean.trim()));
-            }
-        }
-    }
-
-    Ok(results)
+/// Search provider selected in Settings
+fn search_provider(state: &State<'_, AppState>) -> Result<Box<dyn web_search::SearchProvider>, CommandError> {
+    let db = state.db.lock()?;
+    web_search::provider_from_settings(&db).map_err(CommandError::from)
 }
 
 /// Sanitize a string to be used as a filename
@@ -161,31 +106,41 @@ pub async fn research_flight_with_deepseek(
     request: ResearchRequest,
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<crate::deepseek::ResearchResult, String> {
+) -> Result<crate::deepseek::ResearchResult, CommandError> {
     // Get flight details
     let flight = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.get_flight(&request.flight_id)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "Flight not found".to_string())?
+        let db = state.db.lock()?;
+        db.get_flight(&request.flight_id)?
+            .ok_or_else(|| CommandError::not_found("Flight", &request.flight_id))?
     };
 
     // Get DeepSeek API key from environment or settings
     let api_key = get_api_key(&["DEEPSEEK_API_KEY"], "deepseek_api_key", &state)?;
 
+    let prompt_template = {
+        let db = state.db.lock()?;
+        prompt_templates::resolve_body(
+            &db.conn,
+            request.prompt_template_id.as_deref(),
+            prompt_templates::DEEPSEEK_DEFAULT_ID,
+        )
+        .map_err(|e| CommandError::Validation(e.to_string()))?
+    };
+
     // Build memory query string
     let memory_query = format!(
-        "flight:{} news:{} events:{} weather:{} passengers:{}",
+        "flight:{} news:{} events:{} weather:{} passengers:{} template:{}",
         request.flight_id,
         request.research_news,
         request.research_events,
         request.research_weather,
-        request.research_passengers
+        request.research_passengers,
+        request.prompt_template_id.as_deref().unwrap_or(prompt_templates::DEEPSEEK_DEFAULT_ID)
     );
 
     // Check memory cache for recent research (within 24 hours)
     {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let db = state.db.lock()?;
         let conn = db.get_connection();
 
         if let Ok(Some(memory)) = crate::agent_memory::find_similar_memory(
@@ -246,75 +201,114 @@ pub async fn research_flight_with_deepseek(
         .unwrap_or(&flight.departure_datetime)
         .to_string();
 
-    // Perform web searches using DuckDuckGo
-    let mut search_results = Vec::new();
+    // Weather is researched once per flight and date; a stored record (even an
+    // undetermined one) means it is not searched for again
+    let stored_weather = if request.research_weather {
+        let db = state.db.lock()?;
+        db.get_flight_weather(&flight.id)?
+            .filter(|w| w.observation_date == date)
+    } else {
+        None
+    };
+    let research_weather = request.research_weather && stored_weather.is_none();
+    let known_weather = stored_weather.as_ref().and_then(stored_weather_info);
+
+    // Perform web searches with the provider selected in Settings
+    let search = search_provider(&state)?;
+    let mut searches = Vec::new();
 
     if request.research_news {
         let news_query = format!("{} {} news", location_str, date);
-        if let Ok(results) = perform_web_search(&news_query, 5).await {
-            search_results.extend(results);
+        if let Ok(results) = search.search(&news_query, 5).await {
+            searches.push(results);
         }
     }
 
     if request.research_events {
         let events_query = format!("{} {} events conferences", location_str, date);
-        if let Ok(results) = perform_web_search(&events_query, 5).await {
-            search_results.extend(results);
+        if let Ok(results) = search.search(&events_query, 5).await {
+            searches.push(results);
         }
     }
 
-    if request.research_weather {
+    if research_weather {
         let weather_query = format!("{} {} weather conditions", location_str, date);
-        if let Ok(results) = perform_web_search(&weather_query, 3).await {
-            search_results.extend(results);
+        if let Ok(results) = search.search(&weather_query, 3).await {
+            searches.push(results);
         }
     }
 
     if request.research_passengers && !passenger_names.is_empty() {
         for passenger in &passenger_names {
             let passenger_query = format!("{} {} {}", passenger, location_str, date);
-            if let Ok(results) = perform_web_search(&passenger_query, 3).await {
-                search_results.extend(results);
+            if let Ok(results) = search.search(&passenger_query, 3).await {
+                searches.push(results);
             }
         }
     }
 
+    let (search_results, search_stats) =
+        web_search::prepare_results(searches, web_search::DEFAULT_TOKEN_BUDGET);
+
     // If no search results, return empty result
     if search_results.is_empty() {
+        if research_weather {
+            let db = state.db.lock()?;
+            db.save_flight_weather(&flight.id, &date, Some(&location_str), None, "deepseek_research")?;
+        }
         return Ok(crate::deepseek::ResearchResult {
             summary: "No web search results found for the specified criteria.".to_string(),
             news_stories: Vec::new(),
             events: Vec::new(),
-            weather: None,
+            weather: known_weather,
             passenger_mentions: Vec::new(),
+            search_stats,
         });
     }
 
     // Build DeepSeek research request
     let deepseek_request = crate::deepseek::ResearchRequest {
         date: date.clone(),
-        location: location_str,
+        location: location_str.clone(),
         passenger_names,
         research_news: request.research_news,
         research_events: request.research_events,
-        research_weather: request.research_weather,
+        research_weather,
         research_passengers: request.research_passengers,
+        prompt_template: Some(prompt_template),
     };
 
     // Emit thinking event
     crate::agent_tracking::emit_agent_thinking(&app_handle, "DeepSeek", "deepseek-chat");
 
     // Call DeepSeek to analyze search results
-    let result = crate::deepseek::research_flight_context(deepseek_request, search_results, &api_key)
+    let mut result = crate::deepseek::research_flight_context(deepseek_request, search_results, &api_key)
         .await;
 
+    if let Ok(research_result) = &mut result {
+        research_result.search_stats = search_stats;
+        if research_weather {
+            research_result.weather = research_result.weather.take().filter(|w| w.is_determined());
+            let db = state.db.lock()?;
+            db.save_flight_weather(
+                &flight.id,
+                &date,
+                Some(&location_str),
+                research_result.weather.as_ref(),
+                "deepseek_research",
+            )?;
+        } else if known_weather.is_some() {
+            research_result.weather = known_weather;
+        }
+    }
+
     // Emit completion or error event and store in memory
     match &result {
         Ok(research_result) => {
             crate::agent_tracking::emit_agent_complete(&app_handle, "DeepSeek", "deepseek-chat", 0, 0);
 
             // Store result in agent memory
-            let db = state.db.lock().map_err(|e| e.to_string())?;
+            let db = state.db.lock()?;
             let conn = db.get_connection();
 
             // Serialize result for storage
@@ -346,7 +340,68 @@ pub async fn research_flight_with_deepseek(
         Err(e) => crate::agent_tracking::emit_agent_error(&app_handle, "DeepSeek", "deepseek-chat", &e.to_string()),
     }
 
-    result.map_err(|e| format!("DeepSeek research failed: {}", e))
+    result.map_err(CommandError::from)
+}
+
+// ===== PROMPT TEMPLATES =====
+
+/// Built-in and user-defined research prompt templates
+#[tauri::command]
+pub fn list_prompt_templates(state: State<'_, AppState>) -> Result<Vec<PromptTemplate>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    prompt_templates::list_templates(&db.conn).map_err(|e| e.to_string())
+}
+
+/// Create (no id) or update a prompt template; rejected when placeholders are missing or unknown
+#[tauri::command]
+pub fn save_prompt_template(
+    template: PromptTemplateInput,
+    state: State<'_, AppState>,
+) -> Result<PromptTemplate, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    prompt_templates::save_template(&db.conn, &template).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_prompt_template(template_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    prompt_templates::delete_template(&db.conn, &template_id).map_err(|e| e.to_string())
+}
+
+/// Check a template body without saving it
+#[tauri::command]
+pub fn validate_prompt_template(body: String) -> Result<PromptTemplateValidation, String> {
+    Ok(prompt_templates::validate(&body))
+}
+
+/// Weather stored for a flight by research, or None if it was never researched
+/// A record with `determined: false` means research found nothing.
+#[tauri::command]
+pub fn get_flight_weather(
+    flight_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<crate::models::FlightWeather>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_flight_weather(&flight_id).map_err(|e| e.to_string())
+}
+
+/// Rebuild the research result's weather from a determined stored record
+fn stored_weather_info(weather: &crate::models::FlightWeather) -> Option<crate::deepseek::WeatherInfo> {
+    if !weather.determined {
+        return None;
+    }
+    Some(crate::deepseek::WeatherInfo {
+        condition: weather.conditions.clone().unwrap_or_default(),
+        temperature: weather
+            .temperature_c
+            .map(|t| format!("{:.0}°C", t))
+            .unwrap_or_default(),
+        description: weather.description.clone().unwrap_or_default(),
+        temperature_c: weather.temperature_c,
+        wind_direction_deg: weather.wind_direction_deg,
+        wind_speed_kt: weather.wind_speed_kt,
+        visibility_km: weather.visibility_km,
+    })
 }
 
 // ===== GROK AI RESEARCH =====
@@ -357,9 +412,10 @@ pub async fn research_flight_with_grok(
     research_topics: Vec<String>,
     model_name: String, // "grok-4-fast-non-reasoning", "grok-4-fast-reasoning", or "grok-code-fast-1"
     custom_query: Option<String>, // Optional custom query for freeform chat
+    prompt_template_id: Option<String>, // None uses the built-in Grok default
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<crate::grok::GrokAnalysisResult, String> {
+) -> Result<crate::grok::GrokAnalysisResult, CommandError> {
     // Validate model name
     let valid_models = [
         "grok-4-fast-non-reasoning",
@@ -367,22 +423,29 @@ pub async fn research_flight_with_grok(
         "grok-code-fast-1",
     ];
     if !valid_models.contains(&model_name.as_str()) {
-        return Err(format!(
+        return Err(CommandError::Validation(format!(
             "Invalid model name. Must be one of: {:?}",
             valid_models
-        ));
+        )));
     }
 
     // Get Grok API key from environment or settings (XAI_API_KEY or GROK_API_KEY)
     let api_key = get_api_key(&["XAI_API_KEY", "GROK_API_KEY"], "grok_api_key", &state)?;
 
+    let prompt_template = {
+        let db = state.db.lock()?;
+        prompt_templates::resolve_body(&db.conn, prompt_template_id.as_deref(), prompt_templates::GROK_DEFAULT_ID)
+            .map_err(|e| CommandError::Validation(e.to_string()))?
+    };
+
     // Build memory query string for standard research (not custom queries)
     let memory_query = if custom_query.is_none() {
         format!(
-            "flight:{} topics:{:?} model:{}",
+            "flight:{} topics:{:?} model:{} template:{}",
             flight_id,
             research_topics,
-            model_name
+            model_name,
+            prompt_template_id.as_deref().unwrap_or(prompt_templates::GROK_DEFAULT_ID)
         )
     } else {
         custom_query.clone().unwrap_or_default()
@@ -390,7 +453,7 @@ pub async fn research_flight_with_grok(
 
     // Check memory cache for recent research (within 24 hours) - skip for custom queries
     if custom_query.is_none() {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let db = state.db.lock()?;
         let conn = db.get_connection();
 
         if let Ok(Some(memory)) = crate::agent_memory::find_similar_memory(
@@ -424,15 +487,14 @@ pub async fn research_flight_with_grok(
             Err(e) => crate::agent_tracking::emit_agent_error(&app_handle, "Grok", &model_name, &e.to_string()),
         }
 
-        return result.map_err(|e| format!("Grok chat failed: {}", e));
+        return result.map_err(CommandError::from);
     }
 
     // Get flight details
     let flight = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.get_flight(&flight_id)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "Flight not found".to_string())?
+        let db = state.db.lock()?;
+        db.get_flight(&flight_id)?
+            .ok_or_else(|| CommandError::not_found("Flight", &flight_id))?
     };
 
     let flight_route = format!("{} to {}", flight.departure_airport, flight.arrival_airport);
@@ -460,7 +522,8 @@ pub async fn research_flight_with_grok(
     };
 
     // Perform web searches based on research topics
-    let mut search_results = Vec::new();
+    let search = search_provider(&state)?;
+    let mut searches = Vec::new();
 
     for topic in &research_topics {
         let query = match topic.as_str() {
@@ -471,11 +534,14 @@ pub async fn research_flight_with_grok(
             _ => continue,
         };
 
-        if let Ok(results) = perform_web_search(&query, 3).await {
-            search_results.extend(results);
+        if let Ok(results) = search.search(&query, 3).await {
+            searches.push(results);
         }
     }
 
+    let (search_results, search_stats) =
+        web_search::prepare_results(searches, web_search::DEFAULT_TOKEN_BUDGET);
+
     // Emit thinking event
     crate::agent_tracking::emit_agent_thinking(&app_handle, "Grok", &model_name);
 
@@ -488,8 +554,13 @@ pub async fn research_flight_with_grok(
         search_results,
         &api_key,
         &model_name,
+        Some(&prompt_template),
     )
-    .await;
+    .await
+    .map(|mut analysis_result| {
+        analysis_result.search_stats = search_stats;
+        analysis_result
+    });
 
     // Emit completion or error event and store in memory
     match &result {
@@ -497,7 +568,7 @@ pub async fn research_flight_with_grok(
             crate::agent_tracking::emit_agent_complete(&app_handle, "Grok", &model_name, 0, 0);
 
             // Store result in agent memory
-            let db = state.db.lock().map_err(|e| e.to_string())?;
+            let db = state.db.lock()?;
             let conn = db.get_connection();
 
             // Serialize result for storage
@@ -529,31 +600,37 @@ pub async fn research_flight_with_grok(
         Err(e) => crate::agent_tracking::emit_agent_error(&app_handle, "Grok", &model_name, &e.to_string()),
     }
 
-    result.map_err(|e| format!("Grok analysis failed: {}", e))
+    result.map_err(CommandError::from)
 }
 
 #[tauri::command]
 pub async fn multi_provider_flight_research(
     flight_id: String,
     research_topics: Vec<String>,
+    prompt_template_id: Option<String>, // Applied to both providers; None uses each one's default
     state: State<'_, AppState>,
-) -> Result<crate::grok::MultiProviderAnalysis, String> {
+) -> Result<crate::grok::MultiProviderAnalysis, CommandError> {
     // Get API keys from environment or settings
     let grok_api_key = get_api_key(&["XAI_API_KEY", "GROK_API_KEY"], "grok_api_key", &state).ok();
     let deepseek_api_key = get_api_key(&["DEEPSEEK_API_KEY"], "deepseek_api_key", &state).ok();
 
     if grok_api_key.is_none() && deepseek_api_key.is_none() {
-        return Err(
+        return Err(CommandError::Unauthorized(
             "At least one AI provider API key must be configured (Grok or DeepSeek)".to_string(),
-        );
+        ));
     }
 
-    // Get flight details
-    let flight = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        db.get_flight(&flight_id)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "Flight not found".to_string())?
+    // Get flight details and the chosen prompt template
+    let (flight, prompt_template) = {
+        let db = state.db.lock()?;
+        let flight = db
+            .get_flight(&flight_id)?
+            .ok_or_else(|| "Flight not found".to_string())?;
+        let prompt_template = prompt_template_id
+            .as_deref()
+            .map(|id| prompt_templates::resolve_body(&db.conn, Some(id), id))
+            .transpose()?;
+        (flight, prompt_template)
     };
 
     let flight_route = format!("{} to {}", flight.departure_airport, flight.arrival_airport);
@@ -580,23 +657,29 @@ pub async fn multi_provider_flight_research(
         Vec::new()
     };
 
-    // Perform comprehensive web searches
-    let mut search_results = Vec::new();
-    for topic in &research_topics {
-        let query = match topic.as_str() {
-            "news" => format!("{} {} news", flight_route, flight_date),
-            "events" => format!("{} {} events", flight_route, flight_date),
-            "weather" => format!("{} {} weather", flight_route, flight_date),
-            "aviation" => format!("{} flight aviation", flight_route),
-            _ => continue,
-        };
-        if let Ok(results) = perform_web_search(&query, 5).await {
-            search_results.extend(results);
-        }
-    }
+    // Perform comprehensive web searches, a few at a time
+    let search = search_provider(&state)?;
+    let pacing = web_search::SearchPacing::from_settings(&*state.db.lock()?);
+    let queries: Vec<String> = research_topics
+        .iter()
+        .filter_map(|topic| match topic.as_str() {
+            "news" => Some(format!("{} {} news", flight_route, flight_date)),
+            "events" => Some(format!("{} {} events", flight_route, flight_date)),
+            "weather" => Some(format!("{} {} weather", flight_route, flight_date)),
+            "aviation" => Some(format!("{} flight aviation", flight_route)),
+            _ => None,
+        })
+        .collect();
+    let search_started = std::time::Instant::now();
+    let searches = web_search::search_all(search.as_ref(), &queries, 5, pacing).await;
+    let search_timing = crate::grok::ProviderTiming::new("Web search", search_started, &Ok(()));
+
+    // Both providers get the same results, so duplicates would be paid for twice
+    let (search_results, search_stats) =
+        web_search::prepare_results(searches, web_search::DEFAULT_TOKEN_BUDGET);
 
     // Call multi-provider analysis
-    crate::grok::multi_provider_analysis(
+    let mut analysis = crate::grok::multi_provider_analysis(
         &flight_route,
         &flight_date,
         passenger_names,
@@ -604,9 +687,12 @@ pub async fn multi_provider_flight_research(
         search_results,
         grok_api_key.as_deref(),
         deepseek_api_key.as_deref(),
+        prompt_template.as_deref(),
     )
-    .await
-    .map_err(|e| format!("Multi-provider analysis failed: {}", e))
+    .await?;
+    analysis.timings.insert(0, search_timing);
+    analysis.search_stats = search_stats;
+    Ok(analysis)
 }
 
 // ===== GEMINI CHAT =====
@@ -624,10 +710,18 @@ pub async fn chat_with_gemini(
         &state,
     )?;
 
+    // Same question to the same model: answer from the cache
+    let cache_key = chat_cache_key(&model, &query);
+    if let Some(content) = cached_chat_response(&state, &cache_key)? {
+        return Ok(crate::gemini::GeminiChatResult { content, tokens_used: None, cached: true });
+    }
+
     // Call Gemini chat
-    crate::gemini::chat_with_gemini(&query, &api_key, &model)
+    let result = crate::gemini::chat_with_gemini(&query, &api_key, &model)
         .await
-        .map_err(|e| format!("Gemini chat failed: {}", e))
+        .map_err(|e| format!("Gemini chat failed: {}", e))?;
+    cache_chat_response(&state, &cache_key, &model, &result.content);
+    Ok(result)
 }
 
 // ===== DEEPSEEK CHAT =====
@@ -640,10 +734,60 @@ pub async fn chat_with_deepseek(
     // Get DeepSeek API key from environment or settings
     let api_key = get_api_key(&["DEEPSEEK_API_KEY"], "deepseek_api_key", &state)?;
 
+    let cache_key = chat_cache_key("deepseek-chat", &query);
+    if let Some(content) = cached_chat_response(&state, &cache_key)? {
+        return Ok(crate::deepseek::DeepSeekChatResult { content, tokens_used: None, cached: true });
+    }
+
     // Call DeepSeek chat
-    crate::deepseek::chat_with_deepseek(&query, &api_key)
+    let result = crate::deepseek::chat_with_deepseek(&query, &api_key)
         .await
-        .map_err(|e| format!("DeepSeek chat failed: {}", e))
+        .map_err(|e| format!("DeepSeek chat failed: {}", e))?;
+    cache_chat_response(&state, &cache_key, "deepseek-chat", &result.content);
+    Ok(result)
+}
+
+/// How long a chat answer is reused when chat_cache_ttl_hours is not set; chat is about
+/// current events more often than research, so this is shorter than the AI cache default
+const CHAT_CACHE_TTL_HOURS: i64 = 24;
+
+/// Chat answers are cached per model; the model is also the provider used for pricing
+fn chat_cache_key(model: &str, query: &str) -> String {
+    format!("chat:{}:{}", model, query.trim())
+}
+
+/// The chat_cache_ttl_hours setting; 0 turns chat caching off
+fn chat_cache_ttl_hours(db: &crate::database::Database) -> i64 {
+    db.get_setting("chat_cache_ttl_hours")
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|hours| *hours >= 0)
+        .unwrap_or(CHAT_CACHE_TTL_HOURS)
+}
+
+fn cached_chat_response(state: &State<'_, AppState>, cache_key: &str) -> Result<Option<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let ttl_hours = chat_cache_ttl_hours(&db);
+    if ttl_hours == 0 {
+        return Ok(None);
+    }
+    // Entries stored under a longer TTL expire by the current one
+    let cached = crate::ai_cache::lookup_within(&db.conn, cache_key, Some(ttl_hours)).map_err(|e| e.to_string())?;
+    Ok(cached.map(|c| c.response_text))
+}
+
+/// A failure to cache only costs a later provider call, so it is logged, not returned
+fn cache_chat_response(state: &State<'_, AppState>, cache_key: &str, model: &str, content: &str) {
+    let stored = state.db.lock().map_err(|e| anyhow::anyhow!(e.to_string())).and_then(|db| {
+        match chat_cache_ttl_hours(&db) {
+            0 => Ok(()),
+            ttl_hours => crate::ai_cache::store(&db.conn, cache_key, model, content, None, Some(ttl_hours)),
+        }
+    });
+    if let Err(e) = stored {
+        tracing::warn!("Failed to cache {} chat response: {}", model, e);
+    }
 }
 
 // ===== RESEARCH REPORTS =====
@@ -669,6 +813,7 @@ pub fn get_research_report(
         .map_err(|e| e.to_string())
 }
 
+/// Active (non-archived) reports
 #[tauri::command]
 pub fn list_research_reports(
     user_id: String,
@@ -681,6 +826,23 @@ pub fn list_research_reports(
         .map_err(|e| e.to_string())
 }
 
+/// Like list_research_reports, plus the total count and whether more pages follow
+#[tauri::command]
+pub fn list_research_reports_page(
+    user_id: String,
+    limit: i64,
+    offset: i64,
+    state: State<'_, AppState>,
+) -> Result<crate::models::Page<crate::models::ResearchReport>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.read_consistent(|db| {
+        let items = db.list_research_reports(&user_id, limit, offset)?;
+        let total = db.count_research_reports(&user_id)?;
+        Ok(crate::models::Page::new(items, total, limit, offset))
+    })
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn delete_research_report(report_id: String, state: State<'_, AppState>) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
@@ -695,6 +857,151 @@ pub fn count_research_reports(user_id: String, state: State<'_, AppState>) -> Re
         .map_err(|e| e.to_string())
 }
 
+// ===== REPORT ARCHIVAL =====
+
+/// When research reports are archived, configured through settings:
+/// research_archive_enabled, research_archive_after_days, research_archive_min_confidence.
+/// Archiving runs on demand (apply_report_archive_policy) and, once enabled, daily in the
+/// background; reading the report list never archives anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportArchivePolicy {
+    pub enabled: bool,
+    /// Reports older than this many days are candidates
+    pub max_age_days: u32,
+    /// Candidates with a confidence score below this are archived
+    pub min_confidence: f64,
+}
+
+impl ReportArchivePolicy {
+    fn from_settings(db: &crate::database::Database) -> Self {
+        let setting = |key: &str| db.get_setting(key).ok().flatten();
+        Self {
+            enabled: setting("research_archive_enabled").as_deref() == Some("true"),
+            max_age_days: setting("research_archive_after_days")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(90),
+            min_confidence: setting("research_archive_min_confidence")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(0.5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedReportsExport {
+    pub file_path: String,
+    pub report_count: usize,
+    pub purged_count: usize,
+}
+
+#[tauri::command]
+pub fn get_report_archive_policy(state: State<'_, AppState>) -> Result<ReportArchivePolicy, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(ReportArchivePolicy::from_settings(&db))
+}
+
+/// Archive reports matching the configured policy now; returns how many were archived
+#[tauri::command]
+pub fn apply_report_archive_policy(user_id: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let policy = ReportArchivePolicy::from_settings(&db);
+    db.archive_research_reports(&user_id, policy.max_age_days, policy.min_confidence)
+        .map_err(|e| e.to_string())
+}
+
+/// Delay before the first background archival, so startup is not slowed down
+const ARCHIVAL_STARTUP_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+const ARCHIVAL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
+/// Archive the primary user's reports per the policy, when enabled; how many were archived
+fn archive_primary_user_reports(app_handle: &tauri::AppHandle) -> anyhow::Result<usize> {
+    use tauri::Manager;
+    let state = app_handle.state::<AppState>();
+    let db = state.db.lock().map_err(|e| anyhow::anyhow!("Database lock poisoned: {}", e))?;
+    let policy = ReportArchivePolicy::from_settings(&db);
+    if !policy.enabled {
+        return Ok(0);
+    }
+    let Some(user) = db.get_primary_user()? else {
+        return Ok(0);
+    };
+    db.archive_research_reports(&user.id, policy.max_age_days, policy.min_confidence)
+}
+
+/// Background archival, shortly after startup and then daily, while research_archive_enabled is on
+pub(crate) async fn run_report_archival(app_handle: tauri::AppHandle) {
+    tokio::time::sleep(ARCHIVAL_STARTUP_DELAY).await;
+    loop {
+        match archive_primary_user_reports(&app_handle) {
+            Ok(0) => {}
+            Ok(archived) => tracing::info!("Archived {} research report(s)", archived),
+            Err(e) => tracing::warn!("Research report archival failed: {:#}", e),
+        }
+        tokio::time::sleep(ARCHIVAL_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub fn list_archived_reports(
+    user_id: String,
+    limit: i64,
+    offset: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::models::ResearchReport>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_archived_research_reports(&user_id, limit, offset)
+        .map_err(|e| e.to_string())
+}
+
+/// Bundle every archived report into one markdown file under ~/flight-tracker-pro/researcher/archive
+/// With `purge` the archived reports are deleted once the bundle is written.
+#[tauri::command]
+pub fn export_archived_reports(
+    user_id: String,
+    purge: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ArchivedReportsExport, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let reports = db
+        .list_archived_research_reports(&user_id, i64::MAX, 0)
+        .map_err(|e| e.to_string())?;
+    if reports.is_empty() {
+        return Err("No archived reports to export".to_string());
+    }
+
+    let mut markdown = format!("# Archived Research Reports
+
+{} report(s)
+
+", reports.len());
+    for report in &reports {
+        markdown.push('\n');
+        markdown.push_str(&render_report_markdown(report));
+    }
+
+    let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let archive_dir = home_dir.join("flight-tracker-pro").join("researcher").join("archive");
+    std::fs::create_dir_all(&archive_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    let file_path = archive_dir.join(format!(
+        "archived-reports-{}.md",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::write(&file_path, markdown).map_err(|e| format!("Failed to write markdown file: {}", e))?;
+
+    let purged_count = if purge.unwrap_or(false) {
+        db.purge_archived_research_reports(&user_id)
+            .map_err(|e| e.to_string())?
+    } else {
+        0
+    };
+
+    Ok(ArchivedReportsExport {
+        file_path: file_path.to_string_lossy().to_string(),
+        report_count: reports.len(),
+        purged_count,
+    })
+}
+
 #[tauri::command]
 pub fn export_research_report_to_markdown(
     report_id: String,
@@ -741,7 +1048,17 @@ pub fn export_research_report_to_markdown(
     let markdown_filename = format!("{}.md", filename);
     let file_path = agent_dir.join(&markdown_filename);
 
-    // Build markdown content
+    let markdown = render_report_markdown(&report);
+
+    // Write to file
+    fs::write(&file_path, markdown).map_err(|e| format!("Failed to write markdown file: {}", e))?;
+
+    // Return the full path
+    Ok(file_path.to_string_lossy().to_string())
+}
+
+/// Markdown rendering of a single research report
+fn render_report_markdown(report: &crate::models::ResearchReport) -> String {
     let mut markdown = String::new();
 
     // Header
@@ -806,9 +1123,96 @@ pub fn export_research_report_to_markdown(
     markdown.push_str("---\n\n");
     markdown.push_str("*Generated by Flight Tracker Pro*\n");
 
-    // Write to file
-    fs::write(&file_path, markdown).map_err(|e| format!("Failed to write markdown file: {}", e))?;
+    markdown
+}
 
-    // Return the full path
-    Ok(file_path.to_string_lossy().to_string())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_chat_cache_ttl_setting() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        assert_eq!(chat_cache_ttl_hours(&db), CHAT_CACHE_TTL_HOURS);
+        db.set_setting("chat_cache_ttl_hours", "0").unwrap();
+        assert_eq!(chat_cache_ttl_hours(&db), 0);
+        db.set_setting("chat_cache_ttl_hours", "6").unwrap();
+        assert_eq!(chat_cache_ttl_hours(&db), 6);
+        for invalid in ["-1", "soon"] {
+            db.set_setting("chat_cache_ttl_hours", invalid).unwrap();
+            assert_eq!(chat_cache_ttl_hours(&db), CHAT_CACHE_TTL_HOURS);
+        }
+    }
+
+    #[test]
+    fn test_archive_old_low_confidence_reports() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute("INSERT INTO users (id, name) VALUES ('u1', 'Test')", [])
+            .unwrap();
+        for (id, confidence, age_days) in [("old-low", Some(0.2), 200), ("old-high", Some(0.9), 200), ("new-low", None, 1)] {
+            db.conn
+                .execute(
+                    "INSERT INTO research_reports (id, user_id, agent_name, search_query, report_summary, confidence_score, created_at)
+                     VALUES (?1, 'u1', 'Test', 'query', 'summary', ?2, datetime('now', ?3))",
+                    rusqlite::params![id, confidence, format!("-{} days", age_days)],
+                )
+                .unwrap();
+        }
+
+        // Off until the user turns it on
+        let policy = ReportArchivePolicy::from_settings(&db);
+        assert!(!policy.enabled);
+        assert_eq!(policy.max_age_days, 90);
+
+        let archived = db
+            .archive_research_reports("u1", policy.max_age_days, policy.min_confidence)
+            .unwrap();
+        assert_eq!(archived, 1);
+        assert_eq!(db.count_research_reports("u1").unwrap(), 2);
+
+        let archived_reports = db.list_archived_research_reports("u1", 10, 0).unwrap();
+        assert_eq!(archived_reports.len(), 1);
+        assert_eq!(archived_reports[0].id, "old-low");
+        assert!(render_report_markdown(&archived_reports[0]).starts_with("# Research Report: query"));
+    }
+
+    #[test]
+    fn test_flight_weather_records() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Test');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'EGLL', 'KJFK', '2024-06-01T09:00:00');",
+            )
+            .unwrap();
+        assert!(db.get_flight_weather("f1").unwrap().is_none());
+
+        // Nothing found is still recorded so the weather is not researched again
+        db.save_flight_weather("f1", "2024-06-01", Some("EGLL"), None, "test").unwrap();
+        let unknown = db.get_flight_weather("f1").unwrap().unwrap();
+        assert!(!unknown.determined);
+        assert!(stored_weather_info(&unknown).is_none());
+
+        let weather = crate::deepseek::WeatherInfo {
+            condition: "Light rain".to_string(),
+            temperature: "64°F".to_string(),
+            description: "Overcast with light rain".to_string(),
+            temperature_c: None,
+            wind_direction_deg: Some(240),
+            wind_speed_kt: Some(12.0),
+            visibility_km: None,
+        };
+        assert!(weather.is_determined());
+        db.save_flight_weather("f1", "2024-06-01", Some("EGLL"), Some(&weather), "test").unwrap();
+
+        let stored = db.get_flight_weather("f1").unwrap().unwrap();
+        assert!(stored.determined);
+        assert_eq!(stored.conditions.as_deref(), Some("Light rain"));
+        assert!((stored.temperature_c.unwrap() - 17.78).abs() < 0.01);
+        assert_eq!(stored.wind_direction_deg, Some(240));
+        assert_eq!(stored_weather_info(&stored).unwrap().temperature, "18°C");
+    }
 }
@@ -1,4 +1,8 @@
 use super::AppState;
+use crate::arrival_order::{self, ArrivalOrderScan};
+use crate::flight_sanity::{self, SanityScan};
+use crate::flight_verification::{self, FlightVerification, LookupKey};
+use crate::route_reversal;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use uuid::Uuid;
@@ -6,6 +10,7 @@ use uuid::Uuid;
 // ===== DATA STRUCTURES =====
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct UserCorrection {
     pub id: String,
     pub field_name: String,
@@ -15,6 +20,7 @@ pub struct UserCorrection {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct UserPattern {
     pub pattern_type: String,
     pub pattern_key: String,
@@ -306,9 +312,146 @@ pub fn detect_flight_anomalies(
         }
     }
 
+    if let Some(flight) = db.get_flight(&flight_id).map_err(|e| e.to_string())? {
+        // Impossible distance, ground speed or duration (see flight_sanity.rs)
+        for recorded in flight_sanity::record(&db, &flight).map_err(|e| e.to_string())? {
+            anomalies.push(FlightAnomaly {
+                id: recorded.anomaly_id,
+                flight_id: recorded.flight_id,
+                anomaly_type: recorded.issue.anomaly_type,
+                severity: recorded.issue.severity,
+                description: recorded.issue.description,
+                suggested_fix: Some(recorded.issue.suggested_fix),
+                is_resolved: false,
+            });
+        }
+
+        // Lands before it takes off once both ends are in UTC (see arrival_order.rs)
+        let outcome = arrival_order::record(&db, &flight, &mut Default::default()).map_err(|e| e.to_string())?;
+        if let arrival_order::Outcome::ArrivesFirst(issue) = outcome {
+            anomalies.push(FlightAnomaly {
+                id: issue.anomaly_id.clone().unwrap_or_default(),
+                flight_id: flight_id.clone(),
+                anomaly_type: arrival_order::ANOMALY_TYPE.to_string(),
+                severity: "error".to_string(),
+                description: issue.describe(),
+                suggested_fix: Some(arrival_order::SUGGESTED_FIX.to_string()),
+                is_resolved: false,
+            });
+        }
+
+        // Departure and arrival swapped at entry; recorded once while unresolved
+        let threshold = route_reversal::threshold(&db);
+        if let Some(description) = route_reversal::detect(&db, &flight, threshold).map_err(|e| e.to_string())? {
+            let existing: Option<String> = db.conn
+                .query_row(
+                    "SELECT id FROM flight_anomalies WHERE flight_id = ?1 AND anomaly_type = ?2 AND is_resolved = 0",
+                    [&flight_id, route_reversal::ANOMALY_TYPE],
+                    |row| row.get(0),
+                )
+                .ok();
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = Uuid::new_v4().to_string();
+                    db.conn
+                        .execute(
+                            "INSERT INTO flight_anomalies (id, flight_id, anomaly_type, severity, description, suggested_fix) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                            rusqlite::params![&id, &flight_id, route_reversal::ANOMALY_TYPE, "warning", &description, route_reversal::SUGGESTED_FIX],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    id
+                }
+            };
+            anomalies.push(FlightAnomaly {
+                id,
+                flight_id: flight_id.clone(),
+                anomaly_type: route_reversal::ANOMALY_TYPE.to_string(),
+                severity: "warning".to_string(),
+                description,
+                suggested_fix: Some(route_reversal::SUGGESTED_FIX.to_string()),
+                is_resolved: false,
+            });
+        }
+    }
+
     Ok(anomalies)
 }
 
+/// Swap a flight's departure and arrival airports (see route_reversal.rs)
+#[tauri::command]
+pub fn fix_reversed_route(
+    state: State<'_, AppState>,
+    flight_id: String,
+) -> Result<crate::models::Flight, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    route_reversal::fix(&db, &flight_id).map_err(|e| e.to_string())
+}
+
+/// Run the distance and duration sanity checks over all of a user's flights (see flight_sanity.rs)
+#[tauri::command]
+pub fn check_flight_sanity(
+    state: State<'_, AppState>,
+    user_id: String,
+) -> Result<SanityScan, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    flight_sanity::scan(&db, &user_id).map_err(|e| e.to_string())
+}
+
+/// Flag flights that land before they take off in UTC (see arrival_order.rs); flights without
+/// an arrival time or airport timezones are counted as skipped
+#[tauri::command]
+pub fn detect_arrival_before_departure(
+    state: State<'_, AppState>,
+    user_id: String,
+) -> Result<ArrivalOrderScan, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    arrival_order::scan(&db, &user_id).map_err(|e| e.to_string())
+}
+
+/// Cross-check a flight against the flight-data API (see flight_verification.rs)
+#[tauri::command]
+pub async fn verify_flight_externally(
+    state: State<'_, AppState>,
+    flight_id: String,
+) -> Result<FlightVerification, String> {
+    let (flight, key, cached, provider) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let flight = db
+            .get_flight(&flight_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Flight not found: {}", flight_id))?;
+        let Some(key) = LookupKey::for_flight(&flight) else {
+            return Ok(FlightVerification::unverified(&flight_id, "No flight number recorded"));
+        };
+        let cached = flight_verification::cached_legs(&db.conn, &key).map_err(|e| e.to_string())?;
+        let provider = flight_verification::provider_from_settings(&db).map_err(|e| e.to_string())?;
+        (flight, key, cached, provider)
+    };
+
+    let (legs, from_cache) = match cached {
+        Some(legs) => (legs, true),
+        None => {
+            let Some(provider) = provider else {
+                return Ok(FlightVerification::unverified(
+                    &flight_id,
+                    "flight_data_api_key is not configured. Add it in Settings to verify flights.",
+                ));
+            };
+            let legs = provider
+                .flights_on(&key.flight_number, &key.date)
+                .await
+                .map_err(|e| e.to_string())?;
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            flight_verification::store_legs(&db.conn, &key, &legs).map_err(|e| e.to_string())?;
+            (legs, false)
+        }
+    };
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    flight_verification::record(&db, &flight, legs, from_cache).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_pending_anomalies(
     state: State<'_, AppState>,
@@ -379,6 +522,7 @@ pub fn find_duplicates(
                 "SELECT id, departure_airport, arrival_airport, departure_datetime, flight_number
                  FROM flights
                  WHERE id != ?1
+                 AND deleted_at IS NULL
                  AND departure_airport = ?2
                  AND arrival_airport = ?3
                  AND abs(julianday(departure_datetime) - julianday(?4)) < 1",
@@ -520,6 +664,7 @@ pub fn get_route_prediction(
 // ===== FUEL PRICE HISTORY =====
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn save_fuel_price(
     state: State<'_, AppState>,
     airport_code: Option<String>,
@@ -625,18 +770,7 @@ pub fn cache_ai_response(
     ttl_hours: Option<i32>,
 ) -> Result<bool, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    let id = Uuid::new_v4().to_string();
-    let query_hash = format!("{:x}", md5::compute(&query_text));
-    let ttl = ttl_hours.unwrap_or(24 * 7); // Default 1 week
-    let expires = format!("datetime('now', '+{} hours')", ttl);
-
-    db.conn
-        .execute(
-            &format!("INSERT INTO ai_response_cache (id, query_hash, provider, query_text, response_text, confidence, expires_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, {})
-             ON CONFLICT(query_hash) DO UPDATE SET hit_count = hit_count + 1", expires),
-            rusqlite::params![&id, &query_hash, &provider, &query_text, &response_text, confidence],
-        )
+    crate::ai_cache::store(&db.conn, &query_text, &provider, &response_text, confidence, ttl_hours.map(i64::from))
         .map_err(|e| e.to_string())?;
 
     Ok(true)
@@ -648,25 +782,29 @@ pub fn get_cached_ai_response(
     query_text: String,
 ) -> Result<Option<String>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    let query_hash = format!("{:x}", md5::compute(&query_text));
+    let cached = crate::ai_cache::lookup(&db.conn, &query_text).map_err(|e| e.to_string())?;
 
-    let result: Option<String> = db.conn
-        .query_row(
-            "SELECT response_text FROM ai_response_cache WHERE query_hash = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))",
-            [&query_hash],
-            |row| row.get(0),
-        )
-        .ok();
+    Ok(cached.map(|c| c.response_text))
+}
 
-    // Increment hit count
-    if result.is_some() {
-        let _ = db.conn.execute(
-            "UPDATE ai_response_cache SET hit_count = hit_count + 1 WHERE query_hash = ?1",
-            [&query_hash],
-        );
-    }
+/// Entries, hits and estimated savings, with the hit rate over the last `period_days` (default 30)
+#[tauri::command]
+pub fn get_ai_cache_stats(
+    state: State<'_, AppState>,
+    period_days: Option<i64>,
+) -> Result<crate::ai_cache::AiCacheStats, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    crate::ai_cache::stats(&db.conn, period_days).map_err(|e| e.to_string())
+}
 
-    Ok(result)
+/// Remove entries older than `older_than_days`, or just the expired ones when not given
+#[tauri::command]
+pub fn clear_ai_cache(
+    state: State<'_, AppState>,
+    older_than_days: Option<i64>,
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    crate::ai_cache::clear(&db.conn, older_than_days).map_err(|e| e.to_string())
 }
 
 // ===== STATS =====
@@ -733,7 +871,8 @@ pub fn populate_route_statistics(
                 AVG(distance_km) as avg_distance,
                 COUNT(*) as flight_count
              FROM flights
-             WHERE departure_airport IS NOT NULL
+             WHERE deleted_at IS NULL
+               AND departure_airport IS NOT NULL
                AND arrival_airport IS NOT NULL
                AND departure_airport != ''
                AND arrival_airport != ''
@@ -742,6 +881,7 @@ pub fn populate_route_statistics(
         )
         .map_err(|e| e.to_string())?;
 
+    #[allow(clippy::type_complexity)]
     let routes: Vec<(String, String, Option<f64>, Option<f64>, i32)> = stmt
         .query_map([], |row| {
             Ok((
@@ -778,7 +918,7 @@ pub fn populate_route_statistics(
         match result {
             Ok(1) => routes_added += 1,
             Ok(_) => routes_updated += 1,
-            Err(e) => eprintln!("Failed to insert route {}->{}: {}", dep, arr, e),
+            Err(e) => tracing::warn!("Failed to insert route {}->{}: {}", dep, arr, e),
         }
     }
 
@@ -0,0 +1,352 @@
+// Self-test / health check
+// Runs the checks worth doing before filing a bug and reports pass/fail with remediation hints
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{Manager, State};
+
+use super::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not applicable, e.g. a provider without a configured key
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub remediation: Option<String>,
+}
+
+impl SelfTestCheck {
+    fn pass(name: &str, message: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Pass, message: message.into(), remediation: None }
+    }
+
+    fn with_status(name: &str, status: CheckStatus, message: impl Into<String>, remediation: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+            remediation: Some(remediation.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    /// True when no check failed (warnings and skips are allowed)
+    pub healthy: bool,
+    pub ran_at: String,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+/// An AI provider whose key the app reads from the environment or settings
+pub(super) struct Provider {
+    pub(super) name: &'static str,
+    pub(super) env_vars: &'static [&'static str],
+    pub(super) setting: &'static str,
+}
+
+pub(super) const PROVIDERS: [Provider; 3] = [
+    Provider {
+        name: "gemini",
+        env_vars: &["GENAI_API_KEY", "GOOGLE_GENAI_API_KEY", "GEMINI_API_KEY"],
+        setting: "gemini_api_key",
+    },
+    Provider { name: "deepseek", env_vars: &["DEEPSEEK_API_KEY"], setting: "deepseek_api_key" },
+    Provider { name: "grok", env_vars: &["XAI_API_KEY", "GROK_API_KEY"], setting: "grok_api_key" },
+];
+
+/// Run every health check and return a per-check report
+///
+/// Checks the database, schema and orphaned records, the bundled airports.csv, each
+/// configured AI provider key (with a cheap model-list call), the app data directory and
+/// the agent server ports.
+#[tauri::command]
+pub async fn run_self_test(app_handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<SelfTestReport, String> {
+    let mut checks = Vec::new();
+
+    let provider_keys: Vec<(&Provider, Option<String>)> = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        checks.push(check_database(&db));
+        checks.push(check_orphans(&db));
+        PROVIDERS
+            .iter()
+            .map(|provider| (provider, provider_key(&db, provider)))
+            .collect()
+    };
+
+    checks.push(check_airports_csv(&app_handle));
+
+    for (provider, key) in provider_keys {
+        checks.push(check_provider(provider, key).await);
+    }
+
+    checks.push(check_app_data_dir(&app_handle));
+    checks.push(check_agent_server().await);
+    checks.push(check_command_registry());
+
+    Ok(SelfTestReport {
+        healthy: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        ran_at: chrono::Utc::now().to_rfc3339(),
+        checks,
+    })
+}
+
+fn check_database(db: &crate::database::Database) -> SelfTestCheck {
+    const NAME: &str = "database";
+
+    let integrity: Result<String, _> = db.conn.query_row("PRAGMA quick_check", [], |row| row.get(0));
+    match integrity {
+        Ok(result) if result == "ok" => {}
+        Ok(result) => {
+            return SelfTestCheck::with_status(
+                NAME,
+                CheckStatus::Fail,
+                format!("Integrity check failed: {}", result),
+                "Restore the database from a backup or export your data and reimport it",
+            )
+        }
+        Err(e) => {
+            return SelfTestCheck::with_status(
+                NAME,
+                CheckStatus::Fail,
+                format!("Database is not readable: {}", e),
+                "Check that flight_tracker.db in the app data directory exists and is not locked by another process",
+            )
+        }
+    }
+
+    let latest = crate::migrations::latest_version();
+    match crate::migrations::current_version(&db.conn) {
+        Ok(version) if version == latest => match missing_schema_columns(db) {
+            Ok(missing) if missing.is_empty() => {
+                SelfTestCheck::pass(NAME, format!("Database opens and the schema is at version {}", version))
+            }
+            Ok(missing) => SelfTestCheck::with_status(
+                NAME,
+                CheckStatus::Fail,
+                format!("Schema version {} is missing columns: {}", version, missing.join(", ")),
+                "Restore the database from a backup, or export your data and reimport it into a fresh database",
+            ),
+            Err(e) => SelfTestCheck::with_status(
+                NAME,
+                CheckStatus::Warn,
+                format!("Could not compare the schema: {}", e),
+                "Run the self-test again after restarting the app",
+            ),
+        },
+        Ok(version) => SelfTestCheck::with_status(
+            NAME,
+            CheckStatus::Fail,
+            format!("Schema version {} does not match the expected version {}", version, latest),
+            "Restart the app so migrations run; a newer schema means the database was opened by a newer release",
+        ),
+        Err(e) => SelfTestCheck::with_status(
+            NAME,
+            CheckStatus::Fail,
+            format!("Could not read the schema version: {}", e),
+            "Restart the app so migrations run",
+        ),
+    }
+}
+
+/// table.column pairs a freshly created database has but this one lacks
+/// The reference is built from schema.sql and the migrations, so the list cannot drift
+fn missing_schema_columns(db: &crate::database::Database) -> anyhow::Result<Vec<String>> {
+    const COLUMNS_SQL: &str = "SELECT m.name || '.' || p.name
+         FROM sqlite_master m JOIN pragma_table_info(m.name) p
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'";
+    let columns = |conn: &rusqlite::Connection| -> rusqlite::Result<std::collections::HashSet<String>> {
+        conn.prepare(COLUMNS_SQL)?.query_map([], |row| row.get(0))?.collect()
+    };
+
+    let reference = crate::database::Database::new(std::path::PathBuf::from(":memory:"))?;
+    let actual = columns(&db.conn)?;
+    let mut missing: Vec<String> = columns(&reference.conn)?.into_iter().filter(|c| !actual.contains(c)).collect();
+    missing.sort();
+    Ok(missing)
+}
+
+fn check_orphans(db: &crate::database::Database) -> SelfTestCheck {
+    const NAME: &str = "orphaned_records";
+
+    match crate::orphans::find_orphans(&db.conn) {
+        Ok(report) if report.total == 0 => SelfTestCheck::pass(NAME, "No records point at deleted flights or parents"),
+        Ok(report) => {
+            let tables: Vec<String> = report.orphans.iter().map(|o| format!("{}.{} ({})", o.table, o.column, o.count)).collect();
+            SelfTestCheck::with_status(
+                NAME,
+                CheckStatus::Warn,
+                format!("{} orphaned records: {}", report.total, tables.join(", ")),
+                "Run repair_orphaned_records (Data Editor → Data Quality) to remove or unlink them",
+            )
+        }
+        Err(e) => SelfTestCheck::with_status(
+            NAME,
+            CheckStatus::Warn,
+            format!("Could not check for orphaned records: {}", e),
+            "Run the self-test again after restarting the app",
+        ),
+    }
+}
+
+fn check_airports_csv(app_handle: &tauri::AppHandle) -> SelfTestCheck {
+    const NAME: &str = "airports_csv";
+    const HINT: &str = "Reinstall the app, or in development make sure src-tauri/resources/airports.csv is present";
+
+    // Reports the cache the app actually uses, validated once at startup
+    match super::airport_enrichment::airport_cache_status(app_handle) {
+        Ok(count) => SelfTestCheck::pass(NAME, format!("Loaded {} airports", count)),
+        Err(e) => SelfTestCheck::with_status(NAME, CheckStatus::Fail, e.to_string(), HINT),
+    }
+}
+
+/// Registered commands must all be visible to introspect_commands
+fn check_command_registry() -> SelfTestCheck {
+    const NAME: &str = "command_registry";
+
+    let (missing, unregistered) = super::introspection::registry_drift();
+    if !missing.is_empty() {
+        return SelfTestCheck::with_status(
+            NAME,
+            CheckStatus::Warn,
+            format!("Registered commands missing from introspection: {}", missing.join(", ")),
+            "Add the command's module to COMMAND_SOURCES in commands/introspection.rs",
+        );
+    }
+    if !unregistered.is_empty() {
+        return SelfTestCheck::with_status(
+            NAME,
+            CheckStatus::Warn,
+            format!("Commands defined but not registered: {}", unregistered.join(", ")),
+            "Register them in generate_handler! in lib.rs or remove them",
+        );
+    }
+    SelfTestCheck::pass(NAME, "Every registered command is described by introspect_commands")
+}
+
+/// Key from the first set environment variable, falling back to settings
+pub(super) fn provider_key(db: &crate::database::Database, provider: &Provider) -> Option<String> {
+    provider
+        .env_vars
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| db.get_setting(provider.setting).ok().flatten())
+        .filter(|key| !key.trim().is_empty())
+}
+
+/// Validate a key by listing models, which costs nothing on every provider
+async fn check_provider(provider: &Provider, key: Option<String>) -> SelfTestCheck {
+    let name = format!("ai_provider:{}", provider.name);
+    let Some(key) = key else {
+        return SelfTestCheck::with_status(
+            &name,
+            CheckStatus::Skip,
+            "No API key configured",
+            &format!("Add {} in Settings or set {} to use this provider", provider.setting, provider.env_vars[0]),
+        );
+    };
+
+    let client = reqwest::Client::new();
+    let request = match provider.name {
+        "gemini" => client.get("https://generativelanguage.googleapis.com/v1beta/models").query(&[("key", key.as_str())]),
+        "deepseek" => client.get("https://api.deepseek.com/models").bearer_auth(&key),
+        _ => client.get("https://api.x.ai/v1/models").bearer_auth(&key),
+    };
+
+    match request.timeout(Duration::from_secs(10)).send().await {
+        Ok(response) if response.status().is_success() => SelfTestCheck::pass(&name, "API key accepted"),
+        Ok(response) if matches!(response.status().as_u16(), 400 | 401 | 403) => SelfTestCheck::with_status(
+            &name,
+            CheckStatus::Fail,
+            format!("API key rejected ({})", response.status()),
+            &format!("Check {} in Settings (or {}) for typos or a revoked key", provider.setting, provider.env_vars[0]),
+        ),
+        Ok(response) => SelfTestCheck::with_status(
+            &name,
+            CheckStatus::Warn,
+            format!("Provider returned {}", response.status()),
+            "The provider may be having an outage; try again later",
+        ),
+        Err(e) => SelfTestCheck::with_status(
+            &name,
+            CheckStatus::Warn,
+            format!("Could not reach provider: {}", e),
+            "Check your internet connection, proxy or firewall",
+        ),
+    }
+}
+
+fn check_app_data_dir(app_handle: &tauri::AppHandle) -> SelfTestCheck {
+    const NAME: &str = "app_data_dir";
+    const HINT: &str = "Check the directory permissions and free disk space";
+
+    let dir = match app_handle.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return SelfTestCheck::with_status(NAME, CheckStatus::Fail, format!("No app data directory: {}", e), HINT),
+    };
+
+    let probe = dir.join(".self_test_probe");
+    match std::fs::write(&probe, b"ok").and_then(|_| std::fs::remove_file(&probe)) {
+        Ok(()) => SelfTestCheck::pass(NAME, format!("{} is writable", dir.display())),
+        Err(e) => SelfTestCheck::with_status(NAME, CheckStatus::Fail, format!("{} is not writable: {}", dir.display(), e), HINT),
+    }
+}
+
+async fn check_agent_server() -> SelfTestCheck {
+    const NAME: &str = "agent_server";
+
+    let ws_port = crate::agent_server::AGENT_SERVER_PORT;
+    let mut unbound = Vec::new();
+    for port in [ws_port, ws_port + 1] {
+        let connect = tokio::net::TcpStream::connect(("127.0.0.1", port));
+        if !matches!(tokio::time::timeout(Duration::from_millis(500), connect).await, Ok(Ok(_))) {
+            unbound.push(port.to_string());
+        }
+    }
+
+    if unbound.is_empty() {
+        SelfTestCheck::pass(NAME, format!("Listening on ports {} and {}", ws_port, ws_port + 1))
+    } else {
+        SelfTestCheck::with_status(
+            NAME,
+            CheckStatus::Fail,
+            format!("Nothing listening on port(s) {}", unbound.join(", ")),
+            "Another program may hold the port; close it and restart the app (startup errors are printed to the console)",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fresh_database_passes() {
+        let db = crate::database::Database::new(PathBuf::from(":memory:")).unwrap();
+        let check = check_database(&db);
+        assert_eq!(check.status, CheckStatus::Pass, "{}", check.message);
+
+        db.conn.execute_batch("ALTER TABLE flights DROP COLUMN co2_split_method").unwrap();
+        let check = check_database(&db);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.contains("flights.co2_split_method"), "{}", check.message);
+    }
+
+    #[tokio::test]
+    async fn test_missing_key_is_skipped() {
+        let check = check_provider(&PROVIDERS[0], None).await;
+        assert_eq!(check.status, CheckStatus::Skip);
+        assert!(check.remediation.unwrap().contains("gemini_api_key"));
+    }
+}
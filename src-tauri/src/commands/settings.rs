@@ -1,6 +1,7 @@
 // Settings-related commands
 use tauri::State;
 use super::AppState;
+use crate::write_queue::WritePriority;
 
 #[tauri::command]
 pub fn get_setting(key: String, state: State<'_, AppState>) -> Result<Option<String>, String> {
@@ -8,8 +9,11 @@ pub fn get_setting(key: String, state: State<'_, AppState>) -> Result<Option<Str
     db.get_setting(&key).map_err(|e| e.to_string())
 }
 
+/// Goes through the write queue so it is not held up by a running import
 #[tauri::command]
 pub fn set_setting(key: String, value: String, state: State<'_, AppState>) -> Result<(), String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.set_setting(&key, &value).map_err(|e| e.to_string())
+    state
+        .writer
+        .submit(WritePriority::Interactive, move |db| db.set_setting(&key, &value))
+        .map_err(|e| e.to_string())
 }
@@ -1,7 +1,9 @@
 // Statistics and analytics commands
 use tauri::State;
 use super::AppState;
-use crate::models::FlightStatistics;
+use crate::error::CommandError;
+use crate::models::{FlightStatistics, FlightTotals, StatisticsByClass};
+use crate::units::{UnitSystem, UNIT_SYSTEM_SETTING};
 
 #[tauri::command]
 pub fn get_statistics(
@@ -9,5 +11,46 @@ pub fn get_statistics(
     state: State<'_, AppState>,
 ) -> Result<FlightStatistics, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.get_statistics(&user_id).map_err(|e| e.to_string())
-}
\ No newline at end of file
+    let mut stats = db.get_statistics(&user_id).map_err(|e| e.to_string())?;
+    stats.measurements = Some(
+        UnitSystem::configured(&db)
+            .measure()
+            .distance_km(Some(stats.total_distance_km))
+            .carbon_kg(Some(stats.total_carbon_kg)),
+    );
+    Ok(stats)
+}
+
+/// Hours, distance and flights per aircraft class/category, plus the overall total
+#[tauri::command]
+pub fn get_statistics_by_class(
+    user_id: String,
+    state: State<'_, AppState>,
+) -> Result<StatisticsByClass, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut stats = db.get_statistics_by_class(&user_id).map_err(|e| e.to_string())?;
+    let units = UnitSystem::configured(&db);
+    for totals in stats.classes.iter_mut().map(|class| &mut class.totals).chain([&mut stats.total]) {
+        measure_totals(units, totals);
+    }
+    Ok(stats)
+}
+
+fn measure_totals(units: UnitSystem, totals: &mut FlightTotals) {
+    totals.measurements = Some(
+        units
+            .measure()
+            .distance_km(Some(totals.total_distance_km))
+            .carbon_kg(Some(totals.total_carbon_kg)),
+    );
+}
+
+/// Save the display unit system: "metric", "imperial" or "aviation"
+#[tauri::command]
+pub fn set_unit_system(unit_system: String, state: State<'_, AppState>) -> Result<UnitSystem, CommandError> {
+    let units = UnitSystem::from_tag(&unit_system).ok_or_else(|| {
+        CommandError::Validation(format!("Unknown unit system '{}': use metric, imperial or aviation", unit_system))
+    })?;
+    state.db.lock()?.set_setting(UNIT_SYSTEM_SETTING, units.tag())?;
+    Ok(units)
+}
@@ -0,0 +1,137 @@
+// Trip purpose commands - suggest and record business / personal / training per flight
+use tauri::State;
+
+use super::self_test::{provider_key, PROVIDERS};
+use super::AppState;
+use crate::database::Database;
+use crate::error::CommandError;
+use crate::trip_purpose::{self, FlightContext, PurposeSuggestion};
+
+const GEMINI_MODEL: &str = "gemini-2.5-flash-lite";
+const DEEPSEEK_MODEL: &str = "deepseek-chat";
+const GROK_MODEL: &str = "grok-3-fast";
+
+/// Suggest a purpose for a flight, for the user to confirm with accept_flight_purpose
+///
+/// Uses the AI provider named in the "trip_purpose_provider" setting, or the first one
+/// with an API key. The offline heuristic is used instead with `heuristic_only`, when the
+/// setting is "heuristic", when no key is configured, and when the provider call fails.
+/// AI answers are cached, so asking again about an unchanged flight is free.
+#[tauri::command]
+pub async fn classify_flight_purpose(
+    user_id: String,
+    flight_id: String,
+    heuristic_only: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<PurposeSuggestion, CommandError> {
+    let (context, categories, accepted, provider) = {
+        let db = state.db.lock()?;
+        let context = trip_purpose::load_context(&db, &user_id, &flight_id)?
+            .ok_or_else(|| CommandError::not_found("Flight", &flight_id))?;
+        let provider = if heuristic_only.unwrap_or(false) { None } else { ai_provider(&db)? };
+        (context, trip_purpose::categories(&db)?, trip_purpose::accepted_purpose(&db.conn, &flight_id)?, provider)
+    };
+
+    let mut suggestion = trip_purpose::heuristic(&context, &categories);
+    if let Some((provider, api_key)) = provider {
+        match ai_suggestion(&state, &context, &categories, provider, &api_key).await {
+            Ok((category, confidence, reason, cached)) => {
+                suggestion = PurposeSuggestion {
+                    category,
+                    confidence,
+                    source: provider.to_string(),
+                    reasons: if reason.is_empty() { Vec::new() } else { vec![reason] },
+                    cached,
+                    ..suggestion
+                };
+            }
+            Err(e) => {
+                tracing::warn!("[TripPurpose] {} classification failed, using the heuristic: {}", provider, e);
+                suggestion.reasons.push(format!("{} was unavailable, so this is the offline estimate", provider));
+            }
+        }
+    }
+    suggestion.accepted = accepted;
+    Ok(suggestion)
+}
+
+/// Store the confirmed purpose as the flight's "purpose:<category>" tag, replacing any other
+#[tauri::command]
+pub fn accept_flight_purpose(
+    user_id: String,
+    flight_id: String,
+    category: String,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let category = category.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let db = state.db.lock()?;
+    if db.get_flight(&flight_id)?.filter(|f| f.user_id == user_id).is_none() {
+        return Err(CommandError::not_found("Flight", &flight_id));
+    }
+    let categories = trip_purpose::categories(&db)?;
+    if !categories.contains(&category) {
+        return Err(CommandError::Validation(format!(
+            "Unknown purpose \"{}\"; expected one of {}",
+            category,
+            categories.join(", ")
+        )));
+    }
+    trip_purpose::accept_purpose(&db.conn, &flight_id, &category)?;
+    Ok(category)
+}
+
+/// (provider, API key) to classify with; None means use the heuristic
+fn ai_provider(db: &Database) -> Result<Option<(&'static str, String)>, CommandError> {
+    let setting = db.get_setting("trip_purpose_provider")?.unwrap_or_default().trim().to_lowercase();
+    if setting == "heuristic" {
+        return Ok(None);
+    }
+    if !setting.is_empty() && !PROVIDERS.iter().any(|p| p.name == setting) {
+        return Err(CommandError::Validation(format!(
+            "Unknown trip_purpose_provider \"{}\"; use heuristic, gemini, deepseek or grok",
+            setting
+        )));
+    }
+    Ok(PROVIDERS
+        .iter()
+        .filter(|p| setting.is_empty() || p.name == setting)
+        .find_map(|p| provider_key(db, p).map(|key| (p.name, key))))
+}
+
+/// (category, confidence, reason, served from cache) from an AI provider
+async fn ai_suggestion(
+    state: &State<'_, AppState>,
+    context: &FlightContext,
+    categories: &[String],
+    provider: &str,
+    api_key: &str,
+) -> Result<(String, f64, String, bool), CommandError> {
+    let prompt = trip_purpose::ai_prompt(context, categories);
+    let cache_key = format!("trip_purpose:{}:{}", provider, prompt);
+    let cached = {
+        let db = state.db.lock()?;
+        crate::ai_cache::lookup(&db.conn, &cache_key)?
+    };
+    if let Some(cached) = cached {
+        let (category, confidence, reason) = trip_purpose::parse_ai_response(&cached.response_text, categories)?;
+        return Ok((category, confidence, reason, true));
+    }
+
+    let (model, response) = match provider {
+        "gemini" => (GEMINI_MODEL, crate::gemini::chat_with_gemini(&prompt, api_key, GEMINI_MODEL).await?.content),
+        "deepseek" => (DEEPSEEK_MODEL, crate::deepseek::chat_with_deepseek(&prompt, api_key).await?.content),
+        _ => (GROK_MODEL, crate::grok::chat_with_grok(&prompt, api_key, GROK_MODEL).await?.summary),
+    };
+    let (category, confidence, reason) = trip_purpose::parse_ai_response(&response, categories)?;
+
+    // Only answers that parsed are cached; a failure to cache just costs a later call
+    let stored = state
+        .db
+        .lock()
+        .map_err(|e| anyhow::anyhow!(e.to_string()))
+        .and_then(|db| crate::ai_cache::store(&db.conn, &cache_key, model, &response, Some(confidence), None));
+    if let Err(e) = stored {
+        tracing::warn!("[TripPurpose] Failed to cache {} response: {}", provider, e);
+    }
+    Ok((category, confidence, reason, false))
+}
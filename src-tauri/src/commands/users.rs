@@ -1,7 +1,9 @@
 // User management commands
 use tauri::State;
 use super::AppState;
+use crate::error::CommandError;
 use crate::models::User;
+use crate::user_merge;
 
 #[tauri::command]
 pub fn create_user(user: User, state: State<'_, AppState>) -> Result<String, String> {
@@ -19,4 +21,40 @@ pub fn get_user(user_id: String, state: State<'_, AppState>) -> Result<Option<Us
 pub fn get_primary_user(state: State<'_, AppState>) -> Result<Option<User>, String> {
     let db = state.db.lock().map_err(|e| e.to_string())?;
     db.get_primary_user().map_err(|e| e.to_string())
-}
\ No newline at end of file
+}
+/// Both users must exist and differ
+fn check_merge_users(db: &crate::database::Database, source_user_id: &str, target_user_id: &str) -> Result<(), CommandError> {
+    if source_user_id == target_user_id {
+        return Err(CommandError::Validation("Cannot merge a dataset into itself".to_string()));
+    }
+    for user_id in [source_user_id, target_user_id] {
+        if db.get_user(user_id)?.is_none() {
+            return Err(CommandError::not_found("User", user_id));
+        }
+    }
+    Ok(())
+}
+
+/// What merging one dataset into another would move, drop and rename
+#[tauri::command]
+pub fn preview_merge_users(
+    source_user_id: String,
+    target_user_id: String,
+    state: State<'_, AppState>,
+) -> Result<user_merge::MergePreview, CommandError> {
+    let db = state.db.lock()?;
+    check_merge_users(&db, &source_user_id, &target_user_id)?;
+    Ok(user_merge::preview_merge(&db.conn, &source_user_id, &target_user_id)?)
+}
+
+/// Move all of the source dataset into the target and delete the source user
+#[tauri::command]
+pub fn merge_users(
+    source_user_id: String,
+    target_user_id: String,
+    state: State<'_, AppState>,
+) -> Result<user_merge::UserMergeResult, CommandError> {
+    let db = state.db.lock()?;
+    check_merge_users(&db, &source_user_id, &target_user_id)?;
+    Ok(user_merge::merge_users(&db.conn, &source_user_id, &target_user_id)?)
+}
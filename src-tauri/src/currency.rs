@@ -0,0 +1,200 @@
+// Flight currency
+// A flight keeps the currency its source gave it. Otherwise it is inferred from the departure
+// airport's country (where tickets are usually bought and priced), and failing that it takes
+// the "default_currency" setting. Inference can be turned off with the "infer_currency"
+// setting, in which case the default applies directly.
+//
+// The method that set a flight's currency is stored in flights.currency_source so cost
+// analytics can tell a recorded currency from a guessed one.
+
+use crate::database::Database;
+use crate::models::FlightInput;
+
+pub const DEFAULT_CURRENCY_SETTING: &str = "default_currency";
+pub const INFER_CURRENCY_SETTING: &str = "infer_currency";
+
+/// Used when no default has been configured
+pub const FALLBACK_CURRENCY: &str = "USD";
+
+/// Given by the user or the imported data
+pub const SOURCE_EXPLICIT: &str = "explicit";
+/// Inferred from the departure airport's country
+pub const SOURCE_AIRPORT_COUNTRY: &str = "airport_country";
+/// The configured default currency
+pub const SOURCE_USER_DEFAULT: &str = "user_default";
+
+/// ISO 4217 code for an ISO 3166 alpha-2 country code, as stored in airports.country
+pub fn country_currency(country: &str) -> Option<&'static str> {
+    let currency = match country.trim().to_ascii_uppercase().as_str() {
+        "US" | "PR" | "GU" | "VI" | "AS" | "MP" | "EC" | "SV" | "PA" | "TL" | "MH" | "FM" | "PW" | "BQ" | "TC"
+        | "VG" => "USD",
+        "AT" | "BE" | "CY" | "DE" | "EE" | "ES" | "FI" | "FR" | "GR" | "HR" | "IE" | "IT" | "LT" | "LU" | "LV"
+        | "MT" | "NL" | "PT" | "SI" | "SK" | "MC" | "SM" | "VA" | "AD" | "ME" | "XK" | "GF" | "GP" | "MQ" | "RE"
+        | "YT" | "PM" | "BL" | "MF" => "EUR",
+        "GB" | "GG" | "JE" | "IM" => "GBP",
+        "CH" | "LI" => "CHF",
+        "CA" => "CAD",
+        "MX" => "MXN",
+        "BR" => "BRL",
+        "AR" => "ARS",
+        "CL" => "CLP",
+        "CO" => "COP",
+        "PE" => "PEN",
+        "AU" | "KI" | "NR" | "TV" => "AUD",
+        "NZ" | "CK" => "NZD",
+        "JP" => "JPY",
+        "CN" => "CNY",
+        "HK" => "HKD",
+        "MO" => "MOP",
+        "TW" => "TWD",
+        "KR" => "KRW",
+        "SG" => "SGD",
+        "MY" => "MYR",
+        "TH" => "THB",
+        "ID" => "IDR",
+        "PH" => "PHP",
+        "VN" => "VND",
+        "IN" => "INR",
+        "PK" => "PKR",
+        "LK" => "LKR",
+        "NP" => "NPR",
+        "BD" => "BDT",
+        "MV" => "MVR",
+        "AE" => "AED",
+        "SA" => "SAR",
+        "QA" => "QAR",
+        "KW" => "KWD",
+        "BH" => "BHD",
+        "OM" => "OMR",
+        "JO" => "JOD",
+        "IL" => "ILS",
+        "TR" => "TRY",
+        "EG" => "EGP",
+        "MA" => "MAD",
+        "TN" => "TND",
+        "ZA" => "ZAR",
+        "NG" => "NGN",
+        "KE" => "KES",
+        "TZ" => "TZS",
+        "ET" => "ETB",
+        "GH" => "GHS",
+        "SE" => "SEK",
+        "NO" | "SJ" => "NOK",
+        "DK" | "FO" | "GL" => "DKK",
+        "IS" => "ISK",
+        "PL" => "PLN",
+        "CZ" => "CZK",
+        "HU" => "HUF",
+        "RO" => "RON",
+        "BG" => "BGN",
+        "RS" => "RSD",
+        "UA" => "UAH",
+        "RU" => "RUB",
+        "KZ" => "KZT",
+        "GE" => "GEL",
+        "BS" => "BSD",
+        "BM" => "BMD",
+        "KY" => "KYD",
+        "JM" => "JMD",
+        "DO" => "DOP",
+        "CR" => "CRC",
+        "AG" | "DM" | "GD" | "KN" | "LC" | "VC" | "AI" | "MS" => "XCD",
+        "AW" => "AWG",
+        "CW" | "SX" => "ANG",
+        "BB" => "BBD",
+        "TT" => "TTD",
+        "FJ" => "FJD",
+        "PF" | "NC" | "WF" => "XPF",
+        _ => return None,
+    };
+    Some(currency)
+}
+
+/// A three-letter code, uppercased; None for anything else
+fn normalize(code: &str) -> Option<String> {
+    let code = code.trim();
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())).then(|| code.to_ascii_uppercase())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencySettings {
+    pub default_currency: String,
+    pub infer_from_airport: bool,
+}
+
+impl CurrencySettings {
+    /// Settings from the app settings; inference is on unless turned off
+    pub fn configured(db: &Database) -> Self {
+        let setting = |key| db.get_setting(key).ok().flatten();
+        CurrencySettings {
+            default_currency: setting(DEFAULT_CURRENCY_SETTING)
+                .as_deref()
+                .and_then(normalize)
+                .unwrap_or_else(|| FALLBACK_CURRENCY.to_string()),
+            infer_from_airport: setting(INFER_CURRENCY_SETTING).as_deref() != Some("false"),
+        }
+    }
+}
+
+/// The currency for a new flight and how it was chosen
+pub fn resolve(db: &Database, settings: &CurrencySettings, flight: &FlightInput) -> (String, &'static str) {
+    if let Some(currency) = flight.currency.as_deref().and_then(normalize) {
+        return (currency, SOURCE_EXPLICIT);
+    }
+    if settings.infer_from_airport {
+        let inferred = db
+            .get_airport_by_code(&flight.departure_airport)
+            .ok()
+            .flatten()
+            .and_then(|airport| airport.country)
+            .and_then(|country| country_currency(&country));
+        if let Some(currency) = inferred {
+            return (currency.to_string(), SOURCE_AIRPORT_COUNTRY);
+        }
+    }
+    (settings.default_currency.clone(), SOURCE_USER_DEFAULT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_resolve_currency() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, icao_code, iata_code, name, country) VALUES
+                     ('LHR', 'EGLL', 'LHR', 'Heathrow', 'GB'),
+                     ('XXX', NULL, 'XXX', 'Nowhere', NULL);",
+            )
+            .unwrap();
+        let flight = |from: &str, currency: Option<&str>| -> FlightInput {
+            serde_json::from_value(serde_json::json!({
+                "departure_airport": from,
+                "arrival_airport": "JFK",
+                "departure_datetime": "2025-01-01T09:00:00",
+                "currency": currency,
+            }))
+            .unwrap()
+        };
+
+        let settings = CurrencySettings::configured(&db);
+        assert_eq!(resolve(&db, &settings, &flight("LHR", Some("eur"))), ("EUR".to_string(), SOURCE_EXPLICIT));
+        assert_eq!(resolve(&db, &settings, &flight("LHR", None)), ("GBP".to_string(), SOURCE_AIRPORT_COUNTRY));
+        assert_eq!(resolve(&db, &settings, &flight("XXX", None)), ("USD".to_string(), SOURCE_USER_DEFAULT));
+
+        db.set_setting(DEFAULT_CURRENCY_SETTING, "chf").unwrap();
+        db.set_setting(INFER_CURRENCY_SETTING, "false").unwrap();
+        let settings = CurrencySettings::configured(&db);
+        assert_eq!(resolve(&db, &settings, &flight("LHR", None)), ("CHF".to_string(), SOURCE_USER_DEFAULT));
+
+        // Created flights record the method alongside the currency
+        let id = db.create_flight("u1", &flight("LHR", None)).unwrap();
+        let stored = db.get_flight(&id).unwrap().unwrap();
+        assert_eq!(stored.currency.as_deref(), Some("CHF"));
+        assert_eq!(stored.currency_source.as_deref(), Some(SOURCE_USER_DEFAULT));
+    }
+}
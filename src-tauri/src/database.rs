@@ -5,8 +5,15 @@ use rusqlite::{params, Connection, OptionalExtension};
 use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::error::CommandError;
 use crate::models::*;
 
+/// Rows kept in query_performance when it is trimmed
+const QUERY_PERF_MAX_ROWS: i64 = 10_000;
+/// Trim query_performance once every this many recorded queries
+const QUERY_PERF_PRUNE_INTERVAL: u64 = 500;
+static QUERY_PERF_INSERTS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
 pub struct Database {
     pub conn: Connection,
 }
@@ -20,6 +27,10 @@ impl Database {
         conn.execute("PRAGMA foreign_keys = ON", [])
             .context("Failed to enable foreign keys")?;
 
+        // Wait for the other connection (write queue or main) instead of failing with SQLITE_BUSY
+        conn.busy_timeout(std::time::Duration::from_secs(10))
+            .context("Failed to set busy timeout")?;
+
         // Initialize schema
         Self::init_schema(&conn)?;
 
@@ -31,595 +42,23 @@ impl Database {
         &self.conn
     }
 
+    /// Run several reads inside one transaction so they see the same snapshot
+    /// (e.g. a page and its total count, with the write queue committing in between)
+    pub fn read_consistent<T>(&self, read: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        let tx = self.conn.unchecked_transaction().context("Failed to begin read transaction")?;
+        let result = read(self)?;
+        tx.commit().context("Failed to end read transaction")?;
+        Ok(result)
+    }
+
     /// Create the database schema
-    fn init_schema(conn: &Connection) -> Result<()> {
+    pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
         let schema_sql = include_str!("schema.sql");
         conn.execute_batch(schema_sql)
             .context("Failed to initialize database schema")?;
 
-        // Run migrations for new tables (idempotent - safe to run multiple times)
-        Self::run_migrations(conn)?;
-
-        Ok(())
-    }
-
-    /// Run database migrations for new features
-    fn run_migrations(conn: &Connection) -> Result<()> {
-        // Migration: Add trusted device tables for radar feature
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS trusted_wifi_devices (
-                bssid TEXT PRIMARY KEY,
-                ssid TEXT,
-                notes TEXT,
-                added_at TEXT NOT NULL DEFAULT (datetime('now')),
-                last_seen TEXT
-            );
-
-            CREATE TABLE IF NOT EXISTS trusted_bluetooth_devices (
-                address TEXT PRIMARY KEY,
-                name TEXT,
-                device_type TEXT,
-                notes TEXT,
-                added_at TEXT NOT NULL DEFAULT (datetime('now')),
-                last_seen TEXT
-            );
-
-            CREATE TABLE IF NOT EXISTS agent_memory (
-                id TEXT PRIMARY KEY,
-                agent_name TEXT NOT NULL,
-                memory_type TEXT NOT NULL,
-                flight_id TEXT,
-                user_id TEXT,
-                query TEXT,
-                content TEXT NOT NULL,
-                summary TEXT,
-                tokens_used INTEGER DEFAULT 0,
-                cost_usd REAL DEFAULT 0.0,
-                model TEXT,
-                embedding TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                expires_at TEXT,
-                last_accessed TEXT,
-                access_count INTEGER DEFAULT 0,
-                confidence_score REAL,
-                verified INTEGER DEFAULT 0,
-                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE SET NULL,
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
-            );
-
-            CREATE VIRTUAL TABLE IF NOT EXISTS agent_memory_fts USING fts5(
-                query, content, summary,
-                content='agent_memory',
-                content_rowid='rowid'
-            );
-
-            CREATE TRIGGER IF NOT EXISTS agent_memory_fts_insert AFTER INSERT ON agent_memory BEGIN
-                INSERT INTO agent_memory_fts(rowid, query, content, summary)
-                VALUES (new.rowid, new.query, new.content, new.summary);
-            END;
-
-            CREATE TRIGGER IF NOT EXISTS agent_memory_fts_delete AFTER DELETE ON agent_memory BEGIN
-                DELETE FROM agent_memory_fts WHERE rowid = old.rowid;
-            END;
-
-            CREATE TRIGGER IF NOT EXISTS agent_memory_fts_update AFTER UPDATE ON agent_memory BEGIN
-                DELETE FROM agent_memory_fts WHERE rowid = old.rowid;
-                INSERT INTO agent_memory_fts(rowid, query, content, summary)
-                VALUES (new.rowid, new.query, new.content, new.summary);
-            END;
-
-            CREATE INDEX IF NOT EXISTS idx_agent_memory_flight ON agent_memory(flight_id);
-            CREATE INDEX IF NOT EXISTS idx_agent_memory_user ON agent_memory(user_id);
-            CREATE INDEX IF NOT EXISTS idx_agent_memory_type ON agent_memory(memory_type);
-            CREATE INDEX IF NOT EXISTS idx_agent_memory_agent ON agent_memory(agent_name);
-            CREATE INDEX IF NOT EXISTS idx_agent_memory_created ON agent_memory(created_at);
-            CREATE INDEX IF NOT EXISTS idx_agent_memory_expires ON agent_memory(expires_at);
-
-            CREATE TABLE IF NOT EXISTS document_ingestion_queue (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                source_document_id TEXT,
-                source_file_path TEXT NOT NULL,
-                source_file_name TEXT NOT NULL,
-                source_file_hash TEXT NOT NULL,
-                total_pages INTEGER NOT NULL,
-                pages_processed INTEGER DEFAULT 0,
-                status TEXT DEFAULT 'pending',
-                error_message TEXT,
-                priority INTEGER DEFAULT 0,
-                retry_count INTEGER DEFAULT 0,
-                max_retries INTEGER DEFAULT 3,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                started_at TEXT,
-                completed_at TEXT,
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-                FOREIGN KEY (source_document_id) REFERENCES custom_documents(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_doc_queue_status ON document_ingestion_queue(status);
-            CREATE INDEX IF NOT EXISTS idx_doc_queue_user ON document_ingestion_queue(user_id);
-            CREATE INDEX IF NOT EXISTS idx_doc_queue_priority ON document_ingestion_queue(priority DESC, created_at ASC);
-
-            CREATE TABLE IF NOT EXISTS document_chunks (
-                id TEXT PRIMARY KEY,
-                queue_id TEXT NOT NULL,
-                chunk_hash TEXT UNIQUE NOT NULL,
-                chunk_number INTEGER NOT NULL,
-                chunk_type TEXT DEFAULT 'page',
-                content_path TEXT NOT NULL,
-                status TEXT DEFAULT 'pending',
-                processing_method TEXT DEFAULT 'text_extract',
-                ocr_text TEXT,
-                extracted_entities TEXT,
-                processing_stage TEXT DEFAULT 'pending',
-                error_message TEXT,
-                retry_count INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                processed_at TEXT,
-                FOREIGN KEY (queue_id) REFERENCES document_ingestion_queue(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_chunks_queue ON document_chunks(queue_id);
-            CREATE INDEX IF NOT EXISTS idx_chunks_status ON document_chunks(status);
-            CREATE INDEX IF NOT EXISTS idx_chunks_hash ON document_chunks(chunk_hash);
-            CREATE INDEX IF NOT EXISTS idx_chunks_stage ON document_chunks(processing_stage);
-
-            CREATE TABLE IF NOT EXISTS entity_extractions (
-                id TEXT PRIMARY KEY,
-                chunk_id TEXT NOT NULL,
-                entity_type TEXT NOT NULL,
-                entity_value TEXT NOT NULL,
-                confidence REAL DEFAULT 0.0,
-                context TEXT,
-                start_position INTEGER,
-                end_position INTEGER,
-                metadata TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (chunk_id) REFERENCES document_chunks(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_entities_chunk ON entity_extractions(chunk_id);
-            CREATE INDEX IF NOT EXISTS idx_entities_type ON entity_extractions(entity_type);
-            CREATE INDEX IF NOT EXISTS idx_entities_value ON entity_extractions(entity_value);
-
-            CREATE TABLE IF NOT EXISTS document_matches (
-                id TEXT PRIMARY KEY,
-                chunk_id TEXT NOT NULL,
-                flight_id TEXT,
-                match_type TEXT NOT NULL,
-                confidence REAL NOT NULL,
-                evidence TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (chunk_id) REFERENCES document_chunks(id) ON DELETE CASCADE,
-                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_matches_chunk ON document_matches(chunk_id);
-            CREATE INDEX IF NOT EXISTS idx_matches_flight ON document_matches(flight_id);
-            CREATE INDEX IF NOT EXISTS idx_matches_confidence ON document_matches(confidence);
-
-            CREATE TABLE IF NOT EXISTS relationship_graph (
-                id TEXT PRIMARY KEY,
-                source_type TEXT NOT NULL,
-                source_id TEXT NOT NULL,
-                target_type TEXT NOT NULL,
-                target_id TEXT NOT NULL,
-                relationship_type TEXT NOT NULL,
-                weight REAL DEFAULT 1.0,
-                evidence_ids TEXT,
-                metadata TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-                UNIQUE(source_type, source_id, target_type, target_id, relationship_type)
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_graph_source ON relationship_graph(source_type, source_id);
-            CREATE INDEX IF NOT EXISTS idx_graph_target ON relationship_graph(target_type, target_id);
-            CREATE INDEX IF NOT EXISTS idx_graph_type ON relationship_graph(relationship_type);
-
-            -- Fuel Price Cache: AI-searched prices
-            CREATE TABLE IF NOT EXISTS fuel_price_cache (
-                id TEXT PRIMARY KEY,
-                airport_code TEXT,
-                location_name TEXT NOT NULL,
-                region TEXT,
-                country TEXT,
-                fuel_type TEXT NOT NULL DEFAULT 'jet_a',
-                price_per_gallon REAL NOT NULL,
-                price_per_liter REAL,
-                currency TEXT DEFAULT 'USD',
-                effective_date TEXT NOT NULL,
-                source TEXT,
-                source_url TEXT,
-                confidence TEXT DEFAULT 'medium',
-                ai_response TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                expires_at TEXT,
-                UNIQUE(airport_code, fuel_type, effective_date)
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_fuel_cache_airport ON fuel_price_cache(airport_code);
-            CREATE INDEX IF NOT EXISTS idx_fuel_cache_type ON fuel_price_cache(fuel_type);
-            CREATE INDEX IF NOT EXISTS idx_fuel_cache_date ON fuel_price_cache(effective_date);
-            CREATE INDEX IF NOT EXISTS idx_fuel_cache_region ON fuel_price_cache(region);
-
-            -- Fuel Entries: User's actual fuel purchases
-            CREATE TABLE IF NOT EXISTS fuel_entries (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                flight_id TEXT,
-                aircraft_id TEXT,
-                airport_code TEXT,
-                location_name TEXT,
-                fuel_type TEXT NOT NULL DEFAULT 'jet_a',
-                gallons REAL NOT NULL,
-                price_per_gallon REAL NOT NULL,
-                total_cost REAL NOT NULL,
-                currency TEXT DEFAULT 'USD',
-                purchase_date TEXT NOT NULL,
-                fbo_name TEXT,
-                receipt_number TEXT,
-                notes TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE SET NULL
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_fuel_entries_user ON fuel_entries(user_id);
-            CREATE INDEX IF NOT EXISTS idx_fuel_entries_flight ON fuel_entries(flight_id);
-            CREATE INDEX IF NOT EXISTS idx_fuel_entries_airport ON fuel_entries(airport_code);
-            CREATE INDEX IF NOT EXISTS idx_fuel_entries_date ON fuel_entries(purchase_date);
-
-            -- Fuel Types: Custom fuel types defined by users
-            CREATE TABLE IF NOT EXISTS fuel_types (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                code TEXT NOT NULL,
-                name TEXT NOT NULL,
-                description TEXT,
-                category TEXT DEFAULT 'aviation',
-                is_default INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-                UNIQUE(user_id, code)
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_fuel_types_user ON fuel_types(user_id);
-            CREATE INDEX IF NOT EXISTS idx_fuel_types_code ON fuel_types(code);
-
-            -- Custom Schemas: User-defined entity types
-            CREATE TABLE IF NOT EXISTS custom_schemas (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                display_name TEXT NOT NULL,
-                description TEXT,
-                icon TEXT DEFAULT 'database',
-                color TEXT DEFAULT '#6366f1',
-                is_active INTEGER DEFAULT 1,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-                UNIQUE(user_id, name)
-            );
-
-            -- Custom Schema Fields: Field definitions for each schema
-            CREATE TABLE IF NOT EXISTS custom_schema_fields (
-                id TEXT PRIMARY KEY,
-                schema_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                display_name TEXT NOT NULL,
-                field_type TEXT NOT NULL,
-                is_required INTEGER DEFAULT 0,
-                default_value TEXT,
-                options TEXT,
-                validation_rules TEXT,
-                sort_order INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (schema_id) REFERENCES custom_schemas(id) ON DELETE CASCADE,
-                UNIQUE(schema_id, name)
-            );
-
-            -- Custom Entity Records: Actual data for custom schemas
-            CREATE TABLE IF NOT EXISTS custom_records (
-                id TEXT PRIMARY KEY,
-                schema_id TEXT NOT NULL,
-                user_id TEXT NOT NULL,
-                data TEXT NOT NULL,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (schema_id) REFERENCES custom_schemas(id) ON DELETE CASCADE,
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-            );
-
-            -- Flight Custom Fields: Extra fields on flight records
-            CREATE TABLE IF NOT EXISTS flight_custom_fields (
-                id TEXT PRIMARY KEY,
-                flight_id TEXT NOT NULL,
-                field_name TEXT NOT NULL,
-                field_value TEXT,
-                field_type TEXT NOT NULL DEFAULT 'text',
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE,
-                UNIQUE(flight_id, field_name)
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_custom_schemas_user ON custom_schemas(user_id);
-            CREATE INDEX IF NOT EXISTS idx_custom_fields_schema ON custom_schema_fields(schema_id);
-            CREATE INDEX IF NOT EXISTS idx_custom_records_schema ON custom_records(schema_id);
-            CREATE INDEX IF NOT EXISTS idx_custom_records_user ON custom_records(user_id);
-            CREATE INDEX IF NOT EXISTS idx_flight_custom_fields ON flight_custom_fields(flight_id);
-
-            -- ===== SELF-IMPROVEMENT SYSTEM =====
-
-            -- User Corrections: Track corrections for auto-fill learning
-            CREATE TABLE IF NOT EXISTS user_corrections (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                field_name TEXT NOT NULL,
-                original_value TEXT,
-                corrected_value TEXT NOT NULL,
-                context TEXT,
-                occurrence_count INTEGER DEFAULT 1,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-                UNIQUE(user_id, field_name, original_value, corrected_value)
-            );
-
-            -- User Patterns: Track behavioral patterns for smart defaults
-            CREATE TABLE IF NOT EXISTS user_patterns (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                pattern_type TEXT NOT NULL,
-                pattern_key TEXT NOT NULL,
-                pattern_value TEXT NOT NULL,
-                frequency INTEGER DEFAULT 1,
-                last_used TEXT NOT NULL DEFAULT (datetime('now')),
-                metadata TEXT,
-                UNIQUE(user_id, pattern_type, pattern_key)
-            );
-
-            -- Query Performance: Track slow queries for optimization
-            CREATE TABLE IF NOT EXISTS query_performance (
-                id TEXT PRIMARY KEY,
-                query_hash TEXT NOT NULL,
-                query_type TEXT NOT NULL,
-                table_name TEXT,
-                filters TEXT,
-                execution_time_ms INTEGER NOT NULL,
-                result_count INTEGER,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            -- AI Response Cache: Cache AI responses for cost optimization
-            CREATE TABLE IF NOT EXISTS ai_response_cache (
-                id TEXT PRIMARY KEY,
-                query_hash TEXT NOT NULL UNIQUE,
-                provider TEXT NOT NULL,
-                query_text TEXT NOT NULL,
-                response_text TEXT NOT NULL,
-                confidence REAL,
-                hit_count INTEGER DEFAULT 1,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                expires_at TEXT,
-                metadata TEXT
-            );
-
-            -- Flight Anomalies: Detected anomalies for data integrity
-            CREATE TABLE IF NOT EXISTS flight_anomalies (
-                id TEXT PRIMARY KEY,
-                flight_id TEXT NOT NULL,
-                anomaly_type TEXT NOT NULL,
-                severity TEXT DEFAULT 'warning',
-                description TEXT NOT NULL,
-                suggested_fix TEXT,
-                is_resolved INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                resolved_at TEXT,
-                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE
-            );
-
-            -- Duplicate Candidates: Potential duplicate flights
-            CREATE TABLE IF NOT EXISTS duplicate_candidates (
-                id TEXT PRIMARY KEY,
-                flight_id_1 TEXT NOT NULL,
-                flight_id_2 TEXT NOT NULL,
-                similarity_score REAL NOT NULL,
-                match_reasons TEXT,
-                status TEXT DEFAULT 'pending',
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                resolved_at TEXT,
-                FOREIGN KEY (flight_id_1) REFERENCES flights(id) ON DELETE CASCADE,
-                FOREIGN KEY (flight_id_2) REFERENCES flights(id) ON DELETE CASCADE
-            );
-
-            -- Provider Accuracy: Track AI provider accuracy for triangulation
-            CREATE TABLE IF NOT EXISTS provider_accuracy (
-                id TEXT PRIMARY KEY,
-                provider TEXT NOT NULL,
-                query_type TEXT NOT NULL,
-                correct_count INTEGER DEFAULT 0,
-                total_count INTEGER DEFAULT 0,
-                avg_confidence REAL,
-                last_updated TEXT NOT NULL DEFAULT (datetime('now')),
-                UNIQUE(provider, query_type)
-            );
-
-            -- Route Statistics: Learned route patterns
-            CREATE TABLE IF NOT EXISTS route_statistics (
-                id TEXT PRIMARY KEY,
-                departure_airport TEXT NOT NULL,
-                arrival_airport TEXT NOT NULL,
-                avg_duration_minutes REAL,
-                avg_distance_km REAL,
-                common_aircraft TEXT,
-                flight_count INTEGER DEFAULT 1,
-                last_updated TEXT NOT NULL DEFAULT (datetime('now')),
-                UNIQUE(departure_airport, arrival_airport)
-            );
-
-            -- Fuel Price History: Persistent fuel price database
-            CREATE TABLE IF NOT EXISTS fuel_price_history (
-                id TEXT PRIMARY KEY,
-                airport_code TEXT,
-                location_name TEXT NOT NULL,
-                region TEXT,
-                country TEXT,
-                fuel_type TEXT NOT NULL DEFAULT 'jet_a',
-                price_per_gallon REAL NOT NULL,
-                price_per_liter REAL,
-                currency TEXT DEFAULT 'USD',
-                effective_date TEXT NOT NULL,
-                source TEXT NOT NULL,
-                source_url TEXT,
-                verified INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_corrections_user ON user_corrections(user_id);
-            CREATE INDEX IF NOT EXISTS idx_patterns_user ON user_patterns(user_id, pattern_type);
-            CREATE INDEX IF NOT EXISTS idx_query_perf_hash ON query_performance(query_hash);
-            CREATE INDEX IF NOT EXISTS idx_ai_cache_hash ON ai_response_cache(query_hash);
-            CREATE INDEX IF NOT EXISTS idx_anomalies_flight ON flight_anomalies(flight_id);
-            CREATE INDEX IF NOT EXISTS idx_duplicates_flights ON duplicate_candidates(flight_id_1, flight_id_2);
-            CREATE INDEX IF NOT EXISTS idx_route_stats ON route_statistics(departure_airport, arrival_airport);
-            CREATE INDEX IF NOT EXISTS idx_fuel_history_location ON fuel_price_history(airport_code, fuel_type);
-            CREATE INDEX IF NOT EXISTS idx_fuel_history_date ON fuel_price_history(effective_date);
-
-            CREATE TABLE IF NOT EXISTS ocr_corrections (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                field_name TEXT NOT NULL,
-                original_value TEXT,
-                corrected_value TEXT NOT NULL,
-                image_hash TEXT,
-                confidence_score REAL,
-                pattern_type TEXT,
-                applied_count INTEGER DEFAULT 0,
-                verified INTEGER DEFAULT 1,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                last_applied TEXT,
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_ocr_corrections_user ON ocr_corrections(user_id);
-            CREATE INDEX IF NOT EXISTS idx_ocr_corrections_field ON ocr_corrections(field_name);
-            CREATE INDEX IF NOT EXISTS idx_ocr_corrections_original ON ocr_corrections(original_value);
-            CREATE INDEX IF NOT EXISTS idx_ocr_corrections_created ON ocr_corrections(created_at);
-
-            CREATE TABLE IF NOT EXISTS ocr_learning_patterns (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                pattern_type TEXT NOT NULL,
-                field_name TEXT NOT NULL,
-                match_pattern TEXT NOT NULL,
-                replacement_value TEXT NOT NULL,
-                confidence REAL DEFAULT 0.5,
-                occurrence_count INTEGER DEFAULT 1,
-                success_count INTEGER DEFAULT 0,
-                rejection_count INTEGER DEFAULT 0,
-                is_active INTEGER DEFAULT 1,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-                last_used TEXT,
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_learning_patterns_user ON ocr_learning_patterns(user_id);
-            CREATE INDEX IF NOT EXISTS idx_learning_patterns_field ON ocr_learning_patterns(field_name);
-            CREATE INDEX IF NOT EXISTS idx_learning_patterns_type ON ocr_learning_patterns(pattern_type);
-            CREATE INDEX IF NOT EXISTS idx_learning_patterns_active ON ocr_learning_patterns(is_active);
-
-            -- ===== IDENTITY FUSION ARCHITECTURE =====
-            -- Canonical Identity Table (The Master Records)
-            CREATE TABLE IF NOT EXISTS passengers (
-                id TEXT PRIMARY KEY,
-                canonical_name TEXT NOT NULL UNIQUE,
-                notes TEXT,
-                total_flights INTEGER DEFAULT 0,
-                first_seen_date TEXT,
-                last_seen_date TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_passengers_canonical_name ON passengers(canonical_name);
-
-            -- Passenger Aliases Table (Variations linked to Master)
-            CREATE TABLE IF NOT EXISTS passenger_aliases (
-                id TEXT PRIMARY KEY,
-                passenger_id TEXT NOT NULL,
-                raw_name TEXT NOT NULL UNIQUE,
-                usage_count INTEGER DEFAULT 0,
-                source_document TEXT,
-                match_type TEXT,
-                confidence REAL DEFAULT 1.0,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_passenger_aliases_raw ON passenger_aliases(raw_name);
-            CREATE INDEX IF NOT EXISTS idx_passenger_aliases_passenger ON passenger_aliases(passenger_id);
-
-            -- Flight-Passenger Junction Table (Many-to-Many)
-            CREATE TABLE IF NOT EXISTS flight_passengers (
-                flight_id TEXT NOT NULL,
-                passenger_id TEXT NOT NULL,
-                seat_info TEXT,
-                role TEXT DEFAULT 'passenger',
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                PRIMARY KEY (flight_id, passenger_id),
-                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE,
-                FOREIGN KEY (passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_flight_passengers_flight ON flight_passengers(flight_id);
-            CREATE INDEX IF NOT EXISTS idx_flight_passengers_passenger ON flight_passengers(passenger_id);
-
-            -- Do Not Deduplicate List: Passengers that should remain separate
-            CREATE TABLE IF NOT EXISTS passenger_no_dedup (
-                passenger_id TEXT PRIMARY KEY,
-                reason TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
-            );"
-        ).context("Failed to run database migrations")?;
-
-        // Migration: Add entity linking columns to custom_documents
-        // These columns allow documents to be linked to journeys, passengers, and fuel entries
-        let _ = conn.execute(
-            "ALTER TABLE custom_documents ADD COLUMN journey_id TEXT REFERENCES journeys(id) ON DELETE SET NULL",
-            [],
-        );
-        let _ = conn.execute(
-            "ALTER TABLE custom_documents ADD COLUMN passenger_name TEXT",
-            [],
-        );
-        let _ = conn.execute(
-            "ALTER TABLE custom_documents ADD COLUMN fuel_entry_id TEXT REFERENCES fuel_entries(id) ON DELETE SET NULL",
-            [],
-        );
-        // Create indexes (safe to run multiple times)
-        let _ = conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_custom_documents_journey ON custom_documents(journey_id)",
-            [],
-        );
-        let _ = conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_custom_documents_passenger ON custom_documents(passenger_name)",
-            [],
-        );
-        let _ = conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_custom_documents_fuel ON custom_documents(fuel_entry_id)",
-            [],
-        );
-
-        // Migration: Add per_passenger_co2_kg column to flights
-        // This stores the CO2 share per passenger (total CO2 / passenger count, excluding crew)
-        let _ = conn.execute(
-            "ALTER TABLE flights ADD COLUMN per_passenger_co2_kg REAL",
-            [],
-        );
+        // Apply pending versioned migrations (see migrations.rs)
+        crate::migrations::run_migrations(conn)?;
 
         Ok(())
     }
@@ -730,22 +169,51 @@ impl Database {
     // ===== FLIGHT OPERATIONS =====
 
     pub fn create_flight(&self, user_id: &str, flight: &FlightInput) -> Result<String> {
+        self.create_flight_with_source(user_id, flight, "manual")
+    }
+
+    /// Create a flight recording where it came from (manual, extracted, ...)
+    pub fn create_flight_with_source(
+        &self,
+        user_id: &str,
+        flight: &FlightInput,
+        data_source: &str,
+    ) -> Result<String> {
+        let currency = crate::currency::CurrencySettings::configured(self);
+        self.create_flight_with_currency(user_id, flight, data_source, &currency)
+    }
+
+    /// Like create_flight_with_source, with the currency settings loaded by the caller, so
+    /// imports read them once rather than once per flight. Every writer ends up here, so
+    /// this is where the flight is validated.
+    pub fn create_flight_with_currency(
+        &self,
+        user_id: &str,
+        flight: &FlightInput,
+        data_source: &str,
+        currency: &crate::currency::CurrencySettings,
+    ) -> Result<String> {
+        crate::validation::validate_flight_input(flight)?;
         let id = Uuid::new_v4().to_string();
 
         // Use distance_km if provided, otherwise convert from nautical miles
         let distance_km = flight
             .distance_km
-            .or_else(|| flight.distance_nm.map(|nm| nm * 1.852));
+            .or_else(|| flight.distance_nm.map(|nm| nm * crate::geo::KM_PER_NM));
+
+        // Estimates use the aircraft's performance profile, or the type heuristics without one
+        let performance = crate::aircraft_performance::performance_for(
+            self,
+            crate::aircraft_performance::AircraftRef {
+                registration: flight.aircraft_registration.as_deref(),
+                aircraft_type_id: flight.aircraft_type_id.as_deref(),
+            },
+        );
 
         // Calculate flight time if we have distance and no existing duration
-        let flight_duration = flight.flight_duration.or_else(|| {
-            distance_km.map(|km| {
-                crate::calculations::calculate_flight_time(
-                    km,
-                    flight.aircraft_registration.as_deref(),
-                )
-            })
-        });
+        let flight_duration = flight
+            .flight_duration
+            .or_else(|| distance_km.map(|km| performance.flight_time(km)));
 
         // Calculate total duration (block time) if we have flight duration
         let total_duration = flight
@@ -753,14 +221,17 @@ impl Database {
             .or_else(|| flight_duration.map(crate::calculations::calculate_block_time));
 
         // Use provided CO2 emissions, or calculate from distance if available
-        let carbon_emissions_kg = flight.carbon_emissions_kg.or_else(|| {
-            distance_km.map(|km| {
-                crate::calculations::calculate_co2_emissions(
-                    km,
-                    flight.aircraft_registration.as_deref(),
-                )
-            })
-        });
+        let carbon_emissions_kg = flight
+            .carbon_emissions_kg
+            .or_else(|| distance_km.map(|km| performance.co2_emissions(km)));
+
+        let airline = flight
+            .flight_number
+            .as_deref()
+            .and_then(crate::airlines::infer_from_flight_number)
+            .map(|a| a.iata.clone());
+
+        let (currency, currency_source) = crate::currency::resolve(self, currency, flight);
 
         self.conn
             .execute(
@@ -769,10 +240,11 @@ impl Database {
                 departure_datetime, arrival_datetime, aircraft_type_id, aircraft_registration,
                 total_duration, flight_duration, distance_nm, distance_km, booking_reference,
                 ticket_number, seat_number, fare_class, base_fare, taxes, total_cost,
-                currency, carbon_emissions_kg, notes, attachment_path, data_source
+                currency, carbon_emissions_kg, notes, attachment_path, data_source, airline,
+                passenger_count, currency_source
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
-                ?18, ?19, ?20, ?21, ?22, ?23, ?24, 'manual'
+                ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28
             )",
                 params![
                     id,
@@ -795,10 +267,14 @@ impl Database {
                     flight.base_fare,
                     flight.taxes,
                     flight.total_cost,
-                    flight.currency,
+                    currency,
                     carbon_emissions_kg,
                     flight.notes,
-                    flight.attachment_path
+                    flight.attachment_path,
+                    data_source,
+                    airline,
+                    flight.passenger_count,
+                    currency_source
                 ],
             )
             .context("Failed to create flight")?;
@@ -815,7 +291,8 @@ impl Database {
                         booking_reference, ticket_number, seat_number, fare_class,
                         base_fare, taxes, total_cost, currency, carbon_emissions_kg, per_passenger_co2_kg,
                         carbon_offset_purchased, frequent_flyer_program, miles_earned,
-                        notes, attachment_path, data_source, verified, created_at, updated_at
+                        notes, attachment_path, data_source, verified, created_at, updated_at, airline,
+                        scheduled_departure, scheduled_arrival, passenger_count, currency_source
                  FROM flights WHERE id = ?1",
                 params![flight_id],
                 |row| {
@@ -853,6 +330,11 @@ impl Database {
                         verified: row.get(30)?,
                         created_at: row.get(31)?,
                         updated_at: row.get(32)?,
+                        airline: row.get(33)?,
+                        scheduled_departure: row.get(34)?,
+                        scheduled_arrival: row.get(35)?,
+                        passenger_count: row.get(36)?,
+                        currency_source: row.get(37)?,
                     })
                 }
             )
@@ -863,25 +345,60 @@ impl Database {
     }
 
     pub fn list_flights(&self, user_id: &str, limit: i32, offset: i32) -> Result<Vec<Flight>> {
-        let mut stmt = self
-            .conn
-            .prepare(
-                "SELECT id, user_id, flight_number, departure_airport, arrival_airport,
+        self.list_flights_filtered(user_id, &FlightListFilter::default(), limit, offset)
+    }
+
+    /// List flights matching the filter; every condition is pushed into SQL so the
+    /// user, date and airport indexes are used
+    pub fn list_flights_filtered(
+        &self,
+        user_id: &str,
+        filter: &FlightListFilter,
+        limit: i32,
+        offset: i32,
+    ) -> Result<Vec<Flight>> {
+        let (where_clause, mut values) = flight_filter_conditions(user_id, filter);
+        let sort_column = match filter.sort_by.as_deref().unwrap_or("departure_datetime") {
+            column @ ("departure_datetime" | "arrival_datetime" | "departure_airport" | "arrival_airport"
+            | "distance_km" | "flight_duration" | "total_cost" | "created_at") => column,
+            other => anyhow::bail!("Cannot sort flights by '{}'", other),
+        };
+        let sort_dir = match filter.sort_dir.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("desc") => "DESC",
+            Some("asc") => "ASC",
+            Some(other) => anyhow::bail!("Invalid sort direction '{}' (expected asc or desc)", other),
+        };
+        values.push(Box::new(limit));
+        values.push(Box::new(offset));
+
+        let query = format!(
+            "SELECT id, user_id, flight_number, departure_airport, arrival_airport,
                     departure_datetime, arrival_datetime, aircraft_type_id, aircraft_registration,
                     total_duration, flight_duration, block_duration, distance_nm, distance_km,
                     booking_reference, ticket_number, seat_number, fare_class,
                     base_fare, taxes, total_cost, currency, carbon_emissions_kg, per_passenger_co2_kg,
                     carbon_offset_purchased, frequent_flyer_program, miles_earned,
-                    notes, attachment_path, data_source, verified, created_at, updated_at
+                    notes, attachment_path, data_source, verified, created_at, updated_at, airline,
+                    scheduled_departure, scheduled_arrival, passenger_count, currency_source
              FROM flights
-             WHERE user_id = ?1
-             ORDER BY departure_datetime DESC
-             LIMIT ?2 OFFSET ?3",
-            )
+             WHERE {}
+             ORDER BY {} {}, id {}
+             LIMIT ?{} OFFSET ?{}",
+            where_clause,
+            sort_column,
+            sort_dir,
+            sort_dir,
+            values.len() - 1,
+            values.len()
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&query)
             .context("Failed to prepare list flights query")?;
 
+        let params: Vec<&dyn rusqlite::ToSql> = values.iter().map(|v| v.as_ref()).collect();
         let flights = stmt
-            .query_map(params![user_id, limit, offset], |row| {
+            .query_map(params.as_slice(), |row| {
                 Ok(Flight {
                     id: row.get(0)?,
                     user_id: row.get(1)?,
@@ -916,6 +433,11 @@ impl Database {
                     verified: row.get(30)?,
                     created_at: row.get(31)?,
                     updated_at: row.get(32)?,
+                    airline: row.get(33)?,
+                    scheduled_departure: row.get(34)?,
+                    scheduled_arrival: row.get(35)?,
+                    passenger_count: row.get(36)?,
+                    currency_source: row.get(37)?,
                 })
             })
             .context("Failed to query flights")?
@@ -925,14 +447,232 @@ impl Database {
         Ok(flights)
     }
 
+    /// Flights on the given month and day (MM-DD) in years before `before_year`, newest
+    /// first. Placeholder dates before 1903 are skipped.
+    pub fn list_flights_on_day(&self, user_id: &str, month_day: &str, before_year: i32) -> Result<Vec<Flight>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id FROM flights
+                 WHERE user_id = ?1 AND deleted_at IS NULL AND departure_datetime >= '1903-01-01'
+                   AND strftime('%m-%d', departure_datetime) = ?2
+                   AND CAST(strftime('%Y', departure_datetime) AS INTEGER) < ?3
+                 ORDER BY departure_datetime DESC",
+            )
+            .context("Failed to prepare on-this-day query")?;
+        let ids = stmt
+            .query_map(params![user_id, month_day, before_year], |row| row.get::<_, String>(0))
+            .context("Failed to query flights on this day")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to collect flights on this day")?;
+
+        let mut flights = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(flight) = self.get_flight(&id)? {
+                flights.push(flight);
+            }
+        }
+        Ok(flights)
+    }
+
+    pub fn count_flights(&self, user_id: &str, filter: &FlightListFilter) -> Result<i64> {
+        let (where_clause, values) = flight_filter_conditions(user_id, filter);
+        let params: Vec<&dyn rusqlite::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+        self.conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM flights WHERE {}", where_clause),
+                params.as_slice(),
+                |row| row.get(0),
+            )
+            .context("Failed to count flights")
+    }
+
+    /// Fill in the airline of the user's flights from their flight numbers
+    /// Only flights without an airline are touched unless `overwrite` is set; returns how
+    /// many flights got an airline.
+    pub fn infer_flight_airlines(&self, user_id: &str, overwrite: bool) -> Result<usize> {
+        let flights: Vec<(String, Option<String>)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, flight_number FROM flights
+                 WHERE user_id = ?1 AND deleted_at IS NULL AND (?2 OR airline IS NULL)",
+            )?;
+            let rows = stmt.query_map(params![user_id, overwrite], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<std::result::Result<_, _>>()?
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut inferred = 0;
+        for (id, flight_number) in flights {
+            let airline = flight_number
+                .as_deref()
+                .and_then(crate::airlines::infer_from_flight_number)
+                .map(|a| a.iata.as_str());
+            if airline.is_some() {
+                inferred += 1;
+            } else if !overwrite {
+                continue;
+            }
+            tx.execute("UPDATE flights SET airline = ?1 WHERE id = ?2", params![airline, id])?;
+        }
+        tx.commit().context("Failed to save inferred airlines")?;
+
+        Ok(inferred)
+    }
+
+    /// Stored weather for a flight; the most recent observation date when there are several
+    pub fn get_flight_weather(&self, flight_id: &str) -> Result<Option<FlightWeather>> {
+        self.conn
+            .query_row(
+                "SELECT id, flight_id, observation_date, location, determined, conditions, temperature_c,
+                        wind_direction_deg, wind_speed_kt, visibility_km, description, source, created_at
+                 FROM flight_weather WHERE flight_id = ?1
+                 ORDER BY observation_date DESC LIMIT 1",
+                params![flight_id],
+                |row| {
+                    Ok(FlightWeather {
+                        id: row.get(0)?,
+                        flight_id: row.get(1)?,
+                        observation_date: row.get(2)?,
+                        location: row.get(3)?,
+                        determined: row.get(4)?,
+                        conditions: row.get(5)?,
+                        temperature_c: row.get(6)?,
+                        wind_direction_deg: row.get(7)?,
+                        wind_speed_kt: row.get(8)?,
+                        visibility_km: row.get(9)?,
+                        description: row.get(10)?,
+                        source: row.get(11)?,
+                        created_at: row.get(12)?,
+                    })
+                },
+            )
+            .optional()
+            .context("Failed to get flight weather")
+    }
+
+    /// Insert or replace the weather record for a flight and date
+    /// Pass `weather: None` to record that it could not be determined.
+    pub fn save_flight_weather(
+        &self,
+        flight_id: &str,
+        observation_date: &str,
+        location: Option<&str>,
+        weather: Option<&crate::deepseek::WeatherInfo>,
+        source: &str,
+    ) -> Result<()> {
+        let text = |value: Option<&String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        self.conn
+            .execute(
+                "INSERT INTO flight_weather (
+                    id, flight_id, observation_date, location, determined, conditions, temperature_c,
+                    wind_direction_deg, wind_speed_kt, visibility_km, description, source
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT(flight_id, observation_date) DO UPDATE SET
+                    location = excluded.location, determined = excluded.determined,
+                    conditions = excluded.conditions, temperature_c = excluded.temperature_c,
+                    wind_direction_deg = excluded.wind_direction_deg, wind_speed_kt = excluded.wind_speed_kt,
+                    visibility_km = excluded.visibility_km, description = excluded.description,
+                    source = excluded.source, created_at = datetime('now')",
+                params![
+                    Uuid::new_v4().to_string(),
+                    flight_id,
+                    observation_date,
+                    location,
+                    weather.is_some(),
+                    text(weather.map(|w| &w.condition)),
+                    weather.and_then(|w| w.temperature_celsius()),
+                    weather.and_then(|w| w.wind_direction_deg),
+                    weather.and_then(|w| w.wind_speed_kt),
+                    weather.and_then(|w| w.visibility_km),
+                    text(weather.map(|w| &w.description)),
+                    source
+                ],
+            )
+            .context("Failed to save flight weather")?;
+        Ok(())
+    }
+
+    /// Move a flight to the recycle bin; child records stay and are hidden with it
     pub fn delete_flight(&self, flight_id: &str) -> Result<()> {
         self.conn
-            .execute("DELETE FROM flights WHERE id = ?1", params![flight_id])
+            .execute(
+                "UPDATE flights SET deleted_at = datetime('now') WHERE id = ?1 AND deleted_at IS NULL",
+                params![flight_id],
+            )
             .context("Failed to delete flight")?;
 
         Ok(())
     }
 
+    pub fn list_deleted_flights(&self, user_id: &str) -> Result<Vec<DeletedFlight>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, flight_number, departure_airport, arrival_airport, departure_datetime, deleted_at
+                 FROM flights
+                 WHERE user_id = ?1 AND deleted_at IS NOT NULL
+                 ORDER BY deleted_at DESC",
+            )
+            .context("Failed to prepare deleted flights query")?;
+
+        let flights = stmt
+            .query_map(params![user_id], |row| {
+                Ok(DeletedFlight {
+                    id: row.get(0)?,
+                    flight_number: row.get(1)?,
+                    departure_airport: row.get(2)?,
+                    arrival_airport: row.get(3)?,
+                    departure_datetime: row.get(4)?,
+                    deleted_at: row.get(5)?,
+                })
+            })
+            .context("Failed to query deleted flights")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to collect deleted flights")?;
+
+        Ok(flights)
+    }
+
+    /// Bring a flight back from the recycle bin; returns false if it was not deleted
+    pub fn restore_flight(&self, flight_id: &str) -> Result<bool> {
+        let restored = self
+            .conn
+            .execute(
+                "UPDATE flights SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![flight_id],
+            )
+            .context("Failed to restore flight")?;
+
+        Ok(restored > 0)
+    }
+
+    /// Permanently delete flights in the recycle bin, optionally only those deleted more
+    /// than `older_than_days` ago. Child records go with them via ON DELETE CASCADE.
+    pub fn purge_deleted_flights(&self, user_id: &str, older_than_days: Option<u32>) -> Result<usize> {
+        let cutoff = format!("-{} days", older_than_days.unwrap_or(0));
+        let purge_filter = "user_id = ?1 AND deleted_at IS NOT NULL AND deleted_at <= datetime('now', ?2)";
+        let tx = self.conn.unchecked_transaction()?;
+
+        // Tables without a cascading foreign key are cleared explicitly
+        for table in ["pilot_logbook", "journey_flights"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE flight_id IN (SELECT id FROM flights WHERE {})",
+                    table, purge_filter
+                ),
+                params![user_id, cutoff],
+            )
+            .with_context(|| format!("Failed to purge {}", table))?;
+        }
+
+        let purged = tx
+            .execute(&format!("DELETE FROM flights WHERE {}", purge_filter), params![user_id, cutoff])
+            .context("Failed to purge deleted flights")?;
+        tx.commit()?;
+
+        Ok(purged)
+    }
+
     // ===== STATISTICS OPERATIONS =====
 
     pub fn get_statistics(&self, user_id: &str) -> Result<FlightStatistics> {
@@ -947,7 +687,7 @@ impl Database {
                 COALESCE(SUM(flight_duration), 0.0) / 60.0 as total_flight_time_hours,
                 COALESCE(SUM(carbon_emissions_kg), 0.0) as total_carbon_kg
              FROM flights
-             WHERE user_id = ?1",
+             WHERE user_id = ?1 AND deleted_at IS NULL",
                 params![user_id],
                 |row| {
                     Ok((
@@ -966,9 +706,9 @@ impl Database {
             .conn
             .query_row(
                 "SELECT COUNT(DISTINCT airport_code) FROM (
-                    SELECT departure_airport as airport_code FROM flights WHERE user_id = ?1
+                    SELECT departure_airport as airport_code FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
                     UNION
-                    SELECT arrival_airport as airport_code FROM flights WHERE user_id = ?1
+                    SELECT arrival_airport as airport_code FROM flights WHERE user_id = ?1 AND deleted_at IS NULL
                 )",
                 params![user_id],
                 |row| row.get(0),
@@ -985,6 +725,132 @@ impl Database {
             countries_visited: 0,    // TODO: Calculate from airports
             favorite_aircraft: None, // TODO: Calculate
             favorite_route: None,    // TODO: Calculate
+            measurements: None,
+        })
+    }
+
+    /// Totals split by aircraft class and category. Flights without an aircraft type, or
+    /// whose type has no class/category, are grouped as "Unknown" and still count in the total.
+    pub fn get_statistics_by_class(&self, user_id: &str) -> Result<StatisticsByClass> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT
+                COALESCE(NULLIF(TRIM(at.class), ''), 'Unknown') as aircraft_class,
+                COALESCE(NULLIF(TRIM(at.category), ''), 'Unknown') as category,
+                COUNT(*) as total_flights,
+                COALESCE(SUM(f.distance_nm), 0.0) as total_distance_nm,
+                COALESCE(SUM(f.distance_km), 0.0) as total_distance_km,
+                COALESCE(SUM(f.flight_duration), 0.0) / 60.0 as total_flight_time_hours,
+                COALESCE(SUM(f.carbon_emissions_kg), 0.0) as total_carbon_kg
+             FROM flights f
+             LEFT JOIN aircraft_types at ON f.aircraft_type_id = at.id
+             WHERE f.user_id = ?1 AND f.deleted_at IS NULL
+             GROUP BY 1, 2
+             ORDER BY total_flight_time_hours DESC, total_flights DESC",
+            )
+            .context("Failed to prepare class statistics query")?;
+
+        let classes = stmt
+            .query_map(params![user_id], |row| {
+                Ok(ClassStatistics {
+                    aircraft_class: row.get(0)?,
+                    category: row.get(1)?,
+                    totals: FlightTotals {
+                        total_flights: row.get(2)?,
+                        total_distance_nm: row.get(3)?,
+                        total_distance_km: row.get(4)?,
+                        total_flight_time_hours: row.get(5)?,
+                        total_carbon_kg: row.get(6)?,
+                        measurements: None,
+                    },
+                })
+            })
+            .context("Failed to query class statistics")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to collect class statistics")?;
+
+        let total = classes.iter().fold(FlightTotals::default(), |mut total, class| {
+            total.total_flights += class.totals.total_flights;
+            total.total_distance_nm += class.totals.total_distance_nm;
+            total.total_distance_km += class.totals.total_distance_km;
+            total.total_flight_time_hours += class.totals.total_flight_time_hours;
+            total.total_carbon_kg += class.totals.total_carbon_kg;
+            total
+        });
+
+        Ok(StatisticsByClass { classes, total })
+    }
+
+    /// Average delay, on-time share and the routes/airports with the longest average delays.
+    /// Each flight's delay is its arrival delay, or its departure delay when only the
+    /// departure was scheduled; flights without a usable schedule are skipped.
+    pub fn get_punctuality_stats(&self, user_id: &str, worst_limit: usize) -> Result<PunctualityStats> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT departure_airport, arrival_airport, departure_datetime, arrival_datetime,
+                        scheduled_departure, scheduled_arrival
+                 FROM flights
+                 WHERE user_id = ?1 AND deleted_at IS NULL
+                   AND (scheduled_departure IS NOT NULL OR scheduled_arrival IS NOT NULL)",
+            )
+            .context("Failed to prepare punctuality query")?;
+
+        let rows = stmt
+            .query_map(params![user_id], |row| {
+                let departure: String = row.get(0)?;
+                let arrival: String = row.get(1)?;
+                let departed: String = row.get(2)?;
+                let arrived: Option<String> = row.get(3)?;
+                let scheduled_departure: Option<String> = row.get(4)?;
+                let scheduled_arrival: Option<String> = row.get(5)?;
+                let delay = crate::calculations::delay_minutes(scheduled_arrival.as_deref(), arrived.as_deref())
+                    .or_else(|| crate::calculations::delay_minutes(scheduled_departure.as_deref(), Some(&departed)));
+                Ok((departure, arrival, delay))
+            })
+            .context("Failed to query punctuality")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to collect punctuality")?;
+
+        let delayed: Vec<(String, String, i64)> = rows
+            .into_iter()
+            .filter_map(|(departure, arrival, delay)| delay.map(|d| (departure, arrival, d)))
+            .collect();
+
+        let count = delayed.len() as i64;
+        let (average_delay_minutes, on_time_percentage) = if delayed.is_empty() {
+            (None, None)
+        } else {
+            let total: i64 = delayed.iter().map(|(_, _, d)| d).sum();
+            let on_time = delayed
+                .iter()
+                .filter(|(_, _, d)| *d <= crate::calculations::ON_TIME_THRESHOLD_MINUTES)
+                .count();
+            (
+                Some(total as f64 / count as f64),
+                Some(on_time as f64 * 100.0 / count as f64),
+            )
+        };
+
+        let worst_routes = worst_delay_groups(
+            delayed.iter().map(|(departure, arrival, delay)| (format!("{}-{}", departure, arrival), *delay)),
+            worst_limit,
+        );
+        let worst_airports = worst_delay_groups(
+            delayed.iter().flat_map(|(departure, arrival, delay)| {
+                let arrival = (arrival != departure).then(|| (arrival.clone(), *delay));
+                std::iter::once((departure.clone(), *delay)).chain(arrival)
+            }),
+            worst_limit,
+        );
+
+        Ok(PunctualityStats {
+            flights_with_schedule: count,
+            average_delay_minutes,
+            on_time_percentage,
+            worst_routes,
+            worst_airports,
         })
     }
 
@@ -1080,6 +946,25 @@ impl Database {
         user_id: &str,
         limit: i64,
         offset: i64,
+    ) -> Result<Vec<ResearchReport>> {
+        self.query_research_reports(user_id, false, limit, offset)
+    }
+
+    pub fn list_archived_research_reports(
+        &self,
+        user_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ResearchReport>> {
+        self.query_research_reports(user_id, true, limit, offset)
+    }
+
+    fn query_research_reports(
+        &self,
+        user_id: &str,
+        archived: bool,
+        limit: i64,
+        offset: i64,
     ) -> Result<Vec<ResearchReport>> {
         let mut stmt = self
             .conn
@@ -1088,14 +973,14 @@ impl Database {
                     report_summary, report_details, sources, confidence_score, flight_id,
                     report_type, processing_time_ms, created_at
              FROM research_reports
-             WHERE user_id = ?1
+             WHERE user_id = ?1 AND archived = ?2
              ORDER BY created_at DESC
-             LIMIT ?2 OFFSET ?3",
+             LIMIT ?3 OFFSET ?4",
             )
             .context("Failed to prepare statement")?;
 
         let reports = stmt
-            .query_map(params![user_id, limit, offset], |row| {
+            .query_map(params![user_id, archived, limit, offset], |row| {
                 Ok(ResearchReport {
                     id: row.get(0)?,
                     user_id: row.get(1)?,
@@ -1123,6 +1008,35 @@ impl Database {
         Ok(result)
     }
 
+    /// Archive reports older than `max_age_days` whose confidence is below `min_confidence`
+    /// Reports without a confidence score count as low confidence.
+    pub fn archive_research_reports(&self, user_id: &str, max_age_days: u32, min_confidence: f64) -> Result<usize> {
+        let archived = self
+            .conn
+            .execute(
+                "UPDATE research_reports SET archived = 1
+                 WHERE user_id = ?1 AND archived = 0
+                   AND created_at < datetime('now', ?2)
+                   AND COALESCE(confidence_score, 0.0) < ?3",
+                params![user_id, format!("-{} days", max_age_days), min_confidence],
+            )
+            .context("Failed to archive research reports")?;
+
+        Ok(archived)
+    }
+
+    pub fn purge_archived_research_reports(&self, user_id: &str) -> Result<usize> {
+        let purged = self
+            .conn
+            .execute(
+                "DELETE FROM research_reports WHERE user_id = ?1 AND archived = 1",
+                params![user_id],
+            )
+            .context("Failed to purge archived research reports")?;
+
+        Ok(purged)
+    }
+
     pub fn delete_research_report(&self, report_id: &str) -> Result<()> {
         self.conn
             .execute(
@@ -1138,7 +1052,7 @@ impl Database {
         let count: i64 = self
             .conn
             .query_row(
-                "SELECT COUNT(*) FROM research_reports WHERE user_id = ?1",
+                "SELECT COUNT(*) FROM research_reports WHERE user_id = ?1 AND archived = 0",
                 params![user_id],
                 |row| row.get(0),
             )
@@ -1306,10 +1220,11 @@ impl Database {
                         f.booking_reference, f.ticket_number, f.seat_number, f.fare_class,
                         f.base_fare, f.taxes, f.total_cost, f.currency, f.carbon_emissions_kg, f.per_passenger_co2_kg,
                         f.carbon_offset_purchased, f.frequent_flyer_program, f.miles_earned,
-                        f.notes, f.attachment_path, f.data_source, f.verified, f.created_at, f.updated_at
+                        f.notes, f.attachment_path, f.data_source, f.verified, f.created_at, f.updated_at, f.airline,
+                        f.scheduled_departure, f.scheduled_arrival, f.passenger_count, f.currency_source
                  FROM flights f
                  INNER JOIN journey_flights jf ON f.id = jf.flight_id
-                 WHERE jf.journey_id = ?1
+                 WHERE jf.journey_id = ?1 AND f.deleted_at IS NULL
                  ORDER BY jf.sequence_order ASC, f.departure_datetime ASC",
             )
             .context("Failed to prepare journey flights query")?;
@@ -1350,6 +1265,11 @@ impl Database {
                     verified: row.get(30)?,
                     created_at: row.get(31)?,
                     updated_at: row.get(32)?,
+                    airline: row.get(33)?,
+                    scheduled_departure: row.get(34)?,
+                    scheduled_arrival: row.get(35)?,
+                    passenger_count: row.get(36)?,
+                    currency_source: row.get(37)?,
                 })
             })
             .context("Failed to query journey flights")?;
@@ -1364,6 +1284,7 @@ impl Database {
 
     // ===== PILOT LOGBOOK OPERATIONS =====
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_pilot_logbook_entry(
         &self,
         flight_id: &str,
@@ -1429,7 +1350,7 @@ impl Database {
                     simulated_instrument_time, ground_trainer_time, day_takeoffs, day_landings,
                     night_takeoffs, night_landings, ils_approaches, vor_approaches, ndb_approaches,
                     gps_approaches, visual_approaches, ifr_time, vfr_time, pilot_name, copilot_name,
-                    instructor_name, route, remarks, endorsements
+                    instructor_name, route, remarks, endorsements, locked, signed_by, signed_at
              FROM pilot_logbook
              WHERE id = ?1",
             params![entry_id],
@@ -1465,6 +1386,9 @@ impl Database {
                     route: row.get(27)?,
                     remarks: row.get(28)?,
                     endorsements: row.get(29)?,
+                    locked: row.get(30)?,
+                    signed_by: row.get(31)?,
+                    signed_at: row.get(32)?,
                 })
             },
         );
@@ -1483,7 +1407,7 @@ impl Database {
                     simulated_instrument_time, ground_trainer_time, day_takeoffs, day_landings,
                     night_takeoffs, night_landings, ils_approaches, vor_approaches, ndb_approaches,
                     gps_approaches, visual_approaches, ifr_time, vfr_time, pilot_name, copilot_name,
-                    instructor_name, route, remarks, endorsements
+                    instructor_name, route, remarks, endorsements, locked, signed_by, signed_at
              FROM pilot_logbook
              WHERE flight_id = ?1",
             params![flight_id],
@@ -1519,6 +1443,9 @@ impl Database {
                     route: row.get(27)?,
                     remarks: row.get(28)?,
                     endorsements: row.get(29)?,
+                    locked: row.get(30)?,
+                    signed_by: row.get(31)?,
+                    signed_at: row.get(32)?,
                 })
             },
         );
@@ -1537,8 +1464,9 @@ impl Database {
                     simulated_instrument_time, ground_trainer_time, day_takeoffs, day_landings,
                     night_takeoffs, night_landings, ils_approaches, vor_approaches, ndb_approaches,
                     gps_approaches, visual_approaches, ifr_time, vfr_time, pilot_name, copilot_name,
-                    instructor_name, route, remarks, endorsements
+                    instructor_name, route, remarks, endorsements, locked, signed_by, signed_at
              FROM pilot_logbook
+             WHERE flight_id IS NULL OR flight_id NOT IN (SELECT id FROM flights WHERE deleted_at IS NOT NULL)
              ORDER BY id DESC",
         )?;
 
@@ -1575,6 +1503,9 @@ impl Database {
                     route: row.get(27)?,
                     remarks: row.get(28)?,
                     endorsements: row.get(29)?,
+                    locked: row.get(30)?,
+                    signed_by: row.get(31)?,
+                    signed_at: row.get(32)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -1582,6 +1513,7 @@ impl Database {
         Ok(entries)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_pilot_logbook_entry(
         &self,
         entry_id: &str,
@@ -1614,7 +1546,7 @@ impl Database {
         remarks: Option<&str>,
         endorsements: Option<&str>,
     ) -> Result<()> {
-        self.conn.execute(
+        let changed = self.conn.execute(
             "UPDATE pilot_logbook SET
                 pic_time = ?2, sic_time = ?3, dual_time = ?4, instructor_time = ?5, solo_time = ?6,
                 cross_country_time = ?7, day_time = ?8, night_time = ?9, actual_instrument_time = ?10,
@@ -1623,7 +1555,7 @@ impl Database {
                 vor_approaches = ?18, ndb_approaches = ?19, gps_approaches = ?20, visual_approaches = ?21,
                 ifr_time = ?22, vfr_time = ?23, pilot_name = ?24, copilot_name = ?25,
                 instructor_name = ?26, route = ?27, remarks = ?28, endorsements = ?29
-             WHERE id = ?1",
+             WHERE id = ?1 AND locked = 0",
             params![
                 entry_id, pic_time, sic_time, dual_time, instructor_time, solo_time,
                 cross_country_time, day_time, night_time, actual_instrument_time,
@@ -1634,16 +1566,100 @@ impl Database {
             ],
         )
         .context("Failed to update pilot logbook entry")?;
+        if changed == 0 {
+            return Err(self.logbook_write_refused(entry_id));
+        }
         Ok(())
     }
 
+    /// Fails with a "locked" error for a signed entry and "not_found" for a missing one
     pub fn delete_pilot_logbook_entry(&self, entry_id: &str) -> Result<()> {
-        self.conn
-            .execute("DELETE FROM pilot_logbook WHERE id = ?1", params![entry_id])
+        let changed = self
+            .conn
+            .execute("DELETE FROM pilot_logbook WHERE id = ?1 AND locked = 0", params![entry_id])
             .context("Failed to delete pilot logbook entry")?;
+        if changed == 0 {
+            return Err(self.logbook_write_refused(entry_id));
+        }
         Ok(())
     }
 
+    /// Why an update or delete guarded by `locked = 0` matched no row
+    fn logbook_write_refused(&self, entry_id: &str) -> anyhow::Error {
+        let entry = match self.get_pilot_logbook_entry(entry_id) {
+            Ok(entry) => entry,
+            Err(e) => return e,
+        };
+        match entry {
+            Some(entry) if entry.locked => CommandError::Locked(format!(
+                "Logbook entry {} was signed by {} and is locked; unlock it before making changes",
+                entry_id,
+                entry.signed_by.as_deref().unwrap_or("the pilot")
+            ))
+            .into(),
+            _ => CommandError::not_found("Logbook entry", entry_id).into(),
+        }
+    }
+
+    /// Sign an entry so it can no longer be edited or deleted. Returns false when the
+    /// entry does not exist or is already locked.
+    pub fn lock_pilot_logbook_entry(&self, entry_id: &str, signed_by: &str) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        let locked = tx
+            .execute(
+                "UPDATE pilot_logbook SET locked = 1, signed_by = ?2, signed_at = datetime('now')
+                 WHERE id = ?1 AND locked = 0",
+                params![entry_id, signed_by],
+            )
+            .context("Failed to lock pilot logbook entry")?;
+        if locked > 0 {
+            record_logbook_audit(&tx, entry_id, "lock", Some(signed_by), None)?;
+        }
+        tx.commit()?;
+        Ok(locked > 0)
+    }
+
+    /// Clear the signature so the entry can be corrected. Returns false when the entry
+    /// does not exist or is not locked.
+    pub fn unlock_pilot_logbook_entry(&self, entry_id: &str, actor: Option<&str>, reason: Option<&str>) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        let unlocked = tx
+            .execute(
+                "UPDATE pilot_logbook SET locked = 0, signed_by = NULL, signed_at = NULL
+                 WHERE id = ?1 AND locked = 1",
+                params![entry_id],
+            )
+            .context("Failed to unlock pilot logbook entry")?;
+        if unlocked > 0 {
+            record_logbook_audit(&tx, entry_id, "unlock", actor, reason)?;
+        }
+        tx.commit()?;
+        Ok(unlocked > 0)
+    }
+
+    /// Lock/unlock history, newest first; all entries when `entry_id` is None
+    pub fn list_logbook_audit(&self, entry_id: Option<&str>) -> Result<Vec<LogbookAuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, entry_id, action, actor, reason, created_at
+             FROM pilot_logbook_audit
+             WHERE ?1 IS NULL OR entry_id = ?1
+             ORDER BY created_at DESC, rowid DESC",
+        )?;
+        let entries = stmt
+            .query_map(params![entry_id], |row| {
+                Ok(LogbookAuditEntry {
+                    id: row.get(0)?,
+                    entry_id: row.get(1)?,
+                    action: row.get(2)?,
+                    actor: row.get(3)?,
+                    reason: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
     pub fn get_pilot_logbook_totals(&self) -> Result<PilotLogbookTotals> {
         let result = self.conn.query_row(
             "SELECT
@@ -1669,7 +1685,8 @@ impl Database {
                 COALESCE(SUM(visual_approaches), 0),
                 COALESCE(SUM(ifr_time), 0.0),
                 COALESCE(SUM(vfr_time), 0.0)
-             FROM pilot_logbook",
+             FROM pilot_logbook
+             WHERE flight_id IS NULL OR flight_id NOT IN (SELECT id FROM flights WHERE deleted_at IS NOT NULL)",
             [],
             |row| {
                 Ok(PilotLogbookTotals {
@@ -1704,6 +1721,7 @@ impl Database {
 
     // ===== FREQUENT FLYER PROGRAM OPERATIONS =====
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_ffp(
         &self,
         user_id: &str,
@@ -1796,6 +1814,7 @@ impl Database {
         Ok(ffps)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_ffp(
         &self,
         ffp_id: &str,
@@ -1834,6 +1853,7 @@ impl Database {
 
     // ===== AIRPORT OPERATIONS =====
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_airport(
         &self,
         icao_code: Option<&str>,
@@ -1857,7 +1877,7 @@ impl Database {
 
     pub fn get_airport(&self, airport_id: &str) -> Result<Option<Airport>> {
         let result = self.conn.query_row(
-            "SELECT id, icao_code, iata_code, name, city, country, latitude, longitude, timezone
+            "SELECT id, icao_code, iata_code, name, city, country, latitude, longitude, timezone, coordinate_source
              FROM airports WHERE id = ?1",
             params![airport_id],
             |row| {
@@ -1871,6 +1891,7 @@ impl Database {
                     latitude: row.get(6)?,
                     longitude: row.get(7)?,
                     timezone: row.get(8)?,
+                    coordinate_source: row.get(9)?,
                 })
             },
         );
@@ -1882,9 +1903,41 @@ impl Database {
         }
     }
 
+    /// Find an airport by IATA or ICAO code (case-insensitive)
+    pub fn get_airport_by_code(&self, code: &str) -> Result<Option<Airport>> {
+        let airport = self
+            .conn
+            .query_row(
+                "SELECT id, icao_code, iata_code, name, city, country, latitude, longitude, timezone, coordinate_source
+                 FROM airports
+                 WHERE UPPER(iata_code) = UPPER(?1) OR UPPER(icao_code) = UPPER(?1)
+                 ORDER BY CASE WHEN UPPER(iata_code) = UPPER(?1) THEN 0 ELSE 1 END
+                 LIMIT 1",
+                params![code.trim()],
+                |row| {
+                    Ok(Airport {
+                        id: row.get(0)?,
+                        icao_code: row.get(1)?,
+                        iata_code: row.get(2)?,
+                        name: row.get(3)?,
+                        city: row.get(4)?,
+                        country: row.get(5)?,
+                        latitude: row.get(6)?,
+                        longitude: row.get(7)?,
+                        timezone: row.get(8)?,
+                        coordinate_source: row.get(9)?,
+                    })
+                },
+            )
+            .optional()
+            .context("Failed to get airport by code")?;
+
+        Ok(airport)
+    }
+
     pub fn list_all_airports(&self) -> Result<Vec<Airport>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, icao_code, iata_code, name, city, country, latitude, longitude, timezone
+            "SELECT id, icao_code, iata_code, name, city, country, latitude, longitude, timezone, coordinate_source
              FROM airports ORDER BY name ASC",
         )?;
 
@@ -1900,6 +1953,7 @@ impl Database {
                     latitude: row.get(6)?,
                     longitude: row.get(7)?,
                     timezone: row.get(8)?,
+                    coordinate_source: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -1907,6 +1961,7 @@ impl Database {
         Ok(airports)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_airport(
         &self,
         airport_id: &str,
@@ -1919,9 +1974,11 @@ impl Database {
         longitude: Option<f64>,
         timezone: Option<&str>,
     ) -> Result<()> {
+        // Coordinates edited by hand are no longer from the CSV or the geocoder
         self.conn.execute(
             "UPDATE airports SET
                 icao_code = ?2, iata_code = ?3, name = ?4, city = ?5, country = ?6,
+                coordinate_source = CASE WHEN latitude IS ?7 AND longitude IS ?8 THEN coordinate_source END,
                 latitude = ?7, longitude = ?8, timezone = ?9
              WHERE id = ?1",
             params![airport_id, icao_code, iata_code, name, city, country, latitude, longitude, timezone],
@@ -1937,6 +1994,234 @@ impl Database {
         Ok(())
     }
 
+    /// Find likely duplicate airport rows: a shared ICAO/IATA code (case and whitespace
+    /// insensitive), or coordinates within 3 km when the codes don't contradict each other
+    /// (e.g. an IATA-only and an ICAO-only row for the same field)
+    ///
+    /// Rows are grouped by normalised code and swept in latitude order for the distance
+    /// check, so only rows sharing a key or a 3 km latitude band are ever compared.
+    pub fn find_duplicate_airports(&self) -> Result<Vec<DuplicateAirportPair>> {
+        const MAX_KM: f64 = 3.0;
+        // One degree of latitude is at least 110.5 km anywhere on the globe
+        const MAX_LAT_DEG: f64 = MAX_KM / 110.5;
+
+        let airports = self.list_all_airports()?;
+        let normalize = |code: &Option<String>| {
+            code.as_deref()
+                .map(|c| c.trim().to_uppercase())
+                .filter(|c| !c.is_empty())
+        };
+        let icao: Vec<Option<String>> = airports.iter().map(|a| normalize(&a.icao_code)).collect();
+        let iata: Vec<Option<String>> = airports.iter().map(|a| normalize(&a.iata_code)).collect();
+        let distance = |i: usize, j: usize| {
+            let (a, b) = (&airports[i], &airports[j]);
+            match (a.latitude, a.longitude, b.latitude, b.longitude) {
+                (Some(lat1), Some(lon1), Some(lat2), Some(lon2)) => {
+                    Some(crate::geo::calculate_distance(lat1, lon1, lat2, lon2).1)
+                }
+                _ => None,
+            }
+        };
+
+        // Pair (lower index, higher index) -> reason; code matches are found first and win
+        let mut found: std::collections::BTreeMap<(usize, usize), String> = std::collections::BTreeMap::new();
+        for (codes, label) in [(&icao, "ICAO"), (&iata, "IATA")] {
+            let mut groups: std::collections::HashMap<&str, Vec<usize>> = std::collections::HashMap::new();
+            for (index, code) in codes.iter().enumerate() {
+                if let Some(code) = code {
+                    groups.entry(code.as_str()).or_default().push(index);
+                }
+            }
+            for (code, members) in groups {
+                for (n, &i) in members.iter().enumerate() {
+                    for &j in &members[n + 1..] {
+                        found.entry((i, j)).or_insert_with(|| format!("Same {} code {}", label, code));
+                    }
+                }
+            }
+        }
+
+        let mut by_latitude: Vec<(f64, usize)> = airports
+            .iter()
+            .enumerate()
+            .filter_map(|(index, a)| a.longitude.and(a.latitude).map(|lat| (lat, index)))
+            .collect();
+        by_latitude.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (n, &(lat, i)) in by_latitude.iter().enumerate() {
+            for &(other_lat, j) in &by_latitude[n + 1..] {
+                if other_lat - lat > MAX_LAT_DEG {
+                    break;
+                }
+                let key = (i.min(j), i.max(j));
+                let conflicting = (icao[i].is_some() && icao[j].is_some()) || (iata[i].is_some() && iata[j].is_some());
+                if conflicting || found.contains_key(&key) {
+                    continue;
+                }
+                if let Some(km) = distance(i, j).filter(|km| *km <= MAX_KM) {
+                    found.insert(key, format!("Coordinates {:.1} km apart", km));
+                }
+            }
+        }
+
+        Ok(found
+            .into_iter()
+            .map(|((i, j), reason)| DuplicateAirportPair {
+                airport_a: airports[i].clone(),
+                airport_b: airports[j].clone(),
+                reason,
+                distance_km: distance(i, j),
+            })
+            .collect())
+    }
+
+    /// Show what merging `remove_id` into `keep_id` would do: the kept row's empty
+    /// fields are filled from the removed row, and flight/fuel references to codes
+    /// only the removed row carries are re-pointed to the kept airport
+    pub fn preview_airport_merge(&self, keep_id: &str, remove_id: &str) -> Result<AirportMergePreview> {
+        if keep_id == remove_id {
+            anyhow::bail!("Cannot merge an airport into itself");
+        }
+        let keep = self
+            .get_airport(keep_id)?
+            .with_context(|| format!("Airport {} not found", keep_id))?;
+        let remove = self
+            .get_airport(remove_id)?
+            .with_context(|| format!("Airport {} not found", remove_id))?;
+
+        let fill = |k: &Option<String>, r: &Option<String>| {
+            k.clone()
+                .filter(|v| !v.trim().is_empty())
+                .or_else(|| r.clone().filter(|v| !v.trim().is_empty()))
+        };
+        let merged = Airport {
+            id: keep.id.clone(),
+            icao_code: fill(&keep.icao_code, &remove.icao_code),
+            iata_code: fill(&keep.iata_code, &remove.iata_code),
+            name: if keep.name.trim().is_empty() { remove.name.clone() } else { keep.name.clone() },
+            city: fill(&keep.city, &remove.city),
+            country: fill(&keep.country, &remove.country),
+            latitude: keep.latitude.or(remove.latitude),
+            longitude: keep.longitude.or(remove.longitude),
+            timezone: fill(&keep.timezone, &remove.timezone),
+            coordinate_source: if keep.latitude.is_some() {
+                keep.coordinate_source.clone()
+            } else {
+                remove.coordinate_source.clone()
+            },
+        };
+
+        let text = |v: &Option<String>| v.clone().unwrap_or_default();
+        let number = |v: Option<f64>| v.map(|n| n.to_string()).unwrap_or_default();
+        let fields = [
+            ("icao_code", text(&keep.icao_code), text(&remove.icao_code), text(&merged.icao_code)),
+            ("iata_code", text(&keep.iata_code), text(&remove.iata_code), text(&merged.iata_code)),
+            ("name", keep.name.clone(), remove.name.clone(), merged.name.clone()),
+            ("city", text(&keep.city), text(&remove.city), text(&merged.city)),
+            ("country", text(&keep.country), text(&remove.country), text(&merged.country)),
+            ("latitude", number(keep.latitude), number(remove.latitude), number(merged.latitude)),
+            ("longitude", number(keep.longitude), number(remove.longitude), number(merged.longitude)),
+            ("timezone", text(&keep.timezone), text(&remove.timezone), text(&merged.timezone)),
+        ];
+        let differences = fields
+            .into_iter()
+            .filter(|(_, k, r, _)| k.trim() != r.trim())
+            .map(|(field, keep_value, remove_value, merged_value)| AirportFieldDiff {
+                field: field.to_string(),
+                keep_value,
+                remove_value,
+                merged_value,
+            })
+            .collect();
+
+        // Codes that will no longer resolve once the removed row is gone
+        let merged_codes: Vec<String> = [&merged.icao_code, &merged.iata_code]
+            .iter()
+            .filter_map(|c| c.as_deref().map(|c| c.trim().to_uppercase()))
+            .collect();
+        let mut code_rewrites = Vec::new();
+        if let Some(target_code) = merged_codes.first() {
+            for code in [&remove.icao_code, &remove.iata_code].into_iter().flatten() {
+                let code = code.trim().to_uppercase();
+                if !code.is_empty() && !merged_codes.contains(&code) {
+                    code_rewrites.push(AirportCodeRewrite { from_code: code, to_code: target_code.clone() });
+                }
+            }
+        }
+
+        let mut flight_references = 0;
+        for rewrite in &code_rewrites {
+            flight_references += self.conn.query_row(
+                "SELECT COUNT(*) FROM flights
+                 WHERE UPPER(TRIM(departure_airport)) = ?1 OR UPPER(TRIM(arrival_airport)) = ?1",
+                params![rewrite.from_code],
+                |row| row.get::<_, i64>(0),
+            )?;
+        }
+
+        Ok(AirportMergePreview {
+            keep,
+            remove,
+            merged,
+            differences,
+            code_rewrites,
+            flight_references,
+        })
+    }
+
+    /// Merge `remove_id` into `keep_id` in a single transaction (see `preview_airport_merge`)
+    pub fn merge_airports(&self, keep_id: &str, remove_id: &str) -> Result<AirportMergePreview> {
+        let preview = self.preview_airport_merge(keep_id, remove_id)?;
+
+        self.conn.execute("BEGIN TRANSACTION", [])?;
+        let result = (|| -> Result<()> {
+            for rewrite in &preview.code_rewrites {
+                let params = params![rewrite.from_code, rewrite.to_code];
+                self.conn.execute(
+                    "UPDATE flights SET departure_airport = ?2 WHERE UPPER(TRIM(departure_airport)) = ?1",
+                    params,
+                )?;
+                self.conn.execute(
+                    "UPDATE flights SET arrival_airport = ?2 WHERE UPPER(TRIM(arrival_airport)) = ?1",
+                    params,
+                )?;
+                self.conn.execute(
+                    "UPDATE fuel_entries SET airport_code = ?2 WHERE UPPER(TRIM(airport_code)) = ?1",
+                    params,
+                )?;
+            }
+
+            self.delete_airport(remove_id)?;
+            let merged = &preview.merged;
+            self.update_airport(
+                &merged.id,
+                merged.icao_code.as_deref(),
+                merged.iata_code.as_deref(),
+                &merged.name,
+                merged.city.as_deref(),
+                merged.country.as_deref(),
+                merged.latitude,
+                merged.longitude,
+                merged.timezone.as_deref(),
+            )?;
+            self.conn.execute(
+                "UPDATE airports SET coordinate_source = ?2 WHERE id = ?1",
+                params![merged.id, merged.coordinate_source],
+            )?;
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => {
+                self.conn.execute("COMMIT", [])?;
+                Ok(preview)
+            }
+            Err(e) => {
+                self.conn.execute("ROLLBACK", []).ok();
+                Err(e.context("Failed to merge airports"))
+            }
+        }
+    }
+
     // ===== AIRCRAFT TYPE OPERATIONS =====
 
     pub fn create_aircraft_type(
@@ -2006,6 +2291,7 @@ impl Database {
         Ok(aircraft_types)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_aircraft_type(
         &self,
         aircraft_type_id: &str,
@@ -2035,6 +2321,7 @@ impl Database {
 
     // ===== CUSTOM DOCUMENTS OPERATIONS =====
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_custom_document(
         &self,
         user_id: &str,
@@ -2120,6 +2407,7 @@ impl Database {
         Ok(documents)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_custom_document(
         &self,
         document_id: &str,
@@ -2151,6 +2439,7 @@ impl Database {
 
     // ===== FUEL PRICE OPERATIONS =====
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_fuel_price(
         &self,
         user_id: &str,
@@ -2366,6 +2655,7 @@ impl Database {
         Ok(fuel_price)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_fuel_price(
         &self,
         fuel_price_id: &str,
@@ -2398,6 +2688,58 @@ impl Database {
         Ok(())
     }
 
+    // ===== QUERY PERFORMANCE OPERATIONS =====
+
+    /// Record one query execution in query_performance for the index advisor
+    ///
+    /// `filters` lists the columns the query filters on, equality columns first.
+    /// Uses a cached statement so it is cheap enough to leave on for every analytics
+    /// query; the table is trimmed to the newest rows every few hundred inserts.
+    pub fn record_query_performance(
+        &self,
+        query_type: &str,
+        table_name: &str,
+        filters: &[&str],
+        elapsed: std::time::Duration,
+        result_count: usize,
+    ) -> Result<()> {
+        use std::hash::{Hash, Hasher};
+
+        let filters = filters.join(",");
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (query_type, table_name, &filters).hash(&mut hasher);
+
+        self.conn
+            .prepare_cached(
+                "INSERT INTO query_performance (id, query_hash, query_type, table_name, filters, execution_time_ms, result_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?
+            .execute(params![
+                Uuid::new_v4().to_string(),
+                format!("{:016x}", hasher.finish()),
+                query_type,
+                table_name,
+                filters,
+                elapsed.as_millis() as i64,
+                result_count as i64,
+            ])
+            .context("Failed to record query performance")?;
+
+        if QUERY_PERF_INSERTS
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            .is_multiple_of(QUERY_PERF_PRUNE_INTERVAL)
+        {
+            self.conn.execute(
+                "DELETE FROM query_performance WHERE id NOT IN (
+                    SELECT id FROM query_performance ORDER BY created_at DESC LIMIT ?1
+                 )",
+                params![QUERY_PERF_MAX_ROWS],
+            )?;
+        }
+
+        Ok(())
+    }
+
     // ===== ANALYTICS OPERATIONS =====
 
     /// Get temporal flight data grouped by time period
@@ -2420,7 +2762,7 @@ impl Database {
                     strftime('%Y', departure_datetime) || '-' ||
                     printf('%02d', ((CAST(strftime('%m', departure_datetime) AS INTEGER) - 1) / 3) * 3 + 1) || '-01' as period_start
                 FROM flights
-                WHERE user_id = ?1 AND departure_datetime >= '1903-01-01'"
+                WHERE user_id = ?1 AND deleted_at IS NULL AND departure_datetime >= '1903-01-01'"
             ),
             "year" => String::from(
                 "SELECT
@@ -2430,7 +2772,7 @@ impl Database {
                     COALESCE(SUM(carbon_emissions_kg), 0.0) as total_co2_kg,
                     strftime('%Y', departure_datetime) || '-01-01' as period_start
                 FROM flights
-                WHERE user_id = ?1 AND departure_datetime >= '1903-01-01'"
+                WHERE user_id = ?1 AND deleted_at IS NULL AND departure_datetime >= '1903-01-01'"
             ),
             _ => String::from(
                 "SELECT
@@ -2440,7 +2782,7 @@ impl Database {
                     COALESCE(SUM(carbon_emissions_kg), 0.0) as total_co2_kg,
                     strftime('%Y-%m-01', departure_datetime) as period_start
                 FROM flights
-                WHERE user_id = ?1 AND departure_datetime >= '1903-01-01'"
+                WHERE user_id = ?1 AND deleted_at IS NULL AND departure_datetime >= '1903-01-01'"
             ),
         };
 
@@ -2466,6 +2808,7 @@ impl Database {
                         total_distance_km: row.get(2)?,
                         total_co2_kg: row.get(3)?,
                         period_start: row.get(4)?,
+                        measurements: None,
                     })
                 })?.collect::<std::result::Result<Vec<_>, _>>()?
             }
@@ -2477,6 +2820,7 @@ impl Database {
                         total_distance_km: row.get(2)?,
                         total_co2_kg: row.get(3)?,
                         period_start: row.get(4)?,
+                        measurements: None,
                     })
                 })?.collect::<std::result::Result<Vec<_>, _>>()?
             }
@@ -2488,6 +2832,7 @@ impl Database {
                         total_distance_km: row.get(2)?,
                         total_co2_kg: row.get(3)?,
                         period_start: row.get(4)?,
+                        measurements: None,
                     })
                 })?.collect::<std::result::Result<Vec<_>, _>>()?
             }
@@ -2499,6 +2844,7 @@ impl Database {
                         total_distance_km: row.get(2)?,
                         total_co2_kg: row.get(3)?,
                         period_start: row.get(4)?,
+                        measurements: None,
                     })
                 })?.collect::<std::result::Result<Vec<_>, _>>()?
             }
@@ -2518,7 +2864,7 @@ impl Database {
         let mut query = String::from(
             "WITH departures AS (
                 SELECT departure_airport as airport_code, 1 as is_departure, 0 as is_arrival
-                FROM flights WHERE user_id = ? AND departure_airport IS NOT NULL"
+                FROM flights WHERE user_id = ? AND deleted_at IS NULL AND departure_airport IS NOT NULL"
         );
 
         if start_date.is_some() {
@@ -2531,7 +2877,7 @@ impl Database {
         query.push_str(
             "), arrivals AS (
                 SELECT arrival_airport as airport_code, 0 as is_departure, 1 as is_arrival
-                FROM flights WHERE user_id = ? AND arrival_airport IS NOT NULL"
+                FROM flights WHERE user_id = ? AND deleted_at IS NULL AND arrival_airport IS NOT NULL"
         );
 
         if start_date.is_some() {
@@ -2641,7 +2987,7 @@ impl Database {
         let query = format!(
             "SELECT id, notes, distance_km, departure_airport, arrival_airport
              FROM flights
-             WHERE user_id = ?1 AND notes IS NOT NULL AND notes != ''{}",
+             WHERE user_id = ?1 AND deleted_at IS NULL AND notes IS NOT NULL AND notes != ''{}",
             date_filter
         );
 
@@ -2650,6 +2996,7 @@ impl Database {
 
         // Struct to hold flight data
         struct FlightData {
+            #[allow(dead_code)]
             id: String,
             notes: String,
             distance_km: f64,
@@ -2713,8 +3060,9 @@ impl Database {
         // (passenger1, passenger2) -> (flight_count, routes)
         let mut co_travel: HashMap<(String, String), (i64, HashSet<String>)> = HashMap::new();
 
+        let filter = crate::passenger_filter::PassengerFilter::load(&self.conn);
         for flight in &flights {
-            let passengers = Self::parse_passengers_from_notes(&flight.notes);
+            let passengers = Self::parse_passengers_from_notes(&flight.notes, &filter);
 
             // Update passenger stats
             for p in &passengers {
@@ -2766,7 +3114,11 @@ impl Database {
     }
 
     /// Helper function to parse passenger names from notes field
-    fn parse_passengers_from_notes(notes: &str) -> std::collections::HashSet<String> {
+    /// Parsing artifacts ("PAX", "N/A", "2 ADT", ...) are dropped, see passenger_filter.rs
+    fn parse_passengers_from_notes(
+        notes: &str,
+        filter: &crate::passenger_filter::PassengerFilter,
+    ) -> std::collections::HashSet<String> {
         use std::collections::HashSet;
         let mut passengers = HashSet::new();
 
@@ -2774,7 +3126,7 @@ impl Database {
         if let Ok(parsed) = serde_json::from_str::<Vec<String>>(notes) {
             for name in parsed {
                 let trimmed = name.trim();
-                if !trimmed.is_empty() {
+                if !trimmed.is_empty() && !filter.is_artifact(trimmed) {
                     passengers.insert(trimmed.to_uppercase());
                 }
             }
@@ -2790,13 +3142,14 @@ impl Database {
             .replace("pax:", "");
 
         // Split by common delimiters
-        for part in cleaned.split(|c| c == ',' || c == '\n' || c == ';' || c == '|') {
+        for part in cleaned.split([',', '\n', ';', '|']) {
             let trimmed = part.trim();
             // Skip empty strings and common non-passenger entries
             if !trimmed.is_empty()
                 && trimmed.len() > 1
                 && !trimmed.to_lowercase().starts_with("note")
                 && !trimmed.to_lowercase().starts_with("comment")
+                && !filter.is_artifact(trimmed)
             {
                 passengers.insert(trimmed.to_uppercase());
             }
@@ -2829,7 +3182,7 @@ impl Database {
         let query = format!(
             "SELECT id, notes, distance_km, carbon_emissions_kg, total_duration, departure_airport, arrival_airport
              FROM flights
-             WHERE user_id = ?1 AND notes IS NOT NULL AND notes != ''{}",
+             WHERE user_id = ?1 AND deleted_at IS NULL AND notes IS NOT NULL AND notes != ''{}",
             date_filter
         );
 
@@ -2904,8 +3257,9 @@ impl Database {
         // passenger -> (flights, distance, co2, hours, unique_airports_set)
         let mut passenger_stats: HashMap<String, (i64, f64, f64, f64, HashSet<String>)> = HashMap::new();
 
+        let filter = crate::passenger_filter::PassengerFilter::load(&self.conn);
         for flight in &flights {
-            let passengers = Self::parse_passengers_from_notes(&flight.notes);
+            let passengers = Self::parse_passengers_from_notes(&flight.notes, &filter);
 
             for p in passengers {
                 let entry = passenger_stats.entry(p).or_insert((0, 0.0, 0.0, 0.0, HashSet::new()));
@@ -2942,7 +3296,7 @@ impl Database {
             "distance" => metrics.sort_by(|a, b| b.total_distance_km.partial_cmp(&a.total_distance_km).unwrap_or(std::cmp::Ordering::Equal)),
             "co2" => metrics.sort_by(|a, b| b.total_co2_kg.partial_cmp(&a.total_co2_kg).unwrap_or(std::cmp::Ordering::Equal)),
             "hours" => metrics.sort_by(|a, b| b.total_flight_hours.partial_cmp(&a.total_flight_hours).unwrap_or(std::cmp::Ordering::Equal)),
-            _ => metrics.sort_by(|a, b| b.total_flights.cmp(&a.total_flights)), // Default: flights
+            _ => metrics.sort_by_key(|x| std::cmp::Reverse(x.total_flights)), // Default: flights
         }
 
         // Apply limit
@@ -2967,10 +3321,12 @@ impl Database {
                      ELSE 0.0
                 END as avg_flight_hours,
                 MAX(f.departure_datetime) as last_flown,
-                CAST((julianday('now') - julianday(MAX(f.departure_datetime))) AS INTEGER) as days_since_last_flight
+                CAST((julianday('now') - julianday(MAX(f.departure_datetime))) AS INTEGER) as days_since_last_flight,
+                MIN(CASE WHEN f.departure_datetime >= '1901' THEN f.departure_datetime END) as first_flown,
+                CAST((julianday('now') - julianday(MIN(CASE WHEN f.departure_datetime >= '1901' THEN f.departure_datetime END))) AS INTEGER) as days_in_service
             FROM flights f
             LEFT JOIN aircraft_types at ON f.aircraft_type_id = at.id
-            WHERE f.user_id = ?1
+            WHERE f.user_id = ?1 AND f.deleted_at IS NULL
             GROUP BY tail_number, aircraft_type
             ORDER BY total_flights DESC"
         ).context("Failed to prepare aircraft utilization query")?;
@@ -2986,6 +3342,13 @@ impl Database {
                     avg_flight_hours: row.get(5)?,
                     last_flown: row.get(6)?,
                     days_since_last_flight: row.get(7)?,
+                    first_flown: row.get(8)?,
+                    // A single flight today still counts as one day in service
+                    days_in_service: row.get::<_, Option<i64>>(9)?.map(|d| d.max(1)),
+                    hours_per_month: None,
+                    is_idle: false,
+                    idle_cost: None,
+                    cost_per_flight_hour: None,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -2997,7 +3360,7 @@ impl Database {
     pub fn get_cost_breakdown(&self, user_id: &str) -> Result<Vec<CostBreakdown>> {
         // Calculate total for percentage
         let total: f64 = self.conn.query_row(
-            "SELECT COALESCE(SUM(total_cost), 0.0) FROM flights WHERE user_id = ?1",
+            "SELECT COALESCE(SUM(total_cost), 0.0) FROM flights WHERE user_id = ?1 AND deleted_at IS NULL",
             params![user_id],
             |row| row.get(0),
         )?;
@@ -3015,7 +3378,7 @@ impl Database {
                 currency,
                 COUNT(*) as item_count
             FROM flights
-            WHERE user_id = ?1 AND total_cost IS NOT NULL
+            WHERE user_id = ?1 AND deleted_at IS NULL AND total_cost IS NOT NULL
             GROUP BY currency"
         ).context("Failed to prepare cost breakdown query")?;
 
@@ -3045,7 +3408,7 @@ impl Database {
                 COALESCE(SUM(pl.day_landings), 0) as day_landings
             FROM flights f
             JOIN pilot_logbook pl ON f.id = pl.flight_id
-            WHERE f.user_id = ?1",
+            WHERE f.user_id = ?1 AND f.deleted_at IS NULL",
             params![user_id],
             |row| {
                 Ok(DayNightStats {
@@ -3082,7 +3445,7 @@ impl Database {
                 COALESCE(f.departure_datetime, '') as departure_datetime,
                 f.aircraft_type_id as aircraft_type
             FROM flights f
-            WHERE f.user_id = ?1 AND f.id IS NOT NULL
+            WHERE f.user_id = ?1 AND f.deleted_at IS NULL AND f.id IS NOT NULL
             ORDER BY distance_km DESC
             LIMIT ?2"
         ).context("Failed to prepare long haul flights query")?;
@@ -3104,54 +3467,68 @@ impl Database {
         Ok(data)
     }
 
-    /// Get pilot currency requirements
+    /// Get pilot currency requirements as of today
     pub fn get_pilot_currency(&self, user_id: &str) -> Result<Vec<CurrencyItem>> {
-        // This is a simplified example - real implementation would track specific requirements
-        let mut items = Vec::new();
-
-        // Get recent takeoffs/landings for currency
-        let (day_landings, night_landings, last_flight): (i64, i64, Option<String>) = self.conn.query_row(
-            "SELECT
-                COALESCE(SUM(pl.day_landings), 0),
-                COALESCE(SUM(pl.night_landings), 0),
-                MAX(f.departure_datetime)
-            FROM flights f
-            JOIN pilot_logbook pl ON f.id = pl.flight_id
-            WHERE f.user_id = ?1
-            AND f.departure_datetime >= date('now', '-90 days')",
-            params![user_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
-        ).unwrap_or((0, 0, None));
-
-        let days_since = last_flight.as_ref().map(|date| {
-            self.conn.query_row(
-                "SELECT CAST((julianday('now') - julianday(?1)) AS INTEGER)",
-                params![date],
-                |row| row.get(0),
-            ).unwrap_or(0)
-        });
-
-        // Day currency (3 takeoffs/landings in 90 days)
-        items.push(CurrencyItem {
-            requirement: "Day Currency (3 landings/90 days)".to_string(),
-            current_count: day_landings,
-            required: 3,
-            expires_in_days: days_since,
-            status: if day_landings >= 3 { "good" } else { "warning" }.to_string(),
-            last_completed: last_flight.clone(),
-        });
-
-        // Night currency (3 night landings in 90 days)
-        items.push(CurrencyItem {
-            requirement: "Night Currency (3 landings/90 days)".to_string(),
-            current_count: night_landings,
-            required: 3,
-            expires_in_days: days_since,
-            status: if night_landings >= 3 { "good" } else { "warning" }.to_string(),
-            last_completed: last_flight,
-        });
+        self.pilot_currency_on(user_id, chrono::Local::now().date_naive())
+    }
+
+    /// Landing currency as of `today`: 3 landings in the preceding 90 days (14 CFR 61.57),
+    /// lapsing 90 days after the landing that completes the most recent three. Night
+    /// landings count towards day currency too.
+    pub fn pilot_currency_on(&self, user_id: &str, today: chrono::NaiveDate) -> Result<Vec<CurrencyItem>> {
+        const CURRENCY_DAYS: i64 = 90;
+        const REQUIRED_LANDINGS: i64 = 3;
+
+        let mut landings_by_date = self.conn.prepare(
+            "SELECT substr(f.departure_datetime, 1, 10),
+                    COALESCE(pl.day_landings, 0) + COALESCE(pl.night_landings, 0),
+                    COALESCE(pl.night_landings, 0)
+             FROM flights f JOIN pilot_logbook pl ON pl.flight_id = f.id
+             WHERE f.user_id = ?1 AND f.deleted_at IS NULL
+               AND COALESCE(pl.day_landings, 0) + COALESCE(pl.night_landings, 0) > 0
+             ORDER BY f.departure_datetime DESC",
+        )?;
+        let landings = landings_by_date
+            .query_map(params![user_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let window_start = today - chrono::Duration::days(CURRENCY_DAYS);
+
+        let currency = |key: &str, requirement: &str, count: fn(&(String, i64, i64)) -> i64| {
+            let dated: Vec<(chrono::NaiveDate, i64)> = landings
+                .iter()
+                .filter(|landing| count(landing) > 0)
+                .filter_map(|landing| {
+                    chrono::NaiveDate::parse_from_str(&landing.0, "%Y-%m-%d").ok().map(|date| (date, count(landing)))
+                })
+                .collect();
+            let current_count = dated.iter().filter(|(date, _)| *date >= window_start).map(|(_, n)| n).sum();
+            let mut counted = 0;
+            let expires = dated.iter().find_map(|(date, n)| {
+                counted += n;
+                (counted >= REQUIRED_LANDINGS).then(|| *date + chrono::Duration::days(CURRENCY_DAYS))
+            });
+            let expires_in_days = expires.map(|expires| (expires - today).num_days());
+            let status = match expires_in_days {
+                Some(days) if days > 30 => "good",
+                Some(days) if days >= 0 => "warning",
+                _ => "expired",
+            };
+            CurrencyItem {
+                key: key.to_string(),
+                requirement: requirement.to_string(),
+                current_count,
+                required: REQUIRED_LANDINGS,
+                expires_date: expires.map(|date| date.format("%Y-%m-%d").to_string()),
+                expires_in_days,
+                status: status.to_string(),
+                last_completed: dated.first().map(|(date, _)| date.format("%Y-%m-%d").to_string()),
+            }
+        };
 
-        Ok(items)
+        Ok(vec![
+            currency("day_landings", "Day Currency (3 landings/90 days)", |landing| landing.1),
+            currency("night_landings", "Night Currency (3 night landings/90 days)", |landing| landing.2),
+        ])
     }
 
     /// Get monthly cost trend
@@ -3169,7 +3546,7 @@ impl Database {
                 COALESCE(SUM(total_cost), 0.0) as other_costs,
                 COALESCE(currency, 'USD') as currency
             FROM flights
-            WHERE user_id = ?1 AND total_cost IS NOT NULL
+            WHERE user_id = ?1 AND deleted_at IS NULL AND total_cost IS NOT NULL
             GROUP BY period, currency
             ORDER BY period ASC"
         ).context("Failed to prepare monthly cost trend query")?;
@@ -3191,46 +3568,452 @@ impl Database {
         Ok(data)
     }
 
-    /// Get runway risk assessment
-    pub fn get_runway_risk_data(&self, user_id: &str) -> Result<Vec<RunwayRiskData>> {
+    /// Visited airports with their elevation (None when unknown), most visited first
+    fn visited_airport_elevations(&self, user_id: &str) -> Result<Vec<VisitedAirport>> {
         let mut stmt = self.conn.prepare(
             "WITH airport_visits AS (
                 SELECT departure_airport as airport_code
-                FROM flights WHERE user_id = ?1 AND departure_airport IS NOT NULL
+                FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND departure_airport IS NOT NULL
                 UNION ALL
                 SELECT arrival_airport as airport_code
-                FROM flights WHERE user_id = ?1 AND arrival_airport IS NOT NULL
+                FROM flights WHERE user_id = ?1 AND deleted_at IS NULL AND arrival_airport IS NOT NULL
             )
             SELECT
                 av.airport_code,
-                COALESCE(a.name, av.airport_code) as airport_name,
-                5000 as runway_length_ft,
-                COUNT(*) as visits,
-                CASE
-                    WHEN 5000 >= 5000 THEN 'safe'
-                    WHEN 5000 >= 3000 THEN 'marginal'
-                    WHEN 5000 >= 2000 THEN 'watch_out'
-                    ELSE 'nope'
-                END as risk_level
+                COALESCE(MAX(a.name), av.airport_code) as airport_name,
+                MAX(a.elevation_ft) as elevation_ft,
+                MAX(a.longest_runway_ft) as longest_runway_ft,
+                COUNT(*) as visits
             FROM airport_visits av
             LEFT JOIN airports a ON av.airport_code = a.id OR av.airport_code = a.icao_code OR av.airport_code = a.iata_code
             GROUP BY av.airport_code
-            ORDER BY visits DESC
-            LIMIT 20"
-        ).context("Failed to prepare runway risk query")?;
+            ORDER BY visits DESC, av.airport_code ASC"
+        ).context("Failed to prepare visited airports query")?;
 
-        let data = stmt
+        let rows = stmt
             .query_map(params![user_id], |row| {
-                Ok(RunwayRiskData {
-                    airport_code: row.get(0)?,
-                    airport_name: row.get(1)?,
-                    runway_length_ft: row.get(2)?,
-                    visits: row.get(3)?,
-                    risk_level: row.get(4)?,
+                Ok(VisitedAirport {
+                    code: row.get(0)?,
+                    name: row.get(1)?,
+                    elevation_ft: row.get(2)?,
+                    longest_runway_ft: row.get(3)?,
+                    visits: row.get(4)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Get runway risk assessment for the 20 most visited airports from each airport's
+    /// longest runway, falling back to calculations::ASSUMED_RUNWAY_LENGTH_FT only when the
+    /// length is unknown; the risk comes from how much of it a hot day at the field leaves.
+    pub fn get_runway_risk_data(&self, user_id: &str) -> Result<Vec<RunwayRiskData>> {
+        let data = self
+            .visited_airport_elevations(user_id)?
+            .into_iter()
+            .take(20)
+            .map(|airport| {
+                let known_length = airport.longest_runway_ft.filter(|ft| *ft > 0);
+                let runway_length_ft = known_length.unwrap_or(crate::calculations::ASSUMED_RUNWAY_LENGTH_FT);
+                let effective_runway_ft =
+                    crate::calculations::effective_runway_length_ft(runway_length_ft, airport.elevation_ft);
+                RunwayRiskData {
+                    airport_code: airport.code,
+                    airport_name: airport.name,
+                    runway_length_ft,
+                    runway_length_assumed: known_length.is_none(),
+                    elevation_ft: airport.elevation_ft,
+                    effective_runway_ft,
+                    visits: airport.visits,
+                    risk_level: crate::calculations::runway_risk_level(effective_runway_ft).to_string(),
+                }
+            })
+            .collect();
+
+        Ok(data)
+    }
+
+    /// Visited airports at or above `threshold_ft`, highest first. Airports without a known
+    /// elevation are counted in missing_elevation_count instead.
+    pub fn get_high_altitude_airports(&self, user_id: &str, threshold_ft: i32) -> Result<HighAltitudeReport> {
+        let mut missing_elevation_count = 0;
+        let mut airports = Vec::new();
+        for airport in self.visited_airport_elevations(user_id)? {
+            match airport.elevation_ft {
+                None => missing_elevation_count += 1,
+                Some(elevation_ft) if elevation_ft >= threshold_ft => airports.push(HighAltitudeAirport {
+                    airport_code: airport.code,
+                    airport_name: airport.name,
+                    elevation_ft,
+                    hot_day_density_altitude_ft: crate::calculations::hot_day_density_altitude_ft(elevation_ft),
+                    visits: airport.visits,
+                }),
+                Some(_) => {}
+            }
+        }
+        airports.sort_by(|a, b| b.elevation_ft.cmp(&a.elevation_ft).then_with(|| a.airport_code.cmp(&b.airport_code)));
+
+        Ok(HighAltitudeReport { threshold_ft, airports, missing_elevation_count })
+    }
+
+    /// Get flight metrics grouped by an arbitrary dimension:
+    /// "tag", "fare_class", "aircraft_class", "aircraft_category" or "custom_field" (with `field_name`).
+    /// Flights without a value are bucketed under "(none)". A flight with several tags
+    /// is counted once per tag.
+    pub fn get_analytics_by_dimension(
+        &self,
+        user_id: &str,
+        dimension: &str,
+        field_name: Option<&str>,
+    ) -> Result<Vec<DimensionGroupMetrics>> {
+        let (group_expr, join_sql) = match dimension {
+            "tag" => (
+                "t.tag",
+                "LEFT JOIN flight_tags t ON t.flight_id = f.id",
+            ),
+            // Grouped by raw value here, then merged into cabins below
+            "fare_class" => ("f.fare_class", ""),
+            "airline" => ("f.airline", ""),
+            "aircraft_class" => (
+                "at.class",
+                "LEFT JOIN aircraft_types at ON f.aircraft_type_id = at.id",
+            ),
+            "aircraft_category" => (
+                "at.category",
+                "LEFT JOIN aircraft_types at ON f.aircraft_type_id = at.id",
+            ),
+            "custom_field" => (
+                "cf.field_value",
+                "LEFT JOIN flight_custom_fields cf ON cf.flight_id = f.id AND cf.field_name = ?2",
+            ),
+            other => anyhow::bail!("Unknown analytics dimension: {}", other),
+        };
+
+        let field_name = if dimension == "custom_field" {
+            Some(field_name.context("custom_field dimension requires a field_name")?)
+        } else {
+            None
+        };
+
+        let query = format!(
+            "SELECT
+                COALESCE(NULLIF(TRIM({}), ''), '(none)') as grp,
+                COUNT(*) as flight_count,
+                COALESCE(SUM(f.distance_km), 0.0) as total_distance_km,
+                COALESCE(SUM(f.flight_duration), 0.0) / 60.0 as total_duration_hours,
+                COALESCE(SUM(f.total_cost), 0.0) as total_cost,
+                COALESCE(SUM(f.carbon_emissions_kg), 0.0) as total_co2_kg
+            FROM flights f
+            {}
+            WHERE f.user_id = ?1 AND f.deleted_at IS NULL
+            GROUP BY grp
+            ORDER BY flight_count DESC, grp ASC",
+            group_expr, join_sql
+        );
+
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .context("Failed to prepare dimension analytics query")?;
+
+        let map_row = |row: &rusqlite::Row| {
+            Ok(DimensionGroupMetrics {
+                group: row.get(0)?,
+                flight_count: row.get(1)?,
+                total_distance_km: row.get(2)?,
+                total_duration_hours: row.get(3)?,
+                total_cost: row.get(4)?,
+                total_co2_kg: row.get(5)?,
+                measurements: None,
+            })
+        };
+
+        let data = match field_name {
+            Some(name) => stmt.query_map(params![user_id, name], map_row)?,
+            None => stmt.query_map(params![user_id], map_row)?,
+        }
+        .collect::<std::result::Result<Vec<_>, _>>()?;
 
+        if dimension == "fare_class" {
+            return Ok(group_by_cabin(data, &crate::fare_classes::FareClassMapping::from_settings(self)));
+        }
         Ok(data)
     }
+
+    /// Commute statistics relative to a home base airport.
+    /// Uses the user's `home_base:{user_id}` setting when present, otherwise the most
+    /// frequently used airport. When no airport clearly dominates, `home_base` is None
+    /// and only the candidates are reported.
+    pub fn get_commute_analytics(&self, user_id: &str) -> Result<CommuteAnalytics> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT UPPER(TRIM(departure_airport)), UPPER(TRIM(arrival_airport)),
+                        departure_datetime, arrival_datetime
+                 FROM flights
+                 WHERE user_id = ?1 AND deleted_at IS NULL
+                 ORDER BY departure_datetime ASC",
+            )
+            .context("Failed to prepare commute analytics query")?;
+
+        let flights: Vec<(String, String, String, Option<String>)> = stmt
+            .query_map(params![user_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // Airport movements (departures + arrivals) to rank home base candidates
+        let mut movements: std::collections::HashMap<&str, i64> = std::collections::HashMap::new();
+        for (dep, arr, _, _) in &flights {
+            *movements.entry(dep.as_str()).or_insert(0) += 1;
+            *movements.entry(arr.as_str()).or_insert(0) += 1;
+        }
+        let total_movements: i64 = movements.values().sum();
+        let mut ranked: Vec<(&str, i64)> = movements.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let candidates: Vec<HomeBaseCandidate> = ranked
+            .iter()
+            .take(3)
+            .map(|(code, count)| HomeBaseCandidate {
+                airport_code: code.to_string(),
+                movements: *count,
+                share: *count as f64 / total_movements as f64,
+            })
+            .collect();
+
+        let configured = self
+            .get_setting(&format!("home_base:{}", user_id))?
+            .map(|code| code.trim().to_uppercase())
+            .filter(|code| !code.is_empty());
+
+        let (home_base, home_base_source) = match configured {
+            Some(code) => (Some(code), "user"),
+            None => match ranked.as_slice() {
+                [] => (None, "none"),
+                [(code, _)] => (Some(code.to_string()), "derived"),
+                // The top airport must be clearly ahead of the runner-up (25% more movements)
+                [(code, top), (_, second), ..] if *top * 4 >= *second * 5 => {
+                    (Some(code.to_string()), "derived")
+                }
+                _ => (None, "ambiguous"),
+            },
+        };
+
+        let mut analytics = CommuteAnalytics {
+            home_base: home_base.clone(),
+            home_base_source: home_base_source.to_string(),
+            candidates,
+            total_flights: flights.len() as i64,
+            flights_from_home: 0,
+            flights_to_home: 0,
+            trips_completed: 0,
+            average_trip_days: 0.0,
+            longest_trip: None,
+            top_away_destinations: Vec::new(),
+        };
+
+        let home = match home_base {
+            Some(code) => code,
+            None => return Ok(analytics),
+        };
+        // Flights may use either the ICAO or the IATA code of the home airport
+        let home_codes: Vec<String> = match self.get_airport_by_code(&home)? {
+            Some(airport) => [Some(home.clone()), airport.icao_code, airport.iata_code]
+                .into_iter()
+                .flatten()
+                .map(|code| code.trim().to_uppercase())
+                .filter(|code| !code.is_empty())
+                .collect(),
+            None => vec![home],
+        };
+        let is_home = |code: &str| home_codes.iter().any(|h| h == code);
+
+        let mut destination_counts: std::collections::HashMap<&str, i64> = std::collections::HashMap::new();
+        let mut trip_days: Vec<f64> = Vec::new();
+        // (left home at, airports visited) while away
+        let mut open_trip: Option<(chrono::NaiveDateTime, Vec<String>)> = None;
+
+        for (dep, arr, departure, arrival) in &flights {
+            if is_home(dep) {
+                analytics.flights_from_home += 1;
+            }
+            if is_home(arr) {
+                analytics.flights_to_home += 1;
+            } else {
+                *destination_counts.entry(arr.as_str()).or_insert(0) += 1;
+            }
+
+            let departed_at = match crate::timezone::parse_local_datetime(departure) {
+                Some(dt) => dt,
+                None => continue,
+            };
+
+            if is_home(dep) && !is_home(arr) {
+                open_trip = Some((departed_at, vec![arr.clone()]));
+            } else if is_home(arr) && !is_home(dep) {
+                if let Some((left_at, destinations)) = open_trip.take() {
+                    let returned_at = arrival
+                        .as_deref()
+                        .and_then(crate::timezone::parse_local_datetime)
+                        .unwrap_or(departed_at);
+                    let days = (returned_at - left_at).num_minutes().max(0) as f64 / 1440.0;
+                    trip_days.push(days);
+
+                    let is_longest = analytics
+                        .longest_trip
+                        .as_ref()
+                        .is_none_or(|t| days > t.days);
+                    if is_longest {
+                        analytics.longest_trip = Some(TripAway {
+                            departed: left_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                            returned: returned_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                            days,
+                            destinations,
+                        });
+                    }
+                }
+            } else if let Some((_, destinations)) = open_trip.as_mut() {
+                if !is_home(arr) && !destinations.contains(arr) {
+                    destinations.push(arr.clone());
+                }
+            }
+        }
+
+        analytics.trips_completed = trip_days.len() as i64;
+        if !trip_days.is_empty() {
+            analytics.average_trip_days = trip_days.iter().sum::<f64>() / trip_days.len() as f64;
+        }
+
+        let mut destinations: Vec<(&str, i64)> = destination_counts.into_iter().collect();
+        destinations.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        analytics.top_away_destinations = destinations
+            .into_iter()
+            .take(10)
+            .map(|(code, visits)| AwayDestination {
+                airport_code: code.to_string(),
+                visits,
+            })
+            .collect();
+
+        Ok(analytics)
+    }
+}
+
+/// An airport from the user's flights, matched to the airports table when possible
+struct VisitedAirport {
+    code: String,
+    name: String,
+    elevation_ft: Option<i32>,
+    longest_runway_ft: Option<i32>,
+    visits: i64,
+}
+
+/// Average delay per key, longest first, keeping the first `limit`
+fn worst_delay_groups(delays: impl Iterator<Item = (String, i64)>, limit: usize) -> Vec<DelayGroup> {
+    let mut totals: std::collections::HashMap<String, (i64, i64)> = std::collections::HashMap::new();
+    for (key, delay) in delays {
+        let entry = totals.entry(key).or_default();
+        entry.0 += 1;
+        entry.1 += delay;
+    }
+    let mut groups: Vec<DelayGroup> = totals
+        .into_iter()
+        .map(|(key, (flights, total))| DelayGroup {
+            key,
+            flights,
+            average_delay_minutes: total as f64 / flights as f64,
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.average_delay_minutes
+            .partial_cmp(&a.average_delay_minutes)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    groups.truncate(limit);
+    groups
+}
+
+/// Merge raw fare class groups into their cabins ("J" and "Business" are both "business"),
+/// most flights first; "(none)" stays its own group
+fn group_by_cabin(
+    groups: Vec<DimensionGroupMetrics>,
+    mapping: &crate::fare_classes::FareClassMapping,
+) -> Vec<DimensionGroupMetrics> {
+    let mut merged: Vec<DimensionGroupMetrics> = Vec::new();
+    for group in groups {
+        let cabin = if group.group == "(none)" {
+            group.group.clone()
+        } else {
+            mapping.cabin(&group.group).as_str().to_string()
+        };
+        match merged.iter_mut().find(|m| m.group == cabin) {
+            Some(m) => {
+                m.flight_count += group.flight_count;
+                m.total_distance_km += group.total_distance_km;
+                m.total_duration_hours += group.total_duration_hours;
+                m.total_cost += group.total_cost;
+                m.total_co2_kg += group.total_co2_kg;
+            }
+            None => merged.push(DimensionGroupMetrics { group: cabin, ..group }),
+        }
+    }
+    merged.sort_by(|a, b| b.flight_count.cmp(&a.flight_count).then_with(|| a.group.cmp(&b.group)));
+    merged
+}
+
+/// WHERE clause (with numbered parameters) for listing a user's flights
+/// An empty filter only excludes soft-deleted flights.
+pub(crate) fn flight_filter_conditions(user_id: &str, filter: &FlightListFilter) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions = vec!["user_id = ?1".to_string(), "deleted_at IS NULL".to_string()];
+    let mut values: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id.to_string())];
+    let non_empty = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+
+    if let Some(start) = non_empty(&filter.start_date) {
+        values.push(Box::new(start));
+        conditions.push(format!("departure_datetime >= ?{}", values.len()));
+    }
+    if let Some(end) = non_empty(&filter.end_date) {
+        values.push(Box::new(end));
+        conditions.push(format!("departure_datetime < date(?{}, '+1 day')", values.len()));
+    }
+    if let Some(airport) = non_empty(&filter.airport) {
+        values.push(Box::new(airport.to_uppercase()));
+        let n = values.len();
+        conditions.push(format!("(departure_airport = ?{} OR arrival_airport = ?{})", n, n));
+    }
+    if let Some(aircraft) = non_empty(&filter.aircraft) {
+        values.push(Box::new(aircraft));
+        conditions.push(format!("aircraft_registration = ?{} COLLATE NOCASE", values.len()));
+    }
+
+    (conditions.join(" AND "), values)
+}
+
+fn record_logbook_audit(
+    conn: &Connection,
+    entry_id: &str,
+    action: &str,
+    actor: Option<&str>,
+    reason: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO pilot_logbook_audit (id, entry_id, action, actor, reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![Uuid::new_v4().to_string(), entry_id, action, actor, reason],
+    )
+    .context("Failed to record logbook audit entry")?;
+    Ok(())
+}
+
+/// Escape `%`, `_` and `\` so user input matches literally in `LIKE ... ESCAPE '\'`
+pub(crate) fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
 }
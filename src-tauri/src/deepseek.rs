@@ -4,6 +4,8 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::error::CommandError;
+
 const DEEPSEEK_BASE_URL: &str = "https://api.deepseek.com/anthropic";
 const DEEPSEEK_MODEL: &str = "deepseek-chat";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
@@ -17,6 +19,9 @@ pub struct ResearchRequest {
     pub research_events: bool,
     pub research_weather: bool,
     pub research_passengers: bool,
+    /// Prompt template body; None uses the built-in DeepSeek default
+    #[serde(default)]
+    pub prompt_template: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +31,9 @@ pub struct ResearchResult {
     pub events: Vec<Event>,
     pub weather: Option<WeatherInfo>,
     pub passenger_mentions: Vec<PassengerMention>,
+    /// How the web search results were deduplicated and trimmed before analysis
+    #[serde(default)]
+    pub search_stats: crate::web_search::SearchResultStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +56,42 @@ pub struct WeatherInfo {
     pub condition: String,
     pub temperature: String,
     pub description: String,
+    /// METAR-style fields; null when the sources do not give them
+    #[serde(default)]
+    pub temperature_c: Option<f64>,
+    #[serde(default)]
+    pub wind_direction_deg: Option<i32>,
+    #[serde(default)]
+    pub wind_speed_kt: Option<f64>,
+    #[serde(default)]
+    pub visibility_km: Option<f64>,
+}
+
+impl WeatherInfo {
+    /// False for placeholder answers ("Unknown", empty) that carry no observation
+    pub fn is_determined(&self) -> bool {
+        let placeholder = |v: &str| matches!(v.trim().to_lowercase().as_str(), "" | "unknown" | "n/a" | "none");
+        !(placeholder(&self.condition) && placeholder(&self.temperature) && self.temperature_c.is_none())
+    }
+
+    /// temperature_c, or parsed from the free-text temperature ("18°C", "64 F")
+    pub fn temperature_celsius(&self) -> Option<f64> {
+        if self.temperature_c.is_some() {
+            return self.temperature_c;
+        }
+        let text = self.temperature.trim();
+        let end = text
+            .char_indices()
+            .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-')))
+            .map_or(text.len(), |(i, _)| i);
+        let value: f64 = text[..end].parse().ok()?;
+        let unit = text[end..].trim_start_matches(|c: char| c == '°' || c.is_whitespace());
+        match unit.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('F') => Some((value - 32.0) * 5.0 / 9.0),
+            Some('C') | None => Some(value),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,18 +107,38 @@ pub async fn research_flight_context(
     web_search_results: Vec<String>,
     api_key: &str,
 ) -> Result<ResearchResult> {
-    // Build the research prompt
+    let mut topics = Vec::new();
+    for (enabled, topic) in [
+        (request.research_news, "news"),
+        (request.research_events, "events"),
+        (request.research_weather, "weather"),
+        (request.research_passengers, "passengers"),
+    ] {
+        if enabled {
+            topics.push(topic.to_string());
+        }
+    }
+    let passenger_names = if request.research_passengers { request.passenger_names.clone() } else { Vec::new() };
+
+    // Build the research prompt from the template, then the fixed response format
+    let template = request
+        .prompt_template
+        .as_deref()
+        .unwrap_or(crate::prompt_templates::DEEPSEEK_DEFAULT);
     let mut prompt_parts = vec![
-        format!("You are an expert research analyst. Analyze the following information about travel on {} to/from {}.", request.date, request.location),
+        crate::prompt_templates::render(
+            template,
+            &crate::prompt_templates::PromptContext {
+                route: &request.location,
+                date: &request.date,
+                passengers: &passenger_names,
+                topics: &topics,
+                sources: &web_search_results,
+            },
+        ),
         String::new(),
-        "Web search results:".to_string(),
     ];
 
-    for (idx, result) in web_search_results.iter().enumerate() {
-        prompt_parts.push(format!("\n[Source {}]:\n{}", idx + 1, result));
-    }
-
-    prompt_parts.push(String::new());
     prompt_parts.push("Based on these search results, provide a comprehensive analysis in JSON format with the following structure:".to_string());
     prompt_parts.push(
         r#"{
@@ -97,7 +161,11 @@ pub async fn research_flight_context(
   "weather": {
     "condition": "Condition",
     "temperature": "Temperature",
-    "description": "Weather description"
+    "description": "Weather description",
+    "temperature_c": number or null,
+    "wind_direction_deg": integer or null,
+    "wind_speed_kt": number or null,
+    "visibility_km": number or null
   },
   "passenger_mentions": [
     {
@@ -110,29 +178,8 @@ pub async fn research_flight_context(
         .to_string(),
     );
 
-    if !request.passenger_names.is_empty() {
-        prompt_parts.push(format!(
-            "\nPassenger names to research: {}",
-            request.passenger_names.join(", ")
-        ));
-    }
-
-    prompt_parts.push(String::new());
-    prompt_parts.push("Focus on:".to_string());
-    if request.research_news {
-        prompt_parts.push("- Top news stories from that date and location".to_string());
-    }
-    if request.research_events {
-        prompt_parts.push("- Major events, conferences, or gatherings".to_string());
-    }
-    if request.research_weather {
-        prompt_parts.push("- Weather conditions".to_string());
-    }
-    if request.research_passengers {
-        prompt_parts.push("- Any mentions of the passenger names".to_string());
-    }
-
     prompt_parts.push(String::new());
+    prompt_parts.push("Set \"weather\" to null if weather was not requested or the sources do not say.".to_string());
     prompt_parts
         .push("Return ONLY valid JSON, no markdown formatting, no explanation.".to_string());
 
@@ -165,8 +212,7 @@ pub async fn research_flight_context(
         .await?;
 
     if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(anyhow::anyhow!("DeepSeek API error: {}", error_text));
+        return Err(CommandError::from_response("DeepSeek", response).await.into());
     }
 
     let response_json: serde_json::Value = response.json().await?;
@@ -200,6 +246,7 @@ pub async fn research_flight_context(
 }
 
 /// Use DeepSeek for OCR analysis as an alternative to Gemini
+#[allow(dead_code)]
 pub async fn analyze_boarding_pass(
     image_bytes: Vec<u8>,
     api_key: &str,
@@ -300,6 +347,9 @@ Use null for missing values. Return ONLY the JSON object."#
 pub struct DeepSeekChatResult {
     pub content: String,
     pub tokens_used: Option<u32>,
+    /// Served from the AI response cache; no tokens were spent
+    #[serde(default)]
+    pub cached: bool,
 }
 
 /// Simple chat with DeepSeek (no tool calling, just conversation)
@@ -359,6 +409,7 @@ pub async fn chat_with_deepseek(query: &str, api_key: &str) -> Result<DeepSeekCh
     Ok(DeepSeekChatResult {
         content,
         tokens_used,
+        cached: false,
     })
 }
 
@@ -477,6 +528,8 @@ pub struct InvestigationSynthesis {
     pub key_findings: Vec<String>,
     pub corroboration_score: f64,
     pub sources_analysis: Vec<SourceAnalysisItem>,
+    #[serde(default)]
+    pub claims: Vec<crate::investigation::ClaimAnalysis>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -527,6 +580,7 @@ Analyze these search results and provide:
    - 0.4-0.6: Circumstantial or indirect evidence
    - 0.7-0.9: Strong corroborating evidence from multiple sources
    - 1.0: Direct, verified evidence from official records
+5. List each distinct factual claim and the index of every source that states it
 
 Format your response as JSON:
 {{
@@ -540,6 +594,12 @@ Format your response as JSON:
       "credibility": "official|news|social|unverified",
       "key_quote": "Extracted quote or fact"
     }}
+  ],
+  "claims": [
+    {{
+      "claim": "Short factual statement",
+      "source_indices": [0, 2]
+    }}
   ]
 }}
 
@@ -642,38 +702,27 @@ pub async fn run_deepseek_investigation(
     let elapsed_ms = start_time.elapsed().as_millis() as i64;
 
     // Convert to InvestigationResult format matching models.rs structure
-    let sources: Vec<crate::models::InvestigationSource> = search_results
-        .into_iter()
-        .enumerate()
-        .filter_map(|(idx, result)| {
-            let analysis = synthesis
+    let (sources, claims) =
+        crate::investigation::collect_sources(&search_results, &synthesis.claims, |idx| {
+            synthesis
                 .sources_analysis
                 .iter()
-                .find(|s| s.source_index == idx)?;
-
-            let relevance_score = match analysis.relevance.as_str() {
-                "high" => 0.9,
-                "medium" => 0.6,
-                _ => 0.3,
-            };
-
-            Some(crate::models::InvestigationSource {
-                title: result.title.clone(),
-                url: result.url.clone(),
-                excerpt: result.snippet.clone(),
-                relevance_score,
-                publication_date: None, // TODO: Extract from search results
-            })
-        })
-        .collect();
+                .find(|s| s.source_index == idx)
+                .map(|s| crate::investigation::relevance_score(&s.relevance))
+        });
+
+    // Score from independent sources per claim rather than the model's own estimate
+    let assessment = crate::investigation::score_corroboration(&claims, &sources);
 
     Ok(crate::models::InvestigationResult {
         investigation_id: uuid::Uuid::new_v4().to_string(),
         status: "completed".to_string(),
         ai_summary: synthesis.summary,
         sources,
-        corroboration_score: synthesis.corroboration_score,
+        corroboration_score: assessment.score,
         generated_queries: queries,
         processing_time_ms: elapsed_ms as i32,
+        claims: assessment.claims,
+        score_explanation: assessment.explanation,
     })
 }
@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 /// Represents a single chunk from a document (typically one PDF page)
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct DocumentChunk {
     pub id: String,
     pub queue_id: String,
@@ -84,6 +85,7 @@ impl ProcessingStage {
         }
     }
 
+    #[allow(dead_code)]
     pub fn next(&self) -> Option<Self> {
         match self {
             ProcessingStage::Pending => Some(ProcessingStage::OcrExtraction),
@@ -98,6 +100,7 @@ impl ProcessingStage {
 
 /// Represents a document ingestion job in the queue
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct IngestionJob {
     pub id: String,
     pub user_id: String,
@@ -185,6 +188,7 @@ impl DocumentShredder {
     }
 
     /// Extract text from a PDF page using pdf-extract
+    #[allow(dead_code)]
     pub fn extract_text_from_page(&self, page_path: &Path) -> Result<String> {
         let bytes = fs::read(page_path)?;
         let text = pdf_extract::extract_text_from_mem(&bytes)
@@ -193,6 +197,7 @@ impl DocumentShredder {
     }
 
     /// Extract text from entire PDF (fallback method)
+    #[allow(dead_code)]
     pub fn extract_text_from_pdf(&self, pdf_path: &Path) -> Result<String> {
         let bytes = fs::read(pdf_path)?;
         let text = pdf_extract::extract_text_from_mem(&bytes)
@@ -350,6 +355,7 @@ impl IngestionQueue {
     }
 
     /// Get ingestion job by ID
+    #[allow(dead_code)]
     pub fn get_job(&self, job_id: &str) -> Result<Option<IngestionJob>> {
         let job = self.conn
             .query_row(
@@ -52,12 +52,12 @@ impl OcrAgent {
         match self.try_fast_extract(chunk_path) {
             Ok(text) if text.len() > 50 => {
                 // Success! Text is substantial, use it
-                eprintln!("📄 Fast extract successful: {} chars", text.len());
+                tracing::debug!("Fast extract successful: {} chars", text.len());
                 return Ok((text, ExtractionMethod::TextExtract));
             }
             Ok(_) | Err(_) => {
                 // Text is empty/short or extraction failed
-                eprintln!("📸 Fast extract failed/insufficient, falling back to Vision OCR");
+                tracing::debug!("Fast extract failed/insufficient, falling back to Vision OCR");
             }
         }
 
@@ -230,7 +230,7 @@ impl FlightMatcherAgent {
         // Strategy 1: Match by tail number (highest confidence)
         for tail in &tail_numbers {
             let mut stmt = conn.prepare(
-                "SELECT id FROM flights WHERE aircraft_registration = ?1"
+                "SELECT id FROM flights WHERE aircraft_registration = ?1 AND deleted_at IS NULL"
             )?;
 
             let flight_ids: Vec<String> = stmt
@@ -250,7 +250,7 @@ impl FlightMatcherAgent {
         // Strategy 2: Match by flight number
         for flight_num in &flight_numbers {
             let mut stmt = conn.prepare(
-                "SELECT id FROM flights WHERE flight_number = ?1"
+                "SELECT id FROM flights WHERE flight_number = ?1 AND deleted_at IS NULL"
             )?;
 
             let flight_ids: Vec<String> = stmt
@@ -274,7 +274,8 @@ impl FlightMatcherAgent {
                     let mut stmt = conn.prepare(
                         "SELECT id FROM flights
                          WHERE DATE(departure_datetime) = ?1
-                         AND (departure_airport LIKE ?2 OR arrival_airport LIKE ?2)"
+                         AND (departure_airport LIKE ?2 OR arrival_airport LIKE ?2)
+                         AND deleted_at IS NULL"
                     )?;
 
                     let location_pattern = format!("%{}%", location);
@@ -302,7 +303,7 @@ impl FlightMatcherAgent {
             let mut stmt = conn.prepare(
                 "SELECT f.id FROM flights f
                  INNER JOIN passenger_mappings pm ON f.id = pm.flight_id
-                 WHERE pm.passenger_name LIKE ?1 OR pm.full_name LIKE ?1"
+                 WHERE (pm.passenger_name LIKE ?1 OR pm.full_name LIKE ?1) AND f.deleted_at IS NULL"
             )?;
 
             let name_pattern = format!("%{}%", person);
@@ -336,6 +337,7 @@ impl GraphBuilderAgent {
     }
 
     /// Add a relationship edge to the graph
+    #[allow(clippy::too_many_arguments)]
     pub fn add_relationship(
         &self,
         source_type: &str,
@@ -606,7 +608,7 @@ impl WorkerPool {
             };
 
             if let Some(chunk) = chunk {
-                eprintln!("🔧 Processing chunk {} (page {})", chunk.id, chunk.chunk_number);
+                tracing::debug!("Processing chunk {} (page {})", chunk.id, chunk.chunk_number);
 
                 // Mark as processing
                 {
@@ -629,10 +631,10 @@ impl WorkerPool {
                             ProcessingStage::Completed,
                             None,
                         )?;
-                        eprintln!("✅ Chunk {} completed", chunk.id);
+                        tracing::debug!("Chunk {} completed", chunk.id);
                     }
                     Err(e) => {
-                        eprintln!("❌ Chunk {} failed: {}", chunk.id, e);
+                        tracing::error!("Chunk {} failed: {}", chunk.id, e);
                         let queue = self.queue.lock().await;
                         queue.update_chunk_progress(
                             &chunk.id,
@@ -0,0 +1,811 @@
+// Flight import from saved airline emails (.eml)
+// Confirmation emails often embed schema.org FlightReservation JSON-LD for mail clients;
+// when they do it is used as is. Otherwise flight numbers of known carriers are looked for
+// in the text, and the airports, date and times are taken from the lines around each one.
+//
+// The MIME handling covers what itineraries use: multipart bodies, forwarded messages,
+// base64 and quoted-printable parts, and UTF-8 or Latin-1 text.
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::airlines;
+use crate::ocr::OcrFlightResult;
+
+/// Nested multiparts and forwards deeper than this are ignored
+const MAX_MIME_DEPTH: usize = 8;
+
+// ===== MIME =====
+
+/// The readable parts of an email
+#[derive(Debug, Default)]
+pub struct EmlMessage {
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    /// Date header; dates written without a year are placed relative to it
+    pub sent: Option<NaiveDate>,
+    pub text_parts: Vec<String>,
+    pub html_parts: Vec<String>,
+}
+
+pub fn parse_eml(raw: &[u8]) -> Result<EmlMessage> {
+    let raw = String::from_utf8_lossy(raw).replace("\r\n", "\n");
+    let (headers, _) = split_entity(&raw);
+    if headers.is_empty() {
+        bail!("Not an email message: no headers found");
+    }
+
+    let mut message = EmlMessage {
+        subject: header_value(&headers, "subject").map(decode_encoded_words),
+        from: header_value(&headers, "from").map(decode_encoded_words),
+        sent: header_value(&headers, "date").and_then(parse_date_header),
+        ..Default::default()
+    };
+    collect_parts(&raw, &mut message, 0);
+    if message.text_parts.is_empty() && message.html_parts.is_empty() {
+        bail!("The email has no text or HTML body");
+    }
+    Ok(message)
+}
+
+/// Unfolded (lowercase name, value) headers and the body of a message or MIME part
+fn split_entity(entity: &str) -> (Vec<(String, String)>, &str) {
+    let (head, body) = match entity.find("\n\n") {
+        Some(end) => (&entity[..end], &entity[end + 2..]),
+        None => (entity, ""),
+    };
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    (headers, body)
+}
+
+fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+}
+
+fn parse_date_header(value: &str) -> Option<NaiveDate> {
+    // Drop a trailing comment such as "(UTC)", which chrono does not accept
+    let value = value.split('(').next().unwrap_or(value).trim();
+    chrono::DateTime::parse_from_rfc2822(value).ok().map(|d| d.date_naive())
+}
+
+/// "text/html; charset=UTF-8" as the lowercase type and its parameters
+fn parse_content_type(value: &str) -> (String, HashMap<String, String>) {
+    let mut pieces = value.split(';');
+    let mime = pieces.next().unwrap_or("").trim().to_lowercase();
+    let params = pieces
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().trim_matches('"').to_string()))
+        .collect();
+    (mime, params)
+}
+
+fn collect_parts(entity: &str, message: &mut EmlMessage, depth: usize) {
+    if depth > MAX_MIME_DEPTH {
+        return;
+    }
+    let (headers, body) = split_entity(entity);
+    let (mime, params) = parse_content_type(header_value(&headers, "content-type").unwrap_or("text/plain"));
+
+    if mime.starts_with("multipart/") {
+        if let Some(boundary) = params.get("boundary") {
+            for part in split_multipart(body, boundary) {
+                collect_parts(&part, message, depth + 1);
+            }
+        }
+        return;
+    }
+    if mime == "message/rfc822" {
+        // Forwarded itinerary
+        collect_parts(body, message, depth + 1);
+        return;
+    }
+    if mime != "text/plain" && mime != "text/html" {
+        return;
+    }
+
+    let encoding = header_value(&headers, "content-transfer-encoding").unwrap_or("").trim().to_lowercase();
+    let bytes = match encoding.as_str() {
+        "base64" => {
+            let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, compact).unwrap_or_default()
+        }
+        "quoted-printable" => decode_quoted_printable(body, false),
+        _ => body.as_bytes().to_vec(),
+    };
+    let text = decode_charset(&bytes, params.get("charset").map(String::as_str));
+    if mime == "text/html" {
+        message.html_parts.push(text);
+    } else {
+        message.text_parts.push(text);
+    }
+}
+
+fn split_multipart(body: &str, boundary: &str) -> Vec<String> {
+    let delimiter = format!("--{}", boundary);
+    let close = format!("--{}--", boundary);
+    let mut parts = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in body.split('\n') {
+        let line_end = line.trim_end();
+        if line_end == close {
+            break;
+        }
+        if line_end == delimiter {
+            if let Some(part) = current.take() {
+                parts.push(part.join("\n"));
+            }
+            current = Some(Vec::new());
+        } else if let Some(part) = current.as_mut() {
+            part.push(line);
+        }
+    }
+    if let Some(part) = current {
+        parts.push(part.join("\n"));
+    }
+    parts
+}
+
+/// Quoted-printable bytes; `underscores` is the header ("Q" encoded word) variant
+fn decode_quoted_printable(text: &str, underscores: bool) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'=' if bytes.get(i + 1) == Some(&b'\n') => i += 2, // soft line break
+            b'=' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 3;
+                    }
+                    None => {
+                        out.push(b'=');
+                        i += 1;
+                    }
+                }
+            }
+            b'_' if underscores => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let latin1 = charset.is_some_and(|c| {
+        matches!(c.trim().to_lowercase().as_str(), "iso-8859-1" | "iso-8859-15" | "latin1" | "windows-1252" | "cp1252")
+    });
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) if latin1 => bytes.iter().map(|&b| b as char).collect(),
+        Err(_) => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+/// Subject and From headers with RFC 2047 words ("=?UTF-8?B?...?=") decoded
+fn decode_encoded_words(value: &str) -> String {
+    static ENCODED_WORD: OnceLock<Regex> = OnceLock::new();
+    static BETWEEN_WORDS: OnceLock<Regex> = OnceLock::new();
+    let encoded_word = ENCODED_WORD.get_or_init(|| Regex::new(r"=\?([^?]+)\?([BbQq])\?([^?]*)\?=").unwrap());
+    // Whitespace between two encoded words is not part of the text
+    let between_words = BETWEEN_WORDS.get_or_init(|| Regex::new(r"\?=\s+=\?").unwrap());
+
+    let joined = between_words.replace_all(value, "?==?");
+    encoded_word
+        .replace_all(&joined, |caps: &regex::Captures| {
+            let bytes = if caps[2].eq_ignore_ascii_case("b") {
+                base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &caps[3]).unwrap_or_default()
+            } else {
+                decode_quoted_printable(&caps[3], true)
+            };
+            decode_charset(&bytes, Some(&caps[1]))
+        })
+        .trim()
+        .to_string()
+}
+
+/// Visible text of an HTML part, one line per row, paragraph or line break
+pub fn html_to_text(html: &str) -> String {
+    static PATTERNS: OnceLock<[Regex; 5]> = OnceLock::new();
+    let [scripts, styles, breaks, cells, tags] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap(),
+            Regex::new(r"(?is)<style[^>]*>.*?</style>").unwrap(),
+            Regex::new(r"(?i)<br\s*/?>|</(?:p|div|tr|li|table|h[1-6])>").unwrap(),
+            Regex::new(r"(?i)</t[dh]>").unwrap(),
+            Regex::new(r"<[^>]*>").unwrap(),
+        ]
+    });
+
+    let text = scripts.replace_all(html, " ");
+    let text = styles.replace_all(&text, " ");
+    let text = breaks.replace_all(&text, "\n");
+    let text = cells.replace_all(&text, " ");
+    let text = tags.replace_all(&text, "");
+    html_escape::decode_html_entities(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ===== FLIGHT SEGMENTS =====
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentSource {
+    /// schema.org FlightReservation markup; exact
+    JsonLd,
+    /// Read from the itinerary text; check before importing
+    #[default]
+    Text,
+}
+
+/// One flight found in an email
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmlSegment {
+    pub source: SegmentSource,
+    pub flight_number: Option<String>,
+    pub airline: Option<String>,
+    pub departure_airport: Option<String>,
+    pub arrival_airport: Option<String>,
+    /// Local time, YYYY-MM-DDTHH:MM:SS, or just the date when no time was found
+    pub departure_datetime: Option<String>,
+    pub arrival_datetime: Option<String>,
+    pub booking_reference: Option<String>,
+    /// Several passengers on one booking are joined with "; "
+    pub passenger_name: Option<String>,
+    pub seat_number: Option<String>,
+    pub fare_class: Option<String>,
+    pub ticket_number: Option<String>,
+}
+
+impl EmlSegment {
+    fn fields(&self) -> [(&'static str, &Option<String>); 11] {
+        [
+            ("flight_number", &self.flight_number),
+            ("airline", &self.airline),
+            ("departure_airport", &self.departure_airport),
+            ("arrival_airport", &self.arrival_airport),
+            ("departure_datetime", &self.departure_datetime),
+            ("arrival_datetime", &self.arrival_datetime),
+            ("booking_reference", &self.booking_reference),
+            ("passenger_name", &self.passenger_name),
+            ("seat_number", &self.seat_number),
+            ("fare_class", &self.fare_class),
+            ("ticket_number", &self.ticket_number),
+        ]
+    }
+
+    /// Names of the fields that were found
+    pub fn extracted_fields(&self) -> Vec<&'static str> {
+        self.fields().into_iter().filter(|(_, value)| value.is_some()).map(|(name, _)| name).collect()
+    }
+
+    /// Fields a flight cannot be created without
+    pub fn missing_required(&self) -> Vec<&'static str> {
+        [
+            ("departure_airport", &self.departure_airport),
+            ("arrival_airport", &self.arrival_airport),
+            ("departure_datetime", &self.departure_datetime),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// As an OCR result, so flights are created the same way as from a boarding pass
+    pub fn to_ocr_result(&self) -> OcrFlightResult {
+        OcrFlightResult {
+            flight_number: self.flight_number.clone(),
+            departure_airport: self.departure_airport.clone(),
+            arrival_airport: self.arrival_airport.clone(),
+            departure_datetime: self.departure_datetime.clone(),
+            arrival_datetime: self.arrival_datetime.clone(),
+            passenger_name: self.passenger_name.clone(),
+            booking_reference: self.booking_reference.clone(),
+            ticket_number: self.ticket_number.clone(),
+            seat_number: self.seat_number.clone(),
+            fare_class: self.fare_class.clone(),
+            airline: self.airline.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Flights in the email: from JSON-LD when there is any, otherwise from the text
+///
+/// `is_airport` says whether a three-letter code is a known airport, which keeps words
+/// such as "THE" from being read as airports.
+pub fn extract_segments(message: &EmlMessage, is_airport: impl Fn(&str) -> bool) -> Vec<EmlSegment> {
+    let mut reservations = Vec::new();
+    for html in &message.html_parts {
+        for block in json_ld_blocks(html) {
+            collect_reservations(&block, &mut reservations);
+        }
+    }
+    if !reservations.is_empty() {
+        return merge_passengers(reservations);
+    }
+
+    let text = if message.text_parts.is_empty() {
+        message.html_parts.iter().map(|html| html_to_text(html)).collect::<Vec<_>>().join("\n")
+    } else {
+        message.text_parts.join("\n")
+    };
+    let reference = message.sent.unwrap_or_else(|| chrono::Local::now().date_naive());
+    text_segments(&text, reference, &is_airport)
+}
+
+/// Flights in plain itinerary text, such as the OCR text of a printed itinerary; dates
+/// without a year are placed relative to `reference`
+pub fn extract_text_segments(text: &str, reference: NaiveDate, is_airport: impl Fn(&str) -> bool) -> Vec<EmlSegment> {
+    text_segments(text, reference, &is_airport)
+}
+
+// ----- JSON-LD -----
+
+fn json_ld_blocks(html: &str) -> Vec<Value> {
+    static SCRIPT: OnceLock<Regex> = OnceLock::new();
+    let script = SCRIPT
+        .get_or_init(|| Regex::new(r#"(?is)<script[^>]*type\s*=\s*["']application/ld\+json["'][^>]*>(.*?)</script>"#).unwrap());
+    script
+        .captures_iter(html)
+        .filter_map(|caps| serde_json::from_str(caps[1].trim()).ok())
+        .collect()
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    let matches = |t: &Value| t.as_str().is_some_and(|t| t == name || t.ends_with(&format!("/{}", name)));
+    match value.get("@type") {
+        Some(Value::Array(types)) => types.iter().any(matches),
+        Some(t) => matches(t),
+        None => false,
+    }
+}
+
+/// Every FlightReservation anywhere in the markup, including inside @graph and lists
+fn collect_reservations(value: &Value, out: &mut Vec<EmlSegment>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_reservations(item, out)),
+        Value::Object(_) if has_type(value, "FlightReservation") => out.push(segment_from_reservation(value)),
+        Value::Object(map) => map.values().for_each(|item| collect_reservations(item, out)),
+        _ => {}
+    }
+}
+
+/// A string or number at `path`, trimmed; None for objects and empty strings
+fn json_text(value: &Value, path: &[&str]) -> Option<String> {
+    let target = path.iter().try_fold(value, |v, key| v.get(key))?;
+    let text = match target {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn segment_from_reservation(reservation: &Value) -> EmlSegment {
+    let flight = reservation.get("reservationFor").unwrap_or(&Value::Null);
+    let airline_code = json_text(flight, &["airline", "iataCode"]);
+    let flight_number = json_text(flight, &["flightNumber"]).map(|number| {
+        let number: String = number.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+        match &airline_code {
+            // Some carriers give only the digits
+            Some(code) if number.chars().all(|c| c.is_ascii_digit()) => format!("{}{}", code.to_uppercase(), number),
+            _ => number,
+        }
+    });
+    let airport = |key: &str| {
+        json_text(flight, &[key, "iataCode"])
+            .or_else(|| json_text(flight, &[key]))
+            .map(|code| code.to_uppercase())
+    };
+
+    EmlSegment {
+        source: SegmentSource::JsonLd,
+        flight_number,
+        airline: json_text(flight, &["airline", "name"]).or(airline_code),
+        departure_airport: airport("departureAirport"),
+        arrival_airport: airport("arrivalAirport"),
+        departure_datetime: json_text(flight, &["departureTime"]).and_then(|t| normalize_datetime(&t)),
+        arrival_datetime: json_text(flight, &["arrivalTime"]).and_then(|t| normalize_datetime(&t)),
+        booking_reference: json_text(reservation, &["reservationNumber"]),
+        passenger_name: json_text(reservation, &["underName", "name"]),
+        seat_number: json_text(reservation, &["airplaneSeat"])
+            .or_else(|| json_text(reservation, &["reservedTicket", "ticketedSeat", "seatNumber"])),
+        fare_class: json_text(reservation, &["airplaneSeatClass", "name"])
+            .or_else(|| json_text(reservation, &["airplaneSeatClass"])),
+        ticket_number: json_text(reservation, &["reservedTicket", "ticketNumber"])
+            .or_else(|| json_text(reservation, &["ticketNumber"])),
+    }
+}
+
+/// Local wall-clock time of an ISO 8601 timestamp; the airport's time zone is implied
+fn normalize_datetime(value: &str) -> Option<String> {
+    const FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.naive_local().format(FORMAT).to_string());
+    }
+    for pattern in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, pattern) {
+            return Some(datetime.format(FORMAT).to_string());
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// One reservation per passenger is common; fold them into one segment per flight
+fn merge_passengers(segments: Vec<EmlSegment>) -> Vec<EmlSegment> {
+    let mut merged: Vec<EmlSegment> = Vec::new();
+    for segment in segments {
+        let same_flight = merged.iter_mut().find(|m| {
+            m.flight_number == segment.flight_number
+                && m.departure_airport == segment.departure_airport
+                && m.departure_datetime == segment.departure_datetime
+        });
+        match same_flight {
+            Some(existing) => {
+                append_distinct(&mut existing.passenger_name, segment.passenger_name, "; ");
+                append_distinct(&mut existing.seat_number, segment.seat_number, ", ");
+                append_distinct(&mut existing.ticket_number, segment.ticket_number, ", ");
+            }
+            None => merged.push(segment),
+        }
+    }
+    merged
+}
+
+fn append_distinct(target: &mut Option<String>, value: Option<String>, separator: &str) {
+    let Some(value) = value else { return };
+    match target {
+        Some(existing) if existing.split(separator).any(|v| v == value) => {}
+        Some(existing) => {
+            existing.push_str(separator);
+            existing.push_str(&value);
+        }
+        None => *target = Some(value),
+    }
+}
+
+// ----- Text -----
+
+struct TextPatterns {
+    flight_number: Regex,
+    airport_in_parens: Regex,
+    airport: Regex,
+    iso_date: Regex,
+    day_month: Regex,
+    month_day: Regex,
+    time: Regex,
+    booking_reference: Regex,
+    passenger: Regex,
+    seat: Regex,
+}
+
+fn text_patterns() -> &'static TextPatterns {
+    static PATTERNS: OnceLock<TextPatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        const MONTHS: &str = "jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec";
+        TextPatterns {
+            flight_number: Regex::new(r"\b([A-Z0-9]{2}[A-Z]?)\s?-?(\d{1,4})\b").unwrap(),
+            airport_in_parens: Regex::new(r"\(([A-Z]{3})\)").unwrap(),
+            airport: Regex::new(r"\b([A-Z]{3})\b").unwrap(),
+            iso_date: Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap(),
+            day_month: Regex::new(&format!(r"(?i)\b(\d{{1,2}})\s*({})[a-z]*\.?,?\s*(\d{{4}}|\d{{2}}\b)?", MONTHS)).unwrap(),
+            month_day: Regex::new(&format!(r"(?i)\b({})[a-z]*\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?\b,?\s*(\d{{4}})?", MONTHS)).unwrap(),
+            time: Regex::new(r"(?i)\b([01]?\d|2[0-3])[:.h]([0-5]\d)\s*(am|pm)?\b").unwrap(),
+            booking_reference: Regex::new(
+                r"(?i:booking\s+(?:reference|ref\.?|code)|confirmation\s+(?:code|number|#)|record\s+locator|reservation\s+(?:code|number)|\bpnr)\s*(?:is)?\s*[:#]?\s*([A-Z0-9]{6})\b",
+            )
+            .unwrap(),
+            passenger: Regex::new(r"(?i:passengers?|travell?ers?)(?i:\s+names?)?\s*:\s*([A-Za-z][A-Za-z /.'-]{2,60})").unwrap(),
+            seat: Regex::new(r"(?i:seat)\s*:?\s*(\d{1,2}[A-K])\b").unwrap(),
+        }
+    })
+}
+
+fn text_segments(text: &str, reference: NaiveDate, is_airport: &dyn Fn(&str) -> bool) -> Vec<EmlSegment> {
+    let patterns = text_patterns();
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let booking_reference = patterns.booking_reference.captures(text).map(|caps| caps[1].to_string());
+    let passenger_name = patterns.passenger.captures(text).map(|caps| caps[1].trim().to_string());
+
+    // (line index, flight number) of every line naming a flight
+    let flight_lines: Vec<(usize, String)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| find_flight_number(line).map(|number| (i, number)))
+        .collect();
+
+    let mut segments: Vec<EmlSegment> = Vec::new();
+    for (n, (line_index, flight_number)) in flight_lines.iter().enumerate() {
+        // Lines around the flight number, without reaching into the neighbouring flights
+        let start = n
+            .checked_sub(1)
+            .map_or(0, |prev| flight_lines[prev].0 + 1)
+            .max(line_index.saturating_sub(2));
+        let end = flight_lines.get(n + 1).map_or(lines.len(), |next| next.0).min(line_index + 5);
+        let window = lines[start..end].join("\n");
+
+        let airports = find_airports(&window, is_airport);
+        let date = find_date(&window, reference);
+        let times = find_times(&window);
+        let departure_time = times.first().copied();
+        let arrival_time = times.get(1).copied();
+
+        let format = |date: NaiveDate, time: Option<NaiveTime>| match time {
+            Some(time) => date.and_time(time).format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => date.format("%Y-%m-%d").to_string(),
+        };
+        let arrival_datetime = date.zip(arrival_time).map(|(date, arrival)| {
+            // Landing earlier in the day than take-off means the next day
+            let overnight = departure_time.is_some_and(|departure| arrival < departure);
+            format(if overnight { date + Duration::days(1) } else { date }, Some(arrival))
+        });
+
+        let segment = EmlSegment {
+            source: SegmentSource::Text,
+            flight_number: Some(flight_number.clone()),
+            airline: airlines::infer_from_flight_number(flight_number).map(|a| a.name.clone()),
+            departure_airport: airports.first().cloned(),
+            arrival_airport: airports.get(1).cloned(),
+            departure_datetime: date.map(|date| format(date, departure_time)),
+            arrival_datetime,
+            booking_reference: booking_reference.clone(),
+            passenger_name: passenger_name.clone(),
+            seat_number: patterns.seat.captures(&window).map(|caps| caps[1].to_uppercase()),
+            fare_class: None,
+            ticket_number: None,
+        };
+
+        // The same flight is often repeated in a summary; keep the most complete reading
+        let duplicate = segments.iter_mut().find(|s| {
+            s.flight_number == segment.flight_number
+                && (s.departure_datetime.is_none()
+                    || segment.departure_datetime.is_none()
+                    || s.departure_datetime.as_deref().map(|d| &d[..10]) == segment.departure_datetime.as_deref().map(|d| &d[..10]))
+        });
+        match duplicate {
+            Some(existing) if segment.extracted_fields().len() > existing.extracted_fields().len() => *existing = segment,
+            Some(_) => {}
+            None => segments.push(segment),
+        }
+    }
+    segments
+}
+
+/// First flight number of a known carrier in the line, e.g. "BA 0117" as "BA117"
+fn find_flight_number(line: &str) -> Option<String> {
+    text_patterns().flight_number.captures_iter(line).find_map(|caps| {
+        let designator = &caps[1];
+        if !designator.chars().any(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let digits = caps[2].trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+        let number = format!("{}{}", designator, digits);
+        let airline = airlines::infer_from_flight_number(&number)?;
+        // Normalise ICAO designators ("BAW117") to the IATA form
+        Some(format!("{}{}", airline.iata, digits))
+    })
+}
+
+/// Airport codes in order, preferring ones written in parentheses ("London (LHR)")
+fn find_airports(window: &str, is_airport: &dyn Fn(&str) -> bool) -> Vec<String> {
+    let patterns = text_patterns();
+    let mut codes: Vec<String> = Vec::new();
+    for pattern in [&patterns.airport_in_parens, &patterns.airport] {
+        for caps in pattern.captures_iter(window) {
+            let code = &caps[1];
+            if is_airport(code) && !codes.iter().any(|c| c == code) {
+                codes.push(code.to_string());
+            }
+        }
+        if codes.len() >= 2 {
+            break;
+        }
+    }
+    codes
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    let prefix = name.get(..3)?.to_lowercase();
+    MONTHS.iter().position(|m| *m == prefix).map(|i| i as u32 + 1)
+}
+
+/// The earliest date in the window; a date without a year is the first one on or after a
+/// month before `reference`, since confirmations are sent ahead of the trip
+fn find_date(window: &str, reference: NaiveDate) -> Option<NaiveDate> {
+    let patterns = text_patterns();
+    let year_of = |year: Option<regex::Match>| -> Option<i32> {
+        let year: i32 = year?.as_str().parse().ok()?;
+        Some(if year < 100 { 2000 + year } else { year })
+    };
+    let resolve = |year: Option<i32>, month: u32, day: u32| -> Option<NaiveDate> {
+        match year {
+            Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+            None => {
+                let earliest = reference - Duration::days(31);
+                let this_year = NaiveDate::from_ymd_opt(reference.year(), month, day)?;
+                if this_year >= earliest {
+                    Some(this_year)
+                } else {
+                    NaiveDate::from_ymd_opt(reference.year() + 1, month, day)
+                }
+            }
+        }
+    };
+
+    let mut found: Vec<(usize, NaiveDate)> = Vec::new();
+    if let Some(caps) = patterns.iso_date.captures(window) {
+        let parse = |i: usize| caps[i].parse::<u32>().ok();
+        if let Some(date) = NaiveDate::from_ymd_opt(caps[1].parse().unwrap_or(0), parse(2).unwrap_or(0), parse(3).unwrap_or(0)) {
+            found.push((caps.get(0).map_or(0, |m| m.start()), date));
+        }
+    }
+    for caps in patterns.day_month.captures_iter(window) {
+        let day = caps[1].parse().unwrap_or(0);
+        if let Some(date) = month_number(&caps[2]).and_then(|month| resolve(year_of(caps.get(3)), month, day)) {
+            found.push((caps.get(0).map_or(0, |m| m.start()), date));
+            break;
+        }
+    }
+    for caps in patterns.month_day.captures_iter(window) {
+        let day = caps[2].parse().unwrap_or(0);
+        if let Some(date) = month_number(&caps[1]).and_then(|month| resolve(year_of(caps.get(3)), month, day)) {
+            found.push((caps.get(0).map_or(0, |m| m.start()), date));
+            break;
+        }
+    }
+    found.into_iter().min_by_key(|(position, _)| *position).map(|(_, date)| date)
+}
+
+fn find_times(window: &str) -> Vec<NaiveTime> {
+    text_patterns()
+        .time
+        .captures_iter(window)
+        .filter_map(|caps| {
+            let mut hour: u32 = caps[1].parse().ok()?;
+            let minute: u32 = caps[2].parse().ok()?;
+            match caps.get(3).map(|m| m.as_str().to_lowercase()).as_deref() {
+                Some("pm") if hour < 12 => hour += 12,
+                Some("am") if hour == 12 => hour = 0,
+                _ => {}
+            }
+            NaiveTime::from_hms_opt(hour, minute, 0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known_airport(code: &str) -> bool {
+        ["LHR", "JFK", "CDG", "FRA", "SFO"].contains(&code)
+    }
+
+    #[test]
+    fn test_json_ld_reservation() {
+        let eml = "From: =?UTF-8?B?QnJpdGlzaCBBaXJ3YXlz?= <noreply@example.com>\r\n\
+Subject: =?UTF-8?Q?Your_booking_confirmation?=\r\n\
+Date: Mon, 3 Mar 2025 09:00:00 +0000\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/alternative; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+Thank you for booking.\r\n\
+--b1\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+<html><head><script type=3D\"application/ld+json\">[\r\n\
+{\"@context\": \"http://schema.org\", \"@type\": \"FlightReservation\", \"reservationNumber\": \"ABC123\",\r\n\
+ \"underName\": {\"@type\": \"Person\", \"name\": \"Jane Doe\"}, \"airplaneSeat\": \"12A\",\r\n\
+ \"reservationFor\": {\"@type\": \"Flight\", \"flightNumber\": \"117\",\r\n\
+  \"airline\": {\"@type\": \"Airline\", \"name\": \"British Airways\", \"iataCode\": \"BA\"},\r\n\
+  \"departureAirport\": {\"@type\": \"Airport\", \"iataCode\": \"LHR\"},\r\n\
+  \"arrivalAirport\": {\"@type\": \"Airport\", \"iataCode\": \"JFK\"},\r\n\
+  \"departureTime\": \"2025-03-14T08:25:00+00:00\", \"arrivalTime\": \"2025-03-14T11:05:00-04:00\"}},\r\n\
+{\"@context\": \"http://schema.org\", \"@type\": \"FlightReservation\", \"reservationNumber\": \"ABC123\",\r\n\
+ \"underName\": {\"@type\": \"Person\", \"name\": \"John Doe\"}, \"airplaneSeat\": \"12B\",\r\n\
+ \"reservationFor\": {\"@type\": \"Flight\", \"flightNumber\": \"117\",\r\n\
+  \"airline\": {\"@type\": \"Airline\", \"name\": \"British Airways\", \"iataCode\": \"BA\"},\r\n\
+  \"departureAirport\": {\"@type\": \"Airport\", \"iataCode\": \"LHR\"},\r\n\
+  \"arrivalAirport\": {\"@type\": \"Airport\", \"iataCode\": \"JFK\"},\r\n\
+  \"departureTime\": \"2025-03-14T08:25:00+00:00\", \"arrivalTime\": \"2025-03-14T11:05:00-04:00\"}}\r\n\
+]</script></head><body>Flight BA=\r\n\
+117</body></html>\r\n\
+--b1--\r\n";
+
+        let message = parse_eml(eml.as_bytes()).unwrap();
+        assert_eq!(message.subject.as_deref(), Some("Your booking confirmation"));
+        assert!(message.from.as_deref().unwrap().starts_with("British Airways"));
+        assert_eq!(message.sent, NaiveDate::from_ymd_opt(2025, 3, 3));
+        assert!(message.html_parts[0].contains("Flight BA117"));
+
+        let segments = extract_segments(&message, known_airport);
+        assert_eq!(segments.len(), 1);
+        let segment = &segments[0];
+        assert_eq!(segment.source, SegmentSource::JsonLd);
+        assert_eq!(segment.flight_number.as_deref(), Some("BA117"));
+        assert_eq!(segment.departure_airport.as_deref(), Some("LHR"));
+        assert_eq!(segment.arrival_airport.as_deref(), Some("JFK"));
+        assert_eq!(segment.departure_datetime.as_deref(), Some("2025-03-14T08:25:00"));
+        assert_eq!(segment.arrival_datetime.as_deref(), Some("2025-03-14T11:05:00"));
+        assert_eq!(segment.passenger_name.as_deref(), Some("Jane Doe; John Doe"));
+        assert_eq!(segment.seat_number.as_deref(), Some("12A, 12B"));
+        assert!(segment.missing_required().is_empty());
+    }
+
+    #[test]
+    fn test_text_itinerary() {
+        let html = "<table>\
+            <tr><td>Booking reference:</td><td>XYZ789</td></tr>\
+            <tr><td>Passenger: Jane Doe</td></tr>\
+            <tr><td>Flight</td><td>Date</td><td>From</td><td>To</td></tr>\
+            <tr><td>AF 1681</td><td>Fri 14 Mar</td><td>Paris (CDG) 07:10</td><td>Frankfurt (FRA) 08:35</td></tr>\
+            <tr><td>AF 0022</td><td>Sat 15 Mar</td><td>Paris (CDG) 21:30</td><td>New York (JFK) 00:15</td></tr>\
+            </table><p>THE END</p>";
+        let eml = format!(
+            "From: Air France <noreply@example.com>\nSubject: Itinerary\nDate: Mon, 3 Mar 2025 09:00:00 +0100 (CET)\n\
+             Content-Type: text/html; charset=utf-8\nContent-Transfer-Encoding: base64\n\n{}\n",
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, html)
+        );
+
+        let message = parse_eml(eml.as_bytes()).unwrap();
+        let segments = extract_segments(&message, known_airport);
+        assert_eq!(segments.len(), 2);
+
+        let first = &segments[0];
+        assert_eq!(first.source, SegmentSource::Text);
+        assert_eq!(first.flight_number.as_deref(), Some("AF1681"));
+        assert_eq!(first.departure_airport.as_deref(), Some("CDG"));
+        assert_eq!(first.arrival_airport.as_deref(), Some("FRA"));
+        assert_eq!(first.departure_datetime.as_deref(), Some("2025-03-14T07:10:00"));
+        assert_eq!(first.arrival_datetime.as_deref(), Some("2025-03-14T08:35:00"));
+        assert_eq!(first.booking_reference.as_deref(), Some("XYZ789"));
+        assert_eq!(first.passenger_name.as_deref(), Some("Jane Doe"));
+
+        // Lands after midnight
+        let second = &segments[1];
+        assert_eq!(second.flight_number.as_deref(), Some("AF22"));
+        assert_eq!(second.arrival_airport.as_deref(), Some("JFK"));
+        assert_eq!(second.arrival_datetime.as_deref(), Some("2025-03-16T00:15:00"));
+    }
+
+    #[test]
+    fn test_not_an_email() {
+        assert!(parse_eml(b"").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+// Structured command errors
+// Commands returning CommandError reject invoke() with { code, message, retry_after_secs,
+// fields } so the frontend can react to the kind of failure: retry on rate_limited, ask
+// for an API key on unauthorized, highlight the bad inputs of a validation error.
+//
+// Helper modules that return anyhow::Result can wrap a CommandError in the anyhow error;
+// the conversion below recovers it instead of flattening it to "internal".
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// One invalid input field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CommandError {
+    #[error("{0}")]
+    NotFound(String),
+    /// Missing or rejected API key
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{message}")]
+    RateLimited { message: String, retry_after_secs: Option<u64> },
+    /// Bad input from the caller
+    #[error("{0}")]
+    Validation(String),
+    /// Bad input, field by field; code "validation" like Validation
+    #[error("Invalid input: {}", describe_fields(.0))]
+    InvalidFields(Vec<FieldError>),
+    /// The record is signed or otherwise locked against changes
+    #[error("{0}")]
+    Locked(String),
+    #[error("{0}")]
+    Database(String),
+    /// A third-party service failed
+    #[error("{0}")]
+    External(String),
+    /// Bundled data or a local resource the command needs could not be loaded
+    #[error("{0}")]
+    Unavailable(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl CommandError {
+    /// Machine-readable code sent to the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::NotFound(_) => "not_found",
+            CommandError::Unauthorized(_) => "unauthorized",
+            CommandError::RateLimited { .. } => "rate_limited",
+            CommandError::Validation(_) | CommandError::InvalidFields(_) => "validation",
+            CommandError::Locked(_) => "locked",
+            CommandError::Database(_) => "database",
+            CommandError::External(_) => "external",
+            CommandError::Unavailable(_) => "unavailable",
+            CommandError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn not_found(what: &str, id: &str) -> Self {
+        CommandError::NotFound(format!("{} not found: {}", what, id))
+    }
+
+    /// Error for a failed HTTP response from a third-party API
+    pub fn from_http_status(service: &str, status: u16, retry_after_secs: Option<u64>, body: &str) -> Self {
+        let message = format!("{} API error ({}): {}", service, status, body.trim());
+        match status {
+            401 | 403 => CommandError::Unauthorized(message),
+            429 => CommandError::RateLimited {
+                message,
+                retry_after_secs,
+            },
+            _ => CommandError::External(message),
+        }
+    }
+
+    /// Error for a failed response; reads Retry-After and the body
+    pub async fn from_response(service: &str, response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after_secs = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        let body = response.text().await.unwrap_or_default();
+        Self::from_http_status(service, status, retry_after_secs, &body)
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let retry_after_secs = match self {
+            CommandError::RateLimited { retry_after_secs, .. } => *retry_after_secs,
+            _ => None,
+        };
+        let fields: &[FieldError] = match self {
+            CommandError::InvalidFields(fields) => fields,
+            _ => &[],
+        };
+        let mut state = serializer.serialize_struct("CommandError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retry_after_secs", &retry_after_secs)?;
+        state.serialize_field("fields", fields)?;
+        state.end()
+    }
+}
+
+fn describe_fields(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|f| format!("{} {}", f.field, f.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl From<rusqlite::Error> for CommandError {
+    fn from(error: rusqlite::Error) -> Self {
+        match &error {
+            rusqlite::Error::QueryReturnedNoRows => CommandError::NotFound("Record not found".to_string()),
+            rusqlite::Error::SqliteFailure(e, _) if e.code == rusqlite::ErrorCode::ConstraintViolation => {
+                CommandError::Validation(error.to_string())
+            }
+            _ => CommandError::Database(error.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for CommandError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status().map(|s| s.as_u16()) {
+            Some(status) => Self::from_http_status("HTTP", status, None, &error.to_string()),
+            None => CommandError::External(error.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(command_error) = error.downcast_ref::<CommandError>() {
+            return command_error.clone();
+        }
+        let error = match error.downcast::<rusqlite::Error>() {
+            Ok(sqlite) => return sqlite.into(),
+            Err(error) => error,
+        };
+        match error.downcast::<reqwest::Error>() {
+            Ok(http) => http.into(),
+            Err(error) => CommandError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for CommandError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        CommandError::Database("Database lock poisoned".to_string())
+    }
+}
+
+/// Commands not yet migrated still return String errors; `?` works in both directions
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Internal(message)
+    }
+}
+
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let json = serde_json::to_value(CommandError::not_found("Flight", "abc")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "not_found", "message": "Flight not found: abc", "retry_after_secs": null, "fields": []
+            })
+        );
+
+        let limited = CommandError::from_http_status("Grok", 429, Some(30), "slow down");
+        let json = serde_json::to_value(&limited).unwrap();
+        assert_eq!(json["code"], "rate_limited");
+        assert_eq!(json["retry_after_secs"], 30);
+        assert_eq!(CommandError::from_http_status("Grok", 401, None, "").code(), "unauthorized");
+    }
+
+    #[test]
+    fn test_from_anyhow_keeps_kind() {
+        let wrapped = anyhow::Error::new(CommandError::from_http_status("DeepSeek", 403, None, "bad key"));
+        assert_eq!(CommandError::from(wrapped).code(), "unauthorized");
+
+        let sqlite = anyhow::Error::new(rusqlite::Error::QueryReturnedNoRows);
+        assert_eq!(CommandError::from(sqlite).code(), "not_found");
+
+        assert_eq!(CommandError::from(anyhow::anyhow!("boom")), CommandError::Internal("boom".to_string()));
+    }
+}
@@ -0,0 +1,213 @@
+// Expiry alerts
+// Warns before landing currency or a pilot credential lapses. Each check gathers everything
+// expiring within the "expiry_alert_window_days" setting (default 14), and anything that lapsed
+// within the same window, then returns only what has not been alerted yet.
+//
+// Alerts are debounced in expiry_notifications: an item is alerted once while it is due soon
+// and once more when it lapses. A new expiry date (a renewed medical, a later landing) counts
+// as a new expiry and is alerted again when it comes round.
+//
+// Landing currency is the same as the dashboard's (Database::pilot_currency_on). New alerts go
+// to the window and to the digest webhook, when one is set.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::pilot_credentials;
+
+pub const ENABLED_SETTING: &str = "expiry_alerts_enabled";
+pub const WINDOW_SETTING: &str = "expiry_alert_window_days";
+pub const INTERVAL_SETTING: &str = "expiry_alert_interval_hours";
+
+pub const DEFAULT_WINDOW_DAYS: i64 = 14;
+pub const DEFAULT_INTERVAL_HOURS: u64 = 6;
+
+/// Emitted with the new alerts whenever a check finds any
+pub const ALERT_EVENT: &str = "expiry-alerts:due";
+
+pub const STAGE_DUE_SOON: &str = "due_soon";
+pub const STAGE_EXPIRED: &str = "expired";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiryAlertSettings {
+    pub enabled: bool,
+    pub window_days: i64,
+    pub interval_hours: u64,
+}
+
+impl ExpiryAlertSettings {
+    /// Settings from the app settings; alerts are on unless turned off
+    pub fn configured(db: &Database) -> Self {
+        let setting = |key| db.get_setting(key).ok().flatten();
+        ExpiryAlertSettings {
+            enabled: setting(ENABLED_SETTING).as_deref() != Some("false"),
+            window_days: setting(WINDOW_SETTING)
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|&days| days > 0)
+                .unwrap_or(DEFAULT_WINDOW_DAYS),
+            interval_hours: setting(INTERVAL_SETTING)
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .filter(|&hours| hours > 0)
+                .unwrap_or(DEFAULT_INTERVAL_HOURS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiryAlert {
+    /// Stable per item: "currency:day_landings", "credential:<id>" or "credential:flight_review"
+    pub item_key: String,
+    /// "currency" or "credential"
+    pub kind: String,
+    pub label: String,
+    /// YYYY-MM-DD
+    pub expires_date: String,
+    /// Negative once expired
+    pub days_remaining: i64,
+    /// "due_soon" or "expired"
+    pub stage: String,
+}
+
+fn alert(item_key: String, kind: &str, label: String, expires: NaiveDate, today: NaiveDate) -> ExpiryAlert {
+    let days_remaining = (expires - today).num_days();
+    ExpiryAlert {
+        item_key,
+        kind: kind.to_string(),
+        label,
+        expires_date: expires.format("%Y-%m-%d").to_string(),
+        days_remaining,
+        stage: if days_remaining < 0 { STAGE_EXPIRED } else { STAGE_DUE_SOON }.to_string(),
+    }
+}
+
+/// Currency and credentials expiring within `window_days` of `today`, or lapsed within as
+/// many days, soonest first, whether or not they have been alerted
+pub fn expiring_items(db: &Database, user_id: &str, today: NaiveDate, window_days: i64) -> Result<Vec<ExpiryAlert>> {
+    let mut items = Vec::new();
+
+    for currency in db.pilot_currency_on(user_id, today)? {
+        let Some(expires) = currency.expires_date.and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        items.push(alert(format!("currency:{}", currency.key), "currency", currency.requirement, expires, today));
+    }
+
+    for credential in pilot_credentials::expiring_credentials(&db.conn, user_id, today, window_days)? {
+        let Ok(expires) = NaiveDate::parse_from_str(&credential.expires_date, "%Y-%m-%d") else {
+            continue;
+        };
+        let key = match &credential.credential_id {
+            Some(id) => format!("credential:{}", id),
+            None => format!("credential:{}", credential.credential_type),
+        };
+        let kind = credential.credential_type.replace('_', " ");
+        let label = match &credential.name {
+            Some(name) => format!("{} ({})", kind, name),
+            None => kind,
+        };
+        items.push(alert(key, "credential", label, expires, today));
+    }
+
+    items.retain(|item| item.days_remaining.abs() <= window_days);
+    items.sort_by_key(|item| item.days_remaining);
+    Ok(items)
+}
+
+/// Items that have not been alerted at their current expiry date and stage, recorded as
+/// alerted now. Callers deliver whatever this returns.
+pub fn take_due_alerts(db: &Database, user_id: &str, today: NaiveDate, window_days: i64) -> Result<Vec<ExpiryAlert>> {
+    let conn = &db.conn;
+    let mut due = Vec::new();
+    for item in expiring_items(db, user_id, today, window_days)? {
+        let notified: Option<(String, String)> = conn
+            .query_row(
+                "SELECT expires_date, stage FROM expiry_notifications WHERE user_id = ?1 AND item_key = ?2",
+                params![user_id, item.item_key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if notified.is_some_and(|(expires, stage)| expires == item.expires_date && stage == item.stage) {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO expiry_notifications (user_id, item_key, label, expires_date, stage, last_notified_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))
+             ON CONFLICT(user_id, item_key) DO UPDATE SET
+                 label = ?3, expires_date = ?4, stage = ?5, last_notified_at = datetime('now')",
+            params![user_id, item.item_key, item.label, item.expires_date, item.stage],
+        )?;
+        due.push(item);
+    }
+    Ok(due)
+}
+
+/// Alerts sent since `since` (YYYY-MM-DD), as (label, expires_date, stage), for the digest
+pub fn notified_since(conn: &Connection, user_id: &str, since: &str) -> Result<Vec<(String, String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT label, expires_date, stage FROM expiry_notifications
+         WHERE user_id = ?1 AND last_notified_at >= ?2
+         ORDER BY expires_date",
+    )?;
+    let sent = stmt
+        .query_map(params![user_id, since], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_alerts_are_debounced() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime) VALUES
+                     ('f1', 'u1', 'KJFK', 'KBOS', '2025-01-10T09:00:00'),
+                     ('f2', 'u1', 'KBOS', 'KJFK', '2025-02-01T09:00:00'),
+                     ('f3', 'u1', 'KJFK', 'KBOS', '2025-02-20T09:00:00');
+                 INSERT INTO pilot_logbook (id, flight_id, day_landings, night_landings) VALUES
+                     ('e1', 'f1', 1, 1),
+                     ('e2', 'f2', 2, 0),
+                     ('e3', 'f3', 0, 1);
+                 INSERT INTO pilot_credentials (id, user_id, credential_type, name, expires_date) VALUES
+                     ('c1', 'u1', 'medical', 'Class 2', '2025-05-10'),
+                     ('c2', 'u1', 'ipc', NULL, '2026-01-31');",
+            )
+            .unwrap();
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        // Day: the third most recent landing was on 2 February; night: only two on record
+        let today = date("2025-04-28");
+        let currency = db.pilot_currency_on("u1", today).unwrap();
+        assert_eq!(currency[0].expires_date.as_deref(), Some("2025-05-02"));
+        assert_eq!((currency[0].current_count, currency[0].expires_in_days), (3, Some(4)));
+        assert_eq!(currency[0].status, "warning");
+        assert_eq!((currency[1].expires_date.as_deref(), currency[1].status.as_str()), (None, "expired"));
+
+        let due = take_due_alerts(&db, "u1", today, 14).unwrap();
+        let keys: Vec<_> = due.iter().map(|a| a.item_key.as_str()).collect();
+        assert_eq!(keys, ["currency:day_landings", "credential:c1"]);
+        assert_eq!(due[1].label, "medical (Class 2)");
+        assert!(take_due_alerts(&db, "u1", today, 14).unwrap().is_empty());
+
+        // Lapsing is alerted once more; the medical is still only due soon
+        let due = take_due_alerts(&db, "u1", date("2025-05-05"), 14).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].stage.as_str(), due[0].days_remaining), (STAGE_EXPIRED, -3));
+
+        // A renewed medical is a new expiry
+        db.conn.execute("UPDATE pilot_credentials SET expires_date = '2025-05-20' WHERE id = 'c1'", []).unwrap();
+        let due = take_due_alerts(&db, "u1", date("2025-05-08"), 14).unwrap();
+        assert_eq!(due.iter().map(|a| a.expires_date.as_str()).collect::<Vec<_>>(), ["2025-05-20"]);
+
+        assert_eq!(notified_since(&db.conn, "u1", "2000-01-01").unwrap().len(), 2);
+    }
+}
@@ -7,6 +7,7 @@ use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use tokio::fs;
 
+use super::identity_fusion::jaro_winkler_similarity;
 use super::vision_agent::{FlightLogEntry, PageExtractionResult};
 
 /// Aggregated master log containing all flights
@@ -20,6 +21,48 @@ pub struct MasterFlightLog {
     pub date_range: Option<(String, String)>,
     pub entries: Vec<FlightLogEntry>,
     pub processing_errors: Vec<ProcessingError>,
+    /// Page range (1-indexed, inclusive) the log was built from, None for the whole PDF
+    #[serde(default)]
+    pub page_range: Option<(usize, usize)>,
+    /// Entries merged away as repeats of another entry (e.g. rows carried across a page break)
+    #[serde(default)]
+    pub duplicates_collapsed: usize,
+    /// Each merged-away entry as extracted, so collapsed rows can be reviewed
+    #[serde(default)]
+    pub collapsed_entries: Vec<CollapsedEntry>,
+}
+
+/// An extracted entry folded into another entry by dedup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollapsedEntry {
+    /// Page of the entry it was merged into
+    pub merged_into_page: Option<u32>,
+    pub entry: FlightLogEntry,
+}
+
+/// Tolerances for collapsing repeated log entries
+#[derive(Debug, Clone)]
+pub struct DedupConfig {
+    /// Disable to keep every extracted row
+    pub enabled: bool,
+    /// Maximum page distance between two rows treated as the same flight
+    pub max_page_gap: u32,
+    /// Minimum Jaro-Winkler similarity between tail numbers (0.0-1.0)
+    pub aircraft_similarity: f64,
+    /// Also merge identical rows on the same page; off by default since those are
+    /// usually genuine repeat flights on the same day
+    pub merge_same_page: bool,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_page_gap: 1,
+            aircraft_similarity: 0.9,
+            merge_same_page: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +78,7 @@ pub struct OcrCorrector {
     /// Known airport codes
     known_airports: HashSet<String>,
     /// Character substitution map for common OCR errors
+    #[allow(dead_code)]
     char_substitutions: HashMap<char, Vec<char>>,
 }
 
@@ -106,15 +150,16 @@ impl OcrCorrector {
         // Try to correct common OCR errors in tail numbers
         // Tail numbers are typically N + 1-5 digits + 0-2 letters
         if cleaned.starts_with('N') && cleaned.len() >= 2 {
-            let suffix = &cleaned[1..];
+            let suffix: Vec<char> = cleaned[1..].chars().collect();
+            // Up to 2 trailing letters; everything before them is digits
+            let letters = suffix.iter().rev().take(2).take_while(|c| c.is_ascii_alphabetic()).count();
+            let digits = suffix.len() - letters;
             let mut corrected = String::from("N");
 
-            for (i, c) in suffix.chars().enumerate() {
-                // First few characters after N are usually digits
-                if i < 4 {
+            for (i, &c) in suffix.iter().enumerate() {
+                if i < digits {
                     corrected.push(self.likely_digit(c));
                 } else {
-                    // Last 1-2 characters can be letters
                     corrected.push(self.likely_letter(c));
                 }
             }
@@ -219,6 +264,14 @@ impl OcrCorrector {
 
 /// Aggregate multiple page results into a master log
 pub fn aggregate_results(results: Vec<PageExtractionResult>) -> MasterFlightLog {
+    aggregate_results_with_config(results, &DedupConfig::default())
+}
+
+/// Aggregate page results, collapsing repeated entries according to `dedup`
+pub fn aggregate_results_with_config(
+    results: Vec<PageExtractionResult>,
+    dedup: &DedupConfig,
+) -> MasterFlightLog {
     let mut corrector = OcrCorrector::default();
     corrector.load_common_airports();
 
@@ -265,6 +318,12 @@ pub fn aggregate_results(results: Vec<PageExtractionResult>) -> MasterFlightLog
     // Sort entries by page number
     all_entries.sort_by_key(|e| e.source_page);
 
+    // Collapse rows repeated across page breaks, keeping a record of every row dropped
+    let mut collapsed_entries = Vec::new();
+    if dedup.enabled {
+        (all_entries, collapsed_entries) = dedup_entries(all_entries, dedup, &corrector);
+    }
+
     // Determine date range
     let date_range = determine_date_range(&all_entries);
 
@@ -283,7 +342,129 @@ pub fn aggregate_results(results: Vec<PageExtractionResult>) -> MasterFlightLog
         date_range,
         entries: all_entries,
         processing_errors,
+        page_range: None,
+        duplicates_collapsed: collapsed_entries.len(),
+        collapsed_entries,
+    }
+}
+
+/// Merge entries that describe the same flight, keeping the most reliable field values
+/// Returns the kept entries and the ones merged away
+fn dedup_entries(
+    entries: Vec<FlightLogEntry>,
+    config: &DedupConfig,
+    corrector: &OcrCorrector,
+) -> (Vec<FlightLogEntry>, Vec<CollapsedEntry>) {
+    // Each kept entry remembers the pages already merged into it, so two genuine
+    // repeats on one page are not both folded into a single row
+    let mut kept: Vec<(FlightLogEntry, Vec<u32>)> = Vec::new();
+    let mut collapsed = Vec::new();
+
+    for entry in entries {
+        let page = entry.source_page.unwrap_or(0);
+        let existing = kept.iter_mut().rev().find(|(k, pages)| {
+            (config.merge_same_page || !pages.contains(&page)) && is_duplicate_entry(k, &entry, config)
+        });
+
+        match existing {
+            Some((k, pages)) => {
+                collapsed.push(CollapsedEntry { merged_into_page: k.source_page, entry: entry.clone() });
+                merge_entry(k, entry, corrector);
+                pages.push(page);
+            }
+            None => kept.push((entry, vec![page])),
+        }
     }
+
+    (kept.into_iter().map(|(entry, _)| entry).collect(), collapsed)
+}
+
+/// Same date and route, compatible aircraft and flight number, within the page window
+fn is_duplicate_entry(a: &FlightLogEntry, b: &FlightLogEntry, config: &DedupConfig) -> bool {
+    let page_gap = a.source_page.unwrap_or(0).abs_diff(b.source_page.unwrap_or(0));
+    if page_gap > config.max_page_gap || (page_gap == 0 && !config.merge_same_page) {
+        return false;
+    }
+
+    let same = |x: &Option<String>, y: &Option<String>| match (non_empty(x), non_empty(y)) {
+        (Some(x), Some(y)) => x == y,
+        _ => false,
+    };
+    if !same(&a.date, &b.date) || !same(&a.from, &b.from) || !same(&a.to, &b.to) {
+        return false;
+    }
+
+    // Missing values are compatible with anything; present ones must agree
+    let aircraft_match = match (non_empty(&a.aircraft_registration), non_empty(&b.aircraft_registration)) {
+        (Some(x), Some(y)) => jaro_winkler_similarity(x, y) >= config.aircraft_similarity,
+        _ => true,
+    };
+    let flight_number_match = match (non_empty(&a.flight_number), non_empty(&b.flight_number)) {
+        (Some(x), Some(y)) => x.eq_ignore_ascii_case(y),
+        _ => true,
+    };
+
+    aircraft_match && flight_number_match
+}
+
+/// Fill `target` from `other`, preferring values that validate against known data
+fn merge_entry(target: &mut FlightLogEntry, other: FlightLogEntry, corrector: &OcrCorrector) {
+    // Adopt the uncertainty of whichever reading the model was more sure about
+    if other.confidence.unwrap_or(0.0) > target.confidence.unwrap_or(0.0) {
+        target.confidence = other.confidence;
+        target.uncertain_fields = other.uncertain_fields;
+    }
+
+    let tail_confidence = |v: &str| {
+        let valid = v.starts_with('N') && v.len() >= 3 && v.len() <= 6;
+        usize::from(!v.is_empty()) + usize::from(valid || corrector.known_tail_numbers.contains(v))
+    };
+    pick_field(&mut target.aircraft_registration, other.aircraft_registration, tail_confidence);
+    pick_field(&mut target.flight_number, other.flight_number, |v| usize::from(!v.is_empty()));
+
+    // Union passenger lists, keeping first-seen order
+    let mut passengers: Vec<String> = Vec::new();
+    for list in [target.passengers.take(), other.passengers].into_iter().flatten() {
+        for name in list.split(';').map(str::trim).filter(|n| !n.is_empty()) {
+            if !passengers.iter().any(|p| p.eq_ignore_ascii_case(name)) {
+                passengers.push(name.to_string());
+            }
+        }
+    }
+    target.passengers = if passengers.is_empty() { None } else { Some(passengers.join("; ")) };
+}
+
+/// Replace `target` only when `candidate` scores strictly higher
+fn pick_field(target: &mut Option<String>, candidate: Option<String>, confidence: impl Fn(&str) -> usize) {
+    let current = target.as_deref().map(&confidence).unwrap_or(0);
+    if let Some(candidate) = candidate {
+        if confidence(&candidate) > current {
+            *target = Some(candidate);
+        }
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Aggregate only the pages inside a 1-indexed, inclusive page range
+pub fn aggregate_page_range(
+    results: Vec<PageExtractionResult>,
+    page_range: Option<(usize, usize)>,
+    dedup: &DedupConfig,
+) -> MasterFlightLog {
+    let results = match page_range {
+        Some((start, end)) => results
+            .into_iter()
+            .filter(|r| (start..=end).contains(&(r.page_number as usize)))
+            .collect(),
+        None => results,
+    };
+
+    let mut log = aggregate_results_with_config(results, dedup);
+    log.page_range = page_range;
+    log
 }
 
 /// Determine the date range from entries
@@ -333,27 +514,62 @@ pub async fn load_page_results(dir: &Path) -> Result<Vec<PageExtractionResult>>
     Ok(results)
 }
 
+/// Row confidence (0.0 - 1.0): the model's own score, lowered for fields it flagged
+/// as unclear and for values that fail basic format checks
+pub fn entry_confidence(entry: &FlightLogEntry) -> f64 {
+    let mut confidence = entry.confidence.unwrap_or(0.8).clamp(0.0, 1.0);
+
+    confidence -= 0.15 * entry.uncertain_fields.len() as f64;
+
+    let valid_date = non_empty(&entry.date)
+        .is_some_and(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_ok());
+    if !valid_date {
+        confidence -= 0.2;
+    }
+    if non_empty(&entry.aircraft_registration).is_none() {
+        confidence -= 0.1;
+    }
+
+    confidence.clamp(0.0, 1.0)
+}
+
 /// Export master log to CSV format for import into the flight tracker
-/// Headers match what the CSV importer expects: date, from, to, aircraft_registration, passengers, flight_number
+/// Headers match what the CSV importer expects: date, from, to, aircraft_registration, passengers, flight_number,
+/// followed by the row confidence and any fields the model was unsure about
 pub fn export_to_csv(log: &MasterFlightLog) -> String {
+    entries_to_csv(log.entries.iter())
+}
+
+/// Export rows at or above `min_confidence` for auto-import, and the rest for manual review
+pub fn export_to_csv_by_confidence(log: &MasterFlightLog, min_confidence: f64) -> (String, String) {
+    let (accepted, rejected): (Vec<&FlightLogEntry>, Vec<&FlightLogEntry>) = log
+        .entries
+        .iter()
+        .partition(|e| entry_confidence(e) >= min_confidence);
+
+    (entries_to_csv(accepted.into_iter()), entries_to_csv(rejected.into_iter()))
+}
+
+fn entries_to_csv<'a>(entries: impl Iterator<Item = &'a FlightLogEntry>) -> String {
     let mut csv = String::new();
 
     // Header - matches the flight tracker CSV import expected columns
-    csv.push_str("date,from,to,aircraft_registration,passengers,flight_number\n");
+    csv.push_str("date,from,to,aircraft_registration,passengers,flight_number,confidence,uncertain_fields\n");
 
-    for entry in &log.entries {
+    for entry in entries {
         let date = entry.date.as_deref().unwrap_or("");
         let from = entry.from.as_deref().unwrap_or("");
         let to = entry.to.as_deref().unwrap_or("");
         let tail = entry.aircraft_registration.as_deref().unwrap_or("");
         let passengers = entry.passengers.as_deref().unwrap_or("").replace("\"", "\"\"");
         let flight_num = entry.flight_number.as_deref().unwrap_or("");
+        let uncertain = entry.uncertain_fields.join("; ");
 
         // Only include rows that have at least from and to airports
         if !from.is_empty() && !to.is_empty() {
             csv.push_str(&format!(
-                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
-                date, from, to, tail, passengers, flight_num
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{:.2}\",\"{}\"\n",
+                date, from, to, tail, passengers, flight_num, entry_confidence(entry), uncertain
             ));
         }
     }
@@ -370,6 +586,23 @@ pub async fn save_csv_export(log: &MasterFlightLog, output_path: &Path) -> Resul
     Ok(())
 }
 
+/// Save high-confidence rows to `output_path` and the remainder to `review_path`
+pub async fn save_csv_export_by_confidence(
+    log: &MasterFlightLog,
+    output_path: &Path,
+    review_path: &Path,
+    min_confidence: f64,
+) -> Result<()> {
+    let (accepted, rejected) = export_to_csv_by_confidence(log, min_confidence);
+    fs::write(output_path, accepted)
+        .await
+        .context("Failed to write CSV file")?;
+    fs::write(review_path, rejected)
+        .await
+        .context("Failed to write review CSV file")?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -395,4 +628,81 @@ mod tests {
         assert_eq!(corrector.clean_airport_code("psp"), "PSP");
         assert_eq!(corrector.clean_airport_code("CMH"), "CMH");
     }
+
+    fn entry(page: u32, date: &str, from: &str, to: &str, tail: &str, passengers: &str) -> FlightLogEntry {
+        FlightLogEntry {
+            date: Some(date.to_string()),
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            aircraft_registration: Some(tail.to_string()),
+            passengers: Some(passengers.to_string()),
+            flight_number: None,
+            confidence: None,
+            uncertain_fields: vec![],
+            source_page: Some(page),
+        }
+    }
+
+    fn page(page_number: u32, entries: Vec<FlightLogEntry>) -> PageExtractionResult {
+        PageExtractionResult {
+            page_number,
+            image_path: String::new(),
+            entries,
+            raw_response: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_dedup_across_page_break() {
+        let results = vec![
+            page(1, vec![entry(1, "1995-07-03", "PSP", "CMH", "N908SE", "J. Smith")]),
+            page(2, vec![
+                entry(2, "1995-07-03", "PSP", "CMH", "N9O8SE", "J. Smith; A. Jones"),
+                entry(2, "1995-07-05", "CMH", "TEB", "N908SE", ""),
+            ]),
+        ];
+
+        let log = aggregate_results(results);
+
+        assert_eq!(log.duplicates_collapsed, 1);
+        assert_eq!(log.total_entries, 2);
+        let dropped = &log.collapsed_entries[0];
+        assert_eq!((dropped.merged_into_page, dropped.entry.source_page), (Some(1), Some(2)));
+        assert_eq!(dropped.entry.passengers.as_deref(), Some("J. Smith; A. Jones"));
+        assert_eq!(log.entries[0].passengers.as_deref(), Some("J. Smith; A. Jones"));
+    }
+
+    #[test]
+    fn test_dedup_keeps_same_page_repeats() {
+        let results = vec![page(1, vec![
+            entry(1, "1995-07-03", "TEB", "ISP", "N908SE", ""),
+            entry(1, "1995-07-03", "TEB", "ISP", "N908SE", ""),
+        ])];
+
+        let log = aggregate_results(results.clone());
+        assert_eq!(log.duplicates_collapsed, 0);
+
+        let merge_all = DedupConfig { merge_same_page: true, ..Default::default() };
+        let log = aggregate_results_with_config(results, &merge_all);
+        assert_eq!(log.duplicates_collapsed, 1);
+    }
+
+    #[test]
+    fn test_export_by_confidence() {
+        let mut sure = entry(1, "1995-07-03", "PSP", "CMH", "N908SE", "");
+        sure.confidence = Some(0.95);
+        let mut unsure = entry(1, "1995-07-05", "CMH", "TEB", "N908SE", "");
+        unsure.confidence = Some(0.9);
+        unsure.uncertain_fields = vec!["date".to_string(), "to".to_string()];
+
+        let log = aggregate_results(vec![page(1, vec![sure, unsure])]);
+        let (accepted, rejected) = export_to_csv_by_confidence(&log, 0.8);
+
+        assert_eq!(accepted.lines().count(), 2);
+        assert!(accepted.contains("\"PSP\",\"CMH\""));
+        assert!(accepted.contains("\"0.95\""));
+        assert_eq!(rejected.lines().count(), 2);
+        assert!(rejected.contains("\"date; to\""));
+    }
 }
@@ -1,7 +1,6 @@
 // Identity Fusion Module
 // Entity resolution for passenger names using fuzzy matching and AI analysis
 
-use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
@@ -30,7 +29,8 @@ pub struct MergeCandidate {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MatchType {
     ExactMatch,
-    Abbreviation,      // JE -> Jeffrey Epstein
+    Abbreviation,      // JEFFREY -> Jeffrey Epstein
+    Initials,          // JE -> Jeffrey Epstein
     Substring,         // JEFFREY -> JEFFREY EPSTEIN
     FuzzyMatch,        // Typo correction
     AIInferred,        // AI determined these are same person
@@ -45,6 +45,10 @@ pub struct FusionConfig {
     pub auto_merge_threshold: f64,
     /// Known abbreviations to expand
     pub known_abbreviations: HashMap<String, String>,
+    /// Auto-merge initials-only matches (JE -> JEFFREY EPSTEIN); otherwise they need review
+    pub merge_initials: bool,
+    /// Name pairs never to match, as keyed by `never_merge_key`
+    pub never_merge: HashSet<(String, String)>,
 }
 
 impl Default for FusionConfig {
@@ -55,10 +59,19 @@ impl Default for FusionConfig {
             // No pre-defined abbreviations - tool is neutral
             // User defines mappings through the Alias Management UI
             known_abbreviations: HashMap::new(),
+            merge_initials: true,
+            never_merge: HashSet::new(),
         }
     }
 }
 
+/// Order-independent key for a pair of names that must not be merged
+pub fn never_merge_key(a: &str, b: &str) -> (String, String) {
+    let a = a.trim().to_uppercase();
+    let b = b.trim().to_uppercase();
+    if a <= b { (a, b) } else { (b, a) }
+}
+
 /// Jaro-Winkler similarity algorithm
 /// Returns a score between 0.0 (no similarity) and 1.0 (exact match)
 pub fn jaro_winkler_similarity(s1: &str, s2: &str) -> f64 {
@@ -191,6 +204,15 @@ pub fn is_abbreviation(short: &str, long: &str) -> bool {
     false
 }
 
+/// Check if a name is only the initials of another ("JE" for "JEFFREY EPSTEIN")
+pub fn is_initials(short: &str, long: &str) -> bool {
+    let short = short.trim();
+    !short.contains(char::is_whitespace)
+        && short.chars().filter(|c| c.is_alphabetic()).count() == long.split_whitespace().count()
+        && long.split_whitespace().count() > 1
+        && is_abbreviation(short, long)
+}
+
 /// Check if one string is a substring of another (for partial matches)
 pub fn is_partial_match(shorter: &str, longer: &str) -> bool {
     let shorter = shorter.to_uppercase();
@@ -215,6 +237,13 @@ pub struct IdentityFusion {
     name_to_entity: HashMap<String, String>,
 }
 
+impl Default for IdentityFusion {
+    /// Create with default config
+    fn default() -> Self {
+        Self::new(FusionConfig::default())
+    }
+}
+
 impl IdentityFusion {
     pub fn new(config: FusionConfig) -> Self {
         Self {
@@ -224,9 +253,112 @@ impl IdentityFusion {
         }
     }
 
-    /// Create with default config
-    pub fn default() -> Self {
-        Self::new(FusionConfig::default())
+    pub fn config(&self) -> &FusionConfig {
+        &self.config
+    }
+
+    /// Add an existing identity that names can be matched to
+    pub fn add_entity(&mut self, id: &str, canonical_name: &str, flight_count: usize) {
+        let normalized = canonical_name.to_uppercase().trim().to_string();
+        self.entities.insert(
+            id.to_string(),
+            PersonEntity {
+                id: id.to_string(),
+                canonical_name: normalized.clone(),
+                aliases: vec![normalized.clone()],
+                confidence: 1.0,
+                flight_count,
+                notes: None,
+            },
+        );
+        self.name_to_entity.insert(normalized, id.to_string());
+    }
+
+    /// Best entity for a name, if any is similar enough to be a candidate. A candidate tied
+    /// with a different entity is never auto-merged.
+    pub fn best_match(&self, name: &str) -> Option<MergeCandidate> {
+        let normalized = name.to_uppercase().trim().to_string();
+        let allowed = |target: &str| !self.config.never_merge.contains(&never_merge_key(&normalized, target));
+
+        if let Some(entity_id) = self.name_to_entity.get(&normalized) {
+            return Some(MergeCandidate {
+                source_name: normalized.clone(),
+                target_entity_id: entity_id.clone(),
+                target_canonical_name: normalized,
+                similarity_score: 1.0,
+                match_type: MatchType::ExactMatch,
+                auto_merge: true,
+            });
+        }
+
+        // Check known abbreviations first
+        if let Some(expanded) = self.config.known_abbreviations.get(&normalized) {
+            let expanded_upper = expanded.to_uppercase();
+            if let Some(entity_id) = self.name_to_entity.get(&expanded_upper).filter(|_| allowed(&expanded_upper)) {
+                return Some(MergeCandidate {
+                    source_name: normalized.clone(),
+                    target_entity_id: entity_id.clone(),
+                    target_canonical_name: expanded_upper,
+                    similarity_score: 1.0,
+                    match_type: MatchType::Abbreviation,
+                    auto_merge: true,
+                });
+            }
+        }
+
+        // Find best matching entity
+        let mut best_match: Option<MergeCandidate> = None;
+        let mut tied = false;
+
+        for (entity_id, entity) in &self.entities {
+            if !allowed(&entity.canonical_name) {
+                continue;
+            }
+            let (similarity_score, match_type) = if is_abbreviation(&normalized, &entity.canonical_name) {
+                let match_type = if is_initials(&normalized, &entity.canonical_name) {
+                    MatchType::Initials
+                } else {
+                    MatchType::Abbreviation
+                };
+                (0.95, match_type)
+            } else if is_partial_match(&normalized, &entity.canonical_name) {
+                (0.90, MatchType::Substring)
+            } else {
+                let similarity = jaro_winkler_similarity(&normalized, &entity.canonical_name);
+                if similarity < self.config.fuzzy_threshold {
+                    continue;
+                }
+                (similarity, MatchType::FuzzyMatch)
+            };
+
+            let auto_merge = match match_type {
+                MatchType::Substring => false, // Require confirmation
+                MatchType::Initials => self.config.merge_initials && similarity_score >= self.config.auto_merge_threshold,
+                _ => similarity_score >= self.config.auto_merge_threshold,
+            };
+            let candidate = MergeCandidate {
+                source_name: normalized.clone(),
+                target_entity_id: entity_id.clone(),
+                target_canonical_name: entity.canonical_name.clone(),
+                similarity_score,
+                match_type,
+                auto_merge,
+            };
+
+            match &best_match {
+                Some(b) if candidate.similarity_score < b.similarity_score => {}
+                Some(b) if candidate.similarity_score == b.similarity_score => tied = true,
+                _ => {
+                    tied = false;
+                    best_match = Some(candidate);
+                }
+            }
+        }
+
+        best_match.map(|mut candidate| {
+            candidate.auto_merge &= !tied;
+            candidate
+        })
     }
 
     /// Analyze a list of passenger names and their frequencies
@@ -235,7 +367,7 @@ impl IdentityFusion {
 
         // Sort by frequency (most common first)
         let mut sorted_names: Vec<_> = name_counts.to_vec();
-        sorted_names.sort_by(|a, b| b.1.cmp(&a.1));
+        sorted_names.sort_by_key(|x| std::cmp::Reverse(x.1));
 
         // First pass: create entities for high-frequency names
         for (name, count) in &sorted_names {
@@ -243,7 +375,9 @@ impl IdentityFusion {
                 // High-frequency names become canonical entities
                 let normalized = name.to_uppercase().trim().to_string();
 
-                if !self.name_to_entity.contains_key(&normalized) {
+                // Frequent initials or short forms of a more common name are merge
+                // candidates, not people of their own
+                if !self.name_to_entity.contains_key(&normalized) && self.best_match(&normalized).is_none() {
                     let entity_id = uuid::Uuid::new_v4().to_string();
 
                     let entity = PersonEntity {
@@ -262,88 +396,14 @@ impl IdentityFusion {
         }
 
         // Second pass: match remaining names to entities
-        for (name, count) in &sorted_names {
+        for (name, _) in &sorted_names {
             let normalized = name.to_uppercase().trim().to_string();
 
             if self.name_to_entity.contains_key(&normalized) {
                 continue; // Already an entity
             }
 
-            // Check known abbreviations first
-            if let Some(expanded) = self.config.known_abbreviations.get(&normalized) {
-                let expanded_upper = expanded.to_uppercase();
-                if let Some(entity_id) = self.name_to_entity.get(&expanded_upper) {
-                    candidates.push(MergeCandidate {
-                        source_name: normalized.clone(),
-                        target_entity_id: entity_id.clone(),
-                        target_canonical_name: expanded_upper,
-                        similarity_score: 1.0,
-                        match_type: MatchType::Abbreviation,
-                        auto_merge: true,
-                    });
-                    continue;
-                }
-            }
-
-            // Find best matching entity
-            let mut best_match: Option<MergeCandidate> = None;
-
-            for (entity_id, entity) in &self.entities {
-                // Check abbreviation
-                if is_abbreviation(&normalized, &entity.canonical_name) {
-                    let candidate = MergeCandidate {
-                        source_name: normalized.clone(),
-                        target_entity_id: entity_id.clone(),
-                        target_canonical_name: entity.canonical_name.clone(),
-                        similarity_score: 0.95,
-                        match_type: MatchType::Abbreviation,
-                        auto_merge: true,
-                    };
-
-                    if best_match.as_ref().map_or(true, |b| candidate.similarity_score > b.similarity_score) {
-                        best_match = Some(candidate);
-                    }
-                    continue;
-                }
-
-                // Check partial match (substring)
-                if is_partial_match(&normalized, &entity.canonical_name) {
-                    let candidate = MergeCandidate {
-                        source_name: normalized.clone(),
-                        target_entity_id: entity_id.clone(),
-                        target_canonical_name: entity.canonical_name.clone(),
-                        similarity_score: 0.90,
-                        match_type: MatchType::Substring,
-                        auto_merge: false, // Require confirmation
-                    };
-
-                    if best_match.as_ref().map_or(true, |b| candidate.similarity_score > b.similarity_score) {
-                        best_match = Some(candidate);
-                    }
-                    continue;
-                }
-
-                // Check fuzzy match
-                let similarity = jaro_winkler_similarity(&normalized, &entity.canonical_name);
-                if similarity >= self.config.fuzzy_threshold {
-                    let auto_merge = similarity >= self.config.auto_merge_threshold;
-
-                    let candidate = MergeCandidate {
-                        source_name: normalized.clone(),
-                        target_entity_id: entity_id.clone(),
-                        target_canonical_name: entity.canonical_name.clone(),
-                        similarity_score: similarity,
-                        match_type: MatchType::FuzzyMatch,
-                        auto_merge,
-                    };
-
-                    if best_match.as_ref().map_or(true, |b| candidate.similarity_score > b.similarity_score) {
-                        best_match = Some(candidate);
-                    }
-                }
-            }
-
-            if let Some(candidate) = best_match {
+            if let Some(candidate) = self.best_match(&normalized) {
                 candidates.push(candidate);
             }
         }
@@ -438,6 +498,28 @@ mod tests {
         assert!(!is_abbreviation("SARAH", "JOHN DOE"));
     }
 
+    #[test]
+    fn test_best_match_config() {
+        let mut fusion = IdentityFusion::default();
+        fusion.add_entity("1", "JEFFREY EPSTEIN", 100);
+        fusion.add_entity("2", "JOHN SMITH", 10);
+        fusion.add_entity("3", "JOHN DOE", 10);
+
+        let initials = fusion.best_match("je").unwrap();
+        assert!(matches!(initials.match_type, MatchType::Initials));
+        assert!(initials.auto_merge);
+        assert!(fusion.best_match("JEFFREY EPSTIEN").unwrap().auto_merge);
+        // First name shared by two people: a candidate, but not merged automatically
+        assert!(!fusion.best_match("JOHN").unwrap().auto_merge);
+
+        let mut config = FusionConfig { merge_initials: false, ..FusionConfig::default() };
+        config.never_merge.insert(never_merge_key("Jeffrey Epstien", "JEFFREY EPSTEIN"));
+        let mut fusion = IdentityFusion::new(config);
+        fusion.add_entity("1", "JEFFREY EPSTEIN", 100);
+        assert!(!fusion.best_match("JE").unwrap().auto_merge);
+        assert!(fusion.best_match("JEFFREY EPSTIEN").is_none());
+    }
+
     #[test]
     fn test_is_partial_match() {
         assert!(is_partial_match("JEFFREY", "JEFFREY EPSTEIN"));
@@ -6,7 +6,7 @@ pub mod vision_agent;
 pub mod aggregator;
 pub mod identity_fusion;
 
-pub use splitter::{split_pdf, get_page_count, SplitConfig, SplitResult, ImageFormat};
-pub use vision_agent::{VisionAgent, VisionAgentConfig, FlightLogEntry, PageExtractionResult, process_images_concurrent};
-pub use aggregator::{aggregate_results, MasterFlightLog, export_to_csv, save_master_log, save_csv_export};
+pub use splitter::{split_pdf, get_page_count, validate_page_range, SplitConfig, SplitResult, ImageFormat};
+pub use vision_agent::{VisionAgent, VisionAgentConfig, FlightLogEntry, PageExtractionResult, PageProgress, process_images_concurrent};
+pub use aggregator::{aggregate_results, aggregate_results_with_config, aggregate_page_range, CollapsedEntry, DedupConfig, MasterFlightLog, export_to_csv, export_to_csv_by_confidence, entry_confidence, save_master_log, save_csv_export, save_csv_export_by_confidence};
 pub use identity_fusion::{IdentityFusion, FusionConfig, PersonEntity, MergeCandidate, MatchType, FusionResult, jaro_winkler_similarity};
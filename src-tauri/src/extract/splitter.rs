@@ -22,7 +22,7 @@ pub struct SplitConfig {
     /// Output format (png, jpeg, tiff)
     pub format: ImageFormat,
     /// Optional page range (start, end) - 1-indexed, inclusive
-    pub page_range: Option<(u32, u32)>,
+    pub page_range: Option<(usize, usize)>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -86,6 +86,36 @@ pub async fn get_page_count(pdf_path: &Path) -> Result<usize> {
     anyhow::bail!("Could not find page count in pdfinfo output")
 }
 
+/// Check a 1-indexed, inclusive page range against the PDF's page count
+pub fn validate_page_range(range: (usize, usize), total_pages: usize) -> Result<(usize, usize)> {
+    let (start, end) = range;
+    if start == 0 {
+        anyhow::bail!("Invalid page range {}-{}: pages are numbered from 1", start, end);
+    }
+    if start > end {
+        anyhow::bail!("Invalid page range {}-{}: start page is after end page", start, end);
+    }
+    if end > total_pages {
+        anyhow::bail!(
+            "Invalid page range {}-{}: PDF only has {} page(s)",
+            start,
+            end,
+            total_pages
+        );
+    }
+    Ok((start, end))
+}
+
+/// Page number from a pdftoppm output name such as page-007.png
+pub fn page_number_from_path(path: &Path) -> Option<usize> {
+    path.file_stem()?
+        .to_str()?
+        .rsplit('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
 /// Split a PDF into individual page images using pdftoppm
 pub async fn split_pdf(pdf_path: &Path, output_dir: &Path, config: &SplitConfig) -> Result<SplitResult> {
     // Ensure output directory exists
@@ -93,8 +123,12 @@ pub async fn split_pdf(pdf_path: &Path, output_dir: &Path, config: &SplitConfig)
         .await
         .context("Failed to create output directory")?;
 
-    // Get page count first
+    // Get page count first and make sure the requested range exists
     let total_pages = get_page_count(pdf_path).await?;
+    let (start_page, end_page) = match config.page_range {
+        Some(range) => validate_page_range(range, total_pages)?,
+        None => (1, total_pages),
+    };
 
     // Build pdftoppm command
     let mut cmd = Command::new("pdftoppm");
@@ -103,9 +137,9 @@ pub async fn split_pdf(pdf_path: &Path, output_dir: &Path, config: &SplitConfig)
         .arg(config.dpi.to_string());
 
     // Add page range if specified
-    if let Some((start, end)) = config.page_range {
-        cmd.arg("-f").arg(start.to_string());
-        cmd.arg("-l").arg(end.to_string());
+    if config.page_range.is_some() {
+        cmd.arg("-f").arg(start_page.to_string());
+        cmd.arg("-l").arg(end_page.to_string());
     }
 
     // Input PDF and output prefix
@@ -126,9 +160,6 @@ pub async fn split_pdf(pdf_path: &Path, output_dir: &Path, config: &SplitConfig)
     let extension = config.format.extension();
     let mut page_paths = Vec::new();
 
-    let (start_page, end_page) = config.page_range.unwrap_or((1, total_pages as u32));
-    let actual_pages = (end_page - start_page + 1) as usize;
-
     // pdftoppm names files as page-001.png, page-002.png, etc.
     for i in start_page..=end_page {
         // Handle different numbering formats based on total pages
@@ -153,12 +184,14 @@ pub async fn split_pdf(pdf_path: &Path, output_dir: &Path, config: &SplitConfig)
         }
     }
 
-    // If we didn't find files with expected names, scan directory
+    // If we didn't find files with expected names, scan directory for pages in range
     if page_paths.is_empty() {
         let mut entries = fs::read_dir(output_dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
-            if path.extension().map(|e| e == extension).unwrap_or(false) {
+            let in_range = page_number_from_path(&path)
+                .is_none_or(|n| (start_page..=end_page).contains(&n));
+            if path.extension().map(|e| e == extension).unwrap_or(false) && in_range {
                 page_paths.push(path);
             }
         }
@@ -182,11 +215,14 @@ pub async fn split_pdf_batched(
     let total_pages = get_page_count(pdf_path).await?;
     let mut results = Vec::new();
 
-    let (start, end) = config.page_range.unwrap_or((1, total_pages as u32));
+    let (start, end) = match config.page_range {
+        Some(range) => validate_page_range(range, total_pages)?,
+        None => (1, total_pages),
+    };
     let mut current = start;
 
     while current <= end {
-        let batch_end = (current + batch_size as u32 - 1).min(end);
+        let batch_end = (current + batch_size.max(1) - 1).min(end);
         let batch_dir = output_dir.join(format!("batch_{:03}_{:03}", current, batch_end));
 
         let batch_config = SplitConfig {
@@ -221,7 +257,7 @@ mod tests {
     async fn test_get_page_count() {
         // This test requires a real PDF file - use TEST_PDF_PATH env var or skip
         let pdf_path = std::env::var("TEST_PDF_PATH")
-            .map(|p| std::path::PathBuf::from(p))
+            .map(std::path::PathBuf::from)
             .unwrap_or_else(|_| {
                 // Try a relative path in the project directory
                 std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-data").join("sample.pdf")
@@ -235,4 +271,22 @@ mod tests {
             println!("Skipping test: no PDF file at {:?}", pdf_path);
         }
     }
+
+    #[test]
+    fn test_validate_page_range() {
+        assert_eq!(validate_page_range((10, 25), 300).unwrap(), (10, 25));
+        assert_eq!(validate_page_range((300, 300), 300).unwrap(), (300, 300));
+        assert!(validate_page_range((0, 5), 300).is_err());
+        assert!(validate_page_range((25, 10), 300).is_err());
+
+        let err = validate_page_range((290, 310), 300).unwrap_err().to_string();
+        assert!(err.contains("only has 300 page(s)"));
+    }
+
+    #[test]
+    fn test_page_number_from_path() {
+        assert_eq!(page_number_from_path(Path::new("pages/page-007.png")), Some(7));
+        assert_eq!(page_number_from_path(Path::new("page-12.jpg")), Some(12));
+        assert_eq!(page_number_from_path(Path::new("cover.png")), None);
+    }
 }
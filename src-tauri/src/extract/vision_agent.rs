@@ -4,7 +4,9 @@
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
 
 /// Extracted flight log entry - CSV-ready format
@@ -28,6 +30,12 @@ pub struct FlightLogEntry {
     /// Flight number if present
     #[serde(default)]
     pub flight_number: Option<String>,
+    /// Model's confidence in the row as a whole (0.0 - 1.0)
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    /// Fields the model could not read clearly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uncertain_fields: Vec<String>,
     /// Page number this entry came from (added during processing)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source_page: Option<u32>,
@@ -54,6 +62,12 @@ pub struct VisionAgentConfig {
     pub max_tokens: u32,
     /// Temperature for generation (0.0 - 1.0)
     pub temperature: f32,
+    /// Maximum number of pages in flight at once
+    pub max_concurrency: usize,
+    /// Retries per page after a transient failure (rate limit, server error, timeout)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each further attempt
+    pub retry_backoff_ms: u64,
 }
 
 impl Default for VisionAgentConfig {
@@ -63,10 +77,45 @@ impl Default for VisionAgentConfig {
             model: "gemini-2.5-flash-lite".to_string(),
             max_tokens: 8192,
             temperature: 0.1, // Low temperature for structured extraction
+            max_concurrency: 10,
+            max_retries: 3,
+            retry_backoff_ms: 1000,
         }
     }
 }
 
+/// Progress update emitted once per finished page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageProgress {
+    pub page_number: u32,
+    /// Pages finished so far, including this one
+    pub completed: usize,
+    pub total: usize,
+    pub entries: usize,
+    /// Attempts used for this page (1 when no retry was needed)
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// A failure worth retrying: rate limiting or a server-side error
+#[derive(Debug, thiserror::Error)]
+#[error("API error ({status}): {body}")]
+pub struct TransientApiError {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Whether an extraction error is likely to succeed on retry
+fn is_transient(error: &anyhow::Error) -> bool {
+    if error.downcast_ref::<TransientApiError>().is_some() {
+        return true;
+    }
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_timeout() || e.is_connect() || e.is_request())
+}
+
 /// The extraction prompt for Gemini - outputs CSV-ready format
 const EXTRACTION_PROMPT: &str = r#"Extract flight log entries from this scanned handwritten page.
 
@@ -77,7 +126,9 @@ OUTPUT FORMAT: Return ONLY a JSON array. Each entry must have these exact fields
   "to": "XYZ",
   "aircraft_registration": "N12345",
   "passengers": "Name1; Name2",
-  "flight_number": "123"
+  "flight_number": "123",
+  "confidence": 0.9,
+  "uncertain_fields": ["aircraft_registration"]
 }
 
 CRITICAL DATE HANDLING:
@@ -95,6 +146,8 @@ FIELD RULES:
 - aircraft_registration: US tail number starting with N (e.g., N12516, N404CB)
 - passengers: Names from remarks column, semicolon-separated. Extract ALL names mentioned.
 - flight_number: If present in FLT.NO. column
+- confidence: 0.0-1.0, how sure you are the whole row was read correctly
+- uncertain_fields: names of any fields above that were hard to read (empty array if none)
 
 READING HANDWRITING:
 - Tail numbers: N + digits + optional letters. Common confusions: 0/O, 1/I, 5/S, 8/B
@@ -185,6 +238,14 @@ impl VisionAgent {
         let status = response.status();
         let response_text = response.text().await?;
 
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(TransientApiError {
+                status: status.as_u16(),
+                body: response_text,
+            }
+            .into());
+        }
+
         if !status.is_success() {
             return Ok(PageExtractionResult {
                 page_number,
@@ -230,8 +291,8 @@ impl VisionAgent {
                 entries
             }
             Err(e) => {
-                eprintln!("Failed to parse entries from page {}: {}", page_number, e);
-                eprintln!("Raw text: {}", &json_text[..json_text.len().min(500)]);
+                let raw: String = json_text.chars().take(500).collect();
+                tracing::warn!("Failed to parse entries from page {}: {}; raw text: {}", page_number, e, raw);
                 vec![]
             }
         }
@@ -272,37 +333,95 @@ impl VisionAgent {
 }
 
 /// Process multiple images concurrently with rate limiting
+///
+/// At most `max_concurrency` pages are in flight; the next page is not started until
+/// a slot frees up. Transient failures are retried with exponential backoff, and
+/// `on_progress` is called as each page finishes.
 pub async fn process_images_concurrent(
     agent: &VisionAgent,
-    image_paths: Vec<(std::path::PathBuf, u32)>, // (path, page_number)
-    concurrency: usize,
+    image_paths: Vec<(PathBuf, u32)>, // (path, page_number)
+    on_progress: impl Fn(PageProgress) + Send + Sync + 'static,
 ) -> Vec<PageExtractionResult> {
+    let config = agent.config.clone();
+    let worker_agent = Arc::new(VisionAgent::new(config.clone()));
+
+    run_pages(
+        &config,
+        image_paths,
+        move |path, page_num| {
+            let agent = worker_agent.clone();
+            async move { agent.extract_from_image(&path, page_num).await }
+        },
+        on_progress,
+    )
+    .await
+}
+
+/// Concurrency, retry and progress handling around a per-page extraction function
+async fn run_pages<F, Fut>(
+    config: &VisionAgentConfig,
+    image_paths: Vec<(PathBuf, u32)>,
+    extract: F,
+    on_progress: impl Fn(PageProgress) + Send + Sync + 'static,
+) -> Vec<PageExtractionResult>
+where
+    F: Fn(PathBuf, u32) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<PageExtractionResult>> + Send,
+{
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tokio::sync::Semaphore;
-    use std::sync::Arc;
 
-    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let total = image_paths.len();
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+    let extract = Arc::new(extract);
+    let on_progress = Arc::new(on_progress);
+    let completed = Arc::new(AtomicUsize::new(0));
+    let max_retries = config.max_retries;
+    let backoff_ms = config.retry_backoff_ms;
     let mut handles = Vec::new();
 
     for (path, page_num) in image_paths {
+        // Backpressure: wait for a free slot before starting the next page
         let permit = semaphore.clone().acquire_owned().await.unwrap();
-        let agent_config = agent.config.clone();
-        let path_owned = path.clone();
+        let extract = extract.clone();
+        let on_progress = on_progress.clone();
+        let completed = completed.clone();
 
         let handle = tokio::spawn(async move {
-            let agent = VisionAgent::new(agent_config);
-            let result = agent.extract_from_image(&path_owned, page_num).await;
+            let mut attempts = 0;
+            let result = loop {
+                attempts += 1;
+                match extract(path.clone(), page_num).await {
+                    Err(e) if attempts <= max_retries && is_transient(&e) => {
+                        let delay = backoff_ms.saturating_mul(1 << (attempts - 1).min(16));
+                        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                    }
+                    other => break other,
+                }
+            };
             drop(permit); // Release semaphore
 
-            match result {
+            let result = match result {
                 Ok(r) => r,
                 Err(e) => PageExtractionResult {
                     page_number: page_num,
-                    image_path: path_owned.to_string_lossy().to_string(),
+                    image_path: path.to_string_lossy().to_string(),
                     entries: vec![],
                     raw_response: None,
                     error: Some(e.to_string()),
                 },
-            }
+            };
+
+            on_progress(PageProgress {
+                page_number: page_num,
+                completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                total,
+                entries: result.entries.len(),
+                attempts,
+                error: result.error.clone(),
+            });
+
+            result
         });
 
         handles.push(handle);
@@ -341,4 +460,88 @@ mod tests {
         let input = "Here are the entries: [{\"date\": \"25\"}] end";
         assert!(agent.extract_json_array(input).starts_with('['));
     }
+
+    fn page(page_number: u32) -> PageExtractionResult {
+        PageExtractionResult {
+            page_number,
+            image_path: String::new(),
+            entries: vec![],
+            raw_response: None,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let config = VisionAgentConfig {
+            max_concurrency: 3,
+            ..Default::default()
+        };
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let progress = Arc::new(AtomicUsize::new(0));
+
+        let pages = (1..=12).map(|n| (PathBuf::from(format!("page_{}.png", n)), n)).collect();
+        let (in_flight_task, peak_task) = (in_flight.clone(), peak.clone());
+        let progress_task = progress.clone();
+
+        let results = run_pages(
+            &config,
+            pages,
+            move |_, page_num| {
+                let in_flight = in_flight_task.clone();
+                let peak = peak_task.clone();
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(page(page_num))
+                }
+            },
+            move |_| {
+                progress_task.fetch_add(1, Ordering::SeqCst);
+            },
+        )
+        .await;
+
+        assert_eq!(results.len(), 12);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert_eq!(progress.load(Ordering::SeqCst), 12);
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let config = VisionAgentConfig {
+            max_retries: 2,
+            retry_backoff_ms: 1,
+            ..Default::default()
+        };
+        let calls = Arc::new(AtomicU32::new(0));
+        let calls_task = calls.clone();
+
+        let results = run_pages(
+            &config,
+            vec![(PathBuf::from("page_1.png"), 1)],
+            move |_, page_num| {
+                let calls = calls_task.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                        Err(TransientApiError { status: 429, body: String::new() }.into())
+                    } else {
+                        Ok(page(page_num))
+                    }
+                }
+            },
+            |progress| assert_eq!(progress.attempts, 3),
+        )
+        .await;
+
+        assert!(results[0].error.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
 }
@@ -0,0 +1,245 @@
+// Fare class normalization
+// fare_class is free text: booking class letters ("Y", "J"), cabin names ("Business") and
+// everything in between ("econ", "Prem Eco"). Each value is mapped to a canonical cabin,
+// first through the user's own mapping (the "fare_class_mapping" setting, JSON of raw value
+// to cabin, matched case-insensitively), then through the built-in rules. Anything left is
+// "other", and the raw value is kept so it can be added to the mapping.
+
+use anyhow::Result;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::database::Database;
+
+const MAPPING_SETTING: &str = "fare_class_mapping";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cabin {
+    Economy,
+    Premium,
+    Business,
+    First,
+    Other,
+}
+
+impl Cabin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cabin::Economy => "economy",
+            Cabin::Premium => "premium",
+            Cabin::Business => "business",
+            Cabin::First => "first",
+            Cabin::Other => "other",
+        }
+    }
+}
+
+/// Built-in rules for a booking class letter ("J") or cabin name ("Business")
+pub fn builtin_cabin(fare_class: &str) -> Option<Cabin> {
+    let class = fare_class.trim().to_uppercase();
+    if class.len() == 1 {
+        return match class.as_str() {
+            "F" | "A" | "P" => Some(Cabin::First),
+            "J" | "C" | "D" | "I" | "Z" | "R" => Some(Cabin::Business),
+            "W" | "E" => Some(Cabin::Premium),
+            "Y" | "B" | "M" | "H" | "K" | "L" | "Q" | "T" | "V" | "S" | "N" | "O" | "G" | "U" | "X" => {
+                Some(Cabin::Economy)
+            }
+            _ => None,
+        };
+    }
+
+    if class.contains("FIRST") {
+        Some(Cabin::First)
+    } else if class.contains("BUSINESS") || class.contains("CLUB") || class.contains("UPPER") {
+        Some(Cabin::Business)
+    } else if class.contains("PREM") {
+        Some(Cabin::Premium)
+    } else if class.contains("ECON") || class.contains("COACH") || class.contains("MAIN CABIN") {
+        Some(Cabin::Economy)
+    } else {
+        None
+    }
+}
+
+/// The user's mapping on top of the built-in rules
+#[derive(Debug, Clone, Default)]
+pub struct FareClassMapping {
+    /// Keys are trimmed and uppercased
+    custom: HashMap<String, Cabin>,
+}
+
+impl FareClassMapping {
+    pub fn new(custom: HashMap<String, Cabin>) -> Self {
+        Self {
+            custom: custom.into_iter().map(|(raw, cabin)| (normalize_key(&raw), cabin)).collect(),
+        }
+    }
+
+    /// From the fare_class_mapping setting; an unset or invalid setting adds nothing
+    pub fn from_settings(db: &Database) -> Self {
+        let custom = db
+            .get_setting(MAPPING_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self::new(custom)
+    }
+
+    pub fn save(&self, db: &Database) -> Result<()> {
+        db.set_setting(MAPPING_SETTING, &serde_json::to_string(&self.custom)?)
+    }
+
+    pub fn entries(&self) -> BTreeMap<String, Cabin> {
+        self.custom.iter().map(|(raw, cabin)| (raw.clone(), *cabin)).collect()
+    }
+
+    pub fn cabin(&self, fare_class: &str) -> Cabin {
+        self.custom
+            .get(&normalize_key(fare_class))
+            .copied()
+            .or_else(|| builtin_cabin(fare_class))
+            .unwrap_or(Cabin::Other)
+    }
+}
+
+fn normalize_key(raw: &str) -> String {
+    raw.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase()
+}
+
+/// Spend in one cabin and currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CabinRevenue {
+    pub cabin: Cabin,
+    pub currency: String,
+    pub flight_count: i64,
+    pub total_cost: f64,
+    pub base_fare: f64,
+    pub taxes: f64,
+    pub average_cost: Option<f64>,
+    /// Fare class values as entered, so unmapped ones under "other" can be mapped
+    pub raw_fare_classes: Vec<String>,
+}
+
+/// Costs per normalized cabin, one row per cabin and currency, cheapest cabin first
+///
+/// Flights without any cost are left out; flights without a fare class count as "other".
+pub fn revenue_by_cabin(db: &Database, user_id: &str) -> Result<Vec<CabinRevenue>> {
+    let mapping = FareClassMapping::from_settings(db);
+    let mut stmt = db.conn.prepare(
+        "SELECT fare_class, COALESCE(currency, 'USD'), COUNT(*), SUM(total_cost), SUM(base_fare), SUM(taxes),
+                COUNT(total_cost)
+         FROM flights
+         WHERE user_id = ?1 AND deleted_at IS NULL
+           AND (total_cost IS NOT NULL OR base_fare IS NOT NULL OR taxes IS NOT NULL)
+         GROUP BY fare_class, COALESCE(currency, 'USD')",
+    )?;
+    let rows = stmt.query_map(params![user_id], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+            row.get::<_, Option<f64>>(4)?.unwrap_or(0.0),
+            row.get::<_, Option<f64>>(5)?.unwrap_or(0.0),
+            row.get::<_, i64>(6)?,
+        ))
+    })?;
+
+    // Costed flights are tracked separately so the average ignores fare/tax-only rows
+    let mut groups: BTreeMap<(Cabin, String), (CabinRevenue, i64, BTreeSet<String>)> = BTreeMap::new();
+    for row in rows {
+        let (raw, currency, count, total_cost, base_fare, taxes, costed) = row?;
+        let raw = raw.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+        let cabin = raw.as_deref().map(|r| mapping.cabin(r)).unwrap_or(Cabin::Other);
+        let (revenue, costed_count, raws) = groups.entry((cabin, currency.clone())).or_insert_with(|| {
+            let revenue = CabinRevenue {
+                cabin,
+                currency,
+                flight_count: 0,
+                total_cost: 0.0,
+                base_fare: 0.0,
+                taxes: 0.0,
+                average_cost: None,
+                raw_fare_classes: Vec::new(),
+            };
+            (revenue, 0, BTreeSet::new())
+        });
+        revenue.flight_count += count;
+        revenue.total_cost += total_cost;
+        revenue.base_fare += base_fare;
+        revenue.taxes += taxes;
+        *costed_count += costed;
+        raws.extend(raw);
+    }
+
+    Ok(groups
+        .into_values()
+        .map(|(mut revenue, costed, raws)| {
+            revenue.average_cost = (costed > 0).then(|| revenue.total_cost / costed as f64);
+            revenue.raw_fare_classes = raws.into_iter().collect();
+            revenue
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_normalization() {
+        let mapping = FareClassMapping::new(HashMap::from([("  saver  plus".to_string(), Cabin::Premium)]));
+        assert_eq!(mapping.cabin("Y"), Cabin::Economy);
+        assert_eq!(mapping.cabin("econ"), Cabin::Economy);
+        assert_eq!(mapping.cabin("Prem Eco"), Cabin::Premium);
+        assert_eq!(mapping.cabin("Upper Class"), Cabin::Business);
+        assert_eq!(mapping.cabin("Saver Plus"), Cabin::Premium);
+        assert_eq!(mapping.cabin("Basic"), Cabin::Other);
+    }
+
+    #[test]
+    fn test_revenue_by_cabin() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, fare_class, base_fare, taxes, total_cost, currency) VALUES
+                     ('f1', 'u1', 'LHR', 'JFK', '2025-01-01T10:00:00', 'Y', 300, 100, 400, 'USD'),
+                     ('f2', 'u1', 'JFK', 'LHR', '2025-01-09T10:00:00', 'Economy', 500, 100, 600, 'USD'),
+                     ('f3', 'u1', 'LHR', 'CDG', '2025-02-01T10:00:00', 'J', 200, 50, 250, 'EUR'),
+                     ('f4', 'u1', 'CDG', 'LHR', '2025-02-03T10:00:00', 'Basic', NULL, NULL, 90, 'EUR'),
+                     ('f5', 'u1', 'LHR', 'DUB', '2025-03-01T10:00:00', 'Y', NULL, NULL, NULL, 'EUR');",
+            )
+            .unwrap();
+
+        let revenue = revenue_by_cabin(&db, "u1").unwrap();
+        let summary: Vec<_> = revenue
+            .iter()
+            .map(|r| (r.cabin, r.currency.as_str(), r.flight_count, r.total_cost, r.average_cost))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Cabin::Economy, "USD", 2, 1000.0, Some(500.0)),
+                (Cabin::Business, "EUR", 1, 250.0, Some(250.0)),
+                (Cabin::Other, "EUR", 1, 90.0, Some(90.0)),
+            ]
+        );
+        assert_eq!(revenue[0].raw_fare_classes, vec!["Economy", "Y"]);
+        assert_eq!(revenue[2].raw_fare_classes, vec!["Basic"]);
+
+        FareClassMapping::new(HashMap::from([("basic".to_string(), Cabin::Economy)])).save(&db).unwrap();
+        let remapped = revenue_by_cabin(&db, "u1").unwrap();
+        assert!(remapped.iter().all(|r| r.cabin != Cabin::Other));
+
+        // Dimension analytics group the same way, including flights without a cost
+        let groups = db.get_analytics_by_dimension("u1", "fare_class", None).unwrap();
+        let counts: Vec<(&str, i64)> = groups.iter().map(|g| (g.group.as_str(), g.flight_count)).collect();
+        assert_eq!(counts, vec![("economy", 4), ("business", 1)]);
+    }
+}
@@ -0,0 +1,376 @@
+// Flight share cards
+// Renders one flight as a PNG for sharing: route, date, flight number, aircraft, distance
+// and duration beside a small great-circle map. Flights whose airports have no
+// coordinates get the text card without the map.
+//
+// Colours come from a theme in the same fg/bg hex form as the themed donation QR codes.
+// Text is drawn with DejaVu Sans, embedded in the binary so cards look the same on every
+// machine (licence in fonts/LICENSE-DejaVu.txt).
+
+use anyhow::{bail, Context, Result};
+use image::{ImageFormat, Rgba, RgbaImage};
+use rusqlite::{Connection, OptionalExtension};
+use rusttype::{point, Font, Scale};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use crate::units::UnitSystem;
+
+pub const CARD_WIDTH: u32 = 1200;
+pub const CARD_HEIGHT: u32 = 630;
+
+const MARGIN: f32 = 64.0;
+/// The map panel on the right of the card: (x, y, width, height)
+const MAP_PANEL: (f32, f32, f32, f32) = (700.0, 64.0, 436.0, 502.0);
+const ARC_SEGMENTS: usize = 96;
+
+/// Card colours as "#rrggbb" or "#rrggbbaa"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardTheme {
+    pub fg_color: String,
+    pub bg_color: String,
+    /// Route line and airport markers
+    pub accent_color: String,
+}
+
+impl Default for CardTheme {
+    fn default() -> Self {
+        CardTheme {
+            fg_color: "#ffffff".to_string(),
+            bg_color: "#111827".to_string(),
+            accent_color: "#38bdf8".to_string(),
+        }
+    }
+}
+
+struct Palette {
+    fg: Rgba<u8>,
+    muted: Rgba<u8>,
+    bg: Rgba<u8>,
+    panel: Rgba<u8>,
+    accent: Rgba<u8>,
+}
+
+impl CardTheme {
+    /// Fails on a colour that is not #rrggbb or #rrggbbaa
+    pub fn validate(&self) -> Result<()> {
+        self.palette().map(|_| ())
+    }
+
+    fn palette(&self) -> Result<Palette> {
+        let fg = parse_color(&self.fg_color)?;
+        let faded = |alpha: f32| Rgba([fg[0], fg[1], fg[2], (fg[3] as f32 * alpha) as u8]);
+        Ok(Palette {
+            fg,
+            muted: faded(0.6),
+            bg: parse_color(&self.bg_color)?,
+            panel: faded(0.08),
+            accent: parse_color(&self.accent_color)?,
+        })
+    }
+}
+
+fn parse_color(value: &str) -> Result<Rgba<u8>> {
+    let hex = value.trim().trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid colour '{}': expected #rrggbb or #rrggbbaa", value);
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(255);
+    Ok(Rgba([channel(0), channel(2), channel(4), if hex.len() == 8 { channel(6) } else { 255 }]))
+}
+
+/// What goes on the card
+#[derive(Debug, Clone)]
+pub struct FlightCard {
+    pub flight_number: Option<String>,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub aircraft: Option<String>,
+    /// Already converted to the configured unit system, e.g. "5554 km"
+    pub distance: Option<String>,
+    pub duration_minutes: Option<i32>,
+    /// (lat, lon) of departure and arrival; None draws no map
+    pub route: Option<((f64, f64), (f64, f64))>,
+}
+
+impl FlightCard {
+    pub fn load(db: &crate::database::Database, flight_id: &str) -> Result<Option<FlightCard>> {
+        let Some(flight) = db.get_flight(flight_id)? else { return Ok(None) };
+        let aircraft = aircraft_name(&db.conn, flight.aircraft_type_id.as_deref())?.or(flight.aircraft_registration);
+        let route = crate::commands::batch_calculations::airport_coords(db, &flight.departure_airport)
+            .zip(crate::commands::batch_calculations::airport_coords(db, &flight.arrival_airport));
+
+        Ok(Some(FlightCard {
+            flight_number: flight.flight_number,
+            date: flight.departure_datetime.chars().take(10).collect(),
+            aircraft,
+            distance: flight.distance_km.map(|km| {
+                let distance = UnitSystem::configured(db).distance(km);
+                format!("{:.0} {}", distance.value, distance.unit)
+            }),
+            duration_minutes: flight.flight_duration.or(flight.total_duration),
+            route,
+            departure_airport: flight.departure_airport,
+            arrival_airport: flight.arrival_airport,
+        }))
+    }
+}
+
+fn aircraft_name(conn: &Connection, aircraft_type_id: Option<&str>) -> Result<Option<String>> {
+    let Some(id) = aircraft_type_id else { return Ok(None) };
+    Ok(conn
+        .query_row("SELECT manufacturer || ' ' || model FROM aircraft_types WHERE id = ?1", [id], |row| row.get(0))
+        .optional()?)
+}
+
+const REGULAR_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+const BOLD_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans-Bold.ttf");
+
+pub struct CardFonts {
+    regular: Font<'static>,
+    bold: Font<'static>,
+}
+
+impl CardFonts {
+    pub fn bundled() -> Result<CardFonts> {
+        let regular = Font::from_bytes(REGULAR_FONT).context("Bundled regular font is invalid")?;
+        let bold = Font::from_bytes(BOLD_FONT).context("Bundled bold font is invalid")?;
+        Ok(CardFonts { regular, bold })
+    }
+}
+
+fn blend(image: &mut RgbaImage, x: i32, y: i32, color: Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+        return;
+    }
+    let alpha = (color[3] as f32 / 255.0) * coverage.clamp(0.0, 1.0);
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    for c in 0..3 {
+        pixel[c] = (color[c] as f32 * alpha + pixel[c] as f32 * (1.0 - alpha)).round() as u8;
+    }
+    pixel[3] = (alpha * 255.0 + pixel[3] as f32 * (1.0 - alpha)).round() as u8;
+}
+
+/// Draw `text` with its baseline at `y`
+fn draw_text(image: &mut RgbaImage, font: &Font, size: f32, x: f32, y: f32, text: &str, color: Rgba<u8>) {
+    for glyph in font.layout(text, Scale::uniform(size), point(x, y)) {
+        if let Some(bounds) = glyph.pixel_bounding_box() {
+            glyph.draw(|gx, gy, coverage| {
+                blend(image, bounds.min.x + gx as i32, bounds.min.y + gy as i32, color, coverage)
+            });
+        }
+    }
+}
+
+fn text_width(font: &Font, size: f32, text: &str) -> f32 {
+    font.layout(text, Scale::uniform(size), point(0.0, 0.0))
+        .last()
+        .map_or(0.0, |glyph| glyph.position().x + glyph.unpositioned().h_metrics().advance_width)
+}
+
+fn fill_circle(image: &mut RgbaImage, cx: f32, cy: f32, radius: f32, color: Rgba<u8>) {
+    let r = radius.ceil() as i32 + 1;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let distance = ((dx as f32 + 0.5 + cx.floor() - cx).powi(2) + (dy as f32 + 0.5 + cy.floor() - cy).powi(2)).sqrt();
+            blend(image, cx as i32 + dx, cy as i32 + dy, color, radius + 0.5 - distance);
+        }
+    }
+}
+
+fn fill_rect(image: &mut RgbaImage, (x, y, width, height): (f32, f32, f32, f32), color: Rgba<u8>) {
+    for py in y as i32..(y + height) as i32 {
+        for px in x as i32..(x + width) as i32 {
+            blend(image, px, py, color, 1.0);
+        }
+    }
+}
+
+/// Points along the great circle from `from` to `to` as (lat, lon) degrees; longitudes are
+/// unwrapped so a route across the antimeridian stays continuous
+pub fn great_circle_points(from: (f64, f64), to: (f64, f64), segments: usize) -> Vec<(f64, f64)> {
+    let to_vector = |(lat, lon): (f64, f64)| {
+        let (lat, lon) = (lat.to_radians(), lon.to_radians());
+        [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+    };
+    let (a, b) = (to_vector(from), to_vector(to));
+    let angle = (a[0] * b[0] + a[1] * b[1] + a[2] * b[2]).clamp(-1.0, 1.0).acos();
+
+    let mut points = Vec::with_capacity(segments + 1);
+    let mut previous_lon = from.1;
+    for i in 0..=segments {
+        let t = i as f64 / segments as f64;
+        let (wa, wb) = if angle.abs() < 1e-9 {
+            (1.0 - t, t)
+        } else {
+            (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin())
+        };
+        let v = [wa * a[0] + wb * b[0], wa * a[1] + wb * b[1], wa * a[2] + wb * b[2]];
+        let lat = v[2].atan2((v[0] * v[0] + v[1] * v[1]).sqrt()).to_degrees();
+        let mut lon = v[1].atan2(v[0]).to_degrees();
+        while lon - previous_lon > 180.0 {
+            lon -= 360.0;
+        }
+        while lon - previous_lon < -180.0 {
+            lon += 360.0;
+        }
+        previous_lon = lon;
+        points.push((lat, lon));
+    }
+    points
+}
+
+/// Fit the arc into the map panel (equirectangular, longitudes shrunk by the mid latitude)
+fn project(points: &[(f64, f64)]) -> Vec<(f32, f32)> {
+    let (min_lat, max_lat) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+    let (min_lon, max_lon) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    let x_scale = ((min_lat + max_lat) / 2.0).to_radians().cos().max(0.2);
+
+    let (px, py, width, height) = MAP_PANEL;
+    let padding = 48.0;
+    let span_x = ((max_lon - min_lon) * x_scale).max(1e-6);
+    let span_y = (max_lat - min_lat).max(1e-6);
+    let scale = ((width as f64 - 2.0 * padding) / span_x).min((height as f64 - 2.0 * padding) / span_y);
+    let (center_x, center_y) = (px as f64 + width as f64 / 2.0, py as f64 + height as f64 / 2.0);
+    let (mid_lon, mid_lat) = ((min_lon + max_lon) / 2.0, (min_lat + max_lat) / 2.0);
+
+    points
+        .iter()
+        .map(|(lat, lon)| {
+            (
+                (center_x + (lon - mid_lon) * x_scale * scale) as f32,
+                (center_y - (lat - mid_lat) * scale) as f32,
+            )
+        })
+        .collect()
+}
+
+fn draw_map(image: &mut RgbaImage, palette: &Palette, fonts: &CardFonts, card: &FlightCard, from: (f64, f64), to: (f64, f64)) {
+    fill_rect(image, MAP_PANEL, palette.panel);
+    let projected = project(&great_circle_points(from, to, ARC_SEGMENTS));
+    for pair in projected.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        let steps = ((x1 - x0).hypot(y1 - y0).ceil() as usize).max(1);
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            fill_circle(image, x0 + (x1 - x0) * t, y0 + (y1 - y0) * t, 2.5, palette.accent);
+        }
+    }
+    let endpoints = [(projected[0], &card.departure_airport), (projected[projected.len() - 1], &card.arrival_airport)];
+    for ((x, y), code) in endpoints {
+        fill_circle(image, x, y, 9.0, palette.accent);
+        fill_circle(image, x, y, 4.0, palette.bg);
+        let label_x = (x - text_width(&fonts.regular, 24.0, code) / 2.0)
+            .clamp(MAP_PANEL.0 + 8.0, MAP_PANEL.0 + MAP_PANEL.2 - text_width(&fonts.regular, 24.0, code) - 8.0);
+        draw_text(image, &fonts.regular, 24.0, label_x, y + 40.0, code, palette.muted);
+    }
+}
+
+fn format_duration(minutes: i32) -> String {
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Draw the card; the text column narrows to leave room for the map when there is one
+pub fn render(card: &FlightCard, theme: &CardTheme, fonts: &CardFonts) -> Result<RgbaImage> {
+    let palette = theme.palette()?;
+    let mut image = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, palette.bg);
+
+    if let Some((from, to)) = card.route {
+        draw_map(&mut image, &palette, fonts, card, from, to);
+    }
+    let text_right = if card.route.is_some() { MAP_PANEL.0 - 32.0 } else { CARD_WIDTH as f32 - MARGIN };
+
+    let heading = match &card.flight_number {
+        Some(number) => format!("{}  ·  {}", number, card.date),
+        None => card.date.clone(),
+    };
+    draw_text(&mut image, &fonts.regular, 34.0, MARGIN, MARGIN + 30.0, &heading, palette.muted);
+
+    // Fonts without an arrow glyph get a dash
+    let arrow = if fonts.bold.glyph('→').id().0 != 0 { "→" } else { "–" };
+    let route = format!("{} {} {}", card.departure_airport, arrow, card.arrival_airport);
+    // Long ICAO pairs shrink to fit beside the map
+    let route_size = 108.0_f32.min(108.0 * (text_right - MARGIN) / text_width(&fonts.bold, 108.0, &route).max(1.0));
+    draw_text(&mut image, &fonts.bold, route_size, MARGIN - 4.0, MARGIN + 170.0, &route, palette.fg);
+
+    let details = [
+        ("Aircraft", card.aircraft.clone()),
+        ("Distance", card.distance.clone()),
+        ("Duration", card.duration_minutes.map(format_duration)),
+    ];
+    let mut y = MARGIN + 260.0;
+    for (label, value) in details {
+        let Some(value) = value else { continue };
+        draw_text(&mut image, &fonts.regular, 30.0, MARGIN, y, label, palette.muted);
+        draw_text(&mut image, &fonts.bold, 38.0, MARGIN + 170.0, y, &value, palette.fg);
+        y += 62.0;
+    }
+
+    draw_text(
+        &mut image,
+        &fonts.bold,
+        28.0,
+        MARGIN,
+        CARD_HEIGHT as f32 - MARGIN + 10.0,
+        "Flight Tracker Pro",
+        palette.accent,
+    );
+    Ok(image)
+}
+
+pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Png).context("Failed to encode card PNG")?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_great_circle_points() {
+        // JFK to LHR bends north of both endpoints
+        let points = great_circle_points((40.64, -73.78), (51.47, -0.45), 32);
+        assert_eq!(points.len(), 33);
+        assert!((points[0].0 - 40.64).abs() < 1e-6 && (points[32].1 + 0.45).abs() < 1e-6);
+        assert!(points[16].0 > 51.47);
+
+        // SYD to LAX crosses the antimeridian without jumping
+        let points = great_circle_points((-33.95, 151.18), (33.94, -118.41), 32);
+        assert!(points.windows(2).all(|w| (w[1].1 - w[0].1).abs() < 20.0));
+        assert!((points[32].1 - (360.0 - 118.41)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_render_card_with_and_without_map() {
+        let fonts = CardFonts::bundled().unwrap();
+        let mut card = FlightCard {
+            flight_number: Some("BA117".to_string()),
+            departure_airport: "LHR".to_string(),
+            arrival_airport: "JFK".to_string(),
+            date: "2025-06-01".to_string(),
+            aircraft: Some("Boeing 777".to_string()),
+            distance: Some("5554 km".to_string()),
+            duration_minutes: Some(455),
+            route: Some(((51.47, -0.45), (40.64, -73.78))),
+        };
+        let theme = CardTheme::default();
+        let background = parse_color(&theme.bg_color).unwrap();
+        let map_center = (MAP_PANEL.0 + MAP_PANEL.2 / 2.0) as u32;
+
+        let with_map = render(&card, &theme, &fonts).unwrap();
+        assert_eq!(with_map.dimensions(), (CARD_WIDTH, CARD_HEIGHT));
+        assert_ne!(*with_map.get_pixel(map_center, 100), background);
+
+        card.route = None;
+        let text_only = render(&card, &theme, &fonts).unwrap();
+        assert_eq!(*text_only.get_pixel(map_center, 100), background);
+        assert!(encode_png(&text_only).unwrap().starts_with(b"\x89PNG"));
+
+        let bad = CardTheme { fg_color: "white".to_string(), ..CardTheme::default() };
+        assert!(render(&card, &bad, &fonts).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+// Distance and duration sanity checks
+// Imports sometimes match an airport code to the wrong field, giving a 50,000 km flight, or
+// lose a time zone, giving a ten-minute transatlantic crossing. A flight is flagged when:
+// - its distance is longer than any great circle (half the Earth's circumference)
+// - the ground speed implied by distance and duration is beyond its aircraft class
+// - otherwise, its duration is outside the plausible range for the distance and class
+//
+// The aircraft class comes from the flight's performance profile or aircraft type; the
+// registration field is only read as free text when neither is known.
+//
+// Suggested fixes use the great-circle distance between the flight's airports where both
+// are known. Re-checking a flight resolves its open anomalies that no longer apply.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::aircraft_performance;
+use crate::database::Database;
+use crate::models::Flight;
+
+pub const DISTANCE_ANOMALY: &str = "distance_exceeds_max";
+pub const SPEED_ANOMALY: &str = "implausible_ground_speed";
+pub const DURATION_ANOMALY: &str = "implausible_duration";
+
+const ANOMALY_TYPES: [&str; 3] = [DISTANCE_ANOMALY, SPEED_ANOMALY, DURATION_ANOMALY];
+
+/// Half the equatorial circumference; no two points on Earth are further apart
+pub const MAX_GREAT_CIRCLE_KM: f64 = 20_040.0;
+
+/// Below this a flight is a circuit or a hop, and taxi time swamps any speed
+const MIN_CHECKED_DISTANCE_KM: f64 = 50.0;
+
+/// Slowest plausible average ground speed over a whole flight, including taxi and holds
+const MIN_GROUND_SPEED_KMH: f64 = 40.0;
+
+/// A recorded distance this far from the great circle is treated as wrong
+const DISTANCE_MISMATCH_RATIO: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AircraftClass {
+    Piston,
+    Turboprop,
+    Jet,
+}
+
+impl AircraftClass {
+    /// From an aircraft name such as "Cessna 172" or "ATR 72"; unknown aircraft are treated
+    /// as jets
+    pub fn from_aircraft(aircraft: Option<&str>) -> Self {
+        let aircraft = aircraft.unwrap_or("").to_uppercase();
+        let any = |names: &[&str]| names.iter().any(|name| aircraft.contains(name));
+        if any(&["CESSNA", "C172", "C182", "PA-", "PIPER", "CIRRUS", "SR22", "BONANZA", "DIAMOND"]) {
+            Self::Piston
+        } else if any(&["ATR", "DASH", "Q400", "DHC", "KING AIR", "PC-12", "PC12", "SAAB 340", "CARAVAN"]) {
+            Self::Turboprop
+        } else {
+            Self::Jet
+        }
+    }
+
+    /// From a performance profile's cruise speed
+    fn from_cruise_kts(cruise_kts: f64) -> Self {
+        if cruise_kts <= 200.0 {
+            Self::Piston
+        } else if cruise_kts <= 380.0 {
+            Self::Turboprop
+        } else {
+            Self::Jet
+        }
+    }
+
+    /// The class of a flight's aircraft: from the cruise speed of its performance profile
+    /// (registration, then type designator), else from the name of its aircraft type, else
+    /// from whatever the registration field holds
+    pub fn for_flight(db: &Database, flight: &Flight) -> Result<Self> {
+        let found = aircraft_performance::lookup(db, aircraft_performance::AircraftRef::of_flight(flight))?;
+        Ok(match (found.profile, found.type_name) {
+            (Some(profile), _) => Self::from_cruise_kts(profile.cruise_kts),
+            (None, Some(type_name)) => Self::from_aircraft(Some(&type_name)),
+            (None, None) => Self::from_aircraft(flight.aircraft_registration.as_deref()),
+        })
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Piston => "piston aircraft",
+            Self::Turboprop => "turboprop",
+            Self::Jet => "jet",
+        }
+    }
+
+    /// Fastest plausible ground speed, allowing for a strong tailwind
+    pub fn max_ground_speed_kmh(self) -> f64 {
+        match self {
+            Self::Piston => 450.0,
+            Self::Turboprop => 800.0,
+            Self::Jet => 1_250.0,
+        }
+    }
+
+    /// (slow, fast) cruise speeds used for the plausible duration range
+    fn cruise_speeds_kmh(self) -> (f64, f64) {
+        match self {
+            Self::Piston => (150.0, 330.0),
+            Self::Turboprop => (300.0, 650.0),
+            Self::Jet => (550.0, 950.0),
+        }
+    }
+
+    /// (min, max) plausible minutes for a distance: fast cruise with no overhead, up to slow
+    /// cruise plus two hours of taxi, holding and diversions
+    pub fn duration_range_minutes(self, distance_km: f64) -> (i32, i32) {
+        let (slow, fast) = self.cruise_speeds_kmh();
+        let min = distance_km / fast * 60.0;
+        let max = distance_km / slow * 60.0 + 120.0;
+        (min.floor() as i32, max.ceil() as i32)
+    }
+}
+
+/// One failed check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SanityIssue {
+    pub anomaly_type: String,
+    pub severity: String,
+    pub description: String,
+    pub suggested_fix: String,
+}
+
+impl SanityIssue {
+    pub(crate) fn new(anomaly_type: &str, severity: &str, description: String, suggested_fix: String) -> Self {
+        Self {
+            anomaly_type: anomaly_type.to_string(),
+            severity: severity.to_string(),
+            description,
+            suggested_fix,
+        }
+    }
+}
+
+/// Recorded minutes in the air, and which field they came from
+fn recorded_duration(flight: &Flight) -> Option<(i32, &'static str)> {
+    [
+        (flight.flight_duration, "flight_duration"),
+        (flight.total_duration, "total_duration"),
+        (flight.block_duration, "block_duration"),
+    ]
+    .into_iter()
+    .find_map(|(minutes, field)| minutes.filter(|m| *m > 0).map(|m| (m, field)))
+}
+
+fn recorded_distance_km(flight: &Flight) -> Option<f64> {
+    flight
+        .distance_km
+        .or(flight.distance_nm.map(|nm| nm * 1.852))
+        .filter(|km| *km > 0.0)
+}
+
+/// Run the checks against a flight of the given aircraft class; `route_km` is the
+/// great-circle distance between its airports when both are known
+pub fn check(flight: &Flight, class: AircraftClass, route_km: Option<f64>) -> Vec<SanityIssue> {
+    let mut issues = Vec::new();
+    let Some(distance) = recorded_distance_km(flight) else {
+        return issues;
+    };
+    let route = format!("{} to {}", flight.departure_airport, flight.arrival_airport);
+    let distance_is_off = route_km.is_some_and(|km| (distance - km).abs() > km.max(1.0) * DISTANCE_MISMATCH_RATIO);
+    let distance_fix = |fallback: &str| match route_km {
+        Some(km) if distance_is_off => format!("Set the distance to {:.0} km, the great-circle distance for {}", km, route),
+        _ => fallback.to_string(),
+    };
+
+    if distance > MAX_GREAT_CIRCLE_KM {
+        issues.push(SanityIssue::new(
+            DISTANCE_ANOMALY,
+            "error",
+            format!(
+                "Distance of {:.0} km is longer than any great circle ({:.0} km)",
+                distance, MAX_GREAT_CIRCLE_KM
+            ),
+            distance_fix("Check the airport codes and recalculate the distance"),
+        ));
+        // Speed and duration checks against an impossible distance only repeat the problem
+        return issues;
+    }
+
+    let Some((minutes, field)) = recorded_duration(flight) else {
+        return issues;
+    };
+    if distance < MIN_CHECKED_DISTANCE_KM {
+        return issues;
+    }
+
+    let speed = distance / (minutes as f64 / 60.0);
+    let (min_minutes, max_minutes) = class.duration_range_minutes(distance);
+    let expected = format!("{}-{} min", min_minutes, max_minutes);
+    let times_fix = format!(
+        "Check the departure and arrival times and their time zones; {:.0} km usually takes {}",
+        distance, expected
+    );
+
+    if speed > class.max_ground_speed_kmh() || speed < MIN_GROUND_SPEED_KMH {
+        let bound = if speed > class.max_ground_speed_kmh() {
+            format!("above the {:.0} km/h a {} can reach", class.max_ground_speed_kmh(), class.label())
+        } else {
+            format!("below {:.0} km/h", MIN_GROUND_SPEED_KMH)
+        };
+        issues.push(SanityIssue::new(
+            SPEED_ANOMALY,
+            "error",
+            format!(
+                "{:.0} km in {} min ({}) is a ground speed of {:.0} km/h, {}",
+                distance, minutes, field, speed, bound
+            ),
+            distance_fix(&times_fix),
+        ));
+    } else if minutes < min_minutes || minutes > max_minutes {
+        issues.push(SanityIssue::new(
+            DURATION_ANOMALY,
+            "warning",
+            format!(
+                "{} min ({}) for {:.0} km is outside the usual {} for a {}",
+                minutes, field, distance, expected, class.label()
+            ),
+            distance_fix(&times_fix),
+        ));
+    }
+    issues
+}
+
+/// An issue as stored in flight_anomalies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedIssue {
+    pub anomaly_id: String,
+    pub flight_id: String,
+    #[serde(flatten)]
+    pub issue: SanityIssue,
+}
+
+/// Check a flight and store the outcome: each issue raises an anomaly (or updates the open one
+/// of its type), and open anomalies from these checks that no longer apply are resolved
+pub fn record(db: &Database, flight: &Flight) -> Result<Vec<RecordedIssue>> {
+    let route_km = crate::commands::batch_calculations::route_distance(db, &flight.departure_airport, &flight.arrival_airport)
+        .map(|(_, km)| km);
+    let issues = check(flight, AircraftClass::for_flight(db, flight)?, route_km);
+
+    let tx = db.conn.unchecked_transaction()?;
+    for anomaly_type in ANOMALY_TYPES {
+        if !issues.iter().any(|i| i.anomaly_type == anomaly_type) {
+            resolve_open_anomaly(&tx, &flight.id, anomaly_type)?;
+        }
+    }
+    let mut recorded = Vec::with_capacity(issues.len());
+    for issue in issues {
+        let anomaly_id = raise_anomaly(&tx, &flight.id, &issue)?;
+        recorded.push(RecordedIssue { anomaly_id, flight_id: flight.id.clone(), issue });
+    }
+    tx.commit()?;
+    Ok(recorded)
+}
+
+/// Raise an anomaly for the flight, or update the open one of the same type; returns its id
+pub(crate) fn raise_anomaly(conn: &Connection, flight_id: &str, issue: &SanityIssue) -> Result<String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM flight_anomalies WHERE flight_id = ?1 AND anomaly_type = ?2 AND is_resolved = 0",
+            params![flight_id, issue.anomaly_type],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(match existing {
+        Some(id) => {
+            conn.execute(
+                "UPDATE flight_anomalies SET severity = ?2, description = ?3, suggested_fix = ?4 WHERE id = ?1",
+                params![id, issue.severity, issue.description, issue.suggested_fix],
+            )?;
+            id
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO flight_anomalies (id, flight_id, anomaly_type, severity, description, suggested_fix)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, flight_id, issue.anomaly_type, issue.severity, issue.description, issue.suggested_fix],
+            )?;
+            id
+        }
+    })
+}
+
+/// Resolve the flight's open anomalies of a type
+pub(crate) fn resolve_open_anomaly(conn: &Connection, flight_id: &str, anomaly_type: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE flight_anomalies SET is_resolved = 1, resolved_at = datetime('now')
+         WHERE flight_id = ?1 AND anomaly_type = ?2 AND is_resolved = 0",
+        params![flight_id, anomaly_type],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SanityScan {
+    pub checked_count: usize,
+    pub flagged_count: usize,
+    pub issues: Vec<RecordedIssue>,
+}
+
+/// Check every flight of a user
+pub fn scan(db: &Database, user_id: &str) -> Result<SanityScan> {
+    let flights = db.list_flights(user_id, i32::MAX, 0)?;
+    let mut scan = SanityScan { checked_count: flights.len(), flagged_count: 0, issues: Vec::new() };
+    for flight in &flights {
+        let issues = record(db, flight)?;
+        if !issues.is_empty() {
+            scan.flagged_count += 1;
+        }
+        scan.issues.extend(issues);
+    }
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn database() -> Database {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn.execute("INSERT INTO users (id, name) VALUES ('u1', 'Pilot')", []).unwrap();
+        db
+    }
+
+    fn flight_in(db: &Database, distance_km: f64, minutes: i32, registration: Option<&str>, type_id: Option<&str>) -> Flight {
+        db.conn
+            .execute(
+                "INSERT OR REPLACE INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime,
+                                                 distance_km, flight_duration, aircraft_registration, aircraft_type_id)
+                 VALUES ('f1', 'u1', 'LHR', 'JFK', '2025-03-01T10:00:00', ?1, ?2, ?3, ?4)",
+                params![distance_km, minutes, registration, type_id],
+            )
+            .unwrap();
+        db.get_flight("f1").unwrap().unwrap()
+    }
+
+    /// Check a flight whose registration field holds `aircraft`
+    fn checked(distance_km: f64, minutes: i32, aircraft: Option<&str>, route_km: Option<f64>) -> Vec<SanityIssue> {
+        let db = database();
+        let flight = flight_in(&db, distance_km, minutes, aircraft, None);
+        check(&flight, AircraftClass::for_flight(&db, &flight).unwrap(), route_km)
+    }
+
+    fn types(issues: &[SanityIssue]) -> Vec<&str> {
+        issues.iter().map(|i| i.anomaly_type.as_str()).collect()
+    }
+
+    #[test]
+    fn test_check_rules() {
+        // Eight hours across the Atlantic is fine
+        assert!(checked(5_540.0, 480, Some("B777"), Some(5_540.0)).is_empty());
+
+        let issues = checked(50_000.0, 480, None, Some(5_540.0));
+        assert_eq!(types(&issues), vec![DISTANCE_ANOMALY]);
+        assert!(issues[0].suggested_fix.contains("5540 km"));
+
+        // Ten minutes across the Atlantic: the distance is right, so the times are suspect
+        let issues = checked(5_540.0, 10, None, Some(5_540.0));
+        assert_eq!(types(&issues), vec![SPEED_ANOMALY]);
+        assert!(issues[0].suggested_fix.contains("time zones"));
+
+        // Plausible for a jet, far too fast for a Cessna
+        assert!(checked(600.0, 70, Some("Boeing 737"), None).is_empty());
+        assert_eq!(types(&checked(600.0, 70, Some("Cessna 172"), None)), vec![SPEED_ANOMALY]);
+
+        // Possible ground speed, but a 2,000 km flight does not take eleven hours
+        assert_eq!(types(&checked(2_000.0, 660, None, None)), vec![DURATION_ANOMALY]);
+    }
+
+    #[test]
+    fn test_class_from_aircraft_type_and_profile() {
+        let db = database();
+        db.conn
+            .execute(
+                "INSERT INTO aircraft_types (id, manufacturer, model, type_designator) VALUES ('t1', 'Cessna', '172S', 'C172')",
+                [],
+            )
+            .unwrap();
+
+        // A real registration says nothing about the class; the aircraft type does
+        let unknown = flight_in(&db, 600.0, 70, Some("N172SP"), None);
+        assert_eq!(AircraftClass::for_flight(&db, &unknown).unwrap(), AircraftClass::Jet);
+        let piston = flight_in(&db, 600.0, 70, Some("N172SP"), Some("t1"));
+        let class = AircraftClass::for_flight(&db, &piston).unwrap();
+        assert_eq!(class, AircraftClass::Piston);
+        assert_eq!(types(&check(&piston, class, None)), vec![SPEED_ANOMALY]);
+
+        // A registration profile is used without any type
+        db.conn
+            .execute(
+                "INSERT INTO aircraft_performance (id, aircraft_key, key_kind, cruise_kts, burn_gph)
+                 VALUES ('p1', 'GABCD', 'registration', 270, 60)",
+                [],
+            )
+            .unwrap();
+        let turboprop = flight_in(&db, 600.0, 70, Some("G-ABCD"), None);
+        assert_eq!(AircraftClass::for_flight(&db, &turboprop).unwrap(), AircraftClass::Turboprop);
+    }
+
+    #[test]
+    fn test_record_updates_and_resolves() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, distance_km, flight_duration)
+                 VALUES ('f1', 'u1', 'LHR', 'JFK', '2025-03-01T10:00:00', 5540, 10),
+                        ('f2', 'u1', 'LHR', 'JFK', '2025-03-08T10:00:00', 5540, 470);",
+            )
+            .unwrap();
+        let open = |db: &Database| -> i64 {
+            db.conn
+                .query_row("SELECT COUNT(*) FROM flight_anomalies WHERE is_resolved = 0", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let result = scan(&db, "u1").unwrap();
+        assert_eq!((result.checked_count, result.flagged_count), (2, 1));
+        assert_eq!(open(&db), 1);
+
+        // Scanning again updates the open anomaly rather than adding another
+        scan(&db, "u1").unwrap();
+        assert_eq!(open(&db), 1);
+
+        db.conn.execute("UPDATE flights SET flight_duration = 465 WHERE id = 'f1'", []).unwrap();
+        let fixed = db.get_flight("f1").unwrap().unwrap();
+        assert!(record(&db, &fixed).unwrap().is_empty());
+        assert_eq!(open(&db), 0);
+    }
+}
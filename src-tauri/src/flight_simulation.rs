@@ -0,0 +1,249 @@
+// What-if flight simulation
+// Estimates a flight that has not been flown (distance, duration, fuel, fuel cost and CO2)
+// for comparing routings and aircraft before booking. Nothing is written to the database.
+//
+// Each figure is estimated independently, so an unknown airport still leaves the aircraft's
+// figures per hour, and an unknown aircraft still gets a distance. Whatever could not be
+// estimated is None, with the reason in `notes`; estimates resting on generic assumptions
+// are kept and noted too.
+
+use anyhow::Result;
+use chrono::Local;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+use crate::aircraft_performance::{self, AircraftRef, BASIS_GENERIC};
+use crate::calculations::{self, fuel_kg_to_gallons, fuel_kg_to_liters};
+use crate::commands::batch_calculations::airport_coords;
+use crate::commands::fuel_prices::fuel_cost;
+use crate::database::Database;
+use crate::models::FuelPrice;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedAirport {
+    pub code: String,
+    pub name: Option<String>,
+    /// Coordinates were found, so distances can be measured
+    pub located: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedFuelCost {
+    pub total_cost: f64,
+    pub currency: String,
+    pub price_per_unit: f64,
+    pub unit: String,
+    pub fuel_type: String,
+    /// "fuel_price" (the price list) or "fuel_entry" (the latest purchase)
+    pub price_source: String,
+    pub price_date: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightSimulation {
+    pub from: SimulatedAirport,
+    pub to: SimulatedAirport,
+    pub aircraft_type: Option<String>,
+    /// "profile", "type_family" or "generic" (see aircraft_performance.rs)
+    pub performance_basis: String,
+    pub passenger_count: Option<u32>,
+    pub distance_km: Option<f64>,
+    pub distance_nm: Option<f64>,
+    /// Airborne minutes, including climb and descent
+    pub duration_minutes: Option<i32>,
+    pub block_minutes: Option<i32>,
+    pub fuel_kg: Option<f64>,
+    pub fuel_liters: Option<f64>,
+    pub fuel_gallons: Option<f64>,
+    pub fuel_cost: Option<SimulatedFuelCost>,
+    pub co2_kg: Option<f64>,
+    pub per_passenger_co2_kg: Option<f64>,
+    /// What could not be estimated, and which assumptions the estimates rest on
+    pub notes: Vec<String>,
+}
+
+fn airport(db: &Database, code: &str) -> Result<(SimulatedAirport, Option<(f64, f64)>)> {
+    let code = code.trim().to_uppercase();
+    let name = db.get_airport_by_code(&code)?.map(|a| a.name);
+    let coords = airport_coords(db, &code);
+    Ok((SimulatedAirport { code, name, located: coords.is_some() }, coords))
+}
+
+/// The user's price in effect today, else their most recent fuel purchase
+fn latest_fuel_price(db: &Database, user_id: &str) -> Result<Option<(FuelPrice, &'static str)>> {
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    if let Some(price) = db.get_fuel_price_for_date(user_id, &today, None, None)? {
+        return Ok(Some((price, "fuel_price")));
+    }
+    let entry = db
+        .conn
+        .query_row(
+            "SELECT id, fuel_type, price_per_gallon, COALESCE(currency, 'USD'), purchase_date, airport_code, fbo_name, created_at
+             FROM fuel_entries
+             WHERE user_id = ?1 AND price_per_gallon > 0
+             ORDER BY purchase_date DESC, created_at DESC
+             LIMIT 1",
+            [user_id],
+            |row| {
+                let created_at: String = row.get(7)?;
+                Ok(FuelPrice {
+                    id: row.get(0)?,
+                    user_id: user_id.to_string(),
+                    fuel_type: row.get(1)?,
+                    price_per_unit: row.get(2)?,
+                    unit: "gallon".to_string(),
+                    currency: row.get(3)?,
+                    start_date: row.get(4)?,
+                    end_date: None,
+                    location: row.get(5)?,
+                    supplier: row.get(6)?,
+                    notes: None,
+                    created_at: created_at.clone(),
+                    updated_at: created_at,
+                })
+            },
+        )
+        .optional()?;
+    Ok(entry.map(|price| (price, "fuel_entry")))
+}
+
+/// Estimate a flight between two airports without recording it
+pub fn simulate(
+    db: &Database,
+    user_id: &str,
+    from: &str,
+    to: &str,
+    aircraft_type: Option<&str>,
+    passenger_count: Option<u32>,
+) -> Result<FlightSimulation> {
+    let aircraft_type = aircraft_type.map(str::trim).filter(|a| !a.is_empty());
+    let mut notes = Vec::new();
+
+    let (from, from_coords) = airport(db, from)?;
+    let (to, to_coords) = airport(db, to)?;
+    for end in [&from, &to] {
+        if !end.located {
+            notes.push(format!("No coordinates for airport {}: distance, time, fuel and CO2 cannot be estimated", end.code));
+        }
+    }
+
+    let (performance, basis) = aircraft_performance::resolve(db, AircraftRef::named(aircraft_type));
+    match (aircraft_type, basis) {
+        (None, _) => notes.push("No aircraft given: using generic narrow-body jet figures".to_string()),
+        (Some(aircraft), BASIS_GENERIC) => notes.push(format!(
+            "Aircraft \"{}\" is not recognised and has no performance profile: using generic narrow-body jet figures",
+            aircraft
+        )),
+        _ => {}
+    }
+
+    let distance = from_coords
+        .zip(to_coords)
+        .map(|((lat1, lon1), (lat2, lon2))| crate::geo::calculate_geodesic_distance(lat1, lon1, lat2, lon2));
+    let distance_km = distance.map(|(_, km)| km);
+
+    let duration_minutes = distance_km.map(|km| performance.flight_time(km));
+    let fuel_kg = distance_km.map(|km| performance.fuel_consumption(km));
+    let co2_kg = distance_km.map(|km| performance.co2_emissions(km));
+
+    let per_passenger_co2_kg = distance_km.map(|km| {
+        calculations::per_passenger_co2(&performance, km, passenger_count)
+    });
+    if per_passenger_co2_kg.is_some() && passenger_count.is_none_or(|n| n == 0) {
+        notes.push(format!(
+            "No passenger count: CO2 shared across a typical load of {:.0} passengers",
+            performance.typical_load
+        ));
+    }
+
+    let fuel_cost = match (fuel_kg, latest_fuel_price(db, user_id)?) {
+        (Some(fuel_kg), Some((price, source))) => {
+            let (_, total_cost) = fuel_cost(fuel_kg, &price);
+            Some(SimulatedFuelCost {
+                total_cost,
+                currency: price.currency,
+                price_per_unit: price.price_per_unit,
+                unit: price.unit,
+                fuel_type: price.fuel_type,
+                price_source: source.to_string(),
+                price_date: price.start_date,
+            })
+        }
+        (Some(_), None) => {
+            notes.push("No fuel price or fuel purchase recorded: fuel cost cannot be estimated".to_string());
+            None
+        }
+        (None, _) => None,
+    };
+
+    Ok(FlightSimulation {
+        from,
+        to,
+        aircraft_type: aircraft_type.map(str::to_string),
+        performance_basis: basis.to_string(),
+        passenger_count,
+        distance_km,
+        distance_nm: distance.map(|(nm, _)| nm),
+        duration_minutes,
+        block_minutes: duration_minutes.map(calculations::calculate_block_time),
+        fuel_kg,
+        fuel_liters: fuel_kg.map(fuel_kg_to_liters),
+        fuel_gallons: fuel_kg.map(fuel_kg_to_gallons),
+        fuel_cost,
+        co2_kg,
+        per_passenger_co2_kg,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aircraft_performance::BASIS_PROFILE;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_simulate_flight() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, iata_code, name, latitude, longitude) VALUES
+                     ('LHR', 'LHR', 'Heathrow', 51.4700, -0.4543),
+                     ('JFK', 'JFK', 'John F Kennedy', 40.6413, -73.7781),
+                     ('ZZZ', 'ZZZ', 'Unsurveyed', NULL, NULL);
+                 INSERT INTO fuel_entries (id, user_id, fuel_type, gallons, price_per_gallon, total_cost, currency, purchase_date) VALUES
+                     ('old', 'u1', 'jet_a', 100, 5.00, 500, 'USD', '2024-01-01'),
+                     ('new', 'u1', 'jet_a', 100, 6.00, 600, 'USD', '2025-01-01');",
+            )
+            .unwrap();
+        let flights_before: i64 = db.conn.query_row("SELECT COUNT(*) FROM flights", [], |r| r.get(0)).unwrap();
+
+        let sim = simulate(&db, "u1", "lhr", "JFK", Some("B77W"), Some(300)).unwrap();
+        assert_eq!(sim.performance_basis, BASIS_PROFILE);
+        let km = sim.distance_km.unwrap();
+        assert!((5500.0..5600.0).contains(&km), "LHR-JFK is about 5,550 km, got {}", km);
+        let fuel_kg = sim.fuel_kg.unwrap();
+        let cost = sim.fuel_cost.as_ref().unwrap();
+        assert_eq!((cost.price_source.as_str(), cost.price_per_unit), ("fuel_entry", 6.0));
+        assert!((cost.total_cost - fuel_kg / 0.8 / 3.78541 * 6.0).abs() < 0.01);
+        assert!((sim.per_passenger_co2_kg.unwrap() - sim.co2_kg.unwrap() / 300.0).abs() < 1e-6);
+        assert!(sim.notes.is_empty(), "{:?}", sim.notes);
+
+        // Unknown aircraft: still estimated, with the assumption noted
+        let sim = simulate(&db, "u1", "LHR", "JFK", Some("Zeppelin"), None).unwrap();
+        assert_eq!(sim.performance_basis, BASIS_GENERIC);
+        assert!(sim.co2_kg.is_some());
+        assert_eq!(sim.notes.len(), 2, "{:?}", sim.notes);
+
+        // Unlocated airport: nothing distance-based, but the airport is still reported
+        let sim = simulate(&db, "u1", "LHR", "ZZZ", Some("B77W"), Some(300)).unwrap();
+        assert!(!sim.to.located);
+        assert_eq!(sim.to.name.as_deref(), Some("Unsurveyed"));
+        assert!(sim.distance_km.is_none() && sim.fuel_kg.is_none() && sim.fuel_cost.is_none() && sim.co2_kg.is_none());
+        assert!(sim.notes[0].contains("ZZZ"));
+
+        let flights_after: i64 = db.conn.query_row("SELECT COUNT(*) FROM flights", [], |r| r.get(0)).unwrap();
+        assert_eq!(flights_before, flights_after);
+    }
+}
@@ -0,0 +1,256 @@
+// Flight templates
+// A named, user-scoped set of flight fields for routes flown again and again (training
+// circuits, commutes). Instantiating a template on a date keeps its time of day, block
+// length and stored distance and durations: a circuit's route distance is zero, so
+// recomputing them from the airports would lose what the pilot entered.
+
+use crate::error::CommandError;
+use crate::models::FlightInput;
+use crate::validation::FieldErrors;
+use anyhow::{anyhow, bail, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Most flights one series may create
+pub const MAX_SERIES_FLIGHTS: usize = 366;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightTemplate {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    /// Field set copied into each new flight; only the time of day of the datetimes is used
+    pub flight: FlightInput,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn template_from_row(row: &rusqlite::Row) -> rusqlite::Result<FlightTemplate> {
+    let fields: String = row.get(3)?;
+    let flight = serde_json::from_str(&fields)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?;
+    Ok(FlightTemplate {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        flight,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+pub fn list_templates(conn: &Connection, user_id: &str) -> Result<Vec<FlightTemplate>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, user_id, name, fields, created_at, updated_at
+         FROM flight_templates WHERE user_id = ?1 ORDER BY name COLLATE NOCASE",
+    )?;
+    let templates = stmt
+        .query_map(params![user_id], template_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(templates)
+}
+
+pub fn get_template(conn: &Connection, user_id: &str, id: &str) -> Result<Option<FlightTemplate>> {
+    Ok(conn
+        .query_row(
+            "SELECT id, user_id, name, fields, created_at, updated_at FROM flight_templates WHERE id = ?1 AND user_id = ?2",
+            params![id, user_id],
+            template_from_row,
+        )
+        .optional()?)
+}
+
+pub fn create_template(conn: &Connection, user_id: &str, name: &str, flight: &FlightInput) -> Result<FlightTemplate> {
+    validate_template(name, flight)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO flight_templates (id, user_id, name, fields) VALUES (?1, ?2, ?3, ?4)",
+        params![id, user_id, name.trim(), serde_json::to_string(flight)?],
+    )?;
+    get_template(conn, user_id, &id)?.ok_or_else(|| anyhow!("Flight template not found: {}", id))
+}
+
+pub fn update_template(conn: &Connection, user_id: &str, id: &str, name: &str, flight: &FlightInput) -> Result<FlightTemplate> {
+    validate_template(name, flight)?;
+    let updated = conn.execute(
+        "UPDATE flight_templates SET name = ?3, fields = ?4, updated_at = datetime('now') WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id, name.trim(), serde_json::to_string(flight)?],
+    )?;
+    if updated == 0 {
+        return Err(CommandError::not_found("Flight template", id).into());
+    }
+    get_template(conn, user_id, id)?.ok_or_else(|| anyhow!("Flight template not found: {}", id))
+}
+
+pub fn delete_template(conn: &Connection, user_id: &str, id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM flight_templates WHERE id = ?1 AND user_id = ?2", params![id, user_id])? > 0)
+}
+
+/// Templates need a name and valid airports; the datetimes may be left empty
+fn validate_template(name: &str, flight: &FlightInput) -> std::result::Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if name.trim().is_empty() {
+        errors.add("name", "is required");
+    }
+    errors.airport_code("departure_airport", &flight.departure_airport);
+    errors.airport_code("arrival_airport", &flight.arrival_airport);
+    errors.optional_datetime("departure_datetime", Some(&flight.departure_datetime));
+    errors.optional_datetime("arrival_datetime", flight.arrival_datetime.as_deref());
+    errors.into_result()
+}
+
+/// The flight a template produces on `date`
+///
+/// Departure keeps the template's time of day (midnight if it has none); arrival keeps
+/// its offset from departure. Distance, CO2 and durations are kept as stored; per-booking
+/// fields (booking reference, ticket, attachment) are not copied.
+pub fn instantiate(template: &FlightInput, date: NaiveDate) -> FlightInput {
+    let template_departure = crate::timezone::parse_local_datetime(&template.departure_datetime);
+    let departure = date.and_time(template_departure.map(|dt| dt.time()).unwrap_or_default());
+    let arrival = template
+        .arrival_datetime
+        .as_deref()
+        .and_then(crate::timezone::parse_local_datetime)
+        .map(|arrival| match template_departure {
+            Some(template_departure) => departure + (arrival - template_departure),
+            None => date.and_time(arrival.time()),
+        });
+
+    FlightInput {
+        departure_datetime: format_datetime(departure),
+        arrival_datetime: arrival.map(format_datetime),
+        booking_reference: None,
+        ticket_number: None,
+        attachment_path: None,
+        ..template.clone()
+    }
+}
+
+fn format_datetime(value: NaiveDateTime) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Dates from `start` to `end` inclusive, every `every_days` days
+pub fn series_dates(start: NaiveDate, end: NaiveDate, every_days: u32) -> Result<Vec<NaiveDate>> {
+    if every_days == 0 {
+        bail!("Series interval must be at least one day");
+    }
+    if end < start {
+        bail!("Series end date {} is before its start date {}", end, start);
+    }
+    let dates: Vec<NaiveDate> = start
+        .iter_days()
+        .step_by(every_days as usize)
+        .take_while(|d| *d <= end)
+        .take(MAX_SERIES_FLIGHTS + 1)
+        .collect();
+    if dates.len() > MAX_SERIES_FLIGHTS {
+        bail!("A series can create at most {} flights", MAX_SERIES_FLIGHTS);
+    }
+    Ok(dates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn empty_flight() -> FlightInput {
+        FlightInput {
+            flight_number: None,
+            departure_airport: String::new(),
+            arrival_airport: String::new(),
+            departure_datetime: String::new(),
+            arrival_datetime: None,
+            aircraft_type_id: None,
+            aircraft_registration: None,
+            total_duration: None,
+            flight_duration: None,
+            distance_nm: None,
+            distance_km: None,
+            carbon_emissions_kg: None,
+            booking_reference: None,
+            ticket_number: None,
+            seat_number: None,
+            fare_class: None,
+            base_fare: None,
+            taxes: None,
+            total_cost: None,
+            currency: None,
+            notes: None,
+            attachment_path: None,
+            passenger_count: None,
+        }
+    }
+
+    fn circuit() -> FlightInput {
+        FlightInput {
+            departure_airport: "EGKB".to_string(),
+            arrival_airport: "EGKB".to_string(),
+            departure_datetime: "2024-01-05T22:30:00".to_string(),
+            arrival_datetime: Some("2024-01-06T00:15:00".to_string()),
+            aircraft_registration: Some("G-ABCD".to_string()),
+            distance_km: Some(120.0),
+            flight_duration: Some(95),
+            booking_reference: Some("XYZ123".to_string()),
+            notes: Some("Circuits".to_string()),
+            ..empty_flight()
+        }
+    }
+
+    #[test]
+    fn test_instantiate_keeps_times_and_stored_values() {
+        let flight = instantiate(&circuit(), date("2024-06-30"));
+        assert_eq!(flight.departure_datetime, "2024-06-30T22:30:00");
+        // Arrival keeps its offset across midnight
+        assert_eq!(flight.arrival_datetime.as_deref(), Some("2024-07-01T00:15:00"));
+        assert_eq!(flight.aircraft_registration.as_deref(), Some("G-ABCD"));
+        assert_eq!(flight.notes.as_deref(), Some("Circuits"));
+        // A circuit's distance and duration cannot be recomputed from the airports
+        assert_eq!((flight.distance_km, flight.flight_duration), (Some(120.0), Some(95)));
+        assert!(flight.booking_reference.is_none());
+
+        let untimed = FlightInput { departure_datetime: String::new(), arrival_datetime: None, ..circuit() };
+        assert_eq!(instantiate(&untimed, date("2024-06-30")).departure_datetime, "2024-06-30T00:00:00");
+    }
+
+    #[test]
+    fn test_series_dates() {
+        let dates = series_dates(date("2024-06-01"), date("2024-06-15"), 7).unwrap();
+        assert_eq!(dates, vec![date("2024-06-01"), date("2024-06-08"), date("2024-06-15")]);
+        assert!(series_dates(date("2024-06-02"), date("2024-06-01"), 1).is_err());
+        assert!(series_dates(date("2024-01-01"), date("2025-12-31"), 1).is_err());
+        assert!(series_dates(date("2024-01-01"), date("2024-01-02"), 0).is_err());
+    }
+
+    #[test]
+    fn test_template_crud_is_user_scoped() {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch("INSERT INTO users (id, name) VALUES ('user-1', 'One'), ('user-2', 'Two')")
+            .unwrap();
+        let template = create_template(&db.conn, "user-1", " Circuits ", &circuit()).unwrap();
+        assert_eq!(template.name, "Circuits");
+        assert_eq!(template.flight.departure_airport, "EGKB");
+        assert!(list_templates(&db.conn, "user-2").unwrap().is_empty());
+
+        let renamed = update_template(&db.conn, "user-1", &template.id, "Night circuits", &circuit()).unwrap();
+        assert_eq!(renamed.name, "Night circuits");
+        assert!(update_template(&db.conn, "user-1", "missing", "x", &circuit()).is_err());
+
+        // Another user's template is not found, however it is reached
+        assert!(get_template(&db.conn, "user-2", &template.id).unwrap().is_none());
+        assert!(update_template(&db.conn, "user-2", &template.id, "Mine", &circuit()).is_err());
+        assert!(!delete_template(&db.conn, "user-2", &template.id).unwrap());
+
+        let bad = FlightInput { departure_airport: "not an airport".to_string(), ..circuit() };
+        assert!(create_template(&db.conn, "user-1", "", &bad).is_err());
+
+        assert!(delete_template(&db.conn, "user-1", &template.id).unwrap());
+        assert!(list_templates(&db.conn, "user-1").unwrap().is_empty());
+    }
+}
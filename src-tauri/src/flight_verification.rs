@@ -0,0 +1,403 @@
+// External flight verification
+// Cross-checks a flight's number, route and date against a flight-data API (AeroDataBox,
+// key in the flight_data_api_key setting). A flight is a match when the flight number
+// operated its route that day, either as one leg or from the start of one leg to the end of
+// a later one (through flights). A match sets the verified flag; a mismatch, or no record
+// of the flight number operating that day, is flagged as an external_mismatch anomaly.
+// Without an API key the flight stays unverified.
+//
+// Lookups are cached per flight number and date. Past dates do not change, so they are
+// kept for good; today and later are refetched after a few hours. A lookup that found
+// nothing is refetched after a week whatever the date, since providers backfill gaps.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::Flight;
+
+pub const ANOMALY_TYPE: &str = "external_mismatch";
+
+const API_KEY_SETTING: &str = "flight_data_api_key";
+
+const LOOKUP_TIMEOUT_SECS: u64 = 20;
+
+/// One leg the flight number operated on the day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperatedLeg {
+    /// IATA code where known, otherwise ICAO
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    #[serde(default)]
+    pub departure_icao: Option<String>,
+    #[serde(default)]
+    pub arrival_icao: Option<String>,
+    /// Local time as reported by the provider
+    #[serde(default)]
+    pub scheduled_departure: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+impl OperatedLeg {
+    fn departs_from(&self, code: &str) -> bool {
+        airport_matches(code, &self.departure_airport, self.departure_icao.as_deref())
+    }
+
+    fn arrives_at(&self, code: &str) -> bool {
+        airport_matches(code, &self.arrival_airport, self.arrival_icao.as_deref())
+    }
+}
+
+fn airport_matches(code: &str, primary: &str, icao: Option<&str>) -> bool {
+    let code = code.trim();
+    code.eq_ignore_ascii_case(primary) || icao.is_some_and(|icao| code.eq_ignore_ascii_case(icao))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Match,
+    Mismatch,
+    /// The flight number has no record of operating that day
+    NotFound,
+    /// Nothing was checked: no API key or no flight number
+    Unverified,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightVerification {
+    pub flight_id: String,
+    pub status: VerificationStatus,
+    pub details: String,
+    pub legs: Vec<OperatedLeg>,
+    /// The legs came from the cache rather than the API
+    pub cached: bool,
+    /// The anomaly raised or updated on a mismatch
+    pub anomaly_id: Option<String>,
+}
+
+impl FlightVerification {
+    pub fn unverified(flight_id: &str, details: &str) -> Self {
+        Self {
+            flight_id: flight_id.to_string(),
+            status: VerificationStatus::Unverified,
+            details: details.to_string(),
+            legs: Vec::new(),
+            cached: false,
+            anomaly_id: None,
+        }
+    }
+}
+
+/// Flight number (spaces removed, uppercased) and local departure date a flight is looked up by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupKey {
+    pub flight_number: String,
+    pub date: String,
+}
+
+impl LookupKey {
+    /// None when the flight has no flight number
+    pub fn for_flight(flight: &Flight) -> Option<Self> {
+        let flight_number: String = flight
+            .flight_number
+            .as_deref()?
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase();
+        if flight_number.is_empty() {
+            return None;
+        }
+        let date = flight.departure_datetime.chars().take(10).collect();
+        Some(Self { flight_number, date })
+    }
+}
+
+/// A flight-data backend
+#[async_trait]
+pub trait FlightDataProvider: Send + Sync {
+    /// Every leg the flight number operated on the (local) date; empty when it did not operate
+    async fn flights_on(&self, flight_number: &str, date: &str) -> Result<Vec<OperatedLeg>>;
+}
+
+/// The configured provider; None when no API key is set
+pub fn provider_from_settings(db: &Database) -> Result<Option<Box<dyn FlightDataProvider>>> {
+    Ok(db
+        .get_setting(API_KEY_SETTING)?
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .map(|api_key| Box::new(AeroDataBox { api_key }) as Box<dyn FlightDataProvider>))
+}
+
+/// AeroDataBox through RapidAPI (https://aerodatabox.com)
+pub struct AeroDataBox {
+    api_key: String,
+}
+
+#[async_trait]
+impl FlightDataProvider for AeroDataBox {
+    async fn flights_on(&self, flight_number: &str, date: &str) -> Result<Vec<OperatedLeg>> {
+        let response = reqwest::Client::new()
+            .get(format!("https://aerodatabox.p.rapidapi.com/flights/number/{}/{}", flight_number, date))
+            .query(&[("dateLocalRole", "Departure")])
+            .header("X-RapidAPI-Key", &self.api_key)
+            .header("X-RapidAPI-Host", "aerodatabox.p.rapidapi.com")
+            .timeout(std::time::Duration::from_secs(LOOKUP_TIMEOUT_SECS))
+            .send()
+            .await
+            .context("AeroDataBox lookup failed")?;
+        // 204 and 404 both mean no flight with that number on that date
+        if matches!(response.status().as_u16(), 204 | 404) {
+            return Ok(Vec::new());
+        }
+        if !response.status().is_success() {
+            bail!("AeroDataBox returned {}", response.status());
+        }
+        let json: serde_json::Value = response.json().await?;
+        Ok(parse_aerodatabox(&json))
+    }
+}
+
+fn parse_aerodatabox(json: &serde_json::Value) -> Vec<OperatedLeg> {
+    let text = |value: &serde_json::Value, pointer: &str| {
+        value.pointer(pointer).and_then(|v| v.as_str()).map(str::to_string)
+    };
+    json.as_array()
+        .map(|flights| {
+            flights
+                .iter()
+                .filter_map(|flight| {
+                    let departure_icao = text(flight, "/departure/airport/icao");
+                    let arrival_icao = text(flight, "/arrival/airport/icao");
+                    Some(OperatedLeg {
+                        departure_airport: text(flight, "/departure/airport/iata").or_else(|| departure_icao.clone())?,
+                        arrival_airport: text(flight, "/arrival/airport/iata").or_else(|| arrival_icao.clone())?,
+                        departure_icao,
+                        arrival_icao,
+                        scheduled_departure: text(flight, "/departure/scheduledTime/local")
+                            .or_else(|| text(flight, "/departure/scheduledTimeLocal")),
+                        status: text(flight, "/status"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Cached legs for the key, if a usable lookup is cached
+pub fn cached_legs(conn: &Connection, key: &LookupKey) -> Result<Option<Vec<OperatedLeg>>> {
+    let legs: Option<String> = conn
+        .query_row(
+            "SELECT legs FROM flight_verification_cache
+             WHERE flight_number = ?1 AND flight_date = ?2
+               AND (flight_date < date('now', '-1 day') OR fetched_at > datetime('now', '-6 hours'))
+               AND (legs != '[]' OR fetched_at > datetime('now', '-7 days'))",
+            params![key.flight_number, key.date],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(legs.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+pub fn store_legs(conn: &Connection, key: &LookupKey, legs: &[OperatedLeg]) -> Result<()> {
+    conn.execute(
+        "INSERT INTO flight_verification_cache (flight_number, flight_date, legs, fetched_at)
+         VALUES (?1, ?2, ?3, datetime('now'))
+         ON CONFLICT(flight_number, flight_date) DO UPDATE SET legs = excluded.legs, fetched_at = excluded.fetched_at",
+        params![key.flight_number, key.date, serde_json::to_string(legs)?],
+    )?;
+    Ok(())
+}
+
+/// Whether the legs cover the flight's route, and why
+pub fn compare(flight: &Flight, legs: &[OperatedLeg]) -> (VerificationStatus, String) {
+    let number = flight.flight_number.as_deref().unwrap_or_default().trim();
+    let (departure, arrival) = (&flight.departure_airport, &flight.arrival_airport);
+    if legs.is_empty() {
+        let date: String = flight.departure_datetime.chars().take(10).collect();
+        return (VerificationStatus::NotFound, format!("No record of {} operating on {}", number, date));
+    }
+
+    for (start, leg) in legs.iter().enumerate() {
+        if !leg.departs_from(departure) {
+            continue;
+        }
+        if let Some(end) = legs[start..].iter().position(|l| l.arrives_at(arrival)) {
+            let details = if end == 0 {
+                format!("{} operated {} to {} that day", number, departure, arrival)
+            } else {
+                format!("{} operated {} to {} that day, with {} stop(s)", number, departure, arrival, end)
+            };
+            return (VerificationStatus::Match, details);
+        }
+    }
+
+    let operated = legs
+        .iter()
+        .map(|l| format!("{} to {}", l.departure_airport, l.arrival_airport))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let reversed = legs.iter().any(|l| l.departs_from(arrival) && l.arrives_at(departure));
+    let mut details = format!("{} operated {} that day, not {} to {}", number, operated, departure, arrival);
+    if reversed {
+        details.push_str("; the airports may be swapped");
+    }
+    (VerificationStatus::Mismatch, details)
+}
+
+/// Compare and record the outcome: a match sets verified and resolves open external_mismatch
+/// anomalies, anything else raises one (or updates the open one)
+pub fn record(db: &Database, flight: &Flight, legs: Vec<OperatedLeg>, cached: bool) -> Result<FlightVerification> {
+    let (status, details) = compare(flight, &legs);
+    let tx = db.conn.unchecked_transaction()?;
+    let mut anomaly_id = None;
+
+    if status == VerificationStatus::Match {
+        tx.execute("UPDATE flights SET verified = 1, updated_at = datetime('now') WHERE id = ?1", [&flight.id])?;
+        tx.execute(
+            "UPDATE flight_anomalies SET is_resolved = 1, resolved_at = datetime('now')
+             WHERE flight_id = ?1 AND anomaly_type = ?2 AND is_resolved = 0",
+            params![flight.id, ANOMALY_TYPE],
+        )?;
+    } else {
+        let severity = if status == VerificationStatus::Mismatch { "warning" } else { "info" };
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT id FROM flight_anomalies WHERE flight_id = ?1 AND anomaly_type = ?2 AND is_resolved = 0",
+                params![flight.id, ANOMALY_TYPE],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let id = match existing {
+            Some(id) => {
+                tx.execute(
+                    "UPDATE flight_anomalies SET severity = ?2, description = ?3 WHERE id = ?1",
+                    params![id, severity, details],
+                )?;
+                id
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO flight_anomalies (id, flight_id, anomaly_type, severity, description, suggested_fix)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![id, flight.id, ANOMALY_TYPE, severity, details, "Check the flight number, date and airports"],
+                )?;
+                id
+            }
+        };
+        anomaly_id = Some(id);
+    }
+    tx.commit()?;
+
+    Ok(FlightVerification { flight_id: flight.id.clone(), status, details, legs, cached, anomaly_id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn leg(departure: &str, arrival: &str) -> OperatedLeg {
+        OperatedLeg {
+            departure_airport: departure.to_string(),
+            arrival_airport: arrival.to_string(),
+            departure_icao: None,
+            arrival_icao: None,
+            scheduled_departure: None,
+            status: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_compare() {
+        let json = serde_json::json!([
+            {
+                "number": "BA 1",
+                "status": "Arrived",
+                "departure": { "airport": { "icao": "EGLC", "iata": "LCY" }, "scheduledTime": { "local": "2025-01-01 09:30+00:00" } },
+                "arrival": { "airport": { "icao": "EINN", "iata": "SNN" } }
+            },
+            {
+                "number": "BA 1",
+                "departure": { "airport": { "icao": "EINN", "iata": "SNN" } },
+                "arrival": { "airport": { "icao": "KJFK" } }
+            }
+        ]);
+        let legs = parse_aerodatabox(&json);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].scheduled_departure.as_deref(), Some("2025-01-01 09:30+00:00"));
+        assert_eq!(legs[1].arrival_airport, "KJFK");
+
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let mut flight = Flight { flight_number: Some("ba 1".to_string()), ..test_flight(&db) };
+        assert_eq!(LookupKey::for_flight(&flight).unwrap().flight_number, "BA1");
+
+        flight.departure_airport = "EGLC".to_string();
+        flight.arrival_airport = "KJFK".to_string();
+        let (status, details) = compare(&flight, &legs);
+        assert_eq!(status, VerificationStatus::Match);
+        assert!(details.contains("1 stop(s)"));
+
+        flight.departure_airport = "SNN".to_string();
+        flight.arrival_airport = "LCY".to_string();
+        let (status, details) = compare(&flight, &legs);
+        assert_eq!(status, VerificationStatus::Mismatch);
+        assert!(details.ends_with("may be swapped"));
+        assert_eq!(compare(&flight, &[]).0, VerificationStatus::NotFound);
+    }
+
+    fn test_flight(db: &Database) -> Flight {
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, flight_number, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'BA117', 'LHR', 'JFK', '2020-06-01T10:00:00');",
+            )
+            .unwrap();
+        db.get_flight("f1").unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_record_and_cache() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let flight = test_flight(&db);
+        let key = LookupKey::for_flight(&flight).unwrap();
+        assert!(cached_legs(&db.conn, &key).unwrap().is_none());
+        store_legs(&db.conn, &key, &[leg("LHR", "BOS")]).unwrap();
+        let cached = cached_legs(&db.conn, &key).unwrap().unwrap();
+
+        let mismatch = record(&db, &flight, cached, true).unwrap();
+        assert_eq!(mismatch.status, VerificationStatus::Mismatch);
+        let again = record(&db, &flight, vec![leg("LHR", "BOS")], true).unwrap();
+        assert_eq!(again.anomaly_id, mismatch.anomaly_id);
+
+        let matched = record(&db, &flight, vec![leg("LHR", "JFK")], false).unwrap();
+        assert_eq!(matched.status, VerificationStatus::Match);
+        assert_eq!(db.get_flight("f1").unwrap().unwrap().verified, 1);
+        let open: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM flight_anomalies WHERE is_resolved = 0", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(open, 0);
+
+        // Legs for a past date are kept; "not found" is looked up again after a week
+        let backdate = |days: i64| {
+            db.conn
+                .execute("UPDATE flight_verification_cache SET fetched_at = datetime('now', ?1)", [format!("-{} days", days)])
+                .unwrap()
+        };
+        backdate(30);
+        assert!(cached_legs(&db.conn, &key).unwrap().is_some());
+        store_legs(&db.conn, &key, &[]).unwrap();
+        backdate(1);
+        assert_eq!(cached_legs(&db.conn, &key).unwrap(), Some(Vec::new()));
+        backdate(8);
+        assert!(cached_legs(&db.conn, &key).unwrap().is_none());
+    }
+}
@@ -10,6 +10,9 @@ const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta"
 pub struct GeminiChatResult {
     pub content: String,
     pub tokens_used: Option<u32>,
+    /// Served from the AI response cache; no tokens were spent
+    #[serde(default)]
+    pub cached: bool,
 }
 
 /// Simple chat with Gemini (no tool calling, just conversation)
@@ -98,5 +101,6 @@ pub async fn chat_with_gemini_custom(
     Ok(GeminiChatResult {
         content,
         tokens_used,
+        cached: false,
     })
 }
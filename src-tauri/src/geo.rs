@@ -215,12 +215,132 @@ pub fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f
     (distance_nm, distance_km)
 }
 
-/// Calculate distance between two airports by IATA code
+/// Distance formula: spherical haversine (fast, up to ~0.5% off on long routes)
+/// or geodesic on the WGS84 ellipsoid (matches published great-circle distances)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMethod {
+    Haversine,
+    #[default]
+    Geodesic,
+}
+
+impl DistanceMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "haversine" | "spherical" => Some(DistanceMethod::Haversine),
+            "geodesic" | "vincenty" | "wgs84" | "ellipsoid" => Some(DistanceMethod::Geodesic),
+            _ => None,
+        }
+    }
+}
+
+const WGS84_A: f64 = 6_378_137.0; // semi-major axis (m)
+const WGS84_F: f64 = 1.0 / 298.257_223_563; // flattening
+pub const KM_PER_NM: f64 = 1.852;
+
+/// Calculate geodesic distance on the WGS84 ellipsoid using Vincenty's inverse formula
+/// Returns distance in both nautical miles and kilometers
+///
+/// Vincenty does not converge for nearly antipodal points; those fall back to
+/// haversine, which is within ~0.5% there.
+pub fn calculate_geodesic_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64) {
+    match vincenty_inverse_m(lat1, lon1, lat2, lon2) {
+        Some(meters) => {
+            let km = meters / 1000.0;
+            (km / KM_PER_NM, km)
+        }
+        None => calculate_distance(lat1, lon1, lat2, lon2),
+    }
+}
+
+/// Distance in meters, or None when the iteration fails to converge
+fn vincenty_inverse_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Option<f64> {
+    let b = WGS84_A * (1.0 - WGS84_F);
+    let l = (lon2 - lon1).to_radians();
+    let u1 = ((1.0 - WGS84_F) * lat1.to_radians().tan()).atan();
+    let u2 = ((1.0 - WGS84_F) * lat2.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            return Some(0.0); // coincident points
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+        // Both points on the equator: cos²α = 0
+        let cos_2sigma_m = if cos_sq_alpha != 0.0 {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+        } else {
+            0.0
+        };
+        let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+        let lambda_prev = lambda;
+        lambda = l
+            + (1.0 - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+        // λ leaving [-π, π] means the antipodal case is diverging
+        if lambda.abs() > PI {
+            return None;
+        }
+
+        if (lambda - lambda_prev).abs() < 1e-12 {
+            let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+            let big_a = 1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + big_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)
+                            - big_b / 6.0
+                                * cos_2sigma_m
+                                * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                                * (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+            return Some(b * big_a * (sigma - delta_sigma));
+        }
+    }
+
+    None
+}
+
+/// Distance between two coordinates using the chosen formula
+pub fn calculate_distance_with(method: DistanceMethod, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64) {
+    match method {
+        DistanceMethod::Haversine => calculate_distance(lat1, lon1, lat2, lon2),
+        DistanceMethod::Geodesic => calculate_geodesic_distance(lat1, lon1, lat2, lon2),
+    }
+}
+
+/// Coordinates from the built-in list, falling back to the bundled airport CSV
+pub(crate) fn airport_coords(code: &str) -> Option<(f64, f64)> {
+    get_airport_coords(code).or_else(|| crate::commands::airport_enrichment::cached_airport_coords(code))
+}
+
+/// Calculate distance between two airports by IATA or ICAO code (WGS84 geodesic)
 pub fn calculate_airport_distance(from: &str, to: &str) -> Option<(f64, f64)> {
-    let from_coords = get_airport_coords(from)?;
-    let to_coords = get_airport_coords(to)?;
+    calculate_airport_distance_with(from, to, DistanceMethod::Geodesic)
+}
 
-    Some(calculate_distance(
+/// Calculate distance between two airports by IATA code using the chosen formula
+pub fn calculate_airport_distance_with(from: &str, to: &str, method: DistanceMethod) -> Option<(f64, f64)> {
+    let from_coords = airport_coords(from)?;
+    let to_coords = airport_coords(to)?;
+
+    Some(calculate_distance_with(
+        method,
         from_coords.0,
         from_coords.1,
         to_coords.0,
@@ -239,4 +359,30 @@ mod tests {
         assert!((nm - 2150.0).abs() < 100.0); // Within 100nm
         assert!((km - 3983.0).abs() < 200.0); // Within 200km
     }
+
+    #[test]
+    fn test_jfk_to_lhr_geodesic() {
+        // Published WGS84 distance JFK-LHR: 5554 km / 2999 nm
+        let (nm, km) = calculate_airport_distance("JFK", "LHR").unwrap();
+        assert!((km - 5554.0).abs() < 3.0, "got {} km", km);
+        assert!((nm - 2999.0).abs() < 2.0, "got {} nm", nm);
+
+        // Haversine is measurably shorter on this route
+        let (_, haversine_km) = calculate_airport_distance_with("JFK", "LHR", DistanceMethod::Haversine).unwrap();
+        assert!(km - haversine_km > 10.0);
+    }
+
+    #[test]
+    fn test_geodesic_edge_cases() {
+        assert_eq!(calculate_geodesic_distance(51.47, -0.45, 51.47, -0.45), (0.0, 0.0));
+
+        // Equator quarter: a * π / 2
+        let (_, km) = calculate_geodesic_distance(0.0, 0.0, 0.0, 90.0);
+        assert!((km - 10_018.754).abs() < 0.01, "got {} km", km);
+
+        // Nearly antipodal points do not converge; result must still be finite and sensible
+        let (nm, km) = calculate_geodesic_distance(0.0, 0.0, 0.5, 179.7);
+        assert!(km.is_finite() && nm.is_finite());
+        assert!((km - 20_000.0).abs() < 100.0, "got {} km", km);
+    }
 }
@@ -0,0 +1,485 @@
+// GHG Protocol emissions report
+// Flight CO2 arranged by GHG Protocol scope for sustainability disclosures. How a flight was
+// operated decides its scope:
+//   own        - aircraft the organisation owns or operates: Scope 1 mobile combustion, the
+//                whole aircraft's fuel burn
+//   chartered  - a whole aircraft chartered from an operator: Scope 3 category 6 (business
+//                travel), the whole aircraft's fuel burn
+//   commercial - a seat on a scheduled flight: Scope 3 category 6, the traveller's share
+//
+// A flight's operation is its "operation:<type>" flight tag, else the aircraft's listing in
+// the "own_aircraft" / "chartered_aircraft" registrations of the "ghg_report_settings"
+// setting, else commercial. Flights whose accepted trip purpose is "personal" are not
+// business travel and are left out of Scope 3; fuel burnt in the organisation's own aircraft
+// is Scope 1 whatever the purpose.
+//
+// CO2 comes from the flight's stored figures, else is estimated from the distance with the
+// aircraft's performance profile. The radiative forcing multiplier (default 1.7, the UK
+// DESNZ uplift) gives CO2e including non-CO2 effects; it applies to Scope 3, and to Scope 1
+// only when "rf_in_scope_1" is set, as Scope 1 inventories usually report combustion CO2.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::aircraft_performance::{normalize_key, performance_for, AircraftRef};
+use crate::calculations::{per_passenger_co2, AircraftPerformance};
+use crate::database::Database;
+
+pub const SETTINGS_KEY: &str = "ghg_report_settings";
+
+/// Flight tags holding an operation type start with this
+pub const TAG_PREFIX: &str = "operation:";
+
+const PERSONAL_PURPOSE: &str = "personal";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Own,
+    Chartered,
+    Commercial,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 3] = [Operation::Own, Operation::Chartered, Operation::Commercial];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Own => "own",
+            Operation::Chartered => "chartered",
+            Operation::Commercial => "commercial",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Operation::ALL.into_iter().find(|op| op.as_str() == name)
+    }
+
+    /// GHG Protocol scope: 1 for the organisation's own aircraft, else 3
+    pub fn scope(&self) -> u8 {
+        match self {
+            Operation::Own => 1,
+            Operation::Chartered | Operation::Commercial => 3,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Operation::Own => "Scope 1 - mobile combustion, owned or operated aircraft",
+            Operation::Chartered => "Scope 3 category 6 - business travel, chartered aircraft",
+            Operation::Commercial => "Scope 3 category 6 - business travel, commercial flights",
+        }
+    }
+}
+
+/// Stored as JSON in the "ghg_report_settings" setting; fields left out keep their default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GhgSettings {
+    /// CO2e per kg CO2 for non-CO2 effects at altitude; 1.0 reports CO2 only
+    pub rf_multiplier: f64,
+    /// Apply the multiplier to Scope 1 as well as Scope 3
+    pub rf_in_scope_1: bool,
+    /// Registrations the organisation owns or operates
+    pub own_aircraft: Vec<String>,
+    /// Registrations normally chartered whole
+    pub chartered_aircraft: Vec<String>,
+    /// Leave flights with the "personal" trip purpose out of Scope 3
+    pub exclude_personal: bool,
+}
+
+impl Default for GhgSettings {
+    fn default() -> Self {
+        Self {
+            rf_multiplier: 1.7,
+            rf_in_scope_1: false,
+            own_aircraft: Vec::new(),
+            chartered_aircraft: Vec::new(),
+            exclude_personal: true,
+        }
+    }
+}
+
+impl GhgSettings {
+    /// Settings from the app settings, defaults when unset or invalid
+    pub fn configured(db: &Database) -> Self {
+        db.get_setting(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str::<GhgSettings>(&json).ok())
+            .filter(|settings| settings.invalid_fields().is_empty())
+            .map(GhgSettings::normalized)
+            .unwrap_or_default()
+    }
+
+    /// Registrations normalized as aircraft_performance keys, blanks and repeats dropped
+    pub fn normalized(mut self) -> Self {
+        for list in [&mut self.own_aircraft, &mut self.chartered_aircraft] {
+            let mut keys: Vec<String> = list.iter().map(|r| normalize_key(r)).filter(|k| !k.is_empty()).collect();
+            keys.sort();
+            keys.dedup();
+            *list = keys;
+        }
+        self
+    }
+
+    /// Field errors as (field, message)
+    pub fn invalid_fields(&self) -> Vec<(&'static str, &'static str)> {
+        let mut errors = Vec::new();
+        if !(self.rf_multiplier.is_finite() && self.rf_multiplier >= 1.0) {
+            errors.push(("rf_multiplier", "must be a number of at least 1"));
+        }
+        let own: Vec<String> = self.own_aircraft.iter().map(|r| normalize_key(r)).collect();
+        if self.chartered_aircraft.iter().any(|r| own.contains(&normalize_key(r))) {
+            errors.push(("chartered_aircraft", "must not repeat a registration listed as own aircraft"));
+        }
+        errors
+    }
+
+    fn aircraft_operation(&self, registration: Option<&str>) -> Option<Operation> {
+        let key = normalize_key(registration?);
+        if self.own_aircraft.contains(&key) {
+            Some(Operation::Own)
+        } else if self.chartered_aircraft.contains(&key) {
+            Some(Operation::Chartered)
+        } else {
+            None
+        }
+    }
+
+    fn rf_applies(&self, operation: Operation) -> bool {
+        operation.scope() == 3 || self.rf_in_scope_1
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhgFlightLine {
+    pub flight_id: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    pub aircraft_registration: Option<String>,
+    pub operation: Operation,
+    /// Where the operation came from: "flight", "aircraft" or "default"
+    pub operation_source: String,
+    pub scope: u8,
+    pub distance_km: Option<f64>,
+    /// "passenger_share" or "flight_total" when stored on the flight, else "estimated"
+    pub basis: String,
+    pub co2_kg: f64,
+    pub co2e_kg: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhgCategory {
+    pub scope: u8,
+    pub operation: Operation,
+    pub label: String,
+    pub flights: usize,
+    pub distance_km: f64,
+    pub co2_kg: f64,
+    pub co2e_kg: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhgReport {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// The settings the report was made with
+    pub settings: GhgSettings,
+    /// One per operation type, Scope 1 first
+    pub categories: Vec<GhgCategory>,
+    pub scope_1_co2_kg: f64,
+    pub scope_1_co2e_kg: f64,
+    pub scope_3_co2_kg: f64,
+    pub scope_3_co2e_kg: f64,
+    pub total_co2_kg: f64,
+    pub total_co2e_kg: f64,
+    /// Oldest first
+    pub flights: Vec<GhgFlightLine>,
+    /// Personal trips left out of Scope 3
+    pub excluded_personal_flights: usize,
+    /// Flights with no CO2 figure and no distance to estimate one from
+    pub unquantified_flights: Vec<String>,
+    /// How the figures were arrived at, for the disclosure's methodology note
+    pub methodology: Vec<String>,
+}
+
+pub fn flight_operation(conn: &Connection, flight_id: &str) -> Result<Option<Operation>> {
+    let tag: Option<String> = conn
+        .query_row(
+            "SELECT tag FROM flight_tags WHERE flight_id = ?1 AND tag LIKE 'operation:%' ORDER BY created_at DESC LIMIT 1",
+            [flight_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(tag.and_then(|t| Operation::parse(&t[TAG_PREFIX.len()..])))
+}
+
+/// Record the operation as the flight's only "operation:" tag; None clears it
+pub fn set_flight_operation(conn: &Connection, flight_id: &str, operation: Option<Operation>) -> Result<()> {
+    conn.execute("DELETE FROM flight_tags WHERE flight_id = ?1 AND tag LIKE 'operation:%'", [flight_id])?;
+    if let Some(operation) = operation {
+        conn.execute(
+            "INSERT INTO flight_tags (flight_id, tag) VALUES (?1, ?2)",
+            params![flight_id, format!("{}{}", TAG_PREFIX, operation.as_str())],
+        )?;
+    }
+    Ok(())
+}
+
+struct FlightRow {
+    id: String,
+    date: String,
+    departure_airport: String,
+    arrival_airport: String,
+    registration: Option<String>,
+    aircraft_type_id: Option<String>,
+    distance_km: Option<f64>,
+    carbon_emissions_kg: Option<f64>,
+    per_passenger_co2_kg: Option<f64>,
+    passenger_count: Option<i64>,
+    operation_tag: Option<String>,
+    purpose: Option<String>,
+}
+
+fn flights_in_range(conn: &Connection, user_id: &str, start_date: Option<&str>, end_date: Option<&str>) -> Result<Vec<FlightRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT f.id, substr(f.departure_datetime, 1, 10), f.departure_airport, f.arrival_airport,
+                f.aircraft_registration, COALESCE(f.distance_km, f.distance_nm * 1.852),
+                f.carbon_emissions_kg, f.per_passenger_co2_kg, f.passenger_count,
+                (SELECT substr(tag, 11) FROM flight_tags
+                 WHERE flight_id = f.id AND tag LIKE 'operation:%' ORDER BY created_at DESC LIMIT 1),
+                (SELECT substr(tag, 9) FROM flight_tags
+                 WHERE flight_id = f.id AND tag LIKE 'purpose:%' ORDER BY created_at DESC LIMIT 1),
+                f.aircraft_type_id
+         FROM flights f
+         WHERE f.user_id = ?1 AND f.deleted_at IS NULL
+           AND (?2 IS NULL OR substr(f.departure_datetime, 1, 10) >= ?2)
+           AND (?3 IS NULL OR substr(f.departure_datetime, 1, 10) <= ?3)
+         ORDER BY f.departure_datetime",
+    )?;
+    let rows = stmt
+        .query_map(params![user_id, start_date, end_date], |row| {
+            Ok(FlightRow {
+                id: row.get(0)?,
+                date: row.get(1)?,
+                departure_airport: row.get(2)?,
+                arrival_airport: row.get(3)?,
+                registration: row.get(4)?,
+                distance_km: row.get(5)?,
+                carbon_emissions_kg: row.get(6)?,
+                per_passenger_co2_kg: row.get(7)?,
+                passenger_count: row.get(8)?,
+                operation_tag: row.get(9)?,
+                purpose: row.get(10)?,
+                aircraft_type_id: row.get(11)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// CO2 attributable to the organisation and its basis: the whole aircraft for own and
+/// chartered flights, the traveller's share on commercial ones
+fn flight_co2(flight: &FlightRow, operation: Operation, performance: &AircraftPerformance) -> Option<(f64, &'static str)> {
+    match operation {
+        Operation::Own | Operation::Chartered => flight
+            .carbon_emissions_kg
+            .map(|co2| (co2, "flight_total"))
+            .or_else(|| flight.distance_km.map(|km| (performance.co2_emissions(km), "estimated"))),
+        Operation::Commercial => flight.per_passenger_co2_kg.map(|co2| (co2, "passenger_share")).or_else(|| {
+            let passengers = flight.passenger_count.filter(|&n| n > 0).map(|n| n as u32);
+            flight.distance_km.map(|km| (per_passenger_co2(performance, km, passengers), "estimated"))
+        }),
+    }
+}
+
+fn methodology(settings: &GhgSettings, excluded_personal_flights: usize) -> Vec<String> {
+    let mut notes = vec![
+        "Prepared following the GHG Protocol Corporate Standard and Scope 3 Standard.".to_string(),
+        "Scope 1: CO2 from fuel burnt in aircraft the organisation owns or operates, whole aircraft.".to_string(),
+        "Scope 3 category 6 (business travel): chartered aircraft count whole; commercial flights count the traveller's seat share.".to_string(),
+        "CO2 from recorded figures where available, else estimated from great-circle distance and aircraft fuel burn. CH4 and N2O are not estimated.".to_string(),
+    ];
+    notes.push(match (settings.rf_multiplier > 1.0, settings.rf_in_scope_1) {
+        (false, _) => "No radiative forcing uplift applied; CO2e equals CO2.".to_string(),
+        (true, false) => format!("CO2e applies a radiative forcing multiplier of {} to Scope 3; Scope 1 is combustion CO2.", settings.rf_multiplier),
+        (true, true) => format!("CO2e applies a radiative forcing multiplier of {} to Scope 1 and Scope 3.", settings.rf_multiplier),
+    });
+    if excluded_personal_flights > 0 {
+        notes.push(format!("{} personal trip(s) excluded from business travel.", excluded_personal_flights));
+    }
+    notes
+}
+
+/// Emissions by scope for flights between `start_date` and `end_date` (YYYY-MM-DD, inclusive)
+pub fn ghg_report(
+    db: &Database,
+    user_id: &str,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    settings: &GhgSettings,
+) -> Result<GhgReport> {
+    let mut categories: Vec<GhgCategory> = Operation::ALL
+        .into_iter()
+        .map(|operation| GhgCategory {
+            scope: operation.scope(),
+            operation,
+            label: operation.label().to_string(),
+            flights: 0,
+            distance_km: 0.0,
+            co2_kg: 0.0,
+            co2e_kg: 0.0,
+        })
+        .collect();
+    let mut performances: HashMap<(Option<String>, Option<String>), AircraftPerformance> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut excluded_personal_flights = 0;
+    let mut unquantified_flights = Vec::new();
+
+    for flight in flights_in_range(&db.conn, user_id, start_date, end_date)? {
+        let (operation, operation_source) = match flight.operation_tag.as_deref().and_then(Operation::parse) {
+            Some(operation) => (operation, "flight"),
+            None => match settings.aircraft_operation(flight.registration.as_deref()) {
+                Some(operation) => (operation, "aircraft"),
+                None => (Operation::Commercial, "default"),
+            },
+        };
+        if settings.exclude_personal && operation.scope() == 3 && flight.purpose.as_deref() == Some(PERSONAL_PURPOSE) {
+            excluded_personal_flights += 1;
+            continue;
+        }
+
+        let performance = performances
+            .entry((flight.registration.clone(), flight.aircraft_type_id.clone()))
+            .or_insert_with(|| {
+                let aircraft = AircraftRef {
+                    registration: flight.registration.as_deref(),
+                    aircraft_type_id: flight.aircraft_type_id.as_deref(),
+                };
+                performance_for(db, aircraft)
+            });
+        let Some((co2_kg, basis)) = flight_co2(&flight, operation, performance) else {
+            unquantified_flights.push(flight.id);
+            continue;
+        };
+        let co2e_kg = if settings.rf_applies(operation) { co2_kg * settings.rf_multiplier } else { co2_kg };
+
+        let category = categories.iter_mut().find(|c| c.operation == operation).expect("every operation has a category");
+        category.flights += 1;
+        category.distance_km += flight.distance_km.unwrap_or(0.0);
+        category.co2_kg += co2_kg;
+        category.co2e_kg += co2e_kg;
+
+        lines.push(GhgFlightLine {
+            flight_id: flight.id,
+            date: flight.date,
+            departure_airport: flight.departure_airport,
+            arrival_airport: flight.arrival_airport,
+            aircraft_registration: flight.registration,
+            operation,
+            operation_source: operation_source.to_string(),
+            scope: operation.scope(),
+            distance_km: flight.distance_km,
+            basis: basis.to_string(),
+            co2_kg,
+            co2e_kg,
+        });
+    }
+
+    let scope_total = |scope: u8, co2e: bool| -> f64 {
+        categories
+            .iter()
+            .filter(|c| c.scope == scope)
+            .map(|c| if co2e { c.co2e_kg } else { c.co2_kg })
+            .sum()
+    };
+    let (scope_1_co2_kg, scope_1_co2e_kg) = (scope_total(1, false), scope_total(1, true));
+    let (scope_3_co2_kg, scope_3_co2e_kg) = (scope_total(3, false), scope_total(3, true));
+
+    Ok(GhgReport {
+        start_date: start_date.map(str::to_string),
+        end_date: end_date.map(str::to_string),
+        settings: settings.clone(),
+        methodology: methodology(settings, excluded_personal_flights),
+        categories,
+        scope_1_co2_kg,
+        scope_1_co2e_kg,
+        scope_3_co2_kg,
+        scope_3_co2e_kg,
+        total_co2_kg: scope_1_co2_kg + scope_3_co2_kg,
+        total_co2e_kg: scope_1_co2e_kg + scope_3_co2e_kg,
+        flights: lines,
+        excluded_personal_flights,
+        unquantified_flights,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ghg_report_scopes() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime,
+                                      aircraft_registration, distance_km, carbon_emissions_kg, per_passenger_co2_kg) VALUES
+                     ('f1', 'u1', 'EGLL', 'KJFK', '2025-02-01T09:00:00', NULL, 5540, 90000, 600),
+                     ('f2', 'u1', 'EGKB', 'LFPB', '2025-02-03T09:00:00', 'G-ABCD', 340, 2000, NULL),
+                     ('f3', 'u1', 'LFPB', 'EGKB', '2025-02-04T09:00:00', 'g-abcd', 340, 2000, NULL),
+                     ('f4', 'u1', 'EGLL', 'LEMD', '2025-02-10T09:00:00', NULL, 1250, 20000, 150),
+                     ('f5', 'u1', 'EGLL', 'EDDF', '2025-02-12T09:00:00', NULL, NULL, NULL, NULL),
+                     ('f6', 'u1', 'EGLL', 'EDDF', '2025-03-12T09:00:00', NULL, 650, 9000, 80);
+                 INSERT INTO flight_tags (flight_id, tag) VALUES ('f4', 'purpose:personal');",
+            )
+            .unwrap();
+        set_flight_operation(&db.conn, "f3", Some(Operation::Chartered)).unwrap();
+        assert_eq!(flight_operation(&db.conn, "f3").unwrap(), Some(Operation::Chartered));
+
+        let settings = GhgSettings { own_aircraft: vec!["g-abcd".to_string()], ..Default::default() }.normalized();
+        let report = ghg_report(&db, "u1", Some("2025-02-01"), Some("2025-02-28"), &settings).unwrap();
+
+        // f2 is own by its registration; the tag on f3 overrides it; f1 is a commercial seat
+        let lines: Vec<_> = report
+            .flights
+            .iter()
+            .map(|l| (l.flight_id.as_str(), l.operation, l.operation_source.as_str(), l.scope, l.basis.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("f1", Operation::Commercial, "default", 3, "passenger_share"),
+                ("f2", Operation::Own, "aircraft", 1, "flight_total"),
+                ("f3", Operation::Chartered, "flight", 3, "flight_total"),
+            ]
+        );
+        assert_eq!(report.excluded_personal_flights, 1);
+        assert_eq!(report.unquantified_flights, ["f5"]);
+
+        // Radiative forcing on Scope 3 only by default
+        assert_eq!((report.scope_1_co2_kg, report.scope_1_co2e_kg), (2000.0, 2000.0));
+        assert!((report.scope_3_co2_kg - 2600.0).abs() < 1e-9);
+        assert!((report.scope_3_co2e_kg - 2600.0 * 1.7).abs() < 1e-9);
+        assert_eq!(report.categories.iter().map(|c| c.flights).collect::<Vec<_>>(), [1, 1, 1]);
+
+        // Personal trips in Scope 3 when asked; RF on Scope 1 when asked
+        let settings = GhgSettings { exclude_personal: false, rf_in_scope_1: true, rf_multiplier: 2.0, ..settings };
+        let report = ghg_report(&db, "u1", None, None, &settings).unwrap();
+        assert_eq!(report.flights.len(), 5);
+        assert_eq!(report.scope_1_co2e_kg, 4000.0);
+        assert!((report.total_co2e_kg - 2.0 * (2000.0 + 2600.0 + 150.0 + 80.0)).abs() < 1e-9);
+
+        let bad = GhgSettings { rf_multiplier: 0.5, chartered_aircraft: vec!["G-ABCD".to_string()], ..settings };
+        let fields: Vec<_> = bad.invalid_fields().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, ["rf_multiplier", "chartered_aircraft"]);
+    }
+}
@@ -5,10 +5,13 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::error::CommandError;
+
 const GROK_RESPONSES_URL: &str = "https://api.x.ai/v1/responses";
 const GROK_CHAT_URL: &str = "https://api.x.ai/v1/chat/completions";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct GrokSearchRequest {
     pub flight_id: String,
     pub query: String,
@@ -21,6 +24,9 @@ pub struct GrokAnalysisResult {
     pub key_findings: Vec<Finding>,
     pub sources: Vec<Source>,
     pub confidence_score: f32,
+    /// How the web search results were deduplicated and trimmed before analysis
+    #[serde(default)]
+    pub search_stats: crate::web_search::SearchResultStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +44,7 @@ pub struct Source {
 }
 
 /// Analyze flight context using Grok with advanced search
+#[allow(clippy::too_many_arguments)]
 pub async fn analyze_flight_with_grok(
     flight_route: &str,
     flight_date: &str,
@@ -46,33 +53,24 @@ pub async fn analyze_flight_with_grok(
     web_search_results: Vec<String>,
     api_key: &str,
     model_name: &str, // "grok-4-fast-non-reasoning", "grok-4-fast-reasoning", or "grok-code-fast-1"
+    prompt_template: Option<&str>, // None uses the built-in Grok default
 ) -> Result<GrokAnalysisResult> {
     let client = reqwest::Client::new();
 
     // Use the specified model (validated by caller)
     let model = model_name;
 
-    // Build comprehensive prompt
-    let mut prompt_parts = vec![
-        format!("Analyze the following flight and travel context:"),
-        format!("Route: {}", flight_route),
-        format!("Date: {}", flight_date),
-    ];
-
-    if !passenger_names.is_empty() {
-        prompt_parts.push(format!("Passengers: {}", passenger_names.join(", ")));
-    }
-
-    if !research_topics.is_empty() {
-        prompt_parts.push(format!("\nResearch topics: {}", research_topics.join(", ")));
-    }
-
-    if !web_search_results.is_empty() {
-        prompt_parts.push("\nWeb search results:".to_string());
-        for (idx, result) in web_search_results.iter().enumerate() {
-            prompt_parts.push(format!("\n[Source {}]: {}", idx + 1, result));
-        }
-    }
+    // Build the prompt from the template, then the fixed response format
+    let mut prompt_parts = vec![crate::prompt_templates::render(
+        prompt_template.unwrap_or(crate::prompt_templates::GROK_DEFAULT),
+        &crate::prompt_templates::PromptContext {
+            route: flight_route,
+            date: flight_date,
+            passengers: &passenger_names,
+            topics: &research_topics,
+            sources: &web_search_results,
+        },
+    )];
 
     prompt_parts.push("\nProvide analysis in JSON format with this structure:".to_string());
     prompt_parts.push(
@@ -130,8 +128,7 @@ pub async fn analyze_flight_with_grok(
         .await?;
 
     if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(anyhow::anyhow!("Grok API error: {}", error_text));
+        return Err(CommandError::from_response("Grok", response).await.into());
     }
 
     let response_json: serde_json::Value = response.json().await?;
@@ -167,6 +164,7 @@ pub async fn analyze_flight_with_grok(
 
 /// Research flight using Grok's built-in web search capabilities
 /// This uses Grok's native search integration (if available)
+#[allow(dead_code)]
 pub async fn research_with_grok_search(
     query: &str,
     api_key: &str,
@@ -226,9 +224,53 @@ pub struct MultiProviderAnalysis {
     pub deepseek_analysis: Option<crate::deepseek::ResearchResult>,
     pub consensus_summary: String,
     pub disagreements: Vec<String>,
+    /// How the web search results were deduplicated and trimmed before analysis
+    #[serde(default)]
+    pub search_stats: crate::web_search::SearchResultStats,
+    /// How long each stage took, including providers that failed
+    #[serde(default)]
+    pub timings: Vec<ProviderTiming>,
+}
+
+/// Wall-clock time for one provider (or the web search stage) in a multi-provider run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderTiming {
+    pub provider: String,
+    pub duration_ms: u64,
+    /// Set when the provider failed; the other provider's result is still returned
+    pub error: Option<String>,
+}
+
+impl ProviderTiming {
+    pub fn new<T>(provider: &str, started: std::time::Instant, result: &Result<T>) -> Self {
+        Self {
+            provider: provider.to_string(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Time a provider call, or skip it when there is no API key
+async fn timed<T>(
+    provider: &str,
+    call: Option<impl std::future::Future<Output = Result<T>>>,
+) -> Option<(Result<T>, ProviderTiming)> {
+    let call = call?;
+    let started = std::time::Instant::now();
+    let result = call.await;
+    let timing = ProviderTiming::new(provider, started, &result);
+    if let Err(e) = &result {
+        tracing::warn!("{} analysis failed: {}", provider, e);
+    }
+    Some((result, timing))
 }
 
 /// Analyze flight with multiple AI providers for validation
+///
+/// Grok and DeepSeek run concurrently. A provider that fails is reported in `timings`
+/// and left out; the command only fails when every configured provider does.
+#[allow(clippy::too_many_arguments)]
 pub async fn multi_provider_analysis(
     flight_route: &str,
     flight_date: &str,
@@ -237,13 +279,10 @@ pub async fn multi_provider_analysis(
     web_search_results: Vec<String>,
     grok_api_key: Option<&str>,
     deepseek_api_key: Option<&str>,
+    prompt_template: Option<&str>, // Used for both providers; None uses each one's default
 ) -> Result<MultiProviderAnalysis> {
-    let mut grok_result = None;
-    let mut deepseek_result = None;
-
-    // Run Grok analysis if API key provided
-    if let Some(api_key) = grok_api_key {
-        match analyze_flight_with_grok(
+    let grok_call = grok_api_key.map(|api_key| {
+        analyze_flight_with_grok(
             flight_route,
             flight_date,
             passenger_names.clone(),
@@ -251,16 +290,11 @@ pub async fn multi_provider_analysis(
             web_search_results.clone(),
             api_key,
             "grok-4-fast-reasoning", // Use advanced model for comparison
+            prompt_template,
         )
-        .await
-        {
-            Ok(result) => grok_result = Some(result),
-            Err(e) => eprintln!("Grok analysis failed: {}", e),
-        }
-    }
+    });
 
-    // Run DeepSeek analysis if API key provided
-    if let Some(api_key) = deepseek_api_key {
+    let deepseek_call = deepseek_api_key.map(|api_key| {
         let deepseek_request = crate::deepseek::ResearchRequest {
             date: flight_date.to_string(),
             location: flight_route.to_string(),
@@ -269,18 +303,35 @@ pub async fn multi_provider_analysis(
             research_events: research_topics.contains(&"events".to_string()),
             research_weather: research_topics.contains(&"weather".to_string()),
             research_passengers: !passenger_names.is_empty(),
+            prompt_template: prompt_template.map(str::to_string),
         };
+        crate::deepseek::research_flight_context(deepseek_request, web_search_results.clone(), api_key)
+    });
 
-        match crate::deepseek::research_flight_context(
-            deepseek_request,
-            web_search_results.clone(),
-            api_key,
-        )
-        .await
-        {
-            Ok(result) => deepseek_result = Some(result),
-            Err(e) => eprintln!("DeepSeek analysis failed: {}", e),
+    let (grok, deepseek) =
+        futures::future::join(timed("Grok", grok_call), timed("DeepSeek", deepseek_call)).await;
+
+    let mut timings = Vec::new();
+    let mut errors = Vec::new();
+    let mut grok_result = None;
+    let mut deepseek_result = None;
+    if let Some((result, timing)) = grok {
+        match result {
+            Ok(r) => grok_result = Some(r),
+            Err(e) => errors.push(format!("Grok: {}", e)),
+        }
+        timings.push(timing);
+    }
+    if let Some((result, timing)) = deepseek {
+        match result {
+            Ok(r) => deepseek_result = Some(r),
+            Err(e) => errors.push(format!("DeepSeek: {}", e)),
         }
+        timings.push(timing);
+    }
+
+    if grok_result.is_none() && deepseek_result.is_none() && !errors.is_empty() {
+        anyhow::bail!("All AI providers failed: {}", errors.join("; "));
     }
 
     // Build consensus summary
@@ -294,12 +345,20 @@ pub async fn multi_provider_analysis(
     } else {
         "No AI analysis available - missing API keys.".to_string()
     };
+    let failed: Vec<&str> = timings.iter().filter(|t| t.error.is_some()).map(|t| t.provider.as_str()).collect();
+    let consensus_summary = if failed.is_empty() {
+        consensus_summary
+    } else {
+        format!("{} {} failed; see timings for details.", consensus_summary, failed.join(" and "))
+    };
 
     Ok(MultiProviderAnalysis {
         grok_analysis: grok_result,
         deepseek_analysis: deepseek_result,
         consensus_summary,
         disagreements: Vec::new(), // TODO: Implement disagreement detection
+        search_stats: Default::default(),
+        timings,
     })
 }
 
@@ -360,6 +419,7 @@ pub async fn chat_with_grok(
         key_findings: Vec::new(),
         sources: Vec::new(),
         confidence_score: 0.85,
+        search_stats: Default::default(),
     })
 }
 
@@ -368,6 +428,7 @@ pub async fn chat_with_grok(
 // ============================================================================
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 struct GrokResponsesRequest {
     model: String,
     input: Vec<GrokMessage>,
@@ -375,12 +436,14 @@ struct GrokResponsesRequest {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 struct GrokMessage {
     role: String,
     content: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 struct GrokTool {
     #[serde(rename = "type")]
     tool_type: String,
@@ -389,6 +452,7 @@ struct GrokTool {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 struct GrokToolFilters {
     #[serde(skip_serializing_if = "Option::is_none")]
     allowed_domains: Option<Vec<String>>,
@@ -431,7 +495,7 @@ Your mission:
 
 Please provide your findings in a detailed investigation report with:
 - A comprehensive summary (2-3 paragraphs) analyzing all evidence found
-- Key findings with supporting evidence from your searches
+- Key findings as a bulleted list, each ending with the URL(s) of the source(s) that support it
 - Assessment of the strength of evidence (corroboration score 0.0-1.0)
 - List of all sources you examined
 
@@ -488,9 +552,6 @@ Be thorough, objective, and note when evidence is absent, weak, or contradictory
     // Parse the investigation result from Grok's response
     let summary = grok_response.output.clone();
 
-    // Estimate corroboration score based on response content
-    let corroboration_score = estimate_corroboration_score(&summary, &grok_response.citations);
-
     // Build sources from citations
     let sources: Vec<crate::models::InvestigationSource> = grok_response
         .citations
@@ -505,6 +566,10 @@ Be thorough, objective, and note when evidence is absent, weak, or contradictory
         })
         .collect();
 
+    // Score from independent sources per finding, as for the other providers
+    let claims = claims_from_report(&summary, &sources);
+    let assessment = crate::investigation::score_corroboration(&claims, &sources);
+
     let elapsed_ms = start_time.elapsed().as_millis() as i32;
 
     Ok(crate::models::InvestigationResult {
@@ -512,12 +577,33 @@ Be thorough, objective, and note when evidence is absent, weak, or contradictory
         status: "completed".to_string(),
         ai_summary: summary,
         sources,
-        corroboration_score,
+        corroboration_score: assessment.score,
         generated_queries: vec!["Grok agentic search (automated query generation)".to_string()],
         processing_time_ms: elapsed_ms,
+        claims: assessment.claims,
+        score_explanation: assessment.explanation,
     })
 }
 
+/// Key findings of the report as claims, each tied to the cited sources whose URL it mentions
+fn claims_from_report(
+    summary: &str,
+    sources: &[crate::models::InvestigationSource],
+) -> Vec<crate::investigation::ClaimAnalysis> {
+    extract_key_findings(summary)
+        .into_iter()
+        .map(|finding| crate::investigation::ClaimAnalysis {
+            source_indices: sources
+                .iter()
+                .enumerate()
+                .filter(|(_, source)| finding.contains(source.url.as_str()))
+                .map(|(idx, _)| idx)
+                .collect(),
+            claim: finding,
+        })
+        .collect()
+}
+
 /// Extract key findings from Grok's summary using simple heuristics
 fn extract_key_findings(summary: &str) -> Vec<String> {
     let mut findings = Vec::new();
@@ -525,8 +611,8 @@ fn extract_key_findings(summary: &str) -> Vec<String> {
     // Look for numbered lists or bullet points
     for line in summary.lines() {
         let trimmed = line.trim();
-        if trimmed.starts_with("- ") || trimmed.starts_with("• ") {
-            findings.push(trimmed[2..].to_string());
+        if let Some(finding) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("• ")) {
+            findings.push(finding.to_string());
         } else if trimmed.len() > 2 {
             // Check for "1.", "2.", etc.
             if let Some(first_char) = trimmed.chars().next() {
@@ -550,44 +636,35 @@ fn extract_key_findings(summary: &str) -> Vec<String> {
     findings
 }
 
-/// Estimate corroboration score based on response content and citations
-fn estimate_corroboration_score(summary: &str, citations: &[String]) -> f64 {
-    let summary_lower = summary.to_lowercase();
-
-    // Check for strong evidence indicators
-    let strong_indicators = [
-        "confirmed",
-        "verified",
-        "documented",
-        "official record",
-        "according to",
-        "published",
-    ];
-    let weak_indicators = [
-        "no evidence",
-        "could not find",
-        "unconfirmed",
-        "possibly",
-        "might",
-        "unclear",
-    ];
-
-    let strong_count = strong_indicators
-        .iter()
-        .filter(|&indicator| summary_lower.contains(indicator))
-        .count();
-
-    let weak_count = weak_indicators
-        .iter()
-        .filter(|&indicator| summary_lower.contains(indicator))
-        .count();
-
-    // Base score on citations count
-    let citation_score = (citations.len() as f64 * 0.1).min(0.5);
-
-    // Adjust based on indicators
-    let indicator_adjustment = (strong_count as f64 * 0.1) - (weak_count as f64 * 0.15);
-
-    // Final score between 0.0 and 1.0
-    (citation_score + indicator_adjustment + 0.3).clamp(0.0, 1.0)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(url: &str) -> crate::models::InvestigationSource {
+        crate::models::InvestigationSource {
+            title: String::new(),
+            url: url.to_string(),
+            excerpt: String::new(),
+            relevance_score: 0.8,
+            publication_date: None,
+        }
+    }
+
+    #[test]
+    fn test_findings_are_scored_by_their_cited_sources() {
+        let sources = [source("https://news.example.com/a"), source("https://www.other.org/b"), source("https://news.example.com/c")];
+        let summary = "Report\n\
+            - Seen at the gala https://news.example.com/a https://www.other.org/b\n\
+            • Listed as a speaker https://news.example.com/a https://news.example.com/c\n\
+            - Nothing on social media";
+
+        let claims = claims_from_report(summary, &sources);
+        let cited: Vec<_> = claims.iter().map(|c| c.source_indices.clone()).collect();
+        assert_eq!(cited, [vec![0, 1], vec![0, 2], vec![]]);
+
+        // Two sites agree on the first finding; the second is one site twice
+        let assessment = crate::investigation::score_corroboration(&claims, &sources);
+        assert_eq!(assessment.claims.len(), 2);
+        assert_eq!(assessment.score, 0.75);
+    }
 }
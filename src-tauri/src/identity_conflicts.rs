@@ -0,0 +1,322 @@
+// Identity timeline conflicts ("same person, two places")
+// A canonical passenger is one person, so their flights must form a timeline they could have
+// flown: each flight leaves after the previous one lands, and if it leaves from a different
+// airport there must be time to get there. A sequence that breaks this points to a wrong
+// alias merge or to a flight with the wrong date, times or airports.
+//
+// Flights are ordered by UTC departure using their airports' timezones. Flights whose times
+// cannot be placed in UTC, or that only carry a date, are counted as skipped.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::batch_calculations::route_distance;
+use crate::commands::flights::build_flight_times;
+use crate::commands::identity_fusion::names_in_notes;
+use crate::database::Database;
+use crate::flight_sanity::{raise_anomaly, AircraftClass, SanityIssue};
+use crate::timezone::parse_local_datetime;
+
+pub const ANOMALY_TYPE: &str = "identity_conflict";
+
+/// The next flight leaves before the previous one lands
+pub const OVERLAP: &str = "overlap";
+/// The next flight leaves from another airport sooner than anyone could get there
+pub const INSUFFICIENT_TRAVEL_TIME: &str = "insufficient_travel_time";
+
+/// Recorded times are rarely exact; gaps this close to the limit are not flagged
+const SLACK_MINUTES: i64 = 15;
+
+/// A flight in a passenger's timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineFlight {
+    pub flight_id: String,
+    pub flight_number: Option<String>,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    pub departure_utc: String,
+    pub arrival_utc: String,
+    /// True when the arrival was estimated from the duration, or is unknown and taken as the departure
+    pub arrival_estimated: bool,
+    /// The passenger's aliases named in the flight's notes; the ones that linked it
+    pub aliases: Vec<String>,
+    /// Already marked as erroneous with an open identity_conflict anomaly
+    pub is_flagged: bool,
+    #[serde(skip)]
+    departure: NaiveDateTime,
+    #[serde(skip)]
+    arrival: NaiveDateTime,
+}
+
+/// Two flights the same person cannot both have taken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityConflict {
+    pub kind: String,
+    pub earlier: TimelineFlight,
+    pub later: TimelineFlight,
+    /// Minutes from the earlier flight's arrival to the later one's departure; negative when they overlap
+    pub gap_minutes: i64,
+    /// Least time needed between the two airports
+    pub required_minutes: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdentityConflictReport {
+    pub passenger_id: String,
+    pub canonical_name: String,
+    pub flights_checked: usize,
+    pub skipped_no_time: usize,
+    pub conflicts: Vec<IdentityConflict>,
+}
+
+/// Least minutes to get between two airports, flying direct at the fastest jet ground speed
+fn required_minutes(db: &Database, from: &str, to: &str) -> Option<i64> {
+    if from.eq_ignore_ascii_case(to) {
+        return Some(0);
+    }
+    let (_, km) = route_distance(db, from, to)?;
+    Some((km / AircraftClass::Jet.max_ground_speed_kmh() * 60.0).ceil() as i64)
+}
+
+/// Compare a flight against the one the passenger was last on
+fn compare(db: &Database, earlier: &TimelineFlight, later: &TimelineFlight) -> Option<IdentityConflict> {
+    let gap = (later.departure - earlier.arrival).num_minutes();
+    let route = |f: &TimelineFlight| {
+        format!(
+            "{} {} to {}",
+            f.flight_number.as_deref().unwrap_or("flight"),
+            f.departure_airport,
+            f.arrival_airport
+        )
+    };
+    if gap < -SLACK_MINUTES {
+        return Some(IdentityConflict {
+            kind: OVERLAP.to_string(),
+            gap_minutes: gap,
+            required_minutes: 0,
+            description: format!(
+                "{} departs at {}, {} min before {} lands at {}",
+                route(later),
+                later.departure_utc,
+                -gap,
+                route(earlier),
+                earlier.arrival_utc
+            ),
+            earlier: earlier.clone(),
+            later: later.clone(),
+        });
+    }
+    let required = required_minutes(db, &earlier.arrival_airport, &later.departure_airport)?;
+    (gap + SLACK_MINUTES < required).then(|| IdentityConflict {
+        kind: INSUFFICIENT_TRAVEL_TIME.to_string(),
+        gap_minutes: gap,
+        required_minutes: required,
+        description: format!(
+            "{} min after landing at {} the passenger departs {}, at least {} min away",
+            gap, earlier.arrival_airport, later.departure_airport, required
+        ),
+        earlier: earlier.clone(),
+        later: later.clone(),
+    })
+}
+
+/// Order a passenger's flights and find the sequences they cannot have flown
+pub fn detect(db: &Database, passenger_id: &str) -> Result<IdentityConflictReport> {
+    let canonical_name: String = db
+        .conn
+        .query_row("SELECT canonical_name FROM passengers WHERE id = ?1", [passenger_id], |row| row.get(0))
+        .map_err(|e| anyhow!("Passenger not found: {}", e))?;
+
+    let aliases: HashSet<String> = {
+        let mut stmt = db.conn.prepare("SELECT raw_name FROM passenger_aliases WHERE passenger_id = ?1")?;
+        let rows = stmt.query_map([passenger_id], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let flagged: HashSet<String> = {
+        let mut stmt = db.conn.prepare(
+            "SELECT flight_id FROM flight_anomalies WHERE anomaly_type = ?1 AND is_resolved = 0",
+        )?;
+        let rows = stmt.query_map([ANOMALY_TYPE], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let flight_ids: Vec<String> = {
+        // Deleted flights are in the trash, not in the passenger's timeline
+        let mut stmt = db.conn.prepare(
+            "SELECT fp.flight_id FROM flight_passengers fp
+             JOIN flights f ON f.id = fp.flight_id AND f.deleted_at IS NULL
+             WHERE fp.passenger_id = ?1",
+        )?;
+        let rows = stmt.query_map([passenger_id], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let mut report = IdentityConflictReport {
+        passenger_id: passenger_id.to_string(),
+        canonical_name,
+        ..Default::default()
+    };
+    let mut timezones: HashMap<String, Option<String>> = HashMap::new();
+    let mut timezone = |code: &str| -> Result<Option<String>> {
+        if let Some(tz) = timezones.get(code) {
+            return Ok(tz.clone());
+        }
+        let tz = db.get_airport_by_code(code)?.and_then(|a| a.timezone);
+        timezones.insert(code.to_string(), tz.clone());
+        Ok(tz)
+    };
+
+    let mut timeline = Vec::with_capacity(flight_ids.len());
+    for flight_id in &flight_ids {
+        let Some(flight) = db.get_flight(flight_id)? else {
+            continue;
+        };
+        // A bare date would put every flight of the day at midnight
+        if flight.departure_datetime.trim().len() <= 10 {
+            report.skipped_no_time += 1;
+            continue;
+        }
+        let departure_tz = timezone(&flight.departure_airport)?;
+        let arrival_tz = timezone(&flight.arrival_airport)?;
+        let mut flight_aliases: Vec<String> = flight
+            .notes
+            .as_deref()
+            .map(|notes| names_in_notes(notes).into_iter().filter(|name| aliases.contains(name)).collect())
+            .unwrap_or_default();
+        flight_aliases.dedup();
+        let flight_number = flight.flight_number.clone();
+        let times = build_flight_times(flight, departure_tz, arrival_tz);
+
+        let parse = |utc: &Option<String>| utc.as_deref().and_then(parse_local_datetime);
+        let Some(departure) = parse(&times.departure.utc_time) else {
+            report.skipped_no_time += 1;
+            continue;
+        };
+        let arrival = parse(&times.arrival.utc_time).filter(|a| *a >= departure);
+        timeline.push(TimelineFlight {
+            flight_id: flight_id.clone(),
+            flight_number,
+            departure_airport: times.departure.airport_code,
+            arrival_airport: times.arrival.airport_code,
+            departure_utc: times.departure.utc_time.unwrap_or_default(),
+            arrival_utc: match arrival {
+                Some(_) => times.arrival.utc_time.unwrap_or_default(),
+                None => format!("{}Z", departure.format("%Y-%m-%dT%H:%M:%S")),
+            },
+            arrival_estimated: arrival.is_none() || times.arrival.is_estimated,
+            aliases: flight_aliases,
+            is_flagged: flagged.contains(flight_id),
+            departure,
+            arrival: arrival.unwrap_or(departure),
+        });
+    }
+    timeline.sort_by(|a, b| a.departure.cmp(&b.departure).then_with(|| a.flight_id.cmp(&b.flight_id)));
+    report.flights_checked = timeline.len();
+
+    // Compare each flight with the earlier flight that lands last: where the passenger is
+    let mut last: Option<&TimelineFlight> = None;
+    for flight in &timeline {
+        if let Some(previous) = last {
+            if let Some(conflict) = compare(db, previous, flight) {
+                report.conflicts.push(conflict);
+            }
+        }
+        if last.is_none_or(|previous| flight.arrival >= previous.arrival) {
+            last = Some(flight);
+        }
+    }
+    Ok(report)
+}
+
+/// Mark flights from a conflict as erroneous, raising an identity_conflict anomaly on each
+/// (or updating its open one); returns the anomaly ids
+pub fn flag_flights(db: &Database, passenger_id: &str, flight_ids: &[String]) -> Result<Vec<String>> {
+    let canonical_name: String = db
+        .conn
+        .query_row("SELECT canonical_name FROM passengers WHERE id = ?1", [passenger_id], |row| row.get(0))
+        .map_err(|e| anyhow!("Passenger not found: {}", e))?;
+    let issue = SanityIssue::new(
+        ANOMALY_TYPE,
+        "error",
+        format!("Cannot be placed in {}'s timeline alongside their other flights", canonical_name),
+        format!(
+            "Correct the flight's date, times or airports, or remove {} from its passengers",
+            canonical_name
+        ),
+    );
+
+    let tx = db.conn.unchecked_transaction()?;
+    let mut anomaly_ids = Vec::with_capacity(flight_ids.len());
+    for flight_id in flight_ids {
+        let exists: i64 = tx.query_row("SELECT COUNT(*) FROM flights WHERE id = ?1 AND deleted_at IS NULL", params![flight_id], |row| row.get(0))?;
+        if exists == 0 {
+            return Err(anyhow!("Flight not found: {}", flight_id));
+        }
+        anomaly_ids.push(raise_anomaly(&tx, flight_id, &issue)?);
+    }
+    tx.commit()?;
+    Ok(anomaly_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_detect_identity_conflicts() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, iata_code, name, latitude, longitude, timezone) VALUES
+                     ('JFK', 'JFK', 'John F Kennedy', 40.6413, -73.7781, 'America/New_York'),
+                     ('LAX', 'LAX', 'Los Angeles', 33.9416, -118.4085, 'America/Los_Angeles'),
+                     ('MIA', 'MIA', 'Miami', 25.7959, -80.2870, 'America/New_York'),
+                     ('LHR', 'LHR', 'Heathrow', 51.4700, -0.4543, 'Europe/London');
+                 INSERT INTO flights (id, user_id, flight_number, departure_airport, arrival_airport, departure_datetime, arrival_datetime, notes) VALUES
+                     ('a', 'u1', 'AA1', 'JFK', 'LAX', '2025-03-01T08:00:00', '2025-03-01T11:00:00', 'Passengers: JOHN SMITH'),
+                     -- Leaves Miami an hour after landing in Los Angeles
+                     ('b', 'u1', 'AA2', 'MIA', 'JFK', '2025-03-01T15:00:00', '2025-03-01T18:00:00', 'Passengers: J SMITH'),
+                     -- Leaves New York while still in the air from Miami
+                     ('c', 'u1', 'BA1', 'JFK', 'LHR', '2025-03-01T17:00:00', '2025-03-02T05:00:00', 'Passengers: JOHN SMITH'),
+                     ('d', 'u1', 'BA2', 'LHR', 'JFK', '2025-03-05T10:00:00', '2025-03-05T13:00:00', 'Passengers: JOHN SMITH'),
+                     ('e', 'u1', NULL, 'JFK', 'MIA', '2025-03-06', NULL, 'Passengers: JOHN SMITH');
+                 INSERT INTO passengers (id, canonical_name) VALUES ('p1', 'JOHN SMITH');
+                 INSERT INTO passenger_aliases (id, passenger_id, raw_name) VALUES
+                     ('a1', 'p1', 'JOHN SMITH'), ('a2', 'p1', 'J SMITH');
+                 INSERT INTO flight_passengers (flight_id, passenger_id) VALUES
+                     ('a', 'p1'), ('b', 'p1'), ('c', 'p1'), ('d', 'p1'), ('e', 'p1');",
+            )
+            .unwrap();
+
+        let report = detect(&db, "p1").unwrap();
+        assert_eq!((report.flights_checked, report.skipped_no_time), (4, 1));
+        let pairs: Vec<(&str, &str, &str)> = report
+            .conflicts
+            .iter()
+            .map(|c| (c.kind.as_str(), c.earlier.flight_id.as_str(), c.later.flight_id.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![(INSUFFICIENT_TRAVEL_TIME, "a", "b"), (OVERLAP, "b", "c")]);
+        // 11:00 PST is 19:00 UTC; 15:00 EST is 20:00 UTC
+        assert_eq!(report.conflicts[0].gap_minutes, 60);
+        assert!(report.conflicts[0].required_minutes > 60);
+        assert_eq!(report.conflicts[0].later.aliases, vec!["J SMITH".to_string()]);
+
+        let ids = flag_flights(&db, "p1", &["b".to_string()]).unwrap();
+        assert_eq!(ids, flag_flights(&db, "p1", &["b".to_string()]).unwrap());
+        let report = detect(&db, "p1").unwrap();
+        assert!(report.conflicts[1].earlier.is_flagged);
+        assert!(flag_flights(&db, "p1", &["missing".to_string()]).is_err());
+
+        // Deleting flight b clears both conflicts it was part of
+        db.conn.execute("UPDATE flights SET deleted_at = datetime('now') WHERE id = 'b'", []).unwrap();
+        let report = detect(&db, "p1").unwrap();
+        assert_eq!(report.flights_checked, 3);
+        assert!(report.conflicts.is_empty());
+    }
+}
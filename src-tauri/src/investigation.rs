@@ -1,7 +1,7 @@
 // Investigation Engine - AI-Powered Cross-Referencing System
 // Generates search queries, executes searches, and synthesizes evidence
 
-use crate::models::{InvestigationResult, InvestigationSource};
+use crate::models::{InvestigationClaim, InvestigationResult, InvestigationSource};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
@@ -51,6 +51,7 @@ Analyze these search results and provide:
    - 0.4-0.6: Circumstantial or indirect evidence
    - 0.7-0.9: Strong corroborating evidence from multiple sources
    - 1.0: Direct, verified evidence from official records
+5. List each distinct factual claim and the index of every source that states it
 
 Format your response as JSON:
 {
@@ -64,12 +65,19 @@ Format your response as JSON:
       "credibility": "official|news|social|unverified",
       "key_quote": "Extracted quote or fact"
     }
+  ],
+  "claims": [
+    {
+      "claim": "Short factual statement",
+      "source_indices": [0, 2]
+    }
   ]
 }
 
 Be objective and factual. Note when evidence is absent, weak, or contradictory."#;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 struct QueryGenerationResponse {
     queries: Vec<String>,
 }
@@ -80,6 +88,8 @@ pub(crate) struct SynthesisResponse {
     key_findings: Vec<String>,
     corroboration_score: f64,
     sources_analysis: Vec<SourceAnalysis>,
+    #[serde(default)]
+    claims: Vec<ClaimAnalysis>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -90,6 +100,22 @@ struct SourceAnalysis {
     key_quote: String,
 }
 
+/// A claim from the synthesis and the search results that state it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimAnalysis {
+    pub claim: String,
+    #[serde(default)]
+    pub source_indices: Vec<usize>,
+}
+
+/// Corroboration computed from the sources rather than taken from the model
+#[derive(Debug, Clone)]
+pub struct CorroborationAssessment {
+    pub score: f64,
+    pub explanation: String,
+    pub claims: Vec<InvestigationClaim>,
+}
+
 // Simplified search result structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -137,7 +163,7 @@ pub async fn execute_searches(queries: &[String]) -> Result<Vec<SearchResult>> {
                 all_results.extend(results);
             }
             Err(e) => {
-                eprintln!("Search failed for query '{}': {}", query, e);
+                tracing::warn!("Search failed for query '{}': {}", query, e);
                 // Continue with other queries even if one fails
             }
         }
@@ -331,31 +357,16 @@ pub async fn run_investigation(
     .await?;
 
     // Step 4: Convert to investigation sources
-    let sources: Vec<InvestigationSource> = search_results
-        .iter()
-        .enumerate()
-        .filter_map(|(i, result)| {
-            // Find relevance from synthesis
-            let analysis = synthesis
-                .sources_analysis
-                .iter()
-                .find(|a| a.source_index == i)?;
-
-            let relevance_score = match analysis.relevance.as_str() {
-                "high" => 0.9,
-                "medium" => 0.6,
-                _ => 0.3,
-            };
-
-            Some(InvestigationSource {
-                title: result.title.clone(),
-                url: result.url.clone(),
-                excerpt: result.snippet.clone(),
-                relevance_score,
-                publication_date: None, // TODO: Extract from search results
-            })
-        })
-        .collect();
+    let (sources, claims) = collect_sources(&search_results, &synthesis.claims, |i| {
+        synthesis
+            .sources_analysis
+            .iter()
+            .find(|a| a.source_index == i)
+            .map(|a| relevance_score(&a.relevance))
+    });
+
+    // Step 5: Score corroboration from independent sources per claim
+    let assessment = score_corroboration(&claims, &sources);
 
     let elapsed = start_time.elapsed().as_millis() as i32;
 
@@ -364,8 +375,209 @@ pub async fn run_investigation(
         status: "completed".to_string(),
         ai_summary: synthesis.summary,
         sources,
-        corroboration_score: synthesis.corroboration_score,
+        corroboration_score: assessment.score,
         generated_queries: queries,
         processing_time_ms: elapsed,
+        claims: assessment.claims,
+        score_explanation: assessment.explanation,
     })
 }
+
+pub(crate) fn relevance_score(relevance: &str) -> f64 {
+    match relevance {
+        "high" => 0.9,
+        "medium" => 0.6,
+        _ => 0.3,
+    }
+}
+
+/// Keep the search results the synthesis analysed or cited, and re-point claim
+/// source indices at the kept list
+pub(crate) fn collect_sources(
+    search_results: &[SearchResult],
+    claims: &[ClaimAnalysis],
+    relevance_of: impl Fn(usize) -> Option<f64>,
+) -> (Vec<InvestigationSource>, Vec<ClaimAnalysis>) {
+    let mut sources = Vec::new();
+    let mut positions = std::collections::HashMap::new();
+
+    for (i, result) in search_results.iter().enumerate() {
+        let cited = claims.iter().any(|c| c.source_indices.contains(&i));
+        let relevance = match relevance_of(i) {
+            Some(score) => score,
+            None if cited => relevance_score("low"),
+            None => continue,
+        };
+
+        positions.insert(i, sources.len());
+        sources.push(InvestigationSource {
+            title: result.title.clone(),
+            url: result.url.clone(),
+            excerpt: result.snippet.clone(),
+            relevance_score: relevance,
+            publication_date: None, // TODO: Extract from search results
+        });
+    }
+
+    let claims = claims
+        .iter()
+        .map(|c| ClaimAnalysis {
+            claim: c.claim.clone(),
+            source_indices: c
+                .source_indices
+                .iter()
+                .filter_map(|i| positions.get(i).copied())
+                .collect(),
+        })
+        .collect();
+
+    (sources, claims)
+}
+
+/// Score how well the investigation is corroborated
+///
+/// Sources on the same site count once per claim. Each claim scores 1 - 0.5^n for
+/// n independent sources, and the overall score is that of the best-supported claim.
+pub fn score_corroboration(
+    claims: &[ClaimAnalysis],
+    sources: &[InvestigationSource],
+) -> CorroborationAssessment {
+    if sources.is_empty() {
+        return CorroborationAssessment {
+            score: 0.0,
+            explanation: "No sources were found, so nothing corroborates this flight.".to_string(),
+            claims: Vec::new(),
+        };
+    }
+
+    let mut scored: Vec<InvestigationClaim> = claims
+        .iter()
+        .filter_map(|c| {
+            let mut indices: Vec<usize> = c
+                .source_indices
+                .iter()
+                .copied()
+                .filter(|&i| i < sources.len())
+                .collect();
+            indices.sort_unstable();
+            indices.dedup();
+            if indices.is_empty() {
+                return None;
+            }
+
+            let mut domains: Vec<String> = indices
+                .iter()
+                .map(|&i| source_domain(&sources[i].url))
+                .collect();
+            domains.sort();
+            domains.dedup();
+
+            let independent = domains.len() as i32;
+            Some(InvestigationClaim {
+                claim: c.claim.clone(),
+                source_indices: indices,
+                independent_sources: independent,
+                source_domains: domains,
+                support_score: 1.0 - 0.5f64.powi(independent),
+                agreement: agreement_label(independent),
+            })
+        })
+        .collect();
+
+    scored.sort_by_key(|c| std::cmp::Reverse(c.independent_sources));
+
+    let Some(best) = scored.first() else {
+        return CorroborationAssessment {
+            score: 0.1,
+            explanation: format!(
+                "{} source(s) found, but none could be tied to a specific claim.",
+                sources.len()
+            ),
+            claims: scored,
+        };
+    };
+
+    CorroborationAssessment {
+        score: best.support_score,
+        explanation: format!(
+            "Best-supported claim: {} ({} source(s) reviewed, {} claim(s) identified).",
+            best.agreement.to_lowercase(),
+            sources.len(),
+            scored.len()
+        ),
+        claims: scored,
+    }
+}
+
+fn agreement_label(independent_sources: i32) -> String {
+    match independent_sources {
+        1 => "Single source".to_string(),
+        n => format!("{} sources agree", n),
+    }
+}
+
+/// Host of a source URL without a leading "www.", used to spot the same site twice
+fn source_domain(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_lowercase()))
+        .unwrap_or_else(|| url.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(url: &str) -> InvestigationSource {
+        InvestigationSource {
+            title: String::new(),
+            url: url.to_string(),
+            excerpt: String::new(),
+            relevance_score: 0.9,
+            publication_date: None,
+        }
+    }
+
+    fn claim(text: &str, source_indices: Vec<usize>) -> ClaimAnalysis {
+        ClaimAnalysis {
+            claim: text.to_string(),
+            source_indices,
+        }
+    }
+
+    #[test]
+    fn no_sources_scores_zero_with_explanation() {
+        let assessment = score_corroboration(&[], &[]);
+        assert_eq!(assessment.score, 0.0);
+        assert!(assessment.explanation.contains("No sources"));
+    }
+
+    #[test]
+    fn same_site_counts_once() {
+        let sources = vec![
+            source("https://www.example.com/a"),
+            source("https://example.com/b"),
+            source("https://news.test/story"),
+            source("https://records.gov/entry"),
+        ];
+        let claims = vec![
+            claim("Seen at gala", vec![0, 1]),
+            claim("Arrived by private jet", vec![0, 2, 3]),
+        ];
+
+        let assessment = score_corroboration(&claims, &sources);
+
+        assert_eq!(assessment.claims[0].claim, "Arrived by private jet");
+        assert_eq!(assessment.claims[0].agreement, "3 sources agree");
+        assert_eq!(assessment.claims[1].independent_sources, 1);
+        assert!((assessment.score - 0.875).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uncited_sources_score_low() {
+        let sources = vec![source("https://example.com/a")];
+        let assessment = score_corroboration(&[claim("Unsupported", vec![])], &sources);
+        assert!(assessment.score < 0.3);
+        assert!(assessment.claims.is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+// Journey detection
+// Proposes journeys from chains of flights not yet in one: each leg departs from the
+// airport the previous leg arrived at, within a maximum layover. Both times of a layover
+// are local to the same airport, so they compare directly without timezone lookups.
+//
+// Where a hub has several possible continuations (or several arrivals feed one
+// departure), connections are taken shortest layover first and every flight is used at
+// most once, so each suggestion is the most time-contiguous chain.
+
+use anyhow::Result;
+use chrono::{Duration, NaiveDateTime};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::timezone::parse_local_datetime;
+
+/// Longest layover that still counts as a connection when the caller does not say
+pub const DEFAULT_MAX_LAYOVER_HOURS: u32 = 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedLeg {
+    pub flight_id: String,
+    /// Position in the journey, from 1, for add_flight_to_journey
+    pub sequence_order: i32,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    pub departure_datetime: String,
+    /// Time on the ground before this leg; None for the first leg
+    pub layover_minutes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneySuggestion {
+    /// Route summary, e.g. "KJFK → KORD → KSFO"
+    pub name: String,
+    /// YYYY-MM-DD of the first departure
+    pub start_date: String,
+    /// YYYY-MM-DD of the last arrival (or departure when it has no arrival time)
+    pub end_date: String,
+    pub legs: Vec<SuggestedLeg>,
+}
+
+/// A flight as far as chaining is concerned
+#[derive(Debug, Clone)]
+struct Leg {
+    flight_id: String,
+    departure_airport: String,
+    arrival_airport: String,
+    departure_datetime: String,
+    departure: NaiveDateTime,
+    /// Recorded arrival, else departure plus duration (approximate across timezones),
+    /// else the departure itself
+    arrival: NaiveDateTime,
+}
+
+/// The user's flights that are in no journey, oldest first; flights with unparseable
+/// departure times are skipped
+fn unassigned_legs(conn: &Connection, user_id: &str) -> Result<Vec<Leg>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, departure_airport, arrival_airport, departure_datetime, arrival_datetime,
+                COALESCE(flight_duration, total_duration)
+         FROM flights
+         WHERE user_id = ?1 AND deleted_at IS NULL
+           AND id NOT IN (SELECT flight_id FROM journey_flights)
+         ORDER BY departure_datetime",
+    )?;
+    let rows = stmt
+        .query_map(params![user_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<f64>>(5)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut legs: Vec<Leg> = rows
+        .into_iter()
+        .filter_map(|(flight_id, departure_airport, arrival_airport, departure_datetime, arrival_datetime, duration)| {
+            let departure = parse_local_datetime(&departure_datetime)?;
+            let arrival = arrival_datetime
+                .as_deref()
+                .and_then(parse_local_datetime)
+                .or_else(|| duration.map(|minutes| departure + Duration::minutes(minutes as i64)))
+                .unwrap_or(departure);
+            Some(Leg {
+                flight_id,
+                departure_airport: departure_airport.trim().to_uppercase(),
+                arrival_airport: arrival_airport.trim().to_uppercase(),
+                departure_datetime,
+                departure,
+                arrival,
+            })
+        })
+        .collect();
+    legs.sort_by_key(|leg| leg.departure);
+    Ok(legs)
+}
+
+/// Chains of at least two legs (as indexes into `legs`, which must be sorted by departure)
+fn chain_legs(legs: &[Leg], max_layover: Duration) -> Vec<Vec<usize>> {
+    let mut connections = Vec::new();
+    for (i, from) in legs.iter().enumerate() {
+        for (j, to) in legs.iter().enumerate().skip(i + 1) {
+            let layover = to.departure - from.arrival;
+            if layover > max_layover {
+                break;
+            }
+            if layover >= Duration::zero() && to.departure_airport == from.arrival_airport {
+                connections.push((layover, i, j));
+            }
+        }
+    }
+    connections.sort();
+
+    let mut next = vec![None; legs.len()];
+    let mut has_previous = vec![false; legs.len()];
+    for (_, i, j) in connections {
+        if next[i].is_none() && !has_previous[j] {
+            next[i] = Some(j);
+            has_previous[j] = true;
+        }
+    }
+
+    (0..legs.len())
+        .filter(|&start| !has_previous[start] && next[start].is_some())
+        .map(|start| {
+            let mut chain = vec![start];
+            let mut current = start;
+            while let Some(following) = next[current] {
+                chain.push(following);
+                current = following;
+            }
+            chain
+        })
+        .collect()
+}
+
+fn suggestion(legs: &[Leg], chain: &[usize]) -> JourneySuggestion {
+    let chain: Vec<&Leg> = chain.iter().map(|&i| &legs[i]).collect();
+    let mut airports: Vec<&str> = chain.iter().map(|leg| leg.departure_airport.as_str()).collect();
+    airports.extend(chain.last().map(|leg| leg.arrival_airport.as_str()));
+
+    JourneySuggestion {
+        name: airports.join(" → "),
+        start_date: chain[0].departure.format("%Y-%m-%d").to_string(),
+        end_date: chain[chain.len() - 1].arrival.format("%Y-%m-%d").to_string(),
+        legs: chain
+            .iter()
+            .enumerate()
+            .map(|(i, leg)| SuggestedLeg {
+                flight_id: leg.flight_id.clone(),
+                sequence_order: i as i32 + 1,
+                departure_airport: leg.departure_airport.clone(),
+                arrival_airport: leg.arrival_airport.clone(),
+                departure_datetime: leg.departure_datetime.clone(),
+                layover_minutes: i.checked_sub(1).map(|previous| (leg.departure - chain[previous].arrival).num_minutes()),
+            })
+            .collect(),
+    }
+}
+
+/// Candidate journeys from the user's unassigned flights, oldest first
+pub fn suggest_journeys(conn: &Connection, user_id: &str, max_layover_hours: u32) -> Result<Vec<JourneySuggestion>> {
+    let legs = unassigned_legs(conn, user_id)?;
+    Ok(chain_legs(&legs, Duration::hours(max_layover_hours.into()))
+        .iter()
+        .map(|chain| suggestion(&legs, chain))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_suggest_journeys() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Traveller');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, arrival_datetime)
+                 VALUES ('a', 'u1', 'KJFK', 'KORD', '2025-03-01T08:00:00', '2025-03-01T10:00:00'),
+                        -- Two continuations from ORD: the shorter layover wins
+                        ('b', 'u1', 'KORD', 'KSFO', '2025-03-01T15:00:00', '2025-03-01T17:30:00'),
+                        ('c', 'u1', 'KORD', 'KDEN', '2025-03-01T11:30:00', '2025-03-01T13:00:00'),
+                        ('d', 'u1', 'KDEN', 'KLAX', '2025-03-02T09:00:00', '2025-03-02T10:30:00'),
+                        -- Too long on the ground to connect
+                        ('e', 'u1', 'KLAX', 'KJFK', '2025-03-09T09:00:00', '2025-03-09T17:00:00'),
+                        -- Already in a journey
+                        ('f', 'u1', 'KSFO', 'KSEA', '2025-03-01T19:00:00', '2025-03-01T21:00:00');
+                 INSERT INTO journeys (id, user_id, name, start_date) VALUES ('j1', 'u1', 'Seattle', '2025-03-01');
+                 INSERT INTO journey_flights (journey_id, flight_id, sequence_order) VALUES ('j1', 'f', 1);",
+            )
+            .unwrap();
+
+        let suggestions = suggest_journeys(&db.conn, "u1", DEFAULT_MAX_LAYOVER_HOURS).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        let journey = &suggestions[0];
+        assert_eq!(journey.name, "KJFK → KORD → KDEN → KLAX");
+        assert_eq!((journey.start_date.as_str(), journey.end_date.as_str()), ("2025-03-01", "2025-03-02"));
+        let legs: Vec<_> = journey.legs.iter().map(|l| (l.flight_id.as_str(), l.sequence_order, l.layover_minutes)).collect();
+        assert_eq!(legs, [("a", 1, None), ("c", 2, Some(90)), ("d", 3, Some(1200))]);
+    }
+}
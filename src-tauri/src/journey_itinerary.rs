@@ -0,0 +1,216 @@
+// Journey itinerary
+// Each leg of a journey with its local and UTC times, the timezone change it makes, and the
+// connection before it. Connections are measured in UTC, so a layover that crosses a DST
+// change or follows an airport change is still exact.
+//
+// A leg whose airports have no usable timezone keeps its recorded local times but gets no
+// UTC times and is marked "timezone unknown"; nothing is guessed. A connection at a single
+// airport can still be measured from its two local times, which share that airport's zone.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::flights::build_flight_times;
+use crate::database::Database;
+use crate::models::FlightEndpointTimes;
+use crate::timezone::parse_local_datetime;
+
+pub const TIMEZONE_UNKNOWN: &str = "timezone unknown";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegConnection {
+    /// Time between the previous leg's arrival and this leg's departure; negative when they
+    /// overlap, None when it cannot be measured
+    pub minutes: Option<i64>,
+    /// Arrives at one airport and leaves from another
+    pub change_of_airport: bool,
+    /// "utc", "local" (same airport, measured on its clock) or "timezone unknown"
+    pub basis: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItineraryLeg {
+    /// Position in the journey, from 1
+    pub position: usize,
+    pub flight_id: String,
+    pub flight_number: Option<String>,
+    pub departure: FlightEndpointTimes,
+    pub arrival: FlightEndpointTimes,
+    pub elapsed_minutes: Option<i64>,
+    /// Arrival UTC offset minus departure UTC offset
+    pub timezone_shift_minutes: Option<i64>,
+    /// "+5h", "-3h 30m", "no change" or "timezone unknown"
+    pub timezone_shift: String,
+    /// Local arrival date minus local departure date, e.g. 1 for an overnight flight
+    pub arrival_day_offset: Option<i64>,
+    /// None for the first leg
+    pub connection: Option<LegConnection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneyItinerary {
+    pub journey_id: String,
+    pub name: String,
+    pub legs: Vec<ItineraryLeg>,
+    /// First departure to last arrival in UTC
+    pub total_minutes: Option<i64>,
+    /// Last arrival's UTC offset minus the first departure's
+    pub net_timezone_shift_minutes: Option<i64>,
+    pub net_timezone_shift: String,
+    pub legs_with_unknown_timezone: usize,
+}
+
+/// Minutes ahead of UTC at one end of a flight, from its local and UTC times
+fn utc_offset_minutes(end: &FlightEndpointTimes) -> Option<i64> {
+    let local = parse_local_datetime(end.local_time.as_deref()?)?;
+    let utc = parse_local_datetime(end.utc_time.as_deref()?)?;
+    Some((local - utc).num_minutes())
+}
+
+/// "+5h", "-3h 30m", "+45m", "no change", or the unknown marker
+pub fn describe_shift(minutes: Option<i64>) -> String {
+    let Some(minutes) = minutes else {
+        return TIMEZONE_UNKNOWN.to_string();
+    };
+    if minutes == 0 {
+        return "no change".to_string();
+    }
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let (hours, rest) = (minutes.abs() / 60, minutes.abs() % 60);
+    match (hours, rest) {
+        (0, m) => format!("{}{}m", sign, m),
+        (h, 0) => format!("{}{}h", sign, h),
+        (h, m) => format!("{}{}h {}m", sign, h, m),
+    }
+}
+
+fn connection(previous: &FlightEndpointTimes, next: &FlightEndpointTimes) -> LegConnection {
+    let change_of_airport = !previous.airport_code.eq_ignore_ascii_case(&next.airport_code);
+    let between = |a: &Option<String>, b: &Option<String>| {
+        let a = parse_local_datetime(a.as_deref()?)?;
+        let b = parse_local_datetime(b.as_deref()?)?;
+        Some((b - a).num_minutes())
+    };
+    let (minutes, basis) = match between(&previous.utc_time, &next.utc_time) {
+        Some(minutes) => (Some(minutes), "utc"),
+        None if !change_of_airport && !previous.is_estimated => match between(&previous.local_time, &next.local_time) {
+            Some(minutes) => (Some(minutes), "local"),
+            None => (None, TIMEZONE_UNKNOWN),
+        },
+        None => (None, TIMEZONE_UNKNOWN),
+    };
+    LegConnection { minutes, change_of_airport, basis: basis.to_string() }
+}
+
+/// The itinerary of a journey, legs in sequence order; None when the journey does not exist
+pub fn build(db: &Database, journey_id: &str) -> Result<Option<JourneyItinerary>> {
+    let Some(journey) = db.get_journey(journey_id)? else {
+        return Ok(None);
+    };
+
+    let mut legs: Vec<ItineraryLeg> = Vec::new();
+    for (index, flight) in db.get_journey_flights(journey_id)?.into_iter().enumerate() {
+        let departure_tz = db.get_airport_by_code(&flight.departure_airport)?.and_then(|a| a.timezone);
+        let arrival_tz = db.get_airport_by_code(&flight.arrival_airport)?.and_then(|a| a.timezone);
+        let flight_id = flight.id.clone();
+        let flight_number = flight.flight_number.clone();
+        let times = build_flight_times(flight, departure_tz, arrival_tz);
+
+        let timezone_shift_minutes = utc_offset_minutes(&times.arrival)
+            .zip(utc_offset_minutes(&times.departure))
+            .map(|(arrival, departure)| arrival - departure);
+        let date = |end: &FlightEndpointTimes| end.local_time.as_deref().and_then(parse_local_datetime).map(|t| t.date());
+        let arrival_day_offset = date(&times.arrival)
+            .zip(date(&times.departure))
+            .map(|(arrival, departure)| (arrival - departure).num_days());
+        let connection = legs.last().map(|previous| connection(&previous.arrival, &times.departure));
+
+        legs.push(ItineraryLeg {
+            position: index + 1,
+            flight_id,
+            flight_number,
+            elapsed_minutes: times.elapsed_minutes,
+            timezone_shift_minutes,
+            timezone_shift: describe_shift(timezone_shift_minutes),
+            arrival_day_offset,
+            connection,
+            departure: times.departure,
+            arrival: times.arrival,
+        });
+    }
+
+    let (first, last) = (legs.first(), legs.last());
+    let total_minutes = first.zip(last).and_then(|(first, last)| {
+        let departure = parse_local_datetime(first.departure.utc_time.as_deref()?)?;
+        let arrival = parse_local_datetime(last.arrival.utc_time.as_deref()?)?;
+        Some((arrival - departure).num_minutes())
+    });
+    let net_timezone_shift_minutes = first
+        .zip(last)
+        .and_then(|(first, last)| Some(utc_offset_minutes(&last.arrival)? - utc_offset_minutes(&first.departure)?));
+    let legs_with_unknown_timezone = legs.iter().filter(|leg| leg.timezone_shift_minutes.is_none()).count();
+
+    Ok(Some(JourneyItinerary {
+        journey_id: journey.id,
+        name: journey.name,
+        legs,
+        total_minutes,
+        net_timezone_shift_minutes,
+        net_timezone_shift: describe_shift(net_timezone_shift_minutes),
+        legs_with_unknown_timezone,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_itinerary_across_timezones() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, iata_code, name, timezone) VALUES
+                     ('SFO', 'SFO', 'San Francisco', 'America/Los_Angeles'),
+                     ('JFK', 'JFK', 'John F Kennedy', 'America/New_York'),
+                     ('LHR', 'LHR', 'Heathrow', 'Europe/London'),
+                     ('DEL', 'DEL', 'Delhi', 'Asia/Kolkata');
+                 INSERT INTO flights (id, user_id, flight_number, departure_airport, arrival_airport, departure_datetime, arrival_datetime) VALUES
+                     ('l1', 'u1', 'UA1', 'SFO', 'JFK', '2025-06-01T08:00:00', '2025-06-01T16:30:00'),
+                     ('l2', 'u1', 'BA2', 'JFK', 'LHR', '2025-06-01T19:00:00', '2025-06-02T07:00:00'),
+                     ('l3', 'u1', 'AI3', 'LHR', 'DEL', '2025-06-02T10:00:00', '2025-06-02T23:30:00'),
+                     ('l4', 'u1', NULL, 'DEL', 'XXX', '2025-06-05T09:00:00', '2025-06-05T11:00:00');
+                 INSERT INTO journeys (id, user_id, name, start_date) VALUES ('j1', 'u1', 'Round the world', '2025-06-01');
+                 INSERT INTO journey_flights (journey_id, flight_id, sequence_order) VALUES
+                     ('j1', 'l1', 1), ('j1', 'l2', 2), ('j1', 'l3', 3), ('j1', 'l4', 4);",
+            )
+            .unwrap();
+
+        let itinerary = build(&db, "j1").unwrap().unwrap();
+        let legs = &itinerary.legs;
+        assert_eq!(legs.len(), 4);
+        assert!(legs[0].connection.is_none());
+        assert_eq!(legs[0].timezone_shift, "+3h");
+        assert_eq!(legs[0].elapsed_minutes, Some(330));
+        assert_eq!((legs[1].timezone_shift.as_str(), legs[1].arrival_day_offset), ("+5h", Some(1)));
+        assert_eq!(legs[2].timezone_shift, "+4h 30m");
+
+        // 16:30 EDT to 19:00 EDT at JFK; 07:00 BST to 10:00 BST at LHR
+        let layovers: Vec<Option<i64>> = legs[1..].iter().map(|l| l.connection.as_ref().unwrap().minutes).collect();
+        assert_eq!(layovers[..2], [Some(150), Some(180)]);
+        assert_eq!(legs[1].connection.as_ref().unwrap().basis, "utc");
+
+        // The last leg lands somewhere without a timezone: no shift and no UTC arrival
+        assert_eq!(legs[3].timezone_shift, TIMEZONE_UNKNOWN);
+        assert!(legs[3].arrival.utc_time.is_none());
+        assert_eq!(legs[3].arrival.local_time.as_deref(), Some("2025-06-05T11:00:00"));
+        assert_eq!(itinerary.legs_with_unknown_timezone, 1);
+        assert_eq!((itinerary.total_minutes, itinerary.net_timezone_shift.as_str()), (None, TIMEZONE_UNKNOWN));
+
+        assert!(build(&db, "missing").unwrap().is_none());
+        assert_eq!(describe_shift(Some(-210)), "-3h 30m");
+        assert_eq!(describe_shift(Some(45)), "+45m");
+    }
+}
@@ -3,22 +3,69 @@
 mod agent_memory;
 mod agent_server;
 mod agent_tracking;
+mod ai_cache;
+mod aircraft_performance;
+mod airlines;
+mod airport_import;
+mod arrival_order;
+mod backup;
+mod bcbp;
 mod calculations;
 mod commands;
+mod currency;
 mod database;
 mod deepseek;
 mod doc_ingestion;
 mod doc_worker;
+mod eml_import;
+mod error;
+mod expiry_alerts;
 pub mod extract;
+mod fare_classes;
+mod flight_card;
+mod flight_sanity;
+mod flight_simulation;
+mod flight_templates;
+mod flight_verification;
 mod gemini;
 mod geo;
+mod ghg_report;
 mod grok;
+mod identity_conflicts;
 mod investigation;
+mod journey_detection;
+mod journey_itinerary;
+mod locale;
+mod location_change;
+mod logging;
+mod maintenance;
+mod migrations;
 mod models;
+mod network_export;
+mod network_history;
 mod ocr;
+mod ocr_barcode;
 mod ocr_learning;
+mod ocr_preprocess;
+mod operations;
+mod orphans;
+mod passenger_filter;
 mod pdf_dossier;
+mod pilot_credentials;
+mod prompt_templates;
+mod report_templates;
+mod retention;
+mod route_heatmap;
+mod route_reversal;
+mod storage;
+mod timezone;
+mod trip_purpose;
+mod units;
+mod user_merge;
+mod validation;
+mod web_search;
 mod workflow;
+mod write_queue;
 
 use std::sync::Mutex;
 use tauri::Manager;
@@ -40,24 +87,66 @@ pub fn run() {
             std::fs::create_dir_all(&app_dir).expect("Failed to create app data directory");
 
             let db_path = app_dir.join("flight_tracker.db");
+
+            // Log to a file users can attach to bug reports; everything after this goes through tracing
+            if let Err(e) = logging::init(&app_dir, logging::configured_level(&db_path)) {
+                tracing::warn!("Failed to initialize logging: {:#}", e);
+            }
+
+            // Back up before migrations touch the existing database
+            if let Err(e) = backup::backup_on_startup(&db_path, &app_dir) {
+                tracing::warn!("Startup backup failed: {:#}", e);
+            }
+
             let database = database::Database::new(db_path.clone()).expect("Failed to initialize database");
+            if retention::run_on_startup(&database) {
+                match retention::run(&database) {
+                    Ok(report) => tracing::info!("Retention cleanup removed {} rows", report.total_removed),
+                    Err(e) => tracing::warn!("Startup retention cleanup failed: {:#}", e),
+                }
+            }
+            let writer = write_queue::WriteQueue::start(db_path.clone()).expect("Failed to start database writer");
 
             // Store database in app state
             app.manage(commands::AppState {
                 db: Mutex::new(database),
+                writer,
             });
 
             // Initialize workflow state
             app.manage(commands::workflow::WorkflowState::new(app.handle().clone()));
 
+            // Progress and cancellation for long running commands
+            app.manage(operations::OperationRegistry::default());
+
+            // Locate the bundled airport CSV now, then validate and index it off the main thread;
+            // distance lookups that come first wait for that load instead of skipping the CSV
+            if let Err(e) = commands::airport_enrichment::locate_airport_csv(app.handle()) {
+                tracing::error!("[AirportCache] {}", e);
+            }
+            let airport_handle = app.handle().clone();
+            std::thread::spawn(move || commands::airport_enrichment::preload_airport_cache(&airport_handle));
+
+            // Warn about landing currency and credentials coming up for expiry
+            tauri::async_runtime::spawn(commands::pilot_credentials::run_expiry_alert_checks(app.handle().clone()));
+
+            // Archive old low-confidence research reports daily, when turned on
+            tauri::async_runtime::spawn(commands::research::run_report_archival(app.handle().clone()));
+
+            // Record network flows for the sentinel's history queries, when turned on
+            tauri::async_runtime::spawn(commands::network_sentinel::run_network_history_sampler(app.handle().clone()));
+
+            // Notice when the public IP's location moves far enough to suggest a flight, when turned on
+            tauri::async_runtime::spawn(commands::network_sentinel::run_location_change_checks(app.handle().clone()));
+
             // Spawn WebSocket agent server on port 9528 for bridge integration
             let server_db_path = db_path.clone();
             tauri::async_runtime::spawn(async move {
-                let server = agent_server::AgentServer::new(9528, server_db_path);
+                let server = agent_server::AgentServer::new(agent_server::AGENT_SERVER_PORT, server_db_path);
                 if let Err(e) = server.start().await {
-                    eprintln!("❌ Agent server failed to start: {}", e);
+                    tracing::error!("Agent server failed to start: {}", e);
                 } else {
-                    println!("✅ Agent server started successfully");
+                    tracing::info!("Agent server started successfully");
                 }
             });
 
@@ -73,18 +162,41 @@ pub fn run() {
             commands::get_primary_user,
             commands::list_all_users,
             commands::update_user_name,
+            commands::preview_merge_users,
+            commands::merge_users,
             // Flights
             commands::create_flight,
             commands::get_flight,
+            commands::get_flight_with_times,
+            commands::export_flight_card,
+            commands::get_flights_on_this_day,
             commands::list_flights,
+            commands::list_flights_page,
+            commands::lookup_airline,
+            commands::infer_flight_airlines,
             commands::delete_flight,
+            commands::list_deleted_flights,
+            commands::restore_flight,
+            commands::purge_deleted,
+            // Flight Templates
+            commands::create_flight_template,
+            commands::list_flight_templates,
+            commands::update_flight_template,
+            commands::delete_flight_template,
+            commands::create_flight_from_template,
+            commands::create_flights_from_template_series,
             // CSV Import
             commands::preview_csv_import,
             commands::import_flights_from_csv_with_mapping,
+            commands::list_csv_import_presets,
             commands::import_flights_from_csv,
+            commands::import_master_log,
+            commands::import_flights_from_eml,
             commands::preload_test_data_batch,
             // Statistics
             commands::get_statistics,
+            commands::get_statistics_by_class,
+            commands::set_unit_system,
             commands::get_airport_list,
             commands::get_analytics,
             // Distance & CO2 Calculations
@@ -92,11 +204,29 @@ pub fn run() {
             commands::calculate_co2_emissions,
             commands::calculate_per_passenger_co2,
             commands::calculate_flight_duration,
+            commands::simulate_flight,
             commands::fuel_kg_to_liters,
             commands::fuel_kg_to_gallons,
+            commands::get_emissions_context,
+            commands::get_emission_factors,
+            commands::set_emission_factors,
+            commands::get_co2_class_weights,
+            commands::set_co2_class_weights,
+            commands::get_ghg_report,
+            commands::get_ghg_settings,
+            commands::set_ghg_settings,
+            commands::get_flight_operation_type,
+            commands::set_flight_operation_type,
+            commands::list_aircraft_performance,
+            commands::save_aircraft_performance,
+            commands::delete_aircraft_performance,
             // OCR
             commands::analyze_boarding_pass,
             commands::batch_analyze_boarding_passes,
+            commands::reanalyze_boarding_pass,
+            commands::batch_reanalyze_boarding_passes,
+            commands::compare_ocr_preprocessing,
+            commands::create_flights_from_ocr,
             // OCR Learning
             commands::record_ocr_correction,
             commands::get_ocr_suggestions,
@@ -104,15 +234,29 @@ pub fn run() {
             commands::reject_ocr_suggestion,
             commands::get_ocr_correction_history,
             commands::get_active_learning_patterns,
+            commands::set_learning_pattern_active,
             // Data Management
             commands::export_data_to_csv,
+            commands::export_flights_ics,
             commands::reset_database,
+            commands::get_write_queue_depth,
+            commands::get_migration_status,
+            commands::find_orphaned_records,
+            commands::repair_orphaned_records,
+            commands::get_retention_policies,
+            commands::run_retention_cleanup,
+            commands::get_database_stats,
+            commands::compact_database,
             // Investigations
             commands::investigate_flight,
             commands::get_flight_investigation,
             commands::list_all_investigations,
             // Passenger Management
             commands::get_all_passenger_names,
+            commands::find_suspicious_passengers,
+            commands::delete_suspicious_passengers,
+            commands::get_passenger_stop_list,
+            commands::set_passenger_stop_list,
             commands::save_passenger_mapping,
             commands::delete_passenger_mapping,
             commands::get_all_passenger_mappings,
@@ -125,10 +269,19 @@ pub fn run() {
             commands::bootstrap_identities,
             commands::bootstrap_identities_batch,
             commands::merge_alias,
+            commands::list_merge_review_queue,
+            commands::resolve_merge_review,
+            commands::add_never_merge_pair,
+            commands::remove_never_merge_pair,
+            commands::list_never_merge_pairs,
+            commands::detect_identity_conflicts,
+            commands::flag_identity_conflict_flights,
+            commands::unmerge_alias,
             commands::list_canonical_passengers,
             commands::get_passenger_aliases,
             commands::list_unmerged_passengers,
             commands::rename_canonical_passenger,
+            commands::find_passenger_name_inconsistencies,
             commands::get_identity_stats,
             // Do Not Deduplicate Management
             commands::mark_no_dedup,
@@ -150,6 +303,11 @@ pub fn run() {
             commands::batch_split_passengers,
             // DeepSeek Research
             commands::research_flight_with_deepseek,
+            commands::get_flight_weather,
+            commands::list_prompt_templates,
+            commands::save_prompt_template,
+            commands::delete_prompt_template,
+            commands::validate_prompt_template,
             // Grok Research
             commands::research_flight_with_grok,
             commands::multi_provider_flight_research,
@@ -161,8 +319,13 @@ pub fn run() {
             commands::save_research_report,
             commands::get_research_report,
             commands::list_research_reports,
+            commands::list_research_reports_page,
             commands::delete_research_report,
             commands::count_research_reports,
+            commands::get_report_archive_policy,
+            commands::apply_report_archive_policy,
+            commands::list_archived_reports,
+            commands::export_archived_reports,
             commands::export_research_report_to_markdown,
             // Journey Management
             commands::create_journey,
@@ -173,6 +336,9 @@ pub fn run() {
             commands::add_flight_to_journey,
             commands::remove_flight_from_journey,
             commands::get_journey_flights,
+            commands::get_journey_itinerary,
+            commands::suggest_journeys,
+            commands::accept_journey_suggestion,
             // Pilot Logbook
             commands::create_pilot_logbook_entry,
             commands::get_pilot_logbook_entry,
@@ -180,6 +346,15 @@ pub fn run() {
             commands::list_all_pilot_logbook_entries,
             commands::update_pilot_logbook_entry,
             commands::delete_pilot_logbook_entry,
+            commands::lock_logbook_entry,
+            commands::unlock_logbook_entry,
+            commands::get_logbook_audit,
+            commands::create_pilot_credential,
+            commands::list_pilot_credentials,
+            commands::update_pilot_credential,
+            commands::delete_pilot_credential,
+            commands::get_expiring_credentials,
+            commands::check_expiry_alerts,
             commands::get_pilot_logbook_totals,
             // Frequent Flyer Programs
             commands::create_ffp,
@@ -187,14 +362,20 @@ pub fn run() {
             commands::list_user_ffps,
             commands::update_ffp,
             commands::delete_ffp,
+            commands::suggest_ffp_credits,
+            commands::apply_ffp_credits,
             // Airports
             commands::create_airport,
             commands::get_airport,
             commands::list_all_airports,
             commands::update_airport,
             commands::delete_airport,
+            commands::find_duplicate_airports,
+            commands::preview_airport_merge,
+            commands::merge_airports,
             // Airport Enrichment & Search
             commands::enrich_airport_data,
+            commands::cancel_enrichment,
             commands::get_missing_coordinates_count,
             commands::get_total_airports_count,
             commands::lookup_airport,
@@ -204,12 +385,20 @@ pub fn run() {
             commands::get_csv_airports_by_codes,
             commands::get_visited_airport_codes,
             commands::import_visited_airports,
+            commands::import_airports_from_csv,
             // Aircraft Types
             commands::create_aircraft_type,
             commands::get_aircraft_type,
             commands::list_all_aircraft_types,
             commands::update_aircraft_type,
             commands::delete_aircraft_type,
+            // Aircraft Maintenance
+            commands::list_maintenance_schedules,
+            commands::create_maintenance_schedule,
+            commands::update_maintenance_schedule,
+            commands::delete_maintenance_schedule,
+            commands::record_maintenance_done,
+            commands::get_maintenance_due,
             // Custom Documents
             commands::create_custom_document,
             commands::get_custom_document,
@@ -232,21 +421,42 @@ pub fn run() {
             commands::get_fuel_entries,
             commands::get_fuel_stats,
             commands::delete_fuel_entry,
+            commands::export_fuel_entries,
             commands::get_fuel_types,
             commands::add_fuel_type,
             commands::delete_fuel_type,
             // Analytics
             commands::get_temporal_analysis,
             commands::get_geospatial_analysis,
+            commands::get_route_heatmap,
             commands::get_passenger_network,
+            commands::export_passenger_network,
             commands::get_comparative_metrics,
             commands::get_aircraft_utilization,
             commands::get_cost_breakdown,
+            commands::get_revenue_by_cabin,
+            commands::get_fare_class_mapping,
+            commands::save_fare_class_mapping,
             commands::get_day_night_stats,
             commands::get_long_haul_flights,
             commands::get_pilot_currency,
             commands::get_monthly_cost_trend,
             commands::get_runway_risk_data,
+            commands::get_high_altitude_airports,
+            commands::get_analytics_by_dimension,
+            commands::get_punctuality_stats,
+            commands::set_home_base,
+            commands::get_commute_analytics,
+            commands::get_slow_queries,
+            commands::suggest_indexes,
+            commands::compare_flights,
+            commands::compare_periods,
+            commands::generate_digest,
+            commands::list_report_templates,
+            commands::run_report_template,
+            // Self-test
+            commands::run_self_test,
+            commands::introspect_commands,
             // Network Scanner (Physical Security)
             commands::scan_wifi_networks,
             commands::scan_bluetooth_devices,
@@ -276,6 +486,7 @@ pub fn run() {
             commands::delete_custom_schema,
             commands::create_custom_record,
             commands::list_custom_records,
+            commands::list_custom_records_page,
             commands::update_custom_record,
             commands::delete_custom_record,
             commands::set_flight_custom_field,
@@ -288,6 +499,10 @@ pub fn run() {
             commands::detect_flight_anomalies,
             commands::get_pending_anomalies,
             commands::resolve_anomaly,
+            commands::fix_reversed_route,
+            commands::verify_flight_externally,
+            commands::check_flight_sanity,
+            commands::detect_arrival_before_departure,
             commands::find_duplicates,
             commands::get_pending_duplicates,
             commands::update_route_statistics,
@@ -296,6 +511,8 @@ pub fn run() {
             commands::get_fuel_price_history,
             commands::cache_ai_response,
             commands::get_cached_ai_response,
+            commands::get_ai_cache_stats,
+            commands::clear_ai_cache,
             commands::get_self_improvement_stats,
             commands::populate_route_statistics,
             // Workflow
@@ -318,7 +535,10 @@ pub fn run() {
             commands::get_network_flows,
             commands::get_network_anomalies,
             commands::query_network_history,
+            commands::get_network_history_summary,
             commands::detect_location_from_ip,
+            commands::check_location_change,
+            commands::get_location_change_state,
             // Data Editor
             commands::find_duplicate_flights,
             commands::merge_duplicate_flights,
@@ -329,9 +549,18 @@ pub fn run() {
             commands::get_data_editor_stats,
             commands::remove_passenger_from_flights,
             commands::rename_passenger_in_flights,
+            // Flight Tags
+            commands::add_flight_tag,
+            commands::remove_flight_tag,
+            commands::list_flight_tags,
+            commands::list_all_tags,
+            // Trip Purpose
+            commands::classify_flight_purpose,
+            commands::accept_flight_purpose,
             // Media Gallery
             commands::upload_media_file,
             commands::list_media_files,
+            commands::list_media_files_page,
             commands::get_media_file,
             commands::get_media_file_path,
             commands::update_media_file,
@@ -343,9 +572,12 @@ pub fn run() {
             // Batch Calculations (Distance & CO2)
             commands::batch_calculate_missing_distances,
             commands::batch_recalculate_co2,
+            commands::backfill_per_passenger_co2,
             commands::batch_calculate_all,
             commands::batch_calculate_streaming,
             commands::get_calculation_stats,
+            commands::find_distance_inconsistencies,
+            commands::fix_distance_inconsistencies,
             // Active Defense (D-Bus Sentinel Controls)
             commands::get_all_sentinel_metrics,
             commands::get_cpu_snapshot,
@@ -382,6 +614,11 @@ pub fn run() {
             commands::process_reap_zombies,
             commands::process_get_top_cpu,
             commands::process_get_top_memory,
+            commands::get_protected_processes,
+            commands::set_protected_processes,
+            commands::get_active_defense_profile,
+            commands::list_active_defense_presets,
+            commands::set_active_defense_profile,
             commands::emergency_all_systems,
             commands::lockdown_network,
             commands::performance_mode,
@@ -401,9 +638,44 @@ pub fn run() {
             // AI Models
             commands::get_ai_models,
             commands::get_models_by_provider,
+            // Backups
+            commands::list_backups,
+            commands::create_backup,
+            commands::restore_backup,
+            // Logs
+            commands::get_recent_logs,
+            commands::get_log_level,
+            commands::set_log_level,
+            // Long running operations
+            commands::get_operation_status,
+            commands::list_operations,
+            commands::cancel_operation,
             // Initialization
             commands::initialize_app,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                backup_on_shutdown(app);
+            }
+        });
+}
+
+/// Take the shutdown backup if enabled; called once on a clean exit
+fn backup_on_shutdown(app: &tauri::AppHandle) {
+    let (Ok(app_dir), Some(state)) = (app.path().app_data_dir(), app.try_state::<commands::AppState>()) else {
+        return;
+    };
+    let Ok(db) = state.db.lock() else {
+        return;
+    };
+    let policy = backup::BackupPolicy::load(&db.conn);
+    if !policy.on_shutdown || policy.keep == 0 {
+        return;
+    }
+    let dir = backup::backup_dir(&app_dir);
+    if let Err(e) = backup::create_backup(&db.conn, &dir, "shutdown").and_then(|_| backup::rotate(&dir, policy.keep)) {
+        tracing::warn!("Shutdown backup failed: {:#}", e);
+    }
 }
@@ -0,0 +1,339 @@
+// Locale handling for OCR prompts and date/number parsing
+// Stored in the "locale" setting ("en", "de", "fr" or "system")
+
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+    Fr,
+}
+
+/// Month names per language, without accents. Full names first, then abbreviations.
+const EN_MONTHS: [&[&str]; 12] = [
+    &["january", "jan"],
+    &["february", "feb"],
+    &["march", "mar"],
+    &["april", "apr"],
+    &["may"],
+    &["june", "jun"],
+    &["july", "jul"],
+    &["august", "aug"],
+    &["september", "sep", "sept"],
+    &["october", "oct"],
+    &["november", "nov"],
+    &["december", "dec"],
+];
+
+const DE_MONTHS: [&[&str]; 12] = [
+    &["januar", "janner", "jan"],
+    &["februar", "feber", "feb"],
+    &["marz", "maerz", "mrz", "mar"],
+    &["april", "apr"],
+    &["mai"],
+    &["juni", "jun"],
+    &["juli", "jul"],
+    &["august", "aug"],
+    &["september", "sep", "sept"],
+    &["oktober", "okt"],
+    &["november", "nov"],
+    &["dezember", "dez"],
+];
+
+const FR_MONTHS: [&[&str]; 12] = [
+    &["janvier", "janv", "jan"],
+    &["fevrier", "fevr", "fev"],
+    &["mars", "mar"],
+    &["avril", "avr"],
+    &["mai"],
+    &["juin"],
+    &["juillet", "juil"],
+    &["aout"],
+    &["septembre", "sept", "sep"],
+    &["octobre", "oct"],
+    &["novembre", "nov"],
+    &["decembre", "dec"],
+];
+
+impl Locale {
+    /// Parse a language tag such as "fr", "de-AT" or "fr_FR.UTF-8"
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.', '@'])
+            .next()?
+            .trim()
+            .to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            _ => None,
+        }
+    }
+
+    /// Locale from the environment (LC_ALL, LC_TIME, LANG), English when unknown
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .filter(|value| !value.is_empty())
+            .find_map(|value| Self::from_tag(&value))
+            .unwrap_or(Locale::En)
+    }
+
+    /// Locale from the stored setting; missing, "system" or unknown values use the system locale
+    pub fn from_setting(value: Option<&str>) -> Self {
+        value
+            .filter(|v| !v.eq_ignore_ascii_case("system"))
+            .and_then(Self::from_tag)
+            .unwrap_or_else(Self::system)
+    }
+
+    /// Locale configured in the app settings
+    pub fn configured(db: &crate::database::Database) -> Self {
+        Self::from_setting(db.get_setting("locale").ok().flatten().as_deref())
+    }
+
+    fn months(&self) -> &'static [&'static [&'static str]; 12] {
+        match self {
+            Locale::En => &EN_MONTHS,
+            Locale::De => &DE_MONTHS,
+            Locale::Fr => &FR_MONTHS,
+        }
+    }
+
+    /// Whether numeric dates put the day first (31/12/2024 rather than 12/31/2024)
+    fn day_first(&self) -> bool {
+        !matches!(self, Locale::En)
+    }
+
+    /// Month number (1-12) for a month name or abbreviation; English names are always accepted
+    pub fn month_from_name(&self, name: &str) -> Option<u32> {
+        let name = fold_accents(&name.trim().trim_end_matches('.').to_lowercase());
+        if name.len() < 3 {
+            return None;
+        }
+
+        for months in [self.months(), &EN_MONTHS] {
+            if let Some(idx) = months.iter().position(|names| names.contains(&name.as_str())) {
+                return Some(idx as u32 + 1);
+            }
+        }
+
+        // Unambiguous prefix of a full month name ("janv", "sept")
+        let mut found = None;
+        for months in [self.months(), &EN_MONTHS] {
+            for (idx, names) in months.iter().enumerate() {
+                if names[0].starts_with(&name) {
+                    match found {
+                        Some(existing) if existing != idx => return None,
+                        _ => found = Some(idx),
+                    }
+                }
+            }
+        }
+        found.map(|idx| idx as u32 + 1)
+    }
+
+    /// Parse a date written in this locale's conventions
+    ///
+    /// Handles ISO (2024-01-05), compact (20240105), numeric dates in the locale's
+    /// day/month order with the other order as fallback, and textual dates such as
+    /// "5 janvier 2024", "5. Januar 2024", "January 5, 2024" or "05JAN24".
+    pub fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(date);
+        }
+        if value.len() == 8 && value.chars().all(|c| c.is_ascii_digit()) {
+            return NaiveDate::parse_from_str(value, "%Y%m%d").ok();
+        }
+
+        // Split into runs of digits and letters
+        let mut tokens: Vec<String> = Vec::new();
+        let mut current = String::new();
+        for c in value.chars() {
+            let same_kind = current
+                .chars()
+                .last()
+                .is_some_and(|last| last.is_ascii_digit() == c.is_ascii_digit());
+            if c.is_alphanumeric() {
+                if !current.is_empty() && !same_kind {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                current.push(c);
+            } else if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+
+        let numbers: Vec<&str> = tokens
+            .iter()
+            .filter(|t| t.chars().all(|c| c.is_ascii_digit()))
+            .map(|t| t.as_str())
+            .collect();
+        let month_name = tokens
+            .iter()
+            .filter(|t| t.chars().all(|c| c.is_alphabetic()))
+            .find_map(|t| self.month_from_name(t));
+
+        match (month_name, numbers.as_slice()) {
+            (Some(month), [a, b]) => {
+                // Whichever number is four digits (or comes last) is the year
+                let (day, year) = if a.len() == 4 { (b, a) } else { (a, b) };
+                ymd(parse_year(year)?, month, day.parse().ok()?)
+            }
+            (None, [a, b, c]) if a.len() == 4 => {
+                ymd(a.parse().ok()?, b.parse().ok()?, c.parse().ok()?)
+            }
+            (None, [a, b, c]) => {
+                let year = parse_year(c)?;
+                let (a, b): (u32, u32) = (a.parse().ok()?, b.parse().ok()?);
+                let (first, second) = if self.day_first() { ((b, a), (a, b)) } else { ((a, b), (b, a)) };
+                ymd(year, first.0, first.1).or_else(|| ymd(year, second.0, second.1))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse a number that may use a decimal comma and thousands separators
+    /// ("1 234,5", "1.234,5", "1,234.5"). Trailing units such as "km" are ignored.
+    pub fn parse_number(&self, value: &str) -> Option<f64> {
+        let numeric: String = value
+            .trim()
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+' | '\'' | ' ' | '\u{a0}' | '\u{202f}'))
+            .filter(|c| !matches!(c, ' ' | '\u{a0}' | '\u{202f}' | '\''))
+            .collect();
+        if numeric.is_empty() {
+            return None;
+        }
+
+        let commas = numeric.matches(',').count();
+        let dots = numeric.matches('.').count();
+        let decimal = match (commas, dots) {
+            (0, 0) => None,
+            // Both present: whichever comes last is the decimal separator
+            (c, d) if c > 0 && d > 0 => {
+                if numeric.rfind(',') > numeric.rfind('.') { Some(',') } else { Some('.') }
+            }
+            // Repeated separator can only be grouping
+            (c, _) if c > 1 => None,
+            (_, d) if d > 1 => None,
+            (1, 0) => {
+                // "1,234" is a thousands group in English; a decimal comma elsewhere
+                let digits_after = numeric.len() - numeric.rfind(',')? - 1;
+                if self.day_first() || digits_after != 3 { Some(',') } else { None }
+            }
+            _ => Some('.'),
+        };
+
+        let normalized: String = numeric
+            .chars()
+            .filter_map(|c| match c {
+                ',' | '.' if Some(c) == decimal => Some('.'),
+                ',' | '.' => None,
+                other => Some(other),
+            })
+            .collect();
+        normalized.parse().ok()
+    }
+
+    /// Extra OCR prompt guidance describing the field labels and date style to expect
+    pub fn ocr_prompt_hint(&self) -> &'static str {
+        match self {
+            Locale::En => "",
+            Locale::De => "\n\nLANGUAGE: The document may be in German. Field labels to look for: Name/Passagier (passenger_name), Flug (flight_number), Von/Abflug (departure_airport), Nach/Ziel/Ankunft (arrival_airport), Datum and Abflugzeit (departure_datetime), Sitz/Sitzplatz (seat_number), Flugsteig (gate), Klasse/Beförderungsklasse (fare_class), Buchungscode/Buchungsnummer (booking_reference), Ticketnummer (ticket_number). Dates may use German month names (5. Januar 2024) or DD.MM.YYYY; always convert them to ISO 8601.",
+            Locale::Fr => "\n\nLANGUAGE: The document may be in French. Field labels to look for: Nom/Passager (passenger_name), Vol (flight_number), De/Départ/Provenance (departure_airport), À/Vers/Arrivée/Destination (arrival_airport), Date and Heure de départ (departure_datetime), Siège (seat_number), Porte (gate), Classe (fare_class), Référence/Code de réservation (booking_reference), Numéro de billet (ticket_number), Embarquement (boarding). Dates may use French month names (5 janvier 2024) or DD/MM/YYYY; always convert them to ISO 8601.",
+        }
+    }
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Four-digit year, or a two-digit year pivoting at 70 (24 -> 2024, 91 -> 1991)
+fn parse_year(value: &str) -> Option<i32> {
+    let year: i32 = value.parse().ok()?;
+    match value.len() {
+        4 => Some(year),
+        2 if year < 70 => Some(2000 + year),
+        2 => Some(1900 + year),
+        _ => None,
+    }
+}
+
+fn fold_accents(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            'à' | 'â' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'û' | 'ù' | 'ü' => 'u',
+            'ç' => 'c',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn test_french_textual_date() {
+        assert_eq!(Locale::Fr.parse_date("5 janvier 2024"), date(2024, 1, 5));
+        assert_eq!(Locale::Fr.parse_date("1er août 2023"), date(2023, 8, 1));
+        assert_eq!(Locale::Fr.parse_date("12 févr. 2024"), date(2024, 2, 12));
+    }
+
+    #[test]
+    fn test_german_and_english_dates() {
+        assert_eq!(Locale::De.parse_date("5. März 2024"), date(2024, 3, 5));
+        assert_eq!(Locale::De.parse_date("05.01.2024"), date(2024, 1, 5));
+        assert_eq!(Locale::En.parse_date("January 5, 2024"), date(2024, 1, 5));
+        assert_eq!(Locale::En.parse_date("05JAN24"), date(2024, 1, 5));
+    }
+
+    #[test]
+    fn test_numeric_date_order() {
+        assert_eq!(Locale::En.parse_date("01/05/2024"), date(2024, 1, 5));
+        assert_eq!(Locale::Fr.parse_date("01/05/2024"), date(2024, 5, 1));
+        // Falls back to the other order when the first is impossible
+        assert_eq!(Locale::En.parse_date("13/05/2024"), date(2024, 5, 13));
+        assert_eq!(Locale::En.parse_date("2024-01-05"), date(2024, 1, 5));
+    }
+
+    #[test]
+    fn test_comma_decimal_distance() {
+        assert_eq!(Locale::Fr.parse_number("1 234,5 km"), Some(1234.5));
+        assert_eq!(Locale::De.parse_number("1.234,5"), Some(1234.5));
+        assert_eq!(Locale::De.parse_number("345,75"), Some(345.75));
+        assert_eq!(Locale::En.parse_number("1,234.5"), Some(1234.5));
+        assert_eq!(Locale::En.parse_number("1,234"), Some(1234.0));
+        assert_eq!(Locale::En.parse_number("1.3"), Some(1.3));
+    }
+
+    #[test]
+    fn test_locale_tags() {
+        assert_eq!(Locale::from_tag("fr_FR.UTF-8"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::from_setting(Some("de")), Locale::De);
+        assert_eq!(Locale::from_tag("ja_JP"), None);
+    }
+}
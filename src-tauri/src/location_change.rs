@@ -0,0 +1,190 @@
+// Location change detection
+// Notices when the public IP's geolocation moves far enough to mean the user has travelled,
+// so the app can suggest logging the flight. Samples come from detect_location_from_ip every
+// "location_change_interval_minutes" (default 30) while "location_change_enabled" is on; it is
+// off by default since every sample is a request to the geolocation service.
+//
+// A move counts once the new position is more than "location_change_threshold_km" (default 150)
+// from the last known one. IP geolocation is noisy and VPNs jump around, so the new position
+// must hold for "location_change_confirmations" samples in a row (default 2) before it replaces
+// the last known location and a change is reported. A sample back near the last known location
+// discards the pending one.
+//
+// The last known and pending locations persist in the "location_change_state" setting.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+
+pub const ENABLED_SETTING: &str = "location_change_enabled";
+pub const THRESHOLD_SETTING: &str = "location_change_threshold_km";
+pub const CONFIRMATIONS_SETTING: &str = "location_change_confirmations";
+pub const INTERVAL_SETTING: &str = "location_change_interval_minutes";
+const STATE_SETTING: &str = "location_change_state";
+
+pub const DEFAULT_THRESHOLD_KM: f64 = 150.0;
+pub const DEFAULT_CONFIRMATIONS: u32 = 2;
+pub const DEFAULT_INTERVAL_MINUTES: u64 = 30;
+/// The geolocation service allows 45 requests a minute; there is no need to come close
+const MIN_INTERVAL_MINUTES: u64 = 5;
+
+/// Emitted with the LocationChange when a move is confirmed
+pub const CHANGE_EVENT: &str = "location:changed";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationChangeSettings {
+    pub enabled: bool,
+    pub threshold_km: f64,
+    pub confirmations: u32,
+    pub interval_minutes: u64,
+}
+
+impl LocationChangeSettings {
+    /// Settings from the app settings; detection is off unless turned on
+    pub fn configured(db: &Database) -> Self {
+        let setting = |key| db.get_setting(key).ok().flatten();
+        LocationChangeSettings {
+            enabled: setting(ENABLED_SETTING).as_deref() == Some("true"),
+            threshold_km: setting(THRESHOLD_SETTING)
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .filter(|km| km.is_finite() && *km > 0.0)
+                .unwrap_or(DEFAULT_THRESHOLD_KM),
+            confirmations: setting(CONFIRMATIONS_SETTING)
+                .and_then(|v| v.trim().parse::<u32>().ok())
+                .unwrap_or(DEFAULT_CONFIRMATIONS)
+                .max(1),
+            interval_minutes: setting(INTERVAL_SETTING)
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+                .max(MIN_INTERVAL_MINUTES),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownLocation {
+    pub lat: f64,
+    pub lng: f64,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    /// RFC 3339, when last sampled here
+    pub seen_at: String,
+}
+
+impl KnownLocation {
+    fn distance_km(&self, other: &KnownLocation) -> f64 {
+        crate::geo::calculate_distance(self.lat, self.lng, other.lat, other.lng).1
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LocationState {
+    pub last_known: Option<KnownLocation>,
+    /// A far-away position waiting for confirmation, and how many samples in a row saw it
+    pub pending: Option<KnownLocation>,
+    pub pending_samples: u32,
+}
+
+/// An airport suggested for logging the flight, near one end of the move
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyAirport {
+    pub code: String,
+    pub name: String,
+    /// From the location it was picked for
+    pub distance_km: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationChange {
+    pub from: KnownLocation,
+    pub to: KnownLocation,
+    pub distance_km: f64,
+    /// Filled in by the caller where airport data is available
+    pub from_airport: Option<NearbyAirport>,
+    pub to_airport: Option<NearbyAirport>,
+}
+
+/// Fold one sample into `state`, returning the change it confirms, if any
+pub fn observe(state: &mut LocationState, sample: KnownLocation, settings: &LocationChangeSettings) -> Option<LocationChange> {
+    let Some(last_known) = state.last_known.as_mut() else {
+        state.last_known = Some(sample);
+        return None;
+    };
+
+    let distance_km = last_known.distance_km(&sample);
+    if distance_km <= settings.threshold_km {
+        last_known.seen_at = sample.seen_at;
+        state.pending = None;
+        state.pending_samples = 0;
+        return None;
+    }
+
+    match &state.pending {
+        Some(pending) if pending.distance_km(&sample) <= settings.threshold_km => state.pending_samples += 1,
+        _ => state.pending_samples = 1,
+    }
+    state.pending = Some(sample.clone());
+    if state.pending_samples < settings.confirmations {
+        return None;
+    }
+
+    let from = std::mem::replace(last_known, sample.clone());
+    state.pending = None;
+    state.pending_samples = 0;
+    Some(LocationChange { from, to: sample, distance_km, from_airport: None, to_airport: None })
+}
+
+pub fn load_state(db: &Database) -> LocationState {
+    db.get_setting(STATE_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Fold a sample into the stored state and store the result
+pub fn record_sample(db: &Database, sample: KnownLocation, settings: &LocationChangeSettings) -> Result<Option<LocationChange>> {
+    let mut state = load_state(db);
+    let change = observe(&mut state, sample, settings);
+    db.set_setting(STATE_SETTING, &serde_json::to_string(&state)?)?;
+    Ok(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(lat: f64, lng: f64, city: &str) -> KnownLocation {
+        KnownLocation { lat, lng, city: Some(city.to_string()), country: None, seen_at: String::new() }
+    }
+
+    #[test]
+    fn test_change_needs_confirmation() {
+        let settings = LocationChangeSettings { enabled: true, threshold_km: 150.0, confirmations: 2, interval_minutes: 30 };
+        let (madrid, toledo, paris, amsterdam) =
+            (at(40.42, -3.70, "Madrid"), at(39.86, -4.02, "Toledo"), at(48.86, 2.35, "Paris"), at(52.37, 4.90, "Amsterdam"));
+        let mut state = LocationState::default();
+
+        // The first sample is the starting point; nearby noise is not a move
+        assert!(observe(&mut state, madrid.clone(), &settings).is_none());
+        assert!(observe(&mut state, toledo, &settings).is_none());
+
+        // A VPN blip to Paris is discarded by the next sample at home
+        assert!(observe(&mut state, paris.clone(), &settings).is_none());
+        assert!(observe(&mut state, madrid.clone(), &settings).is_none());
+        assert_eq!((state.pending.as_ref(), state.pending_samples), (None, 0));
+
+        // Paris then Amsterdam do not confirm each other; Amsterdam twice does
+        assert!(observe(&mut state, paris, &settings).is_none());
+        assert!(observe(&mut state, amsterdam.clone(), &settings).is_none());
+        let change = observe(&mut state, amsterdam.clone(), &settings).unwrap();
+        assert_eq!(change.from.city.as_deref(), Some("Madrid"));
+        assert_eq!(change.to.city.as_deref(), Some("Amsterdam"));
+        assert!((change.distance_km - 1480.0).abs() < 20.0);
+        assert_eq!(state.last_known, Some(amsterdam.clone()));
+
+        // Settled in Amsterdam now
+        assert!(observe(&mut state, amsterdam, &settings).is_none());
+    }
+}
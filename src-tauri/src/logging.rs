@@ -0,0 +1,239 @@
+// Application logging
+// tracing events go to stderr and to a size-rotated file in <app data>/logs
+// (flight_tracker.log, then .1 and .2 as older generations), so packaged builds keep a
+// log users can attach to bug reports. API keys and bearer tokens are redacted before
+// anything is written. The level comes from the "log_level" setting (default "info")
+// and can be changed at runtime.
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use rusqlite::{Connection, OptionalExtension};
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+pub const LOG_LEVEL_SETTING: &str = "log_level";
+pub const DEFAULT_LEVEL: &str = "info";
+pub const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "flight_tracker.log";
+/// A log file is rotated once it grows past this
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated generations kept next to the live file
+const ROTATED_FILES: usize = 2;
+
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+pub fn log_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join(LOG_DIR)
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter> {
+    match level.trim().to_lowercase().as_str() {
+        "error" => Ok(LevelFilter::ERROR),
+        "warn" | "warning" => Ok(LevelFilter::WARN),
+        "info" => Ok(LevelFilter::INFO),
+        "debug" => Ok(LevelFilter::DEBUG),
+        "trace" => Ok(LevelFilter::TRACE),
+        other => Err(anyhow!("Unknown log level '{}': use one of {}", other, LEVELS.join(", "))),
+    }
+}
+
+/// The log_level setting of a database file that has not been opened yet
+/// A missing file, table or invalid value gives the default.
+pub fn configured_level(db_path: &Path) -> LevelFilter {
+    Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .ok()
+        .and_then(|conn| {
+            conn.query_row("SELECT value FROM settings WHERE key = ?1", [LOG_LEVEL_SETTING], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .ok()
+            .flatten()
+        })
+        .and_then(|level| parse_level(&level).ok())
+        .unwrap_or(LevelFilter::INFO)
+}
+
+/// Install the global subscriber; call once, as early in startup as possible
+pub fn init(app_dir: &Path, level: LevelFilter) -> Result<()> {
+    let file = RotatingFile::open(log_dir(app_dir))?;
+    let (filter, handle) = reload::Layer::new(level);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_ansi(false).with_writer(Mutex::new(Redacting(file))))
+        .with(fmt::layer().with_writer(|| Redacting(io::stderr())))
+        .try_init()
+        .context("Logger already initialized")?;
+
+    let _ = LEVEL_HANDLE.set(handle);
+    Ok(())
+}
+
+/// Change the level of the running logger
+pub fn set_level(level: &str) -> Result<()> {
+    let level = parse_level(level)?;
+    LEVEL_HANDLE
+        .get()
+        .ok_or_else(|| anyhow!("Logger is not initialized"))?
+        .modify(|filter| *filter = level)
+        .context("Failed to change log level")
+}
+
+/// The last `count` lines logged, oldest first, reading back into rotated files as needed
+pub fn recent_lines(dir: &Path, count: usize) -> Result<Vec<String>> {
+    let mut lines: Vec<String> = Vec::new();
+    for generation in 0..=ROTATED_FILES {
+        if lines.len() >= count {
+            break;
+        }
+        let path = generation_path(dir, generation);
+        if !path.exists() {
+            continue;
+        }
+        let content = std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let older: Vec<String> = String::from_utf8_lossy(&content).lines().map(str::to_string).collect();
+        let take = (count - lines.len()).min(older.len());
+        let mut combined = older[older.len() - take..].to_vec();
+        combined.append(&mut lines);
+        lines = combined;
+    }
+    Ok(lines)
+}
+
+/// `text` with API keys, bearer tokens and key query parameters masked
+pub fn redact(text: &str) -> Cow<'_, str> {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        vec![
+            // Google API keys, and the ?key= query parameter the Gemini URLs carry
+            (Regex::new(r"AIza[0-9A-Za-z_\-]{20,}").unwrap(), "[REDACTED]"),
+            (Regex::new(r"(?i)([?&](?:key|api_key|apikey|token)=)[^&\s]+").unwrap(), "${1}[REDACTED]"),
+            // OpenAI/DeepSeek/xAI style secret keys
+            (Regex::new(r"\b(?:sk|xai)-[0-9A-Za-z_\-]{16,}").unwrap(), "[REDACTED]"),
+            (Regex::new(r"(?i)(bearer\s+)[0-9A-Za-z._\-]+").unwrap(), "${1}[REDACTED]"),
+            (
+                Regex::new(r#"(?i)((?:x-api-key|api_key|apikey|api-key)["']?\s*[:=]\s*["']?)[^"'\s,}]+"#).unwrap(),
+                "${1}[REDACTED]",
+            ),
+        ]
+    });
+
+    let mut text = Cow::Borrowed(text);
+    for (pattern, replacement) in patterns {
+        if let Cow::Owned(replaced) = pattern.replace_all(&text, *replacement) {
+            text = Cow::Owned(replaced);
+        }
+    }
+    text
+}
+
+/// Writer that redacts each formatted event before passing it on
+struct Redacting<W>(W);
+
+impl<W: Write> Write for Redacting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+fn generation_path(dir: &Path, generation: usize) -> PathBuf {
+    match generation {
+        0 => dir.join(LOG_FILE),
+        n => dir.join(format!("{}.{}", LOG_FILE, n)),
+    }
+}
+
+/// Append-only log file that shifts itself to .1, .2, ... when it gets too big
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir).context("Failed to create log directory")?;
+        let path = generation_path(&dir, 0);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self { dir, file, size })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for generation in (1..=ROTATED_FILES).rev() {
+            let from = generation_path(&self.dir, generation - 1);
+            if from.exists() {
+                std::fs::rename(&from, generation_path(&self.dir, generation))?;
+            }
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(generation_path(&self.dir, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("POST https://generativelanguage.googleapis.com/v1beta/models/x:generateContent?key=AIzaSyA1234567890abcdefghijklmnopqrstu"),
+            "POST https://generativelanguage.googleapis.com/v1beta/models/x:generateContent?key=[REDACTED]"
+        );
+        assert_eq!(redact("Authorization: Bearer abc.def-123"), "Authorization: Bearer [REDACTED]");
+        assert_eq!(redact(r#"{"x-api-key": "sk-abcdefghijklmnopqrstuvwx"}"#), r#"{"x-api-key": "[REDACTED]"}"#);
+        assert_eq!(redact("gemini_api_key=secret123 rest"), "gemini_api_key=[REDACTED] rest");
+        assert!(matches!(redact("Loaded 42 airports"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_rotation_and_recent_lines() {
+        let dir = std::env::temp_dir().join(format!("logging_test_{}", uuid::Uuid::new_v4()));
+        let mut file = RotatingFile::open(dir.clone()).unwrap();
+        writeln!(file, "first").unwrap();
+        file.rotate().unwrap();
+        writeln!(file, "second\nthird").unwrap();
+
+        assert_eq!(recent_lines(&dir, 2).unwrap(), vec!["second", "third"]);
+        assert_eq!(recent_lines(&dir, 10).unwrap(), vec!["first", "second", "third"]);
+        assert!(generation_path(&dir, 1).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
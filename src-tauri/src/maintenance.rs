@@ -0,0 +1,389 @@
+// Aircraft maintenance schedules
+// Recurring items per tail number (annual, 100-hour, oil change) with an hour interval, a
+// day interval or both, whichever comes first. Hours since the item was last done come
+// from the aircraft's accumulated flight hours, as reported by get_aircraft_utilization.
+
+use crate::database::Database;
+use crate::error::CommandError;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Items this close to due are reported as due soon
+const DUE_SOON_HOURS: f64 = 10.0;
+const DUE_SOON_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceSchedule {
+    pub id: String,
+    pub user_id: String,
+    pub aircraft_registration: String,
+    /// What is due, e.g. "Annual inspection", "Oil change"
+    pub item: String,
+    pub interval_hours: Option<f64>,
+    pub interval_days: Option<i64>,
+    /// Aircraft hours when the item was last done
+    pub last_done_hours: Option<f64>,
+    /// YYYY-MM-DD
+    pub last_done_date: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Editable fields of a schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceScheduleInput {
+    pub aircraft_registration: String,
+    pub item: String,
+    pub interval_hours: Option<f64>,
+    pub interval_days: Option<i64>,
+    pub last_done_hours: Option<f64>,
+    pub last_done_date: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Ordered by urgency: an item with one limit due soon and the other never recorded is due
+/// soon, so it still reaches the digest
+pub enum MaintenanceStatus {
+    Ok,
+    /// Never recorded as done, so there is nothing to count from
+    NotRecorded,
+    DueSoon,
+    Overdue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceDue {
+    pub schedule: MaintenanceSchedule,
+    /// Accumulated flight hours of the aircraft
+    pub current_hours: f64,
+    pub next_due_hours: Option<f64>,
+    /// Negative when overdue
+    pub hours_remaining: Option<f64>,
+    pub next_due_date: Option<String>,
+    /// Negative when overdue
+    pub days_remaining: Option<i64>,
+    /// The worse of the hour and day limits
+    pub status: MaintenanceStatus,
+}
+
+const SCHEDULE_COLUMNS: &str = "id, user_id, aircraft_registration, item, interval_hours, interval_days,
+    last_done_hours, last_done_date, notes, created_at, updated_at";
+
+fn schedule_from_row(row: &rusqlite::Row) -> rusqlite::Result<MaintenanceSchedule> {
+    Ok(MaintenanceSchedule {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        aircraft_registration: row.get(2)?,
+        item: row.get(3)?,
+        interval_hours: row.get(4)?,
+        interval_days: row.get(5)?,
+        last_done_hours: row.get(6)?,
+        last_done_date: row.get(7)?,
+        notes: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+/// Registrations are stored upper case without surrounding whitespace
+fn normalize_registration(registration: &str) -> String {
+    registration.trim().to_uppercase()
+}
+
+/// Schedules of one aircraft, or of every aircraft when `registration` is None
+pub fn list_schedules(conn: &Connection, user_id: &str, registration: Option<&str>) -> Result<Vec<MaintenanceSchedule>> {
+    let registration = registration.map(normalize_registration);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM maintenance_schedules
+         WHERE user_id = ?1 AND (?2 IS NULL OR aircraft_registration = ?2)
+         ORDER BY aircraft_registration, item COLLATE NOCASE",
+        SCHEDULE_COLUMNS
+    ))?;
+    let schedules = stmt
+        .query_map(params![user_id, registration], schedule_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(schedules)
+}
+
+/// One of the user's schedules; None when it does not exist or belongs to someone else
+pub fn get_schedule(conn: &Connection, user_id: &str, id: &str) -> Result<Option<MaintenanceSchedule>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM maintenance_schedules WHERE id = ?1 AND user_id = ?2", SCHEDULE_COLUMNS),
+            params![id, user_id],
+            schedule_from_row,
+        )
+        .optional()?)
+}
+
+pub fn create_schedule(conn: &Connection, user_id: &str, input: &MaintenanceScheduleInput) -> Result<MaintenanceSchedule> {
+    validate_schedule(input)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO maintenance_schedules
+            (id, user_id, aircraft_registration, item, interval_hours, interval_days, last_done_hours, last_done_date, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            id,
+            user_id,
+            normalize_registration(&input.aircraft_registration),
+            input.item.trim(),
+            input.interval_hours,
+            input.interval_days,
+            input.last_done_hours,
+            input.last_done_date,
+            input.notes,
+        ],
+    )?;
+    get_schedule(conn, user_id, &id)?.ok_or_else(|| anyhow!("Maintenance schedule not found: {}", id))
+}
+
+pub fn update_schedule(
+    conn: &Connection,
+    user_id: &str,
+    id: &str,
+    input: &MaintenanceScheduleInput,
+) -> Result<MaintenanceSchedule> {
+    validate_schedule(input)?;
+    let updated = conn.execute(
+        "UPDATE maintenance_schedules
+         SET aircraft_registration = ?2, item = ?3, interval_hours = ?4, interval_days = ?5,
+             last_done_hours = ?6, last_done_date = ?7, notes = ?8, updated_at = datetime('now')
+         WHERE id = ?1 AND user_id = ?9",
+        params![
+            id,
+            normalize_registration(&input.aircraft_registration),
+            input.item.trim(),
+            input.interval_hours,
+            input.interval_days,
+            input.last_done_hours,
+            input.last_done_date,
+            input.notes,
+            user_id,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(CommandError::not_found("Maintenance schedule", id).into());
+    }
+    get_schedule(conn, user_id, id)?.ok_or_else(|| anyhow!("Maintenance schedule not found: {}", id))
+}
+
+/// Mark an item as done at the given aircraft hours and date
+pub fn record_done(conn: &Connection, user_id: &str, id: &str, hours: f64, date: NaiveDate) -> Result<MaintenanceSchedule> {
+    let updated = conn.execute(
+        "UPDATE maintenance_schedules
+         SET last_done_hours = ?2, last_done_date = ?3, updated_at = datetime('now')
+         WHERE id = ?1 AND user_id = ?4",
+        params![id, hours, date.format("%Y-%m-%d").to_string(), user_id],
+    )?;
+    if updated == 0 {
+        return Err(CommandError::not_found("Maintenance schedule", id).into());
+    }
+    get_schedule(conn, user_id, id)?.ok_or_else(|| anyhow!("Maintenance schedule not found: {}", id))
+}
+
+pub fn delete_schedule(conn: &Connection, user_id: &str, id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM maintenance_schedules WHERE id = ?1 AND user_id = ?2", params![id, user_id])? > 0)
+}
+
+fn validate_schedule(input: &MaintenanceScheduleInput) -> Result<()> {
+    let invalid = |message: &str| Err(CommandError::Validation(message.to_string()).into());
+    if input.aircraft_registration.trim().is_empty() {
+        return invalid("Aircraft registration is required");
+    }
+    if input.item.trim().is_empty() {
+        return invalid("Maintenance item name is required");
+    }
+    if input.interval_hours.is_none() && input.interval_days.is_none() {
+        return invalid("Set an interval in hours, in days, or both");
+    }
+    if input.interval_hours.is_some_and(|h| !h.is_finite() || h <= 0.0) {
+        return invalid("Interval hours must be greater than zero");
+    }
+    if input.interval_days.is_some_and(|d| d <= 0) {
+        return invalid("Interval days must be greater than zero");
+    }
+    if input.last_done_hours.is_some_and(|h| !h.is_finite() || h < 0.0) {
+        return invalid("Last done hours cannot be negative");
+    }
+    if let Some(date) = &input.last_done_date {
+        if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return invalid("Last done date must be a YYYY-MM-DD date");
+        }
+    }
+    Ok(())
+}
+
+/// Accumulated flight hours per registration (upper case), from the utilization figures
+pub fn aircraft_hours(db: &Database, user_id: &str) -> Result<std::collections::HashMap<String, f64>> {
+    let mut hours = std::collections::HashMap::new();
+    // Utilization is grouped by tail and type, so one tail can appear more than once
+    for aircraft in db.get_aircraft_utilization(user_id)? {
+        *hours.entry(normalize_registration(&aircraft.tail_number)).or_insert(0.0) += aircraft.total_hours;
+    }
+    Ok(hours)
+}
+
+/// How far each schedule is from due, most urgent first
+pub fn maintenance_due(db: &Database, user_id: &str, registration: Option<&str>, today: NaiveDate) -> Result<Vec<MaintenanceDue>> {
+    let hours = aircraft_hours(db, user_id)?;
+    let mut due: Vec<MaintenanceDue> = list_schedules(&db.conn, user_id, registration)?
+        .into_iter()
+        .map(|schedule| {
+            let current_hours = hours.get(&schedule.aircraft_registration).copied().unwrap_or(0.0);
+            compute_due(schedule, current_hours, today)
+        })
+        .collect();
+    due.sort_by(|a, b| {
+        b.status.cmp(&a.status).then_with(|| {
+            let a_days = a.days_remaining.unwrap_or(i64::MAX);
+            let b_days = b.days_remaining.unwrap_or(i64::MAX);
+            a_days.cmp(&b_days)
+        })
+    });
+    Ok(due)
+}
+
+pub fn compute_due(schedule: MaintenanceSchedule, current_hours: f64, today: NaiveDate) -> MaintenanceDue {
+    let next_due_hours = schedule.interval_hours.zip(schedule.last_done_hours).map(|(interval, last)| last + interval);
+    let hours_remaining = next_due_hours.map(|next| next - current_hours);
+
+    let last_done_date = schedule
+        .last_done_date
+        .as_deref()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    let next_due_date = schedule.interval_days.zip(last_done_date).map(|(interval, last)| last + Duration::days(interval));
+    let days_remaining = next_due_date.map(|next| (next - today).num_days());
+
+    let hours_status = schedule.interval_hours.map(|_| match hours_remaining {
+        None => MaintenanceStatus::NotRecorded,
+        Some(remaining) if remaining < 0.0 => MaintenanceStatus::Overdue,
+        Some(remaining) if remaining <= DUE_SOON_HOURS => MaintenanceStatus::DueSoon,
+        Some(_) => MaintenanceStatus::Ok,
+    });
+    let days_status = schedule.interval_days.map(|_| match days_remaining {
+        None => MaintenanceStatus::NotRecorded,
+        Some(remaining) if remaining < 0 => MaintenanceStatus::Overdue,
+        Some(remaining) if remaining <= DUE_SOON_DAYS => MaintenanceStatus::DueSoon,
+        Some(_) => MaintenanceStatus::Ok,
+    });
+    let status = hours_status.max(days_status).unwrap_or(MaintenanceStatus::NotRecorded);
+
+    MaintenanceDue {
+        schedule,
+        current_hours,
+        next_due_hours,
+        hours_remaining,
+        next_due_date: next_due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+        days_remaining,
+        status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn input(item: &str, interval_hours: Option<f64>, interval_days: Option<i64>) -> MaintenanceScheduleInput {
+        MaintenanceScheduleInput {
+            aircraft_registration: " n12345 ".to_string(),
+            item: item.to_string(),
+            interval_hours,
+            interval_days,
+            last_done_hours: Some(100.0),
+            last_done_date: Some("2025-01-01".to_string()),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_compute_due_takes_the_earlier_limit() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn.execute("INSERT INTO users (id, name) VALUES ('user-1', 'Pilot')", []).unwrap();
+        let schedule = create_schedule(&db.conn, "user-1", &input("Oil change", Some(50.0), Some(120))).unwrap();
+        assert_eq!(schedule.aircraft_registration, "N12345");
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+        // 20 hours and 61 days left: fine
+        let due = compute_due(schedule.clone(), 130.0, today);
+        assert_eq!(due.hours_remaining, Some(20.0));
+        assert_eq!(due.next_due_date.as_deref(), Some("2025-05-01"));
+        assert_eq!(due.status, MaintenanceStatus::Ok);
+
+        // Hours nearly used up
+        assert_eq!(compute_due(schedule.clone(), 145.0, today).status, MaintenanceStatus::DueSoon);
+        // Hours fine but the date has passed
+        let late = compute_due(schedule, 110.0, NaiveDate::from_ymd_opt(2025, 6, 1).unwrap());
+        assert_eq!(late.status, MaintenanceStatus::Overdue);
+        assert_eq!(late.days_remaining, Some(-31));
+    }
+
+    #[test]
+    fn test_maintenance_due_uses_flight_hours() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('user-1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime,
+                                      aircraft_registration, total_duration)
+                 VALUES ('f1', 'user-1', 'KPAO', 'KSQL', '2025-02-01T10:00:00', 'N12345', 3000),
+                        ('f2', 'user-1', 'KSQL', 'KPAO', '2025-02-02T10:00:00', 'N12345', 3060);",
+            )
+            .unwrap();
+        let hundred_hour = create_schedule(&db.conn, "user-1", &input("100-hour", Some(100.0), None)).unwrap();
+        let mut never_done = input("Annual", None, Some(365));
+        never_done.last_done_date = None;
+        create_schedule(&db.conn, "user-1", &never_done).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let due = maintenance_due(&db, "user-1", Some("n12345"), today).unwrap();
+        assert_eq!(due.len(), 2);
+        // 101 hours flown in all; the 100-hour was last done at 100
+        let hundred = due.iter().find(|d| d.schedule.id == hundred_hour.id).unwrap();
+        assert_eq!(hundred.current_hours, 101.0);
+        assert_eq!(hundred.hours_remaining, Some(99.0));
+        assert_eq!(hundred.status, MaintenanceStatus::Ok);
+        assert_eq!(due[0].status, MaintenanceStatus::NotRecorded);
+
+        let done = record_done(&db.conn, "user-1", &hundred_hour.id, 101.0, today).unwrap();
+        assert_eq!(done.last_done_hours, Some(101.0));
+        assert!(create_schedule(&db.conn, "user-1", &input("No interval", None, None)).is_err());
+    }
+
+    #[test]
+    fn test_due_soon_outranks_not_recorded() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn.execute("INSERT INTO users (id, name) VALUES ('user-1', 'Pilot')", []).unwrap();
+        // Hours recorded and nearly used up, date never recorded
+        let mut schedule_input = input("100-hour", Some(100.0), Some(365));
+        schedule_input.last_done_date = None;
+        let schedule = create_schedule(&db.conn, "user-1", &schedule_input).unwrap();
+        let due = compute_due(schedule, 195.0, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(due.status, MaintenanceStatus::DueSoon);
+        assert!(MaintenanceStatus::NotRecorded < MaintenanceStatus::DueSoon);
+    }
+
+    #[test]
+    fn test_schedules_are_scoped_to_their_user() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn.execute_batch("INSERT INTO users (id, name) VALUES ('user-1', 'Pilot'), ('user-2', 'Other');").unwrap();
+        let schedule = create_schedule(&db.conn, "user-1", &input("Annual", None, Some(365))).unwrap();
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+        assert!(get_schedule(&db.conn, "user-2", &schedule.id).unwrap().is_none());
+        assert!(update_schedule(&db.conn, "user-2", &schedule.id, &input("Renamed", None, Some(30))).is_err());
+        assert!(record_done(&db.conn, "user-2", &schedule.id, 0.0, today).is_err());
+        assert!(!delete_schedule(&db.conn, "user-2", &schedule.id).unwrap());
+        assert_eq!(get_schedule(&db.conn, "user-1", &schedule.id).unwrap().unwrap().item, "Annual");
+
+        // Deleting the user takes its schedules with it
+        db.conn.execute("DELETE FROM users WHERE id = 'user-1'", []).unwrap();
+        assert!(list_schedules(&db.conn, "user-1", None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,1259 @@
+// Versioned schema migrations
+// Each migration runs exactly once, in order, inside its own transaction. Applied versions
+// are recorded in schema_migrations; a failing migration rolls back and aborts startup.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// One step of a migration
+enum Step {
+    Sql(&'static str),
+    /// ALTER TABLE ... ADD COLUMN, skipped when the column already exists (databases that
+    /// predate schema_migrations ran these ALTERs unversioned)
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    steps: &'static [Step],
+}
+
+/// All migrations in order. Append new ones with the next version; never edit or reorder
+/// a migration that has shipped.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline feature tables",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS trusted_wifi_devices (
+            bssid TEXT PRIMARY KEY,
+            ssid TEXT,
+            notes TEXT,
+            added_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_seen TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS trusted_bluetooth_devices (
+            address TEXT PRIMARY KEY,
+            name TEXT,
+            device_type TEXT,
+            notes TEXT,
+            added_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_seen TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS agent_memory (
+            id TEXT PRIMARY KEY,
+            agent_name TEXT NOT NULL,
+            memory_type TEXT NOT NULL,
+            flight_id TEXT,
+            user_id TEXT,
+            query TEXT,
+            content TEXT NOT NULL,
+            summary TEXT,
+            tokens_used INTEGER DEFAULT 0,
+            cost_usd REAL DEFAULT 0.0,
+            model TEXT,
+            embedding TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            expires_at TEXT,
+            last_accessed TEXT,
+            access_count INTEGER DEFAULT 0,
+            confidence_score REAL,
+            verified INTEGER DEFAULT 0,
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE SET NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+        );
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS agent_memory_fts USING fts5(
+            query, content, summary,
+            content='agent_memory',
+            content_rowid='rowid'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS agent_memory_fts_insert AFTER INSERT ON agent_memory BEGIN
+            INSERT INTO agent_memory_fts(rowid, query, content, summary)
+            VALUES (new.rowid, new.query, new.content, new.summary);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS agent_memory_fts_delete AFTER DELETE ON agent_memory BEGIN
+            DELETE FROM agent_memory_fts WHERE rowid = old.rowid;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS agent_memory_fts_update AFTER UPDATE ON agent_memory BEGIN
+            DELETE FROM agent_memory_fts WHERE rowid = old.rowid;
+            INSERT INTO agent_memory_fts(rowid, query, content, summary)
+            VALUES (new.rowid, new.query, new.content, new.summary);
+        END;
+
+        CREATE INDEX IF NOT EXISTS idx_agent_memory_flight ON agent_memory(flight_id);
+        CREATE INDEX IF NOT EXISTS idx_agent_memory_user ON agent_memory(user_id);
+        CREATE INDEX IF NOT EXISTS idx_agent_memory_type ON agent_memory(memory_type);
+        CREATE INDEX IF NOT EXISTS idx_agent_memory_agent ON agent_memory(agent_name);
+        CREATE INDEX IF NOT EXISTS idx_agent_memory_created ON agent_memory(created_at);
+        CREATE INDEX IF NOT EXISTS idx_agent_memory_expires ON agent_memory(expires_at);
+
+        CREATE TABLE IF NOT EXISTS document_ingestion_queue (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            source_document_id TEXT,
+            source_file_path TEXT NOT NULL,
+            source_file_name TEXT NOT NULL,
+            source_file_hash TEXT NOT NULL,
+            total_pages INTEGER NOT NULL,
+            pages_processed INTEGER DEFAULT 0,
+            status TEXT DEFAULT 'pending',
+            error_message TEXT,
+            priority INTEGER DEFAULT 0,
+            retry_count INTEGER DEFAULT 0,
+            max_retries INTEGER DEFAULT 3,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            started_at TEXT,
+            completed_at TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (source_document_id) REFERENCES custom_documents(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_doc_queue_status ON document_ingestion_queue(status);
+        CREATE INDEX IF NOT EXISTS idx_doc_queue_user ON document_ingestion_queue(user_id);
+        CREATE INDEX IF NOT EXISTS idx_doc_queue_priority ON document_ingestion_queue(priority DESC, created_at ASC);
+
+        CREATE TABLE IF NOT EXISTS document_chunks (
+            id TEXT PRIMARY KEY,
+            queue_id TEXT NOT NULL,
+            chunk_hash TEXT UNIQUE NOT NULL,
+            chunk_number INTEGER NOT NULL,
+            chunk_type TEXT DEFAULT 'page',
+            content_path TEXT NOT NULL,
+            status TEXT DEFAULT 'pending',
+            processing_method TEXT DEFAULT 'text_extract',
+            ocr_text TEXT,
+            extracted_entities TEXT,
+            processing_stage TEXT DEFAULT 'pending',
+            error_message TEXT,
+            retry_count INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            processed_at TEXT,
+            FOREIGN KEY (queue_id) REFERENCES document_ingestion_queue(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_chunks_queue ON document_chunks(queue_id);
+        CREATE INDEX IF NOT EXISTS idx_chunks_status ON document_chunks(status);
+        CREATE INDEX IF NOT EXISTS idx_chunks_hash ON document_chunks(chunk_hash);
+        CREATE INDEX IF NOT EXISTS idx_chunks_stage ON document_chunks(processing_stage);
+
+        CREATE TABLE IF NOT EXISTS entity_extractions (
+            id TEXT PRIMARY KEY,
+            chunk_id TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_value TEXT NOT NULL,
+            confidence REAL DEFAULT 0.0,
+            context TEXT,
+            start_position INTEGER,
+            end_position INTEGER,
+            metadata TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (chunk_id) REFERENCES document_chunks(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entities_chunk ON entity_extractions(chunk_id);
+        CREATE INDEX IF NOT EXISTS idx_entities_type ON entity_extractions(entity_type);
+        CREATE INDEX IF NOT EXISTS idx_entities_value ON entity_extractions(entity_value);
+
+        CREATE TABLE IF NOT EXISTS document_matches (
+            id TEXT PRIMARY KEY,
+            chunk_id TEXT NOT NULL,
+            flight_id TEXT,
+            match_type TEXT NOT NULL,
+            confidence REAL NOT NULL,
+            evidence TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (chunk_id) REFERENCES document_chunks(id) ON DELETE CASCADE,
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_matches_chunk ON document_matches(chunk_id);
+        CREATE INDEX IF NOT EXISTS idx_matches_flight ON document_matches(flight_id);
+        CREATE INDEX IF NOT EXISTS idx_matches_confidence ON document_matches(confidence);
+
+        CREATE TABLE IF NOT EXISTS relationship_graph (
+            id TEXT PRIMARY KEY,
+            source_type TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            relationship_type TEXT NOT NULL,
+            weight REAL DEFAULT 1.0,
+            evidence_ids TEXT,
+            metadata TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(source_type, source_id, target_type, target_id, relationship_type)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_graph_source ON relationship_graph(source_type, source_id);
+        CREATE INDEX IF NOT EXISTS idx_graph_target ON relationship_graph(target_type, target_id);
+        CREATE INDEX IF NOT EXISTS idx_graph_type ON relationship_graph(relationship_type);
+
+        -- Fuel Price Cache: AI-searched prices
+        CREATE TABLE IF NOT EXISTS fuel_price_cache (
+            id TEXT PRIMARY KEY,
+            airport_code TEXT,
+            location_name TEXT NOT NULL,
+            region TEXT,
+            country TEXT,
+            fuel_type TEXT NOT NULL DEFAULT 'jet_a',
+            price_per_gallon REAL NOT NULL,
+            price_per_liter REAL,
+            currency TEXT DEFAULT 'USD',
+            effective_date TEXT NOT NULL,
+            source TEXT,
+            source_url TEXT,
+            confidence TEXT DEFAULT 'medium',
+            ai_response TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            expires_at TEXT,
+            UNIQUE(airport_code, fuel_type, effective_date)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_fuel_cache_airport ON fuel_price_cache(airport_code);
+        CREATE INDEX IF NOT EXISTS idx_fuel_cache_type ON fuel_price_cache(fuel_type);
+        CREATE INDEX IF NOT EXISTS idx_fuel_cache_date ON fuel_price_cache(effective_date);
+        CREATE INDEX IF NOT EXISTS idx_fuel_cache_region ON fuel_price_cache(region);
+
+        -- Fuel Entries: User's actual fuel purchases
+        CREATE TABLE IF NOT EXISTS fuel_entries (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            flight_id TEXT,
+            aircraft_id TEXT,
+            airport_code TEXT,
+            location_name TEXT,
+            fuel_type TEXT NOT NULL DEFAULT 'jet_a',
+            gallons REAL NOT NULL,
+            price_per_gallon REAL NOT NULL,
+            total_cost REAL NOT NULL,
+            currency TEXT DEFAULT 'USD',
+            purchase_date TEXT NOT NULL,
+            fbo_name TEXT,
+            receipt_number TEXT,
+            notes TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_fuel_entries_user ON fuel_entries(user_id);
+        CREATE INDEX IF NOT EXISTS idx_fuel_entries_flight ON fuel_entries(flight_id);
+        CREATE INDEX IF NOT EXISTS idx_fuel_entries_airport ON fuel_entries(airport_code);
+        CREATE INDEX IF NOT EXISTS idx_fuel_entries_date ON fuel_entries(purchase_date);
+
+        -- Fuel Types: Custom fuel types defined by users
+        CREATE TABLE IF NOT EXISTS fuel_types (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            code TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            category TEXT DEFAULT 'aviation',
+            is_default INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            UNIQUE(user_id, code)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_fuel_types_user ON fuel_types(user_id);
+        CREATE INDEX IF NOT EXISTS idx_fuel_types_code ON fuel_types(code);
+
+        -- Custom Schemas: User-defined entity types
+        CREATE TABLE IF NOT EXISTS custom_schemas (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            description TEXT,
+            icon TEXT DEFAULT 'database',
+            color TEXT DEFAULT '#6366f1',
+            is_active INTEGER DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            UNIQUE(user_id, name)
+        );
+
+        -- Custom Schema Fields: Field definitions for each schema
+        CREATE TABLE IF NOT EXISTS custom_schema_fields (
+            id TEXT PRIMARY KEY,
+            schema_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            field_type TEXT NOT NULL,
+            is_required INTEGER DEFAULT 0,
+            default_value TEXT,
+            options TEXT,
+            validation_rules TEXT,
+            sort_order INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (schema_id) REFERENCES custom_schemas(id) ON DELETE CASCADE,
+            UNIQUE(schema_id, name)
+        );
+
+        -- Custom Entity Records: Actual data for custom schemas
+        CREATE TABLE IF NOT EXISTS custom_records (
+            id TEXT PRIMARY KEY,
+            schema_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (schema_id) REFERENCES custom_schemas(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        -- Flight Custom Fields: Extra fields on flight records
+        CREATE TABLE IF NOT EXISTS flight_custom_fields (
+            id TEXT PRIMARY KEY,
+            flight_id TEXT NOT NULL,
+            field_name TEXT NOT NULL,
+            field_value TEXT,
+            field_type TEXT NOT NULL DEFAULT 'text',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE,
+            UNIQUE(flight_id, field_name)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_custom_schemas_user ON custom_schemas(user_id);
+        CREATE INDEX IF NOT EXISTS idx_custom_fields_schema ON custom_schema_fields(schema_id);
+        CREATE INDEX IF NOT EXISTS idx_custom_records_schema ON custom_records(schema_id);
+        CREATE INDEX IF NOT EXISTS idx_custom_records_user ON custom_records(user_id);
+        CREATE INDEX IF NOT EXISTS idx_flight_custom_fields ON flight_custom_fields(flight_id);
+
+        -- ===== SELF-IMPROVEMENT SYSTEM =====
+
+        -- User Corrections: Track corrections for auto-fill learning
+        CREATE TABLE IF NOT EXISTS user_corrections (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            field_name TEXT NOT NULL,
+            original_value TEXT,
+            corrected_value TEXT NOT NULL,
+            context TEXT,
+            occurrence_count INTEGER DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(user_id, field_name, original_value, corrected_value)
+        );
+
+        -- User Patterns: Track behavioral patterns for smart defaults
+        CREATE TABLE IF NOT EXISTS user_patterns (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            pattern_type TEXT NOT NULL,
+            pattern_key TEXT NOT NULL,
+            pattern_value TEXT NOT NULL,
+            frequency INTEGER DEFAULT 1,
+            last_used TEXT NOT NULL DEFAULT (datetime('now')),
+            metadata TEXT,
+            UNIQUE(user_id, pattern_type, pattern_key)
+        );
+
+        -- Query Performance: Track slow queries for optimization
+        CREATE TABLE IF NOT EXISTS query_performance (
+            id TEXT PRIMARY KEY,
+            query_hash TEXT NOT NULL,
+            query_type TEXT NOT NULL,
+            table_name TEXT,
+            filters TEXT,
+            execution_time_ms INTEGER NOT NULL,
+            result_count INTEGER,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- AI Response Cache: Cache AI responses for cost optimization
+        CREATE TABLE IF NOT EXISTS ai_response_cache (
+            id TEXT PRIMARY KEY,
+            query_hash TEXT NOT NULL UNIQUE,
+            provider TEXT NOT NULL,
+            query_text TEXT NOT NULL,
+            response_text TEXT NOT NULL,
+            confidence REAL,
+            hit_count INTEGER DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            expires_at TEXT,
+            metadata TEXT
+        );
+
+        -- Flight Anomalies: Detected anomalies for data integrity
+        CREATE TABLE IF NOT EXISTS flight_anomalies (
+            id TEXT PRIMARY KEY,
+            flight_id TEXT NOT NULL,
+            anomaly_type TEXT NOT NULL,
+            severity TEXT DEFAULT 'warning',
+            description TEXT NOT NULL,
+            suggested_fix TEXT,
+            is_resolved INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            resolved_at TEXT,
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE
+        );
+
+        -- Duplicate Candidates: Potential duplicate flights
+        CREATE TABLE IF NOT EXISTS duplicate_candidates (
+            id TEXT PRIMARY KEY,
+            flight_id_1 TEXT NOT NULL,
+            flight_id_2 TEXT NOT NULL,
+            similarity_score REAL NOT NULL,
+            match_reasons TEXT,
+            status TEXT DEFAULT 'pending',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            resolved_at TEXT,
+            FOREIGN KEY (flight_id_1) REFERENCES flights(id) ON DELETE CASCADE,
+            FOREIGN KEY (flight_id_2) REFERENCES flights(id) ON DELETE CASCADE
+        );
+
+        -- Provider Accuracy: Track AI provider accuracy for triangulation
+        CREATE TABLE IF NOT EXISTS provider_accuracy (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            query_type TEXT NOT NULL,
+            correct_count INTEGER DEFAULT 0,
+            total_count INTEGER DEFAULT 0,
+            avg_confidence REAL,
+            last_updated TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(provider, query_type)
+        );
+
+        -- Route Statistics: Learned route patterns
+        CREATE TABLE IF NOT EXISTS route_statistics (
+            id TEXT PRIMARY KEY,
+            departure_airport TEXT NOT NULL,
+            arrival_airport TEXT NOT NULL,
+            avg_duration_minutes REAL,
+            avg_distance_km REAL,
+            common_aircraft TEXT,
+            flight_count INTEGER DEFAULT 1,
+            last_updated TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(departure_airport, arrival_airport)
+        );
+
+        -- Fuel Price History: Persistent fuel price database
+        CREATE TABLE IF NOT EXISTS fuel_price_history (
+            id TEXT PRIMARY KEY,
+            airport_code TEXT,
+            location_name TEXT NOT NULL,
+            region TEXT,
+            country TEXT,
+            fuel_type TEXT NOT NULL DEFAULT 'jet_a',
+            price_per_gallon REAL NOT NULL,
+            price_per_liter REAL,
+            currency TEXT DEFAULT 'USD',
+            effective_date TEXT NOT NULL,
+            source TEXT NOT NULL,
+            source_url TEXT,
+            verified INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_corrections_user ON user_corrections(user_id);
+        CREATE INDEX IF NOT EXISTS idx_patterns_user ON user_patterns(user_id, pattern_type);
+        CREATE INDEX IF NOT EXISTS idx_query_perf_hash ON query_performance(query_hash);
+        CREATE INDEX IF NOT EXISTS idx_ai_cache_hash ON ai_response_cache(query_hash);
+        CREATE INDEX IF NOT EXISTS idx_anomalies_flight ON flight_anomalies(flight_id);
+        CREATE INDEX IF NOT EXISTS idx_duplicates_flights ON duplicate_candidates(flight_id_1, flight_id_2);
+        CREATE INDEX IF NOT EXISTS idx_route_stats ON route_statistics(departure_airport, arrival_airport);
+        CREATE INDEX IF NOT EXISTS idx_fuel_history_location ON fuel_price_history(airport_code, fuel_type);
+        CREATE INDEX IF NOT EXISTS idx_fuel_history_date ON fuel_price_history(effective_date);
+
+        CREATE TABLE IF NOT EXISTS ocr_corrections (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            field_name TEXT NOT NULL,
+            original_value TEXT,
+            corrected_value TEXT NOT NULL,
+            image_hash TEXT,
+            confidence_score REAL,
+            pattern_type TEXT,
+            applied_count INTEGER DEFAULT 0,
+            verified INTEGER DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_applied TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_ocr_corrections_user ON ocr_corrections(user_id);
+        CREATE INDEX IF NOT EXISTS idx_ocr_corrections_field ON ocr_corrections(field_name);
+        CREATE INDEX IF NOT EXISTS idx_ocr_corrections_original ON ocr_corrections(original_value);
+        CREATE INDEX IF NOT EXISTS idx_ocr_corrections_created ON ocr_corrections(created_at);
+
+        CREATE TABLE IF NOT EXISTS ocr_learning_patterns (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            pattern_type TEXT NOT NULL,
+            field_name TEXT NOT NULL,
+            match_pattern TEXT NOT NULL,
+            replacement_value TEXT NOT NULL,
+            confidence REAL DEFAULT 0.5,
+            occurrence_count INTEGER DEFAULT 1,
+            success_count INTEGER DEFAULT 0,
+            rejection_count INTEGER DEFAULT 0,
+            is_active INTEGER DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_used TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_learning_patterns_user ON ocr_learning_patterns(user_id);
+        CREATE INDEX IF NOT EXISTS idx_learning_patterns_field ON ocr_learning_patterns(field_name);
+        CREATE INDEX IF NOT EXISTS idx_learning_patterns_type ON ocr_learning_patterns(pattern_type);
+        CREATE INDEX IF NOT EXISTS idx_learning_patterns_active ON ocr_learning_patterns(is_active);
+
+        -- ===== IDENTITY FUSION ARCHITECTURE =====
+        -- Canonical Identity Table (The Master Records)
+        CREATE TABLE IF NOT EXISTS passengers (
+            id TEXT PRIMARY KEY,
+            canonical_name TEXT NOT NULL UNIQUE,
+            notes TEXT,
+            total_flights INTEGER DEFAULT 0,
+            first_seen_date TEXT,
+            last_seen_date TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_passengers_canonical_name ON passengers(canonical_name);
+
+        -- Passenger Aliases Table (Variations linked to Master)
+        CREATE TABLE IF NOT EXISTS passenger_aliases (
+            id TEXT PRIMARY KEY,
+            passenger_id TEXT NOT NULL,
+            raw_name TEXT NOT NULL UNIQUE,
+            usage_count INTEGER DEFAULT 0,
+            source_document TEXT,
+            match_type TEXT,
+            confidence REAL DEFAULT 1.0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_passenger_aliases_raw ON passenger_aliases(raw_name);
+        CREATE INDEX IF NOT EXISTS idx_passenger_aliases_passenger ON passenger_aliases(passenger_id);
+
+        -- Flight-Passenger Junction Table (Many-to-Many)
+        CREATE TABLE IF NOT EXISTS flight_passengers (
+            flight_id TEXT NOT NULL,
+            passenger_id TEXT NOT NULL,
+            seat_info TEXT,
+            role TEXT DEFAULT 'passenger',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (flight_id, passenger_id),
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE,
+            FOREIGN KEY (passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_flight_passengers_flight ON flight_passengers(flight_id);
+        CREATE INDEX IF NOT EXISTS idx_flight_passengers_passenger ON flight_passengers(passenger_id);
+
+        -- Do Not Deduplicate List: Passengers that should remain separate
+        CREATE TABLE IF NOT EXISTS passenger_no_dedup (
+            passenger_id TEXT PRIMARY KEY,
+            reason TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
+        );
+
+        -- Flight Tags: Lightweight classification (training, commercial, personal...)
+        -- User scope comes from the owning flight
+        CREATE TABLE IF NOT EXISTS flight_tags (
+            flight_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (flight_id, tag),
+            FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_flight_tags_flight ON flight_tags(flight_id);
+        CREATE INDEX IF NOT EXISTS idx_flight_tags_tag ON flight_tags(tag);",
+        )],
+    },
+    Migration {
+        version: 2,
+        name: "custom_documents entity links",
+        steps: &[
+            Step::AddColumn {
+                table: "custom_documents",
+                column: "journey_id",
+                definition: "TEXT REFERENCES journeys(id) ON DELETE SET NULL",
+            },
+            Step::AddColumn { table: "custom_documents", column: "passenger_name", definition: "TEXT" },
+            Step::AddColumn {
+                table: "custom_documents",
+                column: "fuel_entry_id",
+                definition: "TEXT REFERENCES fuel_entries(id) ON DELETE SET NULL",
+            },
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_custom_documents_journey ON custom_documents(journey_id);
+                 CREATE INDEX IF NOT EXISTS idx_custom_documents_passenger ON custom_documents(passenger_name);
+                 CREATE INDEX IF NOT EXISTS idx_custom_documents_fuel ON custom_documents(fuel_entry_id);",
+            ),
+        ],
+    },
+    Migration {
+        version: 3,
+        // CO2 share per passenger (total CO2 / passenger count, excluding crew)
+        name: "flights.per_passenger_co2_kg",
+        steps: &[Step::AddColumn { table: "flights", column: "per_passenger_co2_kg", definition: "REAL" }],
+    },
+    Migration {
+        version: 4,
+        name: "ocr_learning_patterns.applied_count",
+        steps: &[Step::AddColumn {
+            table: "ocr_learning_patterns",
+            column: "applied_count",
+            definition: "INTEGER DEFAULT 0",
+        }],
+    },
+    Migration {
+        version: 5,
+        name: "investigations.evidence_json",
+        steps: &[Step::AddColumn { table: "investigations", column: "evidence_json", definition: "TEXT" }],
+    },
+    Migration {
+        version: 6,
+        // "equal" or "class_weighted"
+        name: "flights.co2_split_method",
+        steps: &[Step::AddColumn { table: "flights", column: "co2_split_method", definition: "TEXT" }],
+    },
+    Migration {
+        version: 7,
+        // Soft-delete for the recycle bin; NULL means the flight is live
+        name: "flights.deleted_at",
+        steps: &[
+            Step::AddColumn { table: "flights", column: "deleted_at", definition: "TEXT" },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_flights_deleted_at ON flights(deleted_at)"),
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "research_reports.archived",
+        steps: &[Step::AddColumn {
+            table: "research_reports",
+            column: "archived",
+            definition: "INTEGER NOT NULL DEFAULT 0",
+        }],
+    },
+    Migration {
+        version: 9,
+        // coordinate_source: "csv" (bundled OurAirports data), "geocoded" (online fallback),
+        // NULL for coordinates entered by hand
+        name: "airport coordinate source and geocode cache",
+        steps: &[
+            Step::AddColumn { table: "airports", column: "coordinate_source", definition: "TEXT" },
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS geocode_cache (
+                    query TEXT PRIMARY KEY,
+                    found INTEGER NOT NULL,
+                    latitude REAL,
+                    longitude REAL,
+                    display_name TEXT,
+                    provider TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                )",
+            ),
+        ],
+    },
+    Migration {
+        version: 10,
+        // IATA designator of the operating carrier, inferred from flight_number
+        name: "flight airline",
+        steps: &[
+            Step::AddColumn { table: "flights", column: "airline", definition: "TEXT" },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_flights_user_airline ON flights(user_id, airline)"),
+        ],
+    },
+    Migration {
+        version: 11,
+        // One row per flight credited to a frequent flyer program, so it is never credited twice
+        name: "ffp credits",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS ffp_credits (
+                flight_id TEXT NOT NULL,
+                ffp_id TEXT NOT NULL,
+                miles REAL NOT NULL,
+                tier_miles REAL NOT NULL,
+                credited_at TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (flight_id, ffp_id),
+                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE,
+                FOREIGN KEY (ffp_id) REFERENCES frequent_flyer_programs(id) ON DELETE CASCADE
+            )",
+        )],
+    },
+    Migration {
+        version: 12,
+        // determined = 0 records that research found no weather, so it is not asked again
+        name: "flight weather",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS flight_weather (
+                id TEXT PRIMARY KEY,
+                flight_id TEXT NOT NULL,
+                observation_date TEXT NOT NULL,
+                location TEXT,
+                determined INTEGER NOT NULL DEFAULT 0,
+                conditions TEXT,
+                temperature_c REAL,
+                wind_direction_deg INTEGER,
+                wind_speed_kt REAL,
+                visibility_km REAL,
+                description TEXT,
+                source TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                UNIQUE (flight_id, observation_date),
+                FOREIGN KEY (flight_id) REFERENCES flights(id) ON DELETE CASCADE
+            )",
+        )],
+    },
+    Migration {
+        version: 13,
+        // User-defined research prompts; the built-in defaults live in prompt_templates.rs
+        name: "research prompt templates",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS research_prompt_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+        )],
+    },
+    Migration {
+        version: 14,
+        // Reusable flight field sets; fields is a FlightInput as JSON (see flight_templates.rs)
+        name: "flight templates",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS flight_templates (
+                    id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    fields TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+                )",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_flight_templates_user ON flight_templates(user_id)"),
+        ],
+    },
+    Migration {
+        version: 15,
+        // Timetable times, local to the airport like departure/arrival_datetime; the
+        // difference to the actual times is the delay
+        name: "flight schedule",
+        steps: &[
+            Step::AddColumn { table: "flights", column: "scheduled_departure", definition: "TEXT" },
+            Step::AddColumn { table: "flights", column: "scheduled_arrival", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 16,
+        // Field elevation from the bundled OurAirports CSV, filled in by airport enrichment
+        name: "airports.elevation_ft",
+        steps: &[Step::AddColumn { table: "airports", column: "elevation_ft", definition: "INTEGER" }],
+    },
+    Migration {
+        version: 17,
+        // Signed logbook entries are read-only; every lock and unlock is recorded in the
+        // audit table, which has no foreign key so the trail outlives the entry
+        name: "logbook signatures",
+        steps: &[
+            Step::AddColumn { table: "pilot_logbook", column: "locked", definition: "INTEGER NOT NULL DEFAULT 0" },
+            Step::AddColumn { table: "pilot_logbook", column: "signed_by", definition: "TEXT" },
+            Step::AddColumn { table: "pilot_logbook", column: "signed_at", definition: "TEXT" },
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS pilot_logbook_audit (
+                    id TEXT PRIMARY KEY,
+                    entry_id TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    actor TEXT,
+                    reason TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                )",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_pilot_logbook_audit_entry ON pilot_logbook_audit(entry_id)"),
+        ],
+    },
+    Migration {
+        version: 18,
+        // Expirable medicals, reviews and ratings (see pilot_credentials.rs); dates are YYYY-MM-DD
+        name: "pilot credentials",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS pilot_credentials (
+                    id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL,
+                    credential_type TEXT NOT NULL,
+                    name TEXT,
+                    issued_date TEXT,
+                    expires_date TEXT,
+                    notes TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+                )",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_pilot_credentials_user ON pilot_credentials(user_id)"),
+        ],
+    },
+    Migration {
+        version: 19,
+        // Data-wide operations (dataset merges, ...); details is JSON. No foreign keys, the
+        // records usually describe rows that no longer exist
+        name: "audit log",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                action TEXT NOT NULL,
+                subject_id TEXT,
+                details TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+        )],
+    },
+    Migration {
+        version: 20,
+        // One row per AI cache lookup, hit or miss, for hit rates over a period (see
+        // ai_cache.rs). hit_count now counts only responses served from the cache, so the
+        // 1 every row started with is taken back off
+        name: "ai cache lookups",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS ai_cache_lookups (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    query_hash TEXT NOT NULL,
+                    hit INTEGER NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                )",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_ai_cache_lookups_created ON ai_cache_lookups(created_at)"),
+            Step::Sql("UPDATE ai_response_cache SET hit_count = MAX(COALESCE(hit_count, 1) - 1, 0)"),
+        ],
+    },
+    Migration {
+        version: 21,
+        // Flight-data API lookups per flight number and date (see flight_verification.rs);
+        // legs is the JSON list of legs the flight number operated
+        name: "flight verification cache",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS flight_verification_cache (
+                flight_number TEXT NOT NULL,
+                flight_date TEXT NOT NULL,
+                legs TEXT NOT NULL,
+                fetched_at TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (flight_number, flight_date)
+            )",
+        )],
+    },
+    Migration {
+        version: 22,
+        // Recurring maintenance items per aircraft (see maintenance.rs); an item has an hour
+        // interval, a day interval or both
+        name: "maintenance schedules",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS maintenance_schedules (
+                    id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL,
+                    aircraft_registration TEXT NOT NULL,
+                    item TEXT NOT NULL,
+                    interval_hours REAL,
+                    interval_days INTEGER,
+                    last_done_hours REAL,
+                    last_done_date TEXT,
+                    notes TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    CHECK (interval_hours IS NOT NULL OR interval_days IS NOT NULL),
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+                )",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_maintenance_schedules_aircraft
+                 ON maintenance_schedules(user_id, aircraft_registration)",
+            ),
+        ],
+    },
+    Migration {
+        version: 23,
+        // Passengers on board, excluding crew; filled on create/import when known and
+        // derived once from "Passengers: ..." notes otherwise
+        name: "flights.passenger_count",
+        steps: &[Step::AddColumn { table: "flights", column: "passenger_count", definition: "INTEGER" }],
+    },
+    Migration {
+        version: 24,
+        // Identity bootstrap controls: name pairs never to merge (stored uppercase, name_a <
+        // name_b) and near matches waiting for the user to merge or keep apart
+        name: "passenger merge review",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS passenger_never_merge (
+                    name_a TEXT NOT NULL,
+                    name_b TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (name_a, name_b)
+                )",
+            ),
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS passenger_merge_queue (
+                    id TEXT PRIMARY KEY,
+                    source_name TEXT NOT NULL,
+                    target_passenger_id TEXT NOT NULL,
+                    similarity REAL NOT NULL,
+                    match_type TEXT NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending',
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    resolved_at TEXT,
+                    UNIQUE (source_name, target_passenger_id),
+                    FOREIGN KEY (target_passenger_id) REFERENCES passengers(id) ON DELETE CASCADE
+                )",
+            ),
+        ],
+    },
+    Migration {
+        version: 25,
+        // How a flight's currency was set: "explicit", "airport_country" or "user_default"
+        // (see currency.rs); left NULL on existing flights, whose origin is unknown
+        name: "flights.currency_source",
+        steps: &[Step::AddColumn { table: "flights", column: "currency_source", definition: "TEXT" }],
+    },
+    Migration {
+        version: 26,
+        // Performance profiles feeding flight time, fuel and CO2 estimates (see
+        // aircraft_performance.rs), keyed by normalized type designator or registration.
+        // Shipped defaults for common types; piston types burn avgas at 3.10 kg CO2/kg.
+        name: "aircraft performance profiles",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS aircraft_performance (
+                    id TEXT PRIMARY KEY,
+                    aircraft_key TEXT NOT NULL UNIQUE,
+                    key_kind TEXT NOT NULL CHECK (key_kind IN ('type', 'registration')),
+                    cruise_kts REAL NOT NULL CHECK (cruise_kts > 0),
+                    burn_gph REAL NOT NULL CHECK (burn_gph > 0),
+                    co2_factor REAL NOT NULL DEFAULT 3.15,
+                    seats INTEGER,
+                    is_default INTEGER NOT NULL DEFAULT 0,
+                    notes TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                )",
+            ),
+            Step::Sql(
+                "INSERT OR IGNORE INTO aircraft_performance
+                    (id, aircraft_key, key_kind, cruise_kts, burn_gph, co2_factor, seats, is_default)
+                 VALUES
+                    ('default-b744', 'B744', 'type', 490, 3600, 3.15, 416, 1),
+                    ('default-a388', 'A388', 'type', 490, 3600, 3.15, 525, 1),
+                    ('default-b77w', 'B77W', 'type', 490, 2450, 3.15, 365, 1),
+                    ('default-b772', 'B772', 'type', 490, 2150, 3.15, 314, 1),
+                    ('default-b788', 'B788', 'type', 488, 1600, 3.15, 242, 1),
+                    ('default-b789', 'B789', 'type', 488, 1750, 3.15, 290, 1),
+                    ('default-a359', 'A359', 'type', 488, 1900, 3.15, 325, 1),
+                    ('default-a333', 'A333', 'type', 470, 1900, 3.15, 277, 1),
+                    ('default-b738', 'B738', 'type', 453, 830, 3.15, 162, 1),
+                    ('default-b38m', 'B38M', 'type', 453, 720, 3.15, 172, 1),
+                    ('default-a319', 'A319', 'type', 450, 750, 3.15, 140, 1),
+                    ('default-a320', 'A320', 'type', 450, 800, 3.15, 174, 1),
+                    ('default-a20n', 'A20N', 'type', 450, 700, 3.15, 180, 1),
+                    ('default-a321', 'A321', 'type', 450, 900, 3.15, 200, 1),
+                    ('default-e175', 'E175', 'type', 447, 500, 3.15, 76, 1),
+                    ('default-e190', 'E190', 'type', 447, 560, 3.15, 100, 1),
+                    ('default-crj9', 'CRJ9', 'type', 447, 500, 3.15, 86, 1),
+                    ('default-at76', 'AT76', 'type', 275, 215, 3.15, 70, 1),
+                    ('default-dh8d', 'DH8D', 'type', 360, 320, 3.15, 78, 1),
+                    ('default-c56x', 'C56X', 'type', 430, 210, 3.15, 8, 1),
+                    ('default-cl60', 'CL60', 'type', 459, 330, 3.15, 10, 1),
+                    ('default-glf5', 'GLF5', 'type', 488, 450, 3.15, 16, 1),
+                    ('default-glex', 'GLEX', 'type', 488, 500, 3.15, 13, 1),
+                    ('default-lj45', 'LJ45', 'type', 450, 200, 3.15, 8, 1),
+                    ('default-pc12', 'PC12', 'type', 270, 60, 3.15, 9, 1),
+                    ('default-c172', 'C172', 'type', 122, 8.5, 3.1, 4, 1),
+                    ('default-c182', 'C182', 'type', 145, 13, 3.1, 4, 1),
+                    ('default-pa28', 'PA28', 'type', 125, 9, 3.1, 4, 1),
+                    ('default-sr22', 'SR22', 'type', 180, 17, 3.1, 4, 1)",
+            ),
+        ],
+    },
+    Migration {
+        version: 27,
+        // Last alert sent per expiring item (see expiry_alerts.rs), so the same expiry is not
+        // alerted on every check
+        name: "expiry notifications",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS expiry_notifications (
+                user_id TEXT NOT NULL,
+                item_key TEXT NOT NULL,
+                label TEXT NOT NULL,
+                expires_date TEXT NOT NULL,
+                stage TEXT NOT NULL,
+                last_notified_at TEXT NOT NULL,
+                PRIMARY KEY (user_id, item_key),
+                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+            )",
+        )],
+    },
+    Migration {
+        version: 28,
+        // Sampled network connections for the sentinel's history queries (see
+        // network_history.rs), kept within the network_flows retention policy
+        name: "network flow history",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS network_flows (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    sampled_at TEXT NOT NULL,
+                    process_name TEXT NOT NULL,
+                    pid INTEGER NOT NULL DEFAULT 0,
+                    protocol TEXT NOT NULL,
+                    direction TEXT NOT NULL,
+                    local_addr TEXT NOT NULL,
+                    local_port INTEGER NOT NULL,
+                    remote_addr TEXT NOT NULL,
+                    remote_port INTEGER NOT NULL,
+                    bytes_sent INTEGER NOT NULL DEFAULT 0,
+                    bytes_recv INTEGER NOT NULL DEFAULT 0,
+                    is_anomaly INTEGER NOT NULL DEFAULT 0,
+                    anomaly_reason TEXT
+                )",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_network_flows_sampled_at ON network_flows(sampled_at)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_network_flows_remote_addr ON network_flows(remote_addr, sampled_at)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_network_flows_process ON network_flows(process_name, sampled_at)"),
+        ],
+    },
+    Migration {
+        version: 29,
+        // Longest runway from user airport imports (see airport_import.rs), which also
+        // mark the coordinates they bring with coordinate_source "import"
+        name: "airports.longest_runway_ft",
+        steps: &[Step::AddColumn { table: "airports", column: "longest_runway_ft", definition: "INTEGER" }],
+    },
+    Migration {
+        version: 30,
+        // list_flights matches registrations case-insensitively; the index must use the
+        // same collation or SQLite cannot use it for that comparison
+        name: "flights registration index",
+        steps: &[Step::Sql(
+            "CREATE INDEX IF NOT EXISTS idx_flights_user_registration
+             ON flights(user_id, aircraft_registration COLLATE NOCASE)",
+        )],
+    },
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub name: String,
+    /// When it was applied; None for pending migrations
+    pub applied_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub current_version: i64,
+    pub latest_version: i64,
+    pub applied: Vec<MigrationInfo>,
+    pub pending: Vec<MigrationInfo>,
+}
+
+/// Highest migration version this build knows about
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )
+    .context("Failed to create schema_migrations table")
+}
+
+/// Highest applied version, 0 when none (or when the table does not exist yet)
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    let has_table: bool = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+            [],
+            |_| Ok(true),
+        )
+        .optional()?
+        .unwrap_or(false);
+    if !has_table {
+        return Ok(0);
+    }
+
+    let version: Option<i64> = conn.query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Applied and pending migrations without changing anything (dry run)
+pub fn migration_status(conn: &Connection) -> Result<MigrationStatus> {
+    let current = current_version(conn)?;
+
+    let mut applied = Vec::new();
+    if current > 0 {
+        let mut stmt = conn.prepare("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(MigrationInfo { version: row.get(0)?, name: row.get(1)?, applied_at: row.get(2)? })
+        })?;
+        for row in rows {
+            applied.push(row?);
+        }
+    }
+
+    let pending = MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|a: &MigrationInfo| a.version == m.version))
+        .map(|m| MigrationInfo { version: m.version, name: m.name.to_string(), applied_at: None })
+        .collect();
+
+    Ok(MigrationStatus { current_version: current, latest_version: latest_version(), applied, pending })
+}
+
+/// Apply every pending migration in version order
+pub fn run_migrations(conn: &Connection) -> Result<()> {
+    ensure_migrations_table(conn)?;
+
+    let current = current_version(conn)?;
+    if current > latest_version() {
+        bail!(
+            "Database schema version {} is newer than this build supports ({}); update the app",
+            current,
+            latest_version()
+        );
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        apply_migration(conn, migration)
+            .with_context(|| format!("Migration {} ({}) failed", migration.version, migration.name))?;
+    }
+
+    Ok(())
+}
+
+fn apply_migration(conn: &Connection, migration: &Migration) -> Result<()> {
+    conn.execute_batch("BEGIN")?;
+
+    let result = (|| -> Result<()> {
+        for step in migration.steps {
+            match step {
+                Step::Sql(sql) => conn.execute_batch(sql)?,
+                Step::AddColumn { table, column, definition } => {
+                    if !column_exists(conn, table, column)? {
+                        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+                    }
+                }
+            }
+        }
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            params![migration.version, migration.name],
+        )?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            conn.execute_batch("COMMIT")?;
+            Ok(())
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in columns {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("schema.sql")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_versions_are_sequential() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i64 + 1, "{}", migration.name);
+        }
+    }
+
+    #[test]
+    fn test_migrations_run_once() {
+        let conn = fresh_conn();
+        assert_eq!(migration_status(&conn).unwrap().pending.len(), MIGRATIONS.len());
+
+        run_migrations(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+
+        let status = migration_status(&conn).unwrap();
+        assert_eq!(status.current_version, latest_version());
+        assert_eq!(status.applied.len(), MIGRATIONS.len());
+        assert!(status.pending.is_empty());
+    }
+
+    #[test]
+    fn test_adopts_unversioned_database() {
+        // A database that already ran the old unversioned ALTERs
+        let conn = fresh_conn();
+        conn.execute_batch("ALTER TABLE flights ADD COLUMN per_passenger_co2_kg REAL").unwrap();
+
+        run_migrations(&conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = fresh_conn();
+        ensure_migrations_table(&conn).unwrap();
+        let broken = Migration {
+            version: 1,
+            name: "broken",
+            steps: &[
+                Step::Sql("CREATE TABLE rollback_probe (id INTEGER)"),
+                Step::Sql("ALTER TABLE no_such_table ADD COLUMN x TEXT"),
+            ],
+        };
+
+        assert!(apply_migration(&conn, &broken).is_err());
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        assert!(!column_exists(&conn, "rollback_probe", "id").unwrap());
+    }
+}
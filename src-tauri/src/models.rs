@@ -1,6 +1,7 @@
 // Data models for Flight Tracker Pro
 
 use serde::{Deserialize, Serialize};
+use crate::units::Measurements;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -25,6 +26,41 @@ pub struct Airport {
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
     pub timezone: Option<String>,
+    /// Where the coordinates came from: "csv", "geocoded", or None when entered by hand
+    #[serde(default)]
+    pub coordinate_source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateAirportPair {
+    pub airport_a: Airport,
+    pub airport_b: Airport,
+    pub reason: String,
+    pub distance_km: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportFieldDiff {
+    pub field: String,
+    pub keep_value: String,
+    pub remove_value: String,
+    pub merged_value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportCodeRewrite {
+    pub from_code: String,
+    pub to_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportMergePreview {
+    pub keep: Airport,
+    pub remove: Airport,
+    pub merged: Airport,
+    pub differences: Vec<AirportFieldDiff>,
+    pub code_rewrites: Vec<AirportCodeRewrite>,
+    pub flight_references: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,6 +131,66 @@ pub struct Flight {
     pub verified: i32,
     pub created_at: String,
     pub updated_at: String,
+    /// IATA designator of the operating carrier, inferred from flight_number
+    #[serde(default)]
+    pub airline: Option<String>,
+    /// Timetable departure/arrival, local time; None when the schedule is unknown
+    #[serde(default)]
+    pub scheduled_departure: Option<String>,
+    #[serde(default)]
+    pub scheduled_arrival: Option<String>,
+    /// Passengers on board, excluding crew; None when unknown
+    #[serde(default)]
+    pub passenger_count: Option<i32>,
+    /// How the currency was set: "explicit", "airport_country" or "user_default" (see currency.rs);
+    /// None for flights recorded before it was tracked
+    #[serde(default)]
+    pub currency_source: Option<String>,
+}
+
+/// Optional filters and ordering for list_flights; the default lists everything, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlightListFilter {
+    /// Inclusive YYYY-MM-DD bounds on the departure date
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// Matches the departure OR arrival airport
+    pub airport: Option<String>,
+    /// Aircraft registration (case-insensitive)
+    pub aircraft: Option<String>,
+    /// departure_datetime (default), arrival_datetime, departure_airport, arrival_airport,
+    /// distance_km, flight_duration, total_cost, created_at
+    pub sort_by: Option<String>,
+    /// "asc" or "desc" (default)
+    pub sort_dir: Option<String>,
+}
+
+/// One page of a list plus the total across all pages, read in the same transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total_count: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total_count: i64, limit: i64, offset: i64) -> Self {
+        let has_more = offset + (items.len() as i64) < total_count;
+        Self { items, total_count, limit, offset, has_more }
+    }
+}
+
+/// Flight sitting in the recycle bin (soft-deleted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedFlight {
+    pub id: String,
+    pub flight_number: Option<String>,
+    pub departure_airport: String,
+    pub arrival_airport: String,
+    pub departure_datetime: String,
+    pub deleted_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,6 +217,40 @@ pub struct FlightInput {
     pub currency: Option<String>,
     pub notes: Option<String>,
     pub attachment_path: Option<String>,
+    /// Passengers on board, excluding crew, when known
+    #[serde(default)]
+    pub passenger_count: Option<i32>,
+}
+
+/// The editable fields of a stored flight
+impl From<&Flight> for FlightInput {
+    fn from(flight: &Flight) -> Self {
+        Self {
+            flight_number: flight.flight_number.clone(),
+            departure_airport: flight.departure_airport.clone(),
+            arrival_airport: flight.arrival_airport.clone(),
+            departure_datetime: flight.departure_datetime.clone(),
+            arrival_datetime: flight.arrival_datetime.clone(),
+            aircraft_type_id: flight.aircraft_type_id.clone(),
+            aircraft_registration: flight.aircraft_registration.clone(),
+            total_duration: flight.total_duration,
+            flight_duration: flight.flight_duration,
+            distance_nm: flight.distance_nm,
+            distance_km: flight.distance_km,
+            carbon_emissions_kg: flight.carbon_emissions_kg,
+            booking_reference: flight.booking_reference.clone(),
+            ticket_number: flight.ticket_number.clone(),
+            seat_number: flight.seat_number.clone(),
+            fare_class: flight.fare_class.clone(),
+            base_fare: flight.base_fare,
+            taxes: flight.taxes,
+            total_cost: flight.total_cost,
+            currency: flight.currency.clone(),
+            notes: flight.notes.clone(),
+            attachment_path: flight.attachment_path.clone(),
+            passenger_count: flight.passenger_count,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,6 +285,22 @@ pub struct PilotLogbook {
     pub route: Option<String>,
     pub remarks: Option<String>,
     pub endorsements: Option<String>,
+    /// Signed entries are read-only until unlocked
+    pub locked: bool,
+    pub signed_by: Option<String>,
+    pub signed_at: Option<String>,
+}
+
+/// One lock/unlock of a logbook entry; kept after the entry itself is deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogbookAuditEntry {
+    pub id: String,
+    pub entry_id: String,
+    /// "lock" or "unlock"
+    pub action: String,
+    pub actor: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -213,6 +359,111 @@ pub struct FlightStatistics {
     pub total_carbon_kg: f64,
     pub favorite_aircraft: Option<String>,
     pub favorite_route: Option<String>,
+    /// Totals converted to the configured unit system; filled in by the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurements: Option<Measurements>,
+}
+
+/// Flight, distance, time and carbon totals for one group of flights
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlightTotals {
+    pub total_flights: i32,
+    pub total_distance_nm: f64,
+    pub total_distance_km: f64,
+    pub total_flight_time_hours: f64,
+    pub total_carbon_kg: f64,
+    /// Same totals in the configured unit system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurements: Option<Measurements>,
+}
+
+/// Totals for one aircraft class/category pair ("Unknown" when the flight has no aircraft type)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassStatistics {
+    pub aircraft_class: String,
+    pub category: String,
+    #[serde(flatten)]
+    pub totals: FlightTotals,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatisticsByClass {
+    pub classes: Vec<ClassStatistics>,
+    pub total: FlightTotals,
+}
+
+// ===== FLIGHT TIME MODELS =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightEndpointTimes {
+    pub airport_code: String,
+    pub timezone: Option<String>,
+    pub timezone_status: String,       // resolved | missing | unrecognized
+    pub local_time: Option<String>,    // Wall-clock time at this airport
+    pub utc_time: Option<String>,      // None when the timezone is unknown
+    pub utc_offset: Option<String>,    // e.g. "-05:00"
+    pub is_estimated: bool,            // true when derived from departure + duration
+}
+
+/// Weather at the flight's departure, captured once by research
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightWeather {
+    pub id: String,
+    pub flight_id: String,
+    /// YYYY-MM-DD
+    pub observation_date: String,
+    pub location: Option<String>,
+    /// False when research could not determine the weather; the remaining fields are then null
+    pub determined: bool,
+    pub conditions: Option<String>,
+    pub temperature_c: Option<f64>,
+    pub wind_direction_deg: Option<i32>,
+    pub wind_speed_kt: Option<f64>,
+    pub visibility_km: Option<f64>,
+    pub description: Option<String>,
+    pub source: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightWithTimes {
+    pub flight: Flight,
+    pub departure: FlightEndpointTimes,
+    pub arrival: FlightEndpointTimes,
+    pub elapsed_minutes: Option<i64>,  // True elapsed time, only when both ends resolve
+    #[serde(default)]
+    pub departure_delay_minutes: Option<i64>,  // Actual minus scheduled; negative when early
+    #[serde(default)]
+    pub arrival_delay_minutes: Option<i64>,
+    /// Flight distance and CO2 in the configured unit system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurements: Option<Measurements>,
+}
+
+/// A flight from the same calendar day in an earlier year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnThisDayFlight {
+    pub years_ago: i32,
+    #[serde(flatten)]
+    pub details: FlightWithTimes,
+}
+
+/// Average delay for one route ("KJFK-KBOS") or airport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayGroup {
+    pub key: String,
+    pub flights: i64,
+    pub average_delay_minutes: f64,
+}
+
+/// Delay statistics over the flights that have a schedule; flights without one are left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PunctualityStats {
+    pub flights_with_schedule: i64,
+    pub average_delay_minutes: Option<f64>,
+    pub on_time_percentage: Option<f64>,  // Within calculations::ON_TIME_THRESHOLD_MINUTES
+    pub worst_routes: Vec<DelayGroup>,
+    pub worst_airports: Vec<DelayGroup>,
 }
 
 // ===== INVESTIGATION MODELS =====
@@ -232,6 +483,7 @@ pub struct Investigation {
     pub corroboration_score: Option<f64>,
     pub error_message: Option<String>,
     pub processing_time_ms: Option<i32>,
+    pub evidence_json: Option<String>, // JSON: claims with supporting sources and score explanation
     pub created_at: String,
     pub completed_at: Option<String>,
 }
@@ -254,9 +506,25 @@ pub struct InvestigationResult {
     pub corroboration_score: f64,
     pub generated_queries: Vec<String>,
     pub processing_time_ms: i32,
+    #[serde(default)]
+    pub claims: Vec<InvestigationClaim>,
+    #[serde(default)]
+    pub score_explanation: String,
+}
+
+/// A claim and the independent sources that back it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvestigationClaim {
+    pub claim: String,
+    pub source_indices: Vec<usize>, // Indices into InvestigationResult.sources
+    pub independent_sources: i32,   // Distinct sites among the supporting sources
+    pub source_domains: Vec<String>,
+    pub support_score: f64,
+    pub agreement: String, // e.g. "3 sources agree"
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct InvestigationRequest {
     pub flight_id: String,
     pub passenger_names: Vec<String>,
@@ -445,6 +713,8 @@ pub struct TemporalFlightData {
     pub total_distance_km: f64,
     pub total_co2_kg: f64,
     pub period_start: String, // ISO 8601 start date for sorting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurements: Option<Measurements>, // Period totals in the configured units
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -545,6 +815,18 @@ pub struct AircraftUtilization {
     pub avg_flight_hours: f64,
     pub last_flown: Option<String>,   // ISO date, can be NULL
     pub days_since_last_flight: Option<i64>,
+    #[serde(default)]
+    pub first_flown: Option<String>,  // Earliest known flight date (placeholder dates ignored)
+    #[serde(default)]
+    pub days_in_service: Option<i64>, // Days from first flight to today, at least 1
+    #[serde(default)]
+    pub hours_per_month: Option<f64>, // Flight hours per 30-day period since first flight
+    #[serde(default)]
+    pub is_idle: bool,                // No flight within the idle threshold
+    #[serde(default)]
+    pub idle_cost: Option<f64>,       // Fixed cost accrued since the last flight
+    #[serde(default)]
+    pub cost_per_flight_hour: Option<f64>, // (fixed + hourly costs) over hours flown since first flight
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -578,10 +860,12 @@ pub struct LongHaulFlight {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurrencyItem {
+    pub key: String,                  // day_landings | night_landings
     pub requirement: String,
     pub current_count: i64,
     pub required: i64,
-    pub expires_in_days: Option<i64>,
+    pub expires_date: Option<String>, // last day current; None with too few landings on record
+    pub expires_in_days: Option<i64>, // negative once lapsed
     pub status: String,               // good | warning | expired
     pub last_completed: Option<String>,
 }
@@ -601,7 +885,73 @@ pub struct MonthlyCostData {
 pub struct RunwayRiskData {
     pub airport_code: String,
     pub airport_name: String,
-    pub runway_length_ft: i32,
+    pub runway_length_ft: i32,        // Longest runway, or the assumed length when unknown
+    pub runway_length_assumed: bool,  // True when the airport has no runway data
+    pub elevation_ft: Option<i32>,
+    pub effective_runway_ft: i32,     // After the hot-day density altitude penalty
     pub visits: i64,
     pub risk_level: String,           // safe | marginal | watch_out | nope
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighAltitudeAirport {
+    pub airport_code: String,
+    pub airport_name: String,
+    pub elevation_ft: i32,
+    pub hot_day_density_altitude_ft: i32,
+    pub visits: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighAltitudeReport {
+    pub threshold_ft: i32,
+    pub airports: Vec<HighAltitudeAirport>, // Highest first
+    pub missing_elevation_count: i64,       // Visited airports left out for lack of elevation data
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionGroupMetrics {
+    pub group: String,                // "(none)" when the flight has no value for the dimension
+    pub flight_count: i64,
+    pub total_distance_km: f64,
+    pub total_duration_hours: f64,
+    pub total_cost: f64,
+    pub total_co2_kg: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurements: Option<Measurements>, // Group totals in the configured units
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HomeBaseCandidate {
+    pub airport_code: String,
+    pub movements: i64,               // departures + arrivals
+    pub share: f64,                   // fraction of all movements
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripAway {
+    pub departed: String,
+    pub returned: String,
+    pub days: f64,
+    pub destinations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwayDestination {
+    pub airport_code: String,
+    pub visits: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommuteAnalytics {
+    pub home_base: Option<String>,
+    pub home_base_source: String,     // user | derived | ambiguous | none
+    pub candidates: Vec<HomeBaseCandidate>,
+    pub total_flights: i64,
+    pub flights_from_home: i64,
+    pub flights_to_home: i64,
+    pub trips_completed: i64,
+    pub average_trip_days: f64,
+    pub longest_trip: Option<TripAway>,
+    pub top_away_destinations: Vec<AwayDestination>,
+}
@@ -0,0 +1,198 @@
+// Passenger network export
+// Writes the co-travel graph from get_passenger_network_data as GraphML (Gephi, Cytoscape,
+// yEd) or Graphviz DOT. Node ids are the passenger names, and nodes and edges are sorted,
+// so exporting the same data twice gives identical files.
+
+use crate::models::{PassengerEdge, PassengerNetworkData, PassengerNode};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    GraphMl,
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "dot" | "gv" => Ok(GraphFormat::Dot),
+            other => Err(format!("Unknown graph format '{}': use graphml or dot", other)),
+        }
+    }
+}
+
+pub fn export(network: &PassengerNetworkData, format: GraphFormat) -> String {
+    let (nodes, edges) = sorted(network);
+    match format {
+        GraphFormat::GraphMl => to_graphml(&nodes, &edges),
+        GraphFormat::Dot => to_dot(&nodes, &edges),
+    }
+}
+
+fn sorted(network: &PassengerNetworkData) -> (Vec<PassengerNode>, Vec<PassengerEdge>) {
+    let mut nodes = network.nodes.clone();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut edges = network.edges.clone();
+    for edge in &mut edges {
+        edge.routes.sort();
+    }
+    edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+    (nodes, edges)
+}
+
+fn finite(value: f64) -> f64 {
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
+
+fn to_graphml(nodes: &[PassengerNode], edges: &[PassengerEdge]) -> String {
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="total_flights" for="node" attr.name="total_flights" attr.type="long"/>
+  <key id="total_distance_km" for="node" attr.name="total_distance_km" attr.type="double"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="long"/>
+  <key id="routes" for="edge" attr.name="routes" attr.type="string"/>
+  <graph id="passenger_network" edgedefault="undirected">
+"#,
+    );
+    for node in nodes {
+        let _ = write!(
+            out,
+            "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"total_flights\">{}</data>\n      <data key=\"total_distance_km\">{:.1}</data>\n    </node>\n",
+            xml_escape(&node.id),
+            xml_escape(&node.label),
+            node.total_flights,
+            finite(node.total_distance_km),
+        );
+    }
+    for edge in edges {
+        let _ = write!(
+            out,
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\">\n      <data key=\"weight\">{}</data>\n      <data key=\"routes\">{}</data>\n    </edge>\n",
+            xml_escape(&format!("{}--{}", edge.source, edge.target)),
+            xml_escape(&edge.source),
+            xml_escape(&edge.target),
+            edge.flight_count,
+            xml_escape(&edge.routes.join(";")),
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn to_dot(nodes: &[PassengerNode], edges: &[PassengerEdge]) -> String {
+    let mut out = String::from("graph passenger_network {\n");
+    for node in nodes {
+        let _ = writeln!(
+            out,
+            "  {} [label={}, total_flights={}, total_distance_km={:.1}];",
+            dot_quote(&node.id),
+            dot_quote(&node.label),
+            node.total_flights,
+            finite(node.total_distance_km),
+        );
+    }
+    for edge in edges {
+        let _ = writeln!(
+            out,
+            "  {} -- {} [weight={}, routes={}];",
+            dot_quote(&edge.source),
+            dot_quote(&edge.target),
+            edge.flight_count,
+            dot_quote(&edge.routes.join(";")),
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab/newline are not allowed in XML 1.0
+            c if c.is_control() && c != '\t' && c != '\n' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A DOT double-quoted id
+fn dot_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> PassengerNetworkData {
+        let node = |id: &str, flights: i64| PassengerNode {
+            id: id.to_string(),
+            label: id.to_string(),
+            total_flights: flights,
+            total_distance_km: 1234.56,
+        };
+        PassengerNetworkData {
+            nodes: vec![node("SMITH & \"SON\"", 3), node("ADAMS <A>", 2)],
+            edges: vec![PassengerEdge {
+                source: "ADAMS <A>".to_string(),
+                target: "SMITH & \"SON\"".to_string(),
+                flight_count: 2,
+                routes: vec!["TEB-PBI".to_string(), "PBI-TEB".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_graphml_escapes_and_sorts() {
+        let graphml = export(&network(), GraphFormat::GraphMl);
+        let adams = graphml.find("<node id=\"ADAMS &lt;A&gt;\">").unwrap();
+        let smith = graphml.find("<node id=\"SMITH &amp; &quot;SON&quot;\">").unwrap();
+        assert!(adams < smith);
+        assert!(graphml.contains("<data key=\"total_distance_km\">1234.6</data>"));
+        assert!(graphml.contains("source=\"ADAMS &lt;A&gt;\" target=\"SMITH &amp; &quot;SON&quot;\""));
+        assert!(graphml.contains("<data key=\"routes\">PBI-TEB;TEB-PBI</data>"));
+        assert_eq!(graphml, export(&network(), GraphFormat::GraphMl));
+    }
+
+    #[test]
+    fn test_dot_escapes() {
+        let dot = export(&network(), GraphFormat::Dot);
+        assert!(dot.starts_with("graph passenger_network {\n"));
+        assert!(dot.contains(r#""SMITH & \"SON\"" [label="SMITH & \"SON\"", total_flights=3"#));
+        assert!(dot.contains(r#""ADAMS <A>" -- "SMITH & \"SON\"" [weight=2, routes="PBI-TEB;TEB-PBI"];"#));
+        assert_eq!(dot_quote("a\\b\nc"), r#""a\\b\nc""#);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(GraphFormat::parse("GraphML").unwrap(), GraphFormat::GraphMl);
+        assert_eq!(GraphFormat::parse("dot").unwrap(), GraphFormat::Dot);
+        assert!(GraphFormat::parse("gexf").is_err());
+    }
+}
@@ -0,0 +1,301 @@
+// Network flow history
+// The sentinel's live views only show what is connected right now. When "network_history_enabled"
+// is on, a background sampler records every connection every "network_history_interval_secs"
+// (default 60) into network_flows, so later questions (what talked to this IP last Tuesday,
+// which process sent the most) can be answered. The network_flows retention policy bounds it.
+//
+// A long-lived connection appears in many samples. Queries collapse those into one connection
+// (same process, pid, protocol and both endpoints), seen first at the earliest sample. Byte
+// counters are cumulative per connection, so a connection's bytes are its largest sample,
+// not the sum of them.
+//
+// sampled_at is UTC as "YYYY-MM-DD HH:MM:SS", the same form as SQLite's datetime('now').
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::network_sentinel::NetworkFlow;
+use crate::database::Database;
+
+pub const ENABLED_SETTING: &str = "network_history_enabled";
+pub const INTERVAL_SETTING: &str = "network_history_interval_secs";
+
+pub const DEFAULT_INTERVAL_SECS: u64 = 60;
+/// Sampling runs ss over every socket; more often than this is not worth the load
+pub const MIN_INTERVAL_SECS: u64 = 10;
+
+/// Columns that identify one connection across samples
+const CONNECTION_KEY: &str = "process_name, pid, protocol, local_addr, local_port, remote_addr, remote_port";
+
+/// Filter placeholders ?1-?5 in the order of FilterParams
+const FILTER_SQL: &str = "(?1 IS NULL OR sampled_at >= ?1)
+    AND (?2 IS NULL OR sampled_at <= ?2)
+    AND (?3 IS NULL OR remote_addr = ?3 OR local_addr = ?3)
+    AND (?4 IS NULL OR remote_port = ?4 OR local_port = ?4)
+    AND (?5 IS NULL OR process_name = ?5)";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkHistorySettings {
+    pub enabled: bool,
+    pub interval_secs: u64,
+}
+
+impl NetworkHistorySettings {
+    /// Settings from the app settings; recording is off unless turned on
+    pub fn configured(db: &Database) -> Self {
+        let setting = |key| db.get_setting(key).ok().flatten();
+        NetworkHistorySettings {
+            enabled: setting(ENABLED_SETTING).as_deref() == Some("true"),
+            interval_secs: setting(INTERVAL_SETTING)
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(DEFAULT_INTERVAL_SECS)
+                .max(MIN_INTERVAL_SECS),
+        }
+    }
+}
+
+/// Which samples a query covers; every field narrows it, None matches anything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowFilter {
+    /// UTC "YYYY-MM-DD HH:MM:SS", inclusive
+    pub start: Option<String>,
+    /// UTC "YYYY-MM-DD HH:MM:SS", inclusive
+    pub end: Option<String>,
+    /// Either end of the connection
+    pub ip: Option<String>,
+    /// Either end of the connection
+    pub port: Option<u16>,
+    /// Exact process name
+    pub process: Option<String>,
+}
+
+/// (start, end, ip, port, process), bound to ?1-?5 of FILTER_SQL
+type FilterParams<'a> = (Option<&'a str>, Option<&'a str>, Option<&'a str>, Option<u16>, Option<&'a str>);
+
+impl FlowFilter {
+    fn params(&self) -> FilterParams<'_> {
+        (
+            self.start.as_deref(),
+            self.end.as_deref(),
+            self.ip.as_deref(),
+            self.port,
+            self.process.as_deref(),
+        )
+    }
+}
+
+/// Bytes and connection count for one remote address or process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficTotal {
+    pub key: String,
+    pub connections: i64,
+    pub bytes_sent: i64,
+    pub bytes_recv: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkHistorySummary {
+    pub connections: i64,
+    pub samples: i64,
+    /// RFC 3339, None without any matching samples
+    pub first_sampled_at: Option<String>,
+    pub last_sampled_at: Option<String>,
+    /// Remote addresses by bytes exchanged, then connections
+    pub top_talkers: Vec<TrafficTotal>,
+    /// Processes by bytes exchanged, then connections
+    pub bytes_per_process: Vec<TrafficTotal>,
+}
+
+/// Store one sample's flows under `sampled_at`; returns how many were stored
+pub fn record_sample(conn: &Connection, sampled_at: &str, flows: &[NetworkFlow]) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO network_flows
+                 (sampled_at, process_name, pid, protocol, direction, local_addr, local_port,
+                  remote_addr, remote_port, bytes_sent, bytes_recv, is_anomaly, anomaly_reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        )?;
+        for flow in flows {
+            insert.execute(params![
+                sampled_at,
+                flow.process_name,
+                flow.pid,
+                flow.protocol,
+                flow.direction,
+                flow.local_addr,
+                flow.local_port,
+                flow.remote_addr,
+                flow.remote_port,
+                flow.bytes_sent as i64,
+                flow.bytes_recv as i64,
+                flow.is_anomaly,
+                flow.anomaly_reason,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(flows.len())
+}
+
+/// Connections matching `filter`, most recently first seen first. timestamp is when each was
+/// first seen, bytes its largest sample.
+pub fn query_flows(conn: &Connection, filter: &FlowFilter, limit: u32) -> Result<Vec<NetworkFlow>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT MIN(id), process_name, pid, protocol, MAX(direction), local_addr, local_port,
+                remote_addr, remote_port, MAX(bytes_sent), MAX(bytes_recv), MAX(is_anomaly),
+                MAX(anomaly_reason), strftime('%Y-%m-%dT%H:%M:%SZ', MIN(sampled_at)) AS first_seen
+         FROM network_flows
+         WHERE {FILTER_SQL}
+         GROUP BY {CONNECTION_KEY}
+         ORDER BY first_seen DESC
+         LIMIT ?6"
+    ))?;
+    let (start, end, ip, port, process) = filter.params();
+    let flows = stmt
+        .query_map(params![start, end, ip, port, process, limit], |row| {
+            Ok(NetworkFlow {
+                id: row.get(0)?,
+                process_name: row.get(1)?,
+                pid: row.get(2)?,
+                protocol: row.get(3)?,
+                direction: row.get(4)?,
+                local_addr: row.get(5)?,
+                local_port: row.get(6)?,
+                remote_addr: row.get(7)?,
+                remote_port: row.get(8)?,
+                bytes_sent: row.get::<_, i64>(9)? as u64,
+                bytes_recv: row.get::<_, i64>(10)? as u64,
+                is_anomaly: row.get(11)?,
+                anomaly_reason: row.get(12)?,
+                timestamp: row.get(13)?,
+                geo_country: None,
+                geo_city: None,
+                geo_asn: None,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(flows)
+}
+
+/// Per-connection totals grouped by `group_column`, busiest first
+fn traffic_totals(conn: &Connection, filter: &FlowFilter, group_column: &str, limit: u32) -> Result<Vec<TrafficTotal>> {
+    let mut stmt = conn.prepare(&format!(
+        "WITH connections AS (
+             SELECT {group_column} AS grouped, MAX(bytes_sent) AS sent, MAX(bytes_recv) AS recv
+             FROM network_flows
+             WHERE {FILTER_SQL}
+             GROUP BY {CONNECTION_KEY}
+         )
+         SELECT grouped, COUNT(*), SUM(sent), SUM(recv)
+         FROM connections
+         GROUP BY grouped
+         ORDER BY SUM(sent) + SUM(recv) DESC, COUNT(*) DESC, grouped
+         LIMIT ?6"
+    ))?;
+    let (start, end, ip, port, process) = filter.params();
+    let totals = stmt
+        .query_map(params![start, end, ip, port, process, limit], |row| {
+            Ok(TrafficTotal {
+                key: row.get(0)?,
+                connections: row.get(1)?,
+                bytes_sent: row.get(2)?,
+                bytes_recv: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(totals)
+}
+
+/// Connection and sample counts for `filter`, with the top `limit` talkers and processes
+pub fn summarize(conn: &Connection, filter: &FlowFilter, limit: u32) -> Result<NetworkHistorySummary> {
+    let (start, end, ip, port, process) = filter.params();
+    let (samples, first_sampled_at, last_sampled_at) = conn.query_row(
+        &format!(
+            "SELECT COUNT(DISTINCT sampled_at),
+                    strftime('%Y-%m-%dT%H:%M:%SZ', MIN(sampled_at)),
+                    strftime('%Y-%m-%dT%H:%M:%SZ', MAX(sampled_at))
+             FROM network_flows WHERE {FILTER_SQL}"
+        ),
+        params![start, end, ip, port, process],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let connections = conn.query_row(
+        &format!("SELECT COUNT(*) FROM (SELECT 1 FROM network_flows WHERE {FILTER_SQL} GROUP BY {CONNECTION_KEY})"),
+        params![start, end, ip, port, process],
+        |row| row.get(0),
+    )?;
+    Ok(NetworkHistorySummary {
+        connections,
+        samples,
+        first_sampled_at,
+        last_sampled_at,
+        top_talkers: traffic_totals(conn, filter, "remote_addr", limit)?,
+        bytes_per_process: traffic_totals(conn, filter, "process_name", limit)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn flow(process: &str, remote: &str, local_port: u16, sent: u64, recv: u64) -> NetworkFlow {
+        NetworkFlow {
+            id: 0,
+            process_name: process.to_string(),
+            pid: 100,
+            local_addr: "192.168.1.5".to_string(),
+            local_port,
+            remote_addr: remote.to_string(),
+            remote_port: 443,
+            protocol: "TCP".to_string(),
+            direction: "outbound".to_string(),
+            bytes_sent: sent,
+            bytes_recv: recv,
+            timestamp: String::new(),
+            geo_country: None,
+            geo_city: None,
+            geo_asn: None,
+            is_anomaly: false,
+            anomaly_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_history_collapses_samples() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        // firefox's connection grows across two samples; curl's is seen once
+        record_sample(&db.conn, "2025-03-01 10:00:00", &[flow("firefox", "1.1.1.1", 50000, 100, 1000)]).unwrap();
+        record_sample(
+            &db.conn,
+            "2025-03-01 10:01:00",
+            &[flow("firefox", "1.1.1.1", 50000, 300, 5000), flow("curl", "2.2.2.2", 50001, 10, 20)],
+        )
+        .unwrap();
+        record_sample(&db.conn, "2025-03-02 09:00:00", &[flow("curl", "1.1.1.1", 50002, 1, 1)]).unwrap();
+
+        let day = FlowFilter {
+            start: Some("2025-03-01 00:00:00".to_string()),
+            end: Some("2025-03-01 23:59:59".to_string()),
+            ..Default::default()
+        };
+        let flows = query_flows(&db.conn, &day, 100).unwrap();
+        let seen: Vec<_> = flows.iter().map(|f| (f.process_name.as_str(), f.bytes_recv, f.timestamp.as_str())).collect();
+        assert_eq!(seen, [("curl", 20, "2025-03-01T10:01:00Z"), ("firefox", 5000, "2025-03-01T10:00:00Z")]);
+
+        let by_ip = FlowFilter { ip: Some("1.1.1.1".to_string()), ..Default::default() };
+        assert_eq!(query_flows(&db.conn, &by_ip, 100).unwrap().len(), 2);
+        let by_port = FlowFilter { port: Some(50001), ..Default::default() };
+        assert_eq!(query_flows(&db.conn, &by_port, 100).unwrap()[0].process_name, "curl");
+
+        let summary = summarize(&db.conn, &FlowFilter::default(), 10).unwrap();
+        assert_eq!((summary.connections, summary.samples), (3, 3));
+        assert_eq!(summary.last_sampled_at.as_deref(), Some("2025-03-02T09:00:00Z"));
+        let talkers: Vec<_> = summary.top_talkers.iter().map(|t| (t.key.as_str(), t.connections, t.bytes_recv)).collect();
+        assert_eq!(talkers, [("1.1.1.1", 2, 5001), ("2.2.2.2", 1, 20)]);
+        let processes: Vec<_> = summary.bytes_per_process.iter().map(|t| (t.key.as_str(), t.bytes_sent)).collect();
+        assert_eq!(processes, [("firefox", 300), ("curl", 11)]);
+    }
+}
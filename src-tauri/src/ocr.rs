@@ -2,11 +2,18 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tokio::time::sleep;
-use tauri::Emitter;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::calculations::{self, AircraftPerformance};
+use crate::locale::Locale;
+use crate::models::FlightInput;
+use crate::ocr_barcode;
+use crate::ocr_preprocess::{self, PreprocessOptions, PreprocessReport};
+use crate::operations::OperationHandle;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OcrFlightResult {
     pub flight_number: Option<String>,
     pub departure_airport: Option<String>,
@@ -23,25 +30,425 @@ pub struct OcrFlightResult {
     pub aircraft_type: Option<String>,
     pub airline: Option<String>,
     pub frequent_flyer_number: Option<String>,
+    /// Set when the extracted departure was a place name/coordinates rather than a code
+    #[serde(default)]
+    pub departure_resolution: Option<AirportResolution>,
+    /// Set when the extracted arrival was a place name/coordinates rather than a code
+    #[serde(default)]
+    pub arrival_resolution: Option<AirportResolution>,
+    /// Learned airport misreads that were corrected automatically
+    #[serde(default)]
+    pub applied_corrections: Vec<crate::ocr_learning::CorrectionSuggestion>,
+    /// Model-reported confidence (0.0 - 1.0) per extracted field
+    #[serde(default)]
+    pub field_confidence: BTreeMap<String, f32>,
+    /// Lowest confidence among the extracted fields; None when the model reported none
+    #[serde(default)]
+    pub confidence_score: Option<f32>,
+    /// Extracted fields below the minimum confidence, or with no confidence reported
+    #[serde(default)]
+    pub low_confidence_fields: Vec<String>,
+    /// Set when a minimum confidence was requested and not met; review before accepting
+    #[serde(default)]
+    pub needs_review: bool,
+    /// Model that produced this result
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Model that produced each extracted field; differs from `model` after an escalation
+    #[serde(default)]
+    pub field_models: BTreeMap<String, String>,
+    /// How the result was read
+    #[serde(default)]
+    pub method: OcrMethod,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OcrMethod {
+    /// AI vision model
+    #[default]
+    Vision,
+    /// The boarding pass barcode (IATA BCBP); exact
+    Barcode,
+}
+
+pub const LITE_MODEL: &str = "gemini-2.5-flash-lite";
+pub const PRO_MODEL: &str = "gemini-3-pro-preview";
+
+/// The OCR model for the use_gemini_lite preference
+pub fn model_for(use_lite_model: bool) -> &'static str {
+    if use_lite_model {
+        LITE_MODEL
+    } else {
+        PRO_MODEL
+    }
+}
+
+/// "lite", "pro" or a full model id
+pub fn parse_model(value: &str) -> Result<&'static str> {
+    match value.trim().to_lowercase().as_str() {
+        "lite" | LITE_MODEL => Ok(LITE_MODEL),
+        "pro" | PRO_MODEL => Ok(PRO_MODEL),
+        other => Err(anyhow::anyhow!("Unknown OCR model '{}': use lite or pro", other)),
+    }
+}
+
+impl OcrFlightResult {
+    fn fields(&self) -> [(&'static str, &Option<String>); 15] {
+        [
+            ("flight_number", &self.flight_number),
+            ("departure_airport", &self.departure_airport),
+            ("arrival_airport", &self.arrival_airport),
+            ("departure_datetime", &self.departure_datetime),
+            ("arrival_datetime", &self.arrival_datetime),
+            ("passenger_name", &self.passenger_name),
+            ("booking_reference", &self.booking_reference),
+            ("ticket_number", &self.ticket_number),
+            ("seat_number", &self.seat_number),
+            ("fare_class", &self.fare_class),
+            ("gate", &self.gate),
+            ("terminal", &self.terminal),
+            ("aircraft_type", &self.aircraft_type),
+            ("airline", &self.airline),
+            ("frequent_flyer_number", &self.frequent_flyer_number),
+        ]
+    }
+
+    fn fields_mut(&mut self) -> [(&'static str, &mut Option<String>); 15] {
+        [
+            ("flight_number", &mut self.flight_number),
+            ("departure_airport", &mut self.departure_airport),
+            ("arrival_airport", &mut self.arrival_airport),
+            ("departure_datetime", &mut self.departure_datetime),
+            ("arrival_datetime", &mut self.arrival_datetime),
+            ("passenger_name", &mut self.passenger_name),
+            ("booking_reference", &mut self.booking_reference),
+            ("ticket_number", &mut self.ticket_number),
+            ("seat_number", &mut self.seat_number),
+            ("fare_class", &mut self.fare_class),
+            ("gate", &mut self.gate),
+            ("terminal", &mut self.terminal),
+            ("aircraft_type", &mut self.aircraft_type),
+            ("airline", &mut self.airline),
+            ("frequent_flyer_number", &mut self.frequent_flyer_number),
+        ]
+    }
+
+    /// Names of the fields the model filled in
+    fn extracted_fields(&self) -> Vec<&'static str> {
+        self.fields()
+            .into_iter()
+            .filter(|(_, value)| value.as_deref().is_some_and(|v| !v.trim().is_empty()))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Attribute every extracted field to `model`
+    pub(crate) fn set_model(&mut self, model: &str) {
+        self.model = Some(model.to_string());
+        self.field_models = self.extracted_fields().into_iter().map(|f| (f.to_string(), model.to_string())).collect();
+    }
+
+    /// Combine a re-run on a stronger model with the result it replaces
+    ///
+    /// Each field keeps whichever reading is more confident; ties and unscored fields go
+    /// to the re-run. `field_models` records which model each kept field came from.
+    pub fn escalate(previous: &OcrFlightResult, mut escalated: OcrFlightResult) -> OcrFlightResult {
+        let mut previous = previous.clone();
+        let previous_model = previous.model.clone();
+        let previous_confidence = previous.field_confidence.clone();
+        let previous_models = previous.field_models.clone();
+        let escalated_confidence = escalated.field_confidence.clone();
+
+        let mut kept = Vec::new();
+        for ((name, old), (_, new)) in previous.fields_mut().into_iter().zip(escalated.fields_mut()) {
+            let Some(old_value) = old.take().filter(|v| !v.trim().is_empty()) else { continue };
+            let old_confidence = previous_confidence.get(name).copied().unwrap_or(0.0);
+            let new_confidence = escalated_confidence.get(name).copied().unwrap_or(0.0);
+            if new.as_deref().is_none_or(|v| v.trim().is_empty()) || old_confidence > new_confidence {
+                *new = Some(old_value);
+                kept.push(name);
+            }
+        }
+
+        for name in kept {
+            match previous_confidence.get(name) {
+                Some(confidence) => escalated.field_confidence.insert(name.to_string(), *confidence),
+                None => escalated.field_confidence.remove(name),
+            };
+            if let Some(model) = previous_models.get(name).or(previous_model.as_ref()) {
+                escalated.field_models.insert(name.to_string(), model.clone());
+            }
+            // The place-name resolution belongs to the airport reading it came with
+            match name {
+                "departure_airport" => escalated.departure_resolution = previous.departure_resolution.take(),
+                "arrival_airport" => escalated.arrival_resolution = previous.arrival_resolution.take(),
+                _ => {}
+            }
+        }
+        escalated
+    }
+
+    /// Score the result and flag it for review when any extracted field is below `min_confidence`
+    ///
+    /// Airport confidence is capped by the place-name resolution confidence, so a code
+    /// guessed from "New York" is not trusted more than the guess. Run after post-processing.
+    pub fn apply_confidence(&mut self, min_confidence: Option<f32>) {
+        for (field, resolution) in [
+            ("departure_airport", &self.departure_resolution),
+            ("arrival_airport", &self.arrival_resolution),
+        ] {
+            if let Some(resolution) = resolution {
+                let capped = self
+                    .field_confidence
+                    .get(field)
+                    .map_or(resolution.confidence, |c| c.min(resolution.confidence));
+                self.field_confidence.insert(field.to_string(), capped);
+            }
+        }
+        for confidence in self.field_confidence.values_mut() {
+            *confidence = if confidence.is_finite() { confidence.clamp(0.0, 1.0) } else { 0.0 };
+        }
+
+        let extracted = self.extracted_fields();
+        self.confidence_score = extracted
+            .iter()
+            .filter_map(|name| self.field_confidence.get(*name).copied())
+            .reduce(f32::min);
+
+        let Some(min_confidence) = min_confidence else {
+            self.low_confidence_fields.clear();
+            self.needs_review = false;
+            return;
+        };
+        self.low_confidence_fields = extracted
+            .iter()
+            .filter(|name| self.field_confidence.get(**name).is_none_or(|c| *c < min_confidence))
+            .map(|name| name.to_string())
+            .collect();
+        self.needs_review = extracted.is_empty() || !self.low_confidence_fields.is_empty();
+    }
+
+    /// Departure and arrival as airport codes, or None while either is missing or still a
+    /// place name that could not be resolved. Run after post-processing.
+    pub fn route_codes(&self) -> Option<(String, String)> {
+        let code = |value: &Option<String>, resolution: &Option<AirportResolution>| {
+            if resolution.as_ref().is_some_and(|r| r.airport_code.is_none()) {
+                return None;
+            }
+            value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_uppercase)
+        };
+        Some((
+            code(&self.departure_airport, &self.departure_resolution)?,
+            code(&self.arrival_airport, &self.arrival_resolution)?,
+        ))
+    }
+
+    /// Fields a flight cannot be created without
+    pub fn missing_required(&self) -> Vec<&'static str> {
+        [
+            ("departure_airport", &self.departure_airport),
+            ("arrival_airport", &self.arrival_airport),
+            ("departure_datetime", &self.departure_datetime),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.as_deref().is_none_or(|v| v.trim().is_empty()))
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// The flight to create from this extraction, with distance, duration and CO2 filled in
+    /// from the route `distance` (nm, km) when it is known, using `performance` for the
+    /// extracted aircraft type (see `aircraft_performance::resolve`). Fails when an airport
+    /// or the departure time was not read, rather than inventing them.
+    pub fn to_flight_input(&self, distance: Option<(f64, f64)>, performance: &AircraftPerformance) -> Result<FlightInput> {
+        let missing = self.missing_required();
+        if !missing.is_empty() {
+            anyhow::bail!("Missing {}", missing.join(", "));
+        }
+        let text = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(String::from);
+        let notes: Vec<String> = [
+            ("Passengers", &self.passenger_name),
+            ("Aircraft", &self.aircraft_type),
+            ("Gate", &self.gate),
+            ("Terminal", &self.terminal),
+            ("Airline", &self.airline),
+            ("FFN", &self.frequent_flyer_number),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| text(value).map(|v| format!("{}: {}", label, v)))
+        .collect();
+
+        let flight_duration = distance.map(|(_, km)| performance.flight_time(km));
+
+        Ok(FlightInput {
+            flight_number: text(&self.flight_number),
+            departure_airport: text(&self.departure_airport).unwrap_or_default(),
+            arrival_airport: text(&self.arrival_airport).unwrap_or_default(),
+            departure_datetime: text(&self.departure_datetime).unwrap_or_default(),
+            arrival_datetime: text(&self.arrival_datetime),
+            aircraft_type_id: None,
+            // OCR reads the type, never the registration
+            aircraft_registration: None,
+            total_duration: flight_duration.map(calculations::calculate_block_time),
+            flight_duration,
+            distance_nm: distance.map(|(nm, _)| nm),
+            distance_km: distance.map(|(_, km)| km),
+            carbon_emissions_kg: distance.map(|(_, km)| performance.co2_emissions(km)),
+            booking_reference: text(&self.booking_reference),
+            ticket_number: text(&self.ticket_number),
+            seat_number: text(&self.seat_number),
+            fare_class: text(&self.fare_class),
+            base_fare: None,
+            taxes: None,
+            total_cost: None,
+            currency: None,
+            notes: (!notes.is_empty()).then(|| notes.join(" | ")),
+            attachment_path: None,
+            // A boarding pass names one traveller, not everyone on board
+            passenger_count: None,
+        })
+    }
+}
+
+/// The outcome of creating a flight from an OCR result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrFlightImport {
+    /// None when the result could not become a flight; see `error`
+    pub flight_id: Option<String>,
+    pub error: Option<String>,
+    pub distance_km: Option<f64>,
+    /// Distance, duration and CO2 are missing and the flight was flagged for recalculation
+    pub flagged: bool,
+}
+
+/// One file of a batch OCR run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOcrItem {
+    /// Position in the submitted file list
+    pub index: usize,
+    pub file_path: String,
+    pub result: OcrFlightResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOcrFailure {
+    pub index: usize,
+    pub file_path: String,
+    pub error: String,
+}
+
+/// Batch OCR results split by confidence
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchOcrResults {
+    /// Every extracted field met the minimum confidence
+    pub accepted: Vec<BatchOcrItem>,
+    /// At least one field below the minimum; check manually before importing
+    pub needs_review: Vec<BatchOcrItem>,
+    pub failed: Vec<BatchOcrFailure>,
+    /// Stopped early by cancel_operation; files not yet started are listed under failed
+    pub cancelled: bool,
+}
+
+/// How a non-code OCR location ("New York", "Heathrow", "51.47, -0.45") was resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportResolution {
+    pub input: String,
+    pub airport_code: Option<String>, // None when ambiguous or unresolved - pick from candidates
+    pub confidence: f32,              // 0.0 - 1.0
+    pub method: String,               // "embedded_code", "name", "city", "coordinates", "ambiguous", "unresolved"
+    pub candidates: Vec<AirportCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportCandidate {
+    pub code: String,
+    pub name: String,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub score: f32,
+}
+
+/// Raw and pre-processed OCR of the same image, to check pre-processing helps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreprocessComparison {
+    pub raw: OcrFlightResult,
+    pub preprocessed: OcrFlightResult,
+    pub report: PreprocessReport,
+    /// Fields the two results read differently, or only one of them read
+    pub differing_fields: Vec<String>,
+}
+
+impl PreprocessComparison {
+    pub fn new(raw: OcrFlightResult, preprocessed: OcrFlightResult, report: PreprocessReport) -> Self {
+        let normalize = |value: &Option<String>| value.as_deref().map(|v| v.trim().to_uppercase()).filter(|v| !v.is_empty());
+        let differing_fields = raw
+            .fields()
+            .into_iter()
+            .zip(preprocessed.fields())
+            .filter(|((_, a), (_, b))| normalize(a) != normalize(b))
+            .map(|((name, _), _)| name.to_string())
+            .collect();
+        Self { raw, preprocessed, report, differing_fields }
+    }
+}
+
+/// The image to send for OCR: pre-processed when any step is enabled, otherwise as is
+///
+/// Formats the pipeline cannot decode (PDF, HEIC) are sent unchanged.
+pub fn prepare_image(image_bytes: Vec<u8>, options: &PreprocessOptions) -> Vec<u8> {
+    if !options.is_enabled() {
+        return image_bytes;
+    }
+    match ocr_preprocess::preprocess(&image_bytes, options) {
+        Ok(processed) => processed.bytes,
+        Err(e) => {
+            tracing::warn!("[OCR] Pre-processing skipped: {}", e);
+            image_bytes
+        }
+    }
+}
+
+/// OCR a file: from its boarding pass barcode when one can be read, otherwise with `model`
+pub async fn analyze_file(
+    path: &str,
+    api_key: &str,
+    model: &str,
+    locale: Locale,
+    preprocess: PreprocessOptions,
+) -> Result<OcrFlightResult> {
+    if let Some(result) = ocr_barcode::read_barcode(std::path::Path::new(path)).await {
+        return Ok(result);
+    }
+    let image_bytes = std::fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read image {}: {}", path, e))?;
+    let image_bytes = tokio::task::spawn_blocking(move || prepare_image(image_bytes, &preprocess))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to pre-process image {}: {}", path, e))?;
+    analyze_with_model(image_bytes, api_key, model, locale).await
 }
 
 /// Analyze a boarding pass or travel document using Gemini API
+#[allow(dead_code)]
 pub async fn analyze_with_gemini(
     image_bytes: Vec<u8>,
     api_key: &str,
     use_lite_model: bool,
+    locale: Locale,
+) -> Result<OcrFlightResult> {
+    analyze_with_model(image_bytes, api_key, model_for(use_lite_model), locale).await
+}
+
+/// Analyze with a specific Gemini model (LITE_MODEL or PRO_MODEL)
+pub async fn analyze_with_model(
+    image_bytes: Vec<u8>,
+    api_key: &str,
+    model: &str,
+    locale: Locale,
 ) -> Result<OcrFlightResult> {
     // Convert image bytes to base64
     let image_base64 =
         base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &image_bytes);
 
-    // Choose model based on preference
-    let model = if use_lite_model {
-        "gemini-2.5-flash-lite"
-    } else {
-        "gemini-3-pro-preview"
-    };
-
     // Construct the Gemini API request
     let client = reqwest::Client::new();
     let url = format!(
@@ -54,7 +461,7 @@ pub async fn analyze_with_gemini(
         "contents": [{
             "parts": [
                 {
-                    "text": r#"You are an expert travel document analyzer. Analyze this boarding pass, ticket, or travel document image and extract the following flight information in valid JSON format only, with no other text:
+                    "text": format!("{}{}", r#"You are an expert travel document analyzer. Analyze this boarding pass, ticket, or travel document image and extract the following flight information in valid JSON format only, with no other text:
 
 {
   "flight_number": "string (e.g., 'AA1234', 'UA567')",
@@ -71,7 +478,8 @@ pub async fn analyze_with_gemini(
   "terminal": "string (terminal)",
   "aircraft_type": "string (e.g., 'Boeing 737', 'A320')",
   "airline": "string (airline name)",
-  "frequent_flyer_number": "string (FF number if visible)"
+  "frequent_flyer_number": "string (FF number if visible)",
+  "field_confidence": { "<field name>": "number from 0.0 to 1.0 for each non-null field above" }
 }
 
 IMPORTANT RULES:
@@ -79,7 +487,8 @@ IMPORTANT RULES:
 2. For dates, convert any format to ISO 8601 (YYYY-MM-DDTHH:MM:SS)
 3. For airports, prefer IATA codes (3-letter) if available
 4. Extract only information that is clearly visible
-5. Return ONLY the JSON object, no markdown formatting, no explanation"#
+5. In field_confidence, rate how sure you are of each value you extracted: 1.0 for clearly printed text, lower for blurry, partial or inferred values
+6. Return ONLY the JSON object, no markdown formatting, no explanation"#, locale.ocr_prompt_hint())
                 },
                 {
                     "inlineData": {
@@ -130,29 +539,32 @@ IMPORTANT RULES:
         .trim();
 
     // Parse the JSON into OcrFlightResult
-    let result: OcrFlightResult = serde_json::from_str(json_text).map_err(|e| {
+    let mut result: OcrFlightResult = serde_json::from_str(json_text).map_err(|e| {
         anyhow::anyhow!(
             "Failed to parse Gemini JSON response: {}. Raw text: {}",
             e,
             json_text
         )
     })?;
+    result.set_model(model);
 
     Ok(result)
 }
 
 /// Analyze with intelligent retry logic for rate limiting
+#[allow(dead_code)]
 pub async fn analyze_with_retry(
     image_bytes: Vec<u8>,
     api_key: &str,
     use_lite_model: bool,
+    locale: Locale,
     max_retries: u32,
 ) -> Result<OcrFlightResult> {
     let mut retry_count = 0;
     let mut base_delay = 2000; // Start with 2 seconds
 
     loop {
-        match analyze_with_gemini(image_bytes.clone(), api_key, use_lite_model).await {
+        match analyze_with_gemini(image_bytes.clone(), api_key, use_lite_model, locale).await {
             Ok(result) => return Ok(result),
             Err(e) => {
                 let error_msg = e.to_string();
@@ -169,7 +581,7 @@ pub async fn analyze_with_retry(
                     let jitter = (rand::random::<f64>() * 1000.0) as u64;
                     let delay = base_delay + jitter;
 
-                    eprintln!(
+                    tracing::warn!(
                         "Rate limit hit (429), retry {}/{} after {}ms",
                         retry_count, max_retries, delay
                     );
@@ -191,10 +603,12 @@ pub async fn analyze_with_retry(
 
 /// Batch process multiple images with parallel execution
 /// Returns results in the same order as input paths
+#[allow(dead_code)]
 pub async fn batch_analyze(
     image_paths: Vec<String>,
     api_key: &str,
     use_lite_model: bool,
+    locale: Locale,
 ) -> Vec<Result<OcrFlightResult>> {
     use futures::stream::{FuturesOrdered, StreamExt};
     use std::sync::Arc;
@@ -211,7 +625,7 @@ pub async fn batch_analyze(
 
             match image_result {
                 Ok(image_bytes) => {
-                    analyze_with_gemini(image_bytes, &api_key, use_lite_model).await
+                    analyze_with_gemini(image_bytes, &api_key, use_lite_model, locale).await
                 }
                 Err(e) => Err(anyhow::anyhow!("Failed to read image {}: {}", path, e)),
             }
@@ -232,54 +646,53 @@ pub async fn batch_analyze(
     results
 }
 
-/// Batch process with progress callback
-/// Emits progress events via Tauri
-pub async fn batch_analyze_with_progress<R: tauri::Runtime>(
+/// Batch process as a long running operation
+/// Progress details carry the file path and its status; files not yet started when the
+/// operation is cancelled come back as errors
+pub async fn batch_analyze_with_progress(
     image_paths: Vec<String>,
     api_key: &str,
-    use_lite_model: bool,
-    app_handle: &tauri::AppHandle<R>,
+    model: &'static str,
+    locale: Locale,
+    preprocess: PreprocessOptions,
+    operation: &OperationHandle,
 ) -> Vec<Result<OcrFlightResult>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
     use tokio::sync::Semaphore;
 
     let api_key = Arc::new(api_key.to_string());
     let total = image_paths.len();
     let semaphore = Arc::new(Semaphore::new(3)); // Max 3 concurrent requests
+    let completed = Arc::new(AtomicUsize::new(0));
 
     let mut handles = Vec::new();
 
     for (index, path) in image_paths.into_iter().enumerate() {
         let api_key = Arc::clone(&api_key);
-        let app_handle = app_handle.clone();
+        let operation = operation.clone();
         let semaphore = Arc::clone(&semaphore);
+        let completed = Arc::clone(&completed);
 
         let handle = tokio::spawn(async move {
             // Acquire semaphore permit
             let _permit = semaphore.acquire().await.unwrap();
+            if operation.is_cancelled() {
+                return (index, Err(anyhow::anyhow!("Cancelled before processing")));
+            }
 
-            // Emit progress: starting
-            let _ = app_handle.emit("batch-ocr:progress", serde_json::json!({
-                "current": index + 1,
-                "total": total,
+            // Progress: starting
+            operation.progress_with(completed.load(Ordering::SeqCst), total, &serde_json::json!({
                 "status": "processing",
                 "path": path.clone()
             }));
 
-            let image_result = std::fs::read(&path);
-
-            let result = match image_result {
-                Ok(image_bytes) => {
-                    analyze_with_gemini(image_bytes, &api_key, use_lite_model).await
-                }
-                Err(e) => Err(anyhow::anyhow!("Failed to read image {}: {}", path, e)),
-            };
+            let result = analyze_file(&path, &api_key, model, locale, preprocess).await;
 
-            // Emit progress: complete
+            // Progress: complete
             let status = if result.is_ok() { "success" } else { "error" };
-            let _ = app_handle.emit("batch-ocr:progress", serde_json::json!({
-                "current": index + 1,
-                "total": total,
+            let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+            operation.progress_with(done, total, &serde_json::json!({
                 "status": status,
                 "path": path
             }));
@@ -292,9 +705,11 @@ pub async fn batch_analyze_with_progress<R: tauri::Runtime>(
 
     // Wait for all tasks and collect results in order
     let mut indexed_results = Vec::new();
-    for handle in handles {
-        if let Ok((index, result)) = handle.await {
-            indexed_results.push((index, result));
+    for (index, handle) in handles.into_iter().enumerate() {
+        match handle.await {
+            Ok(indexed) => indexed_results.push(indexed),
+            // Keep a slot per input so results stay aligned with the paths
+            Err(e) => indexed_results.push((index, Err(anyhow::anyhow!("Task panicked: {}", e)))),
         }
     }
 
@@ -398,7 +813,7 @@ pub async fn extract_document_text_with_retry(
                     let jitter = (rand::random::<f64>() * 1000.0) as u64;
                     let delay = base_delay + jitter;
 
-                    eprintln!(
+                    tracing::warn!(
                         "Rate limit hit (429), retry {}/{} after {}ms",
                         retry_count, max_retries, delay
                     );
@@ -417,3 +832,102 @@ pub async fn extract_document_text_with_retry(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(json: &str) -> OcrFlightResult {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_apply_confidence() {
+        let mut ocr = result(
+            r#"{"flight_number": "BA117", "departure_airport": "LHR", "seat_number": "12A", "gate": null,
+                "field_confidence": {"flight_number": 0.95, "departure_airport": 0.9, "seat_number": 0.4, "gate": 0.1}}"#,
+        );
+        ocr.apply_confidence(None);
+        assert_eq!(ocr.confidence_score, Some(0.4));
+        assert!(!ocr.needs_review);
+
+        ocr.apply_confidence(Some(0.8));
+        assert_eq!(ocr.low_confidence_fields, vec!["seat_number"]);
+        assert!(ocr.needs_review);
+
+        // A field with no reported confidence cannot pass a threshold
+        let mut unscored = result(r#"{"flight_number": "BA117"}"#);
+        unscored.apply_confidence(Some(0.5));
+        assert_eq!(unscored.confidence_score, None);
+        assert_eq!(unscored.low_confidence_fields, vec!["flight_number"]);
+    }
+
+    #[test]
+    fn test_escalate_keeps_more_confident_fields() {
+        let mut lite = result(
+            r#"{"flight_number": "BA117", "seat_number": "1ZA", "gate": "B32",
+                "field_confidence": {"flight_number": 0.97, "seat_number": 0.3, "gate": 0.5}}"#,
+        );
+        lite.set_model(LITE_MODEL);
+        let mut pro = result(
+            r#"{"flight_number": "BA117", "seat_number": "12A",
+                "field_confidence": {"flight_number": 0.9, "seat_number": 0.95}}"#,
+        );
+        pro.set_model(PRO_MODEL);
+
+        let mut merged = OcrFlightResult::escalate(&lite, pro);
+        merged.apply_confidence(Some(0.8));
+        assert_eq!(merged.seat_number.as_deref(), Some("12A"));
+        assert_eq!(merged.field_models["seat_number"], PRO_MODEL);
+        assert_eq!(merged.field_models["flight_number"], LITE_MODEL);
+        // Only the lite pass read the gate, so it is kept and still flagged
+        assert_eq!(merged.gate.as_deref(), Some("B32"));
+        assert_eq!(merged.field_models["gate"], LITE_MODEL);
+        assert_eq!(merged.low_confidence_fields, vec!["gate"]);
+        assert_eq!(merged.model.as_deref(), Some(PRO_MODEL));
+        assert_eq!(parse_model("Pro").unwrap(), PRO_MODEL);
+        assert!(parse_model("gpt").is_err());
+    }
+
+    #[test]
+    fn test_apply_confidence_caps_resolved_airports() {
+        let mut ocr = result(
+            r#"{"departure_airport": "JFK", "field_confidence": {"departure_airport": 0.99},
+                "departure_resolution": {"input": "New York", "airport_code": "JFK", "confidence": 0.6,
+                                         "method": "city", "candidates": []}}"#,
+        );
+        ocr.apply_confidence(Some(0.7));
+        assert_eq!(ocr.field_confidence["departure_airport"], 0.6);
+        assert!(ocr.needs_review);
+    }
+
+    #[test]
+    fn test_flight_input_from_resolved_route() {
+        let ocr = result(
+            r#"{"flight_number": "BA117", "departure_airport": "lhr", "arrival_airport": "JFK",
+                "departure_datetime": "2025-06-01T10:00:00", "aircraft_type": "B777", "gate": "B32"}"#,
+        );
+        assert_eq!(ocr.route_codes(), Some(("LHR".to_string(), "JFK".to_string())));
+
+        let b777 = AircraftPerformance::from_aircraft_type("B777");
+        let flight = ocr.to_flight_input(Some((2999.0, 5554.0)), &b777).unwrap();
+        assert_eq!(flight.distance_km, Some(5554.0));
+        assert_eq!(flight.flight_duration, Some(b777.flight_time(5554.0)));
+        assert_eq!(flight.carbon_emissions_kg, Some(b777.co2_emissions(5554.0)));
+        assert_eq!(flight.notes.as_deref(), Some("Aircraft: B777 | Gate: B32"));
+        assert_eq!(ocr.to_flight_input(None, &b777).unwrap().carbon_emissions_kg, None);
+
+        // No date read: rejected rather than dated today
+        let undated = result(r#"{"departure_airport": "LHR", "arrival_airport": "JFK"}"#);
+        assert_eq!(undated.missing_required(), ["departure_datetime"]);
+        assert!(undated.to_flight_input(None, &b777).is_err());
+
+        // An ambiguous place name is not a route yet
+        let ambiguous = result(
+            r#"{"departure_airport": "London", "arrival_airport": "JFK",
+                "departure_resolution": {"input": "London", "airport_code": null, "confidence": 0.4,
+                                         "method": "ambiguous", "candidates": []}}"#,
+        );
+        assert_eq!(ambiguous.route_codes(), None);
+    }
+}
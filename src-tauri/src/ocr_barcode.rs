@@ -0,0 +1,153 @@
+// Boarding pass barcodes
+// The barcode holds the IATA BCBP string (see bcbp.rs), which is exact and costs nothing to
+// read, so OCR tries it before the AI vision path. Images are decoded with zxing-cpp's
+// ZXingReader (PDF417, Aztec, QR, Data Matrix) or, failing that, zbar's zbarimg, whichever
+// is installed; the first page of a PDF is rendered with pdftoppm first. A .txt file is
+// taken as the barcode text itself, e.g. saved from a phone scanner app.
+//
+// Without either tool, or without a barcode that parses as a boarding pass, the caller
+// falls back to vision.
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::bcbp;
+use crate::extract::{split_pdf, ImageFormat, SplitConfig};
+use crate::ocr::OcrFlightResult;
+
+/// Render PDFs at this resolution so the barcode modules stay sharp
+const PDF_DPI: u32 = 300;
+
+/// The boarding pass in the file's barcode, or None when there is no readable one
+pub async fn read_barcode(path: &Path) -> Option<OcrFlightResult> {
+    let texts = match barcode_texts(path).await {
+        Ok(texts) => texts,
+        Err(e) => {
+            tracing::debug!("[OCR] No barcode read from {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let pass = texts.iter().find_map(|text| bcbp::parse(text).ok())?;
+    tracing::info!("[OCR] Read boarding pass barcode from {}", path.display());
+    Some(pass.to_ocr_result(reference_date(path)))
+}
+
+/// When the file was last modified, which for a photo is about when the pass was in hand
+fn reference_date(path: &Path) -> NaiveDate {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).date_naive())
+        .unwrap_or_else(|_| chrono::Local::now().date_naive())
+}
+
+async fn barcode_texts(path: &Path) -> Result<Vec<String>> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    match extension.as_str() {
+        "txt" => Ok(tokio::fs::read_to_string(path).await?.lines().map(str::to_string).collect()),
+        "pdf" => {
+            let output_dir = std::env::temp_dir().join(format!("barcode_{}", uuid::Uuid::new_v4()));
+            let config = SplitConfig { dpi: PDF_DPI, format: ImageFormat::Png, page_range: Some((1, 1)) };
+            let result = match split_pdf(path, &output_dir, &config).await {
+                Ok(split) => match split.page_paths.first() {
+                    Some(page) => decode_image(page).await,
+                    None => Ok(Vec::new()),
+                },
+                Err(e) => Err(e),
+            };
+            let _ = tokio::fs::remove_dir_all(&output_dir).await;
+            result
+        }
+        _ => decode_image(path).await,
+    }
+}
+
+/// Every barcode text in the image, from whichever decoder is installed
+async fn decode_image(path: &Path) -> Result<Vec<String>> {
+    match zxing(path).await {
+        Ok(texts) if !texts.is_empty() => Ok(texts),
+        zxing_result => match zbar(path).await {
+            Ok(texts) => Ok(texts),
+            // Report zxing's error when neither tool could run
+            Err(e) => zxing_result.or(Err(e)),
+        },
+    }
+}
+
+async fn zxing(path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("ZXingReader")
+        .arg(path)
+        .output()
+        .await
+        .context("Failed to execute ZXingReader - is zxing-cpp installed?")?;
+    if !output.status.success() {
+        bail!("ZXingReader failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(parse_zxing_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The quoted `Text:` line of each barcode ZXingReader found
+fn parse_zxing_output(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("Text:"))
+        .map(|text| {
+            let text = text.trim();
+            text.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(text).to_string()
+        })
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+async fn zbar(path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("zbarimg")
+        .args(["--quiet", "--raw"])
+        .arg(path)
+        .output()
+        .await
+        .context("Failed to execute zbarimg - is zbar installed?")?;
+    // Exit status 4 means the image was read but held no barcode
+    if output.status.code() == Some(4) {
+        return Ok(Vec::new());
+    }
+    if !output.status.success() {
+        bail!("zbarimg failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::OcrMethod;
+
+    #[test]
+    fn test_parse_zxing_output() {
+        let stdout = "Text:       \"M1DOE/JOHN MR         EABC123 LHRJFKBA 0117 003Y012A0042 100\"\n\
+                      Bytes:      4D 31\n\
+                      Format:     PDF417\n\
+                      \n\
+                      Text:       \"https://example.com\"\n\
+                      Format:     QRCode\n";
+        let texts = parse_zxing_output(stdout);
+        assert_eq!(texts.len(), 2);
+        assert!(bcbp::parse(&texts[0]).is_ok());
+        assert!(bcbp::parse(&texts[1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_barcode_from_text_file() {
+        let path = std::env::temp_dir().join(format!("pass_{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "M1DOE/JOHN MR         EABC123 LHRJFKBA 0117 003Y012A0042 100\n").unwrap();
+        let result = read_barcode(&path).await.unwrap();
+        assert_eq!(result.method, OcrMethod::Barcode);
+        assert_eq!(result.flight_number.as_deref(), Some("BA117"));
+        assert_eq!(result.seat_number.as_deref(), Some("12A"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
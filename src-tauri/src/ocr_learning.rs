@@ -5,6 +5,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// Identical airport substitutions needed before a misread becomes an auto-applied pattern
+pub const AIRPORT_PATTERN_MIN_OCCURRENCES: i64 = 3;
+
+/// Share of all corrections of a misread that must agree on the replacement
+const AIRPORT_PATTERN_MIN_AGREEMENT: f64 = 0.8;
+
+/// Minimum confidence for a learned airport pattern to be applied without asking
+const AIRPORT_AUTO_APPLY_CONFIDENCE: f64 = 0.9;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OcrCorrection {
     pub id: String,
@@ -33,6 +42,7 @@ pub struct OcrLearningPattern {
     pub occurrence_count: i32,
     pub success_count: i32,
     pub rejection_count: i32,
+    pub applied_count: i32,
     pub is_active: bool,
     pub created_at: String,
     pub updated_at: String,
@@ -82,6 +92,12 @@ pub fn record_correction(
     // Update or create learning pattern
     update_learning_patterns(conn, user_id, field_name, original_value, corrected_value, &pattern_type)?;
 
+    if field_name.contains("airport") {
+        if let Some(orig) = original_value {
+            mine_airport_pattern(conn, user_id, orig, corrected_value)?;
+        }
+    }
+
     Ok(id)
 }
 
@@ -184,22 +200,93 @@ fn update_learning_patterns(
     Ok(())
 }
 
+/// Promote a repeated airport misread (e.g. "0RD" -> "ORD") to a high-confidence
+/// `airport_misread` pattern scoped to all airport fields. Requires the same
+/// substitution AIRPORT_PATTERN_MIN_OCCURRENCES times, agreeing with most other
+/// corrections of that misread. A pattern the user disabled stays disabled.
+fn mine_airport_pattern(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    original_value: &str,
+    corrected_value: &str,
+) -> Result<()> {
+    let original = original_value.trim().to_uppercase();
+    let corrected = corrected_value.trim().to_uppercase();
+    if original.is_empty() || original == corrected {
+        return Ok(());
+    }
+
+    let (matching, total): (i64, i64) = conn.query_row(
+        "SELECT
+            SUM(CASE WHEN UPPER(TRIM(corrected_value)) = ?3 THEN 1 ELSE 0 END),
+            COUNT(*)
+         FROM ocr_corrections
+         WHERE user_id = ?1 AND field_name LIKE '%airport%'
+         AND UPPER(TRIM(original_value)) = ?2",
+        rusqlite::params![user_id, original, corrected],
+        |row| Ok((row.get::<_, Option<i64>>(0)?.unwrap_or(0), row.get(1)?)),
+    )?;
+
+    let agreement = matching as f64 / total.max(1) as f64;
+    if matching < AIRPORT_PATTERN_MIN_OCCURRENCES || agreement < AIRPORT_PATTERN_MIN_AGREEMENT {
+        return Ok(());
+    }
+    let confidence = (0.85 + 0.15 * agreement).min(0.99);
+
+    let updated = conn.execute(
+        "UPDATE ocr_learning_patterns
+         SET replacement_value = ?3, occurrence_count = ?4,
+             confidence = MAX(confidence, ?5), updated_at = datetime('now')
+         WHERE user_id = ?1 AND field_name = 'airport'
+         AND pattern_type = 'airport_misread' AND match_pattern = ?2",
+        rusqlite::params![user_id, original, corrected, matching, confidence],
+    )?;
+
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO ocr_learning_patterns (
+                id, user_id, pattern_type, field_name, match_pattern, replacement_value,
+                confidence, occurrence_count, created_at, updated_at
+            ) VALUES (?1, ?2, 'airport_misread', 'airport', ?3, ?4, ?5, ?6, datetime('now'), datetime('now'))",
+            rusqlite::params![
+                Uuid::new_v4().to_string(),
+                user_id,
+                original,
+                corrected,
+                confidence,
+                matching,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Get all active learning patterns for a user
 pub fn get_active_patterns(
     conn: &rusqlite::Connection,
     user_id: &str,
+) -> Result<Vec<OcrLearningPattern>> {
+    list_patterns(conn, user_id, false)
+}
+
+/// Get learning patterns for review, optionally including disabled ones
+pub fn list_patterns(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    include_inactive: bool,
 ) -> Result<Vec<OcrLearningPattern>> {
     let mut stmt = conn.prepare(
         "SELECT id, user_id, pattern_type, field_name, match_pattern, replacement_value,
                 confidence, occurrence_count, success_count, rejection_count, is_active,
-                created_at, updated_at, last_used
+                created_at, updated_at, last_used, COALESCE(applied_count, 0)
          FROM ocr_learning_patterns
-         WHERE user_id = ?1 AND is_active = 1
-         ORDER BY confidence DESC, occurrence_count DESC"
+         WHERE user_id = ?1 AND (is_active = 1 OR ?2)
+         ORDER BY is_active DESC, confidence DESC, occurrence_count DESC"
     )?;
 
     let patterns = stmt
-        .query_map([user_id], |row| {
+        .query_map(rusqlite::params![user_id, include_inactive], |row| {
             Ok(OcrLearningPattern {
                 id: row.get(0)?,
                 user_id: row.get(1)?,
@@ -211,6 +298,7 @@ pub fn get_active_patterns(
                 occurrence_count: row.get(7)?,
                 success_count: row.get(8)?,
                 rejection_count: row.get(9)?,
+                applied_count: row.get(14)?,
                 is_active: row.get::<_, i32>(10)? == 1,
                 created_at: row.get(11)?,
                 updated_at: row.get(12)?,
@@ -233,10 +321,15 @@ pub fn apply_patterns_to_ocr_result(
 
     for (field_name, value_opt) in field_values {
         if let Some(value) = value_opt {
-            // Find matching patterns for this field
-            for pattern in patterns.iter().filter(|p| &p.field_name == field_name) {
-                // Exact match
-                if &pattern.match_pattern == value {
+            // Find matching patterns for this field ("airport" patterns cover every airport field)
+            for pattern in patterns.iter().filter(|p| {
+                &p.field_name == field_name || (p.field_name == "airport" && field_name.contains("airport"))
+            }) {
+                // Exact match (airport codes compare case-insensitively)
+                if &pattern.match_pattern == value
+                    || (pattern.pattern_type == "airport_misread"
+                        && pattern.match_pattern.eq_ignore_ascii_case(value.trim()))
+                {
                     suggestions.push(CorrectionSuggestion {
                         field_name: field_name.clone(),
                         original_value: value.clone(),
@@ -276,6 +369,78 @@ pub fn apply_patterns_to_ocr_result(
     Ok(suggestions)
 }
 
+/// Auto-apply high-confidence learned airport misreads to a fresh OCR result.
+/// Each replacement is recorded in `applied_corrections` and counted on the pattern.
+pub fn auto_apply_airport_patterns(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    result: &mut crate::ocr::OcrFlightResult,
+) -> Result<()> {
+    let patterns: Vec<OcrLearningPattern> = get_active_patterns(conn, user_id)?
+        .into_iter()
+        .filter(|p| p.pattern_type == "airport_misread" && p.confidence >= AIRPORT_AUTO_APPLY_CONFIDENCE)
+        .collect();
+    if patterns.is_empty() {
+        return Ok(());
+    }
+
+    for (field_name, value) in [
+        ("departure_airport", &mut result.departure_airport),
+        ("arrival_airport", &mut result.arrival_airport),
+    ] {
+        let Some(current) = value.as_deref() else { continue };
+        let Some(pattern) = patterns
+            .iter()
+            .find(|p| p.match_pattern.eq_ignore_ascii_case(current.trim()))
+        else {
+            continue;
+        };
+
+        result.applied_corrections.push(CorrectionSuggestion {
+            field_name: field_name.to_string(),
+            original_value: current.to_string(),
+            suggested_value: pattern.replacement_value.clone(),
+            confidence: pattern.confidence,
+            pattern_type: pattern.pattern_type.clone(),
+            reason: format!(
+                "Auto-corrected: you fixed this misread {} time(s)",
+                pattern.occurrence_count
+            ),
+        });
+        *value = Some(pattern.replacement_value.clone());
+
+        conn.execute(
+            "UPDATE ocr_learning_patterns
+             SET applied_count = COALESCE(applied_count, 0) + 1,
+                 last_used = datetime('now')
+             WHERE id = ?1",
+            rusqlite::params![pattern.id],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Enable or disable a learned pattern (e.g. one that overfits)
+pub fn set_pattern_active(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    pattern_id: &str,
+    active: bool,
+) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE ocr_learning_patterns
+         SET is_active = ?3, updated_at = datetime('now')
+         WHERE id = ?1 AND user_id = ?2",
+        rusqlite::params![pattern_id, user_id, active],
+    )?;
+    if updated == 0 {
+        anyhow::bail!("Learning pattern {} not found", pattern_id);
+    }
+
+    Ok(())
+}
+
 /// Mark a suggestion as accepted (increase confidence)
 pub fn accept_suggestion(
     conn: &rusqlite::Connection,
@@ -0,0 +1,346 @@
+// OCR image pre-processing
+// Phone photos of boarding passes are often skewed, low-contrast or mostly table top. Each
+// step here is optional (ocr_preprocess_<step> settings, all off by default) and runs in
+// this order before the image is sent for OCR:
+//   crop      - to the region dense with edges (text, barcodes, the pass outline), so the
+//               background does not take up most of the picture
+//   deskew    - the rotation that lines text rows up best (projection profile), within
+//               MAX_SKEW_DEGREES
+//   contrast  - stretch the 1st-99th percentile of brightness to the full range
+//   grayscale - drop color
+// The EXIF orientation is applied first, as re-encoding drops it. The result is a JPEG; the
+// original file is never changed, so it can be processed again with other settings or
+// compared against the raw image (compare_ocr_preprocessing).
+
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageReader, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use crate::database::Database;
+
+/// Skew angles beyond this are left alone; larger ones are usually deliberate
+const MAX_SKEW_DEGREES: f32 = 10.0;
+
+/// Smaller corrections do not change OCR results
+const MIN_SKEW_DEGREES: f32 = 0.5;
+
+const SKEW_STEP_DEGREES: f32 = 0.25;
+
+/// Long side of the downscaled copy used for crop and skew detection
+const ANALYSIS_SIZE: u32 = 800;
+
+/// Brightness change between neighbours that counts as an edge
+const EDGE_THRESHOLD: i32 = 48;
+
+const JPEG_QUALITY: u8 = 90;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreprocessOptions {
+    pub grayscale: bool,
+    pub auto_contrast: bool,
+    pub deskew: bool,
+    pub crop: bool,
+}
+
+impl PreprocessOptions {
+    /// Every step on
+    pub fn all() -> Self {
+        Self { grayscale: true, auto_contrast: true, deskew: true, crop: true }
+    }
+
+    pub fn from_settings(db: &Database) -> Self {
+        let enabled = |key: &str| db.get_setting(key).ok().flatten().as_deref() == Some("true");
+        Self {
+            grayscale: enabled("ocr_preprocess_grayscale"),
+            auto_contrast: enabled("ocr_preprocess_contrast"),
+            deskew: enabled("ocr_preprocess_deskew"),
+            crop: enabled("ocr_preprocess_crop"),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.grayscale || self.auto_contrast || self.deskew || self.crop
+    }
+}
+
+/// What the pipeline changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreprocessReport {
+    /// Steps that changed the image, in order
+    pub steps: Vec<String>,
+    /// Skew that was corrected, in degrees; positive when text ran downhill to the right
+    pub skew_degrees: Option<f32>,
+    /// x, y, width, height of the crop in the (oriented) original
+    pub crop: Option<[u32; 4]>,
+    pub original_size: [u32; 2],
+    pub output_size: [u32; 2],
+}
+
+pub struct Preprocessed {
+    /// JPEG
+    pub bytes: Vec<u8>,
+    pub report: PreprocessReport,
+}
+
+/// Run the enabled steps on an encoded image
+pub fn preprocess(image_bytes: &[u8], options: &PreprocessOptions) -> Result<Preprocessed> {
+    let mut decoder = ImageReader::new(Cursor::new(image_bytes))
+        .with_guessed_format()?
+        .into_decoder()
+        .context("Unsupported image format")?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    let mut rgb = image.to_rgb8();
+    let mut report = PreprocessReport { original_size: [rgb.width(), rgb.height()], ..Default::default() };
+
+    if options.crop {
+        if let Some([x, y, width, height]) = content_bounds(&rgb) {
+            rgb = image::imageops::crop_imm(&rgb, x, y, width, height).to_image();
+            report.crop = Some([x, y, width, height]);
+            report.steps.push("crop".to_string());
+        }
+    }
+    if options.deskew {
+        if let Some(angle) = detect_skew(&analysis_copy(&rgb)) {
+            rgb = rotate(&rgb, angle);
+            report.skew_degrees = Some(angle);
+            report.steps.push("deskew".to_string());
+        }
+    }
+    if options.auto_contrast && stretch_contrast(&mut rgb) {
+        report.steps.push("contrast".to_string());
+    }
+
+    let output = if options.grayscale {
+        report.steps.push("grayscale".to_string());
+        DynamicImage::ImageLuma8(DynamicImage::ImageRgb8(rgb).to_luma8())
+    } else {
+        DynamicImage::ImageRgb8(rgb)
+    };
+    report.output_size = [output.width(), output.height()];
+
+    let mut bytes = Vec::new();
+    output.write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))?;
+    Ok(Preprocessed { bytes, report })
+}
+
+/// Downscaled grayscale copy for detection
+fn analysis_copy(rgb: &RgbImage) -> GrayImage {
+    let gray = DynamicImage::ImageRgb8(rgb.clone()).to_luma8();
+    let scale = ANALYSIS_SIZE as f32 / gray.width().max(gray.height()) as f32;
+    if scale >= 1.0 {
+        return gray;
+    }
+    let (width, height) = ((gray.width() as f32 * scale).round() as u32, (gray.height() as f32 * scale).round() as u32);
+    image::imageops::resize(&gray, width.max(1), height.max(1), image::imageops::FilterType::Triangle)
+}
+
+/// Pixels that differ sharply from their right or lower neighbour
+fn edge_points(gray: &GrayImage) -> Vec<(u32, u32)> {
+    let (width, height) = gray.dimensions();
+    let mut points = Vec::new();
+    for y in 0..height.saturating_sub(1) {
+        for x in 0..width.saturating_sub(1) {
+            let value = gray.get_pixel(x, y)[0] as i32;
+            let dx = (gray.get_pixel(x + 1, y)[0] as i32 - value).abs();
+            let dy = (gray.get_pixel(x, y + 1)[0] as i32 - value).abs();
+            if dx + dy > EDGE_THRESHOLD {
+                points.push((x, y));
+            }
+        }
+    }
+    points
+}
+
+/// The box holding the central 96% of the edges, plus a margin; None when it would not
+/// remove a meaningful part of the image or the image has too little detail to judge
+fn content_bounds(rgb: &RgbImage) -> Option<[u32; 4]> {
+    let gray = analysis_copy(rgb);
+    let (width, height) = gray.dimensions();
+    let points = edge_points(&gray);
+    if points.len() < 200 {
+        return None;
+    }
+
+    let span = |mut values: Vec<u32>, size: u32| {
+        values.sort_unstable();
+        let trim = values.len() / 50;
+        let (low, high) = (values[trim], values[values.len() - 1 - trim]);
+        let margin = size / 25;
+        (low.saturating_sub(margin), (high + margin + 1).min(size))
+    };
+    let (left, right) = span(points.iter().map(|p| p.0).collect(), width);
+    let (top, bottom) = span(points.iter().map(|p| p.1).collect(), height);
+
+    let kept = ((right - left) * (bottom - top)) as f32 / (width * height) as f32;
+    if !(0.15..=0.9).contains(&kept) {
+        return None;
+    }
+
+    let scale_x = rgb.width() as f32 / width as f32;
+    let scale_y = rgb.height() as f32 / height as f32;
+    let x = (left as f32 * scale_x) as u32;
+    let y = (top as f32 * scale_y) as u32;
+    let crop_width = ((right as f32 * scale_x) as u32).min(rgb.width()) - x;
+    let crop_height = ((bottom as f32 * scale_y) as u32).min(rgb.height()) - y;
+    Some([x, y, crop_width, crop_height])
+}
+
+/// Angle (degrees, positive when text runs downhill to the right) at which the rows of
+/// edges are sharpest; None below MIN_SKEW_DEGREES
+fn detect_skew(gray: &GrayImage) -> Option<f32> {
+    let points = edge_points(gray);
+    if points.len() < 200 {
+        return None;
+    }
+    let diagonal = ((gray.width().pow(2) + gray.height().pow(2)) as f32).sqrt();
+    let bins = diagonal.ceil() as usize * 2 + 1;
+
+    // Rows of text give a spiky profile at the right angle, a flat one elsewhere
+    let score = |degrees: f32| {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut profile = vec![0u32; bins];
+        for &(x, y) in &points {
+            let row = y as f32 * cos - x as f32 * sin + diagonal;
+            profile[row.round().clamp(0.0, (bins - 1) as f32) as usize] += 1;
+        }
+        profile.iter().map(|&n| (n as f64).powi(2)).sum::<f64>()
+    };
+
+    let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES) as i32;
+    let best = (-steps..=steps)
+        .map(|i| i as f32 * SKEW_STEP_DEGREES)
+        .map(|degrees| (degrees, score(degrees)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?
+        .0;
+    (best.abs() >= MIN_SKEW_DEGREES).then_some(best)
+}
+
+/// Rotate about the centre so text skewed by `degrees` runs level; corners are filled white
+fn rotate(rgb: &RgbImage, degrees: f32) -> RgbImage {
+    let (width, height) = rgb.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    RgbImage::from_fn(width, height, |x, y| {
+        let (u, v) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let source_x = u * cos - v * sin + cx - 0.5;
+        let source_y = u * sin + v * cos + cy - 0.5;
+        bilinear(rgb, source_x, source_y).unwrap_or(image::Rgb([255, 255, 255]))
+    })
+}
+
+fn bilinear(rgb: &RgbImage, x: f32, y: f32) -> Option<image::Rgb<u8>> {
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    if x0 + 1 >= rgb.width() || y0 + 1 >= rgb.height() {
+        return None;
+    }
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let [a, b, c, d] = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| rgb.get_pixel(x0 + dx, y0 + dy).0);
+    let mut out = [0u8; 3];
+    for (channel, value) in out.iter_mut().enumerate() {
+        let top = a[channel] as f32 * (1.0 - fx) + b[channel] as f32 * fx;
+        let bottom = c[channel] as f32 * (1.0 - fx) + d[channel] as f32 * fx;
+        *value = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    Some(image::Rgb(out))
+}
+
+/// Stretch the 1st-99th brightness percentile to 0-255; false when there was nothing to gain
+fn stretch_contrast(rgb: &mut RgbImage) -> bool {
+    let mut histogram = [0usize; 256];
+    for pixel in rgb.pixels() {
+        let [r, g, b] = pixel.0;
+        let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as usize;
+        histogram[luma.min(255)] += 1;
+    }
+    let total: usize = histogram.iter().sum();
+    let percentile = |fraction: f32| {
+        let target = (total as f32 * fraction) as usize;
+        let mut seen = 0;
+        histogram
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen > target
+            })
+            .unwrap_or(255) as f32
+    };
+    let (low, high) = (percentile(0.01), percentile(0.99));
+    // A blank page, or one that already uses the full range
+    if high - low < 16.0 || (low <= 2.0 && high >= 253.0) {
+        return false;
+    }
+
+    let scale = 255.0 / (high - low);
+    for pixel in rgb.pixels_mut() {
+        for value in pixel.0.iter_mut() {
+            *value = ((*value as f32 - low) * scale).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Light grey card with dark text rows on a mid-grey table, tilted by `degrees`
+    fn photo(degrees: f32) -> RgbImage {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        RgbImage::from_fn(600, 400, |x, y| {
+            // Back into the card's own coordinates, centred on it
+            let (u, v) = (x as f32 - 300.0, y as f32 - 200.0);
+            let (card_x, card_y) = (u * cos + v * sin, v * cos - u * sin);
+            let value = if card_x.abs() >= 150.0 || card_y.abs() >= 100.0 {
+                150
+            } else if card_x.abs() < 140.0
+                && card_y.abs() < 90.0
+                && ((card_y + 90.0) as i32) % 24 < 6
+                && ((card_x + 140.0) as i32) % 12 < 9
+            {
+                90
+            } else {
+                200
+            };
+            image::Rgb([value, value, value])
+        })
+    }
+
+    fn encode(rgb: &RgbImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(rgb.clone()).write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, 95)).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_detect_skew() {
+        let level = DynamicImage::ImageRgb8(photo(0.0)).to_luma8();
+        assert_eq!(detect_skew(&level), None);
+        let tilted = DynamicImage::ImageRgb8(photo(4.0)).to_luma8();
+        let angle = detect_skew(&tilted).unwrap();
+        assert!((angle - 4.0).abs() <= 0.5, "detected {}", angle);
+    }
+
+    #[test]
+    fn test_preprocess_pipeline() {
+        let bytes = encode(&photo(3.0));
+        assert!(preprocess(&bytes, &PreprocessOptions::default()).unwrap().report.steps.is_empty());
+
+        let result = preprocess(&bytes, &PreprocessOptions::all()).unwrap();
+        assert_eq!(result.report.steps, vec!["crop", "deskew", "contrast", "grayscale"]);
+        let [_, _, width, height] = result.report.crop.unwrap();
+        assert!((280..450).contains(&width) && (180..330).contains(&height), "crop {}x{}", width, height);
+        assert!((result.report.skew_degrees.unwrap() - 3.0).abs() <= 0.5);
+
+        let output = image::load_from_memory(&result.bytes).unwrap();
+        assert_eq!([output.width(), output.height()], result.report.output_size);
+        assert!(preprocess(b"not an image", &PreprocessOptions::all()).is_err());
+    }
+}
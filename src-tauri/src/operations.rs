@@ -0,0 +1,248 @@
+// Long running operations
+// Batch OCR, batch calculations and similar work register an OperationHandle when they
+// start. The handle carries the operation id, publishes progress as `operation:progress`
+// events and holds a cancellation flag the work checks between items, so the UI can show
+// and cancel any background job the same way. Finished operations are kept (up to
+// MAX_FINISHED) so get_operation_status still answers after the command has returned.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Event carrying an OperationStatus whenever an operation reports progress or finishes
+pub const PROGRESS_EVENT: &str = "operation:progress";
+
+/// Finished operations kept for status queries; the oldest are dropped first
+const MAX_FINISHED: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    Running,
+    Completed,
+    /// Stopped early by cancel_operation; work done before that is kept
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationStatus {
+    pub id: String,
+    /// What is running, e.g. "batch_ocr" or "batch_calculations"
+    pub kind: String,
+    pub state: OperationState,
+    pub current: usize,
+    pub total: usize,
+    /// Operation-specific progress details (phase, counts, current file)
+    pub detail: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub cancel_requested: bool,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+type Listener = Arc<dyn Fn(&OperationStatus) + Send + Sync>;
+
+struct Entry {
+    status: OperationStatus,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Every operation started this session, by id
+#[derive(Clone, Default)]
+pub struct OperationRegistry {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl OperationRegistry {
+    /// Register a running operation and return its handle
+    ///
+    /// `id` lets the caller pick the id up front so it can cancel an operation whose
+    /// command has not returned yet; without one a new UUID is used. Reusing the id of a
+    /// running operation is an error.
+    pub fn start(&self, kind: &str, id: Option<String>) -> anyhow::Result<OperationHandle> {
+        let id = id.filter(|id| !id.trim().is_empty()).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let status = OperationStatus {
+            id: id.clone(),
+            kind: kind.to_string(),
+            state: OperationState::Running,
+            current: 0,
+            total: 0,
+            detail: None,
+            error: None,
+            cancel_requested: false,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+        };
+
+        let mut entries = self.lock();
+        if entries.get(&id).is_some_and(|entry| entry.status.state == OperationState::Running) {
+            anyhow::bail!("Operation {} is already running", id);
+        }
+        prune_finished(&mut entries);
+        entries.insert(id.clone(), Entry { status, cancelled: Arc::clone(&cancelled) });
+        drop(entries);
+
+        Ok(OperationHandle { id, registry: self.clone(), cancelled, listener: None })
+    }
+
+    pub fn status(&self, id: &str) -> Option<OperationStatus> {
+        self.lock().get(id).map(|entry| entry.status.clone())
+    }
+
+    /// All known operations, most recently started first
+    pub fn list(&self) -> Vec<OperationStatus> {
+        let mut statuses: Vec<OperationStatus> = self.lock().values().map(|entry| entry.status.clone()).collect();
+        statuses.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        statuses
+    }
+
+    /// Ask a running operation to stop; false when it is unknown or already finished
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut entries = self.lock();
+        match entries.get_mut(id) {
+            Some(entry) if entry.status.state == OperationState::Running => {
+                entry.cancelled.store(true, Ordering::SeqCst);
+                entry.status.cancel_requested = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn update(&self, id: &str, apply: impl FnOnce(&mut OperationStatus)) -> Option<OperationStatus> {
+        let mut entries = self.lock();
+        let entry = entries.get_mut(id)?;
+        apply(&mut entry.status);
+        Some(entry.status.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        // A panic mid-update leaves the map usable; the worst case is one stale status
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn prune_finished(entries: &mut HashMap<String, Entry>) {
+    let mut finished: Vec<(String, String)> = entries
+        .values()
+        .filter_map(|entry| Some((entry.status.finished_at.clone()?, entry.status.id.clone())))
+        .collect();
+    if finished.len() <= MAX_FINISHED {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() - MAX_FINISHED;
+    for (_, id) in finished.into_iter().take(excess) {
+        entries.remove(&id);
+    }
+}
+
+/// A running operation: its id, its cancellation flag and where its progress goes
+///
+/// Clones share the same operation, so a handle can be passed into spawned tasks.
+#[derive(Clone)]
+pub struct OperationHandle {
+    id: String,
+    registry: OperationRegistry,
+    cancelled: Arc<AtomicBool>,
+    listener: Option<Listener>,
+}
+
+impl OperationHandle {
+    /// Emit every status change to the frontend as a PROGRESS_EVENT
+    pub fn with_events<R: tauri::Runtime>(mut self, app_handle: &tauri::AppHandle<R>) -> Self {
+        use tauri::Emitter;
+        let app_handle = app_handle.clone();
+        self.listener = Some(Arc::new(move |status: &OperationStatus| {
+            let _ = app_handle.emit(PROGRESS_EVENT, status);
+        }));
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Progress, with operation-specific details for the UI
+    pub fn progress_with<D: Serialize>(&self, current: usize, total: usize, detail: &D) {
+        let detail = serde_json::to_value(detail).ok();
+        self.publish(|status| {
+            status.current = current;
+            status.total = total;
+            status.detail = detail;
+        });
+    }
+
+    /// Record how the operation ended, from the result its command returns
+    ///
+    /// An Ok result counts as cancelled when cancellation was requested, since the work
+    /// stopped early and returned what it had.
+    pub fn finish<T, E: std::fmt::Display>(&self, result: &Result<T, E>) {
+        let cancelled = self.is_cancelled();
+        self.publish(|status| {
+            status.state = match result {
+                Ok(_) if cancelled => OperationState::Cancelled,
+                Ok(_) => OperationState::Completed,
+                Err(_) => OperationState::Failed,
+            };
+            status.error = result.as_ref().err().map(|e| e.to_string());
+            status.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        });
+    }
+
+    fn publish(&self, apply: impl FnOnce(&mut OperationStatus)) {
+        if let Some(status) = self.registry.update(&self.id, apply) {
+            if let Some(listener) = &self.listener {
+                listener(&status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_lifecycle() {
+        let registry = OperationRegistry::default();
+        let handle = registry.start("batch_ocr", Some("op-1".to_string())).unwrap();
+        assert!(registry.start("batch_ocr", Some("op-1".to_string())).is_err());
+
+        handle.progress_with(2, 5, &serde_json::json!({ "path": "a.jpg" }));
+        let status = registry.status("op-1").unwrap();
+        assert_eq!((status.current, status.total), (2, 5));
+        assert_eq!(status.state, OperationState::Running);
+
+        assert!(registry.cancel("op-1"));
+        assert!(handle.is_cancelled());
+        handle.finish(&Ok::<(), String>(()));
+        let status = registry.status("op-1").unwrap();
+        assert_eq!(status.state, OperationState::Cancelled);
+        assert!(status.finished_at.is_some());
+
+        // Finished operations cannot be cancelled, and the id can be reused
+        assert!(!registry.cancel("op-1"));
+        assert!(!registry.cancel("missing"));
+        let again = registry.start("batch_ocr", Some("op-1".to_string())).unwrap();
+        again.finish(&Err::<(), _>("boom"));
+        let status = registry.status("op-1").unwrap();
+        assert_eq!(status.state, OperationState::Failed);
+        assert_eq!(status.error.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn test_finished_operations_are_pruned() {
+        let registry = OperationRegistry::default();
+        let _running = registry.start("long", Some("long".to_string())).unwrap();
+        for _ in 0..MAX_FINISHED + 10 {
+            registry.start("short", None).unwrap().finish(&Ok::<(), String>(()));
+        }
+        // Pruning happens as operations start, so the last one finished is still extra
+        assert_eq!(registry.list().len(), MAX_FINISHED + 2);
+        assert_eq!(registry.status("long").unwrap().state, OperationState::Running);
+    }
+}
@@ -0,0 +1,200 @@
+// Orphaned record detection and repair
+// Foreign keys cascade most deletes, but rows written with foreign_keys off (old
+// migrations, manual SQL, tables created without ON DELETE) can still point at parents
+// that are gone. Link rows are deleted; optional links on records worth keeping (media,
+// documents, fuel entries) are cleared instead.
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanRepair {
+    /// The row means nothing without its parent
+    Delete,
+    /// The row stands on its own; only the reference is set to NULL
+    Unlink,
+}
+
+struct OrphanCheck {
+    table: &'static str,
+    column: &'static str,
+    parent: &'static str,
+    repair: OrphanRepair,
+}
+
+const fn check(table: &'static str, column: &'static str, parent: &'static str, repair: OrphanRepair) -> OrphanCheck {
+    OrphanCheck { table, column, parent, repair }
+}
+
+/// Checked in order; parents come before their children so a repair also catches rows
+/// whose parent it just removed
+const CHECKS: [OrphanCheck; 31] = [
+    check("journey_flights", "journey_id", "journeys", OrphanRepair::Delete),
+    check("journey_flights", "flight_id", "flights", OrphanRepair::Delete),
+    check("pilot_logbook", "flight_id", "flights", OrphanRepair::Delete),
+    check("flight_passengers", "flight_id", "flights", OrphanRepair::Delete),
+    check("flight_passengers", "passenger_id", "passengers", OrphanRepair::Delete),
+    check("passenger_aliases", "passenger_id", "passengers", OrphanRepair::Delete),
+    check("passenger_no_dedup", "passenger_id", "passengers", OrphanRepair::Delete),
+    check("passenger_merge_queue", "target_passenger_id", "passengers", OrphanRepair::Delete),
+    check("flight_tags", "flight_id", "flights", OrphanRepair::Delete),
+    check("flight_custom_fields", "flight_id", "flights", OrphanRepair::Delete),
+    check("flight_anomalies", "flight_id", "flights", OrphanRepair::Delete),
+    check("flight_weather", "flight_id", "flights", OrphanRepair::Delete),
+    check("ffp_credits", "flight_id", "flights", OrphanRepair::Delete),
+    check("ffp_credits", "ffp_id", "frequent_flyer_programs", OrphanRepair::Delete),
+    check("duplicate_candidates", "flight_id_1", "flights", OrphanRepair::Delete),
+    check("duplicate_candidates", "flight_id_2", "flights", OrphanRepair::Delete),
+    check("investigations", "flight_id", "flights", OrphanRepair::Delete),
+    check("document_ingestion_queue", "source_document_id", "custom_documents", OrphanRepair::Delete),
+    check("document_chunks", "queue_id", "document_ingestion_queue", OrphanRepair::Delete),
+    check("entity_extractions", "chunk_id", "document_chunks", OrphanRepair::Delete),
+    check("document_matches", "chunk_id", "document_chunks", OrphanRepair::Delete),
+    check("document_matches", "flight_id", "flights", OrphanRepair::Delete),
+    check("custom_schema_fields", "schema_id", "custom_schemas", OrphanRepair::Delete),
+    check("custom_records", "schema_id", "custom_schemas", OrphanRepair::Delete),
+    check("media_files", "flight_id", "flights", OrphanRepair::Unlink),
+    check("media_files", "journey_id", "journeys", OrphanRepair::Unlink),
+    check("custom_documents", "flight_id", "flights", OrphanRepair::Unlink),
+    check("custom_documents", "journey_id", "journeys", OrphanRepair::Unlink),
+    check("research_reports", "flight_id", "flights", OrphanRepair::Unlink),
+    check("fuel_entries", "flight_id", "flights", OrphanRepair::Unlink),
+    check("agent_memory", "flight_id", "flights", OrphanRepair::Unlink),
+];
+
+/// Rows in one table whose reference points at a missing parent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanCount {
+    pub table: String,
+    pub column: String,
+    pub parent_table: String,
+    pub count: i64,
+    pub repair: OrphanRepair,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanReport {
+    pub total: i64,
+    /// Only references with orphans
+    pub orphans: Vec<OrphanCount>,
+}
+
+impl OrphanReport {
+    fn from_counts(orphans: Vec<OrphanCount>) -> Self {
+        OrphanReport { total: orphans.iter().map(|o| o.count).sum(), orphans }
+    }
+}
+
+/// Some tables (media_files) are created on first use
+pub(crate) fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    Ok(conn
+        .query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1", [table], |_| Ok(()))
+        .optional()?
+        .is_some())
+}
+
+fn orphan_filter(check: &OrphanCheck) -> String {
+    format!(
+        "{column} IS NOT NULL AND NOT EXISTS (SELECT 1 FROM {parent} p WHERE p.id = {table}.{column})",
+        table = check.table,
+        column = check.column,
+        parent = check.parent
+    )
+}
+
+fn orphan_count(check: &OrphanCheck, count: i64) -> OrphanCount {
+    OrphanCount {
+        table: check.table.to_string(),
+        column: check.column.to_string(),
+        parent_table: check.parent.to_string(),
+        count,
+        repair: check.repair,
+    }
+}
+
+fn applicable_checks(conn: &Connection) -> Result<Vec<&'static OrphanCheck>> {
+    let mut checks = Vec::new();
+    for check in &CHECKS {
+        if table_exists(conn, check.table)? && table_exists(conn, check.parent)? {
+            checks.push(check);
+        }
+    }
+    Ok(checks)
+}
+
+/// Count orphaned rows per reference without changing anything
+pub fn find_orphans(conn: &Connection) -> Result<OrphanReport> {
+    let mut orphans = Vec::new();
+    for check in applicable_checks(conn)? {
+        let count: i64 = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {} WHERE {}", check.table, orphan_filter(check)), [], |row| {
+                row.get(0)
+            })
+            .with_context(|| format!("Failed to check {}.{}", check.table, check.column))?;
+        if count > 0 {
+            orphans.push(orphan_count(check, count));
+        }
+    }
+    Ok(OrphanReport::from_counts(orphans))
+}
+
+/// Delete or unlink every orphaned row in one transaction; returns what was repaired
+pub fn repair_orphans(conn: &Connection) -> Result<OrphanReport> {
+    let tx = conn.unchecked_transaction()?;
+    let mut repaired = Vec::new();
+    for check in applicable_checks(&tx)? {
+        let sql = match check.repair {
+            OrphanRepair::Delete => format!("DELETE FROM {} WHERE {}", check.table, orphan_filter(check)),
+            OrphanRepair::Unlink => {
+                format!("UPDATE {} SET {} = NULL WHERE {}", check.table, check.column, orphan_filter(check))
+            }
+        };
+        let count = tx
+            .execute(&sql, [])
+            .with_context(|| format!("Failed to repair {}.{}", check.table, check.column))?;
+        if count > 0 {
+            repaired.push(orphan_count(check, count as i64));
+        }
+    }
+    tx.commit()?;
+    Ok(OrphanReport::from_counts(repaired))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_find_and_repair_orphans() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'KJFK', 'KBOS', '2025-01-05T09:00:00');
+                 PRAGMA foreign_keys = OFF;
+                 INSERT INTO flight_tags (flight_id, tag) VALUES ('f1', 'kept'), ('gone', 'orphan');
+                 INSERT INTO fuel_entries (id, user_id, flight_id, gallons, price_per_gallon, total_cost, purchase_date)
+                 VALUES ('fe1', 'u1', 'gone', 100, 6.5, 650, '2025-01-05');
+                 PRAGMA foreign_keys = ON;",
+            )
+            .unwrap();
+
+        let report = find_orphans(&db.conn).unwrap();
+        assert_eq!(report.total, 2);
+        let found: Vec<_> = report.orphans.iter().map(|o| (o.table.as_str(), o.count, o.repair)).collect();
+        assert_eq!(found, [("flight_tags", 1, OrphanRepair::Delete), ("fuel_entries", 1, OrphanRepair::Unlink)]);
+
+        assert_eq!(repair_orphans(&db.conn).unwrap().total, 2);
+        assert_eq!(find_orphans(&db.conn).unwrap().total, 0);
+        let tags: i64 = db.conn.query_row("SELECT COUNT(*) FROM flight_tags", [], |row| row.get(0)).unwrap();
+        assert_eq!(tags, 1);
+        let fuel_flight: Option<String> =
+            db.conn.query_row("SELECT flight_id FROM fuel_entries WHERE id = 'fe1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(fuel_flight, None);
+    }
+}
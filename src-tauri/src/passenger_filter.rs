@@ -0,0 +1,163 @@
+// Passenger name artifact filter
+// Passenger names are parsed from free-text notes, which also yields junk such as "PAX",
+// "N/A", "2 ADT" or header fragments. Those are dropped before analytics by a stop-list
+// (user-editable, setting "passenger_stop_list", one entry per line) plus a few heuristics.
+
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashSet;
+
+pub const STOP_LIST_SETTING: &str = "passenger_stop_list";
+
+/// Tokens that show up in passenger lists but are never names
+pub const DEFAULT_STOP_LIST: &[&str] = &[
+    "PAX", "N/A", "NA", "NONE", "NIL", "NULL", "TBA", "TBD", "UNKNOWN", "UNK", "ADT", "ADULT", "ADULTS", "CHD",
+    "CHILD", "CHILDREN", "INF", "INFANT", "CREW", "PASSENGER", "PASSENGERS", "NAME", "NAMES", "SEAT", "GUEST",
+    "GUESTS", "OTHER", "OTHERS", "VARIOUS", "ETC", "EMPTY", "FERRY", "REPOSITION",
+];
+
+#[derive(Debug, Clone)]
+pub struct PassengerFilter {
+    stop_list: HashSet<String>,
+}
+
+impl Default for PassengerFilter {
+    fn default() -> Self {
+        Self::new(DEFAULT_STOP_LIST.iter().map(|s| s.to_string()))
+    }
+}
+
+impl PassengerFilter {
+    pub fn new(stop_list: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            stop_list: stop_list.into_iter().map(|s| normalize(&s)).filter(|s| !s.is_empty()).collect(),
+        }
+    }
+
+    /// The user's stop-list, or the defaults when none is saved
+    pub fn load(conn: &Connection) -> Self {
+        match load_stop_list(conn) {
+            Some(words) => Self::new(words),
+            None => Self::default(),
+        }
+    }
+
+    /// Why `name` looks like a parsing artifact rather than a person; None for plausible names
+    pub fn artifact_reason(&self, name: &str) -> Option<&'static str> {
+        let name = normalize(name);
+        let alphanumeric: Vec<char> = name.chars().filter(|c| c.is_alphanumeric()).collect();
+        if alphanumeric.len() <= 1 {
+            return Some("too short");
+        }
+        if alphanumeric.iter().all(|c| c.is_ascii_digit()) {
+            return Some("digits only");
+        }
+        if self.stop_list.contains(&name) {
+            return Some("stop-list");
+        }
+        // Passenger counts and fare codes: "2 ADT", "PAX 3", "1 CHD 1 INF"
+        let tokens: Vec<&str> = name.split_whitespace().collect();
+        if tokens.len() > 1
+            && tokens.iter().all(|t| t.chars().all(|c| c.is_ascii_digit()) || self.stop_list.contains(*t))
+        {
+            return Some("passenger count");
+        }
+        if name.ends_with(':') || name.starts_with("NOTE") || name.starts_with("COMMENT") {
+            return Some("header fragment");
+        }
+        None
+    }
+
+    pub fn is_artifact(&self, name: &str) -> bool {
+        self.artifact_reason(name).is_some()
+    }
+}
+
+/// Uppercase with whitespace collapsed, the form names and stop-list entries are compared in
+pub fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase()
+}
+
+/// Saved stop-list entries; None when the user never edited it
+pub fn load_stop_list(conn: &Connection) -> Option<Vec<String>> {
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [STOP_LIST_SETTING], |row| row.get(0))
+        .optional()
+        .ok()
+        .flatten();
+    value.map(|v| v.lines().map(normalize).filter(|s| !s.is_empty()).collect())
+}
+
+/// Stop-list as saved in settings: normalized, deduplicated, one per line
+pub fn serialize_stop_list(words: &[String]) -> String {
+    let mut seen = HashSet::new();
+    words
+        .iter()
+        .map(|w| normalize(w))
+        .filter(|w| !w.is_empty() && seen.insert(w.clone()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Notes with `names` dropped from the "Passengers: " line; None when none of them appear
+/// The rest of the notes is left as it is; a line left with no passengers is removed.
+pub fn remove_from_notes(notes: &str, names: &HashSet<String>) -> Option<String> {
+    let rest = notes.strip_prefix("Passengers: ")?;
+    let (list, remainder) = match rest.find('\n') {
+        Some(end) => rest.split_at(end),
+        None => (rest, ""),
+    };
+
+    let all: Vec<&str> = list.split(',').map(str::trim).filter(|n| !n.is_empty()).collect();
+    let kept: Vec<&str> = all.iter().copied().filter(|n| !names.contains(&normalize(n))).collect();
+    if kept.len() == all.len() {
+        return None;
+    }
+
+    Some(if kept.is_empty() {
+        remainder.trim_start_matches('\n').to_string()
+    } else {
+        format!("Passengers: {}{}", kept.join(", "), remainder)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_reason() {
+        let filter = PassengerFilter::default();
+        assert_eq!(filter.artifact_reason("pax"), Some("stop-list"));
+        assert_eq!(filter.artifact_reason(" N/A "), Some("stop-list"));
+        assert_eq!(filter.artifact_reason("2 ADT"), Some("passenger count"));
+        assert_eq!(filter.artifact_reason("1 chd  1 inf"), Some("passenger count"));
+        assert_eq!(filter.artifact_reason("12345"), Some("digits only"));
+        assert_eq!(filter.artifact_reason("J."), Some("too short"));
+        assert_eq!(filter.artifact_reason("Remarks:"), Some("header fragment"));
+        assert_eq!(filter.artifact_reason("JOHN SMITH"), None);
+        assert_eq!(filter.artifact_reason("JE"), None);
+        assert_eq!(filter.artifact_reason("AC 2"), None);
+    }
+
+    #[test]
+    fn test_custom_stop_list() {
+        let filter = PassengerFilter::new(vec!["  dog ".to_string()]);
+        assert!(filter.is_artifact("DOG"));
+        assert!(!filter.is_artifact("PAX"));
+        assert_eq!(
+            serialize_stop_list(&["pax".to_string(), "PAX".to_string(), " ".to_string(), "n / a".to_string()]),
+            "PAX\nN / A"
+        );
+    }
+
+    #[test]
+    fn test_remove_from_notes() {
+        let names: HashSet<String> = ["PAX".to_string(), "2 ADT".to_string()].into();
+        assert_eq!(
+            remove_from_notes("Passengers: JE, pax, 2 adt\n\nFuel stop", &names).as_deref(),
+            Some("Passengers: JE\n\nFuel stop")
+        );
+        assert_eq!(remove_from_notes("Passengers: PAX\n\nFuel stop", &names).as_deref(), Some("Fuel stop"));
+        assert_eq!(remove_from_notes("Passengers: JE", &names), None);
+    }
+}
@@ -27,6 +27,7 @@ pub struct FlightRecord {
     pub arrival_airport: String,
     pub distance_km: f64,
     pub co2_kg: f64,
+    #[allow(dead_code)]
     pub aircraft_type: Option<String>,
 }
 
@@ -49,7 +50,7 @@ impl PassengerDossier {
                 f.aircraft_type
             FROM flights f
             INNER JOIN passenger_mappings pm ON f.id = pm.flight_id
-            WHERE pm.passenger_name = ?1 AND f.user_id = ?2
+            WHERE pm.passenger_name = ?1 AND f.user_id = ?2 AND f.deleted_at IS NULL
             ORDER BY f.departure_datetime DESC
         ";
 
@@ -88,11 +89,11 @@ impl PassengerDossier {
             WHERE a.iata_code IN (
                 SELECT DISTINCT f.departure_airport FROM flights f
                 INNER JOIN passenger_mappings pm ON f.id = pm.flight_id
-                WHERE pm.passenger_name = ?1 AND f.user_id = ?2
+                WHERE pm.passenger_name = ?1 AND f.user_id = ?2 AND f.deleted_at IS NULL
                 UNION
                 SELECT DISTINCT f.arrival_airport FROM flights f
                 INNER JOIN passenger_mappings pm ON f.id = pm.flight_id
-                WHERE pm.passenger_name = ?1 AND f.user_id = ?2
+                WHERE pm.passenger_name = ?1 AND f.user_id = ?2 AND f.deleted_at IS NULL
             )
             AND a.country IS NOT NULL
         ";
@@ -110,12 +111,12 @@ impl PassengerDossier {
                 SELECT f.departure_airport as airport_code
                 FROM flights f
                 INNER JOIN passenger_mappings pm ON f.id = pm.flight_id
-                WHERE pm.passenger_name = ?1 AND f.user_id = ?2
+                WHERE pm.passenger_name = ?1 AND f.user_id = ?2 AND f.deleted_at IS NULL
                 UNION ALL
                 SELECT f.arrival_airport as airport_code
                 FROM flights f
                 INNER JOIN passenger_mappings pm ON f.id = pm.flight_id
-                WHERE pm.passenger_name = ?1 AND f.user_id = ?2
+                WHERE pm.passenger_name = ?1 AND f.user_id = ?2 AND f.deleted_at IS NULL
             )
             GROUP BY airport_code
             ORDER BY visit_count DESC
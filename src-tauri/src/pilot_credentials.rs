@@ -0,0 +1,350 @@
+// Pilot credentials
+// Expirable certificates and checks (medical, flight review, IPC, type ratings) with their
+// issue and expiry dates. The free-text endorsements on logbook entries stay as they are;
+// these are the ones worth a warning before they lapse. The expiry report also derives a
+// flight review from logbook remarks, alongside the landing currency in get_pilot_currency.
+
+use crate::error::CommandError;
+use crate::validation::FieldErrors;
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Months, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+pub const CREDENTIAL_TYPES: [&str; 5] = ["medical", "flight_review", "ipc", "type_rating", "other"];
+
+/// A flight review is good through the end of the 24th calendar month after it (14 CFR 61.56)
+pub const FLIGHT_REVIEW_MONTHS: u32 = 24;
+
+/// Logbook remarks or endorsements matching one of these mark a flight review
+const FLIGHT_REVIEW_PATTERNS: [&str; 3] = ["%flight review%", "%BFR%", "%61.56%"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PilotCredential {
+    pub id: String,
+    pub user_id: String,
+    /// One of CREDENTIAL_TYPES
+    pub credential_type: String,
+    /// e.g. "Class 2" for a medical or "B737" for a type rating
+    pub name: Option<String>,
+    /// YYYY-MM-DD
+    pub issued_date: Option<String>,
+    /// YYYY-MM-DD; None for credentials that do not expire
+    pub expires_date: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PilotCredentialInput {
+    pub credential_type: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub issued_date: Option<String>,
+    #[serde(default)]
+    pub expires_date: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiringCredential {
+    /// None for credentials derived from the logbook
+    pub credential_id: Option<String>,
+    pub credential_type: String,
+    pub name: Option<String>,
+    pub expires_date: String,
+    /// Negative once expired
+    pub days_remaining: i64,
+    pub expired: bool,
+    /// Computed from logbook activity rather than entered
+    pub derived: bool,
+}
+
+fn credential_from_row(row: &rusqlite::Row) -> rusqlite::Result<PilotCredential> {
+    Ok(PilotCredential {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        credential_type: row.get(2)?,
+        name: row.get(3)?,
+        issued_date: row.get(4)?,
+        expires_date: row.get(5)?,
+        notes: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+pub fn list_credentials(conn: &Connection, user_id: &str) -> Result<Vec<PilotCredential>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, user_id, credential_type, name, issued_date, expires_date, notes, created_at, updated_at
+         FROM pilot_credentials WHERE user_id = ?1
+         ORDER BY expires_date IS NULL, expires_date, credential_type",
+    )?;
+    let credentials = stmt
+        .query_map(params![user_id], credential_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(credentials)
+}
+
+pub fn get_credential(conn: &Connection, id: &str) -> Result<Option<PilotCredential>> {
+    Ok(conn
+        .query_row(
+            "SELECT id, user_id, credential_type, name, issued_date, expires_date, notes, created_at, updated_at
+             FROM pilot_credentials WHERE id = ?1",
+            params![id],
+            credential_from_row,
+        )
+        .optional()?)
+}
+
+pub fn create_credential(conn: &Connection, user_id: &str, input: &PilotCredentialInput) -> Result<PilotCredential> {
+    validate_credential(input)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO pilot_credentials (id, user_id, credential_type, name, issued_date, expires_date, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            user_id,
+            input.credential_type.trim(),
+            clean(&input.name),
+            clean(&input.issued_date),
+            clean(&input.expires_date),
+            clean(&input.notes)
+        ],
+    )?;
+    get_credential(conn, &id)?.ok_or_else(|| anyhow!("Pilot credential not found: {}", id))
+}
+
+pub fn update_credential(conn: &Connection, id: &str, input: &PilotCredentialInput) -> Result<PilotCredential> {
+    validate_credential(input)?;
+    let updated = conn.execute(
+        "UPDATE pilot_credentials SET credential_type = ?2, name = ?3, issued_date = ?4, expires_date = ?5,
+                notes = ?6, updated_at = datetime('now')
+         WHERE id = ?1",
+        params![
+            id,
+            input.credential_type.trim(),
+            clean(&input.name),
+            clean(&input.issued_date),
+            clean(&input.expires_date),
+            clean(&input.notes)
+        ],
+    )?;
+    if updated == 0 {
+        return Err(CommandError::not_found("Pilot credential", id).into());
+    }
+    get_credential(conn, id)?.ok_or_else(|| anyhow!("Pilot credential not found: {}", id))
+}
+
+pub fn delete_credential(conn: &Connection, id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM pilot_credentials WHERE id = ?1", params![id])? > 0)
+}
+
+/// Trimmed value, None when empty so cleared form fields are stored as NULL
+fn clean(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()
+}
+
+fn optional_date(errors: &mut FieldErrors, field: &str, value: &Option<String>) -> Option<NaiveDate> {
+    let value = clean(value)?;
+    let date = parse_date(value);
+    if date.is_none() {
+        errors.add(field, format!("must be a YYYY-MM-DD date, got \"{}\"", value));
+    }
+    date
+}
+
+fn validate_credential(input: &PilotCredentialInput) -> std::result::Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    let credential_type = input.credential_type.trim();
+    if !CREDENTIAL_TYPES.contains(&credential_type) {
+        errors.add("credential_type", format!("must be one of {}", CREDENTIAL_TYPES.join(", ")));
+    }
+    if matches!(credential_type, "type_rating" | "other") && clean(&input.name).is_none() {
+        errors.add("name", "is required for this credential type");
+    }
+    let issued = optional_date(&mut errors, "issued_date", &input.issued_date);
+    let expires = optional_date(&mut errors, "expires_date", &input.expires_date);
+    if let (Some(issued), Some(expires)) = (issued, expires) {
+        if expires < issued {
+            errors.add("expires_date", "must not be before issued_date");
+        }
+    }
+    errors.into_result()
+}
+
+/// Last day a flight review taken on `completed` is valid
+pub fn flight_review_expiry(completed: NaiveDate) -> NaiveDate {
+    let month_start = completed.with_day(1).unwrap_or(completed);
+    month_start
+        .checked_add_months(Months::new(FLIGHT_REVIEW_MONTHS + 1))
+        .and_then(|next_month| next_month.pred_opt())
+        .unwrap_or(NaiveDate::MAX)
+}
+
+/// Most recent flight whose logbook entry mentions a flight review
+fn last_logbook_flight_review(conn: &Connection, user_id: &str) -> Result<Option<NaiveDate>> {
+    let matches = FLIGHT_REVIEW_PATTERNS
+        .iter()
+        .map(|pattern| format!("pl.remarks LIKE '{0}' OR pl.endorsements LIKE '{0}'", pattern))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let last: Option<String> = conn.query_row(
+        &format!(
+            "SELECT MAX(substr(f.departure_datetime, 1, 10))
+             FROM flights f JOIN pilot_logbook pl ON pl.flight_id = f.id
+             WHERE f.user_id = ?1 AND f.deleted_at IS NULL AND ({})",
+            matches
+        ),
+        params![user_id],
+        |row| row.get(0),
+    )?;
+    Ok(last.as_deref().and_then(parse_date))
+}
+
+/// Credentials that expire within `within_days` of `today`, or already have, soonest first
+///
+/// Stored flight reviews without an expiry date get the 24-calendar-month one. A flight
+/// review is also derived from the logbook unless a stored one is at least as recent.
+pub fn expiring_credentials(
+    conn: &Connection,
+    user_id: &str,
+    today: NaiveDate,
+    within_days: i64,
+) -> Result<Vec<ExpiringCredential>> {
+    let credentials = list_credentials(conn, user_id)?;
+    let mut expiring = Vec::new();
+    let mut latest_review: Option<NaiveDate> = None;
+
+    for credential in &credentials {
+        let issued = credential.issued_date.as_deref().and_then(parse_date);
+        if credential.credential_type == "flight_review" {
+            latest_review = latest_review.max(issued);
+        }
+        let expires = match credential.expires_date.as_deref().and_then(parse_date) {
+            Some(expires) => expires,
+            None if credential.credential_type == "flight_review" => match issued {
+                Some(issued) => flight_review_expiry(issued),
+                None => continue,
+            },
+            None => continue,
+        };
+        expiring.push(expiring_entry(
+            Some(credential.id.clone()),
+            &credential.credential_type,
+            credential.name.clone(),
+            expires,
+            today,
+            false,
+        ));
+    }
+
+    if let Some(review) = last_logbook_flight_review(conn, user_id)? {
+        if latest_review.is_none_or(|stored| stored < review) {
+            expiring.push(expiring_entry(None, "flight_review", None, flight_review_expiry(review), today, true));
+        }
+    }
+
+    expiring.retain(|c| c.days_remaining <= within_days);
+    expiring.sort_by_key(|c| c.days_remaining);
+    Ok(expiring)
+}
+
+fn expiring_entry(
+    credential_id: Option<String>,
+    credential_type: &str,
+    name: Option<String>,
+    expires: NaiveDate,
+    today: NaiveDate,
+    derived: bool,
+) -> ExpiringCredential {
+    let days_remaining = (expires - today).num_days();
+    ExpiringCredential {
+        credential_id,
+        credential_type: credential_type.to_string(),
+        name,
+        expires_date: expires.format("%Y-%m-%d").to_string(),
+        days_remaining,
+        expired: days_remaining < 0,
+        derived,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    fn input(credential_type: &str, issued: Option<&str>, expires: Option<&str>) -> PilotCredentialInput {
+        PilotCredentialInput {
+            credential_type: credential_type.to_string(),
+            name: None,
+            issued_date: issued.map(str::to_string),
+            expires_date: expires.map(str::to_string),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_flight_review_expiry() {
+        assert_eq!(flight_review_expiry(date("2024-03-10")), date("2026-03-31"));
+        assert_eq!(flight_review_expiry(date("2024-02-29")), date("2026-02-28"));
+        assert_eq!(flight_review_expiry(date("2023-12-01")), date("2025-12-31"));
+    }
+
+    #[test]
+    fn test_validate_credential() {
+        assert!(validate_credential(&input("medical", Some("2025-01-01"), Some("2026-01-31"))).is_ok());
+        let Err(CommandError::InvalidFields(fields)) =
+            validate_credential(&input("type_rating", Some("2025-13-01"), Some("2024-01-01")))
+        else {
+            panic!("expected field errors");
+        };
+        let names: Vec<_> = fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(names, ["name", "issued_date"]);
+        assert!(validate_credential(&input("medical", Some("2025-06-01"), Some("2025-01-01"))).is_err());
+        assert!(validate_credential(&input("license", None, None)).is_err());
+    }
+
+    #[test]
+    fn test_expiring_credentials() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'KJFK', 'KBOS', '2023-05-20T09:00:00');
+                 INSERT INTO pilot_logbook (id, flight_id, remarks) VALUES ('e1', 'f1', 'BFR with J. Smith CFI');",
+            )
+            .unwrap();
+        let today = date("2025-04-15");
+        let medical = create_credential(&db.conn, "u1", &input("medical", Some("2024-05-01"), Some("2025-05-31"))).unwrap();
+        create_credential(&db.conn, "u1", &input("ipc", Some("2025-01-01"), Some("2025-12-31"))).unwrap();
+
+        let expiring = expiring_credentials(&db.conn, "u1", today, 60).unwrap();
+        assert_eq!(expiring.len(), 2);
+        assert_eq!(expiring[0].credential_id.as_deref(), Some(medical.id.as_str()));
+        assert_eq!(expiring[0].days_remaining, 46);
+        assert!(expiring[1].derived);
+        assert_eq!(expiring[1].expires_date, "2025-05-31");
+
+        // A more recent recorded review replaces the one derived from the logbook
+        create_credential(&db.conn, "u1", &input("flight_review", Some("2025-03-02"), None)).unwrap();
+        let expiring = expiring_credentials(&db.conn, "u1", today, 60).unwrap();
+        assert_eq!(expiring.len(), 1);
+        assert!(!expiring.iter().any(|c| c.derived));
+    }
+}
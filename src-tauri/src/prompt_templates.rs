@@ -0,0 +1,389 @@
+// Research prompt templates
+// User-editable instructions for the AI research providers. A template fills in the
+// flight context through {placeholders}; the JSON response format each provider parses
+// is always appended by the provider and cannot be edited.
+
+use anyhow::{anyhow, bail, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Placeholders a template may use
+pub const PLACEHOLDERS: [&str; 5] = ["route", "date", "passengers", "topics", "sources"];
+
+/// Placeholders every template must contain
+pub const REQUIRED_PLACEHOLDERS: [&str; 2] = ["route", "date"];
+
+const MAX_TEMPLATE_LEN: usize = 8000;
+
+pub const DEEPSEEK_DEFAULT_ID: &str = "builtin:deepseek";
+pub const GROK_DEFAULT_ID: &str = "builtin:grok";
+
+pub const DEEPSEEK_DEFAULT: &str = "You are an expert research analyst. Analyze the following information about travel on {date} to/from {route}.
+
+Web search results:
+{sources}
+
+Passenger names to research: {passengers}
+
+Focus on:
+{topics}";
+
+pub const GROK_DEFAULT: &str = "Analyze the following flight and travel context:
+Route: {route}
+Date: {date}
+Passengers: {passengers}
+
+Research topics:
+{topics}
+
+Web search results:
+{sources}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+    /// Built-in defaults are read-only
+    pub builtin: bool,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateInput {
+    /// None creates a new template
+    pub id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateValidation {
+    pub valid: bool,
+    pub missing: Vec<String>,
+    pub unknown: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Flight context substituted into a template
+pub struct PromptContext<'a> {
+    pub route: &'a str,
+    pub date: &'a str,
+    pub passengers: &'a [String],
+    pub topics: &'a [String],
+    pub sources: &'a [String],
+}
+
+fn builtin_templates() -> Vec<PromptTemplate> {
+    let builtin = |id: &str, name: &str, description: &str, body: &str| PromptTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        body: body.to_string(),
+        builtin: true,
+        updated_at: None,
+    };
+    vec![
+        builtin(
+            DEEPSEEK_DEFAULT_ID,
+            "DeepSeek default",
+            "News, events, weather and passenger mentions around the flight",
+            DEEPSEEK_DEFAULT,
+        ),
+        builtin(
+            GROK_DEFAULT_ID,
+            "Grok default",
+            "General flight and travel context analysis",
+            GROK_DEFAULT,
+        ),
+    ]
+}
+
+/// Built-in defaults followed by the user's templates
+pub fn list_templates(conn: &Connection) -> Result<Vec<PromptTemplate>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description, body, updated_at FROM research_prompt_templates ORDER BY name",
+    )?;
+    let custom = stmt
+        .query_map([], |row| {
+            Ok(PromptTemplate {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                body: row.get(3)?,
+                builtin: false,
+                updated_at: row.get(4)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut templates = builtin_templates();
+    templates.extend(custom);
+    Ok(templates)
+}
+
+pub fn get_template(conn: &Connection, id: &str) -> Result<Option<PromptTemplate>> {
+    if let Some(builtin) = builtin_templates().into_iter().find(|t| t.id == id) {
+        return Ok(Some(builtin));
+    }
+    Ok(conn
+        .query_row(
+            "SELECT id, name, description, body, updated_at FROM research_prompt_templates WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(PromptTemplate {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    body: row.get(3)?,
+                    builtin: false,
+                    updated_at: row.get(4)?,
+                })
+            },
+        )
+        .optional()?)
+}
+
+/// Body of the template to use for a run: the chosen one, or the provider default
+/// Fails when the template does not exist or is invalid, so a run never starts with a
+/// broken prompt.
+pub fn resolve_body(conn: &Connection, template_id: Option<&str>, default_id: &str) -> Result<String> {
+    let id = template_id.filter(|id| !id.trim().is_empty()).unwrap_or(default_id);
+    let template = get_template(conn, id)?.ok_or_else(|| anyhow!("Prompt template not found: {}", id))?;
+    let validation = validate(&template.body);
+    if !validation.valid {
+        bail!("Prompt template '{}' is invalid: {}", template.name, validation.errors.join("; "));
+    }
+    Ok(template.body)
+}
+
+pub fn save_template(conn: &Connection, input: &PromptTemplateInput) -> Result<PromptTemplate> {
+    if input.name.trim().is_empty() {
+        bail!("Template name is required");
+    }
+    let validation = validate(&input.body);
+    if !validation.valid {
+        bail!("Invalid template: {}", validation.errors.join("; "));
+    }
+
+    let id = match input.id.as_deref() {
+        Some(id) if id.starts_with("builtin:") => bail!("Built-in templates cannot be edited"),
+        Some(id) => id.to_string(),
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+    conn.execute(
+        "INSERT INTO research_prompt_templates (id, name, description, body)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name, description = excluded.description, body = excluded.body,
+            updated_at = datetime('now')",
+        params![id, input.name.trim(), input.description.as_deref().map(str::trim), input.body],
+    )?;
+
+    get_template(conn, &id)?.ok_or_else(|| anyhow!("Prompt template not found: {}", id))
+}
+
+pub fn delete_template(conn: &Connection, id: &str) -> Result<()> {
+    if id.starts_with("builtin:") {
+        bail!("Built-in templates cannot be deleted");
+    }
+    conn.execute("DELETE FROM research_prompt_templates WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Check a template body for missing required and unknown placeholders
+pub fn validate(body: &str) -> PromptTemplateValidation {
+    let used = placeholders_in(body);
+    let missing: Vec<String> = REQUIRED_PLACEHOLDERS
+        .iter()
+        .filter(|p| !used.iter().any(|u| u == *p))
+        .map(|p| p.to_string())
+        .collect();
+    // Each unknown placeholder once, in the order it first appears
+    let mut seen = HashSet::new();
+    let unknown: Vec<String> = used
+        .into_iter()
+        .filter(|u| !PLACEHOLDERS.contains(&u.as_str()) && seen.insert(u.clone()))
+        .collect();
+
+    let mut errors = Vec::new();
+    if body.trim().is_empty() {
+        errors.push("Template is empty".to_string());
+    }
+    if body.len() > MAX_TEMPLATE_LEN {
+        errors.push(format!("Template is longer than {} characters", MAX_TEMPLATE_LEN));
+    }
+    if !missing.is_empty() {
+        errors.push(format!("Missing required placeholders: {}", braced(&missing)));
+    }
+    if !unknown.is_empty() {
+        errors.push(format!("Unknown placeholders: {}", braced(&unknown)));
+    }
+
+    PromptTemplateValidation {
+        valid: errors.is_empty(),
+        missing,
+        unknown,
+        errors,
+    }
+}
+
+/// Fill in a template
+///
+/// Lines whose placeholders all render empty (no passengers, no topics) are dropped.
+/// When the template has no {sources}, search results are appended at the end.
+pub fn render(body: &str, context: &PromptContext) -> String {
+    let passengers = context.passengers.join(", ");
+    let topics = context
+        .topics
+        .iter()
+        .map(|t| format!("- {}", topic_description(t)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let sources = format_sources(context.sources);
+    let value = |name: &str| match name {
+        "route" => context.route,
+        "date" => context.date,
+        "passengers" => passengers.as_str(),
+        "topics" => topics.as_str(),
+        "sources" => sources.as_str(),
+        _ => "",
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in body.lines() {
+        let used = placeholders_in(line);
+        if !used.is_empty() && used.iter().all(|p| value(p).is_empty()) {
+            continue;
+        }
+        let mut rendered = line.to_string();
+        for name in used {
+            rendered = rendered.replace(&format!("{{{}}}", name), value(&name));
+        }
+        // Keep at most one blank line in a row after dropped lines
+        if rendered.trim().is_empty() && lines.last().is_some_and(|l| l.trim().is_empty()) {
+            continue;
+        }
+        lines.push(rendered);
+    }
+
+    let mut prompt = lines.join("\n").trim().to_string();
+    if !placeholders_in(body).iter().any(|p| p == "sources") && !sources.is_empty() {
+        prompt.push_str("\n\nWeb search results:\n");
+        prompt.push_str(&sources);
+    }
+    prompt
+}
+
+/// Instruction line for a research topic
+fn topic_description(topic: &str) -> String {
+    match topic {
+        "news" => "Top news stories from that date and location".to_string(),
+        "events" => "Major events, conferences, or gatherings".to_string(),
+        "weather" => "Weather conditions".to_string(),
+        "passengers" => "Any mentions of the passenger names".to_string(),
+        "aviation" => "Flight status, aviation incidents or disruptions".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn format_sources(sources: &[String]) -> String {
+    sources
+        .iter()
+        .enumerate()
+        .map(|(idx, source)| format!("[Source {}]:\n{}", idx + 1, source))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Names inside {braces} that look like placeholders (letters and underscores only)
+fn placeholders_in(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..close];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+            names.push(name.to_string());
+            rest = &rest[close + 1..];
+        }
+    }
+    names
+}
+
+fn braced(names: &[String]) -> String {
+    names.iter().map(|n| format!("{{{}}}", n)).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(DEEPSEEK_DEFAULT).valid);
+        assert!(validate(GROK_DEFAULT).valid);
+
+        let result = validate("Business events in {route} ({topicz})");
+        assert!(!result.valid);
+        assert_eq!(result.missing, vec!["date"]);
+        assert_eq!(result.unknown, vec!["topicz"]);
+        let repeated = validate("{route} {date} {foo} {bar} {foo} {bar}");
+        assert_eq!(repeated.unknown, vec!["foo", "bar"]);
+
+        // JSON braces in a template are not placeholders
+        assert!(validate("{route} on {date}, reply as {\"summary\": \"...\"}").valid);
+    }
+
+    #[test]
+    fn test_render_drops_empty_lines() {
+        let topics = vec!["news".to_string(), "conferences".to_string()];
+        let sources = vec!["Snippet one".to_string()];
+        let context = PromptContext {
+            route: "EGLL to KJFK",
+            date: "2024-06-01",
+            passengers: &[],
+            topics: &topics,
+            sources: &sources,
+        };
+
+        let prompt = render(GROK_DEFAULT, &context);
+        assert!(prompt.contains("Route: EGLL to KJFK\nDate: 2024-06-01\n\nResearch topics:"));
+        assert!(!prompt.contains("Passengers"));
+        assert!(prompt.contains("- Top news stories from that date and location\n- conferences"));
+        assert!(prompt.ends_with("[Source 1]:\nSnippet one"));
+
+        let appended = render("Business events in {route} on {date}", &context);
+        assert_eq!(
+            appended,
+            "Business events in EGLL to KJFK on 2024-06-01\n\nWeb search results:\n[Source 1]:\nSnippet one"
+        );
+    }
+
+    #[test]
+    fn test_save_and_resolve() {
+        let db = crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap();
+        let input = PromptTemplateInput {
+            id: None,
+            name: "Business trips".to_string(),
+            description: None,
+            body: "Business events and conferences in {route} around {date}.".to_string(),
+        };
+        let saved = save_template(&db.conn, &input).unwrap();
+        assert!(!saved.builtin);
+        assert_eq!(list_templates(&db.conn).unwrap().len(), 3);
+        assert_eq!(resolve_body(&db.conn, Some(&saved.id), GROK_DEFAULT_ID).unwrap(), input.body);
+        assert_eq!(resolve_body(&db.conn, None, GROK_DEFAULT_ID).unwrap(), GROK_DEFAULT);
+
+        let invalid = PromptTemplateInput { body: "No placeholders".to_string(), ..input };
+        assert!(save_template(&db.conn, &invalid).is_err());
+        assert!(delete_template(&db.conn, DEEPSEEK_DEFAULT_ID).is_err());
+        assert!(resolve_body(&db.conn, Some("missing"), GROK_DEFAULT_ID).is_err());
+    }
+}
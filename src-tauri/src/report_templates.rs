@@ -0,0 +1,389 @@
+// Read-only report templates
+// Whitelisted, parameterized SELECT queries that can be run by name from the UI or
+// the agent bridge without exposing arbitrary SQL
+
+use anyhow::{anyhow, bail, Result};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Upper bound on rows returned by any template
+const MAX_ROWS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    /// Free text, at most 200 characters
+    Text,
+    /// Calendar date as YYYY-MM-DD
+    Date,
+    Integer,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateParam {
+    pub name: &'static str,
+    pub param_type: ParamType,
+    pub required: bool,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [TemplateParam],
+    /// Output columns, in order
+    pub columns: &'static [&'static str],
+    #[serde(skip)]
+    sql: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportResult {
+    pub template: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    /// True when the result was cut off at the row limit
+    pub truncated: bool,
+}
+
+const USER_ID: TemplateParam = TemplateParam {
+    name: "user_id",
+    param_type: ParamType::Text,
+    required: true,
+    description: "Owner of the flights",
+};
+const START_DATE: TemplateParam = TemplateParam {
+    name: "start_date",
+    param_type: ParamType::Date,
+    required: false,
+    description: "Include flights departing on or after this date",
+};
+const END_DATE: TemplateParam = TemplateParam {
+    name: "end_date",
+    param_type: ParamType::Date,
+    required: false,
+    description: "Include flights departing on or before this date",
+};
+const LIMIT: TemplateParam = TemplateParam {
+    name: "limit",
+    param_type: ParamType::Integer,
+    required: false,
+    description: "Maximum rows (default 20)",
+};
+
+/// Date range filter shared by the flight templates
+macro_rules! date_range {
+    ($col:literal) => {
+        concat!(
+            "(:start_date IS NULL OR ", $col, " >= :start_date) ",
+            "AND (:end_date IS NULL OR substr(", $col, ", 1, 10) <= :end_date)"
+        )
+    };
+}
+
+static TEMPLATES: &[ReportTemplate] = &[
+    ReportTemplate {
+        name: "flights_by_airport",
+        description: "Flights departing from or arriving at an airport, optionally within a date range",
+        params: &[
+            USER_ID,
+            TemplateParam {
+                name: "airport",
+                param_type: ParamType::Text,
+                required: true,
+                description: "Airport code (IATA or ICAO) as stored on the flight",
+            },
+            START_DATE,
+            END_DATE,
+        ],
+        columns: &[
+            "flight_id",
+            "departure_datetime",
+            "departure_airport",
+            "arrival_airport",
+            "flight_number",
+            "aircraft_registration",
+            "distance_km",
+        ],
+        sql: concat!(
+            "SELECT id, departure_datetime, departure_airport, arrival_airport, flight_number,
+                    aircraft_registration, distance_km
+             FROM flights
+             WHERE user_id = :user_id AND deleted_at IS NULL
+               AND (UPPER(departure_airport) = UPPER(:airport) OR UPPER(arrival_airport) = UPPER(:airport))
+               AND ",
+            date_range!("departure_datetime"),
+            " ORDER BY departure_datetime DESC"
+        ),
+    },
+    ReportTemplate {
+        name: "cost_by_aircraft",
+        description: "Flight count, hours and recorded cost per aircraft registration",
+        params: &[USER_ID, START_DATE, END_DATE],
+        columns: &["aircraft_registration", "flights", "hours", "total_cost", "avg_cost_per_flight"],
+        sql: concat!(
+            "SELECT COALESCE(aircraft_registration, 'Unknown'), COUNT(*),
+                    ROUND(COALESCE(SUM(total_duration), 0) / 60.0, 1),
+                    ROUND(COALESCE(SUM(total_cost), 0.0), 2),
+                    ROUND(COALESCE(AVG(total_cost), 0.0), 2)
+             FROM flights
+             WHERE user_id = :user_id AND deleted_at IS NULL AND ",
+            date_range!("departure_datetime"),
+            " GROUP BY 1 ORDER BY 4 DESC"
+        ),
+    },
+    ReportTemplate {
+        name: "monthly_hours",
+        description: "Flights, hours and distance per month, optionally for one year",
+        params: &[
+            USER_ID,
+            TemplateParam {
+                name: "year",
+                param_type: ParamType::Integer,
+                required: false,
+                description: "Calendar year, e.g. 2024",
+            },
+        ],
+        columns: &["month", "flights", "hours", "distance_km"],
+        sql: "SELECT substr(departure_datetime, 1, 7), COUNT(*),
+                     ROUND(COALESCE(SUM(total_duration), 0) / 60.0, 1),
+                     ROUND(COALESCE(SUM(distance_km), 0.0), 0)
+              FROM flights
+              WHERE user_id = :user_id AND deleted_at IS NULL
+                AND (:year IS NULL OR substr(departure_datetime, 1, 4) = CAST(:year AS TEXT))
+              GROUP BY 1 ORDER BY 1",
+    },
+    ReportTemplate {
+        name: "top_routes",
+        description: "Most flown routes (direction-sensitive) with total distance",
+        params: &[USER_ID, START_DATE, END_DATE, LIMIT],
+        columns: &["departure_airport", "arrival_airport", "flights", "total_distance_km"],
+        sql: concat!(
+            "SELECT departure_airport, arrival_airport, COUNT(*), ROUND(COALESCE(SUM(distance_km), 0.0), 0)
+             FROM flights
+             WHERE user_id = :user_id AND deleted_at IS NULL AND ",
+            date_range!("departure_datetime"),
+            " GROUP BY 1, 2 ORDER BY 3 DESC LIMIT COALESCE(:limit, 20)"
+        ),
+    },
+    ReportTemplate {
+        name: "passenger_flights",
+        description: "Flights linked to passengers whose canonical name contains the given text",
+        params: &[
+            USER_ID,
+            TemplateParam {
+                name: "passenger",
+                param_type: ParamType::Text,
+                required: true,
+                description: "Part of the passenger's canonical name (case-insensitive)",
+            },
+            START_DATE,
+            END_DATE,
+        ],
+        columns: &["passenger", "flight_id", "departure_datetime", "departure_airport", "arrival_airport"],
+        sql: concat!(
+            "SELECT p.canonical_name, f.id, f.departure_datetime, f.departure_airport, f.arrival_airport
+             FROM flight_passengers fp
+             JOIN passengers p ON p.id = fp.passenger_id
+             JOIN flights f ON f.id = fp.flight_id
+             WHERE f.user_id = :user_id AND f.deleted_at IS NULL
+               AND p.canonical_name LIKE '%' || :passenger || '%'
+               AND ",
+            date_range!("f.departure_datetime"),
+            " ORDER BY f.departure_datetime DESC"
+        ),
+    },
+    ReportTemplate {
+        name: "co2_by_month",
+        description: "Total and per-passenger CO2 per month",
+        params: &[USER_ID, START_DATE, END_DATE],
+        columns: &["month", "flights", "total_co2_kg", "per_passenger_co2_kg"],
+        sql: concat!(
+            "SELECT substr(departure_datetime, 1, 7), COUNT(*),
+                    ROUND(COALESCE(SUM(carbon_emissions_kg), 0.0), 1),
+                    ROUND(COALESCE(SUM(per_passenger_co2_kg), 0.0), 1)
+             FROM flights
+             WHERE user_id = :user_id AND deleted_at IS NULL AND ",
+            date_range!("departure_datetime"),
+            " GROUP BY 1 ORDER BY 1"
+        ),
+    },
+];
+
+/// All available templates with their parameters and output columns
+pub fn list_templates() -> &'static [ReportTemplate] {
+    TEMPLATES
+}
+
+/// Run a template by name with JSON parameters
+///
+/// Parameters are type-checked against the template and bound by name, so no
+/// caller-supplied text ever becomes part of the SQL.
+pub fn run_template(
+    conn: &Connection,
+    name: &str,
+    params: &HashMap<String, serde_json::Value>,
+) -> Result<ReportResult> {
+    let template = TEMPLATES
+        .iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| anyhow!("Unknown report template: {}", name))?;
+
+    if let Some(unknown) = params.keys().find(|k| !template.params.iter().any(|p| p.name == k.as_str())) {
+        bail!("Template '{}' has no parameter '{}'", name, unknown);
+    }
+
+    let mut bound: Vec<(String, Value)> = Vec::with_capacity(template.params.len());
+    for param in template.params {
+        let value = match params.get(param.name).filter(|v| !v.is_null()) {
+            Some(value) => convert_param(param, value)?,
+            None if param.required => bail!("Missing required parameter '{}'", param.name),
+            None => Value::Null,
+        };
+        bound.push((format!(":{}", param.name), value));
+    }
+
+    let mut stmt = conn.prepare(template.sql)?;
+    if !stmt.readonly() {
+        bail!("Template '{}' is not read-only", name);
+    }
+    let named: Vec<(&str, &dyn rusqlite::ToSql)> = bound
+        .iter()
+        .filter(|(key, _)| stmt.parameter_index(key).ok().flatten().is_some())
+        .map(|(key, value)| (key.as_str(), value as &dyn rusqlite::ToSql))
+        .collect();
+
+    let column_count = stmt.column_count();
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut result_rows = stmt.query(named.as_slice())?;
+    while let Some(row) = result_rows.next()? {
+        if rows.len() == MAX_ROWS {
+            truncated = true;
+            break;
+        }
+        let values = (0..column_count)
+            .map(|i| row.get_ref(i).map(json_value))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.push(values);
+    }
+
+    Ok(ReportResult {
+        template: template.name.to_string(),
+        columns: template.columns.iter().map(|c| c.to_string()).collect(),
+        row_count: rows.len(),
+        rows,
+        truncated,
+    })
+}
+
+fn convert_param(param: &TemplateParam, value: &serde_json::Value) -> Result<Value> {
+    let invalid = || anyhow!("Parameter '{}' must be {:?}", param.name, param.param_type);
+    match param.param_type {
+        ParamType::Text => {
+            let text = value.as_str().ok_or_else(invalid)?.trim();
+            if text.is_empty() || text.chars().count() > 200 {
+                bail!("Parameter '{}' must be between 1 and 200 characters", param.name);
+            }
+            Ok(Value::Text(text.to_string()))
+        }
+        ParamType::Date => {
+            let text = value.as_str().ok_or_else(invalid)?.trim();
+            let date = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Parameter '{}' must be a date (YYYY-MM-DD)", param.name))?;
+            Ok(Value::Text(date.format("%Y-%m-%d").to_string()))
+        }
+        ParamType::Integer => {
+            let n = value
+                .as_i64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .ok_or_else(invalid)?;
+            Ok(Value::Integer(n))
+        }
+    }
+}
+
+fn json_value(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => serde_json::Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        ValueRef::Text(t) => serde_json::Value::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(b) => serde_json::Value::String(format!("<{} bytes>", b.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_db() -> crate::database::Database {
+        crate::database::Database::new(std::path::PathBuf::from(":memory:")).unwrap()
+    }
+
+    fn params(value: serde_json::Value) -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_all_templates_prepare_read_only() {
+        let db = test_db();
+        for template in list_templates() {
+            let stmt = db.conn.prepare(template.sql).unwrap_or_else(|e| panic!("{}: {}", template.name, e));
+            assert!(stmt.readonly(), "{} is not read-only", template.name);
+            assert_eq!(stmt.column_count(), template.columns.len(), "{} column count", template.name);
+        }
+    }
+
+    #[test]
+    fn test_run_flights_by_airport() {
+        let db = test_db();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Test');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('f1', 'u1', 'KTEB', 'KPBI', '2024-03-01T10:00:00'),
+                        ('f2', 'u1', 'KPBI', 'KTEB', '2024-04-01T10:00:00'),
+                        ('f3', 'u1', 'KJFK', 'KLAX', '2024-03-05T10:00:00');",
+            )
+            .unwrap();
+
+        let result = run_template(
+            &db.conn,
+            "flights_by_airport",
+            &params(json!({"user_id": "u1", "airport": "kteb", "end_date": "2024-03-31"})),
+        )
+        .unwrap();
+        assert_eq!(result.row_count, 1);
+        assert_eq!(result.rows[0][0], json!("f1"));
+        assert_eq!(result.columns.len(), 7);
+
+        // Injection attempts are just values
+        let result = run_template(
+            &db.conn,
+            "flights_by_airport",
+            &params(json!({"user_id": "u1", "airport": "' OR 1=1 --"})),
+        )
+        .unwrap();
+        assert_eq!(result.row_count, 0);
+    }
+
+    #[test]
+    fn test_parameter_validation() {
+        let db = test_db();
+        let run = |value| run_template(&db.conn, "cost_by_aircraft", &params(value));
+
+        assert!(run(json!({})).is_err());
+        assert!(run(json!({"user_id": "u1", "start_date": "03/01/2024"})).is_err());
+        assert!(run(json!({"user_id": "u1", "sql": "DROP TABLE flights"})).is_err());
+        assert!(run(json!({"user_id": "u1", "start_date": "2024-01-01"})).is_ok());
+        assert!(run_template(&db.conn, "drop_everything", &HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+// Retention cleanup
+// Log and cache tables grow without bound over years of use. Each one here has a policy:
+// rows older than max_age_days are deleted, then the oldest rows past max_rows. Both limits
+// are settings (retention_<table>_days, retention_<table>_max_rows; 0 turns a limit off),
+// and rows that have passed their own expires_at go regardless of either.
+//
+// All tables are cleaned in one transaction. With retention_on_startup = true the cleanup
+// also runs when the app starts. The network flow sampler applies its own table's policy
+// as it goes (run_table), since it writes a row per connection every sample.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::orphans::table_exists;
+
+struct RetentionTable {
+    table: &'static str,
+    /// Column the age is measured from
+    age_column: &'static str,
+    /// Column holding the row's own expiry, if it has one
+    expires_column: Option<&'static str>,
+    default_max_age_days: u32,
+    default_max_rows: u32,
+}
+
+const TABLES: [RetentionTable; 5] = [
+    RetentionTable {
+        table: "query_performance",
+        age_column: "created_at",
+        expires_column: None,
+        default_max_age_days: 90,
+        default_max_rows: 10_000,
+    },
+    RetentionTable {
+        table: "ai_response_cache",
+        age_column: "created_at",
+        expires_column: Some("expires_at"),
+        default_max_age_days: 180,
+        default_max_rows: 5_000,
+    },
+    RetentionTable {
+        table: "ai_cache_lookups",
+        age_column: "created_at",
+        expires_column: None,
+        default_max_age_days: 90,
+        default_max_rows: 50_000,
+    },
+    // Research memory is worth keeping while it is still being read
+    RetentionTable {
+        table: "agent_memory",
+        age_column: "COALESCE(last_accessed, created_at)",
+        expires_column: Some("expires_at"),
+        default_max_age_days: 365,
+        default_max_rows: 20_000,
+    },
+    RetentionTable {
+        table: "network_flows",
+        age_column: "sampled_at",
+        expires_column: None,
+        default_max_age_days: 30,
+        default_max_rows: 500_000,
+    },
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub table: String,
+    /// None keeps rows of any age
+    pub max_age_days: Option<u32>,
+    /// None keeps any number of rows
+    pub max_rows: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableCleanup {
+    pub table: String,
+    pub expired: usize,
+    pub too_old: usize,
+    pub over_limit: usize,
+    pub remaining: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub tables: Vec<TableCleanup>,
+    pub total_removed: usize,
+}
+
+/// The configured policy for every table, defaults filled in
+pub fn policies(db: &Database) -> Vec<RetentionPolicy> {
+    let limit = |key: String, default: u32| {
+        let value = db
+            .get_setting(&key)
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .unwrap_or(default);
+        (value > 0).then_some(value)
+    };
+    TABLES
+        .iter()
+        .map(|t| RetentionPolicy {
+            table: t.table.to_string(),
+            max_age_days: limit(format!("retention_{}_days", t.table), t.default_max_age_days),
+            max_rows: limit(format!("retention_{}_max_rows", t.table), t.default_max_rows),
+        })
+        .collect()
+}
+
+pub fn run_on_startup(db: &Database) -> bool {
+    db.get_setting("retention_on_startup").ok().flatten().as_deref() == Some("true")
+}
+
+/// Apply every policy in one transaction
+pub fn run(db: &Database) -> Result<RetentionReport> {
+    let policies = policies(db);
+    let tx = db.conn.unchecked_transaction()?;
+    let mut report = RetentionReport::default();
+    for (table, policy) in TABLES.iter().zip(&policies) {
+        if !table_exists(&tx, table.table)? {
+            continue;
+        }
+        let cleanup = clean_table(&tx, table, policy)?;
+        report.total_removed += cleanup.expired + cleanup.too_old + cleanup.over_limit;
+        report.tables.push(cleanup);
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Apply one table's policy on its own; None for a table without a policy or not created yet
+pub fn run_table(db: &Database, name: &str) -> Result<Option<TableCleanup>> {
+    let Some((table, policy)) = TABLES.iter().zip(policies(db)).find(|(t, _)| t.table == name) else {
+        return Ok(None);
+    };
+    if !table_exists(&db.conn, table.table)? {
+        return Ok(None);
+    }
+    Ok(Some(clean_table(&db.conn, table, &policy)?))
+}
+
+fn clean_table(conn: &Connection, table: &RetentionTable, policy: &RetentionPolicy) -> Result<TableCleanup> {
+    let name = table.table;
+    let mut cleanup = TableCleanup { table: name.to_string(), ..Default::default() };
+
+    if let Some(expires) = table.expires_column {
+        cleanup.expired = conn.execute(
+            &format!("DELETE FROM {name} WHERE {expires} IS NOT NULL AND {expires} <= datetime('now')"),
+            [],
+        )?;
+    }
+    if let Some(days) = policy.max_age_days {
+        cleanup.too_old = conn.execute(
+            &format!("DELETE FROM {name} WHERE {} < datetime('now', ?1)", table.age_column),
+            [format!("-{} days", days)],
+        )?;
+    }
+    if let Some(max_rows) = policy.max_rows {
+        cleanup.over_limit = conn.execute(
+            &format!(
+                "DELETE FROM {name} WHERE rowid NOT IN (
+                     SELECT rowid FROM {name} ORDER BY {} DESC LIMIT ?1
+                 )",
+                table.age_column
+            ),
+            [max_rows],
+        )?;
+    }
+    cleanup.remaining = conn.query_row(&format!("SELECT COUNT(*) FROM {name}"), [], |row| row.get(0))?;
+    Ok(cleanup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_retention_cleanup() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO query_performance (id, query_hash, query_type, execution_time_ms, created_at) VALUES
+                     ('q1', 'h', 'stats', 5, datetime('now', '-200 days')),
+                     ('q2', 'h', 'stats', 5, datetime('now', '-2 days')),
+                     ('q3', 'h', 'stats', 5, datetime('now', '-1 days')),
+                     ('q4', 'h', 'stats', 5, datetime('now'));
+                 INSERT INTO agent_memory (id, agent_name, memory_type, content, created_at, expires_at, last_accessed) VALUES
+                     ('m1', 'DeepSeek', 'research_result', 'x', datetime('now', '-400 days'), NULL, datetime('now', '-1 days')),
+                     ('m2', 'DeepSeek', 'research_result', 'x', datetime('now', '-400 days'), NULL, NULL),
+                     ('m3', 'DeepSeek', 'research_result', 'x', datetime('now'), datetime('now', '-1 hours'), NULL);",
+            )
+            .unwrap();
+        db.set_setting("retention_query_performance_max_rows", "2").unwrap();
+        db.set_setting("retention_agent_memory_max_rows", "0").unwrap();
+
+        let agent_memory = policies(&db).into_iter().find(|p| p.table == "agent_memory").unwrap();
+        assert_eq!((agent_memory.max_age_days, agent_memory.max_rows), (Some(365), None));
+
+        let report = run(&db).unwrap();
+        let table = |name: &str| report.tables.iter().find(|t| t.table == name).unwrap();
+        let perf = table("query_performance");
+        assert_eq!((perf.too_old, perf.over_limit, perf.remaining), (1, 1, 2));
+        // m1 was read recently, m2 was not; m3 has expired
+        let memory = table("agent_memory");
+        assert_eq!((memory.expired, memory.too_old, memory.remaining), (1, 1, 1));
+        assert_eq!(report.total_removed, 4);
+    }
+}
@@ -0,0 +1,191 @@
+// Route heatmap
+// Where the user flies most: every route flown with its flight count and the great-circle
+// arc to draw it along, plus each airport's traffic, both weighted 0-1 against the busiest
+// so they can be rendered directly as a heatmap overlay. Visit counts alone are
+// get_geospatial_analysis; this is about route density.
+//
+// With `combine_directions` a route and its return are one entry, keyed alphabetically.
+// Routes with an end that has no coordinates are left out and counted in `skipped_*`.
+
+use anyhow::Result;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::commands::batch_calculations::airport_coords;
+use crate::database::Database;
+use crate::flight_card::great_circle_points;
+
+/// One arc point per this many km, within the bounds below
+const KM_PER_ARC_SEGMENT: f64 = 250.0;
+const MIN_ARC_SEGMENTS: usize = 2;
+const MAX_ARC_SEGMENTS: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapRoute {
+    pub from: String,
+    pub to: String,
+    pub flight_count: i64,
+    /// flight_count relative to the busiest route, 0-1
+    pub weight: f64,
+    pub distance_km: f64,
+    /// (lat, lon) along the great circle; longitudes unwrapped across the antimeridian
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapAirport {
+    pub code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Departures plus arrivals on the mapped routes
+    pub flight_count: i64,
+    /// flight_count relative to the busiest airport, 0-1
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteHeatmap {
+    /// Busiest first
+    pub routes: Vec<HeatmapRoute>,
+    /// Busiest first
+    pub airports: Vec<HeatmapAirport>,
+    pub total_flights: i64,
+    /// Routes (and their flights) left out for an airport without coordinates
+    pub skipped_routes: usize,
+    pub skipped_flights: i64,
+}
+
+fn arc_segments(distance_km: f64) -> usize {
+    ((distance_km / KM_PER_ARC_SEGMENT).ceil() as usize).clamp(MIN_ARC_SEGMENTS, MAX_ARC_SEGMENTS)
+}
+
+/// Flights per route between `start_date` and `end_date` (YYYY-MM-DD, inclusive)
+fn route_counts(
+    db: &Database,
+    user_id: &str,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    combine_directions: bool,
+) -> Result<Vec<((String, String), i64)>> {
+    let mut stmt = db.conn.prepare(
+        "SELECT UPPER(TRIM(departure_airport)), UPPER(TRIM(arrival_airport)), COUNT(*)
+         FROM flights
+         WHERE user_id = ?1 AND deleted_at IS NULL
+           AND departure_airport IS NOT NULL AND arrival_airport IS NOT NULL
+           AND (?2 IS NULL OR substr(departure_datetime, 1, 10) >= ?2)
+           AND (?3 IS NULL OR substr(departure_datetime, 1, 10) <= ?3)
+         GROUP BY 1, 2",
+    )?;
+    let rows = stmt
+        .query_map(params![user_id, start_date, end_date], |row| {
+            Ok(((row.get::<_, String>(0)?, row.get::<_, String>(1)?), row.get::<_, i64>(2)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut counts: HashMap<(String, String), i64> = HashMap::new();
+    for ((from, to), count) in rows {
+        if from.is_empty() || to.is_empty() {
+            continue;
+        }
+        let key = if combine_directions && to < from { (to, from) } else { (from, to) };
+        *counts.entry(key).or_default() += count;
+    }
+    Ok(counts.into_iter().collect())
+}
+
+pub fn route_heatmap(
+    db: &Database,
+    user_id: &str,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    combine_directions: bool,
+) -> Result<RouteHeatmap> {
+    let mut heatmap = RouteHeatmap::default();
+    let mut coords: HashMap<String, Option<(f64, f64)>> = HashMap::new();
+    let mut airport_counts: HashMap<String, i64> = HashMap::new();
+
+    for ((from, to), flight_count) in route_counts(db, user_id, start_date, end_date, combine_directions)? {
+        heatmap.total_flights += flight_count;
+        let mut locate = |code: &str| *coords.entry(code.to_string()).or_insert_with(|| airport_coords(db, code));
+        let (Some(from_coords), Some(to_coords)) = (locate(&from), locate(&to)) else {
+            heatmap.skipped_routes += 1;
+            heatmap.skipped_flights += flight_count;
+            continue;
+        };
+
+        *airport_counts.entry(from.clone()).or_default() += flight_count;
+        *airport_counts.entry(to.clone()).or_default() += flight_count;
+        let (_, distance_km) =
+            crate::geo::calculate_geodesic_distance(from_coords.0, from_coords.1, to_coords.0, to_coords.1);
+        heatmap.routes.push(HeatmapRoute {
+            points: great_circle_points(from_coords, to_coords, arc_segments(distance_km)),
+            from,
+            to,
+            flight_count,
+            weight: 0.0,
+            distance_km,
+        });
+    }
+
+    heatmap.airports = airport_counts
+        .into_iter()
+        .filter_map(|(code, flight_count)| {
+            let (latitude, longitude) = coords.get(&code).copied().flatten()?;
+            Some(HeatmapAirport { code, latitude, longitude, flight_count, weight: 0.0 })
+        })
+        .collect();
+
+    let max_route = heatmap.routes.iter().map(|r| r.flight_count).max().unwrap_or(1) as f64;
+    for route in &mut heatmap.routes {
+        route.weight = route.flight_count as f64 / max_route;
+    }
+    let max_airport = heatmap.airports.iter().map(|a| a.flight_count).max().unwrap_or(1) as f64;
+    for airport in &mut heatmap.airports {
+        airport.weight = airport.flight_count as f64 / max_airport;
+    }
+    heatmap.routes.sort_by(|a, b| b.flight_count.cmp(&a.flight_count).then_with(|| (&a.from, &a.to).cmp(&(&b.from, &b.to))));
+    heatmap.airports.sort_by(|a, b| b.flight_count.cmp(&a.flight_count).then_with(|| a.code.cmp(&b.code)));
+    Ok(heatmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_route_heatmap() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, iata_code, name, latitude, longitude) VALUES
+                     ('AAA', 'AAA', 'Alpha', 10.0, 20.0),
+                     ('BBB', 'BBB', 'Bravo', 12.0, 24.0);
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime) VALUES
+                     ('f1', 'u1', 'AAA', 'BBB', '2025-01-01T09:00:00'),
+                     ('f2', 'u1', 'AAA', 'BBB', '2025-01-05T09:00:00'),
+                     ('f3', 'u1', 'bbb', 'AAA', '2025-01-31T18:00:00'),
+                     ('f4', 'u1', 'AAA', 'QQX', '2025-01-10T09:00:00'),
+                     ('f5', 'u1', 'AAA', 'BBB', '2024-06-01T09:00:00');",
+            )
+            .unwrap();
+
+        let heatmap = route_heatmap(&db, "u1", Some("2025-01-01"), Some("2025-01-31"), false).unwrap();
+        assert_eq!(heatmap.total_flights, 4);
+        assert_eq!((heatmap.skipped_routes, heatmap.skipped_flights), (1, 1));
+        let routes: Vec<_> = heatmap.routes.iter().map(|r| (r.from.as_str(), r.to.as_str(), r.flight_count, r.weight)).collect();
+        assert_eq!(routes, [("AAA", "BBB", 2, 1.0), ("BBB", "AAA", 1, 0.5)]);
+        let arc = &heatmap.routes[0].points;
+        assert_eq!(arc.len(), 3);
+        assert!((arc[0].0 - 10.0).abs() < 1e-9 && (arc[2].1 - 24.0).abs() < 1e-9);
+
+        // Both directions as one route; the unresolved end does not count towards AAA
+        let heatmap = route_heatmap(&db, "u1", None, None, true).unwrap();
+        assert_eq!(heatmap.routes.len(), 1);
+        assert_eq!(heatmap.routes[0].flight_count, 4);
+        let airports: Vec<_> = heatmap.airports.iter().map(|a| (a.code.as_str(), a.flight_count)).collect();
+        assert_eq!(airports, [("AAA", 4), ("BBB", 4)]);
+    }
+}
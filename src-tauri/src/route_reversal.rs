@@ -0,0 +1,172 @@
+// Reversed routes
+// Data entry and OCR sometimes swap the departure and arrival airports. A flight is flagged
+// when the user has flown the route far more often the other way (the ratio is the
+// reversed_route_threshold setting), or when its recorded times only make sense with the
+// airports the other way round: with the current airport timezones it lands before it
+// takes off, with the swapped ones it does not.
+//
+// Distance and CO2 are the same in both directions, so the fix only swaps the airports and
+// re-derives total_duration from the local times under the swapped timezones.
+
+use anyhow::{bail, Result};
+use rusqlite::params;
+
+use crate::commands::flights::build_flight_times;
+use crate::database::Database;
+use crate::models::Flight;
+
+/// The opposite direction must be this many times as common as the flight's own
+pub const DEFAULT_REVERSAL_THRESHOLD: f64 = 3.0;
+
+pub const ANOMALY_TYPE: &str = "reversed_route";
+
+pub const SUGGESTED_FIX: &str = "Swap the departure and arrival airports";
+
+/// reversed_route_threshold from settings, or the default when missing or not above 1
+pub fn threshold(db: &Database) -> f64 {
+    db.get_setting("reversed_route_threshold")
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|t| *t > 1.0)
+        .unwrap_or(DEFAULT_REVERSAL_THRESHOLD)
+}
+
+/// Why the flight looks reversed, or None
+pub fn detect(db: &Database, flight: &Flight, threshold: f64) -> Result<Option<String>> {
+    if flight.departure_airport.eq_ignore_ascii_case(&flight.arrival_airport) {
+        return Ok(None);
+    }
+
+    // Other flights only; this one would count for its own direction
+    let (same, opposite): (i64, i64) = db.conn.query_row(
+        "SELECT COALESCE(SUM(departure_airport = ?2 AND arrival_airport = ?3), 0),
+                COALESCE(SUM(departure_airport = ?3 AND arrival_airport = ?2), 0)
+         FROM flights WHERE user_id = ?1 AND id != ?4 AND deleted_at IS NULL",
+        params![flight.user_id, flight.departure_airport, flight.arrival_airport, flight.id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    if opposite as f64 >= threshold * (same + 1) as f64 {
+        return Ok(Some(format!(
+            "{} to {} was flown {} time(s) in the opposite direction and {} time(s) this way",
+            flight.departure_airport, flight.arrival_airport, opposite, same
+        )));
+    }
+
+    if flight.arrival_datetime.is_none() {
+        return Ok(None);
+    }
+    let (departure_tz, arrival_tz) = airport_timezones(db, flight)?;
+    let elapsed = build_flight_times(flight.clone(), departure_tz.clone(), arrival_tz.clone()).elapsed_minutes;
+    let swapped = build_flight_times(flight.clone(), arrival_tz, departure_tz).elapsed_minutes;
+    Ok(match (elapsed, swapped) {
+        (Some(elapsed), Some(swapped)) if elapsed <= 0 && swapped > 0 => Some(format!(
+            "Arrives {} min before it departs with these airports, but takes {} min with them swapped",
+            -elapsed, swapped
+        )),
+        _ => None,
+    })
+}
+
+/// Swap the airports and re-derive total_duration; open reversed_route anomalies are resolved
+pub fn fix(db: &Database, flight_id: &str) -> Result<Flight> {
+    let Some(mut flight) = db.get_flight(flight_id)? else {
+        bail!("Flight not found: {}", flight_id);
+    };
+    std::mem::swap(&mut flight.departure_airport, &mut flight.arrival_airport);
+
+    // Only recorded times are re-derived; an estimated arrival adds nothing
+    if flight.arrival_datetime.is_some() {
+        let (departure_tz, arrival_tz) = airport_timezones(db, &flight)?;
+        if let Some(elapsed) = build_flight_times(flight.clone(), departure_tz, arrival_tz)
+            .elapsed_minutes
+            .filter(|m| *m > 0)
+        {
+            flight.total_duration = Some(elapsed as i32);
+        }
+    }
+
+    let tx = db.conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE flights SET departure_airport = ?2, arrival_airport = ?3, total_duration = ?4,
+                updated_at = datetime('now')
+         WHERE id = ?1",
+        params![flight.id, flight.departure_airport, flight.arrival_airport, flight.total_duration],
+    )?;
+    tx.execute(
+        "UPDATE flight_anomalies SET is_resolved = 1, resolved_at = datetime('now')
+         WHERE flight_id = ?1 AND anomaly_type = ?2 AND is_resolved = 0",
+        params![flight.id, ANOMALY_TYPE],
+    )?;
+    tx.commit()?;
+
+    Ok(db.get_flight(flight_id)?.unwrap_or(flight))
+}
+
+fn airport_timezones(db: &Database, flight: &Flight) -> Result<(Option<String>, Option<String>)> {
+    Ok((
+        db.get_airport_by_code(&flight.departure_airport)?.and_then(|a| a.timezone),
+        db.get_airport_by_code(&flight.arrival_airport)?.and_then(|a| a.timezone),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn seeded_db() -> Database {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO airports (id, iata_code, name, timezone) VALUES
+                     ('LHR', 'LHR', 'Heathrow', 'Europe/London'),
+                     ('JFK', 'JFK', 'John F Kennedy', 'America/New_York');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime) VALUES
+                     ('o1', 'u1', 'BOS', 'PWM', '2025-01-01T08:00:00'),
+                     ('o2', 'u1', 'BOS', 'PWM', '2025-01-08T08:00:00'),
+                     ('o3', 'u1', 'BOS', 'PWM', '2025-01-15T08:00:00'),
+                     ('r1', 'u1', 'PWM', 'BOS', '2025-01-22T08:00:00');
+                 -- Departs London 10:00, arrives New York 13:00 local: entered the wrong way round
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, arrival_datetime, total_duration)
+                 VALUES ('t1', 'u1', 'JFK', 'LHR', '2025-03-01T10:00:00', '2025-03-01T13:00:00', 180);",
+            )
+            .unwrap();
+        db
+    }
+
+    fn reason(db: &Database, id: &str, threshold: f64) -> Option<String> {
+        detect(db, &db.get_flight(id).unwrap().unwrap(), threshold).unwrap()
+    }
+
+    #[test]
+    fn test_detect_reversed_routes() {
+        let db = seeded_db();
+        assert!(reason(&db, "r1", 3.0).unwrap().contains("3 time(s) in the opposite direction"));
+        assert!(reason(&db, "r1", 4.0).is_none());
+        assert!(reason(&db, "o1", 3.0).is_none());
+        assert!(reason(&db, "t1", 3.0).unwrap().contains("swapped"));
+    }
+
+    #[test]
+    fn test_fix_reversed_route() {
+        let db = seeded_db();
+        db.conn
+            .execute(
+                "INSERT INTO flight_anomalies (id, flight_id, anomaly_type, severity, description) VALUES ('a1', 't1', ?1, 'warning', 'x')",
+                [ANOMALY_TYPE],
+            )
+            .unwrap();
+
+        let fixed = fix(&db, "t1").unwrap();
+        assert_eq!((fixed.departure_airport.as_str(), fixed.arrival_airport.as_str()), ("LHR", "JFK"));
+        // 10:00 GMT to 13:00 EST is eight hours
+        assert_eq!(fixed.total_duration, Some(480));
+        assert!(reason(&db, "t1", 3.0).is_none());
+
+        let resolved: i32 =
+            db.conn.query_row("SELECT is_resolved FROM flight_anomalies WHERE id = 'a1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(resolved, 1);
+    }
+}
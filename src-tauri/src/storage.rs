@@ -0,0 +1,182 @@
+// Database storage reporting and compaction
+// Deleted rows leave free pages behind, so the file only grows. The stats report the file
+// size, the free pages that compaction would return, and per-table row counts with their
+// on-disk size (tables plus their indexes, from the dbstat virtual table).
+//
+// Compaction runs an incremental vacuum when auto_vacuum is incremental, otherwise a full
+// VACUUM. VACUUM rewrites the whole file: it needs exclusive access and temporary disk
+// space about the size of the database, so it is refused when the disk is short.
+
+use anyhow::{bail, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub name: String,
+    pub row_count: i64,
+    /// Pages used by the table and its indexes; None when dbstat is unavailable
+    pub size_bytes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseStats {
+    /// Empty for an in-memory database
+    pub path: String,
+    pub file_size_bytes: u64,
+    /// Write-ahead log not yet checkpointed into the main file
+    pub wal_size_bytes: u64,
+    pub page_size: i64,
+    pub page_count: i64,
+    pub free_pages: i64,
+    pub free_bytes: i64,
+    /// none, full or incremental
+    pub auto_vacuum: String,
+    /// Largest first
+    pub tables: Vec<TableStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactResult {
+    /// vacuum or incremental_vacuum
+    pub method: String,
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub pages_freed: i64,
+}
+
+pub fn database_stats(conn: &Connection) -> Result<DatabaseStats> {
+    let path = database_path(conn)?;
+    let pragma = |name: &str| conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+    let page_size = pragma("page_size")?;
+    let free_pages = pragma("freelist_count")?;
+    let auto_vacuum = match pragma("auto_vacuum")? {
+        1 => "full",
+        2 => "incremental",
+        _ => "none",
+    };
+
+    let names: Vec<String> = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let sizes = table_sizes(conn).unwrap_or_else(|e| {
+        tracing::debug!("dbstat unavailable, table sizes not reported: {}", e);
+        HashMap::new()
+    });
+
+    let mut tables = Vec::with_capacity(names.len());
+    for name in names {
+        let row_count = conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", name), [], |row| row.get(0))?;
+        let size_bytes = sizes.get(&name).copied();
+        tables.push(TableStats { name, row_count, size_bytes });
+    }
+    tables.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then(b.row_count.cmp(&a.row_count)));
+
+    Ok(DatabaseStats {
+        file_size_bytes: file_size(&path, ""),
+        wal_size_bytes: file_size(&path, "-wal"),
+        path: path.map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+        page_size,
+        page_count: pragma("page_count")?,
+        free_pages,
+        free_bytes: free_pages * page_size,
+        auto_vacuum: auto_vacuum.to_string(),
+        tables,
+    })
+}
+
+/// Return free pages to the file system; refused when the disk cannot hold a copy of the database
+pub fn compact(conn: &Connection) -> Result<CompactResult> {
+    let path = database_path(conn)?;
+    let size_before_bytes = file_size(&path, "") + file_size(&path, "-wal");
+    let free_before: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    let incremental = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get::<_, i64>(0))? == 2;
+
+    if incremental {
+        // Frees pages in place; no copy of the database is made
+        conn.execute_batch("PRAGMA incremental_vacuum")?;
+    } else {
+        if let Some(dir) = path.as_ref().and_then(|p| p.parent()) {
+            let available = fs2::available_space(dir)?;
+            if available < size_before_bytes {
+                bail!(
+                    "Compacting needs about {} MB of free disk space next to the database, but only {} MB is available",
+                    size_before_bytes.div_ceil(1024 * 1024),
+                    available / (1024 * 1024)
+                );
+            }
+        }
+        conn.execute_batch("VACUUM")?;
+    }
+    // Fold the WAL back in so the file size reflects the result
+    let _ = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()));
+
+    let free_after: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    Ok(CompactResult {
+        method: if incremental { "incremental_vacuum" } else { "vacuum" }.to_string(),
+        size_before_bytes,
+        size_after_bytes: file_size(&path, "") + file_size(&path, "-wal"),
+        pages_freed: free_before - free_after,
+    })
+}
+
+/// The main database file; None for in-memory databases
+fn database_path(conn: &Connection) -> Result<Option<PathBuf>> {
+    let file: String = conn.query_row("SELECT file FROM pragma_database_list WHERE name = 'main'", [], |row| row.get(0))?;
+    Ok((!file.is_empty()).then(|| PathBuf::from(file)))
+}
+
+fn file_size(path: &Option<PathBuf>, suffix: &str) -> u64 {
+    path.as_ref()
+        .and_then(|p| std::fs::metadata(format!("{}{}", p.display(), suffix)).ok())
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+/// Bytes per table, indexes included
+fn table_sizes(conn: &Connection) -> rusqlite::Result<HashMap<String, i64>> {
+    conn.prepare(
+        "SELECT m.tbl_name, SUM(d.pgsize) FROM dbstat d
+         JOIN sqlite_master m ON m.name = d.name
+         GROUP BY m.tbl_name",
+    )?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_and_compact() {
+        let path = std::env::temp_dir().join(format!("storage_{}.db", uuid::Uuid::new_v4()));
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);
+             CREATE INDEX idx_notes_body ON notes(body);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000)
+             INSERT INTO notes (body) SELECT printf('%.200c', 'x') || i FROM n;
+             DELETE FROM notes WHERE id > 100;",
+        )
+        .unwrap();
+
+        let stats = database_stats(&conn).unwrap();
+        let notes = stats.tables.iter().find(|t| t.name == "notes").unwrap();
+        assert_eq!(notes.row_count, 100);
+        assert!(notes.size_bytes.unwrap() > 0);
+        assert!(stats.free_pages > 0);
+        assert_eq!(stats.file_size_bytes as i64, stats.page_count * stats.page_size);
+
+        let result = compact(&conn).unwrap();
+        assert_eq!(result.method, "vacuum");
+        assert!(result.size_after_bytes < result.size_before_bytes);
+        assert_eq!(database_stats(&conn).unwrap().free_pages, 0);
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,200 @@
+// Timezone helpers - convert local naive flight times to UTC using airport zones
+//
+// Flights store departure/arrival as local naive datetimes. The airports table
+// carries a `timezone` column that may hold an IANA name ("America/New_York")
+// or a fixed offset ("+05:30", "UTC-3"). Anything else is treated as unknown.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// A timezone resolved from an airport's `timezone` column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AirportZone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl AirportZone {
+    /// Parse an IANA zone name or a fixed UTC offset
+    pub fn parse(value: &str) -> Option<Self> {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        if let Ok(tz) = trimmed.parse::<Tz>() {
+            return Some(AirportZone::Named(tz));
+        }
+
+        parse_fixed_offset(trimmed).map(AirportZone::Fixed)
+    }
+
+    /// UTC offset in effect at the given local wall-clock time.
+    /// Ambiguous local times (DST fall-back) resolve to the earlier instant;
+    /// non-existent times (DST spring-forward gap) return None.
+    pub fn offset_at_local(&self, local: &NaiveDateTime) -> Option<FixedOffset> {
+        match self {
+            AirportZone::Named(tz) => tz
+                .from_local_datetime(local)
+                .earliest()
+                .map(|dt| dt.offset().fix()),
+            AirportZone::Fixed(offset) => Some(*offset),
+        }
+    }
+
+    /// UTC offset in effect at the given instant
+    pub fn offset_at_utc(&self, utc: &DateTime<Utc>) -> FixedOffset {
+        match self {
+            AirportZone::Named(tz) => tz.offset_from_utc_datetime(&utc.naive_utc()).fix(),
+            AirportZone::Fixed(offset) => *offset,
+        }
+    }
+
+    /// Convert a local wall-clock time in this zone to UTC
+    pub fn local_to_utc(&self, local: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        let offset = self.offset_at_local(local)?;
+        offset
+            .from_local_datetime(local)
+            .single()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+
+    /// Convert a UTC instant to local wall-clock time in this zone
+    pub fn utc_to_local(&self, utc: &DateTime<Utc>) -> NaiveDateTime {
+        let offset = self.offset_at_utc(utc);
+        utc.with_timezone(&offset).naive_local()
+    }
+}
+
+/// Parse fixed offsets such as "+05:30", "-0300", "UTC+2", "GMT-03:30" or "Z"
+fn parse_fixed_offset(value: &str) -> Option<FixedOffset> {
+    let upper = value.to_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper)
+        .trim();
+
+    if rest.is_empty() || rest == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+
+    let (sign, digits) = if let Some(digits) = rest.strip_prefix('+') {
+        (1, digits)
+    } else if let Some(digits) = rest.strip_prefix('-') {
+        (-1, digits)
+    } else {
+        return None;
+    };
+
+    // Only ASCII digits are accepted, which also keeps the byte slicing below on char boundaries
+    let number = |part: &str| -> Option<i32> {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        part.parse().ok()
+    };
+    let (hours, minutes) = if let Some((h, m)) = digits.split_once(':') {
+        (number(h)?, number(m)?)
+    } else if digits.len() == 4 {
+        (number(digits.get(..2)?)?, number(digits.get(2..)?)?)
+    } else {
+        (number(digits)?, 0)
+    };
+
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse a stored flight datetime as a local naive time.
+/// Accepts ISO 8601 with or without seconds, a space separator, or a bare date (midnight).
+/// If the string carries an explicit offset, its wall-clock part is used.
+pub fn parse_local_datetime(value: &str) -> Option<NaiveDateTime> {
+    let trimmed = value.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(trimmed) {
+        return Some(dt.naive_local());
+    }
+
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return Some(dt);
+        }
+    }
+
+    NaiveDate::parse_from_str(trimmed, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+}
+
+/// Format an offset as "+HH:MM"
+pub fn format_offset(offset: &FixedOffset) -> String {
+    let total = offset.local_minus_utc();
+    let sign = if total < 0 { '-' } else { '+' };
+    let abs = total.abs();
+    format!("{}{:02}:{:02}", sign, abs / 3600, (abs % 3600) / 60)
+}
+
+/// Add a flight duration in minutes to a UTC instant
+pub fn add_minutes(utc: &DateTime<Utc>, minutes: i64) -> DateTime<Utc> {
+    *utc + Duration::minutes(minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zone_variants() {
+        assert!(matches!(AirportZone::parse("America/New_York"), Some(AirportZone::Named(_))));
+        assert_eq!(
+            AirportZone::parse("+05:30"),
+            Some(AirportZone::Fixed(FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap()))
+        );
+        assert_eq!(
+            AirportZone::parse("UTC-3"),
+            Some(AirportZone::Fixed(FixedOffset::west_opt(3 * 3600).unwrap()))
+        );
+        assert_eq!(AirportZone::parse(""), None);
+        assert_eq!(AirportZone::parse("Mars/Olympus"), None);
+    }
+
+    #[test]
+    fn test_parse_fixed_offset_rejects_non_ascii() {
+        assert_eq!(parse_fixed_offset("-0300"), FixedOffset::west_opt(3 * 3600));
+        // Four bytes with a multi-byte char straddling the hour/minute split used to panic
+        assert_eq!(parse_fixed_offset("+1é1"), None);
+        assert_eq!(parse_fixed_offset("UTC+５"), None);
+        assert_eq!(parse_fixed_offset("+é:30"), None);
+        assert_eq!(parse_fixed_offset("+-5"), None);
+        assert_eq!(parse_fixed_offset("GMT+"), None);
+    }
+
+    #[test]
+    fn test_local_to_utc_respects_dst() {
+        let zone = AirportZone::parse("America/New_York").unwrap();
+
+        let winter = parse_local_datetime("2025-01-15T14:00:00").unwrap();
+        let summer = parse_local_datetime("2025-07-15T14:00").unwrap();
+
+        assert_eq!(zone.local_to_utc(&winter).unwrap().format("%H:%M").to_string(), "19:00");
+        assert_eq!(zone.local_to_utc(&summer).unwrap().format("%H:%M").to_string(), "18:00");
+        assert_eq!(format_offset(&zone.offset_at_local(&summer).unwrap()), "-04:00");
+    }
+
+    #[test]
+    fn test_parse_local_datetime_formats() {
+        assert!(parse_local_datetime("1991-04-25").is_some());
+        assert!(parse_local_datetime("2025-03-01 09:30:00").is_some());
+        assert!(parse_local_datetime("2025-03-01T09:30:00+02:00").is_some());
+        assert!(parse_local_datetime("not a date").is_none());
+    }
+}
@@ -0,0 +1,358 @@
+// Trip purpose classification (business / personal / training, or the user's own categories)
+// The heuristic scores each category from the notes, the purposes already accepted for the
+// same route and the same passengers, local flights and the day of the week. It works
+// offline and is also the fallback when no AI provider is configured or the call fails.
+//
+// Accepted purposes are stored as a "purpose:<category>" flight tag, one per flight.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Datelike, NaiveDate, Weekday};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::database::Database;
+use crate::models::Flight;
+
+pub const DEFAULT_CATEGORIES: [&str; 3] = ["business", "personal", "training"];
+
+/// Flight tags holding an accepted purpose start with this
+pub const TAG_PREFIX: &str = "purpose:";
+
+/// Words in the notes that point at one of the default categories
+const KEYWORDS: [(&str, &[&str]); 3] = [
+    ("business", &["meeting", "client", "customer", "conference", "office", "work", "sales", "expense", "site visit"]),
+    ("personal", &["vacation", "holiday", "family", "wedding", "birthday", "visit", "leisure", "weekend"]),
+    ("training", &["lesson", "training", "checkride", "check ride", "instructor", "cfi", "dual", "solo", "sim", "pattern work"]),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurposeSuggestion {
+    pub flight_id: String,
+    pub category: String,
+    /// 0.0 - 1.0
+    pub confidence: f64,
+    /// "heuristic" or the AI provider that made the suggestion
+    pub source: String,
+    /// Why this category was suggested
+    pub reasons: Vec<String>,
+    /// Served from the AI response cache
+    pub cached: bool,
+    /// Purpose already accepted for the flight, if any
+    pub accepted: Option<String>,
+    /// Categories the user can choose from
+    pub categories: Vec<String>,
+}
+
+/// What the classification looks at for one flight
+#[derive(Debug, Clone)]
+pub struct FlightContext {
+    pub flight: Flight,
+    pub passengers: Vec<String>,
+    /// Accepted purposes of other flights on this route, either direction
+    pub route_history: HashMap<String, i64>,
+    /// Accepted purposes of other flights with any of these passengers
+    pub passenger_history: HashMap<String, i64>,
+}
+
+/// Categories from the "trip_purpose_categories" setting (comma separated), else the defaults
+pub fn categories(db: &Database) -> Result<Vec<String>> {
+    let configured: Vec<String> = db
+        .get_setting("trip_purpose_categories")?
+        .unwrap_or_default()
+        .split(',')
+        .map(|c| c.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase())
+        .filter(|c| !c.is_empty())
+        .fold(Vec::new(), |mut list, c| {
+            if !list.contains(&c) {
+                list.push(c);
+            }
+            list
+        });
+    if configured.is_empty() {
+        return Ok(DEFAULT_CATEGORIES.iter().map(|c| c.to_string()).collect());
+    }
+    Ok(configured)
+}
+
+/// The flight and its purpose history; None when the user has no such flight or it is deleted
+pub fn load_context(db: &Database, user_id: &str, flight_id: &str) -> Result<Option<FlightContext>> {
+    let conn = &db.conn;
+    let live: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM flights WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NULL",
+        params![flight_id, user_id],
+        |row| row.get(0),
+    )?;
+    let Some(flight) = db.get_flight(flight_id)?.filter(|_| live > 0) else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT p.canonical_name FROM flight_passengers fp
+         JOIN passengers p ON p.id = fp.passenger_id
+         WHERE fp.flight_id = ?1 ORDER BY p.canonical_name",
+    )?;
+    let passengers = stmt
+        .query_map([flight_id], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let route_history = purpose_counts(
+        conn,
+        "SELECT substr(t.tag, ?2), COUNT(*) FROM flight_tags t
+         JOIN flights f ON f.id = t.flight_id
+         WHERE t.tag LIKE 'purpose:%' AND f.id != ?1 AND f.user_id = ?3 AND f.deleted_at IS NULL
+           AND ((f.departure_airport = ?4 AND f.arrival_airport = ?5)
+             OR (f.departure_airport = ?5 AND f.arrival_airport = ?4))
+         GROUP BY 1",
+        params![flight.id, TAG_PREFIX.len() + 1, flight.user_id, flight.departure_airport, flight.arrival_airport],
+    )?;
+    let passenger_history = purpose_counts(
+        conn,
+        "SELECT substr(t.tag, ?2), COUNT(DISTINCT t.flight_id) FROM flight_tags t
+         JOIN flight_passengers other ON other.flight_id = t.flight_id
+         JOIN flight_passengers this ON this.passenger_id = other.passenger_id AND this.flight_id = ?1
+         JOIN flights f ON f.id = t.flight_id
+         WHERE t.tag LIKE 'purpose:%' AND t.flight_id != ?1 AND f.user_id = ?3 AND f.deleted_at IS NULL
+         GROUP BY 1",
+        params![flight.id, TAG_PREFIX.len() + 1, flight.user_id],
+    )?;
+
+    Ok(Some(FlightContext { flight, passengers, route_history, passenger_history }))
+}
+
+fn purpose_counts(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<HashMap<String, i64>> {
+    let mut stmt = conn.prepare(sql)?;
+    let counts = stmt
+        .query_map(params, |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+    Ok(counts)
+}
+
+fn departure_weekday(flight: &Flight) -> Option<Weekday> {
+    let date = flight.departure_datetime.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok().map(|d| d.weekday())
+}
+
+/// Offline suggestion from the flight's own details and earlier accepted purposes
+pub fn heuristic(context: &FlightContext, categories: &[String]) -> PurposeSuggestion {
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    let mut reasons: Vec<(String, f64, String)> = Vec::new();
+    let mut add = |category: &str, points: f64, reason: String| {
+        if let Some(category) = categories.iter().find(|c| c.as_str() == category) {
+            *scores.entry(category.as_str()).or_insert(0.0) += points;
+            reasons.push((category.clone(), points, reason));
+        }
+    };
+
+    let flight = &context.flight;
+    let notes = flight.notes.as_deref().unwrap_or("").to_lowercase();
+    if !notes.is_empty() {
+        for category in categories {
+            let keywords = KEYWORDS.iter().find(|(name, _)| name == category).map_or(&[][..], |(_, words)| *words);
+            let found = std::iter::once(category.as_str())
+                .chain(keywords.iter().copied())
+                .find(|word| contains_word(&notes, word));
+            if let Some(word) = found {
+                add(category, 3.0, format!("Notes mention \"{}\"", word));
+            }
+        }
+    }
+
+    for (category, count) in &context.route_history {
+        add(category, (*count as f64).min(3.0), format!("{} earlier {} flight(s) on this route", count, category));
+    }
+    for (category, count) in &context.passenger_history {
+        add(category, (*count as f64).min(2.0), format!("These passengers flew {} {} flight(s) before", count, category));
+    }
+
+    if flight.departure_airport.eq_ignore_ascii_case(&flight.arrival_airport) {
+        add("training", 2.0, "Departs and lands at the same airport".to_string());
+    }
+    match departure_weekday(flight) {
+        Some(Weekday::Sat | Weekday::Sun) => add("personal", 1.0, "Departs on a weekend".to_string()),
+        Some(_) => add("business", 1.0, "Departs on a weekday".to_string()),
+        None => {}
+    }
+
+    let total: f64 = scores.values().sum();
+    // Ties go to the category listed first
+    let (category, top) = categories
+        .iter()
+        .map(|c| (c.clone(), scores.get(c.as_str()).copied().unwrap_or(0.0)))
+        .fold((categories[0].clone(), 0.0), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+    // Share of the evidence, discounted while there is little of it
+    let confidence = if total > 0.0 { top / total * (total / 5.0).min(1.0) } else { 0.0 };
+
+    reasons.sort_by(|a, b| b.1.total_cmp(&a.1));
+    PurposeSuggestion {
+        flight_id: flight.id.clone(),
+        reasons: reasons.into_iter().filter(|(c, _, _)| *c == category).map(|(_, _, reason)| reason).collect(),
+        category,
+        confidence: (confidence * 100.0).round() / 100.0,
+        source: "heuristic".to_string(),
+        cached: false,
+        accepted: None,
+        categories: categories.to_vec(),
+    }
+}
+
+fn contains_word(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Prompt for an AI provider; the answer is parsed with `parse_ai_response`
+pub fn ai_prompt(context: &FlightContext, categories: &[String]) -> String {
+    let flight = &context.flight;
+    let history = |counts: &HashMap<String, i64>| {
+        let mut entries: Vec<String> = counts.iter().map(|(c, n)| format!("{} {}", n, c)).collect();
+        entries.sort();
+        if entries.is_empty() { "none".to_string() } else { entries.join(", ") }
+    };
+    format!(
+        "Classify the purpose of this flight as exactly one of: {categories}.\n\
+         Flight: {number} {dep} -> {arr}, departing {departure}{weekday}\n\
+         Aircraft: {aircraft}\n\
+         Passengers: {passengers}\n\
+         Notes: {notes}\n\
+         Purposes already accepted on this route: {route}\n\
+         Purposes already accepted for these passengers: {people}\n\n\
+         Reply with only a JSON object: {{\"category\": \"...\", \"confidence\": 0.0-1.0, \"reason\": \"one sentence\"}}",
+        categories = categories.join(", "),
+        number = flight.flight_number.as_deref().unwrap_or("(no flight number)"),
+        dep = flight.departure_airport,
+        arr = flight.arrival_airport,
+        departure = flight.departure_datetime,
+        weekday = departure_weekday(flight).map(|d| format!(" ({})", d)).unwrap_or_default(),
+        aircraft = flight.aircraft_registration.as_deref().unwrap_or("unknown"),
+        passengers = if context.passengers.is_empty() { "none recorded".to_string() } else { context.passengers.join(", ") },
+        notes = flight.notes.as_deref().unwrap_or("none"),
+        route = history(&context.route_history),
+        people = history(&context.passenger_history),
+    )
+}
+
+/// (category, confidence, reason) from the provider's answer
+pub fn parse_ai_response(response: &str, categories: &[String]) -> Result<(String, f64, String)> {
+    #[derive(Deserialize)]
+    struct Answer {
+        category: String,
+        confidence: Option<f64>,
+        reason: Option<String>,
+    }
+
+    // Models like to wrap the object in prose or a code fence
+    let start = response.find('{').ok_or_else(|| anyhow!("No JSON object in the AI response"))?;
+    let end = response.rfind('}').filter(|end| *end > start).ok_or_else(|| anyhow!("No JSON object in the AI response"))?;
+    let answer: Answer = serde_json::from_str(&response[start..=end])?;
+
+    let category = answer.category.trim().to_lowercase();
+    if !categories.contains(&category) {
+        bail!("AI suggested an unknown category: {}", answer.category);
+    }
+    let confidence = answer.confidence.filter(|c| c.is_finite()).unwrap_or(0.5).clamp(0.0, 1.0);
+    Ok((category, confidence, answer.reason.unwrap_or_default().trim().to_string()))
+}
+
+pub fn accepted_purpose(conn: &Connection, flight_id: &str) -> Result<Option<String>> {
+    let tag: Option<String> = conn
+        .query_row(
+            "SELECT tag FROM flight_tags WHERE flight_id = ?1 AND tag LIKE 'purpose:%' ORDER BY created_at DESC LIMIT 1",
+            [flight_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(tag.map(|t| t[TAG_PREFIX.len()..].to_string()))
+}
+
+/// Record the purpose as the flight's only "purpose:" tag
+///
+/// Both statements run in one transaction, so a failed insert keeps the previous purpose.
+pub fn accept_purpose(conn: &Connection, flight_id: &str, category: &str) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM flight_tags WHERE flight_id = ?1 AND tag LIKE 'purpose:%'", [flight_id])?;
+    tx.execute(
+        "INSERT INTO flight_tags (flight_id, tag) VALUES (?1, ?2)",
+        params![flight_id, format!("{}{}", TAG_PREFIX, category)],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn defaults() -> Vec<String> {
+        DEFAULT_CATEGORIES.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_heuristic_uses_notes_and_history() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u1', 'Pilot');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, notes)
+                 VALUES ('f1', 'u1', 'KPAO', 'KSQL', '2025-03-10T09:00:00', 'Client meeting in San Carlos'),
+                        ('f2', 'u1', 'KSQL', 'KPAO', '2025-03-03T09:00:00', NULL),
+                        ('f3', 'u1', 'KPAO', 'KPAO', '2025-03-15T09:00:00', 'Pattern work with instructor');",
+            )
+            .unwrap();
+        accept_purpose(&db.conn, "f2", "business").unwrap();
+
+        let context = load_context(&db, "u1", "f1").unwrap().unwrap();
+        assert_eq!(context.route_history.get("business"), Some(&1));
+        let suggestion = heuristic(&context, &defaults());
+        assert_eq!(suggestion.category, "business");
+        assert!(suggestion.confidence > 0.7, "confidence {}", suggestion.confidence);
+        assert!(suggestion.reasons[0].starts_with("Notes mention"));
+
+        // Saturday local flight with training keywords
+        let suggestion = heuristic(&load_context(&db, "u1", "f3").unwrap().unwrap(), &defaults());
+        assert_eq!(suggestion.category, "training");
+
+        accept_purpose(&db.conn, "f1", "personal").unwrap();
+        accept_purpose(&db.conn, "f1", "business").unwrap();
+        assert_eq!(accepted_purpose(&db.conn, "f1").unwrap().as_deref(), Some("business"));
+        let tags: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM flight_tags WHERE flight_id = 'f1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tags, 1);
+
+        // Another user's flight is not found, and their purposes do not count as history
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('u2', 'Other');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime)
+                 VALUES ('g1', 'u2', 'KSQL', 'KPAO', '2025-03-04T09:00:00');",
+            )
+            .unwrap();
+        accept_purpose(&db.conn, "g1", "personal").unwrap();
+        assert!(load_context(&db, "u2", "f1").unwrap().is_none());
+        let context = load_context(&db, "u1", "f1").unwrap().unwrap();
+        assert_eq!(context.route_history, HashMap::from([("business".to_string(), 1)]));
+
+        // Deleted flights are not found and drop out of the history
+        db.conn.execute("UPDATE flights SET deleted_at = datetime('now') WHERE id = 'f2'", []).unwrap();
+        assert!(load_context(&db, "u1", "f2").unwrap().is_none());
+        assert!(load_context(&db, "u1", "f1").unwrap().unwrap().route_history.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ai_response() {
+        let response = "```json\n{\"category\": \"Training\", \"confidence\": 1.4, \"reason\": \"Local pattern work\"}\n```";
+        let (category, confidence, reason) = parse_ai_response(response, &defaults()).unwrap();
+        assert_eq!(category, "training");
+        assert_eq!(confidence, 1.0);
+        assert_eq!(reason, "Local pattern work");
+
+        assert!(parse_ai_response("{\"category\": \"charity\"}", &defaults()).is_err());
+        assert!(parse_ai_response("business", &defaults()).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Display units for command responses
+// Stored in the "unit_system" setting ("metric", "imperial" or "aviation"). The database
+// keeps canonical SI values (km, kg); commands attach the converted values with their unit
+// labels so the frontend does not convert on its own.
+
+use serde::{Deserialize, Serialize};
+
+pub const UNIT_SYSTEM_SETTING: &str = "unit_system";
+
+const MILES_PER_KM: f64 = 0.621371;
+const NM_PER_KM: f64 = 1.0 / 1.852;
+const LB_PER_KG: f64 = 2.204623;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    /// km, kg
+    #[default]
+    Metric,
+    /// miles, lb
+    Imperial,
+    /// nautical miles, lb
+    Aviation,
+}
+
+/// A value converted for display, with its unit label
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: String,
+}
+
+impl Quantity {
+    fn new(value: f64, unit: &str) -> Self {
+        Quantity { value, unit: unit.to_string() }
+    }
+}
+
+/// Converted values attached to a response; quantities the response has no value for are omitted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measurements {
+    pub unit_system: UnitSystem,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance: Option<Quantity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carbon: Option<Quantity>,
+}
+
+impl Measurements {
+    pub fn distance_km(mut self, km: Option<f64>) -> Self {
+        self.distance = km.map(|km| self.unit_system.distance(km));
+        self
+    }
+
+    pub fn carbon_kg(mut self, kg: Option<f64>) -> Self {
+        self.carbon = kg.map(|kg| self.unit_system.mass(kg));
+        self
+    }
+
+}
+
+impl UnitSystem {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag.trim().to_lowercase().as_str() {
+            "metric" | "si" => Some(UnitSystem::Metric),
+            "imperial" | "us" => Some(UnitSystem::Imperial),
+            "aviation" => Some(UnitSystem::Aviation),
+            _ => None,
+        }
+    }
+
+    /// Unit system from the stored setting; missing or unknown values are metric
+    pub fn from_setting(value: Option<&str>) -> Self {
+        value.and_then(Self::from_tag).unwrap_or_default()
+    }
+
+    /// Unit system configured in the app settings
+    pub fn configured(db: &crate::database::Database) -> Self {
+        Self::from_setting(db.get_setting(UNIT_SYSTEM_SETTING).ok().flatten().as_deref())
+    }
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "metric",
+            UnitSystem::Imperial => "imperial",
+            UnitSystem::Aviation => "aviation",
+        }
+    }
+
+    /// Empty measurements in this system, filled in with the builder methods
+    pub fn measure(&self) -> Measurements {
+        Measurements {
+            unit_system: *self,
+            distance: None,
+            carbon: None,
+        }
+    }
+
+    pub fn distance(&self, km: f64) -> Quantity {
+        match self {
+            UnitSystem::Metric => Quantity::new(km, "km"),
+            UnitSystem::Imperial => Quantity::new(km * MILES_PER_KM, "mi"),
+            UnitSystem::Aviation => Quantity::new(km * NM_PER_KM, "nm"),
+        }
+    }
+
+    pub fn mass(&self, kg: f64) -> Quantity {
+        match self {
+            UnitSystem::Metric => Quantity::new(kg, "kg"),
+            UnitSystem::Imperial | UnitSystem::Aviation => Quantity::new(kg * LB_PER_KG, "lb"),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_setting() {
+        assert_eq!(UnitSystem::from_setting(Some("Imperial")), UnitSystem::Imperial);
+        assert_eq!(UnitSystem::from_setting(Some("aviation")), UnitSystem::Aviation);
+        assert_eq!(UnitSystem::from_setting(Some("furlongs")), UnitSystem::Metric);
+        assert_eq!(UnitSystem::from_setting(None), UnitSystem::Metric);
+        assert_eq!(UnitSystem::from_tag("US").map(|units| units.tag()), Some("imperial"));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let aviation = UnitSystem::Aviation.measure().distance_km(Some(1852.0)).carbon_kg(None);
+        assert_eq!(aviation.distance.as_ref().map(|q| q.unit.as_str()), Some("nm"));
+        assert!((aviation.distance.unwrap().value - 1000.0).abs() < 1e-9);
+        assert!(aviation.carbon.is_none());
+
+        let imperial = UnitSystem::Imperial;
+        assert!((imperial.distance(100.0).value - 62.1371).abs() < 1e-9);
+        assert!((imperial.mass(100.0).value - 220.4623).abs() < 1e-9);
+        assert_eq!(UnitSystem::Metric.mass(12.5), Quantity { value: 12.5, unit: "kg".to_string() });
+    }
+
+    #[test]
+    fn test_measurements_omit_missing_values() {
+        let json = serde_json::to_value(UnitSystem::Metric.measure().distance_km(Some(10.0))).unwrap();
+        assert_eq!(json, serde_json::json!({ "unit_system": "metric", "distance": { "value": 10.0, "unit": "km" } }));
+    }
+}
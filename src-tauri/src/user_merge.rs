@@ -0,0 +1,343 @@
+// Dataset merges
+// Folds one user (dataset) into another: every row the source owns is re-pointed to the
+// target, rows hanging off flights (logbook, tags, passenger links, FFP credits) follow
+// their flight, passenger identities are bootstrapped from the combined flight notes and
+// the source user is deleted, all in one transaction.
+//
+// A few tables have per-user unique keys. On a clash the target's row wins (fuel types,
+// learned corrections) or, for custom schemas, the source's is kept under a new name so
+// its records survive. Caches keyed by user are cleared for both users and rebuilt on the
+// next read.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::commands::identity_fusion::{bootstrap_user_identities, BootstrapOptions, BootstrapResult};
+use crate::orphans::table_exists;
+
+#[derive(Debug, Clone, Copy)]
+enum Collision {
+    /// No per-user unique key
+    None,
+    /// The target keeps its row; the source's duplicate is dropped
+    KeepTarget(&'static [&'static str]),
+    /// The source's row is kept with the source user's name appended to this column
+    Rename(&'static str),
+    /// A per-user cache; both users' rows are deleted rather than moved
+    Clear,
+}
+
+struct OwnedTable {
+    table: &'static str,
+    collision: Collision,
+}
+
+const fn owned(table: &'static str, collision: Collision) -> OwnedTable {
+    OwnedTable { table, collision }
+}
+
+/// Every table with a user_id column. aircraft_performance profiles are shared by all users
+/// and have none.
+const OWNED_TABLES: [OwnedTable; 24] = [
+    owned("flights", Collision::None),
+    owned("journeys", Collision::None),
+    owned("frequent_flyer_programs", Collision::None),
+    owned("fuel_entries", Collision::None),
+    owned("fuel_types", Collision::KeepTarget(&["code"])),
+    owned("fuel_prices", Collision::None),
+    owned("custom_documents", Collision::None),
+    owned("media_files", Collision::None),
+    owned("document_ingestion_queue", Collision::None),
+    owned("custom_schemas", Collision::Rename("name")),
+    owned("custom_records", Collision::None),
+    owned("flight_templates", Collision::None),
+    owned("pilot_credentials", Collision::None),
+    owned("investigations", Collision::None),
+    owned("research_reports", Collision::None),
+    owned("ocr_queue", Collision::None),
+    owned("ocr_corrections", Collision::None),
+    owned("ocr_learning_patterns", Collision::None),
+    owned("user_corrections", Collision::KeepTarget(&["field_name", "original_value", "corrected_value"])),
+    owned("user_patterns", Collision::KeepTarget(&["pattern_type", "pattern_key"])),
+    owned("agent_memory", Collision::None),
+    owned("maintenance_schedules", Collision::None),
+    owned("expiry_notifications", Collision::KeepTarget(&["item_key"])),
+    owned("statistics_cache", Collision::Clear),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeCount {
+    pub table: String,
+    pub count: i64,
+}
+
+/// What a merge will do (or did, when returned by `merge_users`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergePreview {
+    pub source_user_id: String,
+    pub source_name: String,
+    pub target_user_id: String,
+    pub target_name: String,
+    /// Rows re-pointed to the target, per table that has any
+    pub moved: Vec<MergeCount>,
+    /// Logbook entries, which move with their flights
+    pub logbook_entries: i64,
+    /// Source rows dropped because the target already has one with the same key
+    pub dropped_duplicates: Vec<MergeCount>,
+    /// Source rows renamed because the target already uses the name
+    pub renamed: Vec<MergeCount>,
+    /// Passengers linked to flights in both datasets, which end up as one identity
+    pub shared_passengers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserMergeResult {
+    pub merge: MergePreview,
+    /// Identity fusion over the combined flights
+    pub identities: BootstrapResult,
+}
+
+fn user_name(conn: &Connection, user_id: &str) -> Result<String> {
+    conn.query_row("SELECT name FROM users WHERE id = ?1", [user_id], |row| row.get(0))
+        .optional()?
+        .with_context(|| format!("User '{}' not found", user_id))
+}
+
+/// Matches rows of `table` (unaliased, source side) that clash with a row of the target
+fn collision_filter(table: &str, columns: &[&str]) -> String {
+    let keys: Vec<String> = columns.iter().map(|c| format!("t.{c} IS {table}.{c}")).collect();
+    format!("EXISTS (SELECT 1 FROM {table} t WHERE t.user_id = ?2 AND {})", keys.join(" AND "))
+}
+
+fn count(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<i64> {
+    Ok(conn.query_row(sql, params, |row| row.get(0))?)
+}
+
+fn applicable_tables(conn: &Connection) -> Result<Vec<&'static OwnedTable>> {
+    let mut tables = Vec::new();
+    for owned in &OWNED_TABLES {
+        if table_exists(conn, owned.table)? {
+            tables.push(owned);
+        }
+    }
+    Ok(tables)
+}
+
+/// Count what merging `source_user_id` into `target_user_id` would move, drop and rename
+pub fn preview_merge(conn: &Connection, source_user_id: &str, target_user_id: &str) -> Result<MergePreview> {
+    if source_user_id == target_user_id {
+        bail!("Cannot merge a dataset into itself");
+    }
+    let source_name = user_name(conn, source_user_id)?;
+    let target_name = user_name(conn, target_user_id)?;
+
+    let mut moved = Vec::new();
+    let mut dropped_duplicates = Vec::new();
+    let mut renamed = Vec::new();
+    for owned in applicable_tables(conn)? {
+        let table = owned.table;
+        let total = count(conn, &format!("SELECT COUNT(*) FROM {table} WHERE user_id = ?1"), [source_user_id])
+            .with_context(|| format!("Failed to count {}", table))?;
+        let colliding = match owned.collision {
+            Collision::None | Collision::Clear => 0,
+            Collision::KeepTarget(columns) => collisions(conn, table, columns, source_user_id, target_user_id)?,
+            Collision::Rename(column) => collisions(conn, table, &[column], source_user_id, target_user_id)?,
+        };
+        if colliding > 0 {
+            let entry = MergeCount { table: table.to_string(), count: colliding };
+            match owned.collision {
+                Collision::KeepTarget(_) => dropped_duplicates.push(entry),
+                _ => renamed.push(entry),
+            }
+        }
+        let moving = match owned.collision {
+            Collision::KeepTarget(_) => total - colliding,
+            Collision::Clear => 0,
+            _ => total,
+        };
+        if moving > 0 {
+            moved.push(MergeCount { table: table.to_string(), count: moving });
+        }
+    }
+
+    let logbook_entries = count(
+        conn,
+        "SELECT COUNT(*) FROM pilot_logbook WHERE flight_id IN (SELECT id FROM flights WHERE user_id = ?1)",
+        [source_user_id],
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT p.canonical_name FROM passengers p
+         WHERE EXISTS (SELECT 1 FROM flight_passengers fp JOIN flights f ON f.id = fp.flight_id
+                       WHERE fp.passenger_id = p.id AND f.user_id = ?1)
+           AND EXISTS (SELECT 1 FROM flight_passengers fp JOIN flights f ON f.id = fp.flight_id
+                       WHERE fp.passenger_id = p.id AND f.user_id = ?2)
+         ORDER BY p.canonical_name",
+    )?;
+    let shared_passengers = stmt
+        .query_map(params![source_user_id, target_user_id], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<String>, _>>()?;
+
+    Ok(MergePreview {
+        source_user_id: source_user_id.to_string(),
+        source_name,
+        target_user_id: target_user_id.to_string(),
+        target_name,
+        moved,
+        logbook_entries,
+        dropped_duplicates,
+        renamed,
+        shared_passengers,
+    })
+}
+
+fn collisions(conn: &Connection, table: &str, columns: &[&str], source: &str, target: &str) -> Result<i64> {
+    count(
+        conn,
+        &format!("SELECT COUNT(*) FROM {table} WHERE user_id = ?1 AND {}", collision_filter(table, columns)),
+        params![source, target],
+    )
+    .with_context(|| format!("Failed to check {} for clashes", table))
+}
+
+/// Move everything `source_user_id` owns to `target_user_id`, fuse passenger identities,
+/// delete the source user and record the merge in the audit log
+pub fn merge_users(conn: &Connection, source_user_id: &str, target_user_id: &str) -> Result<UserMergeResult> {
+    let tx = conn.unchecked_transaction()?;
+    let merge = preview_merge(&tx, source_user_id, target_user_id)?;
+
+    for owned in applicable_tables(&tx)? {
+        let table = owned.table;
+        match owned.collision {
+            Collision::None => {}
+            Collision::Clear => {
+                tx.execute(&format!("DELETE FROM {table} WHERE user_id IN (?1, ?2)"), params![source_user_id, target_user_id])
+                    .with_context(|| format!("Failed to clear {}", table))?;
+                continue;
+            }
+            Collision::KeepTarget(columns) => {
+                tx.execute(
+                    &format!("DELETE FROM {table} WHERE user_id = ?1 AND {}", collision_filter(table, columns)),
+                    params![source_user_id, target_user_id],
+                )?;
+            }
+            Collision::Rename(column) => {
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET {column} = {column} || ' (' || ?3 || ')' WHERE user_id = ?1 AND {}",
+                        collision_filter(table, &[column])
+                    ),
+                    params![source_user_id, target_user_id, merge.source_name],
+                )?;
+            }
+        }
+        tx.execute(&format!("UPDATE {table} SET user_id = ?2 WHERE user_id = ?1"), params![source_user_id, target_user_id])
+            .with_context(|| format!("Failed to move {}", table))?;
+    }
+
+    let identities = bootstrap_user_identities(&tx, target_user_id, &BootstrapOptions::default())
+        .context("Failed to fuse passenger identities")?;
+    tx.execute("DELETE FROM users WHERE id = ?1", [source_user_id]).context("Failed to delete the merged user")?;
+
+    tx.execute(
+        "INSERT INTO audit_log (id, action, subject_id, details) VALUES (?1, 'merge_users', ?2, ?3)",
+        params![Uuid::new_v4().to_string(), target_user_id, serde_json::to_string(&merge)?],
+    )
+    .context("Failed to record the merge in the audit log")?;
+    tx.commit()?;
+
+    Ok(UserMergeResult { merge, identities })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_every_user_table_is_merged() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let mut stmt = db
+            .conn
+            .prepare(
+                "SELECT m.name FROM sqlite_master m
+                 WHERE m.type = 'table'
+                   AND EXISTS (SELECT 1 FROM pragma_table_info(m.name) c WHERE c.name = 'user_id')
+                 ORDER BY m.name",
+            )
+            .unwrap();
+        let tables: Vec<String> =
+            stmt.query_map([], |row| row.get(0)).unwrap().collect::<std::result::Result<_, _>>().unwrap();
+        assert!(tables.iter().any(|t| t == "flights"));
+        let missing: Vec<&String> = tables.iter().filter(|t| !OWNED_TABLES.iter().any(|o| o.table == t.as_str())).collect();
+        assert!(missing.is_empty(), "tables with a user_id column left out of the merge: {:?}", missing);
+    }
+
+    #[test]
+    fn test_merge_users() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        db.conn
+            .execute_batch(
+                "INSERT INTO users (id, name) VALUES ('demo', 'Demo'), ('real', 'Real');
+                 INSERT INTO flights (id, user_id, departure_airport, arrival_airport, departure_datetime, notes)
+                 VALUES ('f1', 'demo', 'KJFK', 'KBOS', '2025-01-05T09:00:00', 'Passengers: Ann Lee'),
+                        ('f2', 'real', 'KBOS', 'KJFK', '2025-01-06T09:00:00', 'Passengers: Ann Lee');
+                 INSERT INTO pilot_logbook (id, flight_id) VALUES ('l1', 'f1');
+                 INSERT INTO fuel_types (id, user_id, code, name) VALUES ('t1', 'demo', 'jet_a', 'Jet A'),
+                                                                       ('t2', 'real', 'jet_a', 'Jet A');
+                 INSERT INTO custom_schemas (id, user_id, name, display_name)
+                 VALUES ('s1', 'demo', 'Trips', 'Trips'), ('s2', 'real', 'Trips', 'Trips');
+                 INSERT INTO maintenance_schedules (id, user_id, aircraft_registration, item, interval_days)
+                 VALUES ('m1', 'demo', 'N123AB', 'Annual', 365);
+                 INSERT INTO expiry_notifications (user_id, item_key, label, expires_date, stage, last_notified_at)
+                 VALUES ('demo', 'medical', 'Medical', '2025-06-01', 'due', '2025-05-01'),
+                        ('real', 'medical', 'Medical', '2025-07-01', 'due', '2025-06-01');
+                 INSERT INTO statistics_cache (user_id, total_flights) VALUES ('demo', 1), ('real', 1);",
+            )
+            .unwrap();
+        bootstrap_user_identities(&db.conn, "demo", &BootstrapOptions::default()).unwrap();
+        bootstrap_user_identities(&db.conn, "real", &BootstrapOptions::default()).unwrap();
+
+        let preview = preview_merge(&db.conn, "demo", "real").unwrap();
+        let moved: Vec<_> = preview.moved.iter().map(|m| (m.table.as_str(), m.count)).collect();
+        assert_eq!(moved, [("flights", 1), ("custom_schemas", 1), ("maintenance_schedules", 1)]);
+        assert_eq!(preview.logbook_entries, 1);
+        let dropped: Vec<_> = preview.dropped_duplicates.iter().map(|m| m.table.as_str()).collect();
+        assert_eq!(dropped, ["fuel_types", "expiry_notifications"]);
+        assert_eq!(preview.renamed[0].table, "custom_schemas");
+        assert_eq!(preview.shared_passengers, ["ANN LEE"]);
+        assert!(preview_merge(&db.conn, "real", "real").is_err());
+
+        merge_users(&db.conn, "demo", "real").unwrap();
+        let flights: i64 =
+            db.conn.query_row("SELECT COUNT(*) FROM flights WHERE user_id = 'real'", [], |row| row.get(0)).unwrap();
+        assert_eq!(flights, 2);
+        let maintenance: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM maintenance_schedules WHERE user_id = 'real'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(maintenance, 1);
+        let cached: i64 = db.conn.query_row("SELECT COUNT(*) FROM statistics_cache", [], |row| row.get(0)).unwrap();
+        assert_eq!(cached, 0);
+        let schemas: Vec<String> = db
+            .conn
+            .prepare("SELECT name FROM custom_schemas ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(schemas, ["Trips", "Trips (Demo)"]);
+        assert!(user_name(&db.conn, "demo").is_err());
+        let logged: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM audit_log WHERE action = 'merge_users' AND subject_id = 'real'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(logged, 1);
+    }
+}
@@ -0,0 +1,120 @@
+// Input validation at the command boundary
+// Flights are checked before they are stored so a malformed airport code or datetime is
+// rejected on entry instead of breaking analytics later. Every bad field is reported,
+// not just the first.
+
+use crate::error::{CommandError, FieldError};
+use crate::models::FlightInput;
+
+/// Collects field errors for one input
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// 2-4 letters or digits (IATA, ICAO or a private field identifier)
+    pub fn airport_code(&mut self, field: &str, value: &str) {
+        let code = value.trim();
+        if !(2..=4).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            self.add(field, format!("must be a 2-4 character airport code, got \"{}\"", value));
+        }
+    }
+
+    /// An ISO date or datetime (see timezone::parse_local_datetime)
+    pub fn datetime(&mut self, field: &str, value: &str) {
+        if crate::timezone::parse_local_datetime(value).is_none() {
+            self.add(field, format!("must be an ISO date or datetime, got \"{}\"", value));
+        }
+    }
+
+    /// Like `datetime`, but an empty value (cleared field) is allowed
+    pub fn optional_datetime(&mut self, field: &str, value: Option<&str>) {
+        if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+            self.datetime(field, value);
+        }
+    }
+
+    pub fn non_negative(&mut self, field: &str, value: Option<f64>) {
+        match value {
+            Some(v) if !v.is_finite() => self.add(field, "must be a number"),
+            Some(v) if v < 0.0 => self.add(field, format!("must not be negative, got {}", v)),
+            _ => {}
+        }
+    }
+
+    pub fn non_negative_int(&mut self, field: &str, value: Option<i32>) {
+        if let Some(v) = value.filter(|v| *v < 0) {
+            self.add(field, format!("must not be negative, got {}", v));
+        }
+    }
+
+    pub fn into_result(self) -> Result<(), CommandError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(CommandError::InvalidFields(self.0))
+        }
+    }
+}
+
+/// Check a new flight
+pub fn validate_flight_input(flight: &FlightInput) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    errors.airport_code("departure_airport", &flight.departure_airport);
+    errors.airport_code("arrival_airport", &flight.arrival_airport);
+    errors.datetime("departure_datetime", &flight.departure_datetime);
+    errors.optional_datetime("arrival_datetime", flight.arrival_datetime.as_deref());
+    errors.non_negative_int("total_duration", flight.total_duration);
+    errors.non_negative_int("flight_duration", flight.flight_duration);
+    errors.non_negative_int("passenger_count", flight.passenger_count);
+    errors.non_negative("distance_nm", flight.distance_nm);
+    errors.non_negative("distance_km", flight.distance_km);
+    errors.non_negative("carbon_emissions_kg", flight.carbon_emissions_kg);
+    errors.non_negative("base_fare", flight.base_fare);
+    errors.non_negative("taxes", flight.taxes);
+    errors.non_negative("total_cost", flight.total_cost);
+    errors.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flight(departure: &str, arrival: &str, datetime: &str) -> FlightInput {
+        serde_json::from_value(serde_json::json!({
+            "flight_number": null, "departure_airport": departure, "arrival_airport": arrival,
+            "departure_datetime": datetime, "arrival_datetime": null, "aircraft_type_id": null,
+            "aircraft_registration": null, "total_duration": null, "flight_duration": null,
+            "distance_nm": null, "distance_km": null, "carbon_emissions_kg": null,
+            "booking_reference": null, "ticket_number": null, "seat_number": null, "fare_class": null,
+            "base_fare": null, "taxes": null, "total_cost": null, "currency": null, "notes": null,
+            "attachment_path": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_flight_input() {
+        assert!(validate_flight_input(&flight("KJFK", "LHR", "2024-06-01T10:30:00")).is_ok());
+        assert!(validate_flight_input(&flight("1B9", "EG", "2024-06-01")).is_ok());
+
+        let mut bad = flight("HEATHROW-1", "K JFK", "01/06/2024 10:30");
+        bad.total_cost = Some(-5.0);
+        bad.distance_km = Some(f64::NAN);
+        bad.arrival_datetime = Some("".to_string());
+        let Err(CommandError::InvalidFields(fields)) = validate_flight_input(&bad) else {
+            panic!("expected field errors");
+        };
+        let names: Vec<&str> = fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["departure_airport", "arrival_airport", "departure_datetime", "distance_km", "total_cost"]
+        );
+    }
+}
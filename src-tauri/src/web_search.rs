@@ -0,0 +1,478 @@
+// Web search for AI research
+// Searches go through a SearchProvider chosen in Settings (search_provider): DuckDuckGo
+// HTML scraping (default, no key), the Brave Search or SerpAPI JSON APIs (search_api_key),
+// or none for offline use.
+//
+// Research runs one search per topic (and per passenger), so the same story often comes
+// back several times. Results are deduplicated and capped to a token budget before they
+// are sent to an AI provider.
+//
+// Searches run concurrently, at most research_concurrency at a time, with starts spaced
+// research_request_delay_ms apart so a burst of topics does not trip provider rate limits.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::database::Database;
+use crate::error::CommandError;
+
+/// Rough token budget for all search results in one prompt
+pub const DEFAULT_TOKEN_BUDGET: usize = 3000;
+
+/// Word overlap (Jaccard) at which two results count as the same
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.8;
+
+/// Shorter results are only deduplicated on an exact (normalized) match
+const MIN_WORDS_FOR_SIMILARITY: usize = 4;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResultStats {
+    /// Results returned by all searches
+    pub total: usize,
+    /// Exact or near-duplicate results removed
+    pub deduplicated: usize,
+    /// Results dropped to stay within the token budget
+    pub dropped_for_budget: usize,
+    /// Estimated tokens of the results kept
+    pub estimated_tokens: usize,
+}
+
+struct Candidate {
+    text: String,
+    words: HashSet<String>,
+    normalized: String,
+    /// Position within its search's results; lower is more relevant
+    rank: usize,
+    /// Which search it came from; later searches are dropped first on ties
+    query_index: usize,
+}
+
+/// Deduplicate the results of several searches and cap them to `token_budget`
+///
+/// Each inner Vec is one search's results in relevance order. When over budget, the
+/// least relevant results (highest rank, then latest search) are dropped first. The
+/// results kept stay in their original order.
+pub fn prepare_results(searches: Vec<Vec<String>>, token_budget: usize) -> (Vec<String>, SearchResultStats) {
+    let mut stats = SearchResultStats::default();
+    let mut kept: Vec<Candidate> = Vec::new();
+
+    for (query_index, results) in searches.into_iter().enumerate() {
+        for (rank, text) in results.into_iter().enumerate() {
+            stats.total += 1;
+            let text = collapse_whitespace(&text);
+            let normalized = normalize(&text);
+            if normalized.is_empty() {
+                stats.deduplicated += 1;
+                continue;
+            }
+            let words: HashSet<String> = normalized.split(' ').map(str::to_string).collect();
+
+            if let Some(existing) = kept.iter_mut().find(|c| is_duplicate(c, &normalized, &words)) {
+                // Keep the first copy, but at the best rank either copy had
+                existing.rank = existing.rank.min(rank);
+                stats.deduplicated += 1;
+                continue;
+            }
+            kept.push(Candidate {
+                text,
+                words,
+                normalized,
+                rank,
+                query_index,
+            });
+        }
+    }
+
+    let mut tokens: usize = kept.iter().map(|c| estimate_tokens(&c.text)).sum();
+    while tokens > token_budget && !kept.is_empty() {
+        let least_relevant = kept
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, c)| (c.rank, c.query_index))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        tokens -= estimate_tokens(&kept.remove(least_relevant).text);
+        stats.dropped_for_budget += 1;
+    }
+    stats.estimated_tokens = tokens;
+
+    (kept.into_iter().map(|c| c.text).collect(), stats)
+}
+
+const SEARCH_TIMEOUT_SECS: u64 = 15;
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36";
+
+/// A web search backend
+///
+/// Results are plain text in relevance order: snippets first, then "Title: ..." entries.
+#[async_trait]
+pub trait SearchProvider: Send + Sync {
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<String>>;
+}
+
+/// The provider selected in settings; DuckDuckGo when none is set
+pub fn provider_from_settings(db: &Database) -> Result<Box<dyn SearchProvider>> {
+    let provider = db.get_setting("search_provider")?.unwrap_or_default();
+    let api_key = || -> Result<String> {
+        db.get_setting("search_api_key")?
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| CommandError::Unauthorized("search_api_key is not configured. Add it in Settings.".to_string()).into())
+    };
+
+    Ok(match provider.trim() {
+        "" | "duckduckgo" => Box::new(DuckDuckGo),
+        "brave" => Box::new(BraveSearch { api_key: api_key()? }),
+        "serpapi" => Box::new(SerpApi { api_key: api_key()? }),
+        "none" => Box::new(NoSearch),
+        other => bail!("Unknown search provider: {}", other),
+    })
+}
+
+/// DuckDuckGo's HTML results page (no API key)
+pub struct DuckDuckGo;
+
+#[async_trait]
+impl SearchProvider for DuckDuckGo {
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<String>> {
+        let html = reqwest::Client::new()
+            .get("https://html.duckduckgo.com/html/")
+            .query(&[("q", query)])
+            .header("User-Agent", BROWSER_USER_AGENT)
+            .timeout(std::time::Duration::from_secs(SEARCH_TIMEOUT_SECS))
+            .send()
+            .await
+            .context("DuckDuckGo search failed")?
+            .text()
+            .await?;
+        Ok(parse_duckduckgo_html(&html, max_results))
+    }
+}
+
+/// Brave Search web API (https://api.search.brave.com)
+pub struct BraveSearch {
+    api_key: String,
+}
+
+#[async_trait]
+impl SearchProvider for BraveSearch {
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<String>> {
+        let count = max_results.clamp(1, 20).to_string();
+        let response = reqwest::Client::new()
+            .get("https://api.search.brave.com/res/v1/web/search")
+            .query(&[("q", query), ("count", count.as_str())])
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .timeout(std::time::Duration::from_secs(SEARCH_TIMEOUT_SECS))
+            .send()
+            .await
+            .context("Brave search failed")?;
+        if !response.status().is_success() {
+            bail!("Brave search returned {}", response.status());
+        }
+        let json: serde_json::Value = response.json().await?;
+        Ok(parse_json_results(&json, "/web/results", "description", max_results))
+    }
+}
+
+/// SerpAPI Google results (https://serpapi.com)
+pub struct SerpApi {
+    api_key: String,
+}
+
+#[async_trait]
+impl SearchProvider for SerpApi {
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<String>> {
+        let response = reqwest::Client::new()
+            .get("https://serpapi.com/search.json")
+            .query(&[("engine", "google"), ("q", query), ("api_key", self.api_key.as_str())])
+            .timeout(std::time::Duration::from_secs(SEARCH_TIMEOUT_SECS))
+            .send()
+            .await
+            .context("SerpAPI search failed")?;
+        if !response.status().is_success() {
+            bail!("SerpAPI search returned {}", response.status());
+        }
+        let json: serde_json::Value = response.json().await?;
+        Ok(parse_json_results(&json, "/organic_results", "snippet", max_results))
+    }
+}
+
+/// How many searches may run at once and how far apart they start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchPacing {
+    pub concurrency: usize,
+    pub delay_ms: u64,
+}
+
+impl Default for SearchPacing {
+    fn default() -> Self {
+        Self { concurrency: 3, delay_ms: 250 }
+    }
+}
+
+impl SearchPacing {
+    /// From research_concurrency and research_request_delay_ms; defaults for missing or invalid values
+    pub fn from_settings(db: &Database) -> Self {
+        let default = Self::default();
+        let setting = |key: &str| db.get_setting(key).ok().flatten();
+        Self {
+            concurrency: setting("research_concurrency")
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default.concurrency),
+            delay_ms: setting("research_request_delay_ms")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default.delay_ms),
+        }
+    }
+}
+
+/// Run every query, returning the successful result sets in query order
+///
+/// Failed searches are logged and left out, so one bad query never costs the rest.
+pub async fn search_all(
+    provider: &dyn SearchProvider,
+    queries: &[String],
+    max_results: usize,
+    pacing: SearchPacing,
+) -> Vec<Vec<String>> {
+    use futures::stream::{self, StreamExt};
+
+    let delay = Duration::from_millis(pacing.delay_ms);
+    let start = tokio::time::Instant::now();
+    // Owned queries: futures borrowing the slice make the returned future not general
+    // enough for the tauri command wrappers
+    let queries: Vec<String> = queries.to_vec();
+    stream::iter(queries.into_iter().enumerate())
+        .map(|(i, query)| async move {
+            // Spaced from the batch start rather than the previous finish, so slow
+            // searches do not push the later ones back further
+            tokio::time::sleep_until(start + delay * i as u32).await;
+            match provider.search(&query, max_results).await {
+                Ok(results) => Some(results),
+                Err(e) => {
+                    tracing::warn!("Web search failed for '{}': {}", query, e);
+                    None
+                }
+            }
+        })
+        .buffered(pacing.concurrency.max(1))
+        .filter_map(|results| async move { results })
+        .collect()
+        .await
+}
+
+/// Offline mode: every search returns nothing
+pub struct NoSearch;
+
+#[async_trait]
+impl SearchProvider for NoSearch {
+    async fn search(&self, _query: &str, _max_results: usize) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Snippets and titles from a DuckDuckGo HTML results page
+pub fn parse_duckduckgo_html(html: &str, max_results: usize) -> Vec<String> {
+    static SNIPPET: OnceLock<Regex> = OnceLock::new();
+    static TITLE: OnceLock<Regex> = OnceLock::new();
+    // Match on the class anywhere in the tag; attribute order and extra classes vary
+    let snippet_re = SNIPPET
+        .get_or_init(|| Regex::new(r#"(?s)<a[^>]*class="[^"]*\bresult__snippet\b[^"]*"[^>]*>(.*?)</a>"#).unwrap());
+    let title_re =
+        TITLE.get_or_init(|| Regex::new(r#"(?s)<a[^>]*class="[^"]*\bresult__a\b[^"]*"[^>]*>(.*?)</a>"#).unwrap());
+
+    let extract = |re: &Regex| -> Vec<String> {
+        re.captures_iter(html)
+            .filter_map(|cap| cap.get(1))
+            .map(|m| html_to_text(m.as_str()))
+            .filter(|text| !text.is_empty())
+            .take(max_results)
+            .collect()
+    };
+
+    let mut results = extract(snippet_re);
+    results.extend(extract(title_re).into_iter().map(|title| format!("Title: {}", title)));
+    results
+}
+
+/// Snippets then titles from a JSON results array at `pointer`
+fn parse_json_results(json: &serde_json::Value, pointer: &str, snippet_field: &str, max_results: usize) -> Vec<String> {
+    let items: Vec<&serde_json::Value> = json
+        .pointer(pointer)
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().take(max_results).collect())
+        .unwrap_or_default();
+    let field = |item: &serde_json::Value, name: &str| {
+        item.get(name).and_then(|v| v.as_str()).map(html_to_text).filter(|text| !text.is_empty())
+    };
+
+    let mut results: Vec<String> = items.iter().filter_map(|item| field(item, snippet_field)).collect();
+    results.extend(items.iter().filter_map(|item| field(item, "title")).map(|title| format!("Title: {}", title)));
+    results
+}
+
+/// Strip tags, decode entities and collapse whitespace
+fn html_to_text(html: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag_re = TAG.get_or_init(|| Regex::new(r"<[^>]+>").unwrap());
+    let without_tags = tag_re.replace_all(html, "");
+    collapse_whitespace(&html_escape::decode_html_entities(&without_tags))
+}
+
+/// About four characters per token for English text
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+fn is_duplicate(candidate: &Candidate, normalized: &str, words: &HashSet<String>) -> bool {
+    if candidate.normalized == normalized {
+        return true;
+    }
+    if words.len() < MIN_WORDS_FOR_SIMILARITY || candidate.words.len() < MIN_WORDS_FOR_SIMILARITY {
+        return false;
+    }
+    let shared = candidate.words.intersection(words).count();
+    let union = candidate.words.len() + words.len() - shared;
+    shared as f64 / union as f64 >= NEAR_DUPLICATE_SIMILARITY
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lowercase words with punctuation removed, for comparison only
+fn normalize(text: &str) -> String {
+    let text = text.strip_prefix("Title:").unwrap_or(text);
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_prepare_results_dedupes() {
+        let news = strings(&[
+            "Storm disrupts flights at Heathrow on Saturday afternoon",
+            "Title: Heathrow delays",
+        ]);
+        let weather = strings(&[
+            "  storm DISRUPTS flights at   Heathrow on Saturday afternoon! ",
+            "Storm disrupts many flights at Heathrow on Saturday afternoon",
+            "Title: heathrow  delays",
+            "Sunny skies expected in New York",
+        ]);
+
+        let (results, stats) = prepare_results(vec![news, weather], DEFAULT_TOKEN_BUDGET);
+        assert_eq!(
+            results,
+            strings(&[
+                "Storm disrupts flights at Heathrow on Saturday afternoon",
+                "Title: Heathrow delays",
+                "Sunny skies expected in New York",
+            ])
+        );
+        assert_eq!(stats.total, 6);
+        assert_eq!(stats.deduplicated, 3);
+        assert_eq!(stats.dropped_for_budget, 0);
+    }
+
+    #[test]
+    fn test_prepare_results_token_budget() {
+        let first = strings(&["a".repeat(40).as_str(), "b".repeat(40).as_str()]);
+        let second = strings(&["c".repeat(40).as_str(), "d".repeat(40).as_str()]);
+
+        // 10 tokens each; room for three
+        let (results, stats) = prepare_results(vec![first, second], 30);
+        assert_eq!(stats.dropped_for_budget, 1);
+        assert_eq!(stats.estimated_tokens, 30);
+        assert_eq!(results, strings(&["a".repeat(40).as_str(), "b".repeat(40).as_str(), "c".repeat(40).as_str()]));
+
+        let (results, _) = prepare_results(vec![strings(&["x".repeat(40).as_str()])], 0);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_parse_duckduckgo_fixture() {
+        let html = include_str!("../tests/fixtures/duckduckgo_results.html");
+        let results = parse_duckduckgo_html(html, 5);
+        assert_eq!(
+            results,
+            strings(&[
+                "Passengers at Heathrow faced delays of up to three hours on 1 June 2024 after a fault with the air traffic control system.",
+                "Trooping the Colour rehearsals, the Chelsea Flower Show finale & more things to do in London this weekend.",
+                "Cloudy with showers clearing by evening. Maximum temperature 17°C.",
+                "Title: Heathrow flights disrupted by air traffic control fault",
+                "Title: London weekend events: what's on 1–2 June",
+                "Title: UK weather forecast - Met Office",
+            ])
+        );
+
+        assert_eq!(parse_duckduckgo_html(html, 1).len(), 2);
+        assert!(parse_duckduckgo_html("<html><body>No results.</body></html>", 5).is_empty());
+    }
+
+    #[test]
+    fn test_parse_json_results() {
+        let brave = serde_json::json!({
+            "web": { "results": [
+                { "title": "Heathrow <strong>delays</strong>", "description": "ATC fault at Heathrow." },
+                { "title": "No description" }
+            ]}
+        });
+        assert_eq!(
+            parse_json_results(&brave, "/web/results", "description", 5),
+            strings(&["ATC fault at Heathrow.", "Title: Heathrow delays", "Title: No description"])
+        );
+        assert!(parse_json_results(&serde_json::json!({}), "/organic_results", "snippet", 5).is_empty());
+    }
+
+    /// Echoes the query after a short wait, tracking how many searches overlap
+    struct CountingSearch {
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SearchProvider for CountingSearch {
+        async fn search(&self, query: &str, _max_results: usize) -> Result<Vec<String>> {
+            use std::sync::atomic::Ordering;
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if query == "fail" {
+                bail!("rate limited");
+            }
+            Ok(vec![query.to_string()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_all_bounded_and_ordered() {
+        let provider = CountingSearch { in_flight: Default::default(), peak: Default::default() };
+        let queries = strings(&["a", "b", "fail", "c", "d", "e"]);
+        let pacing = SearchPacing { concurrency: 2, delay_ms: 0 };
+
+        let results = search_all(&provider, &queries, 5, pacing).await;
+
+        assert_eq!(results, vec![strings(&["a"]), strings(&["b"]), strings(&["c"]), strings(&["d"]), strings(&["e"])]);
+        assert_eq!(provider.peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use serde_json::json;
 use crate::workflow::Workflow;
 use std::collections::HashMap;
@@ -83,19 +83,19 @@ pub async fn generate_workflow_from_prompt(
     // Clean and parse the JSON response
     let workflow_json = clean_json_response(&response_content)?;
 
-    eprintln!("=== ATTEMPTING TO PARSE WORKFLOW ===");
+    tracing::debug!("=== ATTEMPTING TO PARSE WORKFLOW ===");
     let mut workflow: Workflow = serde_json::from_str(&workflow_json)
         .map_err(|e| {
-            eprintln!("=== PARSE ERROR ===");
-            eprintln!("Error: {}", e);
-            eprintln!("JSON was: {}", &workflow_json[..workflow_json.len().min(1000)]);
+            tracing::debug!("=== PARSE ERROR ===");
+            tracing::debug!("Error: {}", e);
+            tracing::debug!("JSON was: {}", &workflow_json[..workflow_json.len().min(1000)]);
             anyhow::anyhow!("Failed to parse AI response as workflow JSON: {}", e)
         })?;
 
-    eprintln!("=== PARSE SUCCESS ===");
-    eprintln!("Workflow name: {}", workflow.name);
-    eprintln!("Nodes: {}", workflow.nodes.len());
-    eprintln!("Edges: {}", workflow.edges.len());
+    tracing::debug!("=== PARSE SUCCESS ===");
+    tracing::debug!("Workflow name: {}", workflow.name);
+    tracing::debug!("Nodes: {}", workflow.nodes.len());
+    tracing::debug!("Edges: {}", workflow.edges.len());
 
     // Generate UUIDs for all nodes and edges
     for node in &mut workflow.nodes {
@@ -252,9 +252,9 @@ fn clean_json_response(response: &str) -> Result<String> {
     let trimmed = response.trim();
 
     // Log raw response for debugging
-    eprintln!("=== RAW AI RESPONSE ===");
-    eprintln!("{}", &trimmed[..trimmed.len().min(500)]);
-    eprintln!("=== END RAW RESPONSE ===");
+    tracing::debug!("=== RAW AI RESPONSE ===");
+    tracing::debug!("{}", &trimmed[..trimmed.len().min(500)]);
+    tracing::debug!("=== END RAW RESPONSE ===");
 
     // Remove markdown code blocks if present
     let json_str = if trimmed.starts_with("```") {
@@ -278,15 +278,15 @@ fn clean_json_response(response: &str) -> Result<String> {
         trimmed.to_string()
     };
 
-    eprintln!("=== CLEANED JSON ===");
-    eprintln!("{}", &json_str[..json_str.len().min(500)]);
-    eprintln!("=== END CLEANED ===");
+    tracing::debug!("=== CLEANED JSON ===");
+    tracing::debug!("{}", &json_str[..json_str.len().min(500)]);
+    tracing::debug!("=== END CLEANED ===");
 
     // Find the first '{' and last '}'
     if let (Some(start), Some(end)) = (json_str.find('{'), json_str.rfind('}')) {
         let extracted = json_str[start..=end].to_string();
-        eprintln!("=== EXTRACTED JSON ({}..{}) ===", start, end);
-        eprintln!("{}", &extracted[..extracted.len().min(500)]);
+        tracing::debug!("=== EXTRACTED JSON ({}..{}) ===", start, end);
+        tracing::debug!("{}", &extracted[..extracted.len().min(500)]);
         Ok(extracted)
     } else {
         Err(anyhow::anyhow!("No valid JSON object found in response. Response was: {}", &trimmed[..trimmed.len().min(200)]))
@@ -321,6 +321,6 @@ mod tests {
 That should work!"#;
 
         let cleaned = clean_json_response(response).unwrap();
-        assert_eq!(cleaned, r#"{"name": "Test"}"#.trim());
+        assert_eq!(cleaned, "{\n  \"name\": \"Test\"\n}");
     }
 }
@@ -27,9 +27,11 @@ pub enum ExecutionError {
     ShellError(String),
 
     #[error("AI API error: {0}")]
+    #[allow(dead_code)]
     AIError(String),
 
     #[error("Database error: {0}")]
+    #[allow(dead_code)]
     DatabaseError(String),
 
     #[error("IO error: {0}")]
@@ -1096,6 +1098,7 @@ impl WorkflowManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::workflow::models::ExecutionStatus;
 
     #[tokio::test]
     async fn test_shell_executor() {
@@ -90,6 +90,7 @@ where
 
 impl Node {
     /// Create a new node with the given type and label
+    #[allow(dead_code)]
     pub fn new(node_type: NodeType, label: impl Into<String>) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
@@ -110,6 +111,7 @@ impl Node {
     }
 
     /// Set a configuration value
+    #[allow(dead_code)]
     pub fn set_config(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.config.insert(key.into(), value.into());
     }
@@ -148,6 +150,7 @@ pub struct Edge {
 
 impl Edge {
     /// Create a new edge from source to target
+    #[allow(dead_code)]
     pub fn new(source: impl Into<String>, target: impl Into<String>) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
@@ -176,6 +179,7 @@ fn default_workflow_id() -> String {
 
 impl Workflow {
     /// Create a new empty workflow
+    #[allow(dead_code)]
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
@@ -188,11 +192,13 @@ impl Workflow {
     }
 
     /// Add a node to the workflow
+    #[allow(dead_code)]
     pub fn add_node(&mut self, node: Node) {
         self.nodes.push(node);
     }
 
     /// Add an edge to the workflow
+    #[allow(dead_code)]
     pub fn add_edge(&mut self, edge: Edge) {
         self.edges.push(edge);
     }
@@ -203,11 +209,13 @@ impl Workflow {
     }
 
     /// Get a mutable reference to a node by ID
+    #[allow(dead_code)]
     pub fn get_node_mut(&mut self, id: &str) -> Option<&mut Node> {
         self.nodes.iter_mut().find(|n| n.id == id)
     }
 
     /// Remove a node and all connected edges
+    #[allow(dead_code)]
     pub fn remove_node(&mut self, id: &str) -> bool {
         if let Some(idx) = self.nodes.iter().position(|n| n.id == id) {
             self.nodes.remove(idx);
@@ -219,6 +227,7 @@ impl Workflow {
     }
 
     /// Remove an edge by ID
+    #[allow(dead_code)]
     pub fn remove_edge(&mut self, id: &str) -> bool {
         if let Some(idx) = self.edges.iter().position(|e| e.id == id) {
             self.edges.remove(idx);
@@ -0,0 +1,194 @@
+// Prioritised database write queue
+// A dedicated writer thread owns its own connection and drains two FIFO queues: interactive
+// writes (settings, single flight edits) always run before queued bulk writes (import chunks).
+// A running job is never interrupted, so bulk work should be submitted in small chunks.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::database::Database;
+
+/// Rows per bulk job when splitting large imports
+pub const BULK_CHUNK_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePriority {
+    /// Quick writes triggered directly by the user
+    Interactive,
+    /// Long running work such as import chunks
+    Bulk,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteQueueDepth {
+    pub interactive: usize,
+    pub bulk: usize,
+    /// True while the writer is executing a job
+    pub busy: bool,
+    pub processed: u64,
+}
+
+type Job = Box<dyn FnOnce(&Database) + Send>;
+
+#[derive(Default)]
+struct Queues {
+    interactive: VecDeque<Job>,
+    bulk: VecDeque<Job>,
+    busy: bool,
+    processed: u64,
+}
+
+pub struct WriteQueue {
+    shared: Arc<(Mutex<Queues>, Condvar)>,
+}
+
+impl WriteQueue {
+    /// Open a writer connection on `db_path` and start the writer thread
+    pub fn start(db_path: PathBuf) -> Result<Self> {
+        let db = Database::new(db_path).context("Failed to open writer connection")?;
+        // WAL lets the main connection keep reading while the writer commits
+        db.conn
+            .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .context("Failed to enable WAL journal mode")?;
+
+        let shared: Arc<(Mutex<Queues>, Condvar)> = Arc::default();
+        let worker = Arc::clone(&shared);
+        std::thread::Builder::new()
+            .name("db-writer".to_string())
+            .spawn(move || run_writer(db, worker))
+            .context("Failed to spawn writer thread")?;
+
+        Ok(Self { shared })
+    }
+
+    /// Run `job` on the writer connection and wait for its result
+    ///
+    /// Jobs of the same priority run in submission order, and the call returns only once
+    /// the job has committed, so a read issued afterwards always sees the write.
+    pub fn submit<T, F>(&self, priority: WritePriority, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.enqueue(
+            priority,
+            Box::new(move |db: &Database| {
+                let _ = tx.send(job(db));
+            }),
+        )?;
+        rx.recv()
+            .map_err(|_| anyhow!("Database writer stopped before completing the write"))?
+    }
+
+    /// Current queue lengths, for diagnostics
+    pub fn depth(&self) -> WriteQueueDepth {
+        let queues = self.shared.0.lock().unwrap_or_else(|e| e.into_inner());
+        WriteQueueDepth {
+            interactive: queues.interactive.len(),
+            bulk: queues.bulk.len(),
+            busy: queues.busy,
+            processed: queues.processed,
+        }
+    }
+
+    fn enqueue(&self, priority: WritePriority, job: Job) -> Result<()> {
+        let (lock, ready) = &*self.shared;
+        let mut queues = lock.lock().map_err(|e| anyhow!(e.to_string()))?;
+        match priority {
+            WritePriority::Interactive => queues.interactive.push_back(job),
+            WritePriority::Bulk => queues.bulk.push_back(job),
+        }
+        ready.notify_one();
+        Ok(())
+    }
+}
+
+fn run_writer(db: Database, shared: Arc<(Mutex<Queues>, Condvar)>) {
+    let (lock, ready) = &*shared;
+    loop {
+        let job = {
+            let mut queues = lock.lock().unwrap_or_else(|e| e.into_inner());
+            queues.busy = false;
+            loop {
+                if let Some(job) = queues.interactive.pop_front().or_else(|| queues.bulk.pop_front()) {
+                    queues.busy = true;
+                    break job;
+                }
+                queues = ready.wait(queues).unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        // A panicking job only loses its own reply; the writer keeps serving the queue
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| job(&db)));
+
+        lock.lock().unwrap_or_else(|e| e.into_inner()).processed += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interactive_writes_preempt_queued_bulk_writes() {
+        let path = std::env::temp_dir().join(format!("write_queue_{}.db", uuid::Uuid::new_v4()));
+        let queue = Arc::new(WriteQueue::start(path.clone()).unwrap());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let submit = |priority: WritePriority, label: &'static str, gate: Option<(mpsc::Sender<()>, mpsc::Receiver<()>)>| {
+            let queue = Arc::clone(&queue);
+            let order = Arc::clone(&order);
+            std::thread::spawn(move || {
+                queue
+                    .submit(priority, move |db| {
+                        // A gated job reports that it is running, then holds the writer until released
+                        if let Some((started, release)) = gate {
+                            started.send(()).unwrap();
+                            release.recv().unwrap();
+                        }
+                        db.set_setting(label, "1")?;
+                        order.lock().unwrap().push(label);
+                        Ok(())
+                    })
+                    .unwrap()
+            })
+        };
+
+        let (started_tx, started_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel();
+        let bulk_1 = submit(WritePriority::Bulk, "bulk_1", Some((started_tx, release_rx)));
+        started_rx.recv().unwrap();
+
+        // With bulk_1 on the writer, queue one job of each priority behind it
+        let bulk_2 = submit(WritePriority::Bulk, "bulk_2", None);
+        let interactive = submit(WritePriority::Interactive, "interactive", None);
+        loop {
+            let depth = queue.depth();
+            if depth.bulk == 1 && depth.interactive == 1 {
+                break;
+            }
+            std::thread::yield_now();
+        }
+        release_tx.send(()).unwrap();
+
+        for handle in [bulk_1, bulk_2, interactive] {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec!["bulk_1", "interactive", "bulk_2"]);
+        assert_eq!(queue.depth().processed, 3);
+
+        // Read-your-writes: the result is committed once submit returns
+        let value = queue
+            .submit(WritePriority::Interactive, |db| db.get_setting("interactive"))
+            .unwrap();
+        assert_eq!(value.as_deref(), Some("1"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
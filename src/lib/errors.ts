@@ -0,0 +1,48 @@
+// Errors returned by Tauri commands
+// Migrated commands reject with a structured { code, message } object; older ones still
+// reject with a plain string. errorMessage() handles both.
+
+export type CommandErrorCode =
+  | 'not_found'
+  | 'unauthorized'
+  | 'rate_limited'
+  | 'validation'
+  | 'locked'
+  | 'database'
+  | 'external'
+  | 'unavailable'
+  | 'internal';
+
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  /** Only set for rate_limited, when the provider said how long to wait */
+  retry_after_secs: number | null;
+}
+
+export function isCommandError(err: unknown): err is CommandError {
+  return typeof err === 'object' && err !== null && 'code' in err && 'message' in err;
+}
+
+/** Human-readable message for any invoke() rejection */
+export function errorMessage(err: unknown): string {
+  if (isCommandError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  return String(err);
+}
+
+/** Message with a hint for errors the user can act on (missing key, rate limit) */
+export function describeError(err: unknown): string {
+  const message = errorMessage(err);
+  if (!isCommandError(err)) return message;
+  switch (err.code) {
+    case 'unauthorized':
+      return `${message} Check the API key in Settings.`;
+    case 'rate_limited':
+      return err.retry_after_secs
+        ? `${message} Try again in ${err.retry_after_secs} seconds.`
+        : `${message} Try again in a moment.`;
+    default:
+      return message;
+  }
+}
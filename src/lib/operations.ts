@@ -0,0 +1,45 @@
+// Long running backend operations (batch OCR, batch calculations)
+// The caller picks the operation id up front and passes it as `operationId`, so it can
+// follow progress and cancel before the command returns.
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export type OperationState = 'running' | 'completed' | 'cancelled' | 'failed';
+
+export interface OperationStatus<D = unknown> {
+  id: string;
+  kind: string;
+  state: OperationState;
+  current: number;
+  total: number;
+  /** Operation-specific progress details */
+  detail: D | null;
+  error: string | null;
+  cancel_requested: boolean;
+  started_at: string;
+  finished_at: string | null;
+}
+
+export function newOperationId(): string {
+  return crypto.randomUUID();
+}
+
+/** Call `onProgress` for every status update of one operation */
+export function listenToOperation<D>(
+  operationId: string,
+  onProgress: (status: OperationStatus<D>) => void
+): Promise<UnlistenFn> {
+  return listen<OperationStatus<D>>('operation:progress', (event) => {
+    if (event.payload.id === operationId) onProgress(event.payload);
+  });
+}
+
+/** Ask the operation to stop; resolves false when it already finished */
+export function cancelOperation(operationId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_operation', { operationId });
+}
+
+export function getOperationStatus<D = unknown>(operationId: string): Promise<OperationStatus<D>> {
+  return invoke<OperationStatus<D>>('get_operation_status', { operationId });
+}
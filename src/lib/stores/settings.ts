@@ -93,7 +93,10 @@ export async function detectLocationFromIP(): Promise<void> {
       city?: string;
       country?: string;
       country_code?: string;
-    }>('detect_location_from_ip');
+    } | null>('detect_location_from_ip');
+    if (!result) {
+      throw new Error('Geolocation returned no coordinates');
+    }
 
     const location: UserLocation = {
       lat: result.lat,
@@ -101,6 +101,45 @@ export interface CostBreakdown {
   item_count: number;
 }
 
+export type Cabin = 'economy' | 'premium' | 'business' | 'first' | 'other';
+
+export interface CabinRevenue {
+  cabin: Cabin;
+  currency: string;
+  flight_count: number;
+  total_cost: number;
+  base_fare: number;
+  taxes: number;
+  average_cost: number | null;
+  raw_fare_classes: string[];
+}
+
+export interface MaintenanceSchedule {
+  id: string;
+  user_id: string;
+  aircraft_registration: string;
+  item: string;
+  interval_hours: number | null;
+  interval_days: number | null;
+  last_done_hours: number | null;
+  last_done_date: string | null;
+  notes: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export type MaintenanceStatus = 'ok' | 'due_soon' | 'not_recorded' | 'overdue';
+
+export interface MaintenanceDue {
+  schedule: MaintenanceSchedule;
+  current_hours: number;
+  next_due_hours: number | null;
+  hours_remaining: number | null;
+  next_due_date: string | null;
+  days_remaining: number | null;
+  status: MaintenanceStatus;
+}
+
 export interface DayNightStats {
   total_day_flights: number;
   total_night_flights: number;
@@ -121,9 +160,11 @@ export interface LongHaulFlight {
 }
 
 export interface CurrencyItem {
+  key: string;
   requirement: string;
   current_count: number;
   required: number;
+  expires_date: string | null;
   expires_in_days: number | null;
   status: string;
   last_completed: string | null;
@@ -143,6 +184,24 @@ export interface RunwayRiskData {
   airport_code: string;
   airport_name: string;
   runway_length_ft: number;
+  /** True when the airport has no runway data and runway_length_ft is the default */
+  runway_length_assumed: boolean;
+  elevation_ft: number | null;
+  effective_runway_ft: number;
   visits: number;
   risk_level: string;
 }
+
+export interface HighAltitudeAirport {
+  airport_code: string;
+  airport_name: string;
+  elevation_ft: number;
+  hot_day_density_altitude_ft: number;
+  visits: number;
+}
+
+export interface HighAltitudeReport {
+  threshold_ft: number;
+  airports: HighAltitudeAirport[];
+  missing_elevation_count: number;
+}